@@ -0,0 +1,47 @@
+//! Build-time detection of optional HarfBuzz integrations.
+//!
+//! On macOS, `hb_coretext_font_create` only exists when the system HarfBuzz
+//! was built with CoreText support. Referencing it otherwise fails at link
+//! time, so it is only declared under `cfg(hb_coretext)`. Without it, faces
+//! are shaped from font tables read through CoreText instead.
+//!
+//! Set `GUI_NVIM_HB_CORETEXT=0` or `=1` to override the detection.
+
+use std::env;
+use std::path::Path;
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(hb_coretext)");
+    println!("cargo::rerun-if-env-changed=GUI_NVIM_HB_CORETEXT");
+
+    if env::var("CARGO_CFG_TARGET_VENDOR").as_deref() != Ok("apple") {
+        return;
+    }
+
+    let coretext = match env::var("GUI_NVIM_HB_CORETEXT").as_deref() {
+        Ok("0") => false,
+        Ok(_) => true,
+        Err(_) => harfbuzz_has_coretext(),
+    };
+    if coretext {
+        println!("cargo::rustc-cfg=hb_coretext");
+    } else {
+        println!(
+            "cargo::warning=HarfBuzz has no CoreText support; shaping from font tables instead"
+        );
+    }
+}
+
+/// Whether the HarfBuzz found by pkg-config ships `hb-coretext.h`.
+fn harfbuzz_has_coretext() -> bool {
+    let Ok(library) = pkg_config::Config::new()
+        .cargo_metadata(false)
+        .probe("harfbuzz")
+    else {
+        return false;
+    };
+    library.include_paths.iter().any(|dir| {
+        Path::new(dir).join("hb-coretext.h").exists()
+            || Path::new(dir).join("harfbuzz/hb-coretext.h").exists()
+    })
+}
@@ -0,0 +1,112 @@
+//! Asks Neovim how it reads each key name the GUI sends, so the fuzz
+//! target checks key notation against the real parser rather than a copy
+//! of it.
+//!
+//! Runs `nvim` from PATH, or `$NVIM`. For every name in `NAMES`, records
+//! `keytrans(nvim_replace_termcodes('<name>'))`, which is `<name>` again
+//! only when Neovim knows the name under that spelling, and the character
+//! the name stands for, if it is a single one such as `<lt>` (which Neovim
+//! can only have read as a name).
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Every key name the GUI can produce, including the escapes for `<`, `\`
+/// and `|`. A name the GUI starts sending has to be added here, or the
+/// fuzz target reports it as unknown.
+const NAMES: &[&str] = &[
+    "CR",
+    "Tab",
+    "Space",
+    "BS",
+    "Esc",
+    "Del",
+    "Insert",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "Up",
+    "Down",
+    "Left",
+    "Right",
+    "F1",
+    "F2",
+    "F3",
+    "F4",
+    "F5",
+    "F6",
+    "F7",
+    "F8",
+    "F9",
+    "F10",
+    "F11",
+    "F12",
+    "Help",
+    "Undo",
+    "k0",
+    "k1",
+    "k2",
+    "k3",
+    "k4",
+    "k5",
+    "k6",
+    "k7",
+    "k8",
+    "k9",
+    "kPlus",
+    "kMinus",
+    "kMultiply",
+    "kDivide",
+    "kPoint",
+    "lt",
+    "Bslash",
+    "Bar",
+];
+
+const SCRIPT: &str = r#"
+for _, name in ipairs(_G.arg) do
+  local codes = vim.api.nvim_replace_termcodes('<' .. name .. '>', true, true, true)
+  local char = #codes == 1 and string.byte(codes) or -1
+  io.stdout:write(name, '\t', vim.fn.keytrans(codes), '\t', char, '\n')
+end
+"#;
+
+fn main() {
+    println!("cargo::rerun-if-changed=build.rs");
+    println!("cargo::rerun-if-env-changed=NVIM");
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let script = Path::new(&out_dir).join("keytrans.lua");
+    fs::write(&script, SCRIPT).unwrap();
+
+    let nvim = env::var("NVIM").unwrap_or_else(|_| "nvim".to_string());
+    let output = Command::new(&nvim)
+        .args(["--clean", "--headless", "-l"])
+        .arg(&script)
+        .args(NAMES)
+        .output()
+        .unwrap_or_else(|e| panic!("The fuzz target needs Neovim to build; running {nvim}: {e}"));
+    assert!(
+        output.status.success(),
+        "{nvim} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let mut table = String::from("const KEYTRANS: &[(&str, &str, Option<char>)] = &[\n");
+    for line in String::from_utf8(output.stdout).unwrap().lines() {
+        let mut fields = line.split('\t');
+        let (Some(name), Some(keytrans), Some(char)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            panic!("Unexpected keytrans output: {line:?}");
+        };
+        let char = char.parse::<u8>().ok().map(char::from);
+        writeln!(table, "    ({name:?}, {keytrans:?}, {char:?}),").unwrap();
+    }
+    table.push_str("];\n");
+    fs::write(Path::new(&out_dir).join("keytrans.rs"), table).unwrap();
+}
@@ -0,0 +1,254 @@
+//! Presses random keys with random modifiers and checks that Neovim would
+//! read the key notation the GUI sends back as the same key: every name
+//! must be one Neovim knows (see build.rs), and text must come back
+//! character for character, with `<`, `\` and `|` escaped.
+//!
+//! Run with `cargo fuzz run input_roundtrip` from the repository root.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use gui_nvim::input::{key_to_neovim, Modifiers};
+use libfuzzer_sys::fuzz_target;
+use winit::keyboard::{Key, KeyCode, NamedKey, NativeKey, NativeKeyCode, PhysicalKey, SmolStr};
+
+include!(concat!(env!("OUT_DIR"), "/keytrans.rs"));
+
+const NAMED_KEYS: &[NamedKey] = &[
+    NamedKey::Enter,
+    NamedKey::Tab,
+    NamedKey::Space,
+    NamedKey::Backspace,
+    NamedKey::Escape,
+    NamedKey::Delete,
+    NamedKey::Insert,
+    NamedKey::Home,
+    NamedKey::End,
+    NamedKey::PageUp,
+    NamedKey::PageDown,
+    NamedKey::ArrowUp,
+    NamedKey::ArrowDown,
+    NamedKey::ArrowLeft,
+    NamedKey::ArrowRight,
+    NamedKey::F1,
+    NamedKey::F5,
+    NamedKey::F12,
+    NamedKey::F13,
+    NamedKey::F24,
+    NamedKey::Help,
+    NamedKey::Undo,
+    NamedKey::Redo,
+    NamedKey::Copy,
+    NamedKey::Shift,
+    NamedKey::Control,
+    NamedKey::Alt,
+    NamedKey::Super,
+    NamedKey::Meta,
+    NamedKey::Hyper,
+    NamedKey::Fn,
+    NamedKey::NumLock,
+    NamedKey::CapsLock,
+    NamedKey::ScrollLock,
+    NamedKey::MediaPlayPause,
+    NamedKey::AudioVolumeUp,
+];
+
+/// Physical keys behind a logical key the layout couldn't name.
+const KEY_CODES: &[KeyCode] = &[
+    KeyCode::Enter,
+    KeyCode::NumpadEnter,
+    KeyCode::Tab,
+    KeyCode::Space,
+    KeyCode::Backspace,
+    KeyCode::Escape,
+    KeyCode::Delete,
+    KeyCode::ArrowLeft,
+    KeyCode::F3,
+    KeyCode::Numpad0,
+    KeyCode::Numpad9,
+    KeyCode::NumpadAdd,
+    KeyCode::NumpadSubtract,
+    KeyCode::NumpadMultiply,
+    KeyCode::NumpadDivide,
+    KeyCode::NumpadDecimal,
+    KeyCode::NumpadComma,
+    KeyCode::KeyA,
+    KeyCode::Digit1,
+    KeyCode::BracketLeft,
+    KeyCode::Backslash,
+    KeyCode::IntlBackslash,
+];
+
+#[derive(Debug, Arbitrary)]
+enum FuzzKey {
+    Named(u8),
+    Character(String),
+    /// Index into `KEY_CODES`, or past its end for an unidentified one.
+    Unidentified(u8),
+    Dead,
+}
+
+#[derive(Debug, Arbitrary)]
+struct Press {
+    key: FuzzKey,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    logo: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Decoded {
+    Char(char),
+    Name(&'static str),
+}
+
+/// One key of the notation: its modifiers, in the GUI's `D-C-M-S-` order,
+/// and the key.
+#[derive(Debug, PartialEq)]
+struct Token {
+    mods: String,
+    key: Decoded,
+}
+
+/// Reads `keys` the way `nvim_input` does, panicking on notation Neovim
+/// would take differently.
+fn decode(keys: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = keys;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        if c != '<' {
+            tokens.push(Token {
+                mods: String::new(),
+                key: Decoded::Char(c),
+            });
+            continue;
+        }
+
+        let mut mods = String::new();
+        while let [m @ (b'D' | b'C' | b'M' | b'S'), b'-', _, ..] = rest.as_bytes() {
+            mods.push(char::from(*m));
+            mods.push('-');
+            rest = &rest[2..];
+        }
+
+        // Any single character can follow the modifiers, even `>` or `-`.
+        let mut chars = rest.chars();
+        let first = chars
+            .next()
+            .unwrap_or_else(|| panic!("Unfinished key in {keys:?}"));
+        if chars.next() == Some('>') && !mods.is_empty() {
+            rest = &rest[first.len_utf8() + 1..];
+            tokens.push(Token {
+                mods,
+                key: Decoded::Char(first),
+            });
+            continue;
+        }
+
+        let end = rest
+            .find('>')
+            .unwrap_or_else(|| panic!("Unterminated key in {keys:?}"));
+        let name = &rest[..end];
+        rest = &rest[end + 1..];
+        let &(known, keytrans, char) = KEYTRANS
+            .iter()
+            .find(|(known, _, _)| *known == name)
+            .unwrap_or_else(|| panic!("<{name}> in {keys:?} isn't in build.rs NAMES"));
+        // keytrans spells `<Bar>` and `<Bslash>` as the bare character.
+        assert!(
+            char.is_some() || keytrans == format!("<{name}>"),
+            "Neovim doesn't read <{name}> in {keys:?} as that key, but as {keytrans}"
+        );
+        let key = match char {
+            Some(ch) if mods.is_empty() => Decoded::Char(ch),
+            _ => Decoded::Name(known),
+        };
+        tokens.push(Token { mods, key });
+    }
+    tokens
+}
+
+fn modifier_prefix(press: &Press, shift: bool) -> String {
+    let mut mods = String::new();
+    for (held, prefix) in [
+        (press.logo, "D-"),
+        (press.ctrl, "C-"),
+        (press.alt, "M-"),
+        (shift, "S-"),
+    ] {
+        if held {
+            mods.push_str(prefix);
+        }
+    }
+    mods
+}
+
+fuzz_target!(|press: Press| {
+    let modifiers = Modifiers {
+        ctrl: press.ctrl,
+        shift: press.shift,
+        alt: press.alt,
+        logo: press.logo,
+    };
+    let (logical, physical) = match &press.key {
+        FuzzKey::Named(index) => (
+            Key::Named(NAMED_KEYS[*index as usize % NAMED_KEYS.len()]),
+            PhysicalKey::Code(KeyCode::KeyA),
+        ),
+        FuzzKey::Character(text) => (
+            Key::Character(SmolStr::new(text)),
+            PhysicalKey::Code(KeyCode::KeyA),
+        ),
+        FuzzKey::Unidentified(index) => (
+            Key::Unidentified(NativeKey::Unidentified),
+            KEY_CODES
+                .get(*index as usize)
+                .map(|&code| PhysicalKey::Code(code))
+                .unwrap_or(PhysicalKey::Unidentified(NativeKeyCode::Unidentified)),
+        ),
+        FuzzKey::Dead => (Key::Dead(None), PhysicalKey::Code(KeyCode::KeyA)),
+    };
+
+    let keys = key_to_neovim(&logical, &physical, &modifiers);
+    let tokens = keys.as_deref().map(decode);
+
+    match &press.key {
+        FuzzKey::Character(text) => {
+            let mut chars = text.chars();
+            let single_control =
+                matches!((chars.next(), chars.next()), (Some(c), None) if c.is_control());
+            if text.is_empty() || single_control {
+                assert_eq!(keys, None);
+                return;
+            }
+            // Shift is already in the text, unless other modifiers are held.
+            let modified = press.ctrl || press.alt || press.logo;
+            let mods = if modified {
+                modifier_prefix(&press, press.shift)
+            } else {
+                String::new()
+            };
+            let expected: Vec<Token> = text
+                .chars()
+                .map(|ch| Token {
+                    mods: mods.clone(),
+                    key: Decoded::Char(ch),
+                })
+                .collect();
+            assert_eq!(tokens, Some(expected), "{text:?} sent as {keys:?}");
+        }
+        FuzzKey::Named(_) | FuzzKey::Unidentified(_) => {
+            let Some(tokens) = tokens else {
+                return;
+            };
+            // A single key, though `<Space>` and the like read as text.
+            let [Token { mods, .. }] = tokens.as_slice() else {
+                panic!("{logical:?} sent as {keys:?}, not a single named key");
+            };
+            assert_eq!(*mods, modifier_prefix(&press, press.shift), "{keys:?}");
+        }
+        FuzzKey::Dead => assert_eq!(keys, None),
+    }
+});
@@ -0,0 +1,367 @@
+//! `gui.nvim bench`: renders synthetic editor content offscreen and reports
+//! frame times, as a baseline for spotting performance regressions.
+
+use std::time::{Duration, Instant};
+
+use winit::dpi::PhysicalSize;
+
+use crate::bridge::events::{GridCell, RedrawEvent};
+use crate::config::Config;
+use crate::editor::{Color, EditorState, HighlightAttributes};
+use crate::layout::WindowLayout;
+use crate::renderer::{GpuContext, Renderer};
+
+/// Highlight groups defined for the color noise scenario.
+const NOISE_HIGHLIGHTS: u64 = 64;
+
+/// Frames drawn before measuring, to fill the glyph and shaping caches.
+const WARMUP_FRAMES: usize = 10;
+
+const CODE_LINES: &[&str] = &[
+    "fn main() {",
+    "    let items = vec![1, 2, 3, 4, 5];",
+    "    for (i, item) in items.iter().enumerate() {",
+    "        println!(\"{i}: {item:>8}\");",
+    "    }",
+    "    // The quick brown fox jumps over the lazy dog",
+    "    let total: u64 = items.iter().map(|x| x * x).sum();",
+    "}",
+];
+
+const EMOJI_LINE: &[&str] = &["😀", "🚀", "🦀", "漢", "字", "👍", "🎉", "語", "🔥", "✨"];
+
+/// Synthetic content drawn each frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    /// Code scrolling up by one line per frame.
+    Scroll,
+    /// Every cell redrawn with random text and colors.
+    ColorNoise,
+    /// Wide emoji and CJK lines, scrolling.
+    Emoji,
+}
+
+impl Scenario {
+    pub const ALL: [Scenario; 3] = [Scenario::Scroll, Scenario::ColorNoise, Scenario::Emoji];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scenario::Scroll => "scroll",
+            Scenario::ColorNoise => "color-noise",
+            Scenario::Emoji => "emoji",
+        }
+    }
+
+    /// Events that set up the grid before the first frame.
+    fn setup(self, cols: usize, rows: usize, rng: &mut Rng) -> Vec<RedrawEvent> {
+        let mut events = vec![RedrawEvent::GridResize {
+            grid: 1,
+            width: cols,
+            height: rows,
+        }];
+        if self == Scenario::ColorNoise {
+            events.extend((1..=NOISE_HIGHLIGHTS).map(|id| RedrawEvent::HlAttrDefine {
+                id,
+                attrs: HighlightAttributes {
+                    foreground: Some(Color(rng.next() & 0xFF_FFFF)),
+                    background: Some(Color(rng.next() & 0xFF_FFFF)),
+                    ..Default::default()
+                },
+            }));
+        }
+        events.extend((0..rows).map(|row| self.line(row, row, cols, rng)));
+        events.push(RedrawEvent::Flush);
+        events
+    }
+
+    /// Events for frame `frame`.
+    fn frame(self, frame: usize, cols: usize, rows: usize, rng: &mut Rng) -> Vec<RedrawEvent> {
+        let mut events = Vec::new();
+        match self {
+            Scenario::Scroll | Scenario::Emoji => {
+                events.push(RedrawEvent::GridScroll {
+                    grid: 1,
+                    top: 0,
+                    bot: rows,
+                    left: 0,
+                    right: cols,
+                    rows: 1,
+                });
+                events.push(self.line(rows - 1, frame + rows, cols, rng));
+            }
+            Scenario::ColorNoise => {
+                events.extend((0..rows).map(|row| self.line(row, frame + row, cols, rng)));
+            }
+        }
+        events.push(RedrawEvent::GridCursorGoto {
+            grid: 1,
+            row: frame % rows,
+            col: frame % cols,
+        });
+        events.push(RedrawEvent::Flush);
+        events
+    }
+
+    /// A `grid_line` drawing content line `index` into `row`.
+    fn line(self, row: usize, index: usize, cols: usize, rng: &mut Rng) -> RedrawEvent {
+        let cell = |text: &str, hl_id| GridCell {
+            text: text.to_string(),
+            hl_id,
+            repeat: 1,
+        };
+        let mut cells = Vec::with_capacity(cols);
+        match self {
+            Scenario::Scroll => {
+                let text = format!("{:>4} {}", index + 1, CODE_LINES[index % CODE_LINES.len()]);
+                cells.extend(text.chars().take(cols).map(|c| cell(&c.to_string(), None)));
+            }
+            Scenario::ColorNoise => {
+                for _ in 0..cols {
+                    let c = char::from(b'!' + (rng.next() % 94) as u8);
+                    let hl_id = 1 + rng.next() as u64 % NOISE_HIGHLIGHTS;
+                    cells.push(cell(&c.to_string(), Some(hl_id)));
+                }
+            }
+            Scenario::Emoji => {
+                // Each wide glyph takes two cells, the second one empty.
+                for i in 0..cols / 2 {
+                    cells.push(cell(EMOJI_LINE[(index + i) % EMOJI_LINE.len()], None));
+                    cells.push(cell("", None));
+                }
+            }
+        }
+        let used = cells.len();
+        if used < cols {
+            cells.push(GridCell {
+                text: " ".into(),
+                hl_id: Some(0),
+                repeat: cols - used,
+            });
+        }
+        RedrawEvent::GridLine {
+            grid: 1,
+            row,
+            col_start: 0,
+            cells,
+        }
+    }
+}
+
+/// How much of the window each frame redraws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedrawMode {
+    /// The whole window, every frame.
+    Full,
+    /// Only the damaged grid rows, as in the normal render loop.
+    Damage,
+}
+
+impl RedrawMode {
+    pub const ALL: [RedrawMode; 2] = [RedrawMode::Full, RedrawMode::Damage];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RedrawMode::Full => "full",
+            RedrawMode::Damage => "damage",
+        }
+    }
+}
+
+/// Frame time distribution over a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles of `samples`, or `None` if there are none.
+    pub fn from_samples(samples: &mut [Duration]) -> Option<Self> {
+        samples.sort_unstable();
+        let max = *samples.last()?;
+        let rank = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        Some(Self {
+            p50: rank(50),
+            p95: rank(95),
+            p99: rank(99),
+            max,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BenchOptions {
+    pub frames: usize,
+    pub cols: usize,
+    pub rows: usize,
+}
+
+/// Runs every scenario in every redraw mode on every adapter and prints
+/// frame time percentiles.
+pub fn run(options: BenchOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    let adapters = runtime.block_on(GpuContext::headless_adapters());
+    if adapters.is_empty() {
+        return Err("No GPU adapter found".into());
+    }
+
+    // Default settings, so results are comparable between machines.
+    let config = Config::default();
+    let (cols, rows) = (options.cols.max(1), options.rows.max(1));
+    println!(
+        "gui.nvim bench: {} frames per run, {}x{} grid",
+        options.frames, cols, rows
+    );
+
+    for adapter in &adapters {
+        let info = adapter.get_info();
+        println!();
+        println!("{} ({:?}, {:?})", info.name, info.backend, info.device_type);
+
+        let mut layout = WindowLayout::new();
+        // Size the frame from the default cell size, then fit the real one.
+        let (width, height) = layout.window_size(cols as u64, rows as u64);
+        let size = PhysicalSize::new(width as u32, height as u32);
+        let mut renderer =
+            match runtime.block_on(Renderer::headless(adapter, size, config.clone(), 1.0)) {
+                Ok(renderer) => renderer,
+                Err(e) => {
+                    println!("  skipped: {}", e);
+                    continue;
+                }
+            };
+        let (cell_width, cell_height) = renderer.cell_size();
+        layout.set_cell_size(cell_width as f64, cell_height as f64);
+        let (width, height) = layout.window_size(cols as u64, rows as u64);
+        renderer.resize(PhysicalSize::new(width as u32, height as u32));
+
+        println!(
+            "  {:<12} {:<7} {:>9} {:>9} {:>9} {:>9}",
+            "scenario", "mode", "p50", "p95", "p99", "max"
+        );
+        for scenario in Scenario::ALL {
+            for mode in RedrawMode::ALL {
+                let mut samples = run_scenario(&mut renderer, &layout, scenario, mode, options);
+                let Some(p) = Percentiles::from_samples(&mut samples) else {
+                    continue;
+                };
+                println!(
+                    "  {:<12} {:<7} {:>9} {:>9} {:>9} {:>9}",
+                    scenario.name(),
+                    mode.name(),
+                    format_ms(p.p50),
+                    format_ms(p.p95),
+                    format_ms(p.p99),
+                    format_ms(p.max),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Draws the scenario's frames and returns how long each one took, from
+/// applying its events to the GPU finishing it.
+fn run_scenario(
+    renderer: &mut Renderer,
+    layout: &WindowLayout,
+    scenario: Scenario,
+    mode: RedrawMode,
+    options: BenchOptions,
+) -> Vec<Duration> {
+    let (cols, rows) = (options.cols.max(1), options.rows.max(1));
+    let mut rng = Rng::new(0x9E37_79B9);
+    let mut state = EditorState::new(cols, rows);
+    for event in scenario.setup(cols, rows, &mut rng) {
+        state.handle_redraw_event(&event);
+    }
+    renderer.invalidate();
+    renderer.render_offscreen(&state, layout);
+    state.clear_dirty();
+
+    let mut samples = Vec::with_capacity(options.frames);
+    for frame in 0..WARMUP_FRAMES + options.frames {
+        let events = scenario.frame(frame, cols, rows, &mut rng);
+        let start = Instant::now();
+        for event in &events {
+            state.handle_redraw_event(event);
+        }
+        if mode == RedrawMode::Full {
+            renderer.invalidate();
+        }
+        renderer.render_offscreen(&state, layout);
+        state.clear_dirty();
+        if frame >= WARMUP_FRAMES {
+            samples.push(start.elapsed());
+        }
+    }
+    samples
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Small xorshift generator, so every run draws the same content.
+struct Rng(u32);
+
+impl Rng {
+    fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut samples: Vec<Duration> = (1..=100).rev().map(ms).collect();
+        let p = Percentiles::from_samples(&mut samples).unwrap();
+        assert_eq!(
+            (p.p50, p.p95, p.p99, p.max),
+            (ms(50), ms(95), ms(99), ms(100))
+        );
+
+        let p = Percentiles::from_samples(&mut [ms(7)]).unwrap();
+        assert_eq!((p.p50, p.max), (ms(7), ms(7)));
+        assert_eq!(Percentiles::from_samples(&mut []), None);
+    }
+
+    #[test]
+    fn test_scenarios_fill_whole_rows() {
+        for scenario in Scenario::ALL {
+            let mut rng = Rng::new(1);
+            let mut state = EditorState::new(10, 4);
+            for event in scenario.setup(21, 5, &mut rng) {
+                state.handle_redraw_event(&event);
+            }
+            for frame in 0..3 {
+                for event in scenario.frame(frame, 21, 5, &mut rng) {
+                    if let RedrawEvent::GridLine { cells, .. } = &event {
+                        let width: usize = cells.iter().map(|c| c.repeat).sum();
+                        assert_eq!(width, 21, "{}", scenario.name());
+                    }
+                    state.handle_redraw_event(&event);
+                }
+            }
+            assert_eq!(state.main_grid().width(), 21);
+            assert!(state.splash.is_none());
+            assert!(state.is_dirty());
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use nvim_rs::Value;
+
+use crate::config::{TablineVisibility, UiSettings};
+
+/// API level of Neovim 0.10, the first release with 'smoothscroll'. From
+/// here on Neovim scrolls by screen line, so fractional trackpad deltas map
+/// onto meaningful scroll steps instead of whole buffer lines.
+const SMOOTH_SCROLL_API_LEVEL: u64 = 12;
+
+/// Features of the attached Neovim, negotiated from `nvim_get_api_info`
+/// before the UI attaches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+    /// RPC channel of this UI, used as the `rpcnotify` target for GUI commands.
+    pub channel_id: u64,
+    pub api_level: u64,
+    /// The `ext_*` options Neovim lists in `ui_options`.
+    pub extensions: UiExtensions,
+}
+
+/// `ext_*` options of `nvim_ui_attach`: parts of the UI drawn by the GUI
+/// instead of inside the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UiExtensions {
+    pub multigrid: bool,
+    pub cmdline: bool,
+    pub popupmenu: bool,
+    pub messages: bool,
+    pub tabline: bool,
+}
+
+impl UiExtensions {
+    /// The extensions `ui` asks for.
+    pub fn from_settings(ui: &UiSettings) -> Self {
+        Self {
+            multigrid: ui.multigrid,
+            cmdline: ui.cmdline,
+            popupmenu: ui.popupmenu,
+            messages: ui.messages,
+            tabline: ui.tabline != TablineVisibility::Never,
+        }
+    }
+
+    fn from_ui_options(options: &[Value]) -> Self {
+        let has = |name: &str| options.iter().any(|option| option.as_str() == Some(name));
+        Self {
+            multigrid: has("ext_multigrid"),
+            cmdline: has("ext_cmdline"),
+            popupmenu: has("ext_popupmenu"),
+            messages: has("ext_messages"),
+            tabline: has("ext_tabline"),
+        }
+    }
+
+    /// The extensions in both `self` and `supported`.
+    pub fn intersect(self, supported: Self) -> Self {
+        Self {
+            multigrid: self.multigrid && supported.multigrid,
+            cmdline: self.cmdline && supported.cmdline,
+            popupmenu: self.popupmenu && supported.popupmenu,
+            messages: self.messages && supported.messages,
+            tabline: self.tabline && supported.tabline,
+        }
+    }
+
+    /// Names of the extensions in `self` but not in `other`, such as
+    /// `ext_multigrid`.
+    pub fn missing_from(self, other: Self) -> Vec<&'static str> {
+        [
+            ("ext_multigrid", self.multigrid, other.multigrid),
+            ("ext_cmdline", self.cmdline, other.cmdline),
+            ("ext_popupmenu", self.popupmenu, other.popupmenu),
+            ("ext_messages", self.messages, other.messages),
+            ("ext_tabline", self.tabline, other.tabline),
+        ]
+        .into_iter()
+        .filter(|&(_, here, there)| here && !there)
+        .map(|(name, _, _)| name)
+        .collect()
+    }
+}
+
+impl Capabilities {
+    /// Extracts capabilities from the `[channel_id, metadata]` pair returned
+    /// by `nvim_get_api_info`. Unknown shapes yield the defaults, which keep
+    /// every optional feature off.
+    pub fn from_api_info(info: &[Value]) -> Self {
+        let channel_id = info.first().and_then(Value::as_u64).unwrap_or(0);
+        let metadata = info.get(1);
+        let api_level = metadata
+            .and_then(|m| map_get(m, "version"))
+            .and_then(|v| map_get(v, "api_level"))
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        let extensions = metadata
+            .and_then(|m| map_get(m, "ui_options"))
+            .and_then(Value::as_array)
+            .map(|options| UiExtensions::from_ui_options(options))
+            .unwrap_or_default();
+
+        Self {
+            channel_id,
+            api_level,
+            extensions,
+        }
+    }
+
+    /// What to attach with: the extensions `ui` asks for that this Neovim
+    /// has.
+    pub fn negotiate(&self, ui: &UiSettings) -> UiExtensions {
+        UiExtensions::from_settings(ui).intersect(self.extensions)
+    }
+
+    /// Whether precise (pixel accumulated) trackpad scrolling should be sent
+    /// instead of one scroll tick per wheel event.
+    pub fn analog_scroll(&self) -> bool {
+        self.api_level >= SMOOTH_SCROLL_API_LEVEL
+    }
+}
+
+fn map_get<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    value
+        .as_map()?
+        .iter()
+        .find(|(k, _)| k.as_str() == Some(key))
+        .map(|(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_info(api_level: u64) -> Vec<Value> {
+        vec![
+            Value::from(1u64),
+            Value::Map(vec![(
+                Value::from("version"),
+                Value::Map(vec![
+                    (Value::from("major"), Value::from(0u64)),
+                    (Value::from("api_level"), Value::from(api_level)),
+                ]),
+            )]),
+        ]
+    }
+
+    #[test]
+    fn test_negotiate_ui_extensions() {
+        let mut info = api_info(6);
+        let options = ["rgb", "ext_cmdline", "ext_popupmenu", "ext_linegrid"];
+        if let Value::Map(metadata) = &mut info[1] {
+            metadata.push((
+                Value::from("ui_options"),
+                Value::Array(options.iter().map(|&o| Value::from(o)).collect()),
+            ));
+        }
+        let caps = Capabilities::from_api_info(&info);
+        assert!(caps.extensions.cmdline && caps.extensions.popupmenu);
+        assert!(!caps.extensions.multigrid && !caps.extensions.tabline);
+
+        let ui = UiSettings {
+            multigrid: true,
+            cmdline: true,
+            tabline: TablineVisibility::Never,
+            ..UiSettings::default()
+        };
+        let requested = UiExtensions::from_settings(&ui);
+        let negotiated = caps.negotiate(&ui);
+        assert_eq!(
+            negotiated,
+            UiExtensions {
+                cmdline: true,
+                popupmenu: true,
+                ..UiExtensions::default()
+            }
+        );
+        assert_eq!(requested.missing_from(negotiated), ["ext_multigrid"]);
+    }
+
+    #[test]
+    fn test_from_api_info() {
+        let caps = Capabilities::from_api_info(&api_info(12));
+        assert_eq!(caps.channel_id, 1);
+        assert_eq!(caps.api_level, 12);
+        assert!(caps.analog_scroll());
+    }
+
+    #[test]
+    fn test_old_neovim_has_no_analog_scroll() {
+        let caps = Capabilities::from_api_info(&api_info(11));
+        assert!(!caps.analog_scroll());
+    }
+
+    #[test]
+    fn test_malformed_api_info() {
+        assert_eq!(Capabilities::from_api_info(&[]), Capabilities::default());
+        let caps = Capabilities::from_api_info(&[Value::from(3u64), Value::from("x")]);
+        assert_eq!(caps.channel_id, 3);
+        assert_eq!(caps.api_level, 0);
+        assert!(!caps.analog_scroll());
+    }
+}
@@ -1,19 +1,69 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
-use winit::event_loop::EventLoopProxy;
 
-use crate::bridge::NeovimProcess;
-use crate::constants::{DEFAULT_COLS, DEFAULT_ROWS};
-use crate::event::{NeovimEvent, UserEvent};
+use nvim_rs::Value;
+
+use super::request::{RequestError, RequestId, Requests};
+use crate::bridge::{NeovimProcess, ServerAddress, UiExtensions};
+use crate::config::UiSettings;
+use crate::constants::{DEFAULT_COLS, DEFAULT_ROWS, MAX_GRID_COLS, MAX_GRID_ROWS};
+use crate::event::{EventSender, NeovimEvent, UserEvent};
+
+/// How long Neovim gets to take `qa!` and exit on its own, both together,
+/// before it is killed.
+const NEOVIM_EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Upper bound on how long the GUI blocks waiting for the bridge thread.
+/// Longer than the exit timeout so a kill has time to land.
+const BRIDGE_SHUTDOWN_TIMEOUT: Duration =
+    NEOVIM_EXIT_TIMEOUT.saturating_add(Duration::from_millis(500));
+
+/// Attempts at reaching a `--server` again after its connection dropped,
+/// `RECONNECT_DELAY` apart.
+const RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
 
 pub enum AppCommand {
-    SpawnNeovim(Vec<String>),
+    /// Start `nvim --embed` with these arguments, in `cwd` if given.
+    SpawnNeovim {
+        args: Vec<String>,
+        cwd: Option<PathBuf>,
+    },
+    /// Attach to a server started with `nvim --listen`.
+    Connect(ServerAddress),
+    /// Attach to the last `Connect` server again.
+    Reconnect,
+    /// Start the last `SpawnNeovim` Neovim again, after it crashed.
+    Restart,
     Resize {
         cols: u64,
         rows: u64,
     },
+    /// Ask for a split window's grid to be `cols` by `rows` whatever room
+    /// the window takes; 0 by 0 takes the request back.
+    ResizeGrid {
+        grid: u64,
+        cols: u64,
+        rows: u64,
+    },
     Input(String),
+    /// The window gained or lost focus, for FocusGained and FocusLost.
+    SetFocus(bool),
+    /// Run an Ex command.
+    Command(String),
+    Echo(Vec<String>),
+    /// Open `lines` in a scratch buffer called `name`, in a new window.
+    ShowLines {
+        name: String,
+        lines: Vec<String>,
+    },
+    /// Paste text at the cursor, as `nvim_paste` does.
+    Paste(String),
     MouseInput {
         button: String,
         action: String,
@@ -22,6 +72,19 @@ pub enum AppCommand {
         row: i64,
         col: i64,
     },
+    /// Ask Neovim which tabpages hold modified buffers; answered with
+    /// `NeovimEvent::TabsModified`.
+    QueryTabsModified,
+    /// Run `:confirm qa`, so Neovim asks about unsaved changes before
+    /// quitting. Answered with `NeovimEvent::QuitCancelled` if it doesn't.
+    ConfirmQuit,
+    /// Call an API method; answered with `NeovimEvent::Response`.
+    Request {
+        id: RequestId,
+        method: String,
+        args: Vec<Value>,
+        timeout: Duration,
+    },
     Quit,
 }
 
@@ -29,11 +92,42 @@ pub enum AppCommand {
 impl PartialEq for AppCommand {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::SpawnNeovim(a), Self::SpawnNeovim(b)) => a == b,
+            (Self::SpawnNeovim { args: a1, cwd: c1 }, Self::SpawnNeovim { args: a2, cwd: c2 }) => {
+                a1 == a2 && c1 == c2
+            }
+            (Self::Connect(a), Self::Connect(b)) => a == b,
+            (Self::Reconnect, Self::Reconnect) => true,
+            (Self::Restart, Self::Restart) => true,
             (Self::Resize { cols: c1, rows: r1 }, Self::Resize { cols: c2, rows: r2 }) => {
                 c1 == c2 && r1 == r2
             }
+            (
+                Self::ResizeGrid {
+                    grid: g1,
+                    cols: c1,
+                    rows: r1,
+                },
+                Self::ResizeGrid {
+                    grid: g2,
+                    cols: c2,
+                    rows: r2,
+                },
+            ) => g1 == g2 && c1 == c2 && r1 == r2,
             (Self::Input(a), Self::Input(b)) => a == b,
+            (Self::SetFocus(a), Self::SetFocus(b)) => a == b,
+            (Self::Command(a), Self::Command(b)) => a == b,
+            (Self::Echo(a), Self::Echo(b)) => a == b,
+            (
+                Self::ShowLines {
+                    name: n1,
+                    lines: l1,
+                },
+                Self::ShowLines {
+                    name: n2,
+                    lines: l2,
+                },
+            ) => n1 == n2 && l1 == l2,
+            (Self::Paste(a), Self::Paste(b)) => a == b,
             (
                 Self::MouseInput {
                     button: b1,
@@ -52,6 +146,22 @@ impl PartialEq for AppCommand {
                     col: c2,
                 },
             ) => b1 == b2 && a1 == a2 && m1 == m2 && g1 == g2 && r1 == r2 && c1 == c2,
+            (Self::QueryTabsModified, Self::QueryTabsModified) => true,
+            (Self::ConfirmQuit, Self::ConfirmQuit) => true,
+            (
+                Self::Request {
+                    id: i1,
+                    method: m1,
+                    args: a1,
+                    timeout: t1,
+                },
+                Self::Request {
+                    id: i2,
+                    method: m2,
+                    args: a2,
+                    timeout: t2,
+                },
+            ) => i1 == i2 && m1 == m2 && a1 == a2 && t1 == t2,
             (Self::Quit, Self::Quit) => true,
             _ => false,
         }
@@ -62,13 +172,35 @@ impl PartialEq for AppCommand {
 impl std::fmt::Debug for AppCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::SpawnNeovim(args) => f.debug_tuple("SpawnNeovim").field(args).finish(),
+            Self::SpawnNeovim { args, cwd } => f
+                .debug_struct("SpawnNeovim")
+                .field("args", args)
+                .field("cwd", cwd)
+                .finish(),
+            Self::Connect(address) => f.debug_tuple("Connect").field(address).finish(),
+            Self::Reconnect => write!(f, "Reconnect"),
+            Self::Restart => write!(f, "Restart"),
             Self::Resize { cols, rows } => f
                 .debug_struct("Resize")
                 .field("cols", cols)
                 .field("rows", rows)
                 .finish(),
+            Self::ResizeGrid { grid, cols, rows } => f
+                .debug_struct("ResizeGrid")
+                .field("grid", grid)
+                .field("cols", cols)
+                .field("rows", rows)
+                .finish(),
             Self::Input(keys) => f.debug_tuple("Input").field(keys).finish(),
+            Self::SetFocus(gained) => f.debug_tuple("SetFocus").field(gained).finish(),
+            Self::Command(command) => f.debug_tuple("Command").field(command).finish(),
+            Self::Echo(lines) => f.debug_tuple("Echo").field(lines).finish(),
+            Self::ShowLines { name, lines } => f
+                .debug_struct("ShowLines")
+                .field("name", name)
+                .field("lines", lines)
+                .finish(),
+            Self::Paste(text) => f.debug_tuple("Paste").field(text).finish(),
             Self::MouseInput {
                 button,
                 action,
@@ -85,6 +217,20 @@ impl std::fmt::Debug for AppCommand {
                 .field("row", row)
                 .field("col", col)
                 .finish(),
+            Self::QueryTabsModified => write!(f, "QueryTabsModified"),
+            Self::ConfirmQuit => write!(f, "ConfirmQuit"),
+            Self::Request {
+                id,
+                method,
+                args,
+                timeout,
+            } => f
+                .debug_struct("Request")
+                .field("id", id)
+                .field("method", method)
+                .field("args", args)
+                .field("timeout", timeout)
+                .finish(),
             Self::Quit => write!(f, "Quit"),
         }
     }
@@ -94,23 +240,28 @@ pub struct AppBridge {
     command_tx: mpsc::UnboundedSender<AppCommand>,
     #[allow(dead_code)]
     runtime: Arc<Runtime>,
+    thread: Option<JoinHandle<()>>,
+    next_request: AtomicU64,
 }
 
 impl AppBridge {
-    pub fn new(event_proxy: EventLoopProxy<UserEvent>) -> Self {
+    pub fn new(event_proxy: impl Into<EventSender>, ui: UiSettings) -> Self {
+        let event_proxy = event_proxy.into();
         let runtime = Arc::new(Runtime::new().expect("Failed to create tokio runtime"));
         let (command_tx, command_rx) = mpsc::unbounded_channel();
 
         let rt = runtime.clone();
-        std::thread::spawn(move || {
+        let thread = std::thread::spawn(move || {
             rt.block_on(async move {
-                run_neovim_loop(event_proxy, command_rx).await;
+                run_neovim_loop(event_proxy, command_rx, ui).await;
             });
         });
 
         Self {
             command_tx,
             runtime,
+            thread: Some(thread),
+            next_request: AtomicU64::new(1),
         }
     }
 
@@ -122,23 +273,70 @@ impl AppBridge {
             Self {
                 command_tx,
                 runtime,
+                thread: None,
+                next_request: AtomicU64::new(1),
             },
             command_rx,
         )
     }
 
-    pub fn spawn_neovim(&self, args: Vec<String>) {
-        let _ = self.command_tx.send(AppCommand::SpawnNeovim(args));
+    pub fn spawn_neovim(&self, args: Vec<String>, cwd: Option<PathBuf>) {
+        let _ = self.command_tx.send(AppCommand::SpawnNeovim { args, cwd });
+    }
+
+    pub fn connect(&self, address: ServerAddress) {
+        let _ = self.command_tx.send(AppCommand::Connect(address));
+    }
+
+    pub fn reconnect(&self) {
+        let _ = self.command_tx.send(AppCommand::Reconnect);
+    }
+
+    pub fn restart(&self) {
+        let _ = self.command_tx.send(AppCommand::Restart);
     }
 
     pub fn resize(&self, cols: u64, rows: u64) {
-        let _ = self.command_tx.send(AppCommand::Resize { cols, rows });
+        let _ = self.command_tx.send(AppCommand::Resize {
+            cols: cols.clamp(1, MAX_GRID_COLS),
+            rows: rows.clamp(1, MAX_GRID_ROWS),
+        });
+    }
+
+    /// Asks for `grid` to be `cols` by `rows`, for a zoomed window; 0 by 0
+    /// lets it follow its window's size again.
+    pub fn resize_grid(&self, grid: u64, cols: u64, rows: u64) {
+        let _ = self.command_tx.send(AppCommand::ResizeGrid {
+            grid,
+            cols: cols.min(MAX_GRID_COLS),
+            rows: rows.min(MAX_GRID_ROWS),
+        });
     }
 
     pub fn input(&self, keys: String) {
         let _ = self.command_tx.send(AppCommand::Input(keys));
     }
 
+    pub fn set_focus(&self, gained: bool) {
+        let _ = self.command_tx.send(AppCommand::SetFocus(gained));
+    }
+
+    pub fn command(&self, command: String) {
+        let _ = self.command_tx.send(AppCommand::Command(command));
+    }
+
+    pub fn echo(&self, lines: Vec<String>) {
+        let _ = self.command_tx.send(AppCommand::Echo(lines));
+    }
+
+    pub fn show_lines(&self, name: String, lines: Vec<String>) {
+        let _ = self.command_tx.send(AppCommand::ShowLines { name, lines });
+    }
+
+    pub fn paste(&self, text: String) {
+        let _ = self.command_tx.send(AppCommand::Paste(text));
+    }
+
     pub fn mouse_input(
         &self,
         button: &str,
@@ -158,44 +356,185 @@ impl AppBridge {
         });
     }
 
+    pub fn query_tabs_modified(&self) {
+        let _ = self.command_tx.send(AppCommand::QueryTabsModified);
+    }
+
+    pub fn confirm_quit(&self) {
+        let _ = self.command_tx.send(AppCommand::ConfirmQuit);
+    }
+
     pub fn quit(&self) {
         let _ = self.command_tx.send(AppCommand::Quit);
     }
+
+    /// Calls API `method` with `args`. The result arrives later as
+    /// `NeovimEvent::Response` with the returned id, or a
+    /// `RequestError::Timeout` after `timeout`. Requests still waiting
+    /// when the bridge shuts down are dropped unanswered.
+    pub fn request(&self, method: &str, args: Vec<Value>, timeout: Duration) -> RequestId {
+        let id = self.next_request.fetch_add(1, Ordering::Relaxed);
+        let _ = self.command_tx.send(AppCommand::Request {
+            id,
+            method: method.to_string(),
+            args,
+            timeout,
+        });
+        id
+    }
+
+    /// Quit Neovim and wait for the bridge thread to finish.
+    ///
+    /// Commands are processed in order, so any input queued before this call
+    /// is written to Neovim before `qa!`. Blocks for at most
+    /// `BRIDGE_SHUTDOWN_TIMEOUT`; returns `false` if the thread was still
+    /// running when the deadline passed.
+    pub fn shutdown(mut self) -> bool {
+        self.quit();
+
+        let Some(thread) = self.thread.take() else {
+            return true;
+        };
+
+        let deadline = Instant::now() + BRIDGE_SHUTDOWN_TIMEOUT;
+        while !thread.is_finished() {
+            if Instant::now() >= deadline {
+                log::warn!(
+                    "Bridge thread did not stop within {:?}",
+                    BRIDGE_SHUTDOWN_TIMEOUT
+                );
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        if thread.join().is_err() {
+            log::error!("Bridge thread panicked during shutdown");
+        }
+        true
+    }
 }
 
 async fn run_neovim_loop(
-    event_proxy: EventLoopProxy<UserEvent>,
+    event_proxy: EventSender,
     mut command_rx: mpsc::UnboundedReceiver<AppCommand>,
+    ui: UiSettings,
 ) {
     let mut nvim: Option<NeovimProcess> = None;
+    // Last size asked for, so a reattach starts at the window's size.
+    let mut size = (DEFAULT_COLS, DEFAULT_ROWS);
+    let mut server: Option<ServerAddress> = None;
+    // What the last embedded Neovim was started with, to restart it.
+    let mut spawned: Option<(Vec<String>, Option<PathBuf>)> = None;
+    // Sessions are numbered, so a channel closing late isn't taken for
+    // the current one's.
+    let mut session = 0;
+    let (closed_tx, mut closed_rx) = mpsc::unbounded_channel();
+    let proxy = event_proxy.clone();
+    let mut requests = Requests::new(move |id, result| {
+        let _ = proxy.send_event(UserEvent::Neovim(NeovimEvent::Response { id, result }));
+    });
 
-    while let Some(cmd) = command_rx.recv().await {
-        match cmd {
-            AppCommand::SpawnNeovim(args) => {
-                match NeovimProcess::spawn(event_proxy.clone(), args).await {
-                    Ok(mut process) => {
-                        if let Err(e) = process.ui_attach(DEFAULT_COLS, DEFAULT_ROWS).await {
-                            log::error!("Failed to attach UI: {:?}", e);
-                            continue;
-                        }
-                        log::info!("Neovim UI attached");
-
-                        if let Some(io_handle) = process.io_handle.take() {
-                            let proxy = event_proxy.clone();
-                            tokio::spawn(async move {
-                                let _ = io_handle.await;
-                                let _ = proxy.send_event(UserEvent::Neovim(NeovimEvent::Quit));
-                            });
-                        }
-
-                        nvim = Some(process);
+    loop {
+        let cmd = tokio::select! {
+            cmd = command_rx.recv() => match cmd {
+                Some(cmd) => cmd,
+                None => break,
+            },
+            done = requests.join_next() => {
+                requests.finished(done);
+                continue;
+            }
+            Some(closed) = closed_rx.recv() => {
+                if closed == session {
+                    if let Some(process) = nvim.take() {
+                        let event = process.ended(NEOVIM_EXIT_TIMEOUT).await;
+                        let _ = event_proxy.send_event(UserEvent::Neovim(event));
                     }
+                }
+                continue;
+            }
+        };
+        let mut attach = |process| {
+            session += 1;
+            attach(
+                process,
+                &event_proxy,
+                ui,
+                size,
+                (session, closed_tx.clone()),
+            )
+        };
+        match cmd {
+            AppCommand::SpawnNeovim { args, cwd } => {
+                spawned = Some((args.clone(), cwd.clone()));
+                match NeovimProcess::spawn(event_proxy.clone(), args, cwd).await {
+                    Ok(process) => nvim = attach(process).await,
                     Err(e) => {
                         log::error!("Failed to spawn Neovim: {}", e);
                     }
                 }
             }
+            AppCommand::Restart => {
+                let Some((args, cwd)) = spawned.clone() else {
+                    continue;
+                };
+                log::info!("Restarting Neovim");
+                nvim = None;
+                match NeovimProcess::spawn(event_proxy.clone(), args, cwd).await {
+                    Ok(process) => nvim = attach(process).await,
+                    Err(e) => log::error!("Failed to restart Neovim: {}", e),
+                }
+                if nvim.is_none() {
+                    let _ = event_proxy.send_event(UserEvent::Neovim(NeovimEvent::Quit(1)));
+                }
+            }
+            AppCommand::Connect(address) => {
+                match NeovimProcess::connect(event_proxy.clone(), &address).await {
+                    Ok(process) => nvim = attach(process).await,
+                    Err(e) => log::error!("Failed to connect to {}: {}", address, e),
+                }
+                if nvim.is_none() {
+                    let _ = event_proxy.send_event(UserEvent::Neovim(NeovimEvent::Quit(1)));
+                }
+                server = Some(address);
+            }
+            AppCommand::Reconnect => {
+                let Some(address) = server.clone() else {
+                    continue;
+                };
+                nvim = None;
+                for attempt in 1..=RECONNECT_ATTEMPTS {
+                    match NeovimProcess::connect(event_proxy.clone(), &address).await {
+                        Ok(process) => {
+                            nvim = attach(process).await;
+                            break;
+                        }
+                        Err(e) => log::info!(
+                            "Reconnecting to {} ({}/{}): {}",
+                            address,
+                            attempt,
+                            RECONNECT_ATTEMPTS,
+                            e
+                        ),
+                    }
+                    // Input sent while disconnected has nowhere to go.
+                    tokio::select! {
+                        _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+                        cmd = command_rx.recv() => {
+                            if matches!(cmd, None | Some(AppCommand::Quit)) {
+                                return;
+                            }
+                        }
+                    }
+                }
+                if nvim.is_none() {
+                    log::error!("Lost connection to {}", address);
+                    let _ = event_proxy.send_event(UserEvent::Neovim(NeovimEvent::Quit(1)));
+                }
+            }
             AppCommand::Resize { cols, rows } => {
+                size = (cols, rows);
                 if let Some(ref nvim) = nvim {
                     let neovim = nvim.neovim.clone();
                     tokio::spawn(async move {
@@ -205,6 +544,19 @@ async fn run_neovim_loop(
                     });
                 }
             }
+            AppCommand::ResizeGrid { grid, cols, rows } => {
+                if let Some(ref nvim) = nvim {
+                    let neovim = nvim.neovim.clone();
+                    tokio::spawn(async move {
+                        let resized = neovim
+                            .ui_try_resize_grid(grid as i64, cols as i64, rows as i64)
+                            .await;
+                        if let Err(e) = resized {
+                            log::warn!("Failed to resize grid {}: {:?}", grid, e);
+                        }
+                    });
+                }
+            }
             AppCommand::Input(keys) => {
                 if let Some(ref nvim) = nvim {
                     if let Err(e) = nvim.input(&keys).await {
@@ -212,6 +564,44 @@ async fn run_neovim_loop(
                     }
                 }
             }
+            AppCommand::Command(command) => {
+                if let Some(ref nvim) = nvim {
+                    if let Err(e) = nvim.neovim.command(&command).await {
+                        log::warn!("Failed to run command: {:?}", e);
+                    }
+                }
+            }
+            AppCommand::SetFocus(gained) => {
+                if let Some(ref nvim) = nvim {
+                    let neovim = nvim.neovim.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = neovim.ui_set_focus(gained).await {
+                            log::warn!("Failed to set UI focus: {:?}", e);
+                        }
+                    });
+                }
+            }
+            AppCommand::Echo(lines) => {
+                if let Some(ref nvim) = nvim {
+                    if let Err(e) = nvim.echo(&lines).await {
+                        log::warn!("Failed to echo message: {:?}", e);
+                    }
+                }
+            }
+            AppCommand::ShowLines { name, lines } => {
+                if let Some(ref nvim) = nvim {
+                    if let Err(e) = nvim.show_lines(&name, lines).await {
+                        log::warn!("Failed to show {}: {:?}", name, e);
+                    }
+                }
+            }
+            AppCommand::Paste(text) => {
+                if let Some(ref nvim) = nvim {
+                    if let Err(e) = nvim.neovim.paste(&text, false, -1).await {
+                        log::warn!("Failed to paste: {:?}", e);
+                    }
+                }
+            }
             AppCommand::MouseInput {
                 button,
                 action,
@@ -229,14 +619,134 @@ async fn run_neovim_loop(
                     }
                 }
             }
-            AppCommand::Quit => {
+            AppCommand::QueryTabsModified => {
                 if let Some(ref nvim) = nvim {
-                    let _ = nvim.quit().await;
+                    let neovim = nvim.neovim.clone();
+                    let proxy = event_proxy.clone();
+                    tokio::spawn(async move {
+                        match NeovimProcess::tabs_modified(&neovim).await {
+                            Ok(modified) => {
+                                let _ = proxy.send_event(UserEvent::Neovim(
+                                    NeovimEvent::TabsModified(modified),
+                                ));
+                            }
+                            Err(e) => log::warn!("Failed to query modified tabs: {:?}", e),
+                        }
+                    });
                 }
-                break;
             }
+            AppCommand::ConfirmQuit => {
+                let Some(ref nvim) = nvim else {
+                    // Nothing to ask; let the GUI close.
+                    let _ = event_proxy.send_event(UserEvent::Neovim(NeovimEvent::Quit(0)));
+                    continue;
+                };
+                // The prompt waits for input, which this loop must keep
+                // forwarding, so don't wait for the answer here.
+                let neovim = nvim.neovim.clone();
+                let proxy = event_proxy.clone();
+                tokio::spawn(async move {
+                    // A successful quit closes the channel before replying.
+                    if neovim.command("confirm qa").await.is_ok() {
+                        let _ = proxy.send_event(UserEvent::Neovim(NeovimEvent::QuitCancelled));
+                    }
+                });
+            }
+            AppCommand::Request {
+                id,
+                method,
+                args,
+                timeout,
+            } => {
+                let Some(ref nvim) = nvim else {
+                    requests.reject(id, RequestError::NotConnected);
+                    continue;
+                };
+                let neovim = nvim.neovim.clone();
+                requests.spawn(id, timeout, async move { neovim.call(&method, args).await });
+            }
+            AppCommand::Quit => break,
+        }
+    }
+
+    if !requests.is_empty() {
+        log::debug!("Dropping {} unanswered requests", requests.len());
+    }
+    requests.cancel_all();
+
+    // Reached on an explicit quit or when the bridge is dropped. Either way
+    // make sure the child does not outlive the GUI.
+    if let Some(process) = nvim.take() {
+        process.shutdown(NEOVIM_EXIT_TIMEOUT).await;
+    }
+}
+
+/// Attaches the UI to a freshly started or connected Neovim and watches
+/// the channel, sending the session's number to `closed` when it closes.
+/// Returns `None` if the UI could not attach.
+async fn attach(
+    mut process: NeovimProcess,
+    event_proxy: &EventSender,
+    ui: UiSettings,
+    (cols, rows): (u64, u64),
+    (session, closed): (u64, mpsc::UnboundedSender<u64>),
+) -> Option<NeovimProcess> {
+    // Asked before attaching, so only extensions this Neovim has are
+    // requested.
+    let caps = process.capabilities().await;
+    let requested = UiExtensions::from_settings(&ui);
+    let extensions = match &caps {
+        Ok(caps) => {
+            let negotiated = caps.negotiate(&ui);
+            for name in requested.missing_from(negotiated) {
+                log::warn!("Neovim API level {} has no {}", caps.api_level, name);
+            }
+            negotiated
+        }
+        Err(_) => requested,
+    };
+    if let Err(e) = process.ui_attach(cols, rows, extensions).await {
+        log::error!("Failed to attach UI: {:?}", e);
+        return None;
+    }
+    log::info!("Neovim UI attached");
+
+    match caps {
+        Ok(caps) => {
+            log::info!("Neovim API level {}", caps.api_level);
+            if let Err(e) = process.register_gui_commands(caps.channel_id).await {
+                log::warn!("Failed to register GUI commands: {:?}", e);
+            }
+            if let Err(e) = process.register_gui_functions(caps.channel_id).await {
+                log::warn!("Failed to define the gui Lua functions: {:?}", e);
+            }
+            if let Err(e) = process.register_buffer_state(caps.channel_id).await {
+                log::warn!("Failed to track buffer state: {:?}", e);
+            }
+            if ui.recent_files {
+                if let Err(e) = process.register_recent_files(caps.channel_id).await {
+                    log::warn!("Failed to track recent files: {:?}", e);
+                }
+            }
+            // Also for dragging the selection out of the window on macOS.
+            if ui.primary_selection() || cfg!(target_os = "macos") {
+                if let Err(e) = process.register_visual_selection(caps.channel_id).await {
+                    log::warn!("Failed to track the Visual selection: {:?}", e);
+                }
+            }
+            let _ = event_proxy.send_event(UserEvent::Neovim(NeovimEvent::Capabilities(caps)));
         }
+        Err(e) => log::warn!("Failed to query Neovim API info: {:?}", e),
+    }
+
+    if let Some(io_handle) = process.io_handle.take() {
+        tokio::spawn(async move {
+            let _ = io_handle.await;
+            let _ = closed.send(session);
+        });
     }
+
+    Some(process)
 }
 
 #[cfg(test)]
@@ -248,14 +758,24 @@ mod tests {
         let (bridge, mut rx) = AppBridge::new_for_test();
 
         // SpawnNeovim
-        bridge.spawn_neovim(vec!["--clean".to_string()]);
+        bridge.spawn_neovim(vec!["--clean".to_string()], Some(PathBuf::from("src")));
         match rx.blocking_recv() {
-            Some(AppCommand::SpawnNeovim(args)) => {
+            Some(AppCommand::SpawnNeovim { args, cwd }) => {
                 assert_eq!(args, vec!["--clean".to_string()]);
+                assert_eq!(cwd, Some(PathBuf::from("src")));
             }
             _ => panic!("Expected SpawnNeovim"),
         }
 
+        // Connect / Reconnect
+        let address = ServerAddress::parse("localhost:6666");
+        bridge.connect(address.clone());
+        assert_eq!(rx.blocking_recv(), Some(AppCommand::Connect(address)));
+        bridge.reconnect();
+        assert_eq!(rx.blocking_recv(), Some(AppCommand::Reconnect));
+        bridge.restart();
+        assert_eq!(rx.blocking_recv(), Some(AppCommand::Restart));
+
         // Resize
         bridge.resize(100, 50);
         match rx.blocking_recv() {
@@ -265,6 +785,15 @@ mod tests {
             }
             _ => panic!("Expected Resize"),
         }
+        bridge.resize_grid(2, 40, 10);
+        assert_eq!(
+            rx.blocking_recv(),
+            Some(AppCommand::ResizeGrid {
+                grid: 2,
+                cols: 40,
+                rows: 10
+            })
+        );
 
         // Input
         bridge.input("<Esc>".to_string());
@@ -275,6 +804,24 @@ mod tests {
             _ => panic!("Expected Input"),
         }
 
+        // SetFocus
+        bridge.set_focus(false);
+        assert_eq!(rx.blocking_recv(), Some(AppCommand::SetFocus(false)));
+
+        // Command
+        bridge.command("edit x".to_string());
+        assert_eq!(
+            rx.blocking_recv(),
+            Some(AppCommand::Command("edit x".to_string()))
+        );
+
+        // Echo
+        bridge.echo(vec!["line".to_string()]);
+        assert_eq!(
+            rx.blocking_recv(),
+            Some(AppCommand::Echo(vec!["line".to_string()]))
+        );
+
         // MouseInput
         bridge.mouse_input("left", "press", "", 0, 10, 20);
         match rx.blocking_recv() {
@@ -296,6 +843,33 @@ mod tests {
             _ => panic!("Expected MouseInput"),
         }
 
+        // QueryTabsModified
+        bridge.query_tabs_modified();
+        assert_eq!(rx.blocking_recv(), Some(AppCommand::QueryTabsModified));
+
+        // Request, numbered in order
+        let timeout = Duration::from_secs(1);
+        let first = bridge.request("nvim_get_current_buf", vec![], timeout);
+        let second = bridge.request("nvim_buf_get_name", vec![Value::from(0)], timeout);
+        assert!(second > first);
+        assert_eq!(
+            rx.blocking_recv(),
+            Some(AppCommand::Request {
+                id: first,
+                method: "nvim_get_current_buf".to_string(),
+                args: vec![],
+                timeout,
+            })
+        );
+        assert!(matches!(
+            rx.blocking_recv(),
+            Some(AppCommand::Request { id, .. }) if id == second
+        ));
+
+        // ConfirmQuit
+        bridge.confirm_quit();
+        assert_eq!(rx.blocking_recv(), Some(AppCommand::ConfirmQuit));
+
         // Quit
         bridge.quit();
         match rx.blocking_recv() {
@@ -303,4 +877,19 @@ mod tests {
             _ => panic!("Expected Quit"),
         }
     }
+
+    #[test]
+    fn test_app_bridge_shutdown_sends_quit_after_pending_commands() {
+        let (bridge, mut rx) = AppBridge::new_for_test();
+
+        bridge.input("ZZ".to_string());
+        assert!(bridge.shutdown());
+
+        assert_eq!(
+            rx.blocking_recv(),
+            Some(AppCommand::Input("ZZ".to_string()))
+        );
+        assert_eq!(rx.blocking_recv(), Some(AppCommand::Quit));
+        assert_eq!(rx.blocking_recv(), None);
+    }
 }
@@ -1,6 +1,6 @@
 use nvim_rs::Value;
 
-use crate::editor::{HighlightAttributes, ModeInfo};
+use crate::editor::{HighlightAttributes, ModeInfo, PopupmenuItem, StyledChunk};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum RedrawEvent {
@@ -34,6 +34,93 @@ pub enum RedrawEvent {
     GridDestroy {
         grid: u64,
     },
+    WinPos {
+        grid: u64,
+        win: u64,
+        start_row: usize,
+        start_col: usize,
+        width: usize,
+        height: usize,
+    },
+    WinFloatPos {
+        grid: u64,
+        anchor: FloatAnchor,
+        anchor_grid: u64,
+        anchor_row: f64,
+        anchor_col: f64,
+        focusable: bool,
+        zindex: Option<u64>,
+    },
+    WinHide {
+        grid: u64,
+    },
+    WinClose {
+        grid: u64,
+    },
+    MsgSetPos {
+        grid: u64,
+        row: usize,
+        scrolled: bool,
+        sep_char: String,
+    },
+    PopupmenuShow {
+        items: Vec<PopupmenuItem>,
+        selected: Option<usize>,
+        row: usize,
+        col: usize,
+        grid: u64,
+    },
+    PopupmenuSelect {
+        selected: Option<usize>,
+    },
+    PopupmenuHide,
+    TablineUpdate {
+        /// Index of the current tab in `tabs`.
+        current: usize,
+        /// Buffer name shown for each tab, in order.
+        tabs: Vec<String>,
+    },
+    MsgShow {
+        kind: String,
+        content: Vec<StyledChunk>,
+        replace_last: bool,
+        /// Continue the previous message (Neovim 0.11+).
+        append: bool,
+    },
+    MsgClear,
+    MsgShowmode {
+        content: Vec<StyledChunk>,
+    },
+    MsgShowcmd {
+        content: Vec<StyledChunk>,
+    },
+    MsgRuler {
+        content: Vec<StyledChunk>,
+    },
+    MsgHistoryShow {
+        entries: Vec<(String, Vec<StyledChunk>)>,
+    },
+    MsgHistoryClear,
+    CmdlineShow {
+        content: Vec<StyledChunk>,
+        pos: usize,
+        firstc: String,
+        prompt: String,
+        indent: usize,
+        level: u64,
+    },
+    CmdlinePos {
+        pos: usize,
+        level: u64,
+    },
+    CmdlineSpecialChar {
+        c: String,
+        shift: bool,
+        level: u64,
+    },
+    CmdlineHide {
+        level: u64,
+    },
     HlAttrDefine {
         id: u64,
         attrs: HighlightAttributes,
@@ -73,6 +160,28 @@ pub enum RedrawEvent {
     MouseOff,
 }
 
+/// Which corner of a floating window is placed at its anchor position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatAnchor {
+    #[default]
+    NW,
+    NE,
+    SW,
+    SE,
+}
+
+impl FloatAnchor {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "NW" => Some(Self::NW),
+            "NE" => Some(Self::NE),
+            "SW" => Some(Self::SW),
+            "SE" => Some(Self::SE),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct GridCell {
     pub text: String,
@@ -1,9 +1,16 @@
+mod capabilities;
 mod command;
 pub mod events;
 mod neovim;
 pub mod parser;
 mod process;
+pub mod redraw_history;
+mod request;
 
+pub use capabilities::{Capabilities, UiExtensions};
 pub use command::AppBridge;
+#[cfg(test)]
+pub(crate) use command::AppCommand;
 pub use neovim::NeovimHandler;
-pub use process::{NeovimProcess, NvimWriter};
+pub use process::{NeovimProcess, NvimWriter, ServerAddress, Transport};
+pub use request::{RequestError, RequestId, Response};
@@ -1,18 +1,18 @@
 use async_trait::async_trait;
 use nvim_rs::{Handler, Neovim, Value};
-use winit::event_loop::EventLoopProxy;
 
 use super::parser::parse_redraw;
+use super::redraw_history;
 use super::NvimWriter;
-use crate::event::{NeovimEvent, UserEvent};
+use crate::event::{EventSender, NeovimEvent, Reply, UserEvent};
 
 #[derive(Clone)]
 pub struct NeovimHandler {
-    event_proxy: EventLoopProxy<UserEvent>,
+    event_proxy: EventSender,
 }
 
 impl NeovimHandler {
-    pub fn new(event_proxy: EventLoopProxy<UserEvent>) -> Self {
+    pub fn new(event_proxy: EventSender) -> Self {
         Self { event_proxy }
     }
 
@@ -36,11 +36,23 @@ impl Handler for NeovimHandler {
                 if events.is_empty() {
                     return;
                 }
+                redraw_history::record(&events);
 
                 // Send all events (including Flush) in a single batch.
                 // The window handler will request a redraw for this event.
                 self.send_event(NeovimEvent::Redraw(events));
             }
+            "gui" => {
+                let mut args = args.into_iter();
+                let Some(command) = args.next().and_then(|v| v.as_str().map(str::to_string)) else {
+                    log::warn!("Malformed gui notification");
+                    return;
+                };
+                self.send_event(NeovimEvent::GuiCommand {
+                    name: command,
+                    args: args.collect(),
+                });
+            }
             _ => {
                 log::debug!("Unhandled notification: {}", name);
             }
@@ -55,7 +67,28 @@ impl Handler for NeovimHandler {
     ) -> Result<Value, Value> {
         log::debug!("Request: {} {:?}", name, args);
 
-        Err(Value::from(format!("Unknown request: {}", name)))
+        match name.as_str() {
+            // From the SwapExists autocommand: Neovim waits for the user's
+            // choice, for as long as it takes.
+            "gui_swap_exists" => {
+                let mut args = args
+                    .into_iter()
+                    .map(|v| v.as_str().unwrap_or("").to_string());
+                let (swapname, file) = (
+                    args.next().unwrap_or_default(),
+                    args.next().unwrap_or_default(),
+                );
+                let (reply, answer) = Reply::new();
+                self.send_event(NeovimEvent::SwapExists {
+                    file,
+                    swapname,
+                    reply,
+                });
+                // Without an answer, Neovim asks in its own prompt.
+                Ok(Value::from(answer.await.unwrap_or_default()))
+            }
+            _ => Err(Value::from(format!("Unknown request: {}", name))),
+        }
     }
 }
 
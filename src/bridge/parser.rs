@@ -1,7 +1,9 @@
 use nvim_rs::Value;
 
-use super::events::{GridCell, RedrawEvent};
-use crate::editor::{Color, CursorShape, HighlightAttributes, ModeInfo, StyleFlags};
+use super::events::{FloatAnchor, GridCell, RedrawEvent};
+use crate::editor::{
+    Color, CursorShape, HighlightAttributes, ModeInfo, PopupmenuItem, StyleFlags, StyledChunk,
+};
 
 pub fn parse_redraw(args: Vec<Value>) -> Vec<RedrawEvent> {
     let mut events = Vec::new();
@@ -38,6 +40,30 @@ fn parse_single_event(name: &str, args: &[Value]) -> Option<RedrawEvent> {
         "grid_scroll" => parse_grid_scroll(args),
         "grid_cursor_goto" => parse_grid_cursor_goto(args),
         "grid_destroy" => parse_grid_destroy(args),
+        "win_pos" => parse_win_pos(args),
+        "win_float_pos" => parse_win_float_pos(args),
+        "win_hide" => parse_win_hide(args),
+        "win_close" => parse_win_close(args),
+        "msg_set_pos" => parse_msg_set_pos(args),
+        "popupmenu_show" => parse_popupmenu_show(args),
+        "popupmenu_select" => parse_popupmenu_select(args),
+        "popupmenu_hide" => Some(RedrawEvent::PopupmenuHide),
+        "tabline_update" => parse_tabline_update(args),
+        "msg_show" => parse_msg_show(args),
+        "msg_clear" => Some(RedrawEvent::MsgClear),
+        "msg_showmode" => {
+            parse_styled_content(args).map(|content| RedrawEvent::MsgShowmode { content })
+        }
+        "msg_showcmd" => {
+            parse_styled_content(args).map(|content| RedrawEvent::MsgShowcmd { content })
+        }
+        "msg_ruler" => parse_styled_content(args).map(|content| RedrawEvent::MsgRuler { content }),
+        "msg_history_show" => parse_msg_history_show(args),
+        "msg_history_clear" => Some(RedrawEvent::MsgHistoryClear),
+        "cmdline_show" => parse_cmdline_show(args),
+        "cmdline_pos" => parse_cmdline_pos(args),
+        "cmdline_special_char" => parse_cmdline_special_char(args),
+        "cmdline_hide" => parse_cmdline_hide(args),
         "hl_attr_define" => parse_hl_attr_define(args),
         "hl_group_set" => parse_hl_group_set(args),
         "default_colors_set" => parse_default_colors_set(args),
@@ -165,6 +191,229 @@ fn parse_grid_destroy(args: &[Value]) -> Option<RedrawEvent> {
     })
 }
 
+// win_* events carry the window handle as their second argument. It is an
+// ext value that the grid id already identifies, so it is skipped except
+// by win_pos, which tells which grid a window handle from Lua draws on.
+
+fn parse_win_pos(args: &[Value]) -> Option<RedrawEvent> {
+    if args.len() < 6 {
+        return None;
+    }
+    Some(RedrawEvent::WinPos {
+        grid: as_u64(&args[0])?,
+        win: as_handle(&args[1])?,
+        start_row: as_usize(&args[2])?,
+        start_col: as_usize(&args[3])?,
+        width: as_usize(&args[4])?,
+        height: as_usize(&args[5])?,
+    })
+}
+
+fn parse_win_float_pos(args: &[Value]) -> Option<RedrawEvent> {
+    if args.len() < 7 {
+        return None;
+    }
+    Some(RedrawEvent::WinFloatPos {
+        grid: as_u64(&args[0])?,
+        anchor: FloatAnchor::parse(args[2].as_str()?)?,
+        anchor_grid: as_u64(&args[3])?,
+        anchor_row: as_f64(&args[4])?,
+        anchor_col: as_f64(&args[5])?,
+        focusable: args[6].as_bool().unwrap_or(true),
+        // zindex was added in Neovim 0.10
+        zindex: args.get(7).and_then(as_u64),
+    })
+}
+
+fn parse_win_hide(args: &[Value]) -> Option<RedrawEvent> {
+    if args.is_empty() {
+        return None;
+    }
+    Some(RedrawEvent::WinHide {
+        grid: as_u64(&args[0])?,
+    })
+}
+
+fn parse_win_close(args: &[Value]) -> Option<RedrawEvent> {
+    if args.is_empty() {
+        return None;
+    }
+    Some(RedrawEvent::WinClose {
+        grid: as_u64(&args[0])?,
+    })
+}
+
+fn parse_msg_set_pos(args: &[Value]) -> Option<RedrawEvent> {
+    if args.len() < 4 {
+        return None;
+    }
+    Some(RedrawEvent::MsgSetPos {
+        grid: as_u64(&args[0])?,
+        row: as_usize(&args[1])?,
+        scrolled: args[2].as_bool().unwrap_or(false),
+        sep_char: args[3].as_str().unwrap_or("").to_string(),
+    })
+}
+
+fn parse_popupmenu_show(args: &[Value]) -> Option<RedrawEvent> {
+    if args.len() < 5 {
+        return None;
+    }
+
+    let items = args[0]
+        .as_array()?
+        .iter()
+        .filter_map(|item| {
+            let fields = item.as_array()?;
+            let field = |i: usize| {
+                fields
+                    .get(i)
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string()
+            };
+            Some(PopupmenuItem {
+                word: field(0),
+                kind: field(1),
+                menu: field(2),
+                info: field(3),
+            })
+        })
+        .collect();
+
+    Some(RedrawEvent::PopupmenuShow {
+        items,
+        selected: as_selection(&args[1])?,
+        row: as_usize(&args[2])?,
+        col: as_usize(&args[3])?,
+        // -1 is the cmdline menu (ext_cmdline), which we draw on the main grid
+        grid: as_i64(&args[4]).map_or(1, |g| u64::try_from(g).unwrap_or(1)),
+    })
+}
+
+fn parse_popupmenu_select(args: &[Value]) -> Option<RedrawEvent> {
+    if args.is_empty() {
+        return None;
+    }
+    Some(RedrawEvent::PopupmenuSelect {
+        selected: as_selection(&args[0])?,
+    })
+}
+
+/// Parses `[curtab, [{tab, name}, ...], curbuf, buffers]`. Only the
+/// tabpages are used; the buffer list is for bufferline-style UIs.
+fn parse_tabline_update(args: &[Value]) -> Option<RedrawEvent> {
+    if args.len() < 2 {
+        return None;
+    }
+
+    let current_tab = &args[0];
+    let mut current = 0;
+    let mut tabs = Vec::new();
+    for (i, entry) in args[1].as_array()?.iter().enumerate() {
+        let mut name = String::new();
+        for (key, value) in entry.as_map()? {
+            match key.as_str() {
+                Some("tab") if value == current_tab => current = i,
+                Some("name") => name = value.as_str().unwrap_or("").to_string(),
+                _ => {}
+            }
+        }
+        tabs.push(name);
+    }
+
+    Some(RedrawEvent::TablineUpdate { current, tabs })
+}
+
+/// Parses `[[attr_id, text, ...], ...]` message content. Chunks carry a
+/// trailing hl_id since Neovim 0.11, which we don't need.
+fn parse_chunks(value: &Value) -> Option<Vec<StyledChunk>> {
+    let chunks = value
+        .as_array()?
+        .iter()
+        .filter_map(|chunk| {
+            let fields = chunk.as_array()?;
+            Some(StyledChunk {
+                hl_id: fields.first().and_then(as_u64).unwrap_or(0),
+                text: fields.get(1)?.as_str()?.to_string(),
+            })
+        })
+        .collect();
+    Some(chunks)
+}
+
+fn parse_styled_content(args: &[Value]) -> Option<Vec<StyledChunk>> {
+    parse_chunks(args.first()?)
+}
+
+fn parse_msg_show(args: &[Value]) -> Option<RedrawEvent> {
+    if args.len() < 3 {
+        return None;
+    }
+    Some(RedrawEvent::MsgShow {
+        kind: args[0].as_str().unwrap_or("").to_string(),
+        content: parse_chunks(&args[1])?,
+        replace_last: args[2].as_bool().unwrap_or(false),
+        // args[3] is `history` (0.11), which only matters to Neovim itself
+        append: args.get(4).and_then(Value::as_bool).unwrap_or(false),
+    })
+}
+
+fn parse_msg_history_show(args: &[Value]) -> Option<RedrawEvent> {
+    let entries = args
+        .first()?
+        .as_array()?
+        .iter()
+        .filter_map(|entry| {
+            let fields = entry.as_array()?;
+            let kind = fields.first()?.as_str().unwrap_or("").to_string();
+            Some((kind, parse_chunks(fields.get(1)?)?))
+        })
+        .collect();
+    Some(RedrawEvent::MsgHistoryShow { entries })
+}
+
+fn parse_cmdline_show(args: &[Value]) -> Option<RedrawEvent> {
+    if args.len() < 6 {
+        return None;
+    }
+    Some(RedrawEvent::CmdlineShow {
+        content: parse_chunks(&args[0])?,
+        pos: as_usize(&args[1])?,
+        firstc: args[2].as_str().unwrap_or("").to_string(),
+        prompt: args[3].as_str().unwrap_or("").to_string(),
+        indent: as_usize(&args[4]).unwrap_or(0),
+        level: as_u64(&args[5])?,
+    })
+}
+
+fn parse_cmdline_pos(args: &[Value]) -> Option<RedrawEvent> {
+    if args.len() < 2 {
+        return None;
+    }
+    Some(RedrawEvent::CmdlinePos {
+        pos: as_usize(&args[0])?,
+        level: as_u64(&args[1])?,
+    })
+}
+
+fn parse_cmdline_special_char(args: &[Value]) -> Option<RedrawEvent> {
+    if args.len() < 3 {
+        return None;
+    }
+    Some(RedrawEvent::CmdlineSpecialChar {
+        c: args[0].as_str()?.to_string(),
+        shift: args[1].as_bool().unwrap_or(false),
+        level: as_u64(&args[2])?,
+    })
+}
+
+fn parse_cmdline_hide(args: &[Value]) -> Option<RedrawEvent> {
+    Some(RedrawEvent::CmdlineHide {
+        level: as_u64(args.first()?)?,
+    })
+}
+
 fn parse_hl_attr_define(args: &[Value]) -> Option<RedrawEvent> {
     if args.len() < 2 {
         return None;
@@ -193,55 +442,35 @@ fn parse_hl_attr_define(args: &[Value]) -> Option<RedrawEvent> {
                     attrs.special = Some(Color::from_u24(sp));
                 }
             }
-            "bold" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::BOLD;
-                }
+            "bold" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::BOLD;
             }
-            "italic" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::ITALIC;
-                }
+            "italic" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::ITALIC;
             }
-            "underline" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::UNDERLINE;
-                }
+            "underline" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::UNDERLINE;
             }
-            "undercurl" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::UNDERCURL;
-                }
+            "undercurl" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::UNDERCURL;
             }
-            "underdouble" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::UNDERDOUBLE;
-                }
+            "underdouble" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::UNDERDOUBLE;
             }
-            "underdotted" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::UNDERDOTTED;
-                }
+            "underdotted" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::UNDERDOTTED;
             }
-            "underdashed" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::UNDERDASHED;
-                }
+            "underdashed" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::UNDERDASHED;
             }
-            "strikethrough" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::STRIKETHROUGH;
-                }
+            "strikethrough" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::STRIKETHROUGH;
             }
-            "reverse" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::REVERSE;
-                }
+            "reverse" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::REVERSE;
             }
-            "altfont" => {
-                if value.as_bool().unwrap_or(false) {
-                    attrs.style |= StyleFlags::ALTFONT;
-                }
+            "altfont" if value.as_bool().unwrap_or(false) => {
+                attrs.style |= StyleFlags::ALTFONT;
             }
             "blend" => {
                 if let Some(b) = as_u64(value) {
@@ -257,6 +486,38 @@ fn parse_hl_attr_define(args: &[Value]) -> Option<RedrawEvent> {
         }
     }
 
+    // Only colors are taken from the cterm attributes; they stand in for
+    // RGB ones when 'termguicolors' is off (see `HighlightMap`).
+    if let Some(cterm_attrs) = args.get(2).and_then(Value::as_map) {
+        for (key, value) in cterm_attrs {
+            let index = as_u64(value).and_then(|i| u8::try_from(i).ok());
+            match key.as_str().unwrap_or("") {
+                "foreground" => attrs.cterm_foreground = index,
+                "background" => attrs.cterm_background = index,
+                _ => {}
+            }
+        }
+    }
+
+    // With ext_hlstate, the groups the highlight was combined from.
+    if let Some(info) = args.get(3).and_then(Value::as_array) {
+        attrs.groups = info
+            .iter()
+            .filter_map(Value::as_map)
+            .filter_map(|entry| {
+                let name = |key| {
+                    entry
+                        .iter()
+                        .find(|(k, _)| k.as_str() == Some(key))
+                        .and_then(|(_, v)| v.as_str())
+                };
+                name("hi_name")
+                    .or_else(|| name("ui_name"))
+                    .map(str::to_string)
+            })
+            .collect();
+    }
+
     Some(RedrawEvent::HlAttrDefine { id, attrs })
 }
 
@@ -388,6 +649,15 @@ fn as_u64(value: &Value) -> Option<u64> {
     }
 }
 
+/// A window, buffer or tabpage handle: an ext value holding the handle
+/// as a msgpack integer.
+fn as_handle(value: &Value) -> Option<u64> {
+    match value {
+        Value::Ext(_, data) => as_u64(&rmpv::decode::read_value(&mut data.as_slice()).ok()?),
+        _ => None,
+    }
+}
+
 fn as_i64(value: &Value) -> Option<i64> {
     match value {
         Value::Integer(i) => i.as_i64(),
@@ -395,6 +665,20 @@ fn as_i64(value: &Value) -> Option<i64> {
     }
 }
 
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::F64(f) => Some(*f),
+        Value::F32(f) => Some(*f as f64),
+        Value::Integer(i) => i.as_f64(),
+        _ => None,
+    }
+}
+
+/// Popupmenu selection index, where -1 means no selection.
+fn as_selection(value: &Value) -> Option<Option<usize>> {
+    as_i64(value).map(|i| usize::try_from(i).ok())
+}
+
 fn as_u32(value: &Value) -> Option<u32> {
     as_u64(value).and_then(|v| u32::try_from(v).ok())
 }
@@ -555,8 +839,23 @@ mod tests {
             Value::Array(vec![
                 Value::from(1u64),
                 Value::Map(rgb_attrs),
-                Value::Map(vec![]),   // cterm attrs (ignored)
-                Value::Array(vec![]), // info (ignored)
+                Value::Map(vec![
+                    (Value::from("foreground"), Value::from(196u64)),
+                    (Value::from("bold"), Value::from(true)),
+                ]),
+                Value::Array(vec![
+                    Value::Map(vec![
+                        (Value::from("kind"), Value::from("ui")),
+                        (Value::from("ui_name"), Value::from("CursorLine")),
+                    ]),
+                    Value::Map(vec![
+                        (Value::from("kind"), Value::from("syntax")),
+                        (
+                            Value::from("hi_name"),
+                            Value::from("DiagnosticUnderlineWarn"),
+                        ),
+                    ]),
+                ]),
             ]),
         ])];
         let events = parse_redraw(args);
@@ -564,8 +863,11 @@ mod tests {
         assert_eq!(events.len(), 1);
         if let RedrawEvent::HlAttrDefine { id, attrs } = &events[0] {
             assert_eq!(*id, 1);
+            assert_eq!(attrs.groups, ["CursorLine", "DiagnosticUnderlineWarn"]);
             assert_eq!(attrs.foreground, Some(Color::from_u24(0xFF0000)));
             assert_eq!(attrs.background, Some(Color::from_u24(0x00FF00)));
+            assert_eq!(attrs.cterm_foreground, Some(196));
+            assert_eq!(attrs.cterm_background, None);
             assert!(attrs.is_bold());
             assert!(attrs.is_italic());
         } else {
@@ -760,4 +1062,335 @@ mod tests {
         assert_eq!(events[0], RedrawEvent::Busy { busy: true });
         assert_eq!(events[1], RedrawEvent::Busy { busy: false });
     }
+
+    fn window_handle() -> Value {
+        Value::Ext(1, vec![0xcd, 0x03, 0xe8])
+    }
+
+    #[test]
+    fn test_parse_win_pos() {
+        let args = vec![Value::Array(vec![
+            Value::from("win_pos"),
+            Value::Array(vec![
+                Value::from(2u64),
+                window_handle(),
+                Value::from(1u64),
+                Value::from(0u64),
+                Value::from(40u64),
+                Value::from(20u64),
+            ]),
+        ])];
+        let events = parse_redraw(args);
+
+        assert_eq!(
+            events[0],
+            RedrawEvent::WinPos {
+                grid: 2,
+                win: 1000,
+                start_row: 1,
+                start_col: 0,
+                width: 40,
+                height: 20,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_win_float_pos() {
+        let args = vec![Value::Array(vec![
+            Value::from("win_float_pos"),
+            Value::Array(vec![
+                Value::from(4u64),
+                window_handle(),
+                Value::from("SE"),
+                Value::from(2u64),
+                Value::from(10.0),
+                Value::from(5.5),
+                Value::from(false),
+                Value::from(50u64),
+            ]),
+        ])];
+        let events = parse_redraw(args);
+
+        assert_eq!(
+            events[0],
+            RedrawEvent::WinFloatPos {
+                grid: 4,
+                anchor: FloatAnchor::SE,
+                anchor_grid: 2,
+                anchor_row: 10.0,
+                anchor_col: 5.5,
+                focusable: false,
+                zindex: Some(50),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_win_float_pos_without_zindex() {
+        let args = vec![Value::Array(vec![
+            Value::from("win_float_pos"),
+            Value::Array(vec![
+                Value::from(4u64),
+                window_handle(),
+                Value::from("NW"),
+                Value::from(1u64),
+                Value::from(0u64),
+                Value::from(0u64),
+                Value::from(true),
+            ]),
+        ])];
+        let events = parse_redraw(args);
+
+        assert!(matches!(
+            events[0],
+            RedrawEvent::WinFloatPos { zindex: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_win_hide_and_close() {
+        let args = vec![
+            Value::Array(vec![
+                Value::from("win_hide"),
+                Value::Array(vec![Value::from(3u64)]),
+            ]),
+            Value::Array(vec![
+                Value::from("win_close"),
+                Value::Array(vec![Value::from(3u64)]),
+            ]),
+        ];
+        let events = parse_redraw(args);
+
+        assert_eq!(events[0], RedrawEvent::WinHide { grid: 3 });
+        assert_eq!(events[1], RedrawEvent::WinClose { grid: 3 });
+    }
+
+    #[test]
+    fn test_parse_msg_set_pos() {
+        let args = vec![Value::Array(vec![
+            Value::from("msg_set_pos"),
+            Value::Array(vec![
+                Value::from(5u64),
+                Value::from(20u64),
+                Value::from(true),
+                Value::from("-"),
+            ]),
+        ])];
+        let events = parse_redraw(args);
+
+        assert_eq!(
+            events[0],
+            RedrawEvent::MsgSetPos {
+                grid: 5,
+                row: 20,
+                scrolled: true,
+                sep_char: "-".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_popupmenu_show() {
+        let item = |word: &str, kind: &str| {
+            Value::Array(vec![
+                Value::from(word),
+                Value::from(kind),
+                Value::from(""),
+                Value::from(""),
+            ])
+        };
+        let args = vec![Value::Array(vec![
+            Value::from("popupmenu_show"),
+            Value::Array(vec![
+                Value::Array(vec![item("foo", "f"), item("bar", "v")]),
+                Value::from(-1i64),
+                Value::from(3u64),
+                Value::from(7u64),
+                Value::from(1u64),
+            ]),
+        ])];
+        let events = parse_redraw(args);
+
+        let RedrawEvent::PopupmenuShow {
+            items,
+            selected,
+            row,
+            col,
+            grid,
+        } = &events[0]
+        else {
+            panic!("expected PopupmenuShow, got {:?}", events[0]);
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].word, "bar");
+        assert_eq!(items[1].kind, "v");
+        assert_eq!(*selected, None);
+        assert_eq!((*row, *col, *grid), (3, 7, 1));
+    }
+
+    #[test]
+    fn test_parse_popupmenu_select_and_hide() {
+        let args = vec![
+            Value::Array(vec![
+                Value::from("popupmenu_select"),
+                Value::Array(vec![Value::from(2u64)]),
+            ]),
+            Value::Array(vec![Value::from("popupmenu_hide"), Value::Array(vec![])]),
+        ];
+        let events = parse_redraw(args);
+
+        assert_eq!(
+            events[0],
+            RedrawEvent::PopupmenuSelect { selected: Some(2) }
+        );
+        assert_eq!(events[1], RedrawEvent::PopupmenuHide);
+    }
+
+    #[test]
+    fn test_parse_tabline_update() {
+        let tab = |id: u8, name: &str| {
+            Value::Map(vec![
+                (Value::from("tab"), Value::Ext(2, vec![id])),
+                (Value::from("name"), Value::from(name)),
+            ])
+        };
+        let args = vec![Value::Array(vec![
+            Value::from("tabline_update"),
+            Value::Array(vec![
+                Value::Ext(2, vec![2]),
+                Value::Array(vec![tab(1, "/tmp/a.rs"), tab(2, "b.rs")]),
+                Value::Ext(0, vec![1]),
+                Value::Array(vec![]),
+            ]),
+        ])];
+        let events = parse_redraw(args);
+
+        assert_eq!(
+            events[0],
+            RedrawEvent::TablineUpdate {
+                current: 1,
+                tabs: vec!["/tmp/a.rs".to_string(), "b.rs".to_string()],
+            }
+        );
+    }
+
+    fn chunks(parts: &[(u64, &str)]) -> Value {
+        Value::Array(
+            parts
+                .iter()
+                .map(|(hl, text)| Value::Array(vec![Value::from(*hl), Value::from(*text)]))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_parse_msg_show() {
+        let args = vec![Value::Array(vec![
+            Value::from("msg_show"),
+            Value::Array(vec![
+                Value::from("emsg"),
+                chunks(&[(3, "E492: "), (0, "Not an editor command")]),
+                Value::from(false),
+            ]),
+            Value::Array(vec![
+                Value::from("echo"),
+                chunks(&[(0, "more")]),
+                Value::from(false),
+                Value::from(false),
+                Value::from(true),
+            ]),
+        ])];
+        let events = parse_redraw(args);
+
+        assert_eq!(
+            events[0],
+            RedrawEvent::MsgShow {
+                kind: "emsg".to_string(),
+                content: vec![
+                    StyledChunk {
+                        hl_id: 3,
+                        text: "E492: ".to_string()
+                    },
+                    StyledChunk {
+                        hl_id: 0,
+                        text: "Not an editor command".to_string()
+                    },
+                ],
+                replace_last: false,
+                append: false,
+            }
+        );
+        assert!(matches!(
+            events[1],
+            RedrawEvent::MsgShow { append: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_msg_status_events() {
+        let args = vec![
+            Value::Array(vec![
+                Value::from("msg_ruler"),
+                Value::Array(vec![chunks(&[(0, "1,1  All")])]),
+            ]),
+            Value::Array(vec![
+                Value::from("msg_showmode"),
+                Value::Array(vec![chunks(&[])]),
+            ]),
+            Value::Array(vec![
+                Value::from("msg_history_show"),
+                Value::Array(vec![Value::Array(vec![Value::Array(vec![
+                    Value::from("echomsg"),
+                    chunks(&[(0, "hello")]),
+                ])])]),
+            ]),
+            Value::Array(vec![Value::from("msg_clear"), Value::Array(vec![])]),
+        ];
+        let events = parse_redraw(args);
+
+        assert!(
+            matches!(&events[0], RedrawEvent::MsgRuler { content } if content[0].text == "1,1  All")
+        );
+        assert_eq!(events[1], RedrawEvent::MsgShowmode { content: vec![] });
+        let RedrawEvent::MsgHistoryShow { entries } = &events[2] else {
+            panic!("expected MsgHistoryShow, got {:?}", events[2]);
+        };
+        assert_eq!(entries[0].0, "echomsg");
+        assert_eq!(entries[0].1[0].text, "hello");
+        assert_eq!(events[3], RedrawEvent::MsgClear);
+    }
+
+    #[test]
+    fn test_parse_cmdline_events() {
+        let args = vec![
+            Value::Array(vec![
+                Value::from("cmdline_show"),
+                Value::Array(vec![
+                    chunks(&[(0, "wq")]),
+                    Value::from(2u64),
+                    Value::from(":"),
+                    Value::from(""),
+                    Value::from(0u64),
+                    Value::from(1u64),
+                ]),
+            ]),
+            Value::Array(vec![
+                Value::from("cmdline_pos"),
+                Value::Array(vec![Value::from(1u64), Value::from(1u64)]),
+            ]),
+            Value::Array(vec![
+                Value::from("cmdline_hide"),
+                Value::Array(vec![Value::from(1u64), Value::from(false)]),
+            ]),
+        ];
+        let events = parse_redraw(args);
+
+        assert!(matches!(
+            &events[0],
+            RedrawEvent::CmdlineShow { pos: 2, firstc, level: 1, .. } if firstc == ":"
+        ));
+        assert_eq!(events[1], RedrawEvent::CmdlinePos { pos: 1, level: 1 });
+        assert_eq!(events[2], RedrawEvent::CmdlineHide { level: 1 });
+    }
 }
@@ -1,38 +1,247 @@
 use std::env;
+use std::fmt;
 use std::io;
-use std::process::Stdio;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
 
+use async_trait::async_trait;
 use nvim_rs::compat::tokio::Compat;
-use nvim_rs::create::tokio::new_child_cmd;
 use nvim_rs::error::{CallError, LoopError};
-use nvim_rs::{Neovim, UiAttachOptions};
-use tokio::process::{Child, ChildStdin, Command};
+use nvim_rs::{Neovim, UiAttachOptions, Value};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::process::{Child, Command};
 use tokio::task::JoinHandle;
-use winit::event_loop::EventLoopProxy;
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
-use super::NeovimHandler;
-use crate::event::UserEvent;
+use super::{Capabilities, NeovimHandler, UiExtensions};
+use crate::event::{EventSender, NeovimEvent};
 
-pub type NvimWriter = Compat<ChildStdin>;
+/// Write half of the RPC channel, whatever the transport.
+pub type NvimWriter = Compat<Box<dyn AsyncWrite + Send + Unpin>>;
+type NvimReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/// User commands defined in Neovim, paired with the name they are reported
+/// under in the `gui` notification.
+const GUI_COMMANDS: &[(&str, &str)] = &[
+    ("GuiStats", "stats"),
+    ("GuiSnapshot", "snapshot"),
+    ("GuiInvisible", "invisible"),
+    ("GuiRefreshColors", "refresh_colors"),
+    ("GuiFullscreen", "fullscreen"),
+    ("GuiMirror", "mirror"),
+    ("GuiPerfOverlay", "perf_overlay"),
+    ("GuiLog", "log"),
+    ("GuiMessages", "messages"),
+    ("GuiOpen", "open"),
+    ("GuiSaveAs", "save_as"),
+    ("GuiExport", "export"),
+    ("GuiSet", "set"),
+];
+
+/// Address of a Neovim server started with `--listen`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerAddress {
+    /// `host:port`.
+    Tcp(String),
+    /// Unix domain socket, or a named pipe on Windows.
+    Socket(PathBuf),
+}
+
+impl ServerAddress {
+    /// Reads `--server` the way `nvim --server` does: anything that looks
+    /// like `host:port` is TCP, everything else a socket or pipe path.
+    pub fn parse(address: &str) -> Self {
+        let is_path = address.contains('/') || address.contains('\\');
+        let is_tcp = !is_path
+            && address
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+        if is_tcp {
+            Self::Tcp(address.to_string())
+        } else {
+            Self::Socket(PathBuf::from(address))
+        }
+    }
+
+    /// Opens a connection, split into its read and write halves.
+    async fn connect(&self) -> io::Result<(NvimReader, Box<dyn AsyncWrite + Send + Unpin>)> {
+        match self {
+            Self::Tcp(address) => {
+                let (reader, writer) =
+                    tokio::io::split(tokio::net::TcpStream::connect(address).await?);
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+            #[cfg(unix)]
+            Self::Socket(path) => {
+                let (reader, writer) =
+                    tokio::io::split(tokio::net::UnixStream::connect(path).await?);
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+            #[cfg(windows)]
+            Self::Socket(path) => {
+                let pipe = tokio::net::windows::named_pipe::ClientOptions::new().open(path)?;
+                let (reader, writer) = tokio::io::split(pipe);
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(address) => write!(f, "{}", address),
+            Self::Socket(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// What the RPC channel runs over, and so how the session ends.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Whether Neovim lives on without this GUI, so a dropped channel can
+    /// be reconnected.
+    fn is_remote(&self) -> bool;
+
+    /// Ends the session when the GUI closes.
+    async fn close(&mut self, neovim: &Neovim<NvimWriter>, timeout: Duration);
+
+    /// What to tell the window once the channel has closed on its own.
+    async fn ended(&mut self, timeout: Duration) -> NeovimEvent;
+}
+
+/// Whether Neovim died rather than quit. `:cquit` exits with an error code
+/// on purpose, so only a signal counts, or on Windows an exception.
+fn crashed(status: &ExitStatus) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal().is_some()
+    }
+    #[cfg(not(unix))]
+    {
+        // NTSTATUS error codes, such as an access violation's 0xC0000005.
+        status.code().is_some_and(|code| code as u32 >= 0xC000_0000)
+    }
+}
+
+/// A child process started with `--embed`, talking over its stdio.
+struct ChildTransport {
+    child: Child,
+}
+
+#[async_trait]
+impl Transport for ChildTransport {
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    /// Ask Neovim to quit and wait for the child to exit.
+    ///
+    /// `qa!` usually tears the RPC channel down before a response arrives, so
+    /// its result is ignored. If the child is still alive `timeout` after
+    /// the quit was sent, however long sending it took, it is killed, so
+    /// closing the window never leaves a zombie behind.
+    async fn close(&mut self, neovim: &Neovim<NvimWriter>, timeout: Duration) {
+        if let Ok(Some(status)) = self.child.try_wait() {
+            log::info!("Neovim already exited: {}", status);
+            return;
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let quit = neovim.command("qa!");
+        if tokio::time::timeout_at(deadline, quit).await.is_err() {
+            log::warn!("Timed out sending quit to Neovim");
+        }
+
+        match tokio::time::timeout_at(deadline, self.child.wait()).await {
+            Ok(Ok(status)) => log::info!("Neovim exited: {}", status),
+            Ok(Err(e)) => log::warn!("Failed to wait for Neovim: {}", e),
+            Err(_) => {
+                log::warn!("Neovim did not exit within {:?}, killing it", timeout);
+                if let Err(e) = self.child.kill().await {
+                    log::error!("Failed to kill Neovim: {}", e);
+                }
+            }
+        }
+    }
+
+    /// `Quit` with Neovim's exit code if it quit, `Exited` if it crashed.
+    /// A child that closed its stdio without exiting is killed, and counts
+    /// as crashed.
+    async fn ended(&mut self, timeout: Duration) -> NeovimEvent {
+        let status = match tokio::time::timeout(timeout, self.child.wait()).await {
+            Ok(status) => status,
+            Err(_) => {
+                log::warn!("Neovim closed its channel but is still running, killing it");
+                if let Err(e) = self.child.kill().await {
+                    log::error!("Failed to kill Neovim: {}", e);
+                }
+                self.child.wait().await
+            }
+        };
+        match status {
+            Ok(status) if crashed(&status) => {
+                log::error!("Neovim crashed: {}", status);
+                NeovimEvent::Exited { status }
+            }
+            Ok(status) => {
+                log::info!("Neovim exited: {}", status);
+                NeovimEvent::Quit(status.code().unwrap_or(1))
+            }
+            Err(e) => {
+                log::warn!("Failed to wait for Neovim: {}", e);
+                NeovimEvent::Quit(1)
+            }
+        }
+    }
+}
+
+/// A server started elsewhere with `--listen`. Closing the GUI detaches
+/// and leaves the server running.
+struct RemoteTransport {
+    address: ServerAddress,
+}
+
+#[async_trait]
+impl Transport for RemoteTransport {
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    async fn close(&mut self, neovim: &Neovim<NvimWriter>, timeout: Duration) {
+        match tokio::time::timeout(timeout, neovim.ui_detach()).await {
+            Ok(Ok(())) => log::info!("Detached from {}", self.address),
+            Ok(Err(e)) => log::warn!("Failed to detach from {}: {:?}", self.address, e),
+            Err(_) => log::warn!("Timed out detaching from {}", self.address),
+        }
+    }
+
+    async fn ended(&mut self, _timeout: Duration) -> NeovimEvent {
+        NeovimEvent::Disconnected
+    }
+}
 
 pub struct NeovimProcess {
     pub neovim: Neovim<NvimWriter>,
     #[allow(dead_code)]
     pub io_handle: Option<JoinHandle<Result<(), Box<LoopError>>>>,
-    #[allow(dead_code)]
-    pub child: Child,
+    transport: Box<dyn Transport>,
 }
 
 impl NeovimProcess {
+    /// Starts `nvim --embed` in `cwd`, or the current directory.
     pub async fn spawn(
-        event_proxy: EventLoopProxy<UserEvent>,
+        event_proxy: EventSender,
         args: Vec<String>,
+        cwd: Option<PathBuf>,
     ) -> io::Result<Self> {
         let nvim_path = find_nvim_path()?;
         let handler = NeovimHandler::new(event_proxy);
 
         let current_dir = env::current_dir()?;
-        if current_dir.as_os_str() == "/" {
+        if cwd.is_none() && current_dir.as_os_str() == "/" {
             log::warn!("Current directory is /. This is probably not what you want. Changing to home directory.");
             let home = dirs::home_dir().ok_or_else(|| {
                 io::Error::new(io::ErrorKind::NotFound, "Could not determine home directory")
@@ -40,38 +249,269 @@ impl NeovimProcess {
             env::set_current_dir(home)?;
         }
 
-        let mut cmd = Command::new(&nvim_path);
-        cmd.args(&args)
+        let mut command = Command::new(&nvim_path);
+        if let Some(cwd) = &cwd {
+            command.current_dir(cwd);
+        }
+        let mut child = command
+            .args(&args)
             .arg("--embed")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        let (neovim, io_handle, child) = new_child_cmd(&mut cmd, handler)
-            .await
-            .map_err(io::Error::other)?;
+            .stderr(Stdio::piped())
+            // Should the bridge give up before `close` kills it.
+            .kill_on_drop(true)
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other("Can't open Neovim's stdio"));
+        };
 
         log::info!("Neovim process spawned: {:?}", nvim_path);
 
-        Ok(Self {
+        Ok(Self::start(
+            Box::new(stdout),
+            Box::new(stdin),
+            handler,
+            Box::new(ChildTransport { child }),
+        ))
+    }
+
+    /// Connects to a server started with `nvim --listen`.
+    pub async fn connect(event_proxy: EventSender, address: &ServerAddress) -> io::Result<Self> {
+        let (reader, writer) = address.connect().await?;
+        log::info!("Connected to Neovim server at {}", address);
+
+        Ok(Self::start(
+            reader,
+            writer,
+            NeovimHandler::new(event_proxy),
+            Box::new(RemoteTransport {
+                address: address.clone(),
+            }),
+        ))
+    }
+
+    fn start(
+        reader: NvimReader,
+        writer: Box<dyn AsyncWrite + Send + Unpin>,
+        handler: NeovimHandler,
+        transport: Box<dyn Transport>,
+    ) -> Self {
+        let (neovim, io) = Neovim::new(reader.compat(), writer.compat_write(), handler);
+        Self {
             neovim,
-            io_handle: Some(io_handle),
-            child,
-        })
+            io_handle: Some(tokio::spawn(io)),
+            transport,
+        }
+    }
+
+    /// Whether the session is a server this GUI only attached to.
+    pub fn is_remote(&self) -> bool {
+        self.transport.is_remote()
     }
 
-    pub async fn quit(&self) -> Result<(), Box<nvim_rs::error::CallError>> {
-        self.neovim.command("qa!").await
+    /// Ends the session: quits an embedded Neovim, detaches from a server.
+    pub async fn shutdown(mut self, timeout: Duration) {
+        self.transport.close(&self.neovim, timeout).await;
     }
 
-    pub async fn ui_attach(&self, cols: u64, rows: u64) -> Result<(), Box<CallError>> {
+    /// Reaps the session after its channel closed, waiting up to `timeout`
+    /// for an embedded Neovim to exit: `Quit`, `Exited` if it crashed, or
+    /// `Disconnected` for a server.
+    pub async fn ended(mut self, timeout: Duration) -> NeovimEvent {
+        self.transport.ended(timeout).await
+    }
+
+    pub async fn ui_attach(
+        &self,
+        cols: u64,
+        rows: u64,
+        extensions: UiExtensions,
+    ) -> Result<(), Box<CallError>> {
         let mut opts = UiAttachOptions::new();
-        opts.set_rgb(true).set_linegrid_external(true);
+        opts.set_rgb(true)
+            .set_linegrid_external(true)
+            // For the group names highlights are combined from
+            .set_hlstate_external(true)
+            .set_popupmenu_external(extensions.popupmenu)
+            .set_tabline_external(extensions.tabline)
+            .set_cmdline_external(extensions.cmdline)
+            .set_multigrid_external(extensions.multigrid)
+            // (sic) the setter is misspelled in nvim-rs
+            .set_messages_externa(extensions.messages);
 
         log::info!("Attaching UI with dimensions {}x{}", cols, rows);
         self.neovim.ui_attach(cols as i64, rows as i64, &opts).await
     }
 
+    pub async fn capabilities(&self) -> Result<Capabilities, Box<CallError>> {
+        let info = self.neovim.get_api_info().await?;
+        Ok(Capabilities::from_api_info(&info))
+    }
+
+    /// Defines the `:Gui*` user commands. Each one forwards to this UI as a
+    /// `gui` notification whose first argument names the command.
+    pub async fn register_gui_commands(&self, channel_id: u64) -> Result<(), Box<CallError>> {
+        for (command, name) in GUI_COMMANDS {
+            let definition = format!(
+                "command! -nargs=* {} call rpcnotify({}, 'gui', '{}', <f-args>)",
+                command, channel_id, name
+            );
+            self.neovim.command(&definition).await?;
+        }
+        Ok(())
+    }
+
+    /// Defines the `gui` Lua table's functions. `gui.set_window_zoom(factor)`
+    /// forwards the current window to this UI as a `gui` `window_zoom`
+    /// notification.
+    pub async fn register_gui_functions(&self, channel_id: u64) -> Result<(), Box<CallError>> {
+        const GUI_FUNCTIONS: &str = "local channel = ...
+            _G.gui = _G.gui or {}
+            function gui.set_window_zoom(factor)
+                vim.rpcnotify(channel, 'gui', 'window_zoom',
+                    vim.api.nvim_get_current_win(), factor)
+            end";
+        self.neovim
+            .exec_lua(GUI_FUNCTIONS, vec![channel_id.into()])
+            .await?;
+        Ok(())
+    }
+
+    /// Reports the file and cursor to this UI as a `gui` `recent_file`
+    /// notification whenever a normal file buffer is left, including on exit.
+    pub async fn register_recent_files(&self, channel_id: u64) -> Result<(), Box<CallError>> {
+        let autocmd = format!(
+            "autocmd BufLeave,VimLeavePre * if &buftype ==# '' && expand('%:p') !=# '' \
+             | call rpcnotify({}, 'gui', 'recent_file', expand('%:p'), line('.'), col('.')) \
+             | endif",
+            channel_id
+        );
+        for command in [
+            "augroup gui_nvim_recent",
+            "autocmd!",
+            &autocmd,
+            "augroup END",
+        ] {
+            self.neovim.command(command).await?;
+        }
+        Ok(())
+    }
+
+    /// Reports the text selected in Visual mode, as a list of lines, to this
+    /// UI as a `gui` `selection` notification whenever the selection may
+    /// have changed, followed by where it is on screen (see
+    /// `SelectionRegion::from_value`). Leaving Visual mode reports an empty
+    /// list. Needs `getregion()` (Neovim 0.10); older versions report
+    /// nothing.
+    pub async fn register_visual_selection(&self, channel_id: u64) -> Result<(), Box<CallError>> {
+        let autocmd = format!(
+            "autocmd CursorMoved,ModeChanged * \
+             if mode() =~# \"^[vV\\x16]\" && exists('*getregion') \
+             | call rpcnotify({}, 'gui', 'selection', \
+             getregion(getpos('v'), getpos('.'), #{{type: mode()}}), \
+             #{{mode: mode(), \
+             v: screenpos(0, line('v'), col('v')), \
+             cursor: screenpos(0, line('.'), col('.')), \
+             forward: line('v') < line('.') || line('v') == line('.') && col('v') <= col('.'), \
+             win: win_screenpos(0) + [winwidth(0), winheight(0)]}}) \
+             | endif",
+            channel_id
+        );
+        let cleared = format!(
+            "autocmd ModeChanged * if mode() !~# \"^[vV\\x16]\" \
+             | call rpcnotify({}, 'gui', 'selection', []) | endif",
+            channel_id
+        );
+        for command in [
+            "augroup gui_nvim_selection",
+            "autocmd!",
+            &autocmd,
+            &cleared,
+            "augroup END",
+        ] {
+            self.neovim.command(command).await?;
+        }
+        Ok(())
+    }
+
+    /// Asks this UI about swap files found while opening a file, through a
+    /// `gui_swap_exists` request that answers with `v:swapchoice`, and
+    /// reports whether the current buffer is read-only, whether it is
+    /// modified and the file it edits as a `gui` `buffer_state`
+    /// notification.
+    pub async fn register_buffer_state(&self, channel_id: u64) -> Result<(), Box<CallError>> {
+        let swap = format!(
+            "autocmd SwapExists * let v:swapchoice = \
+             rpcrequest({}, 'gui_swap_exists', v:swapname, expand('<afile>:p'))",
+            channel_id
+        );
+        let notify = format!(
+            "call rpcnotify({}, 'gui', 'buffer_state', \
+             &readonly || !&modifiable ? v:true : v:false, \
+             &modified ? v:true : v:false, \
+             &buftype ==# '' ? expand('%:p') : '')",
+            channel_id
+        );
+        let entered = format!(
+            "autocmd BufEnter,BufReadPost,BufWritePost,BufModifiedSet * {}",
+            notify
+        );
+        let changed = format!("autocmd OptionSet readonly,modifiable {}", notify);
+        for command in [
+            "augroup gui_nvim_buffer",
+            "autocmd!",
+            &swap,
+            &entered,
+            &changed,
+            "augroup END",
+        ] {
+            self.neovim.command(command).await?;
+        }
+        Ok(())
+    }
+
+    /// Shows `lines` in the message area and message history.
+    pub async fn echo(&self, lines: &[String]) -> Result<(), Box<CallError>> {
+        let chunks = vec![Value::Array(vec![Value::from(lines.join("\n"))])];
+        self.neovim.echo(chunks, true, vec![]).await
+    }
+
+    /// Opens `lines` in a new window, in a scratch buffer called `name`
+    /// that goes away with it.
+    pub async fn show_lines(&self, name: &str, lines: Vec<String>) -> Result<(), Box<CallError>> {
+        const SHOW_LINES: &str = "local name, lines = ...
+            vim.cmd('new')
+            local buf = vim.api.nvim_get_current_buf()
+            vim.bo[buf].buftype = 'nofile'
+            vim.bo[buf].bufhidden = 'wipe'
+            vim.bo[buf].swapfile = false
+            vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+            pcall(vim.api.nvim_buf_set_name, buf, name)
+            vim.cmd('normal! G')";
+        let lines = lines.into_iter().map(Value::from).collect();
+        self.neovim
+            .exec_lua(SHOW_LINES, vec![name.into(), Value::Array(lines)])
+            .await?;
+        Ok(())
+    }
+
+    /// Whether each tabpage, in order, shows a modified buffer in any of its
+    /// windows. Takes the handle rather than `&self` so it can run in a
+    /// spawned task.
+    pub async fn tabs_modified(neovim: &Neovim<NvimWriter>) -> Result<Vec<bool>, Box<CallError>> {
+        let result = neovim
+            .eval(
+                "map(gettabinfo(), {_, t -> !empty(filter(copy(t.windows), \
+                 {_, w -> getbufvar(winbufnr(w), '&modified')}))})",
+            )
+            .await?;
+        Ok(result
+            .as_array()
+            .map(|flags| flags.iter().map(|f| f.as_i64() == Some(1)).collect())
+            .unwrap_or_default())
+    }
+
     #[allow(dead_code)]
     pub async fn ui_try_resize(&self, cols: u64, rows: u64) -> Result<(), Box<CallError>> {
         self.neovim.ui_try_resize(cols as i64, rows as i64).await
@@ -165,6 +605,42 @@ mod tests {
         let _ = result;
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_crashed() {
+        use std::os::unix::process::ExitStatusExt;
+        // Killed by SIGSEGV
+        assert!(crashed(&ExitStatus::from_raw(11)));
+        assert!(!crashed(&ExitStatus::from_raw(0)));
+        // `:cquit`
+        assert!(!crashed(&ExitStatus::from_raw(1 << 8)));
+    }
+
+    #[test]
+    fn test_parse_server_address() {
+        assert_eq!(
+            ServerAddress::parse("127.0.0.1:6666"),
+            ServerAddress::Tcp("127.0.0.1:6666".into())
+        );
+        assert_eq!(
+            ServerAddress::parse("localhost:6666"),
+            ServerAddress::Tcp("localhost:6666".into())
+        );
+        assert_eq!(
+            ServerAddress::parse("/tmp/nvim.sock"),
+            ServerAddress::Socket("/tmp/nvim.sock".into())
+        );
+        assert_eq!(
+            ServerAddress::parse(r"\\.\pipe\nvim"),
+            ServerAddress::Socket(r"\\.\pipe\nvim".into())
+        );
+        // No port, so a socket in the current directory
+        assert_eq!(
+            ServerAddress::parse("nvim.sock"),
+            ServerAddress::Socket("nvim.sock".into())
+        );
+    }
+
     #[test]
     fn test_default_dimensions() {
         use crate::constants::{DEFAULT_COLS, DEFAULT_ROWS};
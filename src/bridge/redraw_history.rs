@@ -0,0 +1,88 @@
+//! The last redraw events Neovim sent, for crash reports: what the GUI was
+//! drawing when it went down is often the quickest way to reproduce it.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::events::RedrawEvent;
+
+/// Events kept.
+const HISTORY: usize = 100;
+
+/// Longest an event is written out in a report; a `grid_line` can hold a
+/// whole screen.
+const MAX_EVENT_LEN: usize = 300;
+
+static RECENT: Mutex<VecDeque<RedrawEvent>> = Mutex::new(VecDeque::new());
+
+fn push(history: &mut VecDeque<RedrawEvent>, events: &[RedrawEvent]) {
+    // No use cloning what would be dropped right away.
+    let events = &events[events.len().saturating_sub(HISTORY)..];
+    let overflow = (history.len() + events.len()).saturating_sub(HISTORY);
+    history.drain(..overflow);
+    history.extend(events.iter().cloned());
+}
+
+/// Remembers a batch of redraws as it arrives.
+pub fn record(events: &[RedrawEvent]) {
+    push(
+        &mut RECENT.lock().unwrap_or_else(|e| e.into_inner()),
+        events,
+    );
+}
+
+fn describe(event: &RedrawEvent) -> String {
+    let mut line = format!("{:?}", event);
+    if line.len() > MAX_EVENT_LEN {
+        let end = (0..=MAX_EVENT_LEN)
+            .rev()
+            .find(|&i| line.is_char_boundary(i))
+            .unwrap_or(0);
+        line.truncate(end);
+        line.push_str("...");
+    }
+    line
+}
+
+/// The events remembered, oldest first, one line each. Empty if the lock
+/// is held: a panic hook mustn't wait on the thread that panicked.
+pub fn recent() -> Vec<String> {
+    match RECENT.try_lock() {
+        Ok(history) => history.iter().map(describe).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn title(n: usize) -> RedrawEvent {
+        RedrawEvent::SetTitle {
+            title: n.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_history_keeps_the_last_events() {
+        let mut history = VecDeque::new();
+        push(&mut history, &[title(0), title(1)]);
+        let batch: Vec<_> = (2..HISTORY + 10).map(title).collect();
+        push(&mut history, &batch);
+        assert_eq!(history.len(), HISTORY);
+        assert_eq!(history.front(), Some(&title(10)));
+        push(&mut history, &[RedrawEvent::Flush]);
+        assert_eq!(history.front(), Some(&title(11)));
+        assert_eq!(history.back(), Some(&RedrawEvent::Flush));
+    }
+
+    #[test]
+    fn test_long_events_are_cut_short() {
+        assert_eq!(describe(&title(7)), r#"SetTitle { title: "7" }"#);
+        let long = RedrawEvent::SetTitle {
+            title: "é".repeat(MAX_EVENT_LEN),
+        };
+        let line = describe(&long);
+        assert!(line.len() <= MAX_EVENT_LEN + 3 && line.ends_with("..."));
+    }
+}
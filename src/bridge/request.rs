@@ -0,0 +1,211 @@
+//! Requests to Neovim that are answered, unlike the fire-and-forget
+//! commands. Each runs as its own task on the bridge runtime, so a slow
+//! answer never holds up input, and is answered exactly once: with Neovim's
+//! result, an error, or a timeout.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use nvim_rs::error::CallError;
+use nvim_rs::Value;
+use tokio::task::{self, JoinError, JoinSet};
+
+/// Identifies a request in its `NeovimEvent::Response`.
+pub type RequestId = u64;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RequestError {
+    #[error("Not connected to Neovim")]
+    NotConnected,
+
+    #[error("No answer from Neovim within {0:?}")]
+    Timeout(Duration),
+
+    #[error("Neovim returned an error: {0}")]
+    Neovim(String),
+
+    #[error("Request failed: {0}")]
+    Call(String),
+
+    #[error("Request task panicked")]
+    Panicked,
+}
+
+/// What a request is answered with.
+pub type Response = Result<Value, RequestError>;
+
+type Reply = Arc<dyn Fn(RequestId, Response) + Send + Sync>;
+
+/// Requests in flight. Their tasks must be reaped with `join_next` and
+/// `finished`, which answers for a task that panicked before it could.
+pub struct Requests {
+    tasks: JoinSet<()>,
+    ids: HashMap<task::Id, RequestId>,
+    reply: Reply,
+}
+
+impl Requests {
+    pub fn new(reply: impl Fn(RequestId, Response) + Send + Sync + 'static) -> Self {
+        Self {
+            tasks: JoinSet::new(),
+            ids: HashMap::new(),
+            reply: Arc::new(reply),
+        }
+    }
+
+    /// Runs `call` and answers `id` with its result, or with a timeout if
+    /// it takes longer than `timeout`. Must be called within the runtime.
+    pub fn spawn<F>(&mut self, id: RequestId, timeout: Duration, call: F)
+    where
+        F: Future<Output = Result<Result<Value, Value>, Box<CallError>>> + Send + 'static,
+    {
+        let reply = self.reply.clone();
+        let handle = self.tasks.spawn(async move {
+            let response = match tokio::time::timeout(timeout, call).await {
+                Ok(Ok(Ok(value))) => Ok(value),
+                Ok(Ok(Err(error))) => Err(RequestError::Neovim(error_message(&error))),
+                Ok(Err(e)) => Err(RequestError::Call(e.to_string())),
+                Err(_) => Err(RequestError::Timeout(timeout)),
+            };
+            reply(id, response);
+        });
+        self.ids.insert(handle.id(), id);
+    }
+
+    /// Answers `id` right away, for requests that can't be sent.
+    pub fn reject(&self, id: RequestId, error: RequestError) {
+        (self.reply)(id, Err(error));
+    }
+
+    /// Waits for the next request task to end. Never resolves while none
+    /// are running, so it can sit in a `select!` next to other work.
+    pub async fn join_next(&mut self) -> Result<task::Id, JoinError> {
+        match self.tasks.join_next_with_id().await {
+            Some(result) => result.map(|(id, ())| id),
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Forgets a request task `join_next` returned. A task that panicked
+    /// never answered, so it is answered with `Panicked` here.
+    pub fn finished(&mut self, result: Result<task::Id, JoinError>) {
+        match result {
+            Ok(task) => {
+                self.ids.remove(&task);
+            }
+            Err(e) => {
+                let Some(id) = self.ids.remove(&e.id()) else {
+                    return;
+                };
+                if e.is_panic() {
+                    log::error!("Request {} panicked", id);
+                    (self.reply)(id, Err(RequestError::Panicked));
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Drops every request still waiting, without answering: only done on
+    /// shutdown, when no one is left to receive the answers.
+    pub fn cancel_all(&mut self) {
+        self.tasks.abort_all();
+        self.ids.clear();
+    }
+}
+
+/// Neovim reports errors as `[type, message]`.
+fn error_message(error: &Value) -> String {
+    error
+        .as_array()
+        .and_then(|parts| parts.get(1))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn requests() -> (Requests, mpsc::Receiver<(RequestId, Response)>) {
+        let (tx, rx) = mpsc::channel();
+        let tx = std::sync::Mutex::new(tx);
+        let requests = Requests::new(move |id, response| {
+            let _ = tx.lock().unwrap().send((id, response));
+        });
+        (requests, rx)
+    }
+
+    #[tokio::test]
+    async fn test_request_answers() {
+        let (mut requests, rx) = requests();
+        requests.spawn(1, Duration::from_secs(1), async { Ok(Ok(Value::from(42))) });
+        requests.spawn(2, Duration::from_secs(1), async {
+            Ok(Err(Value::Array(vec![
+                Value::from(0),
+                Value::from("E492: Not an editor command"),
+            ])))
+        });
+        assert_eq!(requests.len(), 2);
+
+        for _ in 0..2 {
+            let done = requests.join_next().await;
+            requests.finished(done);
+        }
+        assert!(requests.is_empty());
+
+        let mut answers: Vec<_> = rx.try_iter().collect();
+        answers.sort_by_key(|(id, _)| *id);
+        assert_eq!(answers[0], (1, Ok(Value::from(42))));
+        assert_eq!(
+            answers[1],
+            (
+                2,
+                Err(RequestError::Neovim(
+                    "E492: Not an editor command".to_string()
+                ))
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let (mut requests, rx) = requests();
+        let timeout = Duration::from_millis(10);
+        requests.spawn(7, timeout, std::future::pending());
+        let done = requests.join_next().await;
+        requests.finished(done);
+        assert_eq!(rx.try_recv(), Ok((7, Err(RequestError::Timeout(timeout)))));
+    }
+
+    #[tokio::test]
+    async fn test_request_panic_is_answered() {
+        let (mut requests, rx) = requests();
+        requests.spawn(3, Duration::from_secs(1), async { panic!("handler bug") });
+        let done = requests.join_next().await;
+        requests.finished(done);
+        assert_eq!(rx.try_recv(), Ok((3, Err(RequestError::Panicked))));
+        assert!(requests.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_all() {
+        let (mut requests, rx) = requests();
+        requests.spawn(4, Duration::from_secs(60), std::future::pending());
+        requests.cancel_all();
+        assert!(requests.is_empty());
+        tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err());
+    }
+}
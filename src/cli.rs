@@ -1,5 +1,11 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
 use clap::{Parser, Subcommand};
 
+use crate::config::FontSettings;
+use crate::constants::{DEFAULT_COLS, DEFAULT_ROWS};
+
 #[derive(Parser)]
 #[command(name = "gui.nvim")]
 #[command(version)]
@@ -19,15 +25,173 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Command>,
 
+    /// Attach to a Neovim started with `--listen` (host:port, socket or pipe)
+    #[arg(long, value_name = "ADDR")]
+    pub server: Option<String>,
+
+    /// Wait in the background with Neovim running, for later launches to open in
+    #[arg(long, conflicts_with = "server")]
+    pub daemon: bool,
+
+    /// Open the files in the gui.nvim already running, if there is one
+    #[arg(long, conflicts_with_all = ["server", "daemon"])]
+    pub remote: bool,
+
+    /// Grid size of the window, such as 120x40
+    #[arg(long, value_name = "COLSxROWS")]
+    pub geometry: Option<Geometry>,
+
+    /// Font family and size, such as "JetBrains Mono:14" ('guifont' syntax works too)
+    #[arg(long, value_name = "FONT", value_parser = parse_font)]
+    pub font: Option<FontSettings>,
+
+    /// Directory to start Neovim in
+    #[arg(long, value_name = "DIR", value_parser = parse_dir)]
+    pub cwd: Option<PathBuf>,
+
+    /// Open the window maximized
+    #[arg(long)]
+    pub maximized: bool,
+
+    /// Open the window fullscreen
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// Also log to FILE, with timestamps, rotated as it grows
+    #[arg(long, value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// Level logged to --log-file and kept for :GuiLog (error, warn, info, debug, trace)
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<log::LevelFilter>,
+
     /// Arguments passed directly to Neovim
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     pub nvim_args: Vec<String>,
 }
 
+impl Cli {
+    /// How the window opens, from the flags.
+    pub fn window_options(&self) -> WindowOptions {
+        WindowOptions {
+            geometry: self.geometry,
+            font: self.font.clone(),
+            cwd: self.cwd.clone(),
+            maximized: self.maximized,
+            fullscreen: self.fullscreen,
+        }
+    }
+}
+
+/// Flags for the window and the Neovim it spawns, over the config and the
+/// geometry saved by the last session.
+#[derive(Debug, Clone, Default)]
+pub struct WindowOptions {
+    pub geometry: Option<Geometry>,
+    /// Only the family and size are set.
+    pub font: Option<FontSettings>,
+    pub cwd: Option<PathBuf>,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+impl WindowOptions {
+    /// Whether any flag was given. A daemon's window can't honor them.
+    pub fn is_set(&self) -> bool {
+        self.geometry.is_some()
+            || self.font.is_some()
+            || self.cwd.is_some()
+            || self.maximized
+            || self.fullscreen
+    }
+}
+
+/// A grid size in cells, `COLSxROWS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    pub cols: u64,
+    pub rows: u64,
+}
+
+impl FromStr for Geometry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (cols, rows) = s
+            .split_once(['x', 'X'])
+            .ok_or_else(|| format!("expected COLSxROWS, such as 120x40, not {:?}", s))?;
+        let parse = |n: &str| match n.trim().parse::<u64>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("{:?} isn't a number of cells", n)),
+        };
+        Ok(Self {
+            cols: parse(cols)?,
+            rows: parse(rows)?,
+        })
+    }
+}
+
+fn parse_dir(s: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(s);
+    if path.is_dir() {
+        Ok(path)
+    } else {
+        Err(format!("{} isn't a directory", path.display()))
+    }
+}
+
+/// Reads `Family:SIZE`, or 'guifont' syntax such as `Family:h14`.
+fn parse_font(s: &str) -> Result<FontSettings, String> {
+    let guifont = match s.rsplit_once(':') {
+        Some((family, size)) if size.parse::<f32>().is_ok() => format!("{}:h{}", family, size),
+        _ => s.to_string(),
+    };
+    FontSettings::from_guifont(&guifont)
+        .filter(|font| font.family.is_some() || font.size.is_some())
+        .ok_or_else(|| {
+            format!(
+                "expected FAMILY:SIZE, such as \"JetBrains Mono:14\", not {:?}",
+                s
+            )
+        })
+}
+
 #[derive(Subcommand)]
 pub enum Command {
     /// Capture shell environment variables for GUI launches
     Env,
+    /// Render synthetic frames offscreen and print frame times
+    Bench {
+        /// Frames measured per scenario and mode
+        #[arg(long, default_value_t = 300)]
+        frames: usize,
+        /// Grid columns
+        #[arg(long, default_value_t = 120)]
+        cols: usize,
+        /// Grid rows
+        #[arg(long, default_value_t = 40)]
+        rows: usize,
+    },
+    /// Report each GPU adapter's limits and what the renderer makes of them
+    GpuInfo,
+    /// Run Neovim offscreen and write its screen to a PNG
+    Render {
+        /// PNG file to write
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+        /// Ex command to run before capturing, such as "e file.rs" (repeatable)
+        #[arg(long = "cmd", value_name = "COMMAND")]
+        commands: Vec<String>,
+        /// Grid columns
+        #[arg(long, default_value_t = DEFAULT_COLS)]
+        cols: u64,
+        /// Grid rows
+        #[arg(long, default_value_t = DEFAULT_ROWS)]
+        rows: u64,
+        /// Seconds Neovim gets to start and run the commands
+        #[arg(long, default_value_t = 10)]
+        timeout: u64,
+    },
 }
 
 #[cfg(test)]
@@ -47,6 +211,136 @@ mod tests {
         assert!(matches!(cli.command, Some(Command::Env)));
     }
 
+    #[test]
+    fn test_parse_bench() {
+        let cli = Cli::parse_from(["gui.nvim", "bench", "--frames", "50"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Bench {
+                frames: 50,
+                cols: 120,
+                rows: 40
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_gpu_info() {
+        let cli = Cli::parse_from(["gui.nvim", "gpu-info"]);
+        assert!(matches!(cli.command, Some(Command::GpuInfo)));
+    }
+
+    #[test]
+    fn test_parse_render() {
+        let cli = Cli::parse_from([
+            "gui.nvim",
+            "render",
+            "--out",
+            "frame.png",
+            "--cmd",
+            "e file.rs",
+            "--cmd",
+            "set nu",
+        ]);
+        let Some(Command::Render {
+            out,
+            commands,
+            cols,
+            rows,
+            timeout,
+        }) = cli.command
+        else {
+            panic!("expected render");
+        };
+        assert_eq!(out, PathBuf::from("frame.png"));
+        assert_eq!(commands, ["e file.rs", "set nu"]);
+        assert_eq!((cols, rows, timeout), (DEFAULT_COLS, DEFAULT_ROWS, 10));
+    }
+
+    #[test]
+    fn test_parse_server() {
+        let cli = Cli::parse_from(["gui.nvim", "--server", "127.0.0.1:6666"]);
+        assert_eq!(cli.server.as_deref(), Some("127.0.0.1:6666"));
+        assert!(cli.nvim_args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_log_options() {
+        let cli = Cli::parse_from([
+            "gui.nvim",
+            "--log-file",
+            "/tmp/gui.log",
+            "--log-level",
+            "debug",
+            "notes.md",
+        ]);
+        assert_eq!(cli.log_file, Some(PathBuf::from("/tmp/gui.log")));
+        assert_eq!(cli.log_level, Some(log::LevelFilter::Debug));
+        assert_eq!(cli.nvim_args, ["notes.md"]);
+        assert!(Cli::try_parse_from(["gui.nvim", "--log-level", "loud"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_daemon() {
+        let cli = Cli::parse_from(["gui.nvim", "--daemon"]);
+        assert!(cli.daemon);
+        assert!(Cli::try_parse_from(["gui.nvim", "--daemon", "--server", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_window_flags() {
+        let dir = std::env::temp_dir();
+        let cli = Cli::parse_from([
+            "gui.nvim".as_ref(),
+            "--geometry".as_ref(),
+            "120x40".as_ref(),
+            "--font".as_ref(),
+            "JetBrains Mono:14".as_ref(),
+            "--cwd".as_ref(),
+            dir.as_os_str(),
+            "--maximized".as_ref(),
+            "notes.md".as_ref(),
+            "--clean".as_ref(),
+        ]);
+        let options = cli.window_options();
+        assert!(options.is_set());
+        assert_eq!(
+            options.geometry,
+            Some(Geometry {
+                cols: 120,
+                rows: 40
+            })
+        );
+        let font = options.font.unwrap();
+        assert_eq!(font.family.as_deref(), Some("JetBrains Mono"));
+        assert_eq!(font.size, Some(14.0));
+        assert_eq!(options.cwd, Some(dir));
+        assert!(options.maximized && !options.fullscreen);
+        assert_eq!(cli.nvim_args, ["notes.md", "--clean"]);
+
+        assert!(!Cli::parse_from(["gui.nvim"]).window_options().is_set());
+    }
+
+    #[test]
+    fn test_parse_geometry() {
+        assert_eq!("80X24".parse(), Ok(Geometry { cols: 80, rows: 24 }));
+        assert!("80".parse::<Geometry>().is_err());
+        assert!("0x24".parse::<Geometry>().is_err());
+        assert!(Cli::try_parse_from(["gui.nvim", "--geometry", "wide"]).is_err());
+        assert!(Cli::try_parse_from(["gui.nvim", "--cwd", "/no/such/dir"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_font() {
+        let font = parse_font("Fira Code:h12.5").unwrap();
+        assert_eq!(font.family.as_deref(), Some("Fira Code"));
+        assert_eq!(font.size, Some(12.5));
+        let font = parse_font("Iosevka").unwrap();
+        assert_eq!((font.family.as_deref(), font.size), (Some("Iosevka"), None));
+        assert_eq!(parse_font(":16").unwrap().family, None);
+        assert!(parse_font("").is_err());
+    }
+
     #[test]
     fn test_parse_nvim_args() {
         let cli = Cli::parse_from(["gui.nvim", "file.txt", "--clean"]);
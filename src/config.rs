@@ -1,13 +1,35 @@
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
+use crate::constants::{PADDING, PADDING_TOP};
+
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct Config {
     #[serde(default)]
     pub font: FontSettings,
     #[serde(default)]
     pub performance: PerformanceSettings,
+    #[serde(default)]
+    pub ui: UiSettings,
+    #[serde(default)]
+    pub window: WindowSettings,
+    #[serde(default)]
+    pub keyboard: KeyboardSettings,
+    #[serde(default)]
+    pub cursor: CursorSettings,
+    #[serde(default)]
+    pub macos: MacosSettings,
+    /// GUI shortcuts: a key chord such as `"cmd+t"` mapped to the keys it
+    /// sends to Neovim, like `":tabnew<CR>"`.
+    #[serde(default)]
+    pub keybinds: BTreeMap<String, String>,
+    /// Underline colors for highlights without `guisp`: a highlight group
+    /// name suffix such as `"Error"` mapped to `"#rrggbb"`, over built-in
+    /// diagnostic colors.
+    #[serde(default)]
+    pub special_colors: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
@@ -42,12 +64,511 @@ impl Default for VsyncMode {
 pub struct PerformanceSettings {
     #[serde(default)]
     pub vsync: VsyncMode,
+    /// Frame rate cap, e.g. 30 to save battery. With `vsync = "disabled"`
+    /// it defaults to the monitor's refresh rate; otherwise vsync alone
+    /// paces frames.
+    pub max_fps: Option<u32>,
+}
+
+/// Which parts of the Neovim UI the GUI draws itself. The `ext_*` options
+/// are only asked for when the attached Neovim lists them.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct UiSettings {
+    /// Draw messages, the cmdline, ruler and showmode outside the grid
+    /// (ext_messages). Neovim then also hands over the cmdline.
+    #[serde(default)]
+    pub messages: bool,
+    /// Draw the cmdline over the bottom row (ext_cmdline), even without
+    /// `messages`.
+    #[serde(default)]
+    pub cmdline: bool,
+    /// Draw the completion menu (ext_popupmenu).
+    #[serde(default = "default_true")]
+    pub popupmenu: bool,
+    /// Have Neovim send every window as a grid of its own (ext_multigrid),
+    /// composed by the GUI. Experimental.
+    #[serde(default)]
+    pub multigrid: bool,
+    /// When to show the GUI tab bar (ext_tabline).
+    #[serde(default)]
+    pub tabline: TablineVisibility,
+    /// Remember recently edited files and list them on a startup dashboard
+    /// when no file is given.
+    #[serde(default)]
+    pub recent_files: bool,
+    /// Where files dropped on the window or chosen with File > Open go.
+    #[serde(default)]
+    pub open_files_in: OpenFilesIn,
+    /// Shift the grid by the part of a trackpad scroll that doesn't make a
+    /// whole line yet, so scrolling follows the fingers pixel by pixel.
+    #[serde(default)]
+    pub smooth_scroll: bool,
+    /// Outline cells holding zero-width, bidi control or unusual space
+    /// characters, whatever 'list' says. `:GuiInvisible` toggles it.
+    #[serde(default)]
+    pub show_invisible: bool,
+    /// Offer text selected in Visual mode as the X11/Wayland primary
+    /// selection, and paste the primary selection on a middle click.
+    /// Ignored on macOS and Windows, which have none.
+    #[serde(default)]
+    pub primary_selection: bool,
+    /// Draw the window mirrored, right to left, for working mostly in RTL
+    /// scripts: the first column sits at the right edge and the padding
+    /// and tab bar flip with it. Glyphs themselves aren't flipped.
+    /// Experimental; `:GuiMirror` toggles it.
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            messages: false,
+            cmdline: false,
+            popupmenu: true,
+            multigrid: false,
+            tabline: TablineVisibility::default(),
+            recent_files: false,
+            open_files_in: OpenFilesIn::default(),
+            smooth_scroll: false,
+            show_invisible: false,
+            primary_selection: false,
+            mirror: false,
+        }
+    }
+}
+
+impl UiSettings {
+    /// Whether to use the primary selection: configured, on a platform
+    /// that has one.
+    pub fn primary_selection(&self) -> bool {
+        self.primary_selection && cfg!(not(any(target_os = "macos", target_os = "windows")))
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenFilesIn {
+    /// The current window; several files become the argument list.
+    #[default]
+    Window,
+    /// A tab page per file.
+    Tab,
+    /// A horizontal split per file.
+    Split,
+    /// A vertical split per file.
+    Vsplit,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TablineVisibility {
+    /// Only while there are two or more tabpages, like `showtabline=1`.
+    #[default]
+    Auto,
+    Always,
+    /// Leave the tabline to Neovim, drawn inside the grid.
+    Never,
+}
+
+/// Appearance of the window itself.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct WindowSettings {
+    /// Opacity of the default background, from 0.0 (clear) to 1.0. Text and
+    /// highlighted backgrounds stay opaque.
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    /// Blur whatever shows through a translucent background. macOS and
+    /// Windows 11 only.
+    #[serde(default)]
+    pub blur: bool,
+    /// Image drawn behind the grid. A leading `~/` is the home directory.
+    /// Only PNG files are supported.
+    pub background_image: Option<String>,
+    /// How the background image fills the window.
+    #[serde(default)]
+    pub background_image_fit: BackgroundFit,
+    /// Opacity of the background image over the default background color.
+    #[serde(default = "default_opacity")]
+    pub background_image_opacity: f32,
+    /// How much of the default background color is blended back over the
+    /// image, from 0.0 (none) to 1.0 (image hidden).
+    #[serde(default)]
+    pub background_image_dim: f32,
+    /// How F11 (Cmd+Ctrl+F on macOS), `:GuiFullscreen` and `--fullscreen`
+    /// make the window fullscreen.
+    #[serde(default)]
+    pub fullscreen: FullscreenMode,
+    /// Space around the grid. Neovim's background color fills it.
+    #[serde(default)]
+    pub padding: Padding,
+    /// Color of the splash shown until Neovim has drawn its first screen,
+    /// as `#rrggbb`. Neovim's background color as it arrives if unset.
+    pub splash_background: Option<String>,
+    /// PNG shown as the window and taskbar icon on Linux and Windows. A
+    /// leading `~/` is the home directory. The gui.nvim icon if unset;
+    /// macOS always uses the app bundle's.
+    pub icon: Option<String>,
+}
+
+/// Space around the grid, in logical pixels. The top holds the tab bar,
+/// and on macOS the titlebar, so it is much the largest by default.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Padding {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+}
+
+impl Default for Padding {
+    fn default() -> Self {
+        Self {
+            left: PADDING,
+            right: PADDING,
+            top: PADDING_TOP,
+            bottom: PADDING,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FullscreenMode {
+    /// A borderless window covering the monitor, which switches to other
+    /// windows without a mode change.
+    #[default]
+    Borderless,
+    /// The monitor's video mode, in its largest size and fastest refresh.
+    Exclusive,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundFit {
+    /// Scaled to the window size, ignoring the aspect ratio.
+    Stretch,
+    /// Scaled to cover the window, cropping whatever sticks out.
+    #[default]
+    Cover,
+    /// Repeated at its own size from the top-left corner.
+    Tile,
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            opacity: default_opacity(),
+            blur: false,
+            background_image: None,
+            background_image_fit: BackgroundFit::default(),
+            background_image_opacity: default_opacity(),
+            background_image_dim: 0.0,
+            fullscreen: FullscreenMode::default(),
+            padding: Padding::default(),
+            splash_background: None,
+            icon: None,
+        }
+    }
+}
+
+impl WindowSettings {
+    /// Opacity clamped to the valid range.
+    pub fn opacity(&self) -> f32 {
+        self.opacity.clamp(0.0, 1.0)
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.opacity() < 1.0
+    }
+
+    /// `splash_background` as `0xRRGGBB`, if it is a valid color.
+    pub fn splash_background(&self) -> Option<u32> {
+        parse_hex_color(self.splash_background.as_deref()?)
+    }
+
+    /// Path of the background image, with `~/` expanded.
+    pub fn background_image_path(&self) -> Option<PathBuf> {
+        expand_home(self.background_image.as_deref()?)
+    }
+
+    /// Path of the window icon, with `~/` expanded.
+    pub fn icon_path(&self) -> Option<PathBuf> {
+        expand_home(self.icon.as_deref()?)
+    }
+}
+
+/// `#rrggbb` as `0xRRGGBB`.
+pub(crate) fn parse_hex_color(color: &str) -> Option<u32> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+fn expand_home(path: &str) -> Option<PathBuf> {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+        None => Some(PathBuf::from(path)),
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct CursorSettings {
+    /// Blink as 'guicursor' says. Off, the cursor stays on whatever
+    /// 'guicursor' asks for.
+    #[serde(default = "default_true")]
+    pub blink: bool,
+    /// How a block cursor looks while the window doesn't have focus.
+    #[serde(default)]
+    pub unfocused: UnfocusedCursor,
+    /// The cursor's color as `#rrggbb`, over the colorscheme's.
+    pub color: Option<String>,
+    /// Color of the text under a block cursor, as `#rrggbb`.
+    pub text_color: Option<String>,
+    /// Least WCAG contrast the cursor keeps with the cell under it, and
+    /// its text with the cursor; colors too alike are swapped for ones that
+    /// stand out. 1.0 leaves them alone.
+    #[serde(default = "default_cursor_contrast")]
+    pub min_contrast: f32,
+    /// Overrides for modes, by 'guicursor' mode name such as `insert`,
+    /// `visual` or `cmdline_normal`.
+    #[serde(default)]
+    pub modes: BTreeMap<String, CursorModeSettings>,
+}
+
+fn default_cursor_contrast() -> f32 {
+    1.5
+}
+
+impl Default for CursorSettings {
+    fn default() -> Self {
+        Self {
+            blink: true,
+            unfocused: UnfocusedCursor::default(),
+            color: None,
+            text_color: None,
+            min_contrast: default_cursor_contrast(),
+            modes: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnfocusedCursor {
+    /// The block's outline, leaving the text under it as it is.
+    #[default]
+    Hollow,
+    /// A line under the cell.
+    Underline,
+}
+
+/// The cursor in one mode, over 'guicursor' and the `[cursor]` colors.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct CursorModeSettings {
+    pub color: Option<String>,
+    pub text_color: Option<String>,
+    pub shape: Option<CursorShapeSetting>,
+    /// Percentage of the cell a bar or underline takes.
+    pub size: Option<u8>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorShapeSetting {
+    Block,
+    Bar,
+    Underline,
+}
+
+/// The `[cursor]` settings that apply in one mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CursorStyle {
+    /// As `0xRRGGBB`.
+    pub color: Option<u32>,
+    pub text_color: Option<u32>,
+    pub shape: Option<CursorShapeSetting>,
+    pub size: Option<u8>,
+}
+
+impl CursorSettings {
+    /// The style in mode `mode`, a mode_info_set name: the mode's override
+    /// where it has one, the `[cursor]` colors otherwise. Colors that don't
+    /// parse are left to the colorscheme.
+    pub fn style(&self, mode: &str) -> CursorStyle {
+        let mode = self.modes.get(mode);
+        let color = |mode: Option<&String>, global: &Option<String>| {
+            parse_hex_color(mode.or(global.as_ref())?)
+        };
+        CursorStyle {
+            color: color(mode.and_then(|m| m.color.as_ref()), &self.color),
+            text_color: color(mode.and_then(|m| m.text_color.as_ref()), &self.text_color),
+            shape: mode.and_then(|m| m.shape),
+            size: mode.and_then(|m| m.size),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyboardSettings {
+    #[serde(default)]
+    pub key_encoding: KeyEncoding,
+    /// Which Option keys type `<M-...>` chords on macOS instead of
+    /// characters such as ƒ.
+    #[serde(default)]
+    pub macos_option_is_meta: OptionAsMeta,
+}
+
+/// Which of the Option keys act as Meta.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionAsMeta {
+    /// Both type characters, as in other macOS apps.
+    #[default]
+    None,
+    Left,
+    Right,
+    Both,
+}
+
+impl OptionAsMeta {
+    /// Whether Option held on the given sides acts as Meta.
+    pub fn applies(self, left: bool, right: bool) -> bool {
+        match self {
+            OptionAsMeta::None => false,
+            OptionAsMeta::Left => left,
+            OptionAsMeta::Right => right,
+            OptionAsMeta::Both => true,
+        }
+    }
+}
+
+/// How key presses are spelled for Neovim.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyEncoding {
+    /// What the key types, as a terminal would send it.
+    #[default]
+    Standard,
+    /// Keys a terminal can't tell apart get names of their own: keypad
+    /// keys, `<S-Space>`, `<C-i>` apart from `<Tab>` and the like.
+    Extended,
+}
+
+/// Settings that only apply on macOS.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MacosSettings {
+    /// Let the OS merge gui.nvim windows into one tabbed frame. Navigate
+    /// with Cmd-Shift-[ and Cmd-Shift-].
+    #[serde(default)]
+    pub native_tabs: bool,
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct FontSettings {
     pub family: Option<String>,
     pub size: Option<f32>,
+    /// Family for text Neovim highlights with `altfont`, for instance a
+    /// proportional or handwriting font for comments.
+    pub alt_family: Option<String>,
+    /// Size of color glyphs (emoji) relative to the two-cell box they are
+    /// fitted into. Defaults to 1.0.
+    pub emoji_scale: Option<f32>,
+    /// Draw a subtle pill behind color glyphs (emoji) about as light or
+    /// dark as the background behind them, so they stand out.
+    #[serde(default)]
+    pub emoji_backing: bool,
+    /// Cell height relative to the font's line height.
+    pub line_height: Option<CellSpacing>,
+    /// Cell width relative to the font's advance.
+    pub letter_spacing: Option<CellSpacing>,
+    #[serde(default)]
+    pub underline: UnderlineSettings,
+    /// Extra pixels between rows, set through Neovim's `linespace`.
+    #[serde(skip)]
+    pub linespace: i64,
+    /// How glyph edges blend with the background. Takes effect on the
+    /// next start.
+    #[serde(default)]
+    pub text_blend: TextBlend,
+}
+
+/// Underlines over the font's own metrics, in logical pixels.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct UnderlineSettings {
+    /// Thickness of underlines, instead of the font's.
+    pub thickness: Option<f32>,
+    /// How much lower than the font puts them underlines go; negative
+    /// moves them up.
+    #[serde(default)]
+    pub position_offset: f32,
+}
+
+/// The color space glyph edges are blended in.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextBlend {
+    /// In linear light: correct, but text looks thinner than in most
+    /// terminals.
+    #[default]
+    Linear,
+    /// On sRGB values, as most terminals do.
+    Srgb,
+}
+
+/// Resizes a cell dimension: a factor such as `1.2`, or `"2px"` to add
+/// (or with a minus, take away) logical pixels.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(try_from = "CellSpacingValue")]
+pub enum CellSpacing {
+    Factor(f32),
+    Pixels(f32),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CellSpacingValue {
+    Number(f32),
+    Text(String),
+}
+
+impl TryFrom<CellSpacingValue> for CellSpacing {
+    type Error = String;
+
+    fn try_from(value: CellSpacingValue) -> Result<Self, Self::Error> {
+        match value {
+            CellSpacingValue::Number(factor) if factor > 0.0 => Ok(CellSpacing::Factor(factor)),
+            CellSpacingValue::Text(text) => text
+                .strip_suffix("px")
+                .and_then(|pixels| pixels.trim().parse().ok())
+                .map(CellSpacing::Pixels)
+                .ok_or_else(|| format!("expected pixels such as \"2px\", got {:?}", text)),
+            CellSpacingValue::Number(factor) => {
+                Err(format!("expected a positive factor, got {}", factor))
+            }
+        }
+    }
+}
+
+impl CellSpacing {
+    /// Whole pixels to add to a cell dimension of `size` physical pixels.
+    pub fn extra(self, size: f32, scale_factor: f64) -> f32 {
+        match self {
+            CellSpacing::Factor(factor) => (size * factor - size).round(),
+            CellSpacing::Pixels(pixels) => (pixels as f64 * scale_factor).round() as f32,
+        }
+    }
 }
 
 impl FontSettings {
@@ -60,20 +581,14 @@ impl FontSettings {
         // Safety: split().next() always returns Some for non-empty strings
         let first_font = guifont.split(',').next().unwrap();
 
-        if let Some((family, size_str)) = first_font.rsplit_once(":h") {
-            let size = size_str.parse::<f32>().ok();
-            let family = family.replace("\\ ", " ");
-            return Some(Self {
-                family: Some(family),
-                size,
-            });
-        }
-
-        // Just family
-        let family = first_font.replace("\\ ", " ");
+        // `Family:hSIZE:b...`; an empty family keeps the current one.
+        let mut parts = first_font.split(':');
+        let family = parts.next().unwrap().replace("\\ ", " ");
+        let size = parts.find_map(|option| option.strip_prefix('h')?.parse::<f32>().ok());
         Some(Self {
-            family: Some(family),
-            size: None,
+            family: (!family.is_empty()).then_some(family),
+            size,
+            ..Self::default()
         })
     }
 }
@@ -130,10 +645,29 @@ mod tests {
         let config = Config::default();
         assert_eq!(config.font.family, None);
         assert_eq!(config.font.size, None);
+        assert_eq!(config.font.text_blend, TextBlend::Linear);
         #[cfg(target_os = "macos")]
         assert_eq!(config.performance.vsync, VsyncMode::DisplayLink);
         #[cfg(not(target_os = "macos"))]
         assert_eq!(config.performance.vsync, VsyncMode::MailboxIfAvailable);
+        assert!(!config.ui.messages);
+        assert!(!config.ui.cmdline && !config.ui.multigrid);
+        assert!(config.ui.popupmenu);
+        assert_eq!(config.ui.tabline, TablineVisibility::Auto);
+        assert_eq!(config.ui.open_files_in, OpenFilesIn::Window);
+        assert!(!config.ui.recent_files);
+        assert!(!config.ui.smooth_scroll);
+        assert!(!config.ui.show_invisible);
+        assert!(!config.ui.primary_selection());
+        assert!(!config.ui.mirror);
+        assert_eq!(config.keyboard.key_encoding, KeyEncoding::Standard);
+        assert!(config.cursor.blink);
+        assert_eq!(config.keyboard.macos_option_is_meta, OptionAsMeta::None);
+        assert!(!config.macos.native_tabs);
+        assert_eq!(config.window.opacity, 1.0);
+        assert!(!config.window.is_transparent());
+        assert_eq!(config.window.fullscreen, FullscreenMode::Borderless);
+        assert_eq!(config.window.padding, Padding::default());
     }
 
     #[test]
@@ -187,6 +721,71 @@ mod tests {
         assert_eq!(settings.size, None);
     }
 
+    #[test]
+    fn test_from_guifont_options() {
+        let settings = FontSettings::from_guifont("JetBrains Mono:b:h13.5:w600").unwrap();
+        assert_eq!(settings.family.as_deref(), Some("JetBrains Mono"));
+        assert_eq!(settings.size, Some(13.5));
+
+        let settings = FontSettings::from_guifont(":h18").unwrap();
+        assert_eq!(settings.family, None);
+        assert_eq!(settings.size, Some(18.0));
+        assert_eq!(settings.linespace, 0);
+    }
+
+    #[test]
+    fn test_parse_underline_config() {
+        let toml = r#"
+            [font.underline]
+            thickness = 1.5
+            position_offset = -1
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(
+            config.font.underline,
+            UnderlineSettings {
+                thickness: Some(1.5),
+                position_offset: -1.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_cell_spacing() {
+        let toml = r#"
+            [font]
+            line_height = 1.5
+            letter_spacing = "-1px"
+            text_blend = "srgb"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(!config.font.emoji_backing);
+        assert_eq!(config.font.text_blend, TextBlend::Srgb);
+        assert_eq!(config.font.line_height, Some(CellSpacing::Factor(1.5)));
+        assert_eq!(config.font.letter_spacing, Some(CellSpacing::Pixels(-1.0)));
+        assert_eq!(config.font.underline, UnderlineSettings::default());
+
+        let config: Config = toml::from_str("[font]\nline_height = 1").unwrap();
+        assert_eq!(config.font.line_height, Some(CellSpacing::Factor(1.0)));
+        assert!(toml::from_str::<Config>("[font]\nline_height = \"2em\"").is_err());
+        assert!(toml::from_str::<Config>("[font]\nline_height = 0").is_err());
+
+        assert_eq!(CellSpacing::Factor(1.2).extra(20.0, 2.0), 4.0);
+        assert_eq!(CellSpacing::Pixels(1.5).extra(20.0, 2.0), 3.0);
+        assert_eq!(CellSpacing::Pixels(-1.0).extra(20.0, 1.0), -1.0);
+    }
+
+    #[test]
+    fn test_parse_special_colors_config() {
+        let toml = r##"
+            [special_colors]
+            SpellBad = "#ff00ff"
+        "##;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.special_colors["SpellBad"], "#ff00ff");
+        assert!(Config::default().special_colors.is_empty());
+    }
+
     #[test]
     fn test_parse_performance_config() {
         let toml = r#"
@@ -195,5 +794,202 @@ mod tests {
         "#;
         let config: Config = toml::from_str(toml).unwrap();
         assert_eq!(config.performance.vsync, VsyncMode::Enabled);
+        assert_eq!(config.performance.max_fps, None);
+
+        let toml = r#"
+            [performance]
+            vsync = "disabled"
+            max_fps = 144
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.performance.max_fps, Some(144));
+    }
+
+    #[test]
+    fn test_parse_ui_config() {
+        let toml = r#"
+            [ui]
+            messages = true
+            tabline = "always"
+            recent_files = true
+            open_files_in = "vsplit"
+            smooth_scroll = true
+            show_invisible = true
+            primary_selection = true
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(config.ui.messages);
+        assert!(config.ui.recent_files);
+        assert_eq!(config.ui.tabline, TablineVisibility::Always);
+        assert_eq!(config.ui.open_files_in, OpenFilesIn::Vsplit);
+        assert!(config.ui.smooth_scroll);
+        assert!(config.ui.show_invisible);
+        assert!(config.ui.primary_selection);
+    }
+
+    #[test]
+    fn test_parse_keyboard_config() {
+        let toml = r#"
+            [keyboard]
+            key_encoding = "extended"
+            macos_option_is_meta = "left"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.keyboard.key_encoding, KeyEncoding::Extended);
+        let option = config.keyboard.macos_option_is_meta;
+        assert!(option.applies(true, false) && !option.applies(false, true));
+        assert!(OptionAsMeta::Both.applies(false, false));
+        assert!(!OptionAsMeta::None.applies(true, true));
+    }
+
+    #[test]
+    fn test_parse_cursor_config() {
+        let config: Config = toml::from_str("[cursor]\nblink = false").unwrap();
+        assert!(!config.cursor.blink);
+        assert_eq!(config.cursor.unfocused, UnfocusedCursor::Hollow);
+        assert_eq!(config.cursor.min_contrast, 1.5);
+        assert_eq!(config.cursor.style("normal"), CursorStyle::default());
+    }
+
+    #[test]
+    fn test_parse_cursor_styles() {
+        let toml = r##"
+            [cursor]
+            unfocused = "underline"
+            color = "#ff8800"
+            min_contrast = 3.0
+
+            [cursor.modes.insert]
+            color = "#00ff00"
+            text_color = "#000000"
+            shape = "bar"
+            size = 15
+
+            [cursor.modes.visual]
+            color = "nope"
+        "##;
+        let config: Config = toml::from_str(toml).unwrap();
+        let cursor = &config.cursor;
+        assert_eq!(cursor.unfocused, UnfocusedCursor::Underline);
+        assert_eq!(cursor.min_contrast, 3.0);
+        assert_eq!(
+            cursor.style("normal"),
+            CursorStyle {
+                color: Some(0xff8800),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            cursor.style("insert"),
+            CursorStyle {
+                color: Some(0x00ff00),
+                text_color: Some(0x000000),
+                shape: Some(CursorShapeSetting::Bar),
+                size: Some(15),
+            }
+        );
+        // A color that doesn't parse leaves the colorscheme's, not the
+        // `[cursor]` one.
+        assert_eq!(cursor.style("visual").color, None);
+    }
+
+    #[test]
+    fn test_parse_window_config() {
+        let toml = r#"
+            [window]
+            opacity = 0.9
+            blur = true
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(config.window.is_transparent());
+        assert!(config.window.blur);
+
+        let toml = r#"
+            [window]
+            opacity = 3.0
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.window.opacity(), 1.0);
+        assert!(!config.window.blur);
+
+        let toml = r#"
+            [window]
+            padding = { left = 8, top = 40 }
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let padding = config.window.padding;
+        assert_eq!((padding.left, padding.top), (8, 40));
+        assert_eq!((padding.right, padding.bottom), (PADDING, PADDING));
+    }
+
+    #[test]
+    fn test_parse_background_image_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.window.background_image_path(), None);
+        assert_eq!(config.window.background_image_fit, BackgroundFit::Cover);
+
+        let toml = r#"
+            [window]
+            background_image = "~/Pictures/bg.png"
+            background_image_fit = "tile"
+            background_image_dim = 0.3
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let path = config.window.background_image_path().unwrap();
+        assert!(path.ends_with("Pictures/bg.png"));
+        assert!(!path.starts_with("~"));
+        assert_eq!(config.window.background_image_fit, BackgroundFit::Tile);
+        assert_eq!(config.window.background_image_opacity, 1.0);
+        assert_eq!(config.window.background_image_dim, 0.3);
+    }
+
+    #[test]
+    fn test_parse_splash_background() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.window.splash_background(), None);
+
+        let config: Config = toml::from_str("[window]\nsplash_background = \"#1e1e2e\"").unwrap();
+        assert_eq!(config.window.splash_background(), Some(0x1e1e2e));
+        let config: Config = toml::from_str("[window]\nsplash_background = \"1e1e2e\"").unwrap();
+        assert_eq!(config.window.splash_background(), None);
+    }
+
+    #[test]
+    fn test_parse_icon_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.window.icon_path(), None);
+
+        let config: Config = toml::from_str("[window]\nicon = \"~/icons/nvim.png\"").unwrap();
+        let path = config.window.icon_path().unwrap();
+        assert!(path.ends_with("icons/nvim.png"));
+        assert!(!path.starts_with("~"));
+        let config: Config = toml::from_str("[window]\nicon = \"/opt/nvim.png\"").unwrap();
+        assert_eq!(
+            config.window.icon_path(),
+            Some(PathBuf::from("/opt/nvim.png"))
+        );
+    }
+
+    #[test]
+    fn test_parse_macos_config() {
+        let toml = r#"
+            [macos]
+            native_tabs = true
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(config.macos.native_tabs);
+    }
+
+    #[test]
+    fn test_parse_keybinds_config() {
+        let toml = r#"
+            [keybinds]
+            "cmd+t" = ":tabnew<CR>"
+            "cmd+shift+]" = "gt"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.keybinds.len(), 2);
+        assert_eq!(config.keybinds["cmd+shift+]"], "gt");
+        assert!(Config::default().keybinds.is_empty());
     }
 }
@@ -1,9 +1,28 @@
 pub const PADDING: u32 = 2;
 pub const PADDING_TOP: u32 = 30;
 
+/// Width of the macOS window buttons at the left of the titlebar, kept clear
+/// by the tab bar (logical px).
+pub const TITLEBAR_BUTTONS_WIDTH: u32 = 72;
+
 pub const DEFAULT_CELL_WIDTH: u32 = 10;
 pub const DEFAULT_CELL_HEIGHT: u32 = 20;
 
 // Bridge constants
 pub const DEFAULT_COLS: u64 = 80;
 pub const DEFAULT_ROWS: u64 = 24;
+
+/// Largest grid accepted from Neovim or requested from it. Cell storage
+/// grows with the product, so anything beyond this is treated as a bug.
+pub const MAX_GRID_COLS: u64 = 1000;
+pub const MAX_GRID_ROWS: u64 = 1000;
+
+// Font size constants (points)
+pub const DEFAULT_FONT_SIZE: f32 = 14.0;
+pub const MIN_FONT_SIZE: f32 = 6.0;
+pub const MAX_FONT_SIZE: f32 = 72.0;
+/// Change in font size per zoom in or out.
+pub const FONT_ZOOM_STEP: f32 = 1.0;
+/// Range of `gui.set_window_zoom` factors.
+pub const MIN_WINDOW_ZOOM: f32 = 0.25;
+pub const MAX_WINDOW_ZOOM: f32 = 4.0;
@@ -0,0 +1,194 @@
+//! Crash reports. When gui.nvim panics, a panic hook writes what is needed
+//! to look into it to `crash-<time>.txt` among the local data: the panic
+//! and its backtrace, the OS, the GPU adapter, the effective config and
+//! the last redraw events from Neovim. The next start offers to open it.
+//!
+//! Panics in any thread are reported, whether or not they take the
+//! process down.
+
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::bridge::redraw_history;
+use crate::config::Config;
+use crate::snapshot::utc_timestamp;
+
+/// Holds the path of the report the next start hasn't shown yet.
+const PENDING: &str = "pending";
+
+/// What the report says besides the panic, filled in as gui.nvim starts.
+#[derive(Debug)]
+struct Context {
+    config: Option<String>,
+    gpu: Option<String>,
+}
+
+static CONTEXT: Mutex<Context> = Mutex::new(Context {
+    config: None,
+    gpu: None,
+});
+
+/// Where reports are written.
+pub fn reports_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("gui-nvim").join("crashes"))
+}
+
+/// Installs the panic hook, which reports `config` as the effective one.
+/// The default hook still prints the panic first.
+pub fn install(config: &Config) {
+    set_context(|context| context.config = Some(format!("{:#?}", config)));
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let Some(dir) = reports_dir() else {
+            return;
+        };
+        let report = report(info, &Backtrace::force_capture());
+        match save(&dir, &report) {
+            Ok(path) => eprintln!("gui.nvim crashed; report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write a crash report: {}", e),
+        }
+    }));
+}
+
+/// Names the GPU adapter in reports, once the renderer has picked it.
+pub fn set_gpu(info: &wgpu::AdapterInfo) {
+    let gpu = format!(
+        "{} ({:?}, {:?}), driver {} {}",
+        info.name, info.backend, info.device_type, info.driver, info.driver_info
+    );
+    set_context(|context| context.gpu = Some(gpu));
+}
+
+fn set_context(set: impl FnOnce(&mut Context)) {
+    set(&mut CONTEXT.lock().unwrap_or_else(|e| e.into_inner()));
+}
+
+fn report(info: &PanicHookInfo, backtrace: &Backtrace) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".to_string());
+    let location = info
+        .location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let thread = std::thread::current();
+    let panic = format!(
+        "thread '{}' panicked at {}:\n{}",
+        thread.name().unwrap_or("<unnamed>"),
+        location,
+        message
+    );
+    // The panicking thread may hold the lock.
+    let (config, gpu) = match CONTEXT.try_lock() {
+        Ok(context) => (context.config.clone(), context.gpu.clone()),
+        Err(_) => (None, None),
+    };
+    format_report(
+        &panic,
+        &backtrace.to_string(),
+        gpu.as_deref(),
+        config.as_deref(),
+        &redraw_history::recent(),
+    )
+}
+
+fn format_report(
+    panic: &str,
+    backtrace: &str,
+    gpu: Option<&str>,
+    config: Option<&str>,
+    redraws: &[String],
+) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "gui.nvim {} crash report", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        out,
+        "os: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(out, "gpu: {}", gpu.unwrap_or("not initialized"));
+    let _ = writeln!(out, "\n{}\n\nbacktrace:\n{}", panic, backtrace.trim_end());
+    let _ = writeln!(out, "\nlast redraw events, oldest first:");
+    for event in redraws {
+        let _ = writeln!(out, "  {}", event);
+    }
+    let _ = writeln!(out, "\nconfig:\n{}", config.unwrap_or("not loaded"));
+    out
+}
+
+/// Writes `report` into `dir` and marks it for the next start. Returns its
+/// path.
+fn save(dir: &Path, report: &str) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let path = dir.join(format!("crash-{}.txt", utc_timestamp(now.as_secs())));
+    std::fs::write(&path, report)?;
+    std::fs::write(dir.join(PENDING), path.to_string_lossy().as_bytes())?;
+    Ok(path)
+}
+
+/// The report written since the last start, if there is one still to
+/// show. Each report is handed out once.
+pub fn take_pending() -> Option<PathBuf> {
+    take_pending_in(&reports_dir()?)
+}
+
+fn take_pending_in(dir: &Path) -> Option<PathBuf> {
+    let marker = dir.join(PENDING);
+    let path = PathBuf::from(std::fs::read_to_string(&marker).ok()?.trim());
+    let _ = std::fs::remove_file(&marker);
+    path.is_file().then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_sections() {
+        let report = format_report(
+            "thread 'main' panicked at src/window/window.rs:10:5:\nboom",
+            "   0: gui_nvim::window::boom\n",
+            Some("llvmpipe (Gl, Cpu), driver mesa 24"),
+            None,
+            &["Flush".to_string()],
+        );
+        assert!(report.starts_with("gui.nvim "));
+        assert!(report.contains(&format!("os: {} ", std::env::consts::OS)));
+        assert!(report.contains("gpu: llvmpipe (Gl, Cpu), driver mesa 24\n"));
+        assert!(report.contains("panicked at src/window/window.rs:10:5:\nboom\n"));
+        assert!(report.contains("backtrace:\n   0: gui_nvim::window::boom\n"));
+        assert!(report.contains("oldest first:\n  Flush\n"));
+        assert!(report.ends_with("config:\nnot loaded\n"));
+    }
+
+    #[test]
+    fn test_reports_are_shown_once() {
+        let dir = std::env::temp_dir().join(format!("gui-nvim-crash-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(take_pending_in(&dir), None);
+
+        let path = save(&dir, "report").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "report");
+        assert_eq!(take_pending_in(&dir), Some(path.clone()));
+        assert_eq!(take_pending_in(&dir), None);
+
+        // A report deleted in the meantime isn't offered.
+        let path = save(&dir, "report").unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(take_pending_in(&dir), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,328 @@
+//! `gui.nvim --daemon`: a process with Neovim already running and no
+//! window yet, waiting on a socket. A later `gui.nvim file` hands it the
+//! files and blocks until the window it opens is closed, so the GUI works
+//! as a fast `$EDITOR`, such as git's.
+//!
+//! A daemon serves one launch: it stops listening, starts its successor
+//! and becomes an ordinary window.
+
+use std::io;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::{Arc, Mutex};
+
+use winit::dpi::PhysicalSize;
+use winit::event_loop::EventLoopProxy;
+
+use crate::config::Config;
+#[cfg(unix)]
+use crate::event::GUIEvent;
+use crate::event::UserEvent;
+use crate::renderer::{GpuContext, Renderer};
+
+/// Files to open in the daemon, relative to `cwd`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchRequest {
+    pub cwd: PathBuf,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DaemonError {
+    #[error("No directory to put the daemon socket in")]
+    NoSocketDir,
+
+    #[error("A daemon is already running at {0}")]
+    AlreadyRunning(PathBuf),
+
+    #[error("Daemon mode needs Unix domain sockets")]
+    Unsupported,
+
+    #[error("Daemon socket error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl LaunchRequest {
+    /// Fields separated by NUL, which can't appear in paths or arguments.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut fields = vec![self.cwd.to_string_lossy().into_owned()];
+        fields.extend(self.files.iter().cloned());
+        fields.join("\0").into_bytes()
+    }
+
+    pub(crate) fn decode(bytes: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(bytes).ok()?;
+        let mut fields = text.split('\0');
+        let cwd = PathBuf::from(fields.next().filter(|cwd| !cwd.is_empty())?);
+        Some(Self {
+            cwd,
+            files: fields.map(str::to_string).collect(),
+        })
+    }
+
+    /// The working directory, then the files, as paths for
+    /// `open_commands`, which turns the directory into a `:cd`.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        std::iter::once(self.cwd.clone())
+            .chain(self.file_paths())
+            .collect()
+    }
+
+    /// The files, relative to the working directory.
+    pub fn file_paths(&self) -> Vec<PathBuf> {
+        self.files.iter().map(|file| self.cwd.join(file)).collect()
+    }
+}
+
+/// Where the daemon listens.
+pub fn socket_path() -> Option<PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("gui-nvim").join("daemon.sock"))
+}
+
+/// Only plain file arguments can be handed to a running Neovim; options
+/// like `--clean` need a fresh one.
+pub(crate) fn daemon_can_open(args: &[String]) -> bool {
+    args.iter()
+        .all(|arg| !arg.starts_with('-') && !arg.starts_with('+'))
+}
+
+/// Opens `args` in a running daemon and waits for its window to close.
+/// Returns the exit code to exit with, or `None` if no daemon took the
+/// launch and the GUI should start as usual.
+pub fn open_in_daemon(args: &[String]) -> Option<i32> {
+    if !daemon_can_open(args) {
+        return None;
+    }
+    let path = socket_path()?;
+    let request = LaunchRequest {
+        cwd: std::env::current_dir().ok()?,
+        files: args.to_vec(),
+    };
+    launch(&path, &request)
+}
+
+#[cfg(unix)]
+fn launch(path: &Path, request: &LaunchRequest) -> Option<i32> {
+    use std::io::Read;
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(path).ok()?;
+    stream
+        .set_read_timeout(Some(crate::ipc::REQUEST_TIMEOUT))
+        .ok()?;
+    if let Err(e) = crate::ipc::send_request(&mut stream, request) {
+        log::warn!("The daemon didn't take the files: {}", e);
+        return None;
+    }
+    log::info!("Opened in the daemon at {}", path.display());
+
+    // The daemon took the files: from here on, waiting is all that's left.
+    let mut reply = String::new();
+    match stream
+        .set_read_timeout(None)
+        .and_then(|()| stream.read_to_string(&mut reply))
+    {
+        Ok(_) => Some(reply.trim().parse().unwrap_or(1)),
+        Err(e) => {
+            log::error!("Lost the daemon: {}", e);
+            Some(1)
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn launch(_path: &Path, _request: &LaunchRequest) -> Option<i32> {
+    None
+}
+
+/// The listening side, owned by the app.
+#[cfg(unix)]
+pub struct Daemon {
+    /// The client whose launch this process took, answered on exit.
+    client: Arc<Mutex<Option<std::os::unix::net::UnixStream>>>,
+}
+
+#[cfg(unix)]
+impl Daemon {
+    /// Listens at `socket_path()`. The launch arrives as
+    /// `GUIEvent::Launch`; once it has, a successor daemon is started.
+    pub fn listen(proxy: EventLoopProxy<UserEvent>) -> Result<Self, DaemonError> {
+        let path = socket_path().ok_or(DaemonError::NoSocketDir)?;
+        let listener = bind(&path)?;
+        log::info!("Daemon listening at {}", path.display());
+
+        let client = Arc::new(Mutex::new(None));
+        let slot = client.clone();
+        std::thread::spawn(move || {
+            let (stream, request) = accept_launch(&listener);
+            drop(listener);
+            let _ = std::fs::remove_file(&path);
+            *slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(stream);
+            spawn_successor();
+            let _ = proxy.send_event(UserEvent::GUI(GUIEvent::Launch(request)));
+        });
+        Ok(Self { client })
+    }
+
+    /// Lets the waiting client exit with `code`.
+    pub fn finish(&self, code: i32) {
+        use std::io::Write;
+
+        let client = self.client.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(mut stream) = client {
+            let _ = writeln!(stream, "{}", code);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub struct Daemon;
+
+#[cfg(not(unix))]
+impl Daemon {
+    pub fn listen(_proxy: EventLoopProxy<UserEvent>) -> Result<Self, DaemonError> {
+        Err(DaemonError::Unsupported)
+    }
+
+    pub fn finish(&self, _code: i32) {}
+}
+
+/// Sets up a throwaway offscreen renderer in the background, so the GPU
+/// driver, its shader cache and the fonts are loaded by the time the
+/// window opens.
+pub fn warm_up(config: Config) {
+    std::thread::spawn(move || {
+        let Ok(runtime) = tokio::runtime::Builder::new_current_thread().build() else {
+            return;
+        };
+        let Some(adapter) = runtime
+            .block_on(GpuContext::headless_adapters())
+            .into_iter()
+            .next()
+        else {
+            log::warn!("No GPU adapter to warm up");
+            return;
+        };
+        let size = PhysicalSize::new(1, 1);
+        if let Err(e) = runtime.block_on(Renderer::headless(&adapter, size, config, 1.0)) {
+            log::warn!("Failed to warm up the renderer: {}", e);
+        }
+    });
+}
+
+/// Binds `path`, clearing a socket left behind by a daemon that died.
+#[cfg(unix)]
+fn bind(path: &Path) -> Result<std::os::unix::net::UnixListener, DaemonError> {
+    crate::ipc::bind_socket(path)?.ok_or_else(|| DaemonError::AlreadyRunning(path.to_path_buf()))
+}
+
+/// Waits for a client with a well-formed request.
+#[cfg(unix)]
+fn accept_launch(
+    listener: &std::os::unix::net::UnixListener,
+) -> (std::os::unix::net::UnixStream, LaunchRequest) {
+    loop {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                log::warn!("Daemon accept failed: {}", e);
+                continue;
+            }
+        };
+        // A client that never finishes its request mustn't block the next.
+        let _ = stream.set_read_timeout(Some(crate::ipc::REQUEST_TIMEOUT));
+        match crate::ipc::receive_request(&mut stream) {
+            Ok(request) => return (stream, request),
+            Err(e) => log::warn!("Ignoring a daemon request: {}", e),
+        }
+    }
+}
+
+/// Starts the daemon that takes over the socket, so the next launch is
+/// fast too.
+#[cfg(unix)]
+fn spawn_successor() {
+    let spawned = std::env::current_exe()
+        .and_then(|exe| std::process::Command::new(exe).arg("--daemon").spawn());
+    if let Err(e) = spawned {
+        log::warn!("Failed to start the next daemon: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_roundtrip() {
+        let request = LaunchRequest {
+            cwd: PathBuf::from("/home/me/repo"),
+            files: vec![".git/COMMIT_EDITMSG".to_string(), "a b.rs".to_string()],
+        };
+        assert_eq!(
+            LaunchRequest::decode(&request.encode()),
+            Some(request.clone())
+        );
+        assert_eq!(
+            request.paths(),
+            [
+                PathBuf::from("/home/me/repo"),
+                PathBuf::from("/home/me/repo/.git/COMMIT_EDITMSG"),
+                PathBuf::from("/home/me/repo/a b.rs"),
+            ]
+        );
+
+        let empty = LaunchRequest {
+            cwd: PathBuf::from("/"),
+            files: vec![],
+        };
+        assert_eq!(LaunchRequest::decode(&empty.encode()), Some(empty));
+        assert_eq!(LaunchRequest::decode(b""), None);
+        assert_eq!(LaunchRequest::decode(&[0xff, 0]), None);
+    }
+
+    #[test]
+    fn test_daemon_can_open() {
+        assert!(daemon_can_open(&[]));
+        assert!(daemon_can_open(&["file.txt".to_string()]));
+        assert!(!daemon_can_open(&["--clean".to_string()]));
+        assert!(!daemon_can_open(&[
+            "+10".to_string(),
+            "file.txt".to_string()
+        ]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bind_replaces_stale_socket() {
+        let dir = std::env::temp_dir().join(format!("gui-nvim-daemon-{}", std::process::id()));
+        let path = dir.join("daemon.sock");
+        let listener = bind(&path).unwrap();
+        assert!(matches!(bind(&path), Err(DaemonError::AlreadyRunning(_))));
+
+        // The socket file outlives a daemon that didn't clean up.
+        drop(listener);
+        assert!(path.exists());
+        let listener = bind(&path).unwrap();
+
+        let request = LaunchRequest {
+            cwd: dir.clone(),
+            files: vec!["x".to_string()],
+        };
+        let client = std::thread::spawn({
+            let path = path.clone();
+            let request = request.clone();
+            move || launch(&path, &request)
+        });
+        let (stream, received) = accept_launch(&listener);
+        assert_eq!(received, request);
+        let daemon = Daemon {
+            client: Arc::new(Mutex::new(Some(stream))),
+        };
+        daemon.finish(3);
+        assert_eq!(client.join().unwrap(), Some(3));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -20,7 +20,7 @@ bitflags! {
 ///
 /// Uses `CompactString` for SSO (small string optimization) - strings up to
 /// 24 bytes are stored inline without heap allocation.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Cell {
     /// The character displayed in this cell.
     /// Empty cells contain a space character.
@@ -81,6 +81,21 @@ impl Cell {
         self.flags.set(CellFlags::WIDE_CHAR_SPACER, spacer);
     }
 
+    /// Returns the first character in this cell that doesn't show on screen:
+    /// zero-width and bidi control characters, which Neovim merges into
+    /// the preceding cell, and spaces other than U+0020. A zero-width joiner
+    /// that joins something, as in emoji sequences, doesn't count.
+    pub fn invisible_char(&self) -> Option<char> {
+        let mut chars = self.text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let joins = c == '\u{200d}' && chars.peek().is_some();
+            if is_invisible(c) && !joins {
+                return Some(c);
+            }
+        }
+        None
+    }
+
     /// Resets the cell to its default state.
     pub fn clear(&mut self) {
         self.text = CompactString::const_new(" ");
@@ -89,6 +104,26 @@ impl Cell {
     }
 }
 
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00a0}'
+            | '\u{00ad}'
+            | '\u{034f}'
+            | '\u{061c}'
+            | '\u{115f}'
+            | '\u{1160}'
+            | '\u{180e}'
+            | '\u{2000}'..='\u{200f}'
+            | '\u{2028}'..='\u{202f}'
+            | '\u{205f}'..='\u{206f}'
+            | '\u{3000}'
+            | '\u{3164}'
+            | '\u{feff}'
+            | '\u{fff9}'..='\u{fffb}'
+    ) || c.is_control()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,4 +210,17 @@ mod tests {
         let cloned = cell.clone();
         assert_eq!(cell, cloned);
     }
+
+    #[test]
+    fn test_cell_invisible_char() {
+        assert_eq!(Cell::new("a", 0).invisible_char(), None);
+        assert_eq!(Cell::default().invisible_char(), None);
+        assert_eq!(Cell::new("a\u{200b}", 0).invisible_char(), Some('\u{200b}'));
+        assert_eq!(Cell::new("\u{00a0}", 0).invisible_char(), Some('\u{00a0}'));
+        assert_eq!(Cell::new("\u{202e}", 0).invisible_char(), Some('\u{202e}'));
+        assert_eq!(Cell::new("e\u{301}", 0).invisible_char(), None);
+        // Joiners inside an emoji sequence are part of the glyph
+        assert_eq!(Cell::new("👨\u{200d}👩", 0).invisible_char(), None);
+        assert_eq!(Cell::new("a\u{200d}", 0).invisible_char(), Some('\u{200d}'));
+    }
 }
@@ -0,0 +1,133 @@
+use super::messages::StyledChunk;
+
+/// A command line from cmdline_show. Neovim hands the cmdline to the UI
+/// whenever ext_messages is on (ext_messages implies ext_cmdline).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cmdline {
+    pub content: Vec<StyledChunk>,
+    /// Cursor position as a byte offset into the concatenated content.
+    pub pos: usize,
+    /// Command type character such as `:`, `/` or `=`; empty for input().
+    pub firstc: String,
+    /// Prompt text for input().
+    pub prompt: String,
+    pub indent: usize,
+    /// Character shown at the cursor after Ctrl-V or Ctrl-R, until the
+    /// next cmdline_show.
+    pub special_char: Option<String>,
+}
+
+impl Cmdline {
+    /// Text before the cmdline content: type character, prompt and indent.
+    pub fn prefix(&self) -> String {
+        format!("{}{}{}", self.firstc, self.prompt, " ".repeat(self.indent))
+    }
+
+    /// The content as plain text.
+    pub fn text(&self) -> String {
+        self.content.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    /// Cursor position in characters from the start of the bar, prefix
+    /// included.
+    pub fn cursor_col(&self) -> usize {
+        let text = self.text();
+        let pos = self.pos.min(text.len());
+        let before = text.char_indices().take_while(|(i, _)| *i < pos).count();
+        self.prefix().chars().count() + before
+    }
+}
+
+/// Nested command lines (e.g. `<C-r>=` inside `:`), indexed by level - 1.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CmdlineStack {
+    levels: Vec<Cmdline>,
+}
+
+impl CmdlineStack {
+    /// Every chunk of every level.
+    pub fn chunks(&self) -> impl Iterator<Item = &StyledChunk> {
+        self.levels.iter().flat_map(|level| &level.content)
+    }
+
+    /// Handles cmdline_show; levels above `level` are dropped.
+    pub fn show(&mut self, level: u64, cmdline: Cmdline) {
+        let index = (level.max(1) - 1) as usize;
+        self.levels.truncate(index);
+        self.levels.resize_with(index, Cmdline::default);
+        self.levels.push(cmdline);
+    }
+
+    fn level_mut(&mut self, level: u64) -> Option<&mut Cmdline> {
+        self.levels.get_mut((level.max(1) - 1) as usize)
+    }
+
+    /// Handles cmdline_pos.
+    pub fn set_pos(&mut self, level: u64, pos: usize) {
+        if let Some(cmdline) = self.level_mut(level) {
+            cmdline.pos = pos;
+            cmdline.special_char = None;
+        }
+    }
+
+    /// Handles cmdline_special_char.
+    pub fn set_special_char(&mut self, level: u64, c: &str) {
+        if let Some(cmdline) = self.level_mut(level) {
+            cmdline.special_char = Some(c.to_string());
+        }
+    }
+
+    /// Handles cmdline_hide.
+    pub fn hide(&mut self, level: u64) {
+        self.levels.truncate((level.max(1) - 1) as usize);
+    }
+
+    /// The innermost command line, which is the one being edited.
+    pub fn active(&self) -> Option<&Cmdline> {
+        self.levels.last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmdline(text: &str, pos: usize) -> Cmdline {
+        Cmdline {
+            content: vec![StyledChunk {
+                hl_id: 0,
+                text: text.to_string(),
+            }],
+            pos,
+            firstc: ":".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cursor_col_counts_chars() {
+        // "é" is two bytes; byte offset 3 is after "éa"
+        let line = cmdline("éab", 3);
+        assert_eq!(line.cursor_col(), 3);
+        assert_eq!(line.prefix(), ":");
+    }
+
+    #[test]
+    fn test_nested_levels() {
+        let mut stack = CmdlineStack::default();
+        stack.show(1, cmdline("echo ", 5));
+        stack.show(2, cmdline("1+1", 3));
+        assert_eq!(stack.active().unwrap().text(), "1+1");
+
+        stack.hide(2);
+        assert_eq!(stack.active().unwrap().text(), "echo ");
+
+        stack.set_special_char(1, "\"");
+        assert_eq!(stack.active().unwrap().special_char.as_deref(), Some("\""));
+        stack.set_pos(1, 2);
+        assert_eq!(stack.active().unwrap().special_char, None);
+
+        stack.hide(1);
+        assert!(stack.active().is_none());
+    }
+}
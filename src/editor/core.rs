@@ -0,0 +1,361 @@
+//! The editor core: applies Neovim's redraw events to the `EditorState` on
+//! a thread of its own, so a burst of them, such as a command printing
+//! 100k lines into `:terminal`, doesn't hold up resizing or closing the
+//! window.
+//!
+//! The core sits between the bridge and the event loop. It applies
+//! redraws under the state's lock and passes every other event on in the
+//! order it came. It wakes the event loop once per batch of changes
+//! rather than once per redraw; the window then picks up what it has to
+//! act on, such as a new title, with `EditorCore::take_update`.
+//!
+//! Redraws queued up behind each other are applied under one lock, but
+//! only for `APPLY_BUDGET` at a time: a plugin flooding the channel would
+//! otherwise keep the window from ever taking the lock to draw.
+//!
+//! Timers read the core's `Clock`, so a headless run or a test can drive
+//! blinks and fades with a `ManualClock` instead of waiting on them.
+
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::bridge::events::RedrawEvent;
+use crate::event::{EventSender, NeovimEvent, UserEvent};
+
+use super::{Clock, EditorState, SystemClock, Tick};
+
+/// Longest the core holds the state's lock for queued redraws.
+const APPLY_BUDGET: Duration = Duration::from_millis(5);
+
+/// Most redraw batches applied under one lock.
+const APPLY_MAX_BATCHES: usize = 64;
+
+/// How long the core stands back after using up its budget, so the window
+/// gets the lock.
+const YIELD_TIME: Duration = Duration::from_millis(1);
+
+/// What the window has to do about the redraws applied since it last
+/// looked, beyond drawing a frame.
+#[derive(Debug, Default, PartialEq)]
+pub struct EditorUpdate {
+    /// The title Neovim set last.
+    pub title: Option<String>,
+    /// The icon text (`'iconstring'`) Neovim set last.
+    pub icon: Option<String>,
+    /// Options, in the order Neovim set them.
+    pub options: Vec<(String, nvim_rs::Value)>,
+    /// Whether the last of `mouse_on`/`mouse_off` turned the mouse on.
+    pub mouse_enabled: Option<bool>,
+    /// The default colors or a highlight group changed.
+    pub colors_changed: bool,
+    /// The tabline changed. It doesn't say which tabs are modified, so
+    /// that needs asking.
+    pub tabline_changed: bool,
+}
+
+impl EditorUpdate {
+    fn record(&mut self, event: RedrawEvent) {
+        match event {
+            RedrawEvent::DefaultColorsSet { .. } | RedrawEvent::HlGroupSet { .. } => {
+                self.colors_changed = true;
+            }
+            RedrawEvent::SetTitle { title } => self.title = Some(title),
+            RedrawEvent::SetIcon { icon } => self.icon = Some(icon),
+            RedrawEvent::OptionSet { name, value } => self.options.push((name, value)),
+            RedrawEvent::MouseOn => self.mouse_enabled = Some(true),
+            RedrawEvent::MouseOff => self.mouse_enabled = Some(false),
+            RedrawEvent::TablineUpdate { .. } => self.tabline_changed = true,
+            _ => {}
+        }
+    }
+}
+
+/// How a run of queued redraws ended.
+#[derive(Default)]
+struct Burst {
+    /// The window needs waking for them.
+    wake: bool,
+    /// The event after them, which isn't a redraw.
+    next: Option<UserEvent>,
+    /// The budget ran out with redraws possibly still queued.
+    exhausted: bool,
+}
+
+/// The editor state, shared between the core's thread and the window.
+/// Clones share the same state.
+#[derive(Clone)]
+pub struct EditorCore {
+    state: Arc<Mutex<EditorState>>,
+    /// Taken by the window; `Some` while a wakeup is on its way to it.
+    update: Arc<Mutex<Option<EditorUpdate>>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl EditorCore {
+    /// A core whose timers run on the system clock.
+    pub fn new(state: EditorState) -> Self {
+        Self::with_clock(state, SystemClock)
+    }
+
+    /// A core whose timers read `clock`, such as a `ManualClock` for
+    /// stepping through blinks and fades one at a time.
+    pub fn with_clock(state: EditorState, clock: impl Clock + 'static) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            update: Arc::new(Mutex::new(None)),
+            clock: Arc::new(clock),
+        }
+    }
+
+    /// The core's time (ms).
+    pub fn now(&self) -> u64 {
+        self.clock.now()
+    }
+
+    /// Advances the state's timers to the clock's time; see
+    /// `EditorState::tick`.
+    pub fn tick(&self) -> Tick {
+        let now = self.now();
+        self.state().tick(now)
+    }
+
+    /// Locks the state. Guards are meant to be short-lived: the core can't
+    /// apply redraws while one is held.
+    pub fn state(&self) -> MutexGuard<'_, EditorState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Starts the core's thread. Events sent to the returned sender reach
+    /// `events` with the redraws applied and replaced by
+    /// `NeovimEvent::EditorUpdated`. The thread ends with the last sender.
+    pub fn spawn(&self, events: impl Into<EventSender>) -> EventSender {
+        let events = events.into();
+        let (sender, receiver) = mpsc::channel();
+        let core = self.clone();
+        let forward = events.clone();
+        let spawned = std::thread::Builder::new()
+            .name("editor-core".to_string())
+            .spawn(move || core.run(receiver, forward));
+        if let Err(e) = spawned {
+            // Not worth failing over: the window applies redraws itself,
+            // just on the event loop.
+            log::error!("Failed to start the editor core: {}", e);
+            return events;
+        }
+        sender.into()
+    }
+
+    fn run(&self, receiver: mpsc::Receiver<UserEvent>, events: EventSender) {
+        let mut next = None;
+        loop {
+            let event = match next.take() {
+                Some(event) => event,
+                None => match receiver.recv() {
+                    Ok(event) => event,
+                    Err(_) => break,
+                },
+            };
+            let event = match event {
+                UserEvent::Neovim(NeovimEvent::Redraw(redraw)) => {
+                    let burst = self.apply_burst(redraw, &receiver);
+                    next = burst.next;
+                    let woken = !burst.wake
+                        || events
+                            .send_event(UserEvent::Neovim(NeovimEvent::EditorUpdated))
+                            .is_ok();
+                    if !woken {
+                        break;
+                    }
+                    if burst.exhausted {
+                        // The rest waits until the window has had its turn.
+                        std::thread::sleep(YIELD_TIME);
+                    }
+                    continue;
+                }
+                event => event,
+            };
+            if events.send_event(event).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Applies `first` and the redraws queued behind it, until the queue
+    /// runs dry, another event comes up or the budget is used up.
+    fn apply_burst(&self, first: Vec<RedrawEvent>, receiver: &mpsc::Receiver<UserEvent>) -> Burst {
+        let started = Instant::now();
+        let mut state = self.state();
+        let mut wake = self.apply_locked(&mut state, first);
+        let mut burst = Burst::default();
+        for _ in 1..APPLY_MAX_BATCHES {
+            if started.elapsed() >= APPLY_BUDGET {
+                break;
+            }
+            match receiver.try_recv() {
+                Ok(UserEvent::Neovim(NeovimEvent::Redraw(redraw))) => {
+                    wake |= self.apply_locked(&mut state, redraw);
+                }
+                Ok(event) => {
+                    burst.next = Some(event);
+                    burst.wake = wake;
+                    return burst;
+                }
+                Err(_) => {
+                    burst.wake = wake;
+                    return burst;
+                }
+            }
+        }
+        burst.wake = wake;
+        burst.exhausted = true;
+        burst
+    }
+
+    /// Applies `events` to the state. Returns whether the window needs
+    /// waking: true unless it already has an update to take.
+    pub fn apply(&self, events: Vec<RedrawEvent>) -> bool {
+        self.apply_locked(&mut self.state(), events)
+    }
+
+    fn apply_locked(&self, state: &mut EditorState, events: Vec<RedrawEvent>) -> bool {
+        let mut update = self.update.lock().unwrap_or_else(|e| e.into_inner());
+        let wake = update.is_none();
+        let update = update.get_or_insert_with(EditorUpdate::default);
+        for event in events {
+            state.handle_redraw_event(&event);
+            update.record(event);
+        }
+        wake
+    }
+
+    /// What changed since the last call, or None if nothing did.
+    pub fn take_update(&self) -> Option<EditorUpdate> {
+        self.update.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redraw(events: Vec<RedrawEvent>) -> UserEvent {
+        UserEvent::Neovim(NeovimEvent::Redraw(events))
+    }
+
+    #[test]
+    fn test_redraws_wake_the_window_once() {
+        let core = EditorCore::new(EditorState::new(80, 24));
+        let (window, woken) = mpsc::channel();
+        let sender = core.spawn(window);
+
+        let title = |title: &str| RedrawEvent::SetTitle {
+            title: title.to_string(),
+        };
+        sender
+            .send_event(redraw(vec![
+                title("one"),
+                RedrawEvent::SetIcon {
+                    icon: "nvim".to_string(),
+                },
+            ]))
+            .unwrap();
+        sender
+            .send_event(redraw(vec![RedrawEvent::MouseOn, title("two")]))
+            .unwrap();
+        sender
+            .send_event(UserEvent::Neovim(NeovimEvent::QuitCancelled))
+            .unwrap();
+        drop(sender);
+
+        let events: Vec<UserEvent> = woken.iter().collect();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                UserEvent::Neovim(NeovimEvent::EditorUpdated),
+                UserEvent::Neovim(NeovimEvent::QuitCancelled)
+            ]
+        ));
+        let update = core.take_update().unwrap();
+        assert_eq!(update.title.as_deref(), Some("two"));
+        assert_eq!(update.icon.as_deref(), Some("nvim"));
+        assert_eq!(update.mouse_enabled, Some(true));
+        assert_eq!(core.take_update(), None);
+
+        // Taking the update lets the next redraw wake the window again.
+        assert!(core.apply(vec![RedrawEvent::Flush]));
+        assert!(!core.apply(vec![RedrawEvent::Flush]));
+    }
+
+    #[test]
+    fn test_bursts_stop_at_the_budget() {
+        let core = EditorCore::new(EditorState::new(80, 24));
+        let (sender, receiver) = mpsc::channel();
+        let flush = || redraw(vec![RedrawEvent::Flush]);
+        for _ in 0..APPLY_MAX_BATCHES + 10 {
+            sender.send(flush()).unwrap();
+        }
+        let burst = core.apply_burst(vec![RedrawEvent::Flush], &receiver);
+        assert!(burst.wake && burst.exhausted && burst.next.is_none());
+        // The first batch and all but one queued one went in.
+        assert_eq!(receiver.try_iter().count(), 11);
+
+        sender.send(flush()).unwrap();
+        sender
+            .send(UserEvent::Neovim(NeovimEvent::QuitCancelled))
+            .unwrap();
+        sender.send(flush()).unwrap();
+        let burst = core.apply_burst(vec![RedrawEvent::Flush], &receiver);
+        assert!(!burst.wake, "the window hasn't taken the last update");
+        assert!(!burst.exhausted);
+        assert!(matches!(
+            burst.next,
+            Some(UserEvent::Neovim(NeovimEvent::QuitCancelled))
+        ));
+        assert_eq!(receiver.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_tick_runs_on_the_injected_clock() {
+        use crate::editor::{ManualClock, ModeInfo};
+
+        let clock = ManualClock::new(1000);
+        let core = EditorCore::with_clock(EditorState::new(80, 24), clock.clone());
+        core.state().splash = None;
+        core.state().mode_info_set(vec![ModeInfo {
+            blink_wait: 0,
+            blink_on: 400,
+            blink_off: 250,
+            ..Default::default()
+        }]);
+        core.state().reset_blink();
+        assert_eq!(
+            core.tick(),
+            Tick {
+                changed: false,
+                deadline: Some(1400)
+            }
+        );
+
+        clock.advance(399);
+        assert!(!core.tick().changed);
+        clock.advance(1);
+        let tick = core.tick();
+        assert!(tick.changed && !core.state().cursor.blink_visible);
+        assert_eq!(tick.deadline, Some(1650));
+
+        clock.set(1650);
+        assert!(core.tick().changed);
+        assert!(core.state().cursor.blink_visible);
+    }
+
+    #[test]
+    fn test_apply_updates_the_state() {
+        let core = EditorCore::new(EditorState::new(80, 24));
+        core.apply(vec![RedrawEvent::GridResize {
+            grid: 1,
+            width: 100,
+            height: 30,
+        }]);
+        assert_eq!(core.state().grid(1).map(|grid| grid.width()), Some(100));
+    }
+}
@@ -0,0 +1,167 @@
+use std::path::PathBuf;
+
+use crate::renderer::overlay::ListState;
+
+/// What to do after Neovim crashed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashChoice {
+    Restart,
+    Quit,
+}
+
+impl CrashChoice {
+    pub const ALL: [CrashChoice; 2] = [CrashChoice::Restart, CrashChoice::Quit];
+
+    /// The letter that picks the choice in the prompt.
+    pub fn key(self) -> &'static str {
+        match self {
+            CrashChoice::Restart => "r",
+            CrashChoice::Quit => "q",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CrashChoice::Restart => "Restart Neovim",
+            CrashChoice::Quit => "Quit",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|choice| choice.key() == key)
+    }
+}
+
+/// Modal shown over the last frame after the embedded Neovim crashed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashPrompt {
+    /// How it ended, such as `signal: 11 (SIGSEGV)`.
+    pub status: String,
+    list: ListState,
+}
+
+impl CrashPrompt {
+    pub fn new(status: String) -> Self {
+        Self {
+            status,
+            // Safety: ALL is not empty
+            list: ListState::new(CrashChoice::ALL.len()).unwrap(),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.list.selected()
+    }
+
+    pub fn select_previous(&mut self) {
+        self.list.select_previous();
+    }
+
+    pub fn select_next(&mut self) {
+        self.list.select_next();
+    }
+
+    /// The choice numbered `index` (0-based), if there is one.
+    pub fn pick(&mut self, index: usize) -> Option<CrashChoice> {
+        self.list.pick(index).then(|| self.current())
+    }
+
+    pub fn current(&self) -> CrashChoice {
+        CrashChoice::ALL[self.list.selected()]
+    }
+}
+
+/// What to do with the report of a crash of gui.nvim itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportChoice {
+    Open,
+    Dismiss,
+}
+
+impl ReportChoice {
+    pub const ALL: [ReportChoice; 2] = [ReportChoice::Open, ReportChoice::Dismiss];
+
+    /// The letter that picks the choice in the prompt.
+    pub fn key(self) -> &'static str {
+        match self {
+            ReportChoice::Open => "o",
+            ReportChoice::Dismiss => "d",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReportChoice::Open => "Open the report",
+            ReportChoice::Dismiss => "Dismiss",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|choice| choice.key() == key)
+    }
+}
+
+/// Modal shown on the first start after gui.nvim crashed, pointing at the
+/// report it left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportPrompt {
+    pub path: PathBuf,
+    list: ListState,
+}
+
+impl ReportPrompt {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            // Safety: ALL is not empty
+            list: ListState::new(ReportChoice::ALL.len()).unwrap(),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.list.selected()
+    }
+
+    pub fn select_previous(&mut self) {
+        self.list.select_previous();
+    }
+
+    pub fn select_next(&mut self) {
+        self.list.select_next();
+    }
+
+    /// The choice numbered `index` (0-based), if there is one.
+    pub fn pick(&mut self, index: usize) -> Option<ReportChoice> {
+        self.list.pick(index).then(|| self.current())
+    }
+
+    pub fn current(&self) -> ReportChoice {
+        ReportChoice::ALL[self.list.selected()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_prompt() {
+        let mut prompt = CrashPrompt::new("signal: 11".to_string());
+        assert_eq!(prompt.current(), CrashChoice::Restart);
+        prompt.select_next();
+        assert_eq!(prompt.current(), CrashChoice::Quit);
+        assert_eq!(prompt.pick(0), Some(CrashChoice::Restart));
+        assert_eq!(prompt.pick(2), None);
+        assert_eq!(CrashChoice::from_key("q"), Some(CrashChoice::Quit));
+    }
+
+    #[test]
+    fn test_report_prompt() {
+        let mut prompt = ReportPrompt::new(PathBuf::from("crash.txt"));
+        assert_eq!(prompt.current(), ReportChoice::Open);
+        prompt.select_next();
+        assert_eq!(prompt.current(), ReportChoice::Dismiss);
+        assert_eq!(prompt.pick(0), Some(ReportChoice::Open));
+        assert_eq!(ReportChoice::from_key("d"), Some(ReportChoice::Dismiss));
+    }
+}
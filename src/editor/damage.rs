@@ -0,0 +1,66 @@
+use std::ops::Range;
+
+/// What changed on screen since the last frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Damage {
+    /// Nothing; no frame is needed.
+    #[default]
+    None,
+    /// Only main grid rows `start..end` (which may include the cursor).
+    Rows(Range<usize>),
+    /// Anything else: overlays, layout, colors. The whole window is redrawn.
+    Full,
+}
+
+/// A `grid_scroll` across the whole main grid: the contents of `rows`
+/// moved up by `delta` rows, or down when it is negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridScrolled {
+    /// Counts scrolls over the session, so a frame can tell those it
+    /// hasn't seen.
+    pub seq: u64,
+    pub rows: Range<usize>,
+    pub delta: i64,
+}
+
+impl Damage {
+    /// Widens the damage to also cover `rows`.
+    pub fn add_rows(&mut self, rows: Range<usize>) {
+        if rows.is_empty() {
+            return;
+        }
+        *self = match std::mem::take(self) {
+            Damage::None => Damage::Rows(rows),
+            Damage::Rows(r) => Damage::Rows(r.start.min(rows.start)..r.end.max(rows.end)),
+            Damage::Full => Damage::Full,
+        };
+    }
+
+    pub fn add_row(&mut self, row: usize) {
+        self.add_rows(row..row + 1);
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Damage::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_rows_merges_into_one_range() {
+        let mut damage = Damage::None;
+        damage.add_rows(3..3);
+        assert!(damage.is_none());
+
+        damage.add_row(5);
+        damage.add_rows(1..3);
+        assert_eq!(damage, Damage::Rows(1..6));
+
+        let mut full = Damage::Full;
+        full.add_row(2);
+        assert_eq!(full, Damage::Full);
+    }
+}
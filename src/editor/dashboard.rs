@@ -0,0 +1,91 @@
+use crate::recent::RecentFile;
+use crate::renderer::overlay::ListState;
+
+/// Startup screen listing recently edited files, shown over an empty
+/// buffer until a file is picked or the dashboard is dismissed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dashboard {
+    pub entries: Vec<RecentFile>,
+    list: ListState,
+}
+
+impl Dashboard {
+    /// Returns None when there is nothing to list.
+    pub fn new(entries: Vec<RecentFile>) -> Option<Self> {
+        let list = ListState::new(entries.len())?;
+        Some(Self { entries, list })
+    }
+
+    pub fn selected(&self) -> usize {
+        self.list.selected()
+    }
+
+    pub fn select_previous(&mut self) {
+        self.list.select_previous();
+    }
+
+    pub fn select_next(&mut self) {
+        self.list.select_next();
+    }
+
+    /// Selects entry `index` if it exists.
+    pub fn pick(&mut self, index: usize) -> Option<&RecentFile> {
+        self.list.pick(index);
+        self.entries.get(index)
+    }
+
+    pub fn current(&self) -> &RecentFile {
+        &self.entries[self.list.selected()]
+    }
+
+    /// Display text for an entry: the path with the home directory shown
+    /// as `~`, and the line the cursor was on.
+    pub fn label(entry: &RecentFile) -> String {
+        let home = dirs::home_dir().map(|h| h.to_string_lossy().into_owned());
+        let path = match home.as_deref().and_then(|h| entry.path.strip_prefix(h)) {
+            Some(rest) if rest.starts_with('/') => format!("~{}", rest),
+            _ => entry.path.clone(),
+        };
+        format!("{}:{}", path, entry.line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> RecentFile {
+        RecentFile {
+            path: path.to_string(),
+            line: 4,
+            col: 1,
+        }
+    }
+
+    #[test]
+    fn test_empty_list_has_no_dashboard() {
+        assert!(Dashboard::new(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_navigation_wraps() {
+        let mut dashboard = Dashboard::new(vec![entry("/a"), entry("/b"), entry("/c")]).unwrap();
+        dashboard.select_previous();
+        assert_eq!(dashboard.current().path, "/c");
+        dashboard.select_next();
+        assert_eq!(dashboard.current().path, "/a");
+
+        assert_eq!(dashboard.pick(1).map(|e| e.path.as_str()), Some("/b"));
+        assert!(dashboard.pick(7).is_none());
+        assert_eq!(dashboard.selected(), 1);
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(Dashboard::label(&entry("/srv/x.rs")), "/srv/x.rs:4");
+        if let Some(home) = dirs::home_dir() {
+            let path = format!("{}/notes.md", home.display());
+            assert_eq!(Dashboard::label(&entry(&path)), "~/notes.md:4");
+        }
+    }
+}
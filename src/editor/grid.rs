@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use super::cell::Cell;
 
 /// A 2D grid of cells representing a Neovim window or the main screen.
@@ -100,6 +102,68 @@ impl Grid {
         self.cells.chunks(self.width)
     }
 
+    /// Returns the text of `cols` in `row` as it appears on screen.
+    ///
+    /// Wide characters are emitted once: the spacer cell to their right is
+    /// skipped, and a range starting on a spacer pulls in the character it
+    /// belongs to. Trailing spaces are trimmed since Neovim pads every row to
+    /// the grid width. Returns None if `row` is out of bounds.
+    pub fn row_text_range(&self, row: usize, cols: Range<usize>) -> Option<String> {
+        let cells = self.row(row)?;
+        let end = cols.end.min(self.width);
+        let mut start = cols.start.min(end);
+        if start > 0 && start < end && is_spacer(&cells[start]) {
+            start -= 1;
+        }
+
+        let mut text = String::with_capacity(end - start);
+        for cell in &cells[start..end] {
+            if !is_spacer(cell) {
+                text.push_str(&cell.text);
+            }
+        }
+        text.truncate(text.trim_end_matches(' ').len());
+        Some(text)
+    }
+
+    /// Returns the text of a whole row. See [`Grid::row_text_range`].
+    pub fn row_text(&self, row: usize) -> Option<String> {
+        self.row_text_range(row, 0..self.width)
+    }
+
+    /// Returns the text of every row, top to bottom.
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.height)
+            .filter_map(|row| self.row_text(row))
+            .collect()
+    }
+
+    /// Returns the text of a linear selection between two (row, col)
+    /// positions, both inclusive, with rows joined by newlines. The ends may
+    /// be given in either order.
+    pub fn text_between(&self, start: (usize, usize), end: (usize, usize)) -> String {
+        let (start, end) = if start <= end {
+            (start, end)
+        } else {
+            (end, start)
+        };
+        let last_row = end.0.min(self.height.saturating_sub(1));
+
+        let mut lines = Vec::new();
+        for row in start.0..=last_row {
+            let first_col = if row == start.0 { start.1 } else { 0 };
+            let end_col = if row == end.0 {
+                end.1.saturating_add(1)
+            } else {
+                self.width
+            };
+            if let Some(text) = self.row_text_range(row, first_col..end_col) {
+                lines.push(text);
+            }
+        }
+        lines.join("\n")
+    }
+
     /// Clears all cells to their default state.
     pub fn clear(&mut self) {
         for cell in &mut self.cells {
@@ -162,7 +226,9 @@ impl Grid {
                     break;
                 }
 
-                let cell = &mut self.cells[row * self.width + col];
+                let index = row * self.width + col;
+                let cell = &mut self.cells[index];
+                let was_spacer = cell.is_wide_spacer();
                 if is_wide_spacer {
                     cell.text = compact_str::CompactString::default();
                     cell.set_wide_spacer(true);
@@ -172,6 +238,12 @@ impl Grid {
                     cell.flags = super::cell::CellFlags::empty();
                 }
                 cell.highlight_id = hl_id;
+                // The cell on the left is wide for as long as this one is
+                // its right half, which may come in a later grid_line.
+                if col > 0 && (is_wide_spacer || was_spacer) {
+                    let left = &mut self.cells[index - 1];
+                    left.set_wide(is_wide_spacer && !left.is_wide_spacer());
+                }
                 col += 1;
             }
         }
@@ -227,6 +299,12 @@ impl Grid {
     }
 }
 
+/// Right half of a wide character. Spacers written by grid_line carry the
+/// flag; the empty-text check also covers cells Neovim sent as `""`.
+fn is_spacer(cell: &Cell) -> bool {
+    cell.is_wide_spacer() || cell.text.is_empty()
+}
+
 impl std::ops::Index<(usize, usize)> for Grid {
     type Output = Cell;
 
@@ -466,4 +544,145 @@ mod tests {
         assert_eq!(grid[(0, 0)].text, " ");
         assert!(grid[(0, 0)].is_empty());
     }
+
+    #[test]
+    fn test_spacers_mark_wide_cells() {
+        let mut grid = Grid::new(1, 10, 2);
+        grid.update_line(0, 0, &[("あ", Some(0), 1), ("", None, 1), ("a", None, 1)]);
+        assert!(grid[(0, 0)].is_wide());
+        assert!(grid[(0, 1)].is_wide_spacer());
+        assert!(!grid[(0, 2)].is_wide());
+
+        // The halves of a wide char can come in separate lines.
+        grid.update_line(0, 3, &[("い", Some(0), 1)]);
+        assert!(!grid[(0, 3)].is_wide());
+        grid.update_line(0, 4, &[("", Some(0), 1)]);
+        assert!(grid[(0, 3)].is_wide());
+
+        // Overwriting the right half leaves a narrow char.
+        grid.update_line(0, 1, &[("b", Some(0), 1)]);
+        assert!(!grid[(0, 0)].is_wide());
+        assert!(!grid[(0, 1)].is_wide_spacer());
+    }
+
+    fn grid_with_line(width: usize, cells: &[(&str, Option<u64>, usize)]) -> Grid {
+        let mut grid = Grid::new(1, width, 3);
+        grid.update_line(0, 0, cells);
+        grid
+    }
+
+    #[test]
+    fn test_row_text_trims_padding() {
+        let grid = grid_with_line(10, &[("a", Some(0), 1), (" ", None, 2), ("b", None, 1)]);
+
+        assert_eq!(grid.row_text(0).unwrap(), "a  b");
+        assert_eq!(grid.row_text(1).unwrap(), "");
+        assert_eq!(grid.row_text(3), None);
+    }
+
+    #[test]
+    fn test_row_text_keeps_leading_whitespace() {
+        let grid = grid_with_line(8, &[(" ", Some(0), 4), ("fn", None, 1)]);
+        assert_eq!(grid.row_text(0).unwrap(), "    fn");
+    }
+
+    #[test]
+    fn test_row_text_joins_wide_chars() {
+        // "日本" takes four cells: char, spacer, char, spacer
+        let grid = grid_with_line(
+            6,
+            &[
+                ("日", Some(0), 1),
+                ("", None, 1),
+                ("本", None, 1),
+                ("", None, 1),
+                ("!", None, 1),
+            ],
+        );
+
+        assert_eq!(grid.row_text(0).unwrap(), "日本!");
+    }
+
+    #[test]
+    fn test_row_text_double_width_emoji() {
+        // Emoji with variation selectors and ZWJ sequences arrive as a
+        // single cell followed by a spacer.
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let grid = grid_with_line(
+            8,
+            &[
+                ("\u{2764}\u{FE0F}", Some(0), 1),
+                ("", None, 1),
+                (family, None, 1),
+                ("", None, 1),
+                ("x", None, 1),
+            ],
+        );
+
+        assert_eq!(
+            grid.row_text(0).unwrap(),
+            format!("\u{2764}\u{FE0F}{}x", family)
+        );
+    }
+
+    #[test]
+    fn test_row_text_range_starting_on_spacer() {
+        let grid = grid_with_line(
+            6,
+            &[
+                ("a", Some(0), 1),
+                ("日", None, 1),
+                ("", None, 1),
+                ("b", None, 1),
+            ],
+        );
+
+        // Column 2 is the right half of "日"
+        assert_eq!(grid.row_text_range(0, 2..4).unwrap(), "日b");
+        // Ending between the halves still yields the whole character
+        assert_eq!(grid.row_text_range(0, 0..2).unwrap(), "a日");
+        assert_eq!(grid.row_text_range(0, 3..3).unwrap(), "");
+        assert_eq!(grid.row_text_range(0, 4..100).unwrap(), "");
+    }
+
+    #[test]
+    fn test_row_text_combining_chars() {
+        let grid = grid_with_line(4, &[("e\u{301}", Some(0), 1), ("x", None, 1)]);
+        assert_eq!(grid.row_text(0).unwrap(), "e\u{301}x");
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut grid = Grid::new(1, 4, 3);
+        grid.update_line(0, 0, &[("a", Some(0), 1)]);
+        grid.update_line(2, 0, &[("c", Some(0), 1), ("", None, 1)]);
+
+        assert_eq!(grid.lines(), vec!["a", "", "c"]);
+    }
+
+    #[test]
+    fn test_text_between() {
+        let mut grid = Grid::new(1, 6, 3);
+        grid.update_line(
+            0,
+            0,
+            &[
+                ("h", Some(0), 1),
+                ("e", None, 1),
+                ("l", None, 2),
+                ("o", None, 1),
+            ],
+        );
+        grid.update_line(1, 0, &[("w", Some(0), 1), ("o", None, 1), ("r", None, 1)]);
+        grid.update_line(2, 0, &[("字", Some(0), 1), ("", None, 1), ("z", None, 1)]);
+
+        assert_eq!(grid.text_between((0, 3), (1, 1)), "lo\nwo");
+        // Reversed ends select the same text
+        assert_eq!(grid.text_between((1, 1), (0, 3)), "lo\nwo");
+        // Ending on the left half of a wide char includes it
+        assert_eq!(grid.text_between((1, 2), (2, 0)), "r\n字");
+        assert_eq!(grid.text_between((2, 1), (2, 2)), "字z");
+        // Rows past the end are ignored
+        assert_eq!(grid.text_between((2, 2), (9, 0)), "z");
+    }
 }
@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use bitflags::bitflags;
 
+use super::palette::xterm_256;
+use super::special::SpecialColors;
+
 /// RGBA color represented as a 32-bit value.
 /// Format: 0xRRGGBBAA (alpha is always 0xFF for solid colors).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -20,6 +23,13 @@ impl Color {
         let b = (rgb & 0xFF) as u8;
         Self::from_rgb(r, g, b)
     }
+
+    /// Whether light text reads better on this color, going by its
+    /// perceived brightness.
+    pub fn is_dark(self) -> bool {
+        let [r, g, b, _] = self.0.to_be_bytes();
+        299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b) < 128_000
+    }
 }
 
 bitflags! {
@@ -84,6 +94,14 @@ pub struct HighlightAttributes {
     pub blend: u8,
     /// URL for clickable hyperlinks.
     pub url: Option<String>,
+    /// 256-color foreground index (`ctermfg`), for sessions without
+    /// 'termguicolors'.
+    pub cterm_foreground: Option<u8>,
+    /// 256-color background index (`ctermbg`).
+    pub cterm_background: Option<u8>,
+    /// Highlight groups the attributes were combined from, lowest priority
+    /// first (ext_hlstate).
+    pub groups: Vec<String>,
 }
 
 impl HighlightAttributes {
@@ -160,29 +178,180 @@ impl Default for DefaultColors {
     }
 }
 
+impl DefaultColors {
+    /// The defaults for a system in light mode.
+    pub fn light() -> Self {
+        Self {
+            foreground: Color::from_rgb(0x2E, 0x2E, 0x2E), // Dark gray
+            background: Color::from_rgb(0xF5, 0xF5, 0xF5), // Off-white
+            special: Color::from_rgb(0x6E, 0x6E, 0x6E),    // Gray
+        }
+    }
+}
+
+/// Highlight count below which stale entries are left alone.
+const MIN_COMPACT_LEN: usize = 1024;
+
 /// Map of highlight IDs to their attributes.
 ///
 /// Neovim sends `hl_attr_define` events that define highlights by ID.
 /// ID 0 always uses default colors with no styles.
-#[derive(Debug, Clone, Default)]
+///
+/// Neovim defines each ID once and never sends it again, so entries can't
+/// simply be evicted. When its table is rebuilt (`:hi clear`, colorscheme
+/// changes), IDs are handed out again from the start with new attributes;
+/// everything defined before the first such redefinition is then stale,
+/// and can go once nothing on screen uses it (see `compact`).
+#[derive(Debug, Clone)]
 pub struct HighlightMap {
+    /// Attributes as Neovim defined them.
     attributes: HashMap<u64, HighlightAttributes>,
+    /// Whether 'termguicolors' is off, so cterm colors fill in for missing
+    /// RGB ones.
+    cterm_colors: bool,
+    /// `attributes` with cterm colors filled in; only kept with
+    /// `cterm_colors`.
+    resolved: HashMap<u64, HighlightAttributes>,
+    /// Builtin UI group names (Pmenu, PmenuSel, ...) from hl_group_set.
+    groups: HashMap<String, u64>,
+    /// Underline colors for highlights without a special color.
+    special_colors: SpecialColors,
+    /// IDs from before Neovim last rebuilt its table, not defined since.
+    stale: HashSet<u64>,
+    /// `compact` only runs once the map has grown to this size.
+    compact_at: usize,
+    /// Entries dropped by `compact` over the session.
+    compacted: u64,
+    /// Bumped whenever `get` may return something else for an ID.
+    generation: u64,
     pub defaults: DefaultColors,
 }
 
+impl Default for HighlightMap {
+    fn default() -> Self {
+        Self {
+            attributes: HashMap::new(),
+            cterm_colors: false,
+            resolved: HashMap::new(),
+            groups: HashMap::new(),
+            special_colors: SpecialColors::default(),
+            stale: HashSet::new(),
+            compact_at: MIN_COMPACT_LEN,
+            compacted: 0,
+            generation: 0,
+            defaults: DefaultColors::default(),
+        }
+    }
+}
+
+/// Highlight table size, reported by `:GuiStats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HighlightStats {
+    pub defined: usize,
+    pub stale: usize,
+    pub compacted: u64,
+}
+
 impl HighlightMap {
     /// Creates a new highlight map with default colors.
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Defines highlight attributes for the given ID.
-    pub fn define(&mut self, id: u64, attrs: HighlightAttributes) {
+    /// Defines highlight attributes for the given ID. Returns true if the
+    /// ID already had different attributes, meaning Neovim rebuilt its
+    /// table and cells drawn with the ID are out of date.
+    pub fn define(&mut self, id: u64, mut attrs: HighlightAttributes) -> bool {
         if id == 0 {
             // ID 0 is reserved for default, don't store it
-            return;
+            return false;
+        }
+        if attrs.special.is_none() && attrs.underline_style() != UnderlineStyle::None {
+            attrs.special = self.special_colors.lookup(&attrs.groups);
+        }
+        let redefined =
+            !self.stale.remove(&id) && self.attributes.get(&id).is_some_and(|old| *old != attrs);
+        if redefined {
+            self.stale = self.attributes.keys().copied().collect();
+            self.stale.remove(&id);
+        }
+        if self.attributes.get(&id) != Some(&attrs) {
+            self.generation += 1;
+        }
+        if self.cterm_colors {
+            self.resolved.insert(id, with_cterm_colors(&attrs));
         }
         self.attributes.insert(id, attrs);
+        redefined
+    }
+
+    /// Number of defined IDs.
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    /// Changes whenever an attribute does, so whatever was drawn with the
+    /// same generation is still up to date.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn stats(&self) -> HighlightStats {
+        HighlightStats {
+            defined: self.attributes.len(),
+            stale: self.stale.len(),
+            compacted: self.compacted,
+        }
+    }
+
+    /// Whether `compact` has anything to do: there are stale entries and
+    /// the map has doubled since the last compaction.
+    pub fn needs_compaction(&self) -> bool {
+        !self.stale.is_empty() && self.attributes.len() >= self.compact_at
+    }
+
+    /// Drops stale entries, except those in `in_use` (still drawn
+    /// somewhere) or named by a UI group. Returns how many were dropped.
+    pub fn compact(&mut self, in_use: &HashSet<u64>) -> usize {
+        let named: HashSet<u64> = self.groups.values().copied().collect();
+        let before = self.attributes.len();
+        self.stale.retain(|id| {
+            let keep = in_use.contains(id) || named.contains(id);
+            if !keep {
+                self.attributes.remove(id);
+                self.resolved.remove(id);
+            }
+            keep
+        });
+        let removed = before - self.attributes.len();
+        if removed > 0 {
+            self.generation += 1;
+        }
+        self.compacted += removed as u64;
+        self.compact_at = (self.attributes.len() * 2).max(MIN_COMPACT_LEN);
+        removed
+    }
+
+    /// Follows 'termguicolors': with it off, highlights without RGB colors
+    /// use their cterm colors from the xterm palette instead.
+    pub fn set_cterm_colors(&mut self, enabled: bool) {
+        if enabled == self.cterm_colors {
+            return;
+        }
+        self.cterm_colors = enabled;
+        self.generation += 1;
+        self.resolved = if enabled {
+            self.attributes
+                .iter()
+                .map(|(&id, attrs)| (id, with_cterm_colors(attrs)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
     }
 
     /// Gets the highlight attributes for the given ID.
@@ -195,15 +364,46 @@ impl HighlightMap {
             style: StyleFlags::empty(),
             blend: 0,
             url: None,
+            cterm_foreground: None,
+            cterm_background: None,
+            groups: Vec::new(),
         };
 
+        let attributes = if self.cterm_colors {
+            &self.resolved
+        } else {
+            &self.attributes
+        };
         if id == 0 {
             &DEFAULT_ATTRS
         } else {
-            self.attributes.get(&id).unwrap_or(&DEFAULT_ATTRS)
+            attributes.get(&id).unwrap_or(&DEFAULT_ATTRS)
         }
     }
 
+    /// IDs Neovim has defined, in ascending order.
+    pub fn ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.attributes.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Records which highlight ID a builtin UI group resolves to.
+    pub fn set_group(&mut self, name: &str, id: u64) {
+        self.groups.insert(name.to_string(), id);
+    }
+
+    /// Gets the attributes of a builtin UI group, if Neovim has reported it.
+    pub fn group(&self, name: &str) -> Option<&HighlightAttributes> {
+        self.groups.get(name).map(|&id| self.get(id))
+    }
+
+    /// Sets the fallbacks for undercurls and underlines without a special
+    /// color. Applies to highlights defined from now on.
+    pub fn set_special_colors(&mut self, special_colors: SpecialColors) {
+        self.special_colors = special_colors;
+    }
+
     /// Sets the default colors.
     pub fn set_defaults(&mut self, foreground: Color, background: Color, special: Color) {
         self.defaults = DefaultColors {
@@ -217,6 +417,17 @@ impl HighlightMap {
     #[allow(dead_code)]
     pub fn clear(&mut self) {
         self.attributes.clear();
+        self.resolved.clear();
+        self.stale.clear();
+        self.generation += 1;
+    }
+}
+
+fn with_cterm_colors(attrs: &HighlightAttributes) -> HighlightAttributes {
+    HighlightAttributes {
+        foreground: attrs.foreground.or(attrs.cterm_foreground.map(xterm_256)),
+        background: attrs.background.or(attrs.cterm_background.map(xterm_256)),
+        ..attrs.clone()
     }
 }
 
@@ -224,6 +435,14 @@ impl HighlightMap {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_color_is_dark() {
+        assert!(Color::from_u24(0x1e1e2e).is_dark());
+        assert!(Color::from_u24(0x0000ff).is_dark());
+        assert!(!Color::from_u24(0xeff1f5).is_dark());
+        assert!(!Color::from_u24(0xffff00).is_dark());
+    }
+
     #[test]
     fn test_underline_style_from_flags() {
         assert_eq!(
@@ -279,6 +498,48 @@ mod tests {
         assert_eq!(attrs_default.effective_bg(&defaults), defaults.background);
     }
 
+    #[test]
+    fn test_cterm_colors_fill_in_without_termguicolors() {
+        let mut map = HighlightMap::new();
+        let red = Color::from_rgb(255, 0, 0);
+        map.define(
+            1,
+            HighlightAttributes {
+                cterm_foreground: Some(196),
+                cterm_background: Some(16),
+                ..Default::default()
+            },
+        );
+        map.define(
+            2,
+            HighlightAttributes {
+                foreground: Some(Color::from_rgb(1, 2, 3)),
+                cterm_foreground: Some(196),
+                ..Default::default()
+            },
+        );
+        assert_eq!(map.get(1).foreground, None);
+
+        map.set_cterm_colors(true);
+        assert_eq!(map.get(1).foreground, Some(red));
+        assert_eq!(map.get(1).background, Some(Color::from_rgb(0, 0, 0)));
+        // RGB colors still win where they are set
+        assert_eq!(map.get(2).foreground, Some(Color::from_rgb(1, 2, 3)));
+
+        map.define(
+            3,
+            HighlightAttributes {
+                cterm_foreground: Some(196),
+                ..Default::default()
+            },
+        );
+        assert_eq!(map.get(3).foreground, Some(red));
+
+        map.set_cterm_colors(false);
+        assert_eq!(map.get(1).foreground, None);
+        assert_eq!(map.get(3).foreground, None);
+    }
+
     #[test]
     fn test_highlight_map_define_and_get() {
         let mut map = HighlightMap::new();
@@ -295,6 +556,70 @@ mod tests {
         assert!(retrieved.is_bold());
     }
 
+    #[test]
+    fn test_highlight_map_redefinition_marks_stale() {
+        let mut map = HighlightMap::new();
+        let red = HighlightAttributes {
+            foreground: Some(Color::from_u24(0xFF0000)),
+            ..Default::default()
+        };
+        let blue = HighlightAttributes {
+            foreground: Some(Color::from_u24(0x0000FF)),
+            ..Default::default()
+        };
+        assert!(!map.define(1, red.clone()));
+        assert!(!map.define(2, red.clone()));
+        assert!(!map.define(3, red.clone()));
+        // Same attributes again, as on UI reattach, aren't a rebuild.
+        assert!(!map.define(1, red.clone()));
+        assert_eq!(map.stats().stale, 0);
+
+        assert!(map.define(1, blue.clone()));
+        assert_eq!(map.stats().stale, 2);
+        // IDs defined again after the rebuild are current.
+        assert!(!map.define(2, blue));
+        assert_eq!(map.stats().stale, 1);
+    }
+
+    #[test]
+    fn test_highlight_map_generation() {
+        let mut map = HighlightMap::new();
+        let red = HighlightAttributes {
+            foreground: Some(Color::from_u24(0xFF0000)),
+            ..Default::default()
+        };
+        map.define(1, red.clone());
+        let generation = map.generation();
+        map.define(1, red);
+        assert_eq!(map.generation(), generation);
+
+        map.set_cterm_colors(true);
+        assert_ne!(map.generation(), generation);
+    }
+
+    #[test]
+    fn test_highlight_map_compact_keeps_ids_in_use() {
+        let mut map = HighlightMap::new();
+        let attrs = |rgb| HighlightAttributes {
+            foreground: Some(Color::from_u24(rgb)),
+            ..Default::default()
+        };
+        for id in 1..=MIN_COMPACT_LEN as u64 {
+            map.define(id, attrs(1));
+        }
+        map.set_group("Pmenu", 5);
+        assert!(!map.needs_compaction());
+
+        map.define(1, attrs(2));
+        assert!(map.needs_compaction());
+        let removed = map.compact(&HashSet::from([3]));
+        assert_eq!(removed, MIN_COMPACT_LEN - 3);
+        assert_eq!(map.ids(), vec![1, 3, 5]);
+        assert_eq!(map.get(3).foreground, Some(Color::from_u24(1)));
+        assert_eq!(map.stats().compacted, removed as u64);
+        assert!(!map.needs_compaction());
+    }
+
     #[test]
     fn test_highlight_map_id_zero() {
         let mut map = HighlightMap::new();
@@ -321,6 +646,50 @@ mod tests {
         assert!(!retrieved.is_bold());
     }
 
+    #[test]
+    fn test_highlight_map_groups() {
+        let mut map = HighlightMap::new();
+        map.define(
+            7,
+            HighlightAttributes {
+                background: Some(Color::from_rgb(40, 40, 40)),
+                ..Default::default()
+            },
+        );
+        map.set_group("Pmenu", 7);
+
+        let pmenu = map.group("Pmenu").unwrap();
+        assert_eq!(pmenu.background, Some(Color::from_rgb(40, 40, 40)));
+        assert!(map.group("PmenuSel").is_none());
+    }
+
+    #[test]
+    fn test_highlight_map_special_fallback() {
+        let mut map = HighlightMap::new();
+        let undercurl = |groups: &[&str], special| HighlightAttributes {
+            special,
+            style: StyleFlags::UNDERCURL,
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+            ..Default::default()
+        };
+        map.define(1, undercurl(&["DiagnosticUnderlineError"], None));
+        map.define(2, undercurl(&["DiagnosticUnderlineError"], Some(Color(1))));
+        map.define(3, undercurl(&["SpellBad"], None));
+        map.define(
+            4,
+            HighlightAttributes {
+                groups: vec!["DiagnosticError".to_string()],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(map.get(1).special, Some(Color::from_u24(0xf14c4c)));
+        assert_eq!(map.get(2).special, Some(Color(1)));
+        assert_eq!(map.get(3).special, None);
+        // Without an underline there is nothing to color.
+        assert_eq!(map.get(4).special, None);
+    }
+
     #[test]
     fn test_highlight_map_set_defaults() {
         let mut map = HighlightMap::new();
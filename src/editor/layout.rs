@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+
+use crate::bridge::events::FloatAnchor;
+
+/// Default z-index Neovim assigns to floating windows.
+pub const DEFAULT_FLOAT_ZINDEX: u64 = 50;
+
+/// Z-index of the message grid; it is drawn above normal windows and
+/// regular floats.
+pub const MESSAGE_ZINDEX: u64 = 200;
+
+/// How a grid is placed on screen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowKind {
+    /// A split window positioned by win_pos.
+    Normal {
+        /// Neovim's handle for the window, as Lua knows it.
+        win: u64,
+        row: usize,
+        col: usize,
+        width: usize,
+        height: usize,
+    },
+    /// A floating window positioned relative to another grid.
+    Floating {
+        anchor: FloatAnchor,
+        anchor_grid: u64,
+        anchor_row: f64,
+        anchor_col: f64,
+        focusable: bool,
+        zindex: u64,
+    },
+    /// The message grid positioned by msg_set_pos.
+    Message {
+        row: usize,
+        scrolled: bool,
+        sep_char: String,
+    },
+}
+
+/// Where the message grid covers the main grid, from `row` to the bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageArea {
+    pub grid: u64,
+    pub row: usize,
+    /// The messages scrolled up over other windows.
+    pub scrolled: bool,
+}
+
+impl MessageArea {
+    /// The row above scrolled messages, where Neovim's own compositor
+    /// draws a line of 'fillchars' msgsep.
+    pub fn separator_row(&self) -> Option<usize> {
+        (self.scrolled && self.row > 0).then(|| self.row - 1)
+    }
+}
+
+/// Placement of a single grid as reported by the win_* / msg_* events.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowPlacement {
+    pub grid: u64,
+    pub kind: WindowKind,
+    pub hidden: bool,
+    /// Order in which the window was last positioned. Breaks z-index ties so
+    /// the most recently placed window ends up on top.
+    seq: u64,
+}
+
+impl WindowPlacement {
+    pub fn zindex(&self) -> u64 {
+        match self.kind {
+            WindowKind::Normal { .. } => 0,
+            WindowKind::Floating { zindex, .. } => zindex,
+            WindowKind::Message { .. } => MESSAGE_ZINDEX,
+        }
+    }
+}
+
+/// Tracks where every non-main grid is placed and which ones are visible.
+///
+/// Grids without a placement (including the main grid) are not part of the
+/// layout. Hidden and closed windows drop out of `visible_in_z_order` so
+/// they don't linger on screen as ghost layers.
+#[derive(Debug, Default)]
+pub struct Layout {
+    windows: HashMap<u64, WindowPlacement>,
+    /// `gui.set_window_zoom` factors by grid, for split windows drawn at
+    /// another cell size than the rest.
+    zooms: HashMap<u64, f32>,
+    next_seq: u64,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn place(&mut self, grid: u64, kind: WindowKind) {
+        self.next_seq += 1;
+        self.windows.insert(
+            grid,
+            WindowPlacement {
+                grid,
+                kind,
+                hidden: false,
+                seq: self.next_seq,
+            },
+        );
+    }
+
+    pub fn win_pos(
+        &mut self,
+        grid: u64,
+        win: u64,
+        row: usize,
+        col: usize,
+        width: usize,
+        height: usize,
+    ) {
+        self.place(
+            grid,
+            WindowKind::Normal {
+                win,
+                row,
+                col,
+                width,
+                height,
+            },
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn win_float_pos(
+        &mut self,
+        grid: u64,
+        anchor: FloatAnchor,
+        anchor_grid: u64,
+        anchor_row: f64,
+        anchor_col: f64,
+        focusable: bool,
+        zindex: Option<u64>,
+    ) {
+        self.place(
+            grid,
+            WindowKind::Floating {
+                anchor,
+                anchor_grid,
+                anchor_row,
+                anchor_col,
+                focusable,
+                zindex: zindex.unwrap_or(DEFAULT_FLOAT_ZINDEX),
+            },
+        );
+    }
+
+    pub fn msg_set_pos(&mut self, grid: u64, row: usize, scrolled: bool, sep_char: &str) {
+        self.place(
+            grid,
+            WindowKind::Message {
+                row,
+                scrolled,
+                sep_char: sep_char.to_string(),
+            },
+        );
+    }
+
+    /// Hides a window until it is positioned again.
+    pub fn hide(&mut self, grid: u64) {
+        if let Some(window) = self.windows.get_mut(&grid) {
+            window.hidden = true;
+        }
+    }
+
+    /// Forgets a window's placement (win_close or grid_destroy).
+    pub fn remove(&mut self, grid: u64) -> bool {
+        self.zooms.remove(&grid);
+        self.windows.remove(&grid).is_some()
+    }
+
+    /// The grid the split window with handle `win` draws on.
+    pub fn window_grid(&self, win: u64) -> Option<u64> {
+        self.windows.values().find_map(|w| match w.kind {
+            WindowKind::Normal { win: handle, .. } if handle == win => Some(w.grid),
+            _ => None,
+        })
+    }
+
+    /// Draws the split window on `grid` `zoom` times the size of the rest;
+    /// 1 draws it like the rest again.
+    pub fn set_zoom(&mut self, grid: u64, zoom: f32) {
+        if zoom == 1.0 {
+            self.zooms.remove(&grid);
+        } else {
+            self.zooms.insert(grid, zoom);
+        }
+    }
+
+    pub fn zoom(&self, grid: u64) -> Option<f32> {
+        self.zooms.get(&grid).copied()
+    }
+
+    /// The cells of each zoomed window's grid, as (grid, cols, rows): as
+    /// many of its zoomed cells as fit the space the window takes.
+    pub fn zoomed_sizes(&self) -> Vec<(u64, usize, usize)> {
+        self.zooms
+            .iter()
+            .filter_map(|(&grid, &zoom)| match self.windows.get(&grid)?.kind {
+                WindowKind::Normal { width, height, .. } => {
+                    let cells = |n: usize| ((n as f32 / zoom).floor() as usize).max(1);
+                    Some((grid, cells(width), cells(height)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn get(&self, grid: u64) -> Option<&WindowPlacement> {
+        self.windows.get(&grid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// The visible message grid's placement, if Neovim has positioned one.
+    pub fn message_area(&self) -> Option<MessageArea> {
+        self.windows
+            .values()
+            .filter(|w| !w.hidden)
+            .find_map(|w| match w.kind {
+                WindowKind::Message { row, scrolled, .. } => Some(MessageArea {
+                    grid: w.grid,
+                    row,
+                    scrolled,
+                }),
+                _ => None,
+            })
+    }
+
+    /// Visible windows ordered bottom to top.
+    pub fn visible_in_z_order(&self) -> Vec<&WindowPlacement> {
+        let mut windows: Vec<_> = self.windows.values().filter(|w| !w.hidden).collect();
+        windows.sort_by_key(|w| (w.zindex(), w.seq));
+        windows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grids(layout: &Layout) -> Vec<u64> {
+        layout.visible_in_z_order().iter().map(|w| w.grid).collect()
+    }
+
+    #[test]
+    fn test_z_order_by_kind() {
+        let mut layout = Layout::new();
+        layout.msg_set_pos(5, 20, false, "");
+        layout.win_float_pos(4, FloatAnchor::NW, 2, 1.0, 1.0, true, None);
+        layout.win_pos(2, 1000, 0, 0, 80, 20);
+
+        assert_eq!(grids(&layout), vec![2, 4, 5]);
+    }
+
+    #[test]
+    fn test_z_order_ties_use_placement_order() {
+        let mut layout = Layout::new();
+        layout.win_float_pos(3, FloatAnchor::NW, 1, 0.0, 0.0, true, Some(50));
+        layout.win_float_pos(4, FloatAnchor::NW, 1, 0.0, 0.0, true, Some(50));
+        assert_eq!(grids(&layout), vec![3, 4]);
+
+        // Repositioning raises the window above its peers
+        layout.win_float_pos(3, FloatAnchor::NW, 1, 2.0, 0.0, true, Some(50));
+        assert_eq!(grids(&layout), vec![4, 3]);
+    }
+
+    #[test]
+    fn test_explicit_zindex_above_messages() {
+        let mut layout = Layout::new();
+        layout.msg_set_pos(5, 20, false, "");
+        layout.win_float_pos(4, FloatAnchor::NW, 1, 0.0, 0.0, true, Some(250));
+
+        assert_eq!(grids(&layout), vec![5, 4]);
+    }
+
+    #[test]
+    fn test_message_area_separator() {
+        let mut layout = Layout::new();
+        assert_eq!(layout.message_area(), None);
+
+        layout.msg_set_pos(5, 20, false, "");
+        let area = layout.message_area().unwrap();
+        assert_eq!((area.grid, area.row), (5, 20));
+        assert_eq!(area.separator_row(), None);
+
+        layout.msg_set_pos(5, 12, true, "─");
+        assert_eq!(layout.message_area().unwrap().separator_row(), Some(11));
+        layout.msg_set_pos(5, 0, true, "─");
+        assert_eq!(layout.message_area().unwrap().separator_row(), None);
+
+        layout.hide(5);
+        assert_eq!(layout.message_area(), None);
+    }
+
+    #[test]
+    fn test_hide_and_show() {
+        let mut layout = Layout::new();
+        layout.win_pos(2, 1000, 0, 0, 80, 20);
+        layout.hide(2);
+        assert!(grids(&layout).is_empty());
+        assert!(layout.get(2).unwrap().hidden);
+
+        layout.win_pos(2, 1000, 0, 0, 80, 20);
+        assert_eq!(grids(&layout), vec![2]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut layout = Layout::new();
+        layout.win_pos(2, 1000, 0, 0, 80, 20);
+        assert!(layout.remove(2));
+        assert!(!layout.remove(2));
+        assert!(layout.is_empty());
+    }
+
+    #[test]
+    fn test_zoomed_sizes() {
+        let mut layout = Layout::new();
+        layout.win_pos(2, 1000, 0, 0, 80, 21);
+        layout.win_pos(3, 1001, 0, 81, 40, 21);
+        assert_eq!(layout.window_grid(1001), Some(3));
+        assert_eq!(layout.window_grid(1002), None);
+
+        layout.set_zoom(2, 2.0);
+        layout.set_zoom(3, 0.5);
+        let mut sizes = layout.zoomed_sizes();
+        sizes.sort();
+        assert_eq!(sizes, vec![(2, 40, 10), (3, 80, 42)]);
+
+        layout.set_zoom(3, 1.0);
+        assert_eq!(layout.zoom(3), None);
+        assert!(layout.remove(2));
+        assert_eq!(layout.zoom(2), None);
+        assert!(layout.zoomed_sizes().is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+/// A run of message text sharing one highlight.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyledChunk {
+    pub hl_id: u64,
+    pub text: String,
+}
+
+/// How long a message stays on screen before it starts fading (ms).
+pub const MESSAGE_TIMEOUT_MS: u64 = 4000;
+
+/// Duration of the fade-out at the end of a message's lifetime (ms).
+pub const MESSAGE_FADE_MS: u64 = 500;
+
+/// A message from msg_show.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub kind: String,
+    pub content: Vec<StyledChunk>,
+    /// Time (ms) the message was first drawn, assigned by `Messages::update`.
+    shown_at: Option<u64>,
+    /// Current opacity, 1.0 until the message starts fading.
+    pub opacity: f32,
+}
+
+impl Message {
+    pub fn new(kind: &str, content: Vec<StyledChunk>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            content,
+            shown_at: None,
+            opacity: 1.0,
+        }
+    }
+
+    /// Prompts wait for the user, so they stay until Neovim clears them.
+    pub fn is_sticky(&self) -> bool {
+        matches!(
+            self.kind.as_str(),
+            "confirm" | "confirm_sub" | "return_prompt"
+        )
+    }
+
+    /// Message text split into lines, keeping highlights. Chunks may contain
+    /// newlines, so a single chunk can end up spread over several lines.
+    pub fn lines(&self) -> Vec<Vec<StyledChunk>> {
+        let mut lines = vec![Vec::new()];
+        for chunk in &self.content {
+            for (i, part) in chunk.text.split('\n').enumerate() {
+                if i > 0 {
+                    lines.push(Vec::new());
+                }
+                if !part.is_empty() {
+                    lines.last_mut().unwrap().push(StyledChunk {
+                        hl_id: chunk.hl_id,
+                        text: part.to_string(),
+                    });
+                }
+            }
+        }
+        // Trailing newlines don't open a visible line
+        while lines.len() > 1 && lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        lines
+    }
+}
+
+/// Everything ext_messages draws outside the grid.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Messages {
+    /// Messages currently on screen, oldest first.
+    pub shown: Vec<Message>,
+    /// Output of `:messages`, shown until dismissed.
+    pub history: Option<Vec<Message>>,
+    pub ruler: Vec<StyledChunk>,
+    pub showmode: Vec<StyledChunk>,
+    pub showcmd: Vec<StyledChunk>,
+}
+
+impl Messages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every chunk held: messages on screen, history, ruler, showmode and
+    /// showcmd.
+    pub fn chunks(&self) -> impl Iterator<Item = &StyledChunk> {
+        let messages = self.shown.iter().chain(self.history.iter().flatten());
+        messages
+            .flat_map(|message| &message.content)
+            .chain(&self.ruler)
+            .chain(&self.showmode)
+            .chain(&self.showcmd)
+    }
+
+    /// Handles msg_show.
+    pub fn show(
+        &mut self,
+        kind: &str,
+        content: Vec<StyledChunk>,
+        replace_last: bool,
+        append: bool,
+    ) {
+        if append {
+            if let Some(last) = self.shown.last_mut() {
+                last.content.extend(content);
+                last.shown_at = None;
+                last.opacity = 1.0;
+                return;
+            }
+        }
+        if replace_last {
+            self.shown.pop();
+        }
+        self.shown.push(Message::new(kind, content));
+    }
+
+    /// Handles msg_clear.
+    pub fn clear(&mut self) {
+        self.shown.clear();
+    }
+
+    /// Handles msg_history_show. An empty history closes the view.
+    pub fn history_show(&mut self, entries: Vec<Message>) {
+        self.history = (!entries.is_empty()).then_some(entries);
+    }
+
+    /// Closes the `:messages` view. Returns true if it was open.
+    pub fn dismiss_history(&mut self) -> bool {
+        self.history.take().is_some()
+    }
+
+    /// Advances message timeouts to `now` (ms), fading and then dropping
+    /// expired messages. Returns true if anything visible changed.
+    pub fn update(&mut self, now: u64) -> bool {
+        let mut changed = false;
+        for message in &mut self.shown {
+            let shown_at = *message.shown_at.get_or_insert(now);
+            if message.is_sticky() {
+                continue;
+            }
+            let opacity = fade_opacity(now.saturating_sub(shown_at));
+            if opacity != message.opacity {
+                message.opacity = opacity;
+                changed = true;
+            }
+        }
+        self.shown.retain(|m| m.opacity > 0.0);
+        changed
+    }
+
+    /// Time (ms) at which `update` next needs to run, if any message is
+    /// still counting down.
+    pub fn next_deadline(&self, now: u64) -> Option<u64> {
+        self.shown
+            .iter()
+            .filter(|m| !m.is_sticky())
+            .map(|m| {
+                let shown_at = m.shown_at.unwrap_or(now);
+                let fade_start = shown_at + MESSAGE_TIMEOUT_MS;
+                if now < fade_start {
+                    fade_start
+                } else {
+                    // Step through the fade at roughly display rate
+                    now + 16
+                }
+            })
+            .min()
+    }
+}
+
+/// Opacity of a message that has been on screen for `elapsed` ms.
+fn fade_opacity(elapsed: u64) -> f32 {
+    if elapsed < MESSAGE_TIMEOUT_MS {
+        return 1.0;
+    }
+    let fade = elapsed - MESSAGE_TIMEOUT_MS;
+    if fade >= MESSAGE_FADE_MS {
+        0.0
+    } else {
+        1.0 - fade as f32 / MESSAGE_FADE_MS as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(text: &str) -> StyledChunk {
+        StyledChunk {
+            hl_id: 0,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_show_replace_and_append() {
+        let mut messages = Messages::new();
+        messages.show("echo", vec![chunk("a")], false, false);
+        messages.show("echo", vec![chunk("b")], true, false);
+        assert_eq!(messages.shown.len(), 1);
+        assert_eq!(messages.shown[0].content, vec![chunk("b")]);
+
+        messages.show("echo", vec![chunk("c")], false, true);
+        assert_eq!(messages.shown[0].content, vec![chunk("b"), chunk("c")]);
+
+        messages.clear();
+        assert!(messages.shown.is_empty());
+    }
+
+    #[test]
+    fn test_message_lines() {
+        let message = Message::new("echo", vec![chunk("one\ntw"), chunk("o\n")]);
+        let lines = message.lines();
+        assert_eq!(
+            lines,
+            vec![vec![chunk("one")], vec![chunk("tw"), chunk("o")]]
+        );
+    }
+
+    #[test]
+    fn test_messages_fade_and_expire() {
+        let mut messages = Messages::new();
+        messages.show("echo", vec![chunk("hi")], false, false);
+
+        assert!(!messages.update(1000));
+        assert_eq!(
+            messages.next_deadline(1000),
+            Some(1000 + MESSAGE_TIMEOUT_MS)
+        );
+
+        let half_faded = 1000 + MESSAGE_TIMEOUT_MS + MESSAGE_FADE_MS / 2;
+        assert!(messages.update(half_faded));
+        assert!((messages.shown[0].opacity - 0.5).abs() < 0.01);
+
+        assert!(messages.update(1000 + MESSAGE_TIMEOUT_MS + MESSAGE_FADE_MS));
+        assert!(messages.shown.is_empty());
+        assert_eq!(messages.next_deadline(0), None);
+    }
+
+    #[test]
+    fn test_prompts_do_not_expire() {
+        let mut messages = Messages::new();
+        messages.show("return_prompt", vec![chunk("Press ENTER")], false, false);
+        messages.update(0);
+        messages.update(MESSAGE_TIMEOUT_MS * 10);
+
+        assert_eq!(messages.shown.len(), 1);
+        assert_eq!(messages.next_deadline(0), None);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut messages = Messages::new();
+        messages.history_show(vec![Message::new("echomsg", vec![chunk("old")])]);
+        assert!(messages.history.is_some());
+        assert!(messages.dismiss_history());
+        assert!(!messages.dismiss_history());
+
+        messages.history_show(Vec::new());
+        assert!(messages.history.is_none());
+    }
+}
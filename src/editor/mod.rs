@@ -1,16 +1,58 @@
 mod cell;
+mod clock;
+mod cmdline;
+mod core;
+mod crash;
+mod damage;
+mod dashboard;
 mod grid;
 mod highlight;
+mod layout;
+mod messages;
+mod options;
+mod palette;
+mod popupmenu;
+mod scrollback;
+mod special;
+mod splash;
 mod state;
+mod swap;
+mod tabline;
+mod zoom;
 
 // Re-export public items for use by the renderer and other modules
+pub use self::core::{EditorCore, EditorUpdate};
 #[allow(unused_imports)]
 pub use cell::{Cell, CellFlags};
+pub use clock::{Clock, ManualClock, SystemClock};
+#[allow(unused_imports)]
+pub use cmdline::{Cmdline, CmdlineStack};
+pub use crash::{CrashChoice, CrashPrompt, ReportChoice, ReportPrompt};
+pub use damage::{Damage, GridScrolled};
+pub use dashboard::Dashboard;
 #[allow(unused_imports)]
 pub use grid::Grid;
 pub use highlight::{Color, HighlightAttributes, StyleFlags, UnderlineStyle};
 #[allow(unused_imports)]
-pub use highlight::{DefaultColors, HighlightMap};
+pub use highlight::{DefaultColors, HighlightMap, HighlightStats};
+#[allow(unused_imports)]
+pub use layout::{
+    Layout, MessageArea, WindowKind, WindowPlacement, DEFAULT_FLOAT_ZINDEX, MESSAGE_ZINDEX,
+};
+pub use messages::StyledChunk;
+#[allow(unused_imports)]
+pub use messages::{Message, Messages, MESSAGE_FADE_MS, MESSAGE_TIMEOUT_MS};
+#[allow(unused_imports)]
+pub use options::{AmbiWidth, OptionChange, OptionsState};
+pub use popupmenu::{Popupmenu, PopupmenuItem, PopupmenuLayout};
+pub use scrollback::{Scrollback, ScrollbackRow};
+pub use special::SpecialColors;
+#[allow(unused_imports)]
+pub use splash::{Splash, SplashFade, SPLASH_HINT_DELAY_MS};
+#[allow(unused_imports)]
+pub use state::{Cursor, Preedit};
+pub use state::{CursorShape, EditorState, FloatWindow, ModeInfo, Tick, ZoomedWindow};
+pub use swap::{SwapChoice, SwapPrompt};
+pub use tabline::{Tab, TabRect, Tabline};
 #[allow(unused_imports)]
-pub use state::Cursor;
-pub use state::{CursorShape, EditorState, ModeInfo};
+pub use zoom::{ZoomIndicator, ZOOM_INDICATOR_FADE_MS, ZOOM_INDICATOR_TIMEOUT_MS};
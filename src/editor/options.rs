@@ -0,0 +1,144 @@
+use nvim_rs::Value;
+
+/// 'ambiwidth': cells taken by East Asian characters of ambiguous width.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmbiWidth {
+    #[default]
+    Single,
+    Double,
+}
+
+/// Which option an `option_set` changed, for what depends on it to catch up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionChange {
+    AmbiWidth,
+    PumBlend,
+    WinBlend,
+    PumHeight,
+    TermGuiColors,
+}
+
+/// The Neovim options, from `option_set`, that decide how the GUI draws
+/// things Neovim leaves to it, such as the popupmenu. Global values only:
+/// Neovim doesn't send window-local ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionsState {
+    ambiwidth: AmbiWidth,
+    pumblend: u8,
+    winblend: u8,
+    pumheight: usize,
+    termguicolors: bool,
+}
+
+impl Default for OptionsState {
+    fn default() -> Self {
+        Self {
+            ambiwidth: AmbiWidth::Single,
+            pumblend: 0,
+            winblend: 0,
+            pumheight: 0,
+            termguicolors: true,
+        }
+    }
+}
+
+impl OptionsState {
+    /// Applies an `option_set`. Returns what changed, or `None` if the
+    /// option isn't tracked or kept its value.
+    pub fn set(&mut self, name: &str, value: &Value) -> Option<OptionChange> {
+        match name {
+            "ambiwidth" => {
+                let ambiwidth = match value.as_str() {
+                    Some("double") => AmbiWidth::Double,
+                    _ => AmbiWidth::Single,
+                };
+                replace(&mut self.ambiwidth, ambiwidth).then_some(OptionChange::AmbiWidth)
+            }
+            "pumblend" => {
+                replace(&mut self.pumblend, blend(value)).then_some(OptionChange::PumBlend)
+            }
+            "winblend" => {
+                replace(&mut self.winblend, blend(value)).then_some(OptionChange::WinBlend)
+            }
+            "pumheight" => {
+                let pumheight = value.as_u64().unwrap_or(0) as usize;
+                replace(&mut self.pumheight, pumheight).then_some(OptionChange::PumHeight)
+            }
+            "termguicolors" => {
+                let termguicolors = value.as_bool().unwrap_or(true);
+                replace(&mut self.termguicolors, termguicolors)
+                    .then_some(OptionChange::TermGuiColors)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn ambiwidth(&self) -> AmbiWidth {
+        self.ambiwidth
+    }
+
+    /// 'pumblend', 0 (opaque) to 100.
+    pub fn pumblend(&self) -> u8 {
+        self.pumblend
+    }
+
+    /// 'winblend', 0 (opaque) to 100.
+    pub fn winblend(&self) -> u8 {
+        self.winblend
+    }
+
+    /// Maximum number of popupmenu items shown, 0 for no limit.
+    pub fn pumheight(&self) -> usize {
+        self.pumheight
+    }
+
+    pub fn termguicolors(&self) -> bool {
+        self.termguicolors
+    }
+}
+
+/// Sets `slot`, returning whether that changed it.
+fn replace<T: PartialEq>(slot: &mut T, value: T) -> bool {
+    if *slot == value {
+        return false;
+    }
+    *slot = value;
+    true
+}
+
+fn blend(value: &Value) -> u8 {
+    value.as_u64().unwrap_or(0).min(100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_set() {
+        let mut options = OptionsState::default();
+        assert_eq!(
+            options.set("pumblend", &Value::from(20u64)),
+            Some(OptionChange::PumBlend)
+        );
+        assert_eq!(options.set("pumblend", &Value::from(20u64)), None);
+        assert_eq!(options.pumblend(), 20);
+
+        options.set("winblend", &Value::from(250u64));
+        assert_eq!(options.winblend(), 100);
+
+        assert_eq!(
+            options.set("ambiwidth", &Value::from("double")),
+            Some(OptionChange::AmbiWidth)
+        );
+        assert_eq!(options.ambiwidth(), AmbiWidth::Double);
+
+        assert_eq!(
+            options.set("termguicolors", &Value::from(false)),
+            Some(OptionChange::TermGuiColors)
+        );
+        assert!(!options.termguicolors());
+
+        assert_eq!(options.set("guifont", &Value::from("Fira Code:h12")), None);
+    }
+}
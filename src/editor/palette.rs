@@ -0,0 +1,45 @@
+use super::highlight::Color;
+
+/// The 16 ANSI colors, as xterm draws them.
+const ANSI: [u32; 16] = [
+    0x000000, 0xCD0000, 0x00CD00, 0xCDCD00, 0x0000EE, 0xCD00CD, 0x00CDCD, 0xE5E5E5, //
+    0x7F7F7F, 0xFF0000, 0x00FF00, 0xFFFF00, 0x5C5CFF, 0xFF00FF, 0x00FFFF, 0xFFFFFF,
+];
+
+/// Channel levels of the 6x6x6 color cube.
+const CUBE_LEVELS: [u8; 6] = [0x00, 0x5F, 0x87, 0xAF, 0xD7, 0xFF];
+
+/// RGB for an xterm 256-color index, as used by `ctermfg`/`ctermbg`.
+pub fn xterm_256(index: u8) -> Color {
+    match index {
+        0..=15 => Color::from_u24(ANSI[index as usize]),
+        16..=231 => {
+            let i = index - 16;
+            Color::from_rgb(
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        232..=255 => {
+            let level = 8 + (index - 232) * 10;
+            Color::from_rgb(level, level, level)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xterm_256() {
+        assert_eq!(xterm_256(1), Color::from_u24(0xCD0000));
+        assert_eq!(xterm_256(16), Color::from_u24(0x000000));
+        assert_eq!(xterm_256(196), Color::from_u24(0xFF0000));
+        assert_eq!(xterm_256(110), Color::from_u24(0x87AFD7));
+        assert_eq!(xterm_256(231), Color::from_u24(0xFFFFFF));
+        assert_eq!(xterm_256(232), Color::from_u24(0x080808));
+        assert_eq!(xterm_256(255), Color::from_u24(0xEEEEEE));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::renderer::overlay::{scroll_into_view, scrollbar_thumb};
+
+/// A completion item from popupmenu_show.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PopupmenuItem {
+    pub word: String,
+    pub kind: String,
+    pub menu: String,
+    pub info: String,
+}
+
+/// The completion menu, drawn by the GUI instead of into the grid
+/// (ext_popupmenu).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Popupmenu {
+    pub items: Vec<PopupmenuItem>,
+    /// Selected item, `None` when nothing is selected.
+    pub selected: Option<usize>,
+    /// Anchor cell of the completed text, relative to `grid`.
+    pub row: usize,
+    pub col: usize,
+    pub grid: u64,
+    /// Index of the first visible item.
+    scroll: usize,
+}
+
+/// Screen placement of the popupmenu, in cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PopupmenuLayout {
+    pub row: usize,
+    pub col: usize,
+    pub width: usize,
+    /// Number of visible items.
+    pub height: usize,
+    /// Index of the first visible item.
+    pub first: usize,
+    pub word_width: usize,
+    pub kind_width: usize,
+    pub menu_width: usize,
+    /// Thumb (start row, length) when the items don't all fit.
+    pub scrollbar: Option<(usize, usize)>,
+}
+
+/// Cells of padding on each side of the text, matching Neovim's own menu.
+const PADDING: usize = 1;
+
+impl Popupmenu {
+    pub fn new(
+        items: Vec<PopupmenuItem>,
+        selected: Option<usize>,
+        row: usize,
+        col: usize,
+        grid: u64,
+    ) -> Self {
+        Self {
+            items,
+            selected,
+            row,
+            col,
+            grid,
+            scroll: 0,
+        }
+    }
+
+    /// Changes the selection and scrolls it into a view of `height` items.
+    pub fn select(&mut self, selected: Option<usize>, height: usize) {
+        self.selected = selected;
+        self.scroll_to_selected(height);
+    }
+
+    pub fn scroll_to_selected(&mut self, height: usize) {
+        self.scroll = scroll_into_view(self.scroll, self.selected, height, self.items.len());
+    }
+
+    /// Places the menu on a `screen_cols` x `screen_rows` screen, with the
+    /// anchor cell at (`row`, `col`) in screen coordinates.
+    ///
+    /// The menu opens below the anchor, or above it when there is more room
+    /// there. `max_height` is 'pumheight' (0 means as many as fit). Returns
+    /// `None` if there is no room at all.
+    pub fn layout(
+        &self,
+        row: usize,
+        col: usize,
+        screen_cols: usize,
+        screen_rows: usize,
+        max_height: usize,
+    ) -> Option<PopupmenuLayout> {
+        if self.items.is_empty() {
+            return None;
+        }
+
+        let wanted = match max_height {
+            0 => self.items.len(),
+            max => self.items.len().min(max),
+        };
+        let below = screen_rows.saturating_sub(row + 1);
+        let above = row.min(screen_rows);
+        let (top, height) = if wanted <= below || below >= above {
+            (row + 1, wanted.min(below))
+        } else {
+            let height = wanted.min(above);
+            (row - height, height)
+        };
+        if height == 0 {
+            return None;
+        }
+
+        let column_width = |f: fn(&PopupmenuItem) -> &str| {
+            self.items
+                .iter()
+                .map(|item| f(item).chars().count())
+                .max()
+                .unwrap_or(0)
+        };
+        let word_width = column_width(|item| &item.word);
+        let kind_width = column_width(|item| &item.kind);
+        let menu_width = column_width(|item| &item.menu);
+
+        let scrollbar_width = usize::from(self.items.len() > height);
+        let mut width = PADDING + word_width + PADDING + scrollbar_width;
+        if kind_width > 0 {
+            width += kind_width + PADDING;
+        }
+        if menu_width > 0 {
+            width += menu_width + PADDING;
+        }
+        let width = width.min(screen_cols);
+        if width == 0 {
+            return None;
+        }
+        let col = col.min(screen_cols - width);
+
+        let first = self.scroll.min(self.items.len() - height);
+        let scrollbar = scrollbar_thumb(self.items.len(), height, first);
+
+        Some(PopupmenuLayout {
+            row: top,
+            col,
+            width,
+            height,
+            first,
+            word_width,
+            kind_width,
+            menu_width,
+            scrollbar,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu(count: usize) -> Popupmenu {
+        let items = (0..count)
+            .map(|i| PopupmenuItem {
+                word: format!("item{}", i),
+                kind: "f".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        Popupmenu::new(items, None, 0, 0, 1)
+    }
+
+    #[test]
+    fn test_layout_below_anchor() {
+        let layout = menu(3).layout(2, 4, 80, 24, 0).unwrap();
+        assert_eq!((layout.row, layout.col, layout.height), (3, 4, 3));
+        // " item0 f "
+        assert_eq!(layout.width, 9);
+        assert_eq!(layout.scrollbar, None);
+    }
+
+    #[test]
+    fn test_layout_flips_above_when_cramped() {
+        let layout = menu(10).layout(20, 0, 80, 24, 0).unwrap();
+        assert_eq!((layout.row, layout.height), (10, 10));
+    }
+
+    #[test]
+    fn test_layout_clamps_to_right_edge() {
+        let layout = menu(1).layout(0, 78, 80, 24, 0).unwrap();
+        assert_eq!(layout.col, 80 - layout.width);
+    }
+
+    #[test]
+    fn test_pumheight_and_scrollbar() {
+        let mut pum = menu(20);
+        pum.select(Some(12), 5);
+        let layout = pum.layout(0, 0, 80, 24, 5).unwrap();
+        assert_eq!(layout.height, 5);
+        assert_eq!(layout.first, 8);
+        // 5 * 5 / 20 rounds down to a one-row thumb
+        assert_eq!(layout.scrollbar, Some((2, 1)));
+
+        pum.select(Some(0), 5);
+        assert_eq!(pum.layout(0, 0, 80, 24, 5).unwrap().first, 0);
+    }
+
+    #[test]
+    fn test_empty_menu_has_no_layout() {
+        assert_eq!(menu(0).layout(0, 0, 80, 24, 0), None);
+    }
+}
@@ -0,0 +1,162 @@
+/// Highlight group a line of `:messages` is drawn with: errors such as
+/// `E492: Not an editor command` in `ErrorMsg`, warnings in `WarningMsg`.
+pub fn line_group(line: &str) -> Option<&'static str> {
+    if numbered(line, 'E') || line.starts_with("Error") {
+        Some("ErrorMsg")
+    } else if numbered(line, 'W') {
+        Some("WarningMsg")
+    } else {
+        None
+    }
+}
+
+/// Whether `line` starts with a message number such as `E492:`.
+fn numbered(line: &str, prefix: char) -> bool {
+    let Some(rest) = line.strip_prefix(prefix) else {
+        return false;
+    };
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && rest[digits..].starts_with(':')
+}
+
+/// One screen row of the history, a whole line or part of a long one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrollbackRow {
+    pub text: String,
+    pub group: Option<&'static str>,
+}
+
+/// The `:messages` history as shown by `:GuiMessages`, in a panel over
+/// the grid that scrolls without Neovim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scrollback {
+    lines: Vec<String>,
+    /// First row shown, counting the rows long lines wrap to. Clamped
+    /// when drawn, so it can point past the end to stick to the bottom.
+    top: usize,
+}
+
+impl Scrollback {
+    /// The history in `output`, scrolled to the newest message.
+    pub fn new(output: &str) -> Self {
+        Self {
+            lines: output.lines().map(str::to_string).collect(),
+            top: usize::MAX,
+        }
+    }
+
+    /// Columns and rows of text in a panel over a `cols` by `rows` grid:
+    /// a column of padding on either side, a title above and a footer
+    /// below.
+    pub fn text_area(cols: usize, rows: usize) -> (usize, usize) {
+        (cols.saturating_sub(2), rows.saturating_sub(2))
+    }
+
+    /// Every row of the history, with lines wrapped at `width` columns.
+    pub fn rows(&self, width: usize) -> Vec<ScrollbackRow> {
+        let width = width.max(1);
+        let mut rows = Vec::new();
+        for line in &self.lines {
+            let group = line_group(line);
+            let chars: Vec<char> = line.chars().collect();
+            if chars.is_empty() {
+                rows.push(ScrollbackRow {
+                    text: String::new(),
+                    group,
+                });
+            }
+            for part in chars.chunks(width) {
+                rows.push(ScrollbackRow {
+                    text: part.iter().collect(),
+                    group,
+                });
+            }
+        }
+        rows
+    }
+
+    /// The first row shown in a `width` by `height` text area.
+    pub fn first_row(&self, width: usize, height: usize) -> usize {
+        self.top.min(self.rows(width).len().saturating_sub(height))
+    }
+
+    /// Scrolls by `delta` rows, down if positive, within the history.
+    pub fn scroll_by(&mut self, delta: isize, width: usize, height: usize) {
+        let first = self.first_row(width, height);
+        self.top = first.saturating_add_signed(delta);
+        self.top = self.first_row(width, height);
+    }
+
+    pub fn to_top(&mut self) {
+        self.top = 0;
+    }
+
+    pub fn to_bottom(&mut self) {
+        self.top = usize::MAX;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_groups() {
+        assert_eq!(
+            line_group("E492: Not an editor command: x"),
+            Some("ErrorMsg")
+        );
+        assert_eq!(
+            line_group("Error detected while processing init.lua:"),
+            Some("ErrorMsg")
+        );
+        assert_eq!(
+            line_group("W10: Warning: Changing a readonly file"),
+            Some("WarningMsg")
+        );
+        assert_eq!(line_group("Every line is fine"), None);
+        assert_eq!(line_group("E: not a number"), None);
+        assert_eq!(line_group("W"), None);
+    }
+
+    #[test]
+    fn test_long_lines_wrap() {
+        let scrollback = Scrollback::new("abcdef\n\nE1: é");
+        let rows: Vec<_> = scrollback
+            .rows(4)
+            .into_iter()
+            .map(|row| (row.text, row.group))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("abcd".to_string(), None),
+                ("ef".to_string(), None),
+                (String::new(), None),
+                ("E1: ".to_string(), Some("ErrorMsg")),
+                ("é".to_string(), Some("ErrorMsg")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scrolling_stays_within_the_history() {
+        let output: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        let mut scrollback = Scrollback::new(&output.join("\n"));
+        // Opens at the newest message.
+        assert_eq!(scrollback.first_row(80, 4), 6);
+
+        scrollback.scroll_by(-2, 80, 4);
+        assert_eq!(scrollback.first_row(80, 4), 4);
+        scrollback.scroll_by(100, 80, 4);
+        assert_eq!(scrollback.first_row(80, 4), 6);
+        scrollback.scroll_by(-100, 80, 4);
+        assert_eq!(scrollback.first_row(80, 4), 0);
+
+        scrollback.to_bottom();
+        assert_eq!(scrollback.first_row(80, 20), 0);
+        assert_eq!(scrollback.first_row(80, 4), 6);
+        scrollback.to_top();
+        assert_eq!(scrollback.first_row(80, 4), 0);
+    }
+}
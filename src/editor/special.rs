@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+
+use super::highlight::Color;
+use crate::config::parse_hex_color;
+
+/// Underline colors for highlights that don't set `guisp`, so undercurled
+/// diagnostics stand out from the text whatever the colorscheme does.
+/// Keyed by the end of a highlight group name.
+const DEFAULT_SPECIAL_COLORS: &[(&str, u32)] = &[
+    ("Error", 0xf14c4c),
+    ("Warn", 0xcca700),
+    ("Info", 0x3794ff),
+    ("Hint", 0x4ec9b0),
+];
+
+/// Fallbacks for the special color, from the `[special_colors]` config
+/// table over the defaults.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecialColors {
+    /// Longest suffix first, so the most specific one matches.
+    colors: Vec<(String, Color)>,
+}
+
+impl Default for SpecialColors {
+    fn default() -> Self {
+        Self::from_config(&BTreeMap::new())
+    }
+}
+
+impl SpecialColors {
+    /// Merges the config table, `"#rrggbb"` colors keyed by group name
+    /// suffix, over the defaults. An empty color removes a default.
+    /// Colors that don't parse are logged and skipped.
+    pub fn from_config(table: &BTreeMap<String, String>) -> Self {
+        let mut merged: BTreeMap<String, Option<Color>> = DEFAULT_SPECIAL_COLORS
+            .iter()
+            .map(|&(suffix, rgb)| (suffix.to_string(), Some(Color::from_u24(rgb))))
+            .collect();
+        for (suffix, color) in table {
+            if color.is_empty() {
+                merged.insert(suffix.clone(), None);
+                continue;
+            }
+            match parse_hex_color(color).map(Color::from_u24) {
+                Some(color) => {
+                    merged.insert(suffix.clone(), Some(color));
+                }
+                None => log::warn!("Ignoring special color {:?}: not #rrggbb", color),
+            }
+        }
+
+        let mut colors: Vec<_> = merged
+            .into_iter()
+            .filter_map(|(suffix, color)| Some((suffix, color?)))
+            .collect();
+        colors.sort_by_key(|(suffix, _)| std::cmp::Reverse(suffix.len()));
+        Self { colors }
+    }
+
+    /// The fallback for a highlight combined from `groups`, lowest
+    /// priority first: the last group with a matching suffix wins.
+    pub fn lookup(&self, groups: &[String]) -> Option<Color> {
+        groups.iter().rev().find_map(|group| {
+            self.colors
+                .iter()
+                .find(|(suffix, _)| group.ends_with(suffix.as_str()))
+                .map(|&(_, color)| color)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_default_special_colors() {
+        let colors = SpecialColors::default();
+        assert_eq!(
+            colors.lookup(&groups(&["DiagnosticUnderlineError"])),
+            Some(Color::from_u24(0xf14c4c))
+        );
+        assert_eq!(
+            colors.lookup(&groups(&[
+                "DiagnosticUnderlineError",
+                "DiagnosticUnderlineHint"
+            ])),
+            Some(Color::from_u24(0x4ec9b0))
+        );
+        assert_eq!(colors.lookup(&groups(&["ErrorMsg", "Underlined"])), None);
+        assert_eq!(colors.lookup(&[]), None);
+    }
+
+    #[test]
+    fn test_special_colors_from_config() {
+        let table = BTreeMap::from([
+            ("Hint".to_string(), String::new()),
+            ("SpellBad".to_string(), "#ff00ff".to_string()),
+            (
+                "DiagnosticUnderlineError".to_string(),
+                "#aa0000".to_string(),
+            ),
+            ("Warn".to_string(), "yellow".to_string()),
+        ]);
+        let colors = SpecialColors::from_config(&table);
+
+        assert_eq!(colors.lookup(&groups(&["DiagnosticUnderlineHint"])), None);
+        assert_eq!(
+            colors.lookup(&groups(&["SpellBad"])),
+            Some(Color::from_u24(0xff00ff))
+        );
+        // The longer suffix beats the default `Error`.
+        assert_eq!(
+            colors.lookup(&groups(&["DiagnosticUnderlineError"])),
+            Some(Color::from_u24(0xaa0000))
+        );
+        assert_eq!(
+            colors.lookup(&groups(&["DiagnosticVirtualTextError"])),
+            Some(Color::from_u24(0xf14c4c))
+        );
+        // Unparsable colors leave the default in place.
+        assert_eq!(
+            colors.lookup(&groups(&["DiagnosticUnderlineWarn"])),
+            Some(Color::from_u24(0xcca700))
+        );
+    }
+}
@@ -0,0 +1,182 @@
+/// How long startup may take before the splash says what is going on (ms).
+/// Quick startups never show the hint, so it doesn't flash by.
+pub const SPLASH_HINT_DELAY_MS: u64 = 500;
+
+/// Time between two frames of the spinner shown with the hint (ms).
+pub const SPLASH_SPINNER_FRAME_MS: u64 = 80;
+
+/// Duration of the fade from the startup splash into the grid (ms).
+pub const SPLASH_FADE_MS: u64 = 200;
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Placeholder shown from launch until Neovim's first flush, while init
+/// scripts run: `[window] splash_background` with the logo, drawn instead
+/// of the grid. Early UI events already apply underneath it, and it fades
+/// into the first real frame once that is in. Also shown over the last
+/// frame while reconnecting to a server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Splash {
+    /// Time (ms) of the first `update`.
+    started: Option<u64>,
+    hint_visible: bool,
+    /// Spinner frames since the hint appeared.
+    spinner: u64,
+    reconnecting: bool,
+}
+
+impl Splash {
+    /// Splash for a dropped server connection, until the next flush.
+    pub fn reconnecting() -> Self {
+        Self {
+            reconnecting: true,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the splash is drawn instead of the grid, rather than over
+    /// the last frame.
+    pub fn covers_grid(&self) -> bool {
+        !self.reconnecting
+    }
+
+    /// Advances the splash to `now` (ms). Returns true if the hint appeared
+    /// or the spinner moved on.
+    pub fn update(&mut self, now: u64) -> bool {
+        let started = *self.started.get_or_insert(now);
+        let shown_for = now
+            .saturating_sub(started)
+            .checked_sub(SPLASH_HINT_DELAY_MS);
+        let visible = shown_for.is_some();
+        let spinner = shown_for.map_or(0, |time| time / SPLASH_SPINNER_FRAME_MS);
+        let changed = visible != self.hint_visible || spinner != self.spinner;
+        self.hint_visible = visible;
+        self.spinner = spinner;
+        changed
+    }
+
+    /// When the hint is due, or the spinner's next frame once it is shown
+    /// (ms).
+    pub fn next_deadline(&self) -> Option<u64> {
+        let hint_at = self.started? + SPLASH_HINT_DELAY_MS;
+        if !self.hint_visible {
+            return Some(hint_at);
+        }
+        Some(hint_at + (self.spinner + 1) * SPLASH_SPINNER_FRAME_MS)
+    }
+
+    /// The spinner's current frame, shown with the hint.
+    pub fn spinner(&self) -> Option<char> {
+        self.hint_visible
+            .then(|| SPINNER[(self.spinner % SPINNER.len() as u64) as usize])
+    }
+
+    /// Progress hint to draw, once startup has been slow for a while.
+    pub fn hint(&self, busy: bool) -> Option<&'static str> {
+        self.hint_visible.then_some(if self.reconnecting {
+            "Reconnecting…"
+        } else if busy {
+            "Neovim is busy…"
+        } else {
+            "Loading plugins…"
+        })
+    }
+}
+
+/// The startup splash fading out over the first real frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplashFade {
+    /// Time (ms) of the first `update`.
+    started: Option<u64>,
+    /// Current opacity of the splash, 0.0 once it is gone.
+    pub opacity: f32,
+}
+
+impl Default for SplashFade {
+    fn default() -> Self {
+        Self {
+            started: None,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl SplashFade {
+    /// Advances the fade to `now` (ms). Returns true if the opacity changed.
+    pub fn update(&mut self, now: u64) -> bool {
+        let started = *self.started.get_or_insert(now);
+        let elapsed = now.saturating_sub(started);
+        let opacity = if elapsed >= SPLASH_FADE_MS {
+            0.0
+        } else {
+            1.0 - elapsed as f32 / SPLASH_FADE_MS as f32
+        };
+        let changed = opacity != self.opacity;
+        self.opacity = opacity;
+        changed
+    }
+
+    /// Time (ms) at which `update` next needs to run.
+    pub fn next_deadline(&self, now: u64) -> u64 {
+        // Step through the fade at roughly display rate
+        now + 16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hint_appears_after_delay() {
+        let mut splash = Splash::default();
+        assert!(!splash.update(1000));
+        assert_eq!(splash.hint(false), None);
+        assert_eq!(splash.next_deadline(), Some(1000 + SPLASH_HINT_DELAY_MS));
+
+        assert!(splash.update(1000 + SPLASH_HINT_DELAY_MS));
+        assert_eq!(splash.hint(false), Some("Loading plugins…"));
+        assert_eq!(splash.hint(true), Some("Neovim is busy…"));
+        assert_eq!(splash.spinner(), Some('⠋'));
+    }
+
+    #[test]
+    fn test_spinner_turns_with_the_hint() {
+        let mut splash = Splash::default();
+        splash.update(0);
+        assert_eq!(splash.spinner(), None);
+        splash.update(SPLASH_HINT_DELAY_MS);
+        let next = SPLASH_HINT_DELAY_MS + SPLASH_SPINNER_FRAME_MS;
+        assert_eq!(splash.next_deadline(), Some(next));
+        assert!(!splash.update(next - 1));
+
+        assert!(splash.update(next));
+        assert_eq!(splash.spinner(), Some('⠙'));
+        assert_eq!(splash.next_deadline(), Some(next + SPLASH_SPINNER_FRAME_MS));
+        // The frames go round.
+        splash.update(SPLASH_HINT_DELAY_MS + 10 * SPLASH_SPINNER_FRAME_MS);
+        assert_eq!(splash.spinner(), Some('⠋'));
+    }
+
+    #[test]
+    fn test_fade_into_the_grid() {
+        assert!(Splash::default().covers_grid());
+        assert!(!Splash::reconnecting().covers_grid());
+
+        let mut fade = SplashFade::default();
+        assert!(!fade.update(1000));
+        assert_eq!(fade.opacity, 1.0);
+        assert!(fade.update(1000 + SPLASH_FADE_MS / 2));
+        assert!((fade.opacity - 0.5).abs() < 0.01);
+        assert!(fade.update(1000 + SPLASH_FADE_MS));
+        assert_eq!(fade.opacity, 0.0);
+    }
+
+    #[test]
+    fn test_reconnecting_hint() {
+        let mut splash = Splash::reconnecting();
+        splash.update(0);
+        splash.update(SPLASH_HINT_DELAY_MS);
+        assert_eq!(splash.hint(true), Some("Reconnecting…"));
+    }
+}
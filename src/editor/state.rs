@@ -1,10 +1,26 @@
-use std::collections::HashMap;
-
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use super::cell::Cell;
+use super::cmdline::{Cmdline, CmdlineStack};
+use super::crash::{CrashPrompt, ReportPrompt};
+use super::damage::{Damage, GridScrolled};
+use super::dashboard::Dashboard;
 use super::grid::Grid;
 #[cfg(test)]
 use super::highlight::StyleFlags;
 use super::highlight::{Color, HighlightAttributes, HighlightMap};
-use crate::bridge::events::RedrawEvent;
+use super::layout::{Layout, MessageArea, WindowKind};
+use super::messages::{Message, Messages};
+use super::options::{OptionChange, OptionsState};
+use super::popupmenu::{Popupmenu, PopupmenuLayout};
+use super::scrollback::Scrollback;
+use super::splash::{Splash, SplashFade};
+use super::swap::SwapPrompt;
+use super::tabline::Tabline;
+use super::zoom::ZoomIndicator;
+use crate::bridge::events::{FloatAnchor, RedrawEvent};
+use crate::constants::{MAX_GRID_COLS, MAX_GRID_ROWS};
 
 /// Cursor shape as defined by Neovim's mode_info_set.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -55,6 +71,47 @@ impl Default for Cursor {
     }
 }
 
+/// In-progress IME composition, drawn over the grid at the cursor.
+///
+/// This is GUI-side state: Neovim never sees the text until it is committed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preedit {
+    pub text: String,
+    /// Byte range of the IME caret/selection within `text`, if any.
+    pub cursor: Option<(usize, usize)>,
+}
+
+/// A floating window's grid and where its top-left cell lands on the main
+/// grid, in fractional cells.
+#[derive(Debug, Clone, Copy)]
+pub struct FloatWindow<'a> {
+    pub grid: &'a Grid,
+    pub row: f64,
+    pub col: f64,
+}
+
+/// A split window drawn `zoom` times the size of the rest
+/// (`gui.set_window_zoom`): its grid, the screen cell its top-left corner
+/// is at and the screen cells it covers.
+#[derive(Debug, Clone, Copy)]
+pub struct ZoomedWindow<'a> {
+    pub grid: &'a Grid,
+    pub row: usize,
+    pub col: usize,
+    pub width: usize,
+    pub height: usize,
+    pub zoom: f32,
+}
+
+/// What `EditorState::tick` found.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tick {
+    /// Something on screen changed, so a frame is due.
+    pub changed: bool,
+    /// When (ms) the next tick is due, if anything is waiting on one.
+    pub deadline: Option<u64>,
+}
+
 /// Central container for all editor state.
 ///
 /// This struct holds the complete state needed to render the Neovim UI:
@@ -69,18 +126,75 @@ pub struct EditorState {
     pub highlights: HighlightMap,
     /// Cursor state.
     pub cursor: Cursor,
+    /// Active IME composition, if any.
+    pub preedit: Option<Preedit>,
+    /// Screen placement of non-main grids (multigrid).
+    pub layout: Layout,
+    /// Completion menu from popupmenu_show (ext_popupmenu).
+    pub popupmenu: Option<Popupmenu>,
+    /// Messages, ruler and showmode drawn outside the grid (ext_messages).
+    pub messages: Messages,
+    /// Command lines drawn outside the grid (implied by ext_messages).
+    pub cmdline: CmdlineStack,
+    /// Tab bar drawn above the grid (ext_tabline).
+    pub tabline: Tabline,
+    /// Recent files screen shown at startup, until dismissed.
+    pub dashboard: Option<Dashboard>,
+    /// "Swap file found" question Neovim is waiting on.
+    pub swap_prompt: Option<SwapPrompt>,
+    /// Asks whether to restart Neovim after it crashed.
+    pub crash_prompt: Option<CrashPrompt>,
+    /// Points at the report gui.nvim left when it last crashed.
+    pub report_prompt: Option<ReportPrompt>,
+    /// `:messages` history opened with `:GuiMessages`.
+    pub scrollback: Option<Scrollback>,
+    /// Placeholder shown until Neovim's first flush.
+    pub splash: Option<Splash>,
+    /// The startup splash fading into the first frame.
+    pub splash_fade: Option<SplashFade>,
+    /// Files are being dragged over the window.
+    pub file_hover: bool,
+    /// The font size, for a moment after zooming.
+    pub zoom_indicator: Option<ZoomIndicator>,
+    /// URL of the link under the mouse pointer, whose cells are underlined.
+    pub hovered_link: Option<String>,
+    /// Mark cells holding invisible characters (`:GuiInvisible`).
+    pub show_invisible: bool,
+    /// Draw frame times, quad counts and cache hit rates over the grid
+    /// (`:GuiPerfOverlay`).
+    pub perf_overlay: bool,
+    /// Pixels the grid is drawn shifted down by, the part of a smooth
+    /// trackpad scroll Neovim hasn't scrolled yet.
+    pub scroll_offset: f32,
+    /// Between busy_start and busy_stop, when the cursor is hidden.
+    pub busy: bool,
+    /// `[cursor] blink`: blink as 'guicursor' says.
+    pub cursor_blink: bool,
+    /// Whether the window has focus. The cursor is drawn hollow and doesn't
+    /// blink without it.
+    focused: bool,
+    /// Options from option_set that overlays are drawn with.
+    options: OptionsState,
     /// Mode definitions from mode_info_set.
     modes: Vec<ModeInfo>,
     /// Current mode index.
     current_mode: usize,
+    /// Name of the current mode, such as "normal" or "cmdline_insert".
+    mode_name: String,
     /// Default grid dimensions (columns x rows).
     #[allow(dead_code)]
     default_cols: usize,
     #[allow(dead_code)]
     default_rows: usize,
-    /// Whether the editor state has changed since the last render.
-    /// Set by any mutation; cleared by the renderer after drawing a frame.
+    /// Whether the editor state has changed in a way that needs the whole
+    /// window redrawn. Set by most mutations; cleared after drawing a frame.
     dirty: bool,
+    /// Main grid rows changed since the last frame, for changes that stay
+    /// within them (grid lines, scrolls, the cursor).
+    row_damage: Damage,
+    /// Main grid scrolls since the last frame, oldest first.
+    scrolls: Vec<GridScrolled>,
+    scroll_seq: u64,
 }
 
 impl EditorState {
@@ -97,14 +211,53 @@ impl EditorState {
                 visible: true,
                 ..Default::default()
             },
+            preedit: None,
+            layout: Layout::new(),
+            popupmenu: None,
+            messages: Messages::new(),
+            cmdline: CmdlineStack::default(),
+            tabline: Tabline::default(),
+            dashboard: None,
+            swap_prompt: None,
+            crash_prompt: None,
+            report_prompt: None,
+            scrollback: None,
+            splash: Some(Splash::default()),
+            splash_fade: None,
+            file_hover: false,
+            zoom_indicator: None,
+            hovered_link: None,
+            show_invisible: false,
+            perf_overlay: false,
+            scroll_offset: 0.0,
+            busy: false,
+            cursor_blink: true,
+            focused: true,
+            options: OptionsState::default(),
             modes: vec![ModeInfo::default()],
             current_mode: 0,
+            mode_name: "normal".to_string(),
             default_cols: cols,
             default_rows: rows,
             dirty: true,
+            row_damage: Damage::None,
+            scrolls: Vec::new(),
+            scroll_seq: 0,
         }
     }
 
+    /// Number of live grids, including the main grid.
+    pub fn grid_count(&self) -> usize {
+        self.grids.len()
+    }
+
+    /// IDs of the live grids, in ascending order.
+    pub fn grid_ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.grids.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
     /// Returns the main grid (ID 1).
     pub fn main_grid(&self) -> &Grid {
         self.grids.get(&1).expect("main grid always exists")
@@ -133,9 +286,26 @@ impl EditorState {
         self.modes.get(self.current_mode).unwrap_or(&self.modes[0])
     }
 
+    /// Returns the name of the current mode, as sent by mode_change.
+    pub fn mode_name(&self) -> &str {
+        &self.mode_name
+    }
+
     /// Returns `true` if the editor state has changed since the last render.
     pub fn is_dirty(&self) -> bool {
-        self.dirty
+        self.dirty || !self.row_damage.is_none()
+    }
+
+    /// What needs redrawing in the next frame.
+    pub fn damage(&self) -> Damage {
+        // Damaged rows would be redrawn where they sit unshifted, the
+        // invisible character label sits on the row next to the cursor and
+        // the perf overlay changes with every frame.
+        if self.dirty || self.scroll_offset != 0.0 || self.show_invisible || self.perf_overlay {
+            Damage::Full
+        } else {
+            self.row_damage.clone()
+        }
     }
 
     /// Marks the state as dirty, requiring a re-render.
@@ -146,10 +316,57 @@ impl EditorState {
     /// Clears the dirty flag after a frame has been rendered.
     pub fn clear_dirty(&mut self) {
         self.dirty = false;
+        self.row_damage = Damage::None;
+        self.scrolls.clear();
+    }
+
+    /// Full-width scrolls of the main grid since the last frame, for a
+    /// renderer that keeps rows across frames to move them along.
+    pub fn main_grid_scrolls(&self) -> &[GridScrolled] {
+        &self.scrolls
     }
 
-    /// Handles a grid_resize event.
+    /// Damages the row the cursor is drawn on.
+    fn damage_cursor(&mut self) {
+        if self.cursor.grid == 1 {
+            self.row_damage.add_row(self.cursor.row);
+        } else {
+            self.dirty = true;
+        }
+    }
+
+    /// Updates the IME composition. An empty string ends the composition.
+    pub fn set_preedit(&mut self, text: &str, cursor: Option<(usize, usize)>) {
+        let preedit = (!text.is_empty()).then(|| Preedit {
+            text: text.to_string(),
+            cursor,
+        });
+        if self.preedit != preedit {
+            self.preedit = preedit;
+            self.dirty = true;
+        }
+    }
+
+    /// Drops any in-progress IME composition.
+    pub fn clear_preedit(&mut self) {
+        self.set_preedit("", None);
+    }
+
+    /// Handles a grid_resize event. Sizes beyond [`MAX_GRID_COLS`] x
+    /// [`MAX_GRID_ROWS`] are rejected, keeping the grid as it was, rather
+    /// than allocating cells for them.
     pub fn grid_resize(&mut self, grid_id: u64, width: usize, height: usize) {
+        if width as u64 > MAX_GRID_COLS || height as u64 > MAX_GRID_ROWS {
+            log::warn!(
+                "Ignoring grid_resize of grid {} to {}x{} (max {}x{})",
+                grid_id,
+                width,
+                height,
+                MAX_GRID_COLS,
+                MAX_GRID_ROWS
+            );
+            return;
+        }
         if let Some(grid) = self.grids.get_mut(&grid_id) {
             grid.resize(width, height);
         } else {
@@ -159,6 +376,23 @@ impl EditorState {
         self.dirty = true;
     }
 
+    /// Handles a grid_destroy event: drops the grid's cells and placement,
+    /// along with any cursor or popupmenu still pointing at it. The main
+    /// grid (ID 1) is never destroyed.
+    pub fn grid_destroy(&mut self, grid_id: u64) {
+        if grid_id == 1 || self.grids.remove(&grid_id).is_none() {
+            return;
+        }
+        self.layout.remove(grid_id);
+        if self.cursor.grid == grid_id {
+            self.cursor.grid = 1;
+        }
+        if self.popupmenu.as_ref().is_some_and(|p| p.grid == grid_id) {
+            self.popupmenu = None;
+        }
+        self.dirty = true;
+    }
+
     /// Handles a grid_clear event.
     pub fn grid_clear(&mut self, grid_id: u64) {
         if let Some(grid) = self.grids.get_mut(&grid_id) {
@@ -178,7 +412,11 @@ impl EditorState {
         if let Some(grid) = self.grids.get_mut(&grid_id) {
             grid.update_line(row, col_start, cells);
         }
-        self.dirty = true;
+        if grid_id == 1 {
+            self.row_damage.add_row(row);
+        } else {
+            self.dirty = true;
+        }
     }
 
     /// Handles a grid_scroll event.
@@ -193,25 +431,57 @@ impl EditorState {
     ) {
         if let Some(grid) = self.grids.get_mut(&grid_id) {
             grid.scroll(top, bot, left, right, rows);
+            if grid_id == 1 && left == 0 && right >= grid.width() {
+                self.scroll_seq += 1;
+                self.scrolls.push(GridScrolled {
+                    seq: self.scroll_seq,
+                    rows: top..bot,
+                    delta: rows,
+                });
+            }
+        }
+        if grid_id == 1 {
+            self.row_damage.add_rows(top..bot);
+        } else {
+            self.dirty = true;
         }
-        self.dirty = true;
     }
 
     /// Handles a grid_cursor_goto event.
     pub fn grid_cursor_goto(&mut self, grid_id: u64, row: usize, col: usize) {
+        self.damage_cursor();
         self.cursor.grid = grid_id;
         self.cursor.row = row;
         self.cursor.col = col;
         self.reset_blink();
-        self.dirty = true;
+        self.damage_cursor();
     }
 
     /// Handles a hl_attr_define event.
     pub fn hl_attr_define(&mut self, id: u64, attrs: HighlightAttributes) {
+        // A redefined ID changes cells already drawn with it; the full
+        // redraw covers them.
         self.highlights.define(id, attrs);
         self.dirty = true;
     }
 
+    /// Highlight IDs something on screen is drawn with: grid cells, the
+    /// cursor modes, messages and the cmdline.
+    fn highlights_in_use(&self) -> HashSet<u64> {
+        let cells = self
+            .grids
+            .values()
+            .flat_map(|grid| grid.rows().flatten())
+            .map(|cell| cell.highlight_id);
+        let modes = self.modes.iter().map(|mode| mode.attr_id);
+        let chunks = self
+            .messages
+            .chunks()
+            .chain(self.cmdline.chunks())
+            .map(|chunk| chunk.hl_id);
+        cells.chain(modes).chain(chunks).collect()
+    }
+
     /// Handles a default_colors_set event.
     pub fn default_colors_set(&mut self, fg: u32, bg: u32, sp: u32) {
         self.highlights.set_defaults(
@@ -229,16 +499,381 @@ impl EditorState {
             self.modes.push(ModeInfo::default());
         }
         self.reset_blink();
-        self.dirty = true;
+        self.damage_cursor();
     }
 
     /// Handles a mode_change event.
-    pub fn mode_change(&mut self, _mode: &str, mode_idx: usize) {
+    pub fn mode_change(&mut self, mode: &str, mode_idx: usize) {
         self.current_mode = mode_idx;
+        self.mode_name = mode.to_string();
         self.reset_blink();
+        self.damage_cursor();
+    }
+
+    /// Returns the top-left screen position (row, col) of a grid, resolving
+    /// float anchors through their anchor grids. The main grid is at the
+    /// origin; grids without a placement return `None`.
+    pub fn window_origin(&self, grid_id: u64) -> Option<(f64, f64)> {
+        // Anchor chains are short in practice; the bound guards against cycles.
+        self.window_origin_depth(grid_id, 8)
+    }
+
+    fn window_origin_depth(&self, grid_id: u64, depth: usize) -> Option<(f64, f64)> {
+        if grid_id == 1 {
+            return Some((0.0, 0.0));
+        }
+        if depth == 0 {
+            return None;
+        }
+
+        let window = self.layout.get(grid_id)?;
+        match &window.kind {
+            WindowKind::Normal { row, col, .. } => Some((*row as f64, *col as f64)),
+            WindowKind::Message { row, .. } => Some((*row as f64, 0.0)),
+            WindowKind::Floating {
+                anchor,
+                anchor_grid,
+                anchor_row,
+                anchor_col,
+                ..
+            } => {
+                let (base_row, base_col) = self.window_origin_depth(*anchor_grid, depth - 1)?;
+                // The anchor is in the anchor grid's cells, which are
+                // bigger or smaller in a zoomed window.
+                let zoom = self.layout.zoom(*anchor_grid).unwrap_or(1.0) as f64;
+                let row = base_row + anchor_row * zoom;
+                let col = base_col + anchor_col * zoom;
+                let (width, height) = self
+                    .grid(grid_id)
+                    .map(|g| (g.width() as f64, g.height() as f64))
+                    .unwrap_or((0.0, 0.0));
+                Some(match anchor {
+                    FloatAnchor::NW => (row, col),
+                    FloatAnchor::NE => (row, col - width),
+                    FloatAnchor::SW => (row - height, col),
+                    FloatAnchor::SE => (row - height, col - width),
+                })
+            }
+        }
+    }
+
+    /// Where the message grid covers the main grid (msg_set_pos), if it is
+    /// positioned and has been created.
+    pub fn message_area(&self) -> Option<MessageArea> {
+        self.layout
+            .message_area()
+            .filter(|area| self.grids.contains_key(&area.grid))
+    }
+
+    /// Visible split windows that have a grid, with ext_multigrid: each
+    /// grid and the screen cell its top-left corner is at. Zoomed windows
+    /// are left to `zoomed_windows`.
+    pub fn split_windows(&self) -> Vec<(&Grid, usize, usize)> {
+        self.layout
+            .visible_in_z_order()
+            .into_iter()
+            .filter(|window| self.layout.zoom(window.grid).is_none())
+            .filter_map(|window| match window.kind {
+                WindowKind::Normal { row, col, .. } => Some((self.grid(window.grid)?, row, col)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Visible split windows that have a grid and a `gui.set_window_zoom`
+    /// factor, drawn over the main grid at their own cell size.
+    pub fn zoomed_windows(&self) -> Vec<ZoomedWindow<'_>> {
+        self.layout
+            .visible_in_z_order()
+            .into_iter()
+            .filter_map(|window| match window.kind {
+                WindowKind::Normal {
+                    row,
+                    col,
+                    width,
+                    height,
+                    ..
+                } => Some(ZoomedWindow {
+                    grid: self.grid(window.grid)?,
+                    row,
+                    col,
+                    width,
+                    height,
+                    zoom: self.layout.zoom(window.grid)?,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Draws the split window with handle `win` `zoom` times the size of
+    /// the rest. False if no split window has that handle, as without
+    /// ext_multigrid.
+    pub fn set_window_zoom(&mut self, win: u64, zoom: f32) -> bool {
+        let Some(grid) = self.layout.window_grid(win) else {
+            return false;
+        };
+        self.layout.set_zoom(grid, zoom);
+        self.dirty = true;
+        true
+    }
+
+    /// Rows of the main grid as they appear on screen: split windows
+    /// (ext_multigrid) are drawn into it, and from its row down the message
+    /// grid's rows take the place of the main grid's. Zoomed windows leave
+    /// blank cells to be drawn over.
+    pub fn screen_rows(&self) -> impl Iterator<Item = Cow<'_, [Cell]>> + '_ {
+        let message = self
+            .message_area()
+            .and_then(|area| Some((area.row, self.grid(area.grid)?)));
+        let splits = self.split_windows();
+        let zoomed: Vec<_> = self
+            .zoomed_windows()
+            .iter()
+            .map(|w| (w.row..w.row + w.height, w.col, w.width))
+            .collect();
+        self.main_grid()
+            .rows()
+            .enumerate()
+            .map(move |(row, cells)| {
+                if let Some(cells) = message.and_then(|(top, grid)| grid.row(row.checked_sub(top)?))
+                {
+                    return Cow::Borrowed(cells);
+                }
+                let mut composed = Cow::Borrowed(cells);
+                for (rows, left, width) in &zoomed {
+                    if !rows.contains(&row) || *left >= composed.len() {
+                        continue;
+                    }
+                    let composed = composed.to_mut();
+                    let end = (left + width).min(composed.len());
+                    composed[*left..end].fill(Cell::default());
+                }
+                for &(grid, top, left) in &splits {
+                    let Some(window_row) = row.checked_sub(top).and_then(|r| grid.row(r)) else {
+                        continue;
+                    };
+                    let composed = composed.to_mut();
+                    let width = window_row.len().min(composed.len().saturating_sub(left));
+                    composed[left..left + width].clone_from_slice(&window_row[..width]);
+                }
+                composed
+            })
+    }
+
+    /// Visible floating windows that have a grid, ordered bottom to top.
+    pub fn floats(&self) -> Vec<FloatWindow<'_>> {
+        self.layout
+            .visible_in_z_order()
+            .into_iter()
+            .filter(|window| matches!(window.kind, WindowKind::Floating { .. }))
+            .filter_map(|window| {
+                let (row, col) = self.window_origin(window.grid)?;
+                Some(FloatWindow {
+                    grid: self.grid(window.grid)?,
+                    row,
+                    col,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns where the popupmenu is drawn on the main grid, if it is shown.
+    pub fn popupmenu_layout(&self) -> Option<PopupmenuLayout> {
+        let pum = self.popupmenu.as_ref()?;
+        let (origin_row, origin_col) = self.window_origin(pum.grid).unwrap_or((0.0, 0.0));
+        let zoom = self.layout.zoom(pum.grid).unwrap_or(1.0) as f64;
+        let main = self.main_grid();
+        pum.layout(
+            (origin_row + pum.row as f64 * zoom).max(0.0) as usize,
+            (origin_col + pum.col as f64 * zoom).max(0.0) as usize,
+            main.width(),
+            main.height(),
+            self.options.pumheight(),
+        )
+    }
+
+    /// Keeps the popupmenu selection inside its visible window.
+    fn scroll_popupmenu(&mut self) {
+        let height = self.popupmenu_layout().map_or(0, |l| l.height);
+        if let Some(pum) = &mut self.popupmenu {
+            pum.scroll_to_selected(height);
+        }
+    }
+
+    /// Applies per-tab modified flags queried from Neovim.
+    pub fn set_tabs_modified(&mut self, modified: &[bool]) {
+        self.dirty |= self.tabline.set_modified(modified);
+    }
+
+    /// Advances everything that moves with time to `now` (ms): the cursor
+    /// blink, message fades, the splash and the zoom indicator.
+    pub fn tick(&mut self, now: u64) -> Tick {
+        let changed = [
+            self.update_blink(now),
+            self.update_messages(now),
+            self.update_splash(now),
+            self.update_splash_fade(now),
+            self.update_zoom_indicator(now),
+        ]
+        .contains(&true);
+        let deadline = [
+            self.next_blink_deadline(now),
+            self.messages.next_deadline(now),
+            self.splash.as_ref().and_then(|s| s.next_deadline()),
+            self.splash_fade
+                .as_ref()
+                .map(|fade| fade.next_deadline(now)),
+            self.zoom_indicator
+                .as_ref()
+                .map(|indicator| indicator.next_deadline(now)),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        Tick { changed, deadline }
+    }
+
+    /// Advances message timeouts to `now` (ms).
+    /// Returns true if a message faded or expired (requiring a redraw).
+    pub fn update_messages(&mut self, now: u64) -> bool {
+        let changed = self.messages.update(now);
+        if changed {
+            self.dirty = true;
+        }
+        changed
+    }
+
+    /// Covers the grid with a reconnecting splash after the server
+    /// connection dropped. The next flush takes it down again.
+    pub fn disconnected(&mut self) {
+        self.splash = Some(Splash::reconnecting());
+        self.busy = false;
         self.dirty = true;
     }
 
+    /// Asks what to do after the embedded Neovim crashed, over its last
+    /// frame.
+    pub fn crashed(&mut self, status: String) {
+        self.crash_prompt = Some(CrashPrompt::new(status));
+        self.swap_prompt = None;
+        self.busy = false;
+        self.dirty = true;
+    }
+
+    /// Starts over for a restarted Neovim, at the same size and with the
+    /// old colors until it sets its own. What the GUI keeps for itself,
+    /// such as focus, stays.
+    pub fn restart(&mut self) {
+        let grid = self.main_grid();
+        let mut state = Self::new(grid.width(), grid.height());
+        state.highlights = std::mem::replace(&mut self.highlights, HighlightMap::new());
+        state.focused = self.focused;
+        state.show_invisible = self.show_invisible;
+        state.perf_overlay = self.perf_overlay;
+        state.cursor_blink = self.cursor_blink;
+        state.scroll_seq = self.scroll_seq;
+        *self = state;
+    }
+
+    /// Advances the startup splash to `now` (ms).
+    /// Returns true if its hint appeared or its spinner moved (requiring a
+    /// redraw).
+    pub fn update_splash(&mut self, now: u64) -> bool {
+        let changed = self.splash.as_mut().is_some_and(|s| s.update(now));
+        if changed {
+            self.dirty = true;
+        }
+        changed
+    }
+
+    /// Advances the splash's fade into the grid to `now` (ms), dropping it
+    /// once it is gone. Returns true if it changed (requiring a redraw).
+    pub fn update_splash_fade(&mut self, now: u64) -> bool {
+        let changed = self
+            .splash_fade
+            .as_mut()
+            .is_some_and(|fade| fade.update(now));
+        if self
+            .splash_fade
+            .as_ref()
+            .is_some_and(|fade| fade.opacity <= 0.0)
+        {
+            self.splash_fade = None;
+        }
+        if changed {
+            self.dirty = true;
+        }
+        changed
+    }
+
+    /// Shows the new font size after zooming, from the start again if it
+    /// is already up.
+    pub fn show_zoom(&mut self, size: f32) {
+        self.zoom_indicator = Some(ZoomIndicator::new(size));
+        self.dirty = true;
+    }
+
+    /// Advances the zoom indicator's fade to `now` (ms).
+    /// Returns true if it faded or went away (requiring a redraw).
+    pub fn update_zoom_indicator(&mut self, now: u64) -> bool {
+        let changed = self
+            .zoom_indicator
+            .as_mut()
+            .is_some_and(|indicator| indicator.update(now));
+        if self
+            .zoom_indicator
+            .as_ref()
+            .is_some_and(|indicator| indicator.opacity <= 0.0)
+        {
+            self.zoom_indicator = None;
+        }
+        if changed {
+            self.dirty = true;
+        }
+        changed
+    }
+
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Records a focus change. The cursor shows, and blinks again from
+    /// the start once focus is back.
+    pub fn set_focused(&mut self, focused: bool) {
+        if self.focused == focused {
+            return;
+        }
+        self.focused = focused;
+        self.reset_blink();
+        self.damage_cursor();
+    }
+
+    pub fn options(&self) -> &OptionsState {
+        &self.options
+    }
+
+    /// Brings what's drawn from an option up to date with its new value.
+    fn option_changed(&mut self, change: OptionChange) {
+        match change {
+            OptionChange::TermGuiColors => {
+                self.highlights
+                    .set_cterm_colors(!self.options.termguicolors());
+                self.dirty = true;
+            }
+            OptionChange::PumHeight => {
+                if self.popupmenu.is_some() {
+                    self.scroll_popupmenu();
+                    self.dirty = true;
+                }
+            }
+            // Blends and widths can change any overlay.
+            OptionChange::AmbiWidth | OptionChange::PumBlend | OptionChange::WinBlend => {
+                self.dirty = true;
+            }
+        }
+    }
+
     /// Resets the blink timer (e.g. on cursor move).
     pub fn reset_blink(&mut self) {
         self.cursor.blink_reset_pending = true;
@@ -248,6 +883,9 @@ impl EditorState {
     /// Updates the cursor blink state based on the current time (ms).
     /// Returns true if the blink state changed (requiring a redraw).
     pub fn update_blink(&mut self, now: u64) -> bool {
+        if !self.focused || self.busy {
+            return false;
+        }
         let old_visible = self.cursor.blink_visible;
 
         if self.cursor.blink_reset_pending {
@@ -256,17 +894,17 @@ impl EditorState {
             self.cursor.blink_visible = true;
             let changed = !old_visible;
             if changed {
-                self.dirty = true;
+                self.damage_cursor();
             }
             return changed;
         }
 
         let mode = self.current_mode();
-        if mode.blink_on == 0 || mode.blink_off == 0 {
+        if !self.cursor_blink || mode.blink_on == 0 || mode.blink_off == 0 {
             self.cursor.blink_visible = true;
             let changed = !old_visible;
             if changed {
-                self.dirty = true;
+                self.damage_cursor();
             }
             return changed;
         }
@@ -278,7 +916,7 @@ impl EditorState {
             self.cursor.blink_visible = true;
             let changed = !old_visible;
             if changed {
-                self.dirty = true;
+                self.damage_cursor();
             }
             return changed;
         }
@@ -290,7 +928,7 @@ impl EditorState {
             self.cursor.blink_visible = true;
             let changed = !old_visible;
             if changed {
-                self.dirty = true;
+                self.damage_cursor();
             }
             return changed;
         }
@@ -300,11 +938,31 @@ impl EditorState {
 
         let changed = self.cursor.blink_visible != old_visible;
         if changed {
-            self.dirty = true;
+            self.damage_cursor();
         }
         changed
     }
 
+    /// When (ms) the cursor next turns on or off, for `update_blink` to be
+    /// called then. `None` while it doesn't blink, as when unfocused.
+    pub fn next_blink_deadline(&self, now: u64) -> Option<u64> {
+        let mode = self.current_mode();
+        let (on, off) = (mode.blink_on as u64, mode.blink_off as u64);
+        if !self.cursor_blink || on == 0 || off == 0 || !self.focused || self.busy {
+            return None;
+        }
+        if self.cursor.blink_reset_pending {
+            return Some(now);
+        }
+        let blink_start = self.cursor.last_blink_time + mode.blink_wait as u64;
+        if now < blink_start {
+            return Some(blink_start);
+        }
+        let phase = (now - blink_start) % (on + off);
+        let next = if phase < on { on } else { on + off };
+        Some(now - phase + next)
+    }
+
     /// Process a RedrawEvent to update the editor state.
     pub fn handle_redraw_event(&mut self, event: &RedrawEvent) {
         match event {
@@ -356,12 +1014,174 @@ impl EditorState {
                 self.mode_change(mode, *mode_idx);
             }
             RedrawEvent::GridDestroy { grid } => {
-                // Never destroy the main grid (ID 1).
-                if *grid != 1 {
-                    self.grids.remove(grid);
+                self.grid_destroy(*grid);
+            }
+            RedrawEvent::WinPos {
+                grid,
+                win,
+                start_row,
+                start_col,
+                width,
+                height,
+            } => {
+                self.layout
+                    .win_pos(*grid, *win, *start_row, *start_col, *width, *height);
+                self.dirty = true;
+            }
+            RedrawEvent::WinFloatPos {
+                grid,
+                anchor,
+                anchor_grid,
+                anchor_row,
+                anchor_col,
+                focusable,
+                zindex,
+            } => {
+                self.layout.win_float_pos(
+                    *grid,
+                    *anchor,
+                    *anchor_grid,
+                    *anchor_row,
+                    *anchor_col,
+                    *focusable,
+                    *zindex,
+                );
+                self.dirty = true;
+            }
+            RedrawEvent::WinHide { grid } => {
+                self.layout.hide(*grid);
+                self.dirty = true;
+            }
+            RedrawEvent::WinClose { grid } => {
+                self.layout.remove(*grid);
+                self.dirty = true;
+            }
+            RedrawEvent::MsgSetPos {
+                grid,
+                row,
+                scrolled,
+                sep_char,
+            } => {
+                self.layout.msg_set_pos(*grid, *row, *scrolled, sep_char);
+                self.dirty = true;
+            }
+            RedrawEvent::PopupmenuShow {
+                items,
+                selected,
+                row,
+                col,
+                grid,
+            } => {
+                self.popupmenu = Some(Popupmenu::new(items.clone(), *selected, *row, *col, *grid));
+                self.scroll_popupmenu();
+                self.dirty = true;
+            }
+            RedrawEvent::PopupmenuSelect { selected } => {
+                let height = self.popupmenu_layout().map_or(0, |l| l.height);
+                if let Some(pum) = &mut self.popupmenu {
+                    pum.select(*selected, height);
                     self.dirty = true;
                 }
             }
+            RedrawEvent::PopupmenuHide => {
+                self.dirty |= self.popupmenu.take().is_some();
+            }
+            RedrawEvent::TablineUpdate { current, tabs } => {
+                self.tabline.update(*current, tabs);
+                self.dirty = true;
+            }
+            RedrawEvent::MsgShow {
+                kind,
+                content,
+                replace_last,
+                append,
+            } => {
+                self.messages
+                    .show(kind, content.clone(), *replace_last, *append);
+                self.dirty = true;
+            }
+            RedrawEvent::MsgClear => {
+                self.messages.clear();
+                self.dirty = true;
+            }
+            RedrawEvent::MsgShowmode { content } => {
+                self.messages.showmode = content.clone();
+                self.dirty = true;
+            }
+            RedrawEvent::MsgShowcmd { content } => {
+                self.messages.showcmd = content.clone();
+                self.dirty = true;
+            }
+            RedrawEvent::MsgRuler { content } => {
+                self.messages.ruler = content.clone();
+                self.dirty = true;
+            }
+            RedrawEvent::MsgHistoryShow { entries } => {
+                self.messages.history_show(
+                    entries
+                        .iter()
+                        .map(|(kind, content)| Message::new(kind, content.clone()))
+                        .collect(),
+                );
+                self.dirty = true;
+            }
+            RedrawEvent::MsgHistoryClear => {
+                self.dirty |= self.messages.dismiss_history();
+            }
+            RedrawEvent::CmdlineShow {
+                content,
+                pos,
+                firstc,
+                prompt,
+                indent,
+                level,
+            } => {
+                self.cmdline.show(
+                    *level,
+                    Cmdline {
+                        content: content.clone(),
+                        pos: *pos,
+                        firstc: firstc.clone(),
+                        prompt: prompt.clone(),
+                        indent: *indent,
+                        special_char: None,
+                    },
+                );
+                self.dirty = true;
+            }
+            RedrawEvent::CmdlinePos { pos, level } => {
+                self.cmdline.set_pos(*level, *pos);
+                self.dirty = true;
+            }
+            RedrawEvent::CmdlineSpecialChar { c, level, .. } => {
+                self.cmdline.set_special_char(*level, c);
+                self.dirty = true;
+            }
+            RedrawEvent::CmdlineHide { level } => {
+                self.cmdline.hide(*level);
+                self.dirty = true;
+            }
+            RedrawEvent::HlGroupSet { name, id } => {
+                // Overlays and the tab bar are drawn with these groups.
+                self.highlights.set_group(name, *id);
+                self.dirty = true;
+            }
+            RedrawEvent::OptionSet { name, value } => {
+                if let Some(change) = self.options.set(name, value) {
+                    self.option_changed(change);
+                }
+            }
+            RedrawEvent::Busy { busy } => {
+                if self.busy != *busy {
+                    self.busy = *busy;
+                    self.damage_cursor();
+                    // Back on, the cursor starts its blink over.
+                    if !busy {
+                        self.reset_blink();
+                    }
+                }
+                self.dirty |= self.splash.is_some();
+            }
             RedrawEvent::Flush => {
                 self.flush();
             }
@@ -369,9 +1189,24 @@ impl EditorState {
         }
     }
 
-    /// Handles a flush event (marks end of a batch of updates).
+    /// Handles a flush event (marks end of a batch of updates). The first
+    /// one ends startup, fading the splash into the grid.
     pub fn flush(&mut self) {
-        // Currently a no-op, but could trigger a render request
+        if let Some(splash) = self.splash.take() {
+            if splash.covers_grid() {
+                self.splash_fade = Some(SplashFade::default());
+            }
+            self.dirty = true;
+        }
+        // Only IDs nothing is drawn with go, so rows on screen look the same.
+        if self.highlights.needs_compaction() {
+            let removed = self.highlights.compact(&self.highlights_in_use());
+            log::debug!(
+                "Compacted highlights: {} dropped, {} left",
+                removed,
+                self.highlights.len()
+            );
+        }
     }
 }
 
@@ -384,6 +1219,8 @@ impl Default for EditorState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::editor::splash::SPLASH_FADE_MS;
+    use crate::editor::SPLASH_HINT_DELAY_MS;
 
     #[test]
     fn test_new() {
@@ -401,6 +1238,22 @@ mod tests {
         assert_eq!(state.main_grid().height(), 30);
     }
 
+    #[test]
+    fn test_grid_resize_rejects_huge_sizes() {
+        let mut state = EditorState::new(80, 24);
+
+        state.grid_resize(1, usize::MAX, 30);
+        state.grid_resize(1, 100, MAX_GRID_ROWS as usize + 1);
+        assert_eq!(state.main_grid().width(), 80);
+        assert_eq!(state.main_grid().height(), 24);
+
+        state.grid_resize(2, 1 << 20, 1 << 20);
+        assert!(!state.grids.contains_key(&2));
+
+        state.grid_resize(1, MAX_GRID_COLS as usize, MAX_GRID_ROWS as usize);
+        assert_eq!(state.main_grid().width(), MAX_GRID_COLS as usize);
+    }
+
     #[test]
     fn test_grid_clear() {
         let mut state = EditorState::new(80, 24);
@@ -445,8 +1298,103 @@ mod tests {
     fn test_cursor_move_marks_old_row() {
         let mut state = EditorState::new(80, 24);
         state.grid_cursor_goto(1, 5, 0);
+        state.clear_dirty();
 
         state.grid_cursor_goto(1, 10, 0);
+        assert_eq!(state.damage(), Damage::Rows(5..11));
+    }
+
+    #[test]
+    fn test_first_flush_ends_splash() {
+        let mut state = EditorState::new(80, 24);
+        assert!(!state.update_splash(0));
+        assert!(state.update_splash(SPLASH_HINT_DELAY_MS));
+
+        state.handle_redraw_event(&RedrawEvent::Busy { busy: true });
+        assert!(state.busy);
+
+        state.clear_dirty();
+        state.handle_redraw_event(&RedrawEvent::Flush);
+        assert!(state.splash.is_none());
+        assert!(state.is_dirty());
+        assert!(!state.update_splash(10_000));
+
+        // It fades into the grid, and is gone for good after that.
+        assert!(state.splash_fade.is_some());
+        assert!(!state.tick(10_000).changed);
+        assert!(state.tick(10_000 + SPLASH_FADE_MS).changed);
+        assert!(state.splash_fade.is_none());
+        state.disconnected();
+        state.handle_redraw_event(&RedrawEvent::Flush);
+        assert!(state.splash_fade.is_none());
+    }
+
+    #[test]
+    fn test_row_damage() {
+        let mut state = EditorState::new(80, 24);
+        state.clear_dirty();
+        assert_eq!(state.damage(), Damage::None);
+
+        state.grid_line(1, 3, 0, &[("a".to_string(), Some(0), 1)]);
+        state.grid_scroll(1, 6, 9, 0, 80, 1);
+        assert!(state.is_dirty());
+        assert_eq!(state.damage(), Damage::Rows(3..9));
+
+        state.handle_redraw_event(&RedrawEvent::MsgClear);
+        assert_eq!(state.damage(), Damage::Full);
+
+        state.clear_dirty();
+        assert!(!state.is_dirty());
+    }
+
+    #[test]
+    fn test_main_grid_scrolls() {
+        let mut state = EditorState::new(80, 24);
+        state.grid_scroll(1, 0, 20, 0, 80, 3);
+        // Part of the width, a vertical split, moves nothing whole.
+        state.grid_scroll(1, 0, 20, 0, 40, 1);
+        state.grid_scroll(1, 2, 24, 0, 80, -2);
+        assert_eq!(
+            state.main_grid_scrolls(),
+            [
+                GridScrolled {
+                    seq: 1,
+                    rows: 0..20,
+                    delta: 3
+                },
+                GridScrolled {
+                    seq: 2,
+                    rows: 2..24,
+                    delta: -2
+                }
+            ]
+        );
+
+        state.clear_dirty();
+        assert!(state.main_grid_scrolls().is_empty());
+        state.grid_scroll(1, 0, 24, 0, 80, 1);
+        assert_eq!(state.main_grid_scrolls()[0].seq, 3);
+    }
+
+    #[test]
+    fn test_preedit() {
+        let mut state = EditorState::new(80, 24);
+        state.clear_dirty();
+
+        state.set_preedit("にほ", Some((6, 6)));
+        assert!(state.is_dirty());
+        assert_eq!(state.preedit.as_ref().unwrap().text, "にほ");
+        assert_eq!(state.preedit.as_ref().unwrap().cursor, Some((6, 6)));
+
+        // Same composition again does not force a redraw
+        state.clear_dirty();
+        state.set_preedit("にほ", Some((6, 6)));
+        assert!(!state.is_dirty());
+
+        // Empty preedit ends the composition
+        state.set_preedit("", None);
+        assert!(state.preedit.is_none());
+        assert!(state.is_dirty());
     }
 
     #[test]
@@ -463,6 +1411,36 @@ mod tests {
         assert!(state.highlights.get(1).is_bold());
     }
 
+    #[test]
+    fn test_flush_compacts_stale_highlights() {
+        use crate::bridge::events::GridCell;
+
+        let mut state = EditorState::new(10, 2);
+        let attrs = |rgb| HighlightAttributes {
+            foreground: Some(Color::from_u24(rgb)),
+            ..Default::default()
+        };
+        for id in 1..=1100 {
+            state.hl_attr_define(id, attrs(1));
+        }
+        state.handle_redraw_event(&RedrawEvent::GridLine {
+            grid: 1,
+            row: 0,
+            col_start: 0,
+            cells: vec![GridCell {
+                text: "x".into(),
+                hl_id: Some(500),
+                repeat: 1,
+            }],
+        });
+
+        // A colorscheme change hands out IDs from 1 again.
+        state.hl_attr_define(1, attrs(2));
+        state.flush();
+        assert_eq!(state.highlights.ids(), vec![1, 500]);
+        assert_eq!(state.highlights.stats().compacted, 1098);
+    }
+
     #[test]
     fn test_default_colors_set() {
         let mut state = EditorState::new(80, 24);
@@ -474,6 +1452,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hl_group_set_redraws_everything() {
+        let mut state = EditorState::new(80, 24);
+        state.clear_dirty();
+
+        state.handle_redraw_event(&RedrawEvent::HlGroupSet {
+            name: "Pmenu".to_string(),
+            id: 7,
+        });
+        assert_eq!(state.damage(), Damage::Full);
+    }
+
     #[test]
     fn test_mode_info_set() {
         let mut state = EditorState::new(80, 24);
@@ -510,8 +1500,10 @@ mod tests {
         state.mode_info_set(modes);
         state.cursor.row = 5;
 
+        assert_eq!(state.mode_name(), "normal");
         state.mode_change("insert", 1);
         assert_eq!(state.current_mode().cursor_shape, CursorShape::Vertical);
+        assert_eq!(state.mode_name(), "insert");
     }
 
     #[test]
@@ -523,6 +1515,313 @@ mod tests {
         assert_eq!(state.grid(2).unwrap().width(), 40);
     }
 
+    #[test]
+    fn test_window_origin_resolves_float_anchor() {
+        let mut state = EditorState::new(80, 24);
+        state.grid_resize(2, 40, 20);
+        state.grid_resize(3, 10, 4);
+        state.handle_redraw_event(&RedrawEvent::WinPos {
+            grid: 2,
+            win: 1000,
+            start_row: 1,
+            start_col: 40,
+            width: 40,
+            height: 20,
+        });
+        state.handle_redraw_event(&RedrawEvent::WinFloatPos {
+            grid: 3,
+            anchor: FloatAnchor::SE,
+            anchor_grid: 2,
+            anchor_row: 10.0,
+            anchor_col: 20.0,
+            focusable: true,
+            zindex: None,
+        });
+
+        assert_eq!(state.window_origin(1), Some((0.0, 0.0)));
+        assert_eq!(state.window_origin(2), Some((1.0, 40.0)));
+        assert_eq!(state.window_origin(3), Some((7.0, 50.0)));
+        assert_eq!(state.window_origin(9), None);
+    }
+
+    #[test]
+    fn test_floats_in_z_order() {
+        let mut state = EditorState::new(80, 24);
+        state.grid_resize(2, 10, 3);
+        state.grid_resize(3, 10, 3);
+        let float = |grid, zindex| RedrawEvent::WinFloatPos {
+            grid,
+            anchor: FloatAnchor::NW,
+            anchor_grid: 1,
+            anchor_row: 2.0,
+            anchor_col: 4.5,
+            focusable: true,
+            zindex,
+        };
+        state.handle_redraw_event(&float(3, Some(60)));
+        state.handle_redraw_event(&float(2, None));
+        // Positioned but never created
+        state.handle_redraw_event(&float(4, Some(70)));
+
+        let floats = state.floats();
+        let ids: Vec<u64> = floats.iter().map(|f| f.grid.id).collect();
+        assert_eq!(ids, [2, 3]);
+        assert_eq!((floats[0].row, floats[0].col), (2.0, 4.5));
+
+        state.handle_redraw_event(&RedrawEvent::WinHide { grid: 3 });
+        assert_eq!(state.floats().len(), 1);
+    }
+
+    #[test]
+    fn test_screen_rows_overlay_message_grid() {
+        let mut state = EditorState::new(3, 4);
+        state.grid_line(1, 3, 0, &[("a".to_string(), Some(0), 3)]);
+        state.grid_resize(5, 3, 1);
+        state.grid_line(5, 0, 0, &[("m".to_string(), Some(0), 3)]);
+        let texts = |state: &EditorState| -> Vec<String> {
+            state
+                .screen_rows()
+                .map(|cells| cells.iter().map(|c| c.text.as_str()).collect())
+                .collect()
+        };
+
+        // Not shown until Neovim positions it
+        assert_eq!(texts(&state)[3], "aaa");
+
+        state.handle_redraw_event(&RedrawEvent::MsgSetPos {
+            grid: 5,
+            row: 3,
+            scrolled: true,
+            sep_char: "─".to_string(),
+        });
+        assert_eq!(texts(&state), ["   ", "   ", "   ", "mmm"]);
+        assert_eq!(state.message_area().unwrap().separator_row(), Some(2));
+
+        state.grid_destroy(5);
+        assert_eq!(state.message_area(), None);
+        assert_eq!(texts(&state)[3], "aaa");
+    }
+
+    #[test]
+    fn test_screen_rows_compose_split_windows() {
+        let mut state = EditorState::new(5, 3);
+        state.grid_line(1, 0, 0, &[("|".to_string(), Some(0), 5)]);
+        state.grid_resize(2, 2, 2);
+        state.grid_line(2, 0, 0, &[("a".to_string(), Some(0), 2)]);
+        state.grid_line(2, 1, 0, &[("b".to_string(), Some(0), 2)]);
+        let texts = |state: &EditorState| -> Vec<String> {
+            state
+                .screen_rows()
+                .map(|cells| cells.iter().map(|c| c.text.as_str()).collect())
+                .collect()
+        };
+        assert_eq!(texts(&state), ["|||||", "     ", "     "]);
+
+        // A window reaching past the screen's edge is cut off there.
+        state.handle_redraw_event(&RedrawEvent::WinPos {
+            grid: 2,
+            win: 1000,
+            start_row: 1,
+            start_col: 4,
+            width: 2,
+            height: 2,
+        });
+        assert_eq!(texts(&state), ["|||||", "    a", "    b"]);
+        assert_eq!(state.split_windows().len(), 1);
+
+        state.handle_redraw_event(&RedrawEvent::WinHide { grid: 2 });
+        assert_eq!(texts(&state)[1], "     ");
+    }
+
+    #[test]
+    fn test_zoomed_window() {
+        let mut state = EditorState::new(5, 3);
+        state.grid_line(1, 0, 0, &[("|".to_string(), Some(0), 5)]);
+        state.grid_line(1, 1, 0, &[("|".to_string(), Some(0), 5)]);
+        state.grid_resize(2, 2, 2);
+        state.grid_line(2, 0, 0, &[("a".to_string(), Some(0), 2)]);
+        state.handle_redraw_event(&RedrawEvent::WinPos {
+            grid: 2,
+            win: 1000,
+            start_row: 0,
+            start_col: 1,
+            width: 4,
+            height: 2,
+        });
+        assert!(!state.set_window_zoom(1001, 2.0));
+        assert!(state.set_window_zoom(1000, 2.0));
+
+        // Drawn over blank cells rather than into the main grid.
+        let texts: Vec<String> = state
+            .screen_rows()
+            .map(|cells| cells.iter().map(|c| c.text.as_str()).collect())
+            .collect();
+        assert_eq!(texts, ["|    ", "|    ", "     "]);
+        assert!(state.split_windows().is_empty());
+        let zoomed = state.zoomed_windows();
+        assert_eq!(zoomed.len(), 1);
+        assert_eq!((zoomed[0].grid.id, zoomed[0].zoom), (2, 2.0));
+
+        // Floats anchored to it are placed in its cells.
+        state.grid_resize(3, 1, 1);
+        state.handle_redraw_event(&RedrawEvent::WinFloatPos {
+            grid: 3,
+            anchor: FloatAnchor::NW,
+            anchor_grid: 2,
+            anchor_row: 1.0,
+            anchor_col: 1.0,
+            focusable: true,
+            zindex: None,
+        });
+        assert_eq!(state.window_origin(3), Some((2.0, 3.0)));
+
+        state.set_window_zoom(1000, 1.0);
+        assert!(state.zoomed_windows().is_empty());
+        assert_eq!(state.split_windows().len(), 1);
+    }
+
+    #[test]
+    fn test_grid_destroy_drops_placement() {
+        let mut state = EditorState::new(80, 24);
+        state.grid_resize(2, 40, 20);
+        state.handle_redraw_event(&RedrawEvent::WinPos {
+            grid: 2,
+            win: 1000,
+            start_row: 0,
+            start_col: 0,
+            width: 40,
+            height: 20,
+        });
+        state.handle_redraw_event(&RedrawEvent::GridDestroy { grid: 2 });
+
+        assert!(state.grid(2).is_none());
+        assert!(state.layout.get(2).is_none());
+    }
+
+    #[test]
+    fn test_grid_destroy_resets_references() {
+        let mut state = EditorState::new(80, 24);
+        state.grid_resize(2, 40, 20);
+        state.grid_cursor_goto(2, 3, 4);
+        state.popupmenu = Some(Popupmenu::new(Vec::new(), None, 3, 4, 2));
+
+        state.handle_redraw_event(&RedrawEvent::GridDestroy { grid: 2 });
+        assert_eq!(state.cursor.grid, 1);
+        assert!(state.popupmenu.is_none());
+
+        state.handle_redraw_event(&RedrawEvent::GridDestroy { grid: 1 });
+        assert_eq!(state.grid_count(), 1);
+    }
+
+    #[test]
+    fn test_popupmenu_lifecycle() {
+        use super::super::popupmenu::PopupmenuItem;
+
+        let mut state = EditorState::new(80, 24);
+        let items: Vec<_> = (0..30)
+            .map(|i| PopupmenuItem {
+                word: format!("w{}", i),
+                ..Default::default()
+            })
+            .collect();
+        state.handle_redraw_event(&RedrawEvent::OptionSet {
+            name: "pumheight".to_string(),
+            value: nvim_rs::Value::from(10u64),
+        });
+        state.handle_redraw_event(&RedrawEvent::PopupmenuShow {
+            items,
+            selected: None,
+            row: 2,
+            col: 5,
+            grid: 1,
+        });
+        let layout = state.popupmenu_layout().unwrap();
+        assert_eq!((layout.row, layout.col, layout.height), (3, 5, 10));
+
+        state.clear_dirty();
+        state.handle_redraw_event(&RedrawEvent::PopupmenuSelect { selected: Some(15) });
+        assert!(state.is_dirty());
+        assert_eq!(state.popupmenu_layout().unwrap().first, 6);
+
+        state.handle_redraw_event(&RedrawEvent::PopupmenuHide);
+        assert!(state.popupmenu.is_none());
+        assert!(state.popupmenu_layout().is_none());
+    }
+
+    #[test]
+    fn test_termguicolors_off_uses_cterm_colors() {
+        let mut state = EditorState::new(80, 24);
+        state.hl_attr_define(
+            1,
+            HighlightAttributes {
+                cterm_foreground: Some(9),
+                ..Default::default()
+            },
+        );
+        state.clear_dirty();
+        state.handle_redraw_event(&RedrawEvent::OptionSet {
+            name: "termguicolors".to_string(),
+            value: nvim_rs::Value::from(false),
+        });
+        assert!(state.is_dirty());
+        assert_eq!(
+            state.highlights.get(1).foreground,
+            Some(Color::from_rgb(255, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_tabline_update() {
+        let mut state = EditorState::new(80, 24);
+        state.handle_redraw_event(&RedrawEvent::TablineUpdate {
+            current: 1,
+            tabs: vec!["a".to_string(), "b".to_string()],
+        });
+        assert!(state.tabline.is_visible());
+        assert_eq!(state.tabline.current, 1);
+
+        state.clear_dirty();
+        state.set_tabs_modified(&[true, false]);
+        assert!(state.is_dirty());
+        assert!(state.tabline.tabs[0].modified);
+    }
+
+    #[test]
+    fn test_messages_and_cmdline() {
+        use super::super::messages::{StyledChunk, MESSAGE_FADE_MS, MESSAGE_TIMEOUT_MS};
+
+        let mut state = EditorState::new(80, 24);
+        let content = vec![StyledChunk {
+            hl_id: 0,
+            text: "written".to_string(),
+        }];
+        state.handle_redraw_event(&RedrawEvent::MsgShow {
+            kind: "bufwrite".to_string(),
+            content: content.clone(),
+            replace_last: false,
+            append: false,
+        });
+        state.handle_redraw_event(&RedrawEvent::CmdlineShow {
+            content,
+            pos: 0,
+            firstc: ":".to_string(),
+            prompt: String::new(),
+            indent: 0,
+            level: 1,
+        });
+        assert_eq!(state.messages.shown.len(), 1);
+        assert!(state.cmdline.active().is_some());
+
+        state.update_messages(0);
+        state.clear_dirty();
+        assert!(state.update_messages(MESSAGE_TIMEOUT_MS + MESSAGE_FADE_MS));
+        assert!(state.is_dirty());
+        assert!(state.messages.shown.is_empty());
+
+        state.handle_redraw_event(&RedrawEvent::CmdlineHide { level: 1 });
+        assert!(state.cmdline.active().is_none());
+    }
+
     #[test]
     fn test_cursor_blinking() {
         let mut state = EditorState::new(80, 24);
@@ -562,6 +1861,121 @@ mod tests {
         assert!(!state.cursor.blink_visible);
     }
 
+    #[test]
+    fn test_next_blink_deadline() {
+        let mut state = EditorState::new(80, 24);
+        state.mode_info_set(vec![ModeInfo {
+            blink_wait: 700,
+            blink_on: 400,
+            blink_off: 250,
+            ..Default::default()
+        }]);
+        assert_eq!(state.next_blink_deadline(5), Some(5));
+
+        state.update_blink(1000);
+        assert_eq!(state.next_blink_deadline(1000), Some(1700));
+        // Off at 2100, on again at 2350, off at 2750.
+        assert_eq!(state.next_blink_deadline(1700), Some(2100));
+        assert_eq!(state.next_blink_deadline(2100), Some(2350));
+        assert_eq!(state.next_blink_deadline(2400), Some(2750));
+
+        for deadline in [2100, 2350, 2750] {
+            assert!(!state.update_blink(deadline - 1));
+            assert!(state.update_blink(deadline));
+        }
+
+        state.mode_info_set(vec![ModeInfo::default()]);
+        assert_eq!(state.next_blink_deadline(3000), None);
+    }
+
+    #[test]
+    fn test_busy_hides_the_cursor() {
+        let mut state = EditorState::new(80, 24);
+        state.splash = None;
+        state.mode_info_set(vec![ModeInfo {
+            blink_on: 100,
+            blink_off: 100,
+            ..Default::default()
+        }]);
+        state.update_blink(0);
+        state.clear_dirty();
+
+        state.handle_redraw_event(&RedrawEvent::Busy { busy: true });
+        assert_eq!(state.damage(), Damage::Rows(0..1));
+        assert!(!state.update_blink(150), "nothing to blink");
+        assert_eq!(state.next_blink_deadline(150), None);
+
+        state.clear_dirty();
+        state.handle_redraw_event(&RedrawEvent::Busy { busy: false });
+        assert_eq!(state.damage(), Damage::Rows(0..1));
+        assert!(state.cursor.blink_visible);
+        assert_eq!(state.next_blink_deadline(300), Some(300));
+        state.update_blink(300);
+        assert_eq!(state.next_blink_deadline(300), Some(400));
+    }
+
+    #[test]
+    fn test_blinking_turned_off() {
+        let mut state = EditorState::new(80, 24);
+        state.cursor_blink = false;
+        state.mode_info_set(vec![ModeInfo {
+            blink_on: 100,
+            blink_off: 100,
+            ..Default::default()
+        }]);
+        state.update_blink(0);
+        assert!(!state.update_blink(150));
+        assert!(state.cursor.blink_visible);
+        assert_eq!(state.next_blink_deadline(150), None);
+    }
+
+    #[test]
+    fn test_unfocused_cursor_stops_blinking() {
+        let mut state = EditorState::new(80, 24);
+        state.mode_info_set(vec![ModeInfo {
+            blink_on: 100,
+            blink_off: 100,
+            ..Default::default()
+        }]);
+        state.update_blink(0);
+        assert!(state.update_blink(150));
+        assert!(!state.cursor.blink_visible);
+
+        state.clear_dirty();
+        state.set_focused(false);
+        assert!(state.cursor.blink_visible);
+        assert!(state.is_dirty());
+        assert!(!state.update_blink(400));
+        assert!(state.cursor.blink_visible);
+        assert_eq!(state.next_blink_deadline(400), None);
+
+        state.set_focused(true);
+        state.update_blink(500);
+        assert_eq!(state.next_blink_deadline(500), Some(600));
+    }
+
+    #[test]
+    fn test_crash_and_restart() {
+        let mut state = EditorState::new(80, 24);
+        state.handle_redraw_event(&RedrawEvent::GridResize {
+            grid: 1,
+            width: 100,
+            height: 30,
+        });
+        state.handle_redraw_event(&RedrawEvent::Flush);
+        state.set_focused(false);
+        state.perf_overlay = true;
+        state.crashed("signal: 11".to_string());
+        assert!(state.crash_prompt.is_some());
+
+        state.restart();
+        assert!(state.crash_prompt.is_none());
+        assert!(state.splash.is_some());
+        assert_eq!(state.main_grid().width(), 100);
+        assert!(!state.focused());
+        assert!(state.perf_overlay);
+    }
+
     #[test]
     fn test_handle_redraw_event() {
         let mut state = EditorState::new(80, 24);
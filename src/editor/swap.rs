@@ -0,0 +1,121 @@
+use crate::renderer::overlay::ListState;
+
+/// An answer to Neovim's "swap file found" question, as `v:swapchoice`
+/// takes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapChoice {
+    OpenReadOnly,
+    EditAnyway,
+    Recover,
+    Delete,
+    Quit,
+    Abort,
+}
+
+impl SwapChoice {
+    /// In the order Neovim's own prompt lists them.
+    pub const ALL: [SwapChoice; 6] = [
+        SwapChoice::OpenReadOnly,
+        SwapChoice::EditAnyway,
+        SwapChoice::Recover,
+        SwapChoice::Delete,
+        SwapChoice::Quit,
+        SwapChoice::Abort,
+    ];
+
+    /// The `v:swapchoice` letter, which also picks the choice in the prompt.
+    pub fn key(self) -> &'static str {
+        match self {
+            SwapChoice::OpenReadOnly => "o",
+            SwapChoice::EditAnyway => "e",
+            SwapChoice::Recover => "r",
+            SwapChoice::Delete => "d",
+            SwapChoice::Quit => "q",
+            SwapChoice::Abort => "a",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SwapChoice::OpenReadOnly => "Open read-only",
+            SwapChoice::EditAnyway => "Edit anyway",
+            SwapChoice::Recover => "Recover",
+            SwapChoice::Delete => "Delete the swap file",
+            SwapChoice::Quit => "Quit",
+            SwapChoice::Abort => "Abort",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|choice| choice.key() == key)
+    }
+}
+
+/// Modal question shown when a file being opened already has a swap file,
+/// while Neovim waits for the answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapPrompt {
+    pub file: String,
+    pub swapname: String,
+    list: ListState,
+}
+
+impl SwapPrompt {
+    pub fn new(file: String, swapname: String) -> Self {
+        Self {
+            file,
+            swapname,
+            // Safety: ALL is not empty
+            list: ListState::new(SwapChoice::ALL.len()).unwrap(),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.list.selected()
+    }
+
+    pub fn select_previous(&mut self) {
+        self.list.select_previous();
+    }
+
+    pub fn select_next(&mut self) {
+        self.list.select_next();
+    }
+
+    /// The choice numbered `index` (0-based), if there is one.
+    pub fn pick(&mut self, index: usize) -> Option<SwapChoice> {
+        self.list.pick(index).then(|| self.current())
+    }
+
+    pub fn current(&self) -> SwapChoice {
+        SwapChoice::ALL[self.list.selected()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_prompt() {
+        let mut prompt = SwapPrompt::new("/a.txt".to_string(), "/.a.txt.swp".to_string());
+        assert_eq!(prompt.current(), SwapChoice::OpenReadOnly);
+        prompt.select_previous();
+        assert_eq!(prompt.current(), SwapChoice::Abort);
+        prompt.select_next();
+        prompt.select_next();
+        assert_eq!(prompt.current(), SwapChoice::EditAnyway);
+
+        assert_eq!(prompt.pick(2), Some(SwapChoice::Recover));
+        assert_eq!(prompt.pick(6), None);
+        assert_eq!(prompt.selected(), 2);
+    }
+
+    #[test]
+    fn test_swap_choice_keys() {
+        for choice in SwapChoice::ALL {
+            assert_eq!(SwapChoice::from_key(choice.key()), Some(choice));
+        }
+        assert_eq!(SwapChoice::from_key("x"), None);
+    }
+}
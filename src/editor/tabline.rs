@@ -0,0 +1,194 @@
+use crate::config::TablineVisibility;
+
+/// Widest a tab label may get, in cells.
+const MAX_TAB_WIDTH: usize = 24;
+
+/// A Neovim tabpage from tabline_update.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tab {
+    /// Name of the buffer in the tab's current window.
+    pub name: String,
+    /// Whether any window in the tab shows a modified buffer.
+    pub modified: bool,
+}
+
+impl Tab {
+    /// Label drawn in the tab bar: the file name without its directory,
+    /// followed by `+` when modified, padded by a cell on each side.
+    pub fn label(&self) -> String {
+        let name = self
+            .name
+            .rsplit(['/', '\\'])
+            .find(|part| !part.is_empty())
+            .unwrap_or("[No Name]");
+        if self.modified {
+            format!(" {} + ", name)
+        } else {
+            format!(" {} ", name)
+        }
+    }
+}
+
+/// Cell span of a tab in the tab bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabRect {
+    pub index: usize,
+    pub col: usize,
+    pub width: usize,
+    pub label: String,
+}
+
+/// The GUI tab bar (ext_tabline), drawn in the strip above the grid.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tabline {
+    pub tabs: Vec<Tab>,
+    /// Index of the current tab.
+    pub current: usize,
+    pub visibility: TablineVisibility,
+    /// Cells kept free on the left, e.g. for the macOS window buttons.
+    pub inset_cols: usize,
+}
+
+impl Tabline {
+    /// Handles tabline_update. Modified flags are kept for tabs whose name
+    /// is unchanged until fresh ones arrive.
+    pub fn update(&mut self, current: usize, names: &[String]) {
+        let tabs = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Tab {
+                name: name.clone(),
+                modified: self
+                    .tabs
+                    .get(i)
+                    .is_some_and(|t| t.name == *name && t.modified),
+            })
+            .collect();
+        self.tabs = tabs;
+        self.current = current.min(self.tabs.len().saturating_sub(1));
+    }
+
+    /// Applies per-tab modified flags, in tab order.
+    pub fn set_modified(&mut self, modified: &[bool]) -> bool {
+        let mut changed = false;
+        for (tab, &modified) in self.tabs.iter_mut().zip(modified) {
+            changed |= tab.modified != modified;
+            tab.modified = modified;
+        }
+        changed
+    }
+
+    pub fn is_visible(&self) -> bool {
+        match self.visibility {
+            TablineVisibility::Never => false,
+            TablineVisibility::Always => !self.tabs.is_empty(),
+            TablineVisibility::Auto => self.tabs.len() > 1,
+        }
+    }
+
+    /// Lays the tabs out left to right on a bar `cols` cells wide. Labels
+    /// longer than the share each tab gets are cut with an ellipsis, and tabs
+    /// that don't fit at all are left out.
+    pub fn layout(&self, cols: usize) -> Vec<TabRect> {
+        if !self.is_visible() {
+            return Vec::new();
+        }
+
+        let available = cols.saturating_sub(self.inset_cols);
+        let share = (available / self.tabs.len()).clamp(4, MAX_TAB_WIDTH);
+        let mut col = self.inset_cols;
+        let mut rects = Vec::with_capacity(self.tabs.len());
+        for (index, tab) in self.tabs.iter().enumerate() {
+            let label = tab.label();
+            let width = label.chars().count().min(share);
+            if col + width > cols {
+                break;
+            }
+            let label = if width < label.chars().count() {
+                let mut cut: String = label.chars().take(width.saturating_sub(2)).collect();
+                cut.push_str("\u{2026} ");
+                cut
+            } else {
+                label
+            };
+            rects.push(TabRect {
+                index,
+                col,
+                width,
+                label,
+            });
+            col += width;
+        }
+        rects
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tabline(names: &[&str]) -> Tabline {
+        let mut tabline = Tabline::default();
+        let names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        tabline.update(0, &names);
+        tabline
+    }
+
+    #[test]
+    fn test_label() {
+        let tab = Tab {
+            name: "/home/me/src/main.rs".to_string(),
+            modified: true,
+        };
+        assert_eq!(tab.label(), " main.rs + ");
+        assert_eq!(Tab::default().label(), " [No Name] ");
+    }
+
+    #[test]
+    fn test_visibility() {
+        let mut single = tabline(&["a"]);
+        assert!(!single.is_visible());
+        single.visibility = TablineVisibility::Always;
+        assert!(single.is_visible());
+
+        let mut many = tabline(&["a", "b"]);
+        assert!(many.is_visible());
+        many.visibility = TablineVisibility::Never;
+        assert!(many.layout(80).is_empty());
+    }
+
+    #[test]
+    fn test_layout() {
+        let mut tabs = tabline(&["a.rs", "b.rs"]);
+        tabs.inset_cols = 8;
+        let rects = tabs.layout(80);
+
+        assert_eq!(rects.len(), 2);
+        assert_eq!((rects[0].col, rects[0].width), (8, 6));
+        assert_eq!((rects[1].col, rects[1].width), (14, 6));
+    }
+
+    #[test]
+    fn test_layout_truncates_long_names() {
+        let name = "a_really_long_file_name_that_goes_on.rs";
+        let tabs = tabline(&[name, name, name]);
+        let rects = tabs.layout(30);
+
+        assert_eq!(rects.len(), 3);
+        assert_eq!(rects[0].width, 10);
+        assert!(rects[0].label.ends_with("\u{2026} "));
+        assert_eq!(rects[0].label.chars().count(), 10);
+    }
+
+    #[test]
+    fn test_modified_flags_survive_updates() {
+        let mut tabs = tabline(&["a", "b"]);
+        assert!(tabs.set_modified(&[false, true]));
+        assert!(!tabs.set_modified(&[false, true]));
+
+        tabs.update(1, &["a".to_string(), "b".to_string(), "c".to_string()]);
+        assert!(tabs.tabs[1].modified);
+        assert!(!tabs.tabs[2].modified);
+        assert_eq!(tabs.current, 1);
+    }
+}
@@ -0,0 +1,81 @@
+/// How long the font size stays on screen after the last zoom (ms).
+pub const ZOOM_INDICATOR_TIMEOUT_MS: u64 = 1000;
+
+/// Duration of the indicator's fade-out (ms).
+pub const ZOOM_INDICATOR_FADE_MS: u64 = 300;
+
+/// The font size, shown for a moment after zooming in or out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoomIndicator {
+    /// In points.
+    pub size: f32,
+    /// Time (ms) of the first `update`.
+    shown_at: Option<u64>,
+    /// Current opacity, 1.0 until the indicator starts fading.
+    pub opacity: f32,
+}
+
+impl ZoomIndicator {
+    pub fn new(size: f32) -> Self {
+        Self {
+            size,
+            shown_at: None,
+            opacity: 1.0,
+        }
+    }
+
+    /// What to draw, such as ` 14 pt `.
+    pub fn label(&self) -> String {
+        format!(" {} pt ", self.size)
+    }
+
+    /// Advances the fade to `now` (ms). Returns true if the opacity changed;
+    /// it reaches 0.0 once the indicator is gone.
+    pub fn update(&mut self, now: u64) -> bool {
+        let shown_at = *self.shown_at.get_or_insert(now);
+        let elapsed = now.saturating_sub(shown_at);
+        let opacity = match elapsed.checked_sub(ZOOM_INDICATOR_TIMEOUT_MS) {
+            None => 1.0,
+            Some(fade) if fade >= ZOOM_INDICATOR_FADE_MS => 0.0,
+            Some(fade) => 1.0 - fade as f32 / ZOOM_INDICATOR_FADE_MS as f32,
+        };
+        let changed = opacity != self.opacity;
+        self.opacity = opacity;
+        changed
+    }
+
+    /// Time (ms) at which `update` next needs to run.
+    pub fn next_deadline(&self, now: u64) -> u64 {
+        let fade_start = self.shown_at.unwrap_or(now) + ZOOM_INDICATOR_TIMEOUT_MS;
+        if now < fade_start {
+            fade_start
+        } else {
+            // Step through the fade at roughly display rate
+            now + 16
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indicator_fades_out() {
+        let mut indicator = ZoomIndicator::new(15.0);
+        assert_eq!(indicator.label(), " 15 pt ");
+        assert!(!indicator.update(1000));
+        assert_eq!(
+            indicator.next_deadline(1000),
+            1000 + ZOOM_INDICATOR_TIMEOUT_MS
+        );
+
+        let half_faded = 1000 + ZOOM_INDICATOR_TIMEOUT_MS + ZOOM_INDICATOR_FADE_MS / 2;
+        assert!(indicator.update(half_faded));
+        assert!((indicator.opacity - 0.5).abs() < 0.01);
+        assert_eq!(indicator.next_deadline(half_faded), half_faded + 16);
+
+        assert!(indicator.update(half_faded + ZOOM_INDICATOR_FADE_MS));
+        assert_eq!(indicator.opacity, 0.0);
+    }
+}
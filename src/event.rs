@@ -1,9 +1,15 @@
-use std::sync::Arc;
+use std::fmt;
+use std::sync::{mpsc, Arc, Mutex};
+use tokio::sync::oneshot;
 use winit::dpi::PhysicalSize;
 use winit::event::KeyEvent;
+use winit::event_loop::{EventLoopClosed, EventLoopProxy};
 use winit::window::Window;
 
 use crate::bridge::events::RedrawEvent;
+use crate::bridge::{Capabilities, RequestId, Response};
+use crate::daemon::LaunchRequest;
+use crate::window::dialogs::{FileChoice, FileDialog};
 
 #[derive(Debug, Clone)]
 pub enum UserEvent {
@@ -11,10 +17,105 @@ pub enum UserEvent {
     GUI(GUIEvent),
 }
 
+/// Where the bridge delivers its events: the window's event loop, or a
+/// plain channel when there is no window, as in `gui.nvim render`.
+#[derive(Clone)]
+pub enum EventSender {
+    EventLoop(EventLoopProxy<UserEvent>),
+    Channel(mpsc::Sender<UserEvent>),
+}
+
+impl EventSender {
+    /// Fails once the receiving end is gone, dropping the event.
+    pub fn send_event(&self, event: UserEvent) -> Result<(), EventLoopClosed<()>> {
+        match self {
+            EventSender::EventLoop(proxy) => {
+                proxy.send_event(event).map_err(|_| EventLoopClosed(()))
+            }
+            EventSender::Channel(sender) => sender.send(event).map_err(|_| EventLoopClosed(())),
+        }
+    }
+}
+
+impl From<EventLoopProxy<UserEvent>> for EventSender {
+    fn from(proxy: EventLoopProxy<UserEvent>) -> Self {
+        EventSender::EventLoop(proxy)
+    }
+}
+
+impl From<mpsc::Sender<UserEvent>> for EventSender {
+    fn from(sender: mpsc::Sender<UserEvent>) -> Self {
+        EventSender::Channel(sender)
+    }
+}
+
+/// Answers a request Neovim is blocked on. Events are cloned, so the
+/// answer goes to whichever copy sends first; later sends are dropped.
+pub struct Reply<T>(Arc<Mutex<Option<oneshot::Sender<T>>>>);
+
+impl<T> Reply<T> {
+    pub fn new() -> (Self, oneshot::Receiver<T>) {
+        let (sender, receiver) = oneshot::channel();
+        (Self(Arc::new(Mutex::new(Some(sender)))), receiver)
+    }
+
+    pub fn send(&self, value: T) {
+        let sender = self.0.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(sender) = sender {
+            let _ = sender.send(value);
+        }
+    }
+}
+
+impl<T> Clone for Reply<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> fmt::Debug for Reply<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Reply")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum NeovimEvent {
     Redraw(Vec<RedrawEvent>),
-    Quit,
+    /// The editor core applied redraws; see `EditorCore::take_update`.
+    EditorUpdated,
+    Capabilities(Capabilities),
+    /// A `:Gui*` command invoked in Neovim.
+    GuiCommand {
+        name: String,
+        args: Vec<nvim_rs::Value>,
+    },
+    /// Per-tabpage modified flags, answering `AppCommand::QueryTabsModified`.
+    TabsModified(Vec<bool>),
+    /// A file being opened has a swap file. Neovim waits for the
+    /// `v:swapchoice` letter to go back through `reply`.
+    SwapExists {
+        file: String,
+        swapname: String,
+        reply: Reply<String>,
+    },
+    /// The answer to `AppBridge::request`.
+    Response {
+        id: RequestId,
+        result: Response,
+    },
+    /// `:confirm qa` returned with Neovim still running: the user kept
+    /// the session open.
+    QuitCancelled,
+    /// The connection to a `--server` dropped; the GUI may reconnect.
+    Disconnected,
+    /// The embedded Neovim crashed; the GUI may restart it.
+    Exited {
+        status: std::process::ExitStatus,
+    },
+    /// The session is over, with the code for gui.nvim to exit with:
+    /// Neovim's own exit code, such as `:cquit`'s 1.
+    Quit(i32),
 }
 
 #[derive(Debug, Clone)]
@@ -25,30 +126,154 @@ pub enum GUIEvent {
     KeyboardInput(KeyEvent),
     ScaleFactorChanged(f64),
     Focused(bool),
+    /// An item was chosen from the native menu bar.
+    Menu(MenuAction),
+    /// `gui.nvim file` handed its files to this daemon.
+    Launch(LaunchRequest),
+    /// `gui.nvim --remote file` handed its files to this window.
+    Open(LaunchRequest),
+    /// A file picker from `:GuiOpen` or `:GuiSaveAs` was closed.
+    FileChosen(FileDialog, FileChoice),
+}
+
+/// Menu bar items handled by the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    NewWindow,
+    Open,
+    SaveAs,
+    Quit,
+    Cut,
+    Copy,
+    Paste,
+    SelectAll,
+    ZoomIn,
+    ZoomOut,
+}
+
+impl MenuAction {
+    pub const ALL: [MenuAction; 10] = [
+        MenuAction::NewWindow,
+        MenuAction::Open,
+        MenuAction::SaveAs,
+        MenuAction::Quit,
+        MenuAction::Cut,
+        MenuAction::Copy,
+        MenuAction::Paste,
+        MenuAction::SelectAll,
+        MenuAction::ZoomIn,
+        MenuAction::ZoomOut,
+    ];
+
+    /// Identifies the action in a menu item's tag.
+    pub fn tag(self) -> isize {
+        Self::ALL.iter().position(|&a| a == self).unwrap_or(0) as isize
+    }
+
+    pub fn from_tag(tag: isize) -> Option<Self> {
+        Self::ALL.get(usize::try_from(tag).ok()?).copied()
+    }
+
+    /// Keys that perform an Edit menu action in Neovim's `mode` (a
+    /// mode_change name), going through the `+` register. `None` if the
+    /// action does nothing there, or isn't an Edit action.
+    pub fn keys(self, mode: &str) -> Option<&'static str> {
+        let visual = matches!(mode, "visual" | "visual_select");
+        let insert = matches!(mode, "insert" | "replace");
+        let cmdline = mode.starts_with("cmdline");
+        match self {
+            MenuAction::Cut if visual => Some("\"+x"),
+            MenuAction::Copy if visual => Some("\"+y"),
+            MenuAction::Paste => match mode {
+                "normal" => Some("\"+gP"),
+                "terminal" => Some("<C-\\><C-n>\"+pi"),
+                _ if visual => Some("\"+P"),
+                _ if insert => Some("<C-r><C-o>+"),
+                _ if cmdline => Some("<C-r><C-r>+"),
+                _ => None,
+            },
+            MenuAction::SelectAll => Some("<C-\\><C-n>ggVG"),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_reply_answers_once() {
+        let (reply, mut answer) = Reply::new();
+        let copy = reply.clone();
+        copy.send("e".to_string());
+        reply.send("q".to_string());
+        assert_eq!(answer.try_recv().unwrap(), "e");
+    }
+
     #[test]
     fn test_user_event_debug() {
         let event = UserEvent::Neovim(NeovimEvent::Redraw(vec![]));
         assert!(format!("{:?}", event).contains("Redraw"));
     }
 
+    #[test]
+    fn test_channel_sender() {
+        let (tx, rx) = mpsc::channel();
+        let sender = EventSender::from(tx);
+        assert!(sender
+            .send_event(UserEvent::Neovim(NeovimEvent::Quit(0)))
+            .is_ok());
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(UserEvent::Neovim(NeovimEvent::Quit(0)))
+        ));
+        drop(rx);
+        assert!(sender
+            .send_event(UserEvent::Neovim(NeovimEvent::Quit(0)))
+            .is_err());
+    }
+
     #[test]
     fn test_gui_event_debug() {
         let event = GUIEvent::Focused(true);
         assert!(format!("{:?}", event).contains("Focused"));
     }
 
+    #[test]
+    fn test_menu_action_tags() {
+        for action in MenuAction::ALL {
+            assert_eq!(MenuAction::from_tag(action.tag()), Some(action));
+        }
+        assert_eq!(MenuAction::from_tag(-1), None);
+        assert_eq!(MenuAction::from_tag(MenuAction::ALL.len() as isize), None);
+    }
+
+    #[test]
+    fn test_menu_action_keys() {
+        assert_eq!(MenuAction::Copy.keys("visual"), Some("\"+y"));
+        assert_eq!(MenuAction::Copy.keys("normal"), None);
+        assert_eq!(MenuAction::Cut.keys("visual_select"), Some("\"+x"));
+        assert_eq!(MenuAction::Paste.keys("normal"), Some("\"+gP"));
+        assert_eq!(MenuAction::Paste.keys("insert"), Some("<C-r><C-o>+"));
+        assert_eq!(
+            MenuAction::Paste.keys("cmdline_normal"),
+            Some("<C-r><C-r>+")
+        );
+        assert_eq!(MenuAction::Paste.keys("operator"), None);
+        assert_eq!(
+            MenuAction::SelectAll.keys("insert"),
+            Some("<C-\\><C-n>ggVG")
+        );
+        assert_eq!(MenuAction::ZoomIn.keys("normal"), None);
+    }
+
     #[test]
     fn test_neovim_event_variants() {
         let redraw = NeovimEvent::Redraw(vec![]);
-        let quit = NeovimEvent::Quit;
+        let quit = NeovimEvent::Quit(1);
 
         assert!(matches!(redraw, NeovimEvent::Redraw(_)));
-        assert!(matches!(quit, NeovimEvent::Quit));
+        assert!(matches!(quit, NeovimEvent::Quit(1)));
     }
 }
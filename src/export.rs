@@ -0,0 +1,607 @@
+//! `:GuiExport html|pdf`: the screen as drawn, written to a file with its
+//! colors and text styles.
+//!
+//! Both formats lay the screen out in the renderer's cells: every run of
+//! text is placed at its column and sized to its cells, so columns line up
+//! whatever font the HTML is viewed with. The PDF draws text in Courier,
+//! stretched to the cell width, as it embeds no font; characters outside
+//! Latin-1 come out as `?` there.
+
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::editor::{Cell, Color, EditorState, HighlightAttributes, StyleFlags, UnderlineStyle};
+use crate::snapshot::utc_timestamp;
+
+/// CSS pixels to PDF points.
+const PT_PER_PX: f32 = 0.75;
+
+/// Courier's advance, in ems.
+const COURIER_ADVANCE: f32 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Html,
+    Pdf,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "html" => Some(Self::Html),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// The renderer's cell and font, in logical pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportMetrics {
+    pub cell_width: f32,
+    pub cell_height: f32,
+    /// From the top of a cell down to the baseline.
+    pub ascent: f32,
+    pub font_size: f32,
+    pub family: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("No documents or home directory to export to")]
+    NoDir,
+
+    #[error("Failed to write {path:?}: {source}")]
+    Write { path: PathBuf, source: io::Error },
+}
+
+/// Where exports go without a path, and what relative paths are from.
+pub fn exports_dir() -> Option<PathBuf> {
+    dirs::document_dir().or_else(dirs::home_dir)
+}
+
+/// Writes the screen as `format` to `path`, or to a timestamped file in
+/// the documents directory. Returns the path written.
+pub fn save(
+    format: ExportFormat,
+    path: Option<&str>,
+    state: &EditorState,
+    metrics: &ExportMetrics,
+    title: &str,
+) -> Result<PathBuf, ExportError> {
+    let path = match path {
+        Some(path) => resolve(path).ok_or(ExportError::NoDir)?,
+        None => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            let name = format!(
+                "gui-nvim-{}.{}",
+                utc_timestamp(now.as_secs()),
+                format.extension()
+            );
+            exports_dir().ok_or(ExportError::NoDir)?.join(name)
+        }
+    };
+    let screen = Screen::new(state);
+    let contents = match format {
+        ExportFormat::Html => html(&screen, metrics, title).into_bytes(),
+        ExportFormat::Pdf => pdf(&screen, metrics),
+    };
+    std::fs::write(&path, contents).map_err(|source| ExportError::Write {
+        path: path.clone(),
+        source,
+    })?;
+    Ok(path)
+}
+
+/// `path` with `~/` expanded, and relative to the exports directory.
+fn resolve(path: &str) -> Option<PathBuf> {
+    if let Some(rest) = path.strip_prefix("~/") {
+        return dirs::home_dir().map(|home| home.join(rest));
+    }
+    let path = Path::new(path);
+    if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else {
+        exports_dir().map(|dir| dir.join(path))
+    }
+}
+
+/// A stretch of cells drawn alike: the same highlight, and no wide
+/// character unless it is one on its own.
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    col: usize,
+    cols: usize,
+    text: String,
+    hl: u64,
+}
+
+/// How a run is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RunStyle {
+    fg: Color,
+    bg: Color,
+    sp: Color,
+    style: StyleFlags,
+    underline: UnderlineStyle,
+}
+
+impl RunStyle {
+    fn new(attrs: &HighlightAttributes, state: &EditorState) -> Self {
+        let defaults = &state.highlights.defaults;
+        Self {
+            fg: attrs.effective_fg(defaults),
+            bg: attrs.effective_bg(defaults),
+            sp: attrs.effective_special(defaults),
+            style: attrs.style,
+            underline: attrs.underline_style(),
+        }
+    }
+}
+
+/// The screen as runs, with floating windows drawn over the grid.
+struct Screen {
+    cols: usize,
+    rows: Vec<Vec<(Run, RunStyle)>>,
+    fg: Color,
+    bg: Color,
+}
+
+impl Screen {
+    fn new(state: &EditorState) -> Self {
+        let mut cells: Vec<Vec<Cell>> = state.screen_rows().map(|row| row.into_owned()).collect();
+        for float in state.floats() {
+            let (top, left) = (float.row.round() as usize, float.col.round() as usize);
+            for (row, float_cells) in float.grid.rows().enumerate() {
+                let Some(screen_row) = cells.get_mut(top + row) else {
+                    break;
+                };
+                let width = float_cells.len().min(screen_row.len().saturating_sub(left));
+                screen_row[left..left + width].clone_from_slice(&float_cells[..width]);
+            }
+        }
+        let defaults = &state.highlights.defaults;
+        Self {
+            cols: state.main_grid().width(),
+            rows: cells
+                .iter()
+                .map(|row| {
+                    runs(row)
+                        .into_iter()
+                        .map(|run| {
+                            let style = RunStyle::new(state.highlights.get(run.hl), state);
+                            (run, style)
+                        })
+                        .collect()
+                })
+                .collect(),
+            fg: defaults.foreground,
+            bg: defaults.background,
+        }
+    }
+}
+
+fn runs(cells: &[Cell]) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for (col, cell) in cells.iter().enumerate() {
+        if cell.is_wide_spacer() {
+            continue;
+        }
+        let text = if cell.text.is_empty() {
+            " "
+        } else {
+            &cell.text
+        };
+        let cols = if cell.is_wide() { 2 } else { 1 };
+        match runs.last_mut() {
+            Some(run) if run.hl == cell.highlight_id && cols == 1 && run.cols == run_chars(run) => {
+                run.cols += 1;
+                run.text.push_str(text);
+            }
+            _ => runs.push(Run {
+                col,
+                cols,
+                text: text.to_string(),
+                hl: cell.highlight_id,
+            }),
+        }
+    }
+    runs
+}
+
+/// Cells a run would take at one per character; a wide character's run
+/// takes more and is left alone.
+fn run_chars(run: &Run) -> usize {
+    run.text.chars().count()
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:06x}", color.0 >> 8)
+}
+
+fn html_escape(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn html(screen: &Screen, metrics: &ExportMetrics, title: &str) -> String {
+    let mut out =
+        String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>");
+    html_escape(title, &mut out);
+    let mut family = String::new();
+    html_escape(&metrics.family.replace('\'', ""), &mut family);
+    let _ = write!(
+        out,
+        "</title>\n<style>\n\
+         body {{ margin: 0; background: {bg}; }}\n\
+         pre {{ margin: 0; width: {width}px; color: {fg}; background: {bg}; \
+         font: {size}px/{height}px '{family}', monospace; }}\n\
+         pre span {{ display: inline-block; height: {height}px; overflow: hidden; \
+         white-space: pre; vertical-align: top; }}\n\
+         </style>\n</head>\n<body>\n<pre>",
+        bg = hex(screen.bg),
+        fg = hex(screen.fg),
+        width = screen.cols as f32 * metrics.cell_width,
+        size = metrics.font_size,
+        height = metrics.cell_height,
+        family = family,
+    );
+    for (i, row) in screen.rows.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        for (run, style) in row {
+            let _ = write!(
+                out,
+                "<span style=\"width: {}px; color: {}; background: {}",
+                run.cols as f32 * metrics.cell_width,
+                hex(style.fg),
+                hex(style.bg)
+            );
+            if style.style.contains(StyleFlags::BOLD) {
+                out.push_str("; font-weight: bold");
+            }
+            if style.style.contains(StyleFlags::ITALIC) {
+                out.push_str("; font-style: italic");
+            }
+            let line = match style.underline {
+                UnderlineStyle::None => None,
+                UnderlineStyle::Single => Some("underline solid"),
+                UnderlineStyle::Double => Some("underline double"),
+                UnderlineStyle::Curl => Some("underline wavy"),
+                UnderlineStyle::Dotted => Some("underline dotted"),
+                UnderlineStyle::Dashed => Some("underline dashed"),
+            };
+            let strike = style.style.contains(StyleFlags::STRIKETHROUGH);
+            match (line, strike) {
+                (Some(line), true) => {
+                    let _ = write!(
+                        out,
+                        "; text-decoration: {} line-through {}",
+                        line,
+                        hex(style.sp)
+                    );
+                }
+                (Some(line), false) => {
+                    let _ = write!(out, "; text-decoration: {} {}", line, hex(style.sp));
+                }
+                (None, true) => out.push_str("; text-decoration: line-through"),
+                (None, false) => {}
+            }
+            out.push_str("\">");
+            html_escape(&run.text, &mut out);
+            out.push_str("</span>");
+        }
+    }
+    out.push_str("</pre>\n</body>\n</html>\n");
+    out
+}
+
+/// `text` as a PDF string in WinAnsi encoding.
+fn pdf_string(text: &str) -> String {
+    let mut out = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            // Latin-1 is where WinAnsi and Unicode agree.
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            _ => out.push('?'),
+        }
+    }
+    out.push(')');
+    out
+}
+
+fn pdf_color(color: Color) -> String {
+    let [r, g, b, _] = color.0.to_be_bytes();
+    format!(
+        "{:.3} {:.3} {:.3}",
+        f32::from(r) / 255.0,
+        f32::from(g) / 255.0,
+        f32::from(b) / 255.0
+    )
+}
+
+fn pdf_font(style: StyleFlags) -> &'static str {
+    match (
+        style.contains(StyleFlags::BOLD),
+        style.contains(StyleFlags::ITALIC),
+    ) {
+        (false, false) => "/F1",
+        (true, false) => "/F2",
+        (false, true) => "/F3",
+        (true, true) => "/F4",
+    }
+}
+
+/// The page's drawing: backgrounds, then text and its lines.
+fn pdf_content(screen: &Screen, metrics: &ExportMetrics) -> String {
+    let cell_w = metrics.cell_width * PT_PER_PX;
+    let cell_h = metrics.cell_height * PT_PER_PX;
+    let page_w = screen.cols as f32 * cell_w;
+    let page_h = screen.rows.len() as f32 * cell_h;
+    let size = metrics.font_size * PT_PER_PX;
+    // Stretches Courier's advance to the cell.
+    let scale = 100.0 * cell_w / (COURIER_ADVANCE * size);
+    let line_width = (size / 14.0).max(0.5);
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{} rg 0 0 {:.2} {:.2} re f",
+        pdf_color(screen.bg),
+        page_w,
+        page_h
+    );
+    for (row, runs) in screen.rows.iter().enumerate() {
+        let top = page_h - row as f32 * cell_h;
+        for (run, style) in runs {
+            if style.bg != screen.bg {
+                let _ = writeln!(
+                    out,
+                    "{} rg {:.2} {:.2} {:.2} {:.2} re f",
+                    pdf_color(style.bg),
+                    run.col as f32 * cell_w,
+                    top - cell_h,
+                    run.cols as f32 * cell_w,
+                    cell_h
+                );
+            }
+        }
+    }
+    for (row, runs) in screen.rows.iter().enumerate() {
+        let top = page_h - row as f32 * cell_h;
+        let baseline = top - metrics.ascent * PT_PER_PX;
+        for (run, style) in runs {
+            let x = run.col as f32 * cell_w;
+            let width = run.cols as f32 * cell_w;
+            if !run.text.trim().is_empty() {
+                // A wide character is stretched over both its cells.
+                let scale = scale * run.cols as f32 / run_chars(run).max(1) as f32;
+                let _ = writeln!(
+                    out,
+                    "BT {} {:.2} Tf {:.1} Tz {} rg {:.2} {:.2} Td {} Tj ET",
+                    pdf_font(style.style),
+                    size,
+                    scale,
+                    pdf_color(style.fg),
+                    x,
+                    baseline,
+                    pdf_string(&run.text)
+                );
+            }
+            let mut lines = Vec::new();
+            if style.underline != UnderlineStyle::None {
+                lines.push((baseline - line_width * 1.5, style.sp));
+            }
+            if style.underline == UnderlineStyle::Double {
+                lines.push((baseline - line_width * 3.5, style.sp));
+            }
+            if style.style.contains(StyleFlags::STRIKETHROUGH) {
+                lines.push((baseline + size * 0.3, style.fg));
+            }
+            for (y, color) in lines {
+                let _ = writeln!(
+                    out,
+                    "{} RG {:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+                    pdf_color(color),
+                    line_width,
+                    x,
+                    y,
+                    x + width,
+                    y
+                );
+            }
+        }
+    }
+    out
+}
+
+fn pdf(screen: &Screen, metrics: &ExportMetrics) -> Vec<u8> {
+    let content = pdf_content(screen, metrics);
+    let page_w = screen.cols as f32 * metrics.cell_width * PT_PER_PX;
+    let page_h = screen.rows.len() as f32 * metrics.cell_height * PT_PER_PX;
+    let font = |name: &str| {
+        format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+            name
+        )
+    };
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R /F2 6 0 R /F3 7 0 R /F4 8 0 R >> >> >>",
+            page_w, page_h
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ),
+        font("Courier"),
+        font("Courier-Bold"),
+        font("Courier-Oblique"),
+        font("Courier-BoldOblique"),
+    ];
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        let _ = writeln!(out, "{} 0 obj\n{}\nendobj", i + 1, object);
+    }
+    let xref = out.len();
+    let _ = writeln!(out, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(out, "{:010} 00000 n ", offset);
+    }
+    let _ = writeln!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF",
+        objects.len() + 1,
+        xref
+    );
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::events::{GridCell, RedrawEvent};
+
+    fn metrics() -> ExportMetrics {
+        ExportMetrics {
+            cell_width: 8.0,
+            cell_height: 16.0,
+            ascent: 12.0,
+            font_size: 14.0,
+            family: "JetBrains Mono".to_string(),
+        }
+    }
+
+    fn state() -> EditorState {
+        let mut state = EditorState::new(6, 2);
+        state.handle_redraw_event(&RedrawEvent::HlAttrDefine {
+            id: 3,
+            attrs: HighlightAttributes {
+                foreground: Some(Color::from_u24(0xff8000)),
+                style: StyleFlags::BOLD | StyleFlags::UNDERCURL,
+                ..Default::default()
+            },
+        });
+        state.handle_redraw_event(&RedrawEvent::GridLine {
+            grid: 1,
+            row: 0,
+            col_start: 0,
+            cells: vec![
+                GridCell {
+                    text: "<a".into(),
+                    hl_id: Some(0),
+                    repeat: 1,
+                },
+                GridCell {
+                    text: "b".into(),
+                    hl_id: Some(3),
+                    repeat: 2,
+                },
+                GridCell {
+                    text: "界".into(),
+                    hl_id: Some(3),
+                    repeat: 1,
+                },
+                GridCell {
+                    text: "".into(),
+                    hl_id: Some(3),
+                    repeat: 1,
+                },
+            ],
+        });
+        state
+    }
+
+    #[test]
+    fn test_runs_split_at_highlights_and_wide_chars() {
+        let screen = Screen::new(&state());
+        let runs: Vec<_> = screen.rows[0]
+            .iter()
+            .map(|(run, _)| (run.col, run.cols, run.text.as_str(), run.hl))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                (0, 1, "<a", 0),
+                (1, 2, "bb", 3),
+                (3, 2, "界", 3),
+                (5, 1, " ", 0)
+            ]
+        );
+        let (_, style) = &screen.rows[0][1];
+        assert_eq!(style.fg, Color::from_u24(0xff8000));
+        assert_eq!(style.underline, UnderlineStyle::Curl);
+        assert_eq!(screen.rows.len(), 2);
+    }
+
+    #[test]
+    fn test_html_export() {
+        let html = html(&Screen::new(&state()), &metrics(), "a & b");
+        assert!(html.contains("<title>a &amp; b</title>"));
+        assert!(html.contains("font: 14px/16px 'JetBrains Mono', monospace;"));
+        assert!(html.contains(
+            "<span style=\"width: 16px; color: #ff8000; background: #1e1e1e; \
+             font-weight: bold; text-decoration: underline wavy #ff8000\">bb</span>"
+        ));
+        assert!(html.contains(">&lt;a</span>"));
+        assert_eq!(html.matches("<span").count(), 4 + 1);
+    }
+
+    #[test]
+    fn test_pdf_strings() {
+        assert_eq!(pdf_string("a(b)\\"), "(a\\(b\\)\\\\)");
+        assert_eq!(pdf_string("é界"), "(\\351?)");
+    }
+
+    #[test]
+    fn test_pdf_export() {
+        let pdf = String::from_utf8(pdf(&Screen::new(&state()), &metrics())).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        // 6 by 2 cells of 8 by 16 pixels, in points.
+        assert!(pdf.contains("/MediaBox [0 0 36.00 24.00]"));
+        assert!(pdf.contains("/F2 10.50 Tf 95.2 Tz 1.000 0.502 0.000 rg 6.00 15.00 Td (bb) Tj ET"));
+
+        // Each object is where the cross-reference table says.
+        let xref = pdf.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let xref: usize = pdf[xref..].lines().next().unwrap().parse().unwrap();
+        assert!(pdf[xref..].starts_with("xref\n0 9\n"));
+        for (i, line) in pdf[xref..].lines().skip(3).take(8).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! `gui.nvim gpu-info`: reports every GPU adapter, the limits it offers
+//! and how the renderer would size itself on it, for bug reports about
+//! GPUs that fail to start or draw.
+
+use crate::renderer::{GpuContext, GpuSupport};
+
+pub fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    let adapters = runtime.block_on(GpuContext::headless_adapters());
+    if adapters.is_empty() {
+        return Err("No GPU adapter found".into());
+    }
+
+    println!("gui.nvim {}", env!("CARGO_PKG_VERSION"));
+    for adapter in &adapters {
+        println!();
+        for line in GpuSupport::new(adapter).lines() {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
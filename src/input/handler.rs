@@ -1,15 +1,62 @@
 use winit::dpi::PhysicalPosition;
-use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta};
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, Touch, TouchPhase};
+use winit::keyboard::ModifiersKeyState;
+use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
 
 use crate::bridge::AppBridge;
+use crate::config::{KeyEncoding, OptionAsMeta};
+use crate::editor::TabRect;
 use crate::input::{
-    key_event_to_neovim, modifiers_to_string, mouse_button_to_type, pixel_to_grid,
-    scroll_delta_to_direction, CellMetrics, Modifiers, MouseAction, MouseState,
+    is_fullscreen_key, is_snapshot_key, key_event_to_neovim, menu_key, modifiers_to_string,
+    mouse_button_to_type, pixel_to_window, scroll_delta_to_direction, tab_shortcut, tabline_hit,
+    GridPosition, Keybinds, MenuKey, Modifiers, MouseAction, MouseButtonType, MouseState,
+    ScrollAccumulator, ScrollDirection, TabShortcut, TouchScroll, ZoomAccumulator, ZoomedArea,
 };
+use crate::layout::WindowLayout;
+
+/// Where a drag of the Visual selection out of the window is at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SelectionDrag {
+    None,
+    /// Pressed in this cell; moving to another one starts the drag.
+    Pressed(GridPosition),
+    /// Handed to the system, which tracks the pointer until the drop.
+    Started,
+}
 
 pub struct InputHandler {
     modifiers: Modifiers,
     mouse_state: MouseState,
+    /// Set when the attached Neovim supports precise trackpad scrolling.
+    analog_scroll: bool,
+    scroll_accumulator: ScrollAccumulator,
+    zoom_accumulator: ZoomAccumulator,
+    touch_scroll: TouchScroll,
+    /// Pointer position in physical pixels, for hit-testing outside the grid.
+    pointer: Option<PhysicalPosition<f64>>,
+    /// A press went to the tab bar, so its release must not reach Neovim.
+    tabline_pressed: bool,
+    /// A press opened a link; its release is kept from Neovim too.
+    link_pressed: bool,
+    /// A Cmd-press inside the Visual selection, dragging its text out.
+    selection_drag: SelectionDrag,
+    /// `[ui] primary_selection`: middle clicks paste the primary selection.
+    primary_selection: bool,
+    /// A middle press pasted the primary selection; its release is kept
+    /// from Neovim too.
+    primary_pressed: bool,
+    /// Neovim takes the mouse in the current mode (mouse_on/mouse_off).
+    mouse_enabled: bool,
+    /// 'mousemoveevent': report moves without a button held.
+    mouse_move_events: bool,
+    keybinds: Keybinds,
+    key_encoding: KeyEncoding,
+    /// `[keyboard] macos_option_is_meta`.
+    option_as_meta: OptionAsMeta,
+    /// Which of the Option (Alt) keys are held, left and right.
+    option_keys: (bool, bool),
+    /// Zoomed windows, whose cells the pointer is mapped to by itself.
+    zoomed: Vec<ZoomedArea>,
 }
 
 impl Default for InputHandler {
@@ -23,15 +70,121 @@ impl InputHandler {
         Self {
             modifiers: Modifiers::default(),
             mouse_state: MouseState::new(),
+            analog_scroll: false,
+            scroll_accumulator: ScrollAccumulator::default(),
+            zoom_accumulator: ZoomAccumulator::default(),
+            touch_scroll: TouchScroll::default(),
+            pointer: None,
+            tabline_pressed: false,
+            link_pressed: false,
+            selection_drag: SelectionDrag::None,
+            primary_selection: false,
+            primary_pressed: false,
+            mouse_enabled: true,
+            mouse_move_events: false,
+            keybinds: Keybinds::default(),
+            key_encoding: KeyEncoding::default(),
+            option_as_meta: OptionAsMeta::default(),
+            option_keys: (false, false),
+            zoomed: Vec::new(),
         }
     }
 
+    pub fn set_keybinds(&mut self, keybinds: Keybinds) {
+        self.keybinds = keybinds;
+    }
+
+    pub fn set_key_encoding(&mut self, encoding: KeyEncoding) {
+        self.key_encoding = encoding;
+    }
+
+    pub fn set_option_as_meta(&mut self, option: OptionAsMeta) {
+        self.option_as_meta = option;
+    }
+
+    pub fn set_primary_selection(&mut self, enabled: bool) {
+        self.primary_selection = enabled;
+    }
+
+    pub fn set_zoomed_windows(&mut self, zoomed: Vec<ZoomedArea>) {
+        self.zoomed = zoomed;
+    }
+
+    pub fn set_mouse_enabled(&mut self, enabled: bool) {
+        self.mouse_enabled = enabled;
+    }
+
+    pub fn set_mouse_move_events(&mut self, enabled: bool) {
+        self.mouse_move_events = enabled;
+    }
+
+    pub fn set_analog_scroll(&mut self, enabled: bool) {
+        self.analog_scroll = enabled;
+        self.scroll_accumulator.reset();
+    }
+
     pub fn handle_modifiers_changed(&mut self, state: winit::event::Modifiers) {
         self.modifiers = Modifiers::from(state.state());
+        self.option_keys = (
+            state.lalt_state() == ModifiersKeyState::Pressed,
+            state.ralt_state() == ModifiersKeyState::Pressed,
+        );
+    }
+
+    pub fn modifiers(&self) -> &Modifiers {
+        &self.modifiers
+    }
+
+    /// Returns the menu navigation this key press triggers, if any.
+    pub fn menu_key(&self, event: &KeyEvent) -> Option<MenuKey> {
+        if event.state != ElementState::Pressed {
+            return None;
+        }
+        menu_key(&event.logical_key, &self.modifiers)
+    }
+
+    /// The keys `[keybinds]` sends to Neovim for this key press, if any.
+    pub fn keybind(&self, event: &KeyEvent) -> Option<&str> {
+        if event.state != ElementState::Pressed {
+            return None;
+        }
+        // Shift+] binds as `shift+]` whatever the layout types for it.
+        self.keybinds
+            .lookup(&event.key_without_modifiers(), &self.modifiers)
+    }
+
+    /// Whether this key press takes a snapshot (F12).
+    pub fn is_snapshot_key(&self, event: &KeyEvent) -> bool {
+        event.state == ElementState::Pressed && is_snapshot_key(&event.logical_key, &self.modifiers)
+    }
+
+    /// Whether this key press toggles fullscreen (F11, Cmd+Ctrl+F on macOS).
+    pub fn is_fullscreen_key(&self, event: &KeyEvent) -> bool {
+        event.state == ElementState::Pressed
+            && is_fullscreen_key(&event.key_without_modifiers(), &self.modifiers)
+    }
+
+    /// Returns the native tab shortcut this key press triggers, if any.
+    pub fn tab_shortcut(&self, event: &KeyEvent) -> Option<TabShortcut> {
+        if event.state != ElementState::Pressed {
+            return None;
+        }
+        tab_shortcut(&event.physical_key, &self.modifiers)
+    }
+
+    /// Whether the Option keys held type Meta chords.
+    fn option_is_meta(&self) -> bool {
+        let (left, right) = self.option_keys;
+        self.option_as_meta.applies(left, right)
     }
 
     pub fn handle_keyboard_input(&self, event: &KeyEvent, bridge: &AppBridge) {
-        if let Some(keys) = key_event_to_neovim(event, &self.modifiers) {
+        if let Some(keys) = key_event_to_neovim(
+            event,
+            &self.modifiers,
+            self.key_encoding,
+            self.option_is_meta(),
+        ) {
             log::trace!("Keyboard input: {}", keys);
             bridge.input(keys);
         }
@@ -61,7 +214,7 @@ impl InputHandler {
                     button_type.as_str(),
                     action.as_str(),
                     &modifier_str,
-                    0,
+                    grid_pos.grid,
                     grid_pos.row,
                     grid_pos.col,
                 );
@@ -69,50 +222,508 @@ impl InputHandler {
         }
     }
 
+    /// Switches tabs when a tab in the GUI tab bar is clicked. Returns true
+    /// if the event was consumed and must not be forwarded as mouse input.
+    pub fn handle_tabline_click(
+        &mut self,
+        state: ElementState,
+        button: MouseButton,
+        layout: &WindowLayout,
+        tabs: &[TabRect],
+        bridge: &AppBridge,
+    ) -> bool {
+        match state {
+            ElementState::Pressed if button == MouseButton::Left => {
+                let Some(index) = self
+                    .pointer
+                    .and_then(|position| tabline_hit(position, layout, tabs))
+                else {
+                    return false;
+                };
+                bridge.input(format!("<Cmd>tabnext {}<CR>", index + 1));
+                self.tabline_pressed = true;
+                true
+            }
+            ElementState::Released if button == MouseButton::Left => {
+                std::mem::take(&mut self.tabline_pressed)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the modifier of GUI mouse shortcuts is held: Cmd on macOS,
+    /// Ctrl elsewhere.
+    fn command_held(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            self.modifiers.logo
+        } else {
+            self.modifiers.ctrl
+        }
+    }
+
+    /// Opens links on Ctrl-click, or Cmd-click on macOS, when `on_link`.
+    /// Returns true if the event was consumed: the caller opens the link on
+    /// the press, and neither it nor its release is forwarded.
+    pub fn handle_link_click(
+        &mut self,
+        state: ElementState,
+        button: MouseButton,
+        on_link: bool,
+    ) -> bool {
+        match state {
+            ElementState::Pressed if button == MouseButton::Left => {
+                self.link_pressed = self.command_held() && on_link;
+                self.link_pressed
+            }
+            ElementState::Released if button == MouseButton::Left => {
+                std::mem::take(&mut self.link_pressed)
+            }
+            _ => false,
+        }
+    }
+
+    /// Takes a Cmd-press, or Ctrl-press off macOS, `in_selection` for a
+    /// drag of the selected text out of the window. Returns true if the
+    /// event was consumed: neither it nor its release is forwarded.
+    pub fn handle_selection_drag_press(
+        &mut self,
+        state: ElementState,
+        button: MouseButton,
+        in_selection: bool,
+    ) -> bool {
+        if button != MouseButton::Left {
+            return false;
+        }
+        match state {
+            ElementState::Pressed => {
+                self.selection_drag = match self.mouse_state.last_position {
+                    Some(cell) if in_selection && self.command_held() => {
+                        SelectionDrag::Pressed(cell)
+                    }
+                    _ => SelectionDrag::None,
+                };
+                self.selection_drag != SelectionDrag::None
+            }
+            ElementState::Released => {
+                std::mem::replace(&mut self.selection_drag, SelectionDrag::None)
+                    != SelectionDrag::None
+            }
+        }
+    }
+
+    /// Whether the pointer left the cell a selection drag was pressed in,
+    /// so the drag is to start. True once per press.
+    pub fn take_selection_drag(&mut self, layout: &WindowLayout) -> bool {
+        let SelectionDrag::Pressed(pressed) = self.selection_drag else {
+            return false;
+        };
+        match self.pointer_cell(layout) {
+            Some(cell) if cell == pressed => false,
+            _ => {
+                self.selection_drag = SelectionDrag::Started;
+                true
+            }
+        }
+    }
+
+    /// Pastes the text `primary` returns at the pointer on a middle click,
+    /// with `[ui] primary_selection`, the way X11 programs do. Returns true
+    /// if the event was consumed; without a selection, the click goes to
+    /// Neovim as usual.
+    pub fn handle_primary_paste(
+        &mut self,
+        state: ElementState,
+        button: MouseButton,
+        bridge: &AppBridge,
+        primary: impl FnOnce() -> Option<String>,
+    ) -> bool {
+        if button != MouseButton::Middle {
+            return false;
+        }
+        match state {
+            ElementState::Pressed => {
+                if !self.primary_selection || !self.mouse_enabled {
+                    return false;
+                }
+                let Some(grid_pos) = self.mouse_state.last_position else {
+                    return false;
+                };
+                let Some(text) = primary() else {
+                    return false;
+                };
+                // A plain click moves the cursor to the pointer first.
+                for action in [MouseAction::Press, MouseAction::Release] {
+                    bridge.mouse_input(
+                        MouseButtonType::Left.as_str(),
+                        action.as_str(),
+                        "",
+                        grid_pos.grid,
+                        grid_pos.row,
+                        grid_pos.col,
+                    );
+                }
+                bridge.paste(text);
+                self.primary_pressed = true;
+                true
+            }
+            ElementState::Released => std::mem::take(&mut self.primary_pressed),
+        }
+    }
+
+    pub fn handle_cursor_left(&mut self) {
+        self.pointer = None;
+    }
+
+    /// The grid cell under the mouse pointer, if it is over the grid rather
+    /// than the padding or tab bar.
+    pub fn pointer_cell(&self, layout: &WindowLayout) -> Option<GridPosition> {
+        self.pointer
+            .filter(|p| p.x >= layout.padding_left && p.y >= layout.padding_top)
+            .map(|p| pixel_to_window(p, layout, &self.zoomed))
+    }
+
     pub fn handle_cursor_moved(
         &mut self,
         position: PhysicalPosition<f64>,
-        cell_metrics: &CellMetrics,
+        layout: &WindowLayout,
         bridge: &AppBridge,
     ) {
-        let grid_pos = pixel_to_grid(position, cell_metrics);
+        self.pointer = Some(position);
+        let grid_pos = pixel_to_window(position, layout, &self.zoomed);
         let old_pos = self.mouse_state.last_position;
         self.mouse_state.update_position(grid_pos);
 
-        if self.mouse_state.is_dragging()
-            && old_pos
-                .map(|p| p.row != grid_pos.row || p.col != grid_pos.col)
-                .unwrap_or(true)
-        {
+        // One event per cell entered, however many pixels the pointer moved.
+        let cell_changed = old_pos.map(|p| p != grid_pos).unwrap_or(true);
+        if !cell_changed {
+            return;
+        }
+
+        let modifier_str = modifiers_to_string(&self.modifiers);
+        if self.mouse_state.is_dragging() {
             if let Some(button_type) = self.mouse_state.pressed_button {
-                let modifier_str = modifiers_to_string(&self.modifiers);
                 bridge.mouse_input(
                     button_type.as_str(),
                     MouseAction::Drag.as_str(),
                     &modifier_str,
-                    0,
+                    grid_pos.grid,
                     grid_pos.row,
                     grid_pos.col,
                 );
             }
+        } else if self.mouse_move_events && self.mouse_enabled {
+            // `<MouseMove>`: Neovim takes "move" as the button and ignores
+            // the action.
+            bridge.mouse_input(
+                "move",
+                MouseAction::Move.as_str(),
+                &modifier_str,
+                grid_pos.grid,
+                grid_pos.row,
+                grid_pos.col,
+            );
         }
     }
 
-    pub fn handle_mouse_wheel(&self, delta: MouseScrollDelta, bridge: &AppBridge) {
-        if let Some(grid_pos) = self.mouse_state.last_position {
-            if let Some((direction, count)) = scroll_delta_to_direction(delta) {
-                let modifier_str = modifiers_to_string(&self.modifiers);
-                for _ in 0..count {
-                    bridge.mouse_input(
-                        "wheel",
-                        direction.as_str(),
-                        &modifier_str,
-                        0,
-                        grid_pos.row,
-                        grid_pos.col,
-                    );
+    /// Trackpad scroll accumulated but not yet sent, in pixels. Drawing the
+    /// grid shifted by this much makes scrolling smooth.
+    pub fn scroll_offset(&self, cell_height: f64) -> f64 {
+        self.scroll_accumulator.pending_lines() * cell_height
+    }
+
+    /// Zooms on Ctrl+wheel, or Cmd+wheel on macOS. Returns the font size
+    /// steps this wheel event makes, positive zooming in, or None if it
+    /// scrolls as usual.
+    pub fn handle_zoom_wheel(
+        &mut self,
+        delta: MouseScrollDelta,
+        phase: TouchPhase,
+        cell_height: f64,
+    ) -> Option<i32> {
+        if !self.command_held() {
+            self.zoom_accumulator.reset();
+            return None;
+        }
+        let steps = self.zoom_accumulator.accumulate(delta, cell_height);
+        if matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled) {
+            self.zoom_accumulator.reset();
+        }
+        Some(steps)
+    }
+
+    /// Zooms on a trackpad pinch. Returns the font size steps it makes;
+    /// `steps_per_size` is the current size in steps.
+    pub fn handle_pinch(&mut self, delta: f64, phase: TouchPhase, steps_per_size: f64) -> i32 {
+        if phase == TouchPhase::Started {
+            self.zoom_accumulator.reset();
+        }
+        self.zoom_accumulator
+            .accumulate_pinch(delta, steps_per_size)
+    }
+
+    pub fn handle_mouse_wheel(
+        &mut self,
+        delta: MouseScrollDelta,
+        phase: TouchPhase,
+        cell_height: f64,
+        bridge: &AppBridge,
+    ) {
+        match delta {
+            MouseScrollDelta::PixelDelta(d) if self.analog_scroll => {
+                let ended = matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled);
+                self.scroll_pixels(d, ended, cell_height, bridge);
+            }
+            _ => self.send_wheel(scroll_delta_to_direction(delta), bridge),
+        }
+    }
+
+    /// Scrolls by a finger dragged on a touchscreen, which goes to Neovim
+    /// as wheel events the way trackpad scrolling does. `now` is in ms.
+    pub fn handle_touch(
+        &mut self,
+        touch: Touch,
+        now: u64,
+        layout: &WindowLayout,
+        bridge: &AppBridge,
+    ) {
+        match touch.phase {
+            TouchPhase::Started => {
+                self.settle_touch_scroll(bridge);
+                self.touch_scroll.start(touch.id, touch.location, now);
+                self.mouse_state.update_position(pixel_to_window(
+                    touch.location,
+                    layout,
+                    &self.zoomed,
+                ));
+            }
+            TouchPhase::Moved => {
+                if let Some(delta) = self.touch_scroll.moved(touch.id, touch.location, now) {
+                    self.scroll_pixels(delta, false, layout.cell_height, bridge);
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                let cancelled = touch.phase == TouchPhase::Cancelled;
+                let scrolling = self.touch_scroll.ended(touch.id, now, cancelled);
+                if scrolling && !self.touch_scroll.is_coasting() {
+                    self.settle_touch_scroll(bridge);
                 }
             }
         }
     }
+
+    /// Carries a touch swipe on to `now` (ms). Returns when (ms) to call
+    /// again, or None once it has come to rest.
+    pub fn coast_touch_scroll(
+        &mut self,
+        now: u64,
+        cell_height: f64,
+        bridge: &AppBridge,
+    ) -> Option<u64> {
+        let (delta, rest) = self.touch_scroll.coast(now)?;
+        self.scroll_pixels(delta, rest, cell_height, bridge);
+        self.touch_scroll.next_deadline()
+    }
+
+    /// Ends touch scrolling on a whole line.
+    fn settle_touch_scroll(&mut self, bridge: &AppBridge) {
+        let rest = self.scroll_accumulator.settle();
+        self.send_wheel(rest, bridge);
+    }
+
+    /// Scrolls by `delta` pixels, carrying what falls short of a line over
+    /// to the next. Once the gesture (and its momentum) is over (`ended`),
+    /// scrolls to the nearest line so the grid rests unshifted.
+    fn scroll_pixels(
+        &mut self,
+        delta: PhysicalPosition<f64>,
+        ended: bool,
+        cell_height: f64,
+        bridge: &AppBridge,
+    ) {
+        let scroll = self.scroll_accumulator.accumulate(delta, cell_height);
+        let rest = ended.then(|| self.scroll_accumulator.settle()).flatten();
+        self.send_wheel(scroll.into_iter().chain(rest), bridge);
+    }
+
+    fn send_wheel(
+        &self,
+        scroll: impl IntoIterator<Item = (ScrollDirection, u32)>,
+        bridge: &AppBridge,
+    ) {
+        let Some(grid_pos) = self.mouse_state.last_position else {
+            return;
+        };
+        let modifier_str = modifiers_to_string(&self.modifiers);
+        for (direction, count) in scroll {
+            for _ in 0..count {
+                bridge.mouse_input(
+                    "wheel",
+                    direction.as_str(),
+                    &modifier_str,
+                    grid_pos.grid,
+                    grid_pos.row,
+                    grid_pos.col,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::AppCommand;
+
+    fn move_to(handler: &mut InputHandler, bridge: &AppBridge, x: f64, y: f64) {
+        let layout = WindowLayout::default();
+        handler.handle_cursor_moved(PhysicalPosition::new(x, y), &layout, bridge);
+    }
+
+    #[test]
+    fn test_mouse_move_events() {
+        let (bridge, mut commands) = AppBridge::new_for_test();
+        let mut handler = InputHandler::new();
+        let layout = WindowLayout::default();
+        let (x, y) = (layout.padding_left + 1.0, layout.padding_top + 1.0);
+
+        move_to(&mut handler, &bridge, x, y);
+        assert!(commands.try_recv().is_err(), "off by default");
+
+        handler.set_mouse_move_events(true);
+        move_to(&mut handler, &bridge, x + layout.cell_width, y);
+        assert_eq!(
+            commands.try_recv().unwrap(),
+            AppCommand::MouseInput {
+                button: "move".into(),
+                action: "move".into(),
+                modifier: String::new(),
+                grid: 0,
+                row: 0,
+                col: 1,
+            }
+        );
+        // Still inside the same cell
+        move_to(&mut handler, &bridge, x + layout.cell_width + 2.0, y);
+        assert!(commands.try_recv().is_err());
+
+        handler.set_mouse_enabled(false);
+        move_to(&mut handler, &bridge, x, y + layout.cell_height);
+        assert!(commands.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_primary_paste() {
+        let (bridge, mut commands) = AppBridge::new_for_test();
+        let mut handler = InputHandler::new();
+        let layout = WindowLayout::default();
+        move_to(
+            &mut handler,
+            &bridge,
+            layout.padding_left + layout.cell_width * 2.5,
+            layout.padding_top + layout.cell_height * 1.5,
+        );
+        let selection = || Some("text".to_string());
+
+        let pressed = ElementState::Pressed;
+        let (middle, left) = (MouseButton::Middle, MouseButton::Left);
+        assert!(!handler.handle_primary_paste(pressed, middle, &bridge, selection));
+
+        handler.set_primary_selection(true);
+        assert!(!handler.handle_primary_paste(pressed, left, &bridge, selection));
+        assert!(
+            !handler.handle_primary_paste(pressed, middle, &bridge, || None),
+            "nothing selected"
+        );
+        assert!(commands.try_recv().is_err());
+
+        assert!(handler.handle_primary_paste(pressed, middle, &bridge, selection));
+        for action in ["press", "release"] {
+            assert_eq!(
+                commands.try_recv().unwrap(),
+                AppCommand::MouseInput {
+                    button: "left".into(),
+                    action: action.into(),
+                    modifier: String::new(),
+                    grid: 0,
+                    row: 1,
+                    col: 2,
+                }
+            );
+        }
+        assert_eq!(
+            commands.try_recv().unwrap(),
+            AppCommand::Paste("text".into())
+        );
+        assert!(handler.handle_primary_paste(ElementState::Released, middle, &bridge, selection));
+        assert!(!handler.handle_primary_paste(ElementState::Released, middle, &bridge, selection));
+    }
+
+    #[test]
+    fn test_touch_drag_scrolls() {
+        let (bridge, mut commands) = AppBridge::new_for_test();
+        let mut handler = InputHandler::new();
+        let layout = WindowLayout::default();
+        let touch = |phase, y| Touch {
+            device_id: winit::event::DeviceId::dummy(),
+            phase,
+            location: PhysicalPosition::new(layout.padding_left + 1.0, y),
+            force: None,
+            id: 7,
+        };
+        let top = layout.padding_top + 1.0;
+        handler.handle_touch(touch(TouchPhase::Started, top), 0, &layout, &bridge);
+        // Two and a half lines down, then lifted slowly: no momentum.
+        let y = top + layout.cell_height * 2.5;
+        handler.handle_touch(touch(TouchPhase::Moved, y), 10_000, &layout, &bridge);
+        handler.handle_touch(touch(TouchPhase::Ended, y), 20_000, &layout, &bridge);
+        assert_eq!(
+            handler.coast_touch_scroll(20_016, layout.cell_height, &bridge),
+            None
+        );
+
+        let wheel: Vec<_> = std::iter::from_fn(|| commands.try_recv().ok())
+            .map(|command| match command {
+                AppCommand::MouseInput {
+                    button,
+                    action,
+                    row,
+                    ..
+                } => (button, action, row),
+                command => panic!("{:?}", command),
+            })
+            .collect();
+        // Two lines, and the half rounds up to a third on lifting.
+        assert_eq!(wheel, vec![("wheel".into(), "up".into(), 0); 3]);
+    }
+
+    #[test]
+    fn test_selection_drag() {
+        let (bridge, mut commands) = AppBridge::new_for_test();
+        let mut handler = InputHandler::new();
+        let layout = WindowLayout::default();
+        let (x, y) = (layout.padding_left + 1.0, layout.padding_top + 1.0);
+        move_to(&mut handler, &bridge, x, y);
+        let (pressed, released) = (ElementState::Pressed, ElementState::Released);
+        let left = MouseButton::Left;
+
+        assert!(
+            !handler.handle_selection_drag_press(pressed, left, true),
+            "no modifier"
+        );
+        handler.modifiers.ctrl = cfg!(not(target_os = "macos"));
+        handler.modifiers.logo = cfg!(target_os = "macos");
+        assert!(!handler.handle_selection_drag_press(pressed, left, false));
+        assert!(handler.handle_selection_drag_press(pressed, left, true));
+
+        // Moving within the pressed cell doesn't drag yet.
+        move_to(&mut handler, &bridge, x + 1.0, y);
+        assert!(!handler.take_selection_drag(&layout));
+        move_to(&mut handler, &bridge, x + layout.cell_width, y);
+        assert!(handler.take_selection_drag(&layout));
+        assert!(!handler.take_selection_drag(&layout), "once per press");
+        assert!(commands.try_recv().is_err(), "nothing reaches Neovim");
+
+        assert!(handler.handle_selection_drag_press(released, left, true));
+        assert!(!handler.handle_selection_drag_press(released, left, true));
+    }
 }
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use winit::keyboard::{Key, NamedKey};
+
+use super::keyboard::Modifiers;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum KeybindError {
+    #[error("Unknown modifier {0:?}")]
+    UnknownModifier(String),
+
+    #[error("Unknown key {0:?}")]
+    UnknownKey(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ChordKey {
+    /// A printable key, lowercase, as typed without modifiers.
+    Char(String),
+    Named(NamedKey),
+}
+
+/// A key with the exact set of modifiers that must be held, such as
+/// `cmd+shift+]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    key: ChordKey,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    logo: bool,
+}
+
+impl KeyChord {
+    /// Parses `mod+mod+key`. Modifiers are `ctrl`, `shift`, `alt` (or
+    /// `option`) and `cmd` (or `super`, `win`); keys are a single character
+    /// as typed without Shift, or a name like `tab`, `enter` or `f5`.
+    pub fn parse(chord: &str) -> Result<Self, KeybindError> {
+        let chord = chord.trim().to_lowercase();
+        // `ctrl++` binds the plus key itself.
+        let (mods, key) = match chord.strip_suffix("++") {
+            Some(mods) => (mods, "+"),
+            None => match chord.rsplit_once('+') {
+                Some((mods, key)) => (mods, key),
+                None => ("", chord.as_str()),
+            },
+        };
+
+        let mut parsed = Self {
+            key: chord_key(key).ok_or_else(|| KeybindError::UnknownKey(key.to_string()))?,
+            ctrl: false,
+            shift: false,
+            alt: false,
+            logo: false,
+        };
+        for modifier in mods.split('+').filter(|m| !m.is_empty()) {
+            let flag = match modifier {
+                "ctrl" | "control" => &mut parsed.ctrl,
+                "shift" => &mut parsed.shift,
+                "alt" | "option" | "opt" => &mut parsed.alt,
+                "cmd" | "command" | "super" | "win" | "logo" => &mut parsed.logo,
+                other => return Err(KeybindError::UnknownModifier(other.to_string())),
+            };
+            *flag = true;
+        }
+        Ok(parsed)
+    }
+
+    /// Whether `key`, as typed without modifiers, and `modifiers` make this
+    /// chord.
+    pub fn matches(&self, key: &Key, modifiers: &Modifiers) -> bool {
+        let key_matches = match (&self.key, key) {
+            (ChordKey::Char(c), Key::Character(typed)) => typed.to_lowercase() == *c,
+            (ChordKey::Named(named), Key::Named(typed)) => named == typed,
+            _ => false,
+        };
+        key_matches
+            && self.ctrl == modifiers.ctrl
+            && self.shift == modifiers.shift
+            && self.alt == modifiers.alt
+            && self.logo == modifiers.logo
+    }
+}
+
+fn chord_key(key: &str) -> Option<ChordKey> {
+    let named = match key {
+        "enter" | "return" | "cr" => NamedKey::Enter,
+        "tab" => NamedKey::Tab,
+        "space" => NamedKey::Space,
+        "backspace" | "bs" => NamedKey::Backspace,
+        "esc" | "escape" => NamedKey::Escape,
+        "delete" | "del" => NamedKey::Delete,
+        "insert" => NamedKey::Insert,
+        "home" => NamedKey::Home,
+        "end" => NamedKey::End,
+        "pageup" => NamedKey::PageUp,
+        "pagedown" => NamedKey::PageDown,
+        "up" => NamedKey::ArrowUp,
+        "down" => NamedKey::ArrowDown,
+        "left" => NamedKey::ArrowLeft,
+        "right" => NamedKey::ArrowRight,
+        "f1" => NamedKey::F1,
+        "f2" => NamedKey::F2,
+        "f3" => NamedKey::F3,
+        "f4" => NamedKey::F4,
+        "f5" => NamedKey::F5,
+        "f6" => NamedKey::F6,
+        "f7" => NamedKey::F7,
+        "f8" => NamedKey::F8,
+        "f9" => NamedKey::F9,
+        "f10" => NamedKey::F10,
+        "f11" => NamedKey::F11,
+        "f12" => NamedKey::F12,
+        key if key.chars().count() == 1 => return Some(ChordKey::Char(key.to_string())),
+        _ => return None,
+    };
+    Some(ChordKey::Named(named))
+}
+
+/// GUI shortcuts from the `[keybinds]` config table, each sending its keys
+/// to Neovim in place of the shortcut. Unbound keys go to Neovim as usual.
+#[derive(Clone, Debug, Default)]
+pub struct Keybinds {
+    bindings: Vec<(KeyChord, String)>,
+}
+
+impl Keybinds {
+    /// Parses the config table. Bindings that don't parse are logged and
+    /// skipped, so one typo doesn't cost the rest.
+    pub fn from_config(table: &BTreeMap<String, String>) -> Self {
+        let bindings = table
+            .iter()
+            .filter_map(|(chord, keys)| match KeyChord::parse(chord) {
+                Ok(parsed) => Some((parsed, keys.clone())),
+                Err(e) => {
+                    log::warn!("Ignoring keybind {:?}: {}", chord, e);
+                    None
+                }
+            })
+            .collect();
+        Self { bindings }
+    }
+
+    /// The keys bound to this key press, in Neovim's key notation.
+    pub fn lookup(&self, key: &Key, modifiers: &Modifiers) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|(chord, _)| chord.matches(key, modifiers))
+            .map(|(_, keys)| keys.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mods(ctrl: bool, shift: bool, alt: bool, logo: bool) -> Modifiers {
+        Modifiers {
+            ctrl,
+            shift,
+            alt,
+            logo,
+        }
+    }
+
+    #[test]
+    fn test_parse_chord() {
+        let chord = KeyChord::parse("Cmd+Shift+]").unwrap();
+        assert!(chord.matches(&Key::Character("]".into()), &mods(false, true, false, true)));
+        assert!(!chord.matches(
+            &Key::Character("]".into()),
+            &mods(false, false, false, true)
+        ));
+
+        let chord = KeyChord::parse("ctrl++").unwrap();
+        assert!(chord.matches(
+            &Key::Character("+".into()),
+            &mods(true, false, false, false)
+        ));
+
+        let chord = KeyChord::parse("alt+f5").unwrap();
+        assert!(chord.matches(&Key::Named(NamedKey::F5), &mods(false, false, true, false)));
+
+        assert_eq!(
+            KeyChord::parse("hyper+t"),
+            Err(KeybindError::UnknownModifier("hyper".into()))
+        );
+        assert_eq!(
+            KeyChord::parse("cmd+nope"),
+            Err(KeybindError::UnknownKey("nope".into()))
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let table = BTreeMap::from([
+            ("cmd+t".to_string(), ":tabnew<CR>".to_string()),
+            ("cmd+bogus".to_string(), "x".to_string()),
+        ]);
+        let keybinds = Keybinds::from_config(&table);
+        let cmd = mods(false, false, false, true);
+
+        assert_eq!(
+            keybinds.lookup(&Key::Character("T".into()), &cmd),
+            Some(":tabnew<CR>")
+        );
+        assert_eq!(keybinds.lookup(&Key::Character("w".into()), &cmd), None);
+        assert_eq!(
+            keybinds.lookup(&Key::Character("t".into()), &Modifiers::default()),
+            None
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use winit::event::{ElementState, KeyEvent};
-use winit::keyboard::{Key, KeyCode, ModifiersState, NamedKey, PhysicalKey};
+use winit::keyboard::{Key, KeyCode, KeyLocation, ModifiersState, NamedKey, PhysicalKey};
+use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
+
+use crate::config::KeyEncoding;
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Modifiers {
@@ -20,12 +23,99 @@ impl From<ModifiersState> for Modifiers {
     }
 }
 
-pub fn key_event_to_neovim(event: &KeyEvent, modifiers: &Modifiers) -> Option<String> {
+/// `option_is_meta`: on macOS, whether the Option key held is to type
+/// Meta chords (see `option_key`).
+pub fn key_event_to_neovim(
+    event: &KeyEvent,
+    modifiers: &Modifiers,
+    encoding: KeyEncoding,
+    option_is_meta: bool,
+) -> Option<String> {
     if event.state != ElementState::Pressed {
         return None;
     }
+    let base = event.key_without_modifiers();
+    let (logical, modifiers) = if cfg!(target_os = "macos") {
+        option_key(
+            &event.logical_key,
+            &base,
+            &event.physical_key,
+            modifiers,
+            option_is_meta,
+        )
+    } else {
+        (event.logical_key.clone(), *modifiers)
+    };
+    match encoding {
+        KeyEncoding::Standard => key_to_neovim(&logical, &event.physical_key, &modifiers),
+        KeyEncoding::Extended => extended_key_to_neovim(
+            &logical,
+            &base,
+            &event.physical_key,
+            event.location,
+            &modifiers,
+        ),
+    }
+}
+
+/// The key and modifiers to report for a press with Option held, on macOS,
+/// where Option types characters: Option+f is ƒ. As Meta (`meta`), it is
+/// the key pressed, so `<M-f>`; otherwise the character typed, without
+/// `M-`. Keys that type nothing, such as the arrows, keep it either way.
+/// Option+e and the like start an accent instead; as Meta they are the key
+/// pressed too, taken from `physical` if the layout doesn't say.
+pub fn option_key(
+    logical: &Key,
+    base: &Key,
+    physical: &PhysicalKey,
+    modifiers: &Modifiers,
+    meta: bool,
+) -> (Key, Modifiers) {
+    let dead = matches!(logical, Key::Dead(_));
+    let (Key::Character(_) | Key::Dead(_), true) = (logical, modifiers.alt) else {
+        return (logical.clone(), *modifiers);
+    };
+    if meta {
+        let key = match base {
+            Key::Character(_) => base.clone(),
+            _ if dead => dead_key_character(physical).unwrap_or_else(|| logical.clone()),
+            _ => logical.clone(),
+        };
+        return (key, *modifiers);
+    }
+    if dead {
+        return (logical.clone(), *modifiers);
+    }
+    let modifiers = Modifiers {
+        alt: false,
+        ..*modifiers
+    };
+    (logical.clone(), modifiers)
+}
+
+/// The character under the keys Option turns into accents on the US
+/// layouts: ´ ¨ ˆ ˜ and `.
+fn dead_key_character(physical: &PhysicalKey) -> Option<Key> {
+    let c = match physical {
+        PhysicalKey::Code(KeyCode::KeyE) => "e",
+        PhysicalKey::Code(KeyCode::KeyU) => "u",
+        PhysicalKey::Code(KeyCode::KeyI) => "i",
+        PhysicalKey::Code(KeyCode::KeyN) => "n",
+        PhysicalKey::Code(KeyCode::Backquote) => "`",
+        _ => return None,
+    };
+    Some(Key::Character(c.into()))
+}
 
-    let (key_str, is_special) = match &event.logical_key {
+/// The key notation for pressing `logical` (or `physical`, where the layout
+/// gives no logical key). Split from `key_event_to_neovim` because a
+/// `KeyEvent` can only come from winit.
+pub fn key_to_neovim(
+    logical: &Key,
+    physical: &PhysicalKey,
+    modifiers: &Modifiers,
+) -> Option<String> {
+    let (key_str, is_special) = match logical {
         Key::Named(named) => (named_key_to_str(*named)?, true),
         Key::Character(c) => {
             let s = c.as_str();
@@ -37,13 +127,73 @@ pub fn key_event_to_neovim(event: &KeyEvent, modifiers: &Modifiers) -> Option<St
             }
             (s.to_string(), false)
         }
-        Key::Unidentified(_) => return try_physical_key(&event.physical_key, modifiers),
+        Key::Unidentified(_) => return try_physical_key(physical, modifiers),
         Key::Dead(_) => return None,
     };
 
     format_with_modifiers(&key_str, modifiers, is_special)
 }
 
+/// `[keyboard] key_encoding = "extended"`: like `key_to_neovim`, but tells
+/// apart keys the standard notation folds together, the way the kitty
+/// keyboard protocol does for terminals. The keypad sends `<kEnter>`,
+/// `<k1>` and so on rather than what they type, a space keeps its
+/// modifiers (`<S-Space>`), and a Ctrl chord the layout turns into a
+/// control character is spelled with the key pressed (`<C-S-a>`, `<C-[>`)
+/// instead of being dropped. `base` is the key without modifiers applied.
+pub fn extended_key_to_neovim(
+    logical: &Key,
+    base: &Key,
+    physical: &PhysicalKey,
+    location: KeyLocation,
+    modifiers: &Modifiers,
+) -> Option<String> {
+    // With Num Lock off the keypad moves the cursor; those keep their names.
+    let keypad_input = matches!(logical, Key::Character(_) | Key::Named(NamedKey::Enter));
+    if location == KeyLocation::Numpad && keypad_input {
+        if let Some(name) = keypad_key_to_str(physical) {
+            return format_with_modifiers(&name, modifiers, true);
+        }
+    }
+    match logical {
+        Key::Character(c) if c == " " => format_with_modifiers("Space", modifiers, true),
+        Key::Character(c) if c.chars().all(char::is_control) => match base {
+            Key::Character(b) if !b.chars().any(char::is_control) => format_character(b, modifiers),
+            _ => try_physical_key(physical, modifiers),
+        },
+        Key::Character(c) => format_character(c, modifiers),
+        _ => key_to_neovim(logical, physical, modifiers),
+    }
+}
+
+/// `text` typed with `modifiers`, with the characters key notation gives a
+/// meaning spelled by name, as in `<C-lt>` and `<M-Bar>`.
+fn format_character(text: &str, modifiers: &Modifiers) -> Option<String> {
+    let name = match text {
+        "<" => "lt",
+        "\\" => "Bslash",
+        "|" => "Bar",
+        _ => return format_with_modifiers(text, modifiers, false),
+    };
+    if modifiers.ctrl || modifiers.alt || modifiers.logo {
+        format_with_modifiers(name, modifiers, true)
+    } else {
+        Some(escape_text(text))
+    }
+}
+
+/// Neovim's name for a keypad key.
+fn keypad_key_to_str(physical: &PhysicalKey) -> Option<String> {
+    match physical {
+        PhysicalKey::Code(KeyCode::NumpadEnter) => Some("kEnter".to_string()),
+        PhysicalKey::Code(KeyCode::NumpadComma) => Some("kComma".to_string()),
+        PhysicalKey::Code(KeyCode::NumpadEqual) => Some("kEqual".to_string()),
+        // The digits and operators are named the same as elsewhere.
+        PhysicalKey::Code(code) => physical_keycode_to_str(*code).filter(|s| s.starts_with('k')),
+        PhysicalKey::Unidentified(_) => None,
+    }
+}
+
 fn named_key_to_str(key: NamedKey) -> Option<String> {
     let s = match key {
         NamedKey::Enter => "CR",
@@ -153,6 +303,9 @@ fn physical_keycode_to_str(code: KeyCode) -> Option<String> {
 }
 
 fn format_with_modifiers(key: &str, modifiers: &Modifiers, is_special: bool) -> Option<String> {
+    if key.is_empty() {
+        return None;
+    }
     let has_modifiers = modifiers.ctrl || modifiers.alt || modifiers.logo;
     let shift_relevant = modifiers.shift && (is_special || has_modifiers);
 
@@ -161,7 +314,7 @@ fn format_with_modifiers(key: &str, modifiers: &Modifiers, is_special: bool) ->
             return Some(format!("<{}>", key));
         }
         // Handle special characters that need escaping
-        return Some(escape_literal(key));
+        return Some(escape_text(key));
     }
 
     let mut prefix = String::new();
@@ -178,7 +331,111 @@ fn format_with_modifiers(key: &str, modifiers: &Modifiers, is_special: bool) ->
         prefix.push_str("S-");
     }
 
-    Some(format!("<{}{}>", prefix, key))
+    if is_special {
+        return Some(format!("<{}{}>", prefix, key));
+    }
+    // `<C-...>` holds a single character, so text of several (from a
+    // compose sequence, say) becomes one modified key per character.
+    Some(
+        key.chars()
+            .map(|ch| format!("<{}{}>", prefix, ch))
+            .collect(),
+    )
+}
+
+/// Native window tab navigation requested from the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TabShortcut {
+    Previous,
+    Next,
+}
+
+/// Matches the macOS tab switching shortcuts, Cmd-Shift-[ and Cmd-Shift-].
+/// Uses the physical key since Shift turns the brackets into braces.
+pub fn tab_shortcut(physical: &PhysicalKey, modifiers: &Modifiers) -> Option<TabShortcut> {
+    if !(modifiers.logo && modifiers.shift) || modifiers.ctrl || modifiers.alt {
+        return None;
+    }
+    match physical {
+        PhysicalKey::Code(KeyCode::BracketLeft) => Some(TabShortcut::Previous),
+        PhysicalKey::Code(KeyCode::BracketRight) => Some(TabShortcut::Next),
+        _ => None,
+    }
+}
+
+/// Whether the key takes a snapshot for bug reports: F12 without
+/// modifiers. Neovim still gets F12 with any modifier held.
+pub fn is_snapshot_key(key: &Key, modifiers: &Modifiers) -> bool {
+    let plain = !(modifiers.ctrl || modifiers.alt || modifiers.logo || modifiers.shift);
+    plain && *key == Key::Named(NamedKey::F12)
+}
+
+/// Whether the key toggles fullscreen: Cmd+Ctrl+F on macOS, F11 without
+/// modifiers elsewhere. `key` is the key without modifiers applied.
+pub fn is_fullscreen_key(key: &Key, modifiers: &Modifiers) -> bool {
+    if cfg!(target_os = "macos") {
+        let chord = modifiers.logo && modifiers.ctrl && !(modifiers.alt || modifiers.shift);
+        chord && matches!(key, Key::Character(c) if c.eq_ignore_ascii_case("f"))
+    } else {
+        let plain = !(modifiers.ctrl || modifiers.alt || modifiers.logo || modifiers.shift);
+        plain && *key == Key::Named(NamedKey::F11)
+    }
+}
+
+/// Navigation in GUI-drawn menus such as the startup dashboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuKey {
+    Up,
+    Down,
+    Open,
+    Close,
+    /// A digit 1-9, picking that entry directly (0-based).
+    Pick(usize),
+}
+
+/// Maps arrows, j/k, Enter, Esc/q and digits to menu navigation. Keys with
+/// modifiers other than Shift are left alone.
+pub fn menu_key(key: &Key, modifiers: &Modifiers) -> Option<MenuKey> {
+    if modifiers.ctrl || modifiers.alt || modifiers.logo {
+        return None;
+    }
+    match key {
+        Key::Named(NamedKey::ArrowUp) => Some(MenuKey::Up),
+        Key::Named(NamedKey::ArrowDown) => Some(MenuKey::Down),
+        Key::Named(NamedKey::Enter) => Some(MenuKey::Open),
+        Key::Named(NamedKey::Escape) => Some(MenuKey::Close),
+        Key::Character(c) => match c.as_str() {
+            "k" => Some(MenuKey::Up),
+            "j" => Some(MenuKey::Down),
+            "q" => Some(MenuKey::Close),
+            digit => digit
+                .parse::<usize>()
+                .ok()
+                .filter(|d| (1..=9).contains(d))
+                .map(|d| MenuKey::Pick(d - 1)),
+        },
+        _ => None,
+    }
+}
+
+/// Converts text committed by an IME into a string for `nvim_input`.
+///
+/// Only characters with special meaning in key notation are escaped; the
+/// rest is sent verbatim.
+pub fn ime_commit_to_neovim(text: &str) -> Option<String> {
+    if text.is_empty() {
+        return None;
+    }
+    Some(escape_text(text))
+}
+
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut buf = [0; 4];
+    for ch in text.chars() {
+        out.push_str(&escape_literal(ch.encode_utf8(&mut buf)));
+    }
+    out
 }
 
 fn escape_literal(key: &str) -> String {
@@ -434,6 +691,306 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_several_characters() {
+        assert_eq!(
+            format_with_modifiers("a<", &no_mods(), false),
+            Some("a<lt>".to_string())
+        );
+        assert_eq!(
+            format_with_modifiers("ab", &with_ctrl(), false),
+            Some("<C-a><C-b>".to_string())
+        );
+        assert_eq!(format_with_modifiers("", &with_ctrl(), false), None);
+    }
+
+    #[test]
+    fn test_tab_shortcut() {
+        let cmd_shift = Modifiers {
+            logo: true,
+            shift: true,
+            ..Default::default()
+        };
+        let left = PhysicalKey::Code(KeyCode::BracketLeft);
+        let right = PhysicalKey::Code(KeyCode::BracketRight);
+
+        assert_eq!(tab_shortcut(&left, &cmd_shift), Some(TabShortcut::Previous));
+        assert_eq!(tab_shortcut(&right, &cmd_shift), Some(TabShortcut::Next));
+
+        let cmd = Modifiers {
+            logo: true,
+            ..Default::default()
+        };
+        assert_eq!(tab_shortcut(&left, &cmd), None);
+        assert_eq!(
+            tab_shortcut(&PhysicalKey::Code(KeyCode::KeyA), &cmd_shift),
+            None
+        );
+    }
+
+    #[test]
+    fn test_snapshot_key() {
+        let f12 = Key::Named(NamedKey::F12);
+        assert!(is_snapshot_key(&f12, &Modifiers::default()));
+        let shift = Modifiers {
+            shift: true,
+            ..Default::default()
+        };
+        assert!(!is_snapshot_key(&f12, &shift));
+        assert!(!is_snapshot_key(
+            &Key::Named(NamedKey::F11),
+            &Modifiers::default()
+        ));
+    }
+
+    #[test]
+    fn test_fullscreen_key() {
+        let f11 = Key::Named(NamedKey::F11);
+        let f = Key::Character("f".into());
+        let cmd_ctrl = Modifiers {
+            ctrl: true,
+            logo: true,
+            ..Default::default()
+        };
+        let plain = Modifiers::default();
+        if cfg!(target_os = "macos") {
+            assert!(is_fullscreen_key(&f, &cmd_ctrl));
+            assert!(!is_fullscreen_key(&f11, &plain));
+        } else {
+            assert!(is_fullscreen_key(&f11, &plain));
+            assert!(!is_fullscreen_key(&f, &cmd_ctrl));
+        }
+        assert!(!is_fullscreen_key(&f, &plain));
+    }
+
+    #[test]
+    fn test_menu_key() {
+        let none = Modifiers::default();
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            menu_key(&Key::Named(NamedKey::ArrowDown), &none),
+            Some(MenuKey::Down)
+        );
+        assert_eq!(
+            menu_key(&Key::Character("k".into()), &none),
+            Some(MenuKey::Up)
+        );
+        assert_eq!(
+            menu_key(&Key::Character("3".into()), &none),
+            Some(MenuKey::Pick(2))
+        );
+        assert_eq!(menu_key(&Key::Character("0".into()), &none), None);
+        assert_eq!(menu_key(&Key::Character("j".into()), &ctrl), None);
+    }
+
+    /// One row of the extended encoding's translation table.
+    struct Extended {
+        logical: Key,
+        base: Key,
+        physical: KeyCode,
+        location: KeyLocation,
+        modifiers: Modifiers,
+        expected: Option<&'static str>,
+    }
+
+    fn extended(logical: Key, physical: KeyCode, modifiers: Modifiers) -> Extended {
+        Extended {
+            base: logical.clone(),
+            logical,
+            physical,
+            location: KeyLocation::Standard,
+            modifiers,
+            expected: None,
+        }
+    }
+
+    impl Extended {
+        fn base(self, base: &str) -> Self {
+            Self {
+                base: Key::Character(base.into()),
+                ..self
+            }
+        }
+
+        fn numpad(self) -> Self {
+            Self {
+                location: KeyLocation::Numpad,
+                ..self
+            }
+        }
+
+        fn sends(self, expected: &'static str) -> Self {
+            Self {
+                expected: Some(expected),
+                ..self
+            }
+        }
+    }
+
+    fn char(c: &str) -> Key {
+        Key::Character(c.into())
+    }
+
+    #[test]
+    fn test_extended_translation_table() {
+        let with_ctrl_alt = Modifiers {
+            ctrl: true,
+            alt: true,
+            ..Default::default()
+        };
+        let table = [
+            // The keypad keeps its own names.
+            extended(Key::Named(NamedKey::Enter), KeyCode::NumpadEnter, no_mods())
+                .numpad()
+                .sends("<kEnter>"),
+            extended(
+                Key::Named(NamedKey::Enter),
+                KeyCode::NumpadEnter,
+                with_shift(),
+            )
+            .numpad()
+            .sends("<S-kEnter>"),
+            extended(char("1"), KeyCode::Numpad1, no_mods())
+                .numpad()
+                .sends("<k1>"),
+            extended(char("+"), KeyCode::NumpadAdd, with_ctrl())
+                .numpad()
+                .sends("<C-kPlus>"),
+            extended(char("."), KeyCode::NumpadDecimal, no_mods())
+                .numpad()
+                .sends("<kPoint>"),
+            extended(char("="), KeyCode::NumpadEqual, no_mods())
+                .numpad()
+                .sends("<kEqual>"),
+            // Num Lock off: the keypad moves the cursor.
+            extended(Key::Named(NamedKey::ArrowUp), KeyCode::Numpad8, no_mods())
+                .numpad()
+                .sends("<Up>"),
+            // The main Enter is still <CR>.
+            extended(Key::Named(NamedKey::Enter), KeyCode::Enter, no_mods()).sends("<CR>"),
+            extended(Key::Named(NamedKey::Enter), KeyCode::Enter, with_shift()).sends("<S-CR>"),
+            // Space keeps its modifiers, whichever way the layout reports it.
+            extended(char(" "), KeyCode::Space, no_mods()).sends("<Space>"),
+            extended(char(" "), KeyCode::Space, with_shift()).sends("<S-Space>"),
+            extended(Key::Named(NamedKey::Space), KeyCode::Space, with_shift()).sends("<S-Space>"),
+            extended(char(" "), KeyCode::Space, with_ctrl()).sends("<C-Space>"),
+            // Control characters are spelled with the key pressed.
+            extended(char("\u{9}"), KeyCode::KeyI, with_ctrl())
+                .base("i")
+                .sends("<C-i>"),
+            extended(char("\u{1b}"), KeyCode::BracketLeft, with_ctrl())
+                .base("[")
+                .sends("<C-[>"),
+            extended(char("\u{1}"), KeyCode::KeyA, with_ctrl_shift())
+                .base("a")
+                .sends("<C-S-a>"),
+            extended(char("\u{1c}"), KeyCode::Backslash, with_ctrl())
+                .base("\\")
+                .sends("<C-Bslash>"),
+            // Characters with a meaning in key notation go by name.
+            extended(char("<"), KeyCode::Comma, with_ctrl_alt).sends("<C-M-lt>"),
+            extended(char("|"), KeyCode::Backslash, with_alt()).sends("<M-Bar>"),
+            extended(char("<"), KeyCode::Comma, with_shift()).sends("<lt>"),
+            // Everything else is as in the standard encoding.
+            extended(char("a"), KeyCode::KeyA, no_mods()).sends("a"),
+            extended(char("A"), KeyCode::KeyA, with_shift()).sends("A"),
+            extended(char("a"), KeyCode::KeyA, with_logo()).sends("<D-a>"),
+            extended(Key::Named(NamedKey::Tab), KeyCode::Tab, with_shift()).sends("<S-Tab>"),
+            extended(Key::Named(NamedKey::F5), KeyCode::F5, with_ctrl()).sends("<C-F5>"),
+        ];
+        for row in table {
+            let sent = extended_key_to_neovim(
+                &row.logical,
+                &row.base,
+                &PhysicalKey::Code(row.physical),
+                row.location,
+                &row.modifiers,
+            );
+            assert_eq!(
+                sent.as_deref(),
+                row.expected,
+                "{:?} on {:?}",
+                row.logical,
+                row.physical
+            );
+        }
+    }
+
+    #[test]
+    fn test_option_key() {
+        let f = char("f");
+        let florin = char("ƒ");
+        let key_f = PhysicalKey::Code(KeyCode::KeyF);
+        assert_eq!(option_key(&florin, &f, &key_f, &with_alt(), true).0, f);
+        let (key, mods) = option_key(&florin, &f, &key_f, &with_alt(), false);
+        assert_eq!(key, florin);
+        assert!(!mods.alt);
+        assert_eq!(
+            key_to_neovim(&key, &PhysicalKey::Code(KeyCode::KeyF), &mods),
+            Some("ƒ".to_string())
+        );
+
+        // Option still moves by word.
+        let left = Key::Named(NamedKey::ArrowLeft);
+        let arrow = PhysicalKey::Code(KeyCode::ArrowLeft);
+        let (key, mods) = option_key(&left, &left, &arrow, &with_alt(), false);
+        assert_eq!(key, left);
+        assert!(mods.alt);
+
+        // Without Option nothing changes.
+        let (key, mods) = option_key(&florin, &f, &key_f, &no_mods(), true);
+        assert_eq!(key, florin);
+        assert!(!mods.alt);
+    }
+
+    #[test]
+    fn test_option_dead_keys_as_meta() {
+        let acute = Key::Dead(Some('´'));
+        let key_e = PhysicalKey::Code(KeyCode::KeyE);
+        // The layout's key without modifiers, where it has one.
+        let (key, mods) = option_key(&acute, &char("e"), &key_e, &with_alt(), true);
+        assert_eq!(
+            key_to_neovim(&key, &key_e, &mods),
+            Some("<M-e>".to_string())
+        );
+        // Otherwise the physical key.
+        for (code, sent) in [
+            (KeyCode::KeyE, "<M-e>"),
+            (KeyCode::KeyU, "<M-u>"),
+            (KeyCode::KeyI, "<M-i>"),
+            (KeyCode::KeyN, "<M-n>"),
+            (KeyCode::Backquote, "<M-`>"),
+        ] {
+            let physical = PhysicalKey::Code(code);
+            let (key, mods) = option_key(&acute, &acute, &physical, &with_alt(), true);
+            assert_eq!(
+                key_to_neovim(&key, &physical, &mods),
+                Some(sent.to_string())
+            );
+        }
+
+        // Not as Meta, the accent is left to compose with the next key.
+        let (key, _) = option_key(&acute, &acute, &key_e, &with_alt(), false);
+        assert_eq!(key_to_neovim(&key, &key_e, &with_alt()), None);
+    }
+
+    #[test]
+    fn test_extended_dead_keys_send_nothing() {
+        let dead = Key::Dead(Some('´'));
+        let sent = extended_key_to_neovim(
+            &dead,
+            &dead,
+            &PhysicalKey::Code(KeyCode::Quote),
+            KeyLocation::Standard,
+            &no_mods(),
+        );
+        assert_eq!(sent, None);
+    }
+
     #[test]
     fn test_escape_less_than() {
         assert_eq!(escape_literal("<"), "<lt>".to_string());
@@ -454,6 +1011,13 @@ mod tests {
         assert_eq!(escape_literal("a"), "a".to_string());
     }
 
+    #[test]
+    fn test_ime_commit() {
+        assert_eq!(ime_commit_to_neovim("日本語"), Some("日本語".to_string()));
+        assert_eq!(ime_commit_to_neovim("a<b"), Some("a<lt>b".to_string()));
+        assert_eq!(ime_commit_to_neovim(""), None);
+    }
+
     #[test]
     fn test_modifiers_from_state() {
         let state = ModifiersState::CONTROL | ModifiersState::ALT;
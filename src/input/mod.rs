@@ -1,7 +1,9 @@
 mod handler;
+mod keybinds;
 mod keyboard;
 mod mouse;
 
 pub use handler::*;
+pub use keybinds::*;
 pub use keyboard::*;
 pub use mouse::*;
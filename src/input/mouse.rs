@@ -2,14 +2,14 @@ use winit::dpi::PhysicalPosition;
 use winit::event::{MouseButton, MouseScrollDelta};
 
 use super::keyboard::Modifiers;
-use crate::constants::{DEFAULT_CELL_HEIGHT, DEFAULT_CELL_WIDTH, PADDING};
+use crate::editor::{EditorState, TabRect};
+use crate::layout::WindowLayout;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MouseAction {
     Press,
     Release,
     Drag,
-    #[allow(dead_code)]
     Move,
 }
 
@@ -50,41 +50,105 @@ pub fn mouse_button_to_type(button: MouseButton) -> Option<MouseButtonType> {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+/// A cell for Neovim's mouse input: of the screen, where Neovim finds
+/// the window, when `grid` is 0, or else of that grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct GridPosition {
+    pub grid: i64,
     pub row: i64,
     pub col: i64,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct CellMetrics {
-    pub cell_width: f64,
-    pub cell_height: f64,
-    pub padding_x: f64,
-    pub padding_y: f64,
+/// The screen cells a zoomed window (`gui.set_window_zoom`) covers, which
+/// hold its own grid's cells at `zoom` times the size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZoomedArea {
+    pub grid: u64,
+    pub row: usize,
+    pub col: usize,
+    pub width: usize,
+    pub height: usize,
+    pub zoom: f32,
 }
 
-impl Default for CellMetrics {
-    fn default() -> Self {
-        Self {
-            cell_width: DEFAULT_CELL_WIDTH as f64,
-            cell_height: DEFAULT_CELL_HEIGHT as f64,
-            padding_x: PADDING as f64,
-            padding_y: PADDING as f64,
-        }
+pub fn pixel_to_grid(position: PhysicalPosition<f64>, layout: &WindowLayout) -> GridPosition {
+    let x = (position.x - layout.padding_left).max(0.0);
+    let y = (position.y - layout.padding_top).max(0.0);
+
+    GridPosition {
+        grid: 0,
+        col: (x / layout.cell_width).floor() as i64,
+        row: (y / layout.cell_height).floor() as i64,
     }
 }
 
-pub fn pixel_to_grid(position: PhysicalPosition<f64>, metrics: &CellMetrics) -> GridPosition {
-    let x = (position.x - metrics.padding_x).max(0.0);
-    let y = (position.y - metrics.padding_y).max(0.0);
-
+/// Like `pixel_to_grid`, with a position over a zoomed window in its grid's
+/// cells, which Neovim doesn't know the size of.
+pub fn pixel_to_window(
+    position: PhysicalPosition<f64>,
+    layout: &WindowLayout,
+    zoomed: &[ZoomedArea],
+) -> GridPosition {
+    let screen = pixel_to_grid(position, layout);
+    let inside = |area: &&ZoomedArea| {
+        let (row, col) = (area.row as i64, area.col as i64);
+        (row..row + area.height as i64).contains(&screen.row)
+            && (col..col + area.width as i64).contains(&screen.col)
+    };
+    let Some(area) = zoomed.iter().find(inside) else {
+        return screen;
+    };
+    let x = position.x - layout.padding_left - area.col as f64 * layout.cell_width;
+    let y = position.y - layout.padding_top - area.row as f64 * layout.cell_height;
+    let zoom = area.zoom as f64;
     GridPosition {
-        col: (x / metrics.cell_width).floor() as i64,
-        row: (y / metrics.cell_height).floor() as i64,
+        grid: area.grid as i64,
+        col: (x / (layout.cell_width * zoom)).floor() as i64,
+        row: (y / (layout.cell_height * zoom)).floor() as i64,
     }
 }
 
+/// The URL of the link (a highlight with `url`) at `position`. Floating
+/// windows are tried from the top down, then the grid below them.
+pub fn link_at(state: &EditorState, position: GridPosition) -> Option<&str> {
+    let row = usize::try_from(position.row).ok()?;
+    let col = usize::try_from(position.col).ok()?;
+    if position.grid != 0 {
+        let grid = state.grid(position.grid as u64)?;
+        let highlight_id = grid.row(row)?.get(col)?.highlight_id;
+        return state.highlights.get(highlight_id).url.as_deref();
+    }
+    let in_float = state.floats().into_iter().rev().find_map(|float| {
+        let float_row = (row as f64 - float.row).floor();
+        let float_col = (col as f64 - float.col).floor();
+        if float_row < 0.0 || float_col < 0.0 {
+            return None;
+        }
+        float.grid.row(float_row as usize)?.get(float_col as usize)
+    });
+    let highlight_id = match in_float {
+        Some(cell) => cell.highlight_id,
+        None => state.screen_rows().nth(row)?.get(col)?.highlight_id,
+    };
+    state.highlights.get(highlight_id).url.as_deref()
+}
+
+/// Returns the index of the tab under `position`, if it is on the tab bar.
+/// The bar spans the top padding, above grid row 0.
+pub fn tabline_hit(
+    position: PhysicalPosition<f64>,
+    layout: &WindowLayout,
+    tabs: &[TabRect],
+) -> Option<usize> {
+    if position.y < 0.0 || position.y >= layout.padding_top || position.x < layout.padding_left {
+        return None;
+    }
+    let col = ((position.x - layout.padding_left) / layout.cell_width).floor() as usize;
+    tabs.iter()
+        .find(|tab| (tab.col..tab.col + tab.width).contains(&col))
+        .map(|tab| tab.index)
+}
+
 pub fn modifiers_to_string(modifiers: &Modifiers) -> String {
     let mut result = String::new();
     if modifiers.shift {
@@ -142,6 +206,221 @@ pub fn scroll_delta_to_direction(delta: MouseScrollDelta) -> Option<(ScrollDirec
     }
 }
 
+/// Accumulates trackpad pixel deltas into whole-line scroll steps.
+///
+/// Unlike `scroll_delta_to_direction`, which rounds every event up to at
+/// least one line, the fractional remainder is carried over to the next
+/// event, so the scroll distance tracks the finger movement exactly.
+/// Momentum scrolling after the fingers lift arrives as more deltas and
+/// accumulates the same way.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScrollAccumulator {
+    x: f64,
+    y: f64,
+}
+
+impl ScrollAccumulator {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The vertical scroll not yet sent, in lines (-1, 1). Positive scrolls
+    /// the content down.
+    pub fn pending_lines(&self) -> f64 {
+        self.y
+    }
+
+    /// Ends a gesture: the remainder rounds to the nearest line, so the
+    /// viewport comes to rest on a whole line.
+    pub fn settle(&mut self) -> Option<(ScrollDirection, u32)> {
+        let y = self.y.round();
+        self.reset();
+        match y {
+            y if y > 0.0 => Some((ScrollDirection::Up, y as u32)),
+            y if y < 0.0 => Some((ScrollDirection::Down, -y as u32)),
+            _ => None,
+        }
+    }
+
+    pub fn accumulate(
+        &mut self,
+        delta: PhysicalPosition<f64>,
+        line_height: f64,
+    ) -> Option<(ScrollDirection, u32)> {
+        if line_height <= 0.0 {
+            return None;
+        }
+
+        self.x += delta.x / line_height;
+        self.y += delta.y / line_height;
+
+        // Scroll along the dominant axis only and drop the other, matching
+        // the quantized path. Carrying both would produce diagonal jitter.
+        let (value, dir_pos, dir_neg) = if self.y.abs() >= self.x.abs() {
+            self.x = 0.0;
+            (&mut self.y, ScrollDirection::Up, ScrollDirection::Down)
+        } else {
+            self.y = 0.0;
+            (&mut self.x, ScrollDirection::Left, ScrollDirection::Right)
+        };
+
+        let lines = value.trunc();
+        if lines == 0.0 {
+            return None;
+        }
+        *value -= lines;
+
+        let direction = if lines > 0.0 { dir_pos } else { dir_neg };
+        Some((direction, lines.abs() as u32))
+    }
+}
+
+/// Trackpad travel, in lines, per font size step when zooming.
+const ZOOM_STEP_LINES: f64 = 2.0;
+
+/// Turns Ctrl+wheel into font size steps: one per wheel notch, and one
+/// per `ZOOM_STEP_LINES` of trackpad travel. The remainder carries over
+/// like `ScrollAccumulator`'s, so a slow two-finger scroll steps through
+/// the sizes one at a time rather than jumping several per gesture.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ZoomAccumulator {
+    steps: f64,
+}
+
+impl ZoomAccumulator {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Whole steps to zoom by; positive zooms in, as scrolling up does.
+    pub fn accumulate(&mut self, delta: MouseScrollDelta, line_height: f64) -> i32 {
+        self.steps += match delta {
+            MouseScrollDelta::LineDelta(_, y) => y as f64,
+            MouseScrollDelta::PixelDelta(_) if line_height <= 0.0 => return 0,
+            MouseScrollDelta::PixelDelta(d) => d.y / line_height / ZOOM_STEP_LINES,
+        };
+        self.take_steps()
+    }
+
+    /// Whole steps for a trackpad pinch by `magnification` (0.1 for 10%
+    /// larger). `steps_per_size` is the font size in steps, so the text
+    /// grows with the fingers: pinching a 14 pt font out to twice the
+    /// spread makes 14 single-point steps.
+    pub fn accumulate_pinch(&mut self, magnification: f64, steps_per_size: f64) -> i32 {
+        self.steps += magnification * steps_per_size;
+        self.take_steps()
+    }
+
+    fn take_steps(&mut self) -> i32 {
+        let steps = self.steps.trunc();
+        self.steps -= steps;
+        steps as i32
+    }
+}
+
+/// Time constant (ms) of a touch swipe's momentum: its speed drops to a
+/// third in this long.
+const MOMENTUM_DECAY_MS: f64 = 325.0;
+
+/// Speed (px/ms) below which a swipe comes to rest.
+const MOMENTUM_MIN_SPEED: f64 = 0.02;
+
+/// How often (ms) momentum scrolling steps.
+const MOMENTUM_STEP_MS: u64 = 16;
+
+/// Scrolling by dragging a finger on a touchscreen. The finger's travel
+/// comes out as pixel deltas, as a trackpad's does, and when it lifts the
+/// swipe carries on, slowing down, like the system's own momentum.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TouchScroll {
+    /// The finger that scrolls: its id, where it was last and when (ms).
+    finger: Option<(u64, PhysicalPosition<f64>, u64)>,
+    /// The swipe's speed, in px/ms.
+    velocity: (f64, f64),
+    /// Time (ms) of the last momentum step, while the swipe carries on.
+    coasting: Option<u64>,
+}
+
+impl TouchScroll {
+    /// A finger touched down. Others touching down meanwhile are ignored,
+    /// and the first stops any swipe still under way.
+    pub fn start(&mut self, id: u64, location: PhysicalPosition<f64>, now: u64) {
+        if self.finger.is_none() {
+            *self = Self {
+                finger: Some((id, location, now)),
+                ..Self::default()
+            };
+        }
+    }
+
+    /// The finger `id` moved to `location`; returns the pixels to scroll.
+    pub fn moved(
+        &mut self,
+        id: u64,
+        location: PhysicalPosition<f64>,
+        now: u64,
+    ) -> Option<PhysicalPosition<f64>> {
+        let (finger, last, at) = self.finger.as_mut()?;
+        if *finger != id {
+            return None;
+        }
+        let delta = PhysicalPosition::new(location.x - last.x, location.y - last.y);
+        let elapsed = now.saturating_sub(*at);
+        if elapsed > 0 {
+            // Smoothed over the last few moves, so one uneven event
+            // doesn't decide how far the swipe goes.
+            let speed = |d: f64, v: f64| 0.8 * d / elapsed as f64 + 0.2 * v;
+            self.velocity = (
+                speed(delta.x, self.velocity.0),
+                speed(delta.y, self.velocity.1),
+            );
+            *at = now;
+        }
+        *last = location;
+        Some(delta)
+    }
+
+    /// The finger `id` lifted (`cancelled`: the system took the touch).
+    /// The swipe carries on if it was fast enough. Returns whether this
+    /// was the scrolling finger.
+    pub fn ended(&mut self, id: u64, now: u64, cancelled: bool) -> bool {
+        if self.finger.is_none_or(|(finger, _, _)| finger != id) {
+            return false;
+        }
+        self.finger = None;
+        let (vx, vy) = self.velocity;
+        if !cancelled && vx.hypot(vy) >= MOMENTUM_MIN_SPEED {
+            self.coasting = Some(now);
+        }
+        true
+    }
+
+    /// Whether a swipe is carrying on after the finger lifted.
+    pub fn is_coasting(&self) -> bool {
+        self.coasting.is_some()
+    }
+
+    /// Advances the swipe to `now`. Returns the pixels to scroll and
+    /// whether it has come to rest.
+    pub fn coast(&mut self, now: u64) -> Option<(PhysicalPosition<f64>, bool)> {
+        let at = self.coasting?;
+        let elapsed = now.saturating_sub(at) as f64;
+        let decay = (-elapsed / MOMENTUM_DECAY_MS).exp();
+        // The distance covered while slowing from v to v * decay.
+        let travel = |v: f64| v * MOMENTUM_DECAY_MS * (1.0 - decay);
+        let delta = PhysicalPosition::new(travel(self.velocity.0), travel(self.velocity.1));
+        self.velocity = (self.velocity.0 * decay, self.velocity.1 * decay);
+        let rest = self.velocity.0.hypot(self.velocity.1) < MOMENTUM_MIN_SPEED;
+        self.coasting = (!rest).then_some(now);
+        Some((delta, rest))
+    }
+
+    /// Time (ms) of the next momentum step, if the swipe carries on.
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.coasting.map(|at| at + MOMENTUM_STEP_MS)
+    }
+}
+
 #[derive(Default)]
 pub struct MouseState {
     pub last_position: Option<GridPosition>,
@@ -209,52 +488,188 @@ mod tests {
 
     #[test]
     fn test_pixel_to_grid_basic() {
-        let metrics = CellMetrics {
+        let layout = WindowLayout {
             cell_width: 10.0,
             cell_height: 20.0,
-            padding_x: 2.0,
-            padding_y: 2.0,
+            padding_left: 2.0,
+            padding_top: 2.0,
+            ..WindowLayout::default()
         };
 
         let pos = PhysicalPosition::new(12.0, 22.0); // First cell
-        let grid = pixel_to_grid(pos, &metrics);
+        let grid = pixel_to_grid(pos, &layout);
         assert_eq!(grid.col, 1);
         assert_eq!(grid.row, 1);
     }
 
     #[test]
     fn test_pixel_to_grid_origin() {
-        let metrics = CellMetrics::default();
-        let pos = PhysicalPosition::new(2.0, 2.0); // At padding
-        let grid = pixel_to_grid(pos, &metrics);
+        let layout = WindowLayout::default();
+        let pos = PhysicalPosition::new(2.0, 30.0); // At padding
+        let grid = pixel_to_grid(pos, &layout);
         assert_eq!(grid.col, 0);
         assert_eq!(grid.row, 0);
     }
 
     #[test]
     fn test_pixel_to_grid_negative_clamps() {
-        let metrics = CellMetrics::default();
+        let layout = WindowLayout::default();
         let pos = PhysicalPosition::new(-10.0, -10.0);
-        let grid = pixel_to_grid(pos, &metrics);
+        let grid = pixel_to_grid(pos, &layout);
         assert_eq!(grid.col, 0);
         assert_eq!(grid.row, 0);
     }
 
     #[test]
     fn test_pixel_to_grid_large_coords() {
-        let metrics = CellMetrics {
+        let layout = WindowLayout {
             cell_width: 10.0,
             cell_height: 20.0,
-            padding_x: 0.0,
-            padding_y: 0.0,
+            padding_left: 0.0,
+            padding_top: 0.0,
+            ..WindowLayout::default()
         };
 
         let pos = PhysicalPosition::new(85.0, 105.0);
-        let grid = pixel_to_grid(pos, &metrics);
+        let grid = pixel_to_grid(pos, &layout);
         assert_eq!(grid.col, 8);
         assert_eq!(grid.row, 5);
     }
 
+    #[test]
+    fn test_pixel_to_window_in_zoomed_window() {
+        let layout = WindowLayout {
+            cell_width: 10.0,
+            cell_height: 20.0,
+            padding_left: 5.0,
+            padding_top: 0.0,
+            ..WindowLayout::default()
+        };
+        let zoomed = [ZoomedArea {
+            grid: 3,
+            row: 2,
+            col: 4,
+            width: 10,
+            height: 6,
+            zoom: 2.0,
+        }];
+        let at = |x, y| pixel_to_window(PhysicalPosition::new(x, y), &layout, &zoomed);
+
+        assert_eq!(
+            at(44.0, 45.0),
+            GridPosition {
+                grid: 0,
+                row: 2,
+                col: 3
+            }
+        );
+        assert_eq!(
+            at(45.0, 40.0),
+            GridPosition {
+                grid: 3,
+                row: 0,
+                col: 0
+            }
+        );
+        assert_eq!(
+            at(110.0, 105.0),
+            GridPosition {
+                grid: 3,
+                row: 1,
+                col: 3
+            }
+        );
+        assert_eq!(
+            at(145.0, 45.0),
+            GridPosition {
+                grid: 0,
+                row: 2,
+                col: 14
+            }
+        );
+    }
+
+    #[test]
+    fn test_link_at() {
+        use crate::bridge::events::{GridCell, RedrawEvent};
+        use crate::editor::HighlightAttributes;
+
+        let mut state = EditorState::new(20, 5);
+        state.handle_redraw_event(&RedrawEvent::HlAttrDefine {
+            id: 1,
+            attrs: HighlightAttributes {
+                url: Some("https://neovim.io".to_string()),
+                ..Default::default()
+            },
+        });
+        let cell = |text: &str, hl_id| GridCell {
+            text: text.to_string(),
+            hl_id: Some(hl_id),
+            repeat: 1,
+        };
+        state.handle_redraw_event(&RedrawEvent::GridLine {
+            grid: 1,
+            row: 2,
+            col_start: 3,
+            cells: vec![cell("x", 0), cell("n", 1), cell("v", 1), cell("y", 0)],
+        });
+
+        let at = |row, col| link_at(&state, GridPosition { grid: 0, row, col });
+        assert_eq!(at(2, 4), Some("https://neovim.io"));
+        assert_eq!(at(2, 5), Some("https://neovim.io"));
+        assert_eq!(at(2, 3), None);
+        assert_eq!(at(2, 6), None);
+        assert_eq!(at(-1, 4), None);
+        assert_eq!(at(9, 4), None);
+    }
+
+    #[test]
+    fn test_tabline_hit() {
+        let layout = WindowLayout {
+            cell_width: 10.0,
+            cell_height: 20.0,
+            padding_left: 2.0,
+            padding_top: 30.0,
+            ..WindowLayout::default()
+        };
+        let tabs = vec![
+            TabRect {
+                index: 0,
+                col: 8,
+                width: 6,
+                label: " a.rs ".to_string(),
+            },
+            TabRect {
+                index: 1,
+                col: 14,
+                width: 6,
+                label: " b.rs ".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            tabline_hit(PhysicalPosition::new(85.0, 10.0), &layout, &tabs),
+            Some(0)
+        );
+        assert_eq!(
+            tabline_hit(PhysicalPosition::new(145.0, 29.0), &layout, &tabs),
+            Some(1)
+        );
+        // Left of the first tab, past the last one, and on the grid
+        assert_eq!(
+            tabline_hit(PhysicalPosition::new(40.0, 10.0), &layout, &tabs),
+            None
+        );
+        assert_eq!(
+            tabline_hit(PhysicalPosition::new(205.0, 10.0), &layout, &tabs),
+            None
+        );
+        assert_eq!(
+            tabline_hit(PhysicalPosition::new(85.0, 40.0), &layout, &tabs),
+            None
+        );
+    }
+
     #[test]
     fn test_modifiers_to_string_empty() {
         let mods = Modifiers::default();
@@ -347,6 +762,150 @@ mod tests {
         assert_eq!(result, Some((ScrollDirection::Up, 2)));
     }
 
+    #[test]
+    fn test_scroll_accumulator_carries_remainder() {
+        let mut acc = ScrollAccumulator::default();
+        // Three 8px moves with 20px lines: nothing, nothing, then one line
+        assert_eq!(acc.accumulate(PhysicalPosition::new(0.0, -8.0), 20.0), None);
+        assert_eq!(acc.accumulate(PhysicalPosition::new(0.0, -8.0), 20.0), None);
+        assert_eq!(
+            acc.accumulate(PhysicalPosition::new(0.0, -8.0), 20.0),
+            Some((ScrollDirection::Down, 1))
+        );
+        // 4px left over, so another 16px completes the next line
+        assert_eq!(
+            acc.accumulate(PhysicalPosition::new(0.0, -16.0), 20.0),
+            Some((ScrollDirection::Down, 1))
+        );
+    }
+
+    #[test]
+    fn test_scroll_accumulator_large_delta() {
+        let mut acc = ScrollAccumulator::default();
+        assert_eq!(
+            acc.accumulate(PhysicalPosition::new(0.0, 70.0), 20.0),
+            Some((ScrollDirection::Up, 3))
+        );
+    }
+
+    #[test]
+    fn test_scroll_accumulator_horizontal() {
+        let mut acc = ScrollAccumulator::default();
+        assert_eq!(
+            acc.accumulate(PhysicalPosition::new(25.0, 2.0), 20.0),
+            Some((ScrollDirection::Left, 1))
+        );
+    }
+
+    #[test]
+    fn test_scroll_accumulator_reset() {
+        let mut acc = ScrollAccumulator::default();
+        acc.accumulate(PhysicalPosition::new(0.0, 15.0), 20.0);
+        acc.reset();
+        assert_eq!(acc.accumulate(PhysicalPosition::new(0.0, 10.0), 20.0), None);
+    }
+
+    #[test]
+    fn test_scroll_accumulator_settle() {
+        let mut acc = ScrollAccumulator::default();
+        acc.accumulate(PhysicalPosition::new(0.0, -12.0), 20.0);
+        assert_eq!(acc.pending_lines(), -0.6);
+        assert_eq!(acc.settle(), Some((ScrollDirection::Down, 1)));
+        assert_eq!(acc.pending_lines(), 0.0);
+
+        acc.accumulate(PhysicalPosition::new(0.0, 8.0), 20.0);
+        assert_eq!(acc.settle(), None);
+        assert_eq!(acc.pending_lines(), 0.0);
+    }
+
+    #[test]
+    fn test_zoom_accumulator() {
+        let mut acc = ZoomAccumulator::default();
+        // A notch per step, down zooming out
+        assert_eq!(
+            acc.accumulate(MouseScrollDelta::LineDelta(0.0, 1.0), 20.0),
+            1
+        );
+        assert_eq!(
+            acc.accumulate(MouseScrollDelta::LineDelta(0.0, -2.0), 20.0),
+            -2
+        );
+
+        // 40px of trackpad travel per step with 20px lines
+        let pixels = |y| MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, y));
+        assert_eq!(acc.accumulate(pixels(15.0), 20.0), 0);
+        assert_eq!(acc.accumulate(pixels(15.0), 20.0), 0);
+        assert_eq!(acc.accumulate(pixels(15.0), 20.0), 1);
+        // 5px left over
+        assert_eq!(acc.accumulate(pixels(30.0), 20.0), 0);
+        acc.reset();
+        assert_eq!(acc.accumulate(pixels(35.0), 20.0), 0);
+    }
+
+    #[test]
+    fn test_pinch_zoom_steps() {
+        let mut acc = ZoomAccumulator::default();
+        // 14 pt grows a point per 1/14 of magnification
+        assert_eq!(acc.accumulate_pinch(0.05, 14.0), 0);
+        assert_eq!(acc.accumulate_pinch(0.05, 14.0), 1);
+        assert_eq!(acc.accumulate_pinch(0.5, 14.0), 7);
+        // 0.4 left over from growing
+        assert_eq!(acc.accumulate_pinch(-0.3, 14.0), -3);
+    }
+
+    #[test]
+    fn test_touch_scroll_follows_the_finger() {
+        let at = |y| PhysicalPosition::new(100.0, y);
+        let mut touch = TouchScroll::default();
+        touch.start(1, at(200.0), 0);
+        // A second finger doesn't scroll.
+        touch.start(2, at(300.0), 5);
+        assert_eq!(touch.moved(2, at(350.0), 10), None);
+
+        assert_eq!(touch.moved(1, at(230.0), 10).unwrap().y, 30.0);
+        assert_eq!(touch.moved(1, at(220.0), 20).unwrap().y, -10.0);
+        assert!(!touch.ended(2, 30, false));
+        assert!(touch.ended(1, 30, true));
+        assert!(!touch.is_coasting(), "a cancelled touch doesn't carry on");
+    }
+
+    #[test]
+    fn test_touch_swipe_coasts_to_rest() {
+        let at = |y| PhysicalPosition::new(0.0, y);
+        let mut touch = TouchScroll::default();
+        touch.start(1, at(0.0), 0);
+        for i in 1..=5 {
+            touch.moved(1, at(i as f64 * 20.0), i * 10);
+        }
+        assert!(touch.ended(1, 50, false));
+        assert!(touch.is_coasting());
+        assert_eq!(touch.next_deadline(), Some(50 + MOMENTUM_STEP_MS));
+
+        let mut total = 0.0;
+        let mut previous = f64::INFINITY;
+        let mut now = 50;
+        loop {
+            now += MOMENTUM_STEP_MS;
+            let (delta, rest) = touch.coast(now).unwrap();
+            assert!(delta.y > 0.0 && delta.y < previous, "slows down");
+            previous = delta.y;
+            total += delta.y;
+            if rest {
+                break;
+            }
+        }
+        assert!(!touch.is_coasting() && touch.coast(now + 16).is_none());
+        // Most of v * decay time, ~2 px/ms for 325 ms
+        assert!((500.0..700.0).contains(&total), "{}", total);
+
+        // Touching down again stops a swipe.
+        touch.start(1, at(0.0), 0);
+        touch.moved(1, at(50.0), 10);
+        touch.ended(1, 10, false);
+        touch.start(3, at(0.0), 20);
+        assert!(!touch.is_coasting());
+    }
+
     #[test]
     fn test_scroll_pixel_delta_small() {
         let delta = MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 5.0));
@@ -365,7 +924,11 @@ mod tests {
     #[test]
     fn test_mouse_state_update_position() {
         let mut state = MouseState::new();
-        state.update_position(GridPosition { row: 5, col: 10 });
+        state.update_position(GridPosition {
+            grid: 0,
+            row: 5,
+            col: 10,
+        });
         assert!(state.last_position.is_some());
         let pos = state.last_position.unwrap();
         assert_eq!(pos.row, 5);
@@ -385,13 +948,4 @@ mod tests {
         assert!(!state.is_dragging());
         assert!(state.pressed_button.is_none());
     }
-
-    #[test]
-    fn test_cell_metrics_default() {
-        let metrics = CellMetrics::default();
-        assert_eq!(metrics.cell_width, 10.0);
-        assert_eq!(metrics.cell_height, 20.0);
-        assert_eq!(metrics.padding_x, 2.0);
-        assert_eq!(metrics.padding_y, 2.0);
-    }
 }
@@ -0,0 +1,323 @@
+//! `gui.nvim --remote file`: opens the files in the gui.nvim already
+//! running, and brings its window to the front, instead of opening a
+//! second one.
+//!
+//! Every window listens for them, on a Unix socket or a named pipe on
+//! Windows; the first to start gets the address, and later ones do
+//! without. As with the daemon, only plain file arguments can be handed
+//! over. The launching process exits as soon as the files are taken.
+
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use winit::event_loop::EventLoopProxy;
+
+use crate::daemon::{self, LaunchRequest};
+use crate::event::{GUIEvent, UserEvent};
+
+/// Largest request taken, far above any command line.
+const MAX_REQUEST_LEN: u32 = 1024 * 1024;
+
+/// Sent back once the files are taken.
+const ACK: u8 = 1;
+
+/// How long either end of a request waits on the other.
+pub(crate) const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, thiserror::Error)]
+pub enum InstanceError {
+    #[error("No directory to put the instance socket in")]
+    NoSocketDir,
+
+    #[error("Another gui.nvim is listening at {0}")]
+    AlreadyRunning(PathBuf),
+
+    #[error("Instance socket error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Where the running instance listens.
+#[cfg(unix)]
+pub fn socket_path() -> Option<PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("gui-nvim").join("instance.sock"))
+}
+
+/// Where the running instance listens: a pipe per user.
+#[cfg(windows)]
+pub fn socket_path() -> Option<PathBuf> {
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    Some(PathBuf::from(format!(r"\\.\pipe\gui-nvim-{}", user)))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn socket_path() -> Option<PathBuf> {
+    None
+}
+
+/// Hands `args` to the running instance. Returns the code to exit with,
+/// or `None` if there is no instance to take them and the GUI should
+/// start as usual.
+pub fn open_in_instance(args: &[String]) -> Option<i32> {
+    if !daemon::daemon_can_open(args) {
+        log::info!("Neovim options need a window of their own, not --remote");
+        return None;
+    }
+    let request = LaunchRequest {
+        cwd: std::env::current_dir().ok()?,
+        files: args.to_vec(),
+    };
+    let mut stream = connect(&socket_path()?)?;
+    match send_request(&mut stream, &request) {
+        Ok(()) => {
+            log::info!("Opened in the running gui.nvim");
+            Some(0)
+        }
+        Err(e) => {
+            log::warn!("The running gui.nvim didn't take the files: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect(path: &std::path::Path) -> Option<std::os::unix::net::UnixStream> {
+    let stream = std::os::unix::net::UnixStream::connect(path).ok()?;
+    // An instance that never answers mustn't hang the launch.
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT)).ok()?;
+    Some(stream)
+}
+
+#[cfg(windows)]
+fn connect(path: &std::path::Path) -> Option<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .ok()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn connect(_path: &std::path::Path) -> Option<std::fs::File> {
+    None
+}
+
+/// Writes `request`, length first, and waits for the acknowledgement.
+/// The framing for both `--remote` and the daemon.
+pub(crate) fn send_request(
+    stream: &mut (impl Read + Write),
+    request: &LaunchRequest,
+) -> io::Result<()> {
+    let bytes = request.encode();
+    stream.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    stream.write_all(&bytes)?;
+    stream.flush()?;
+    let mut ack = [0];
+    stream.read_exact(&mut ack)?;
+    if ack[0] != ACK {
+        return Err(io::Error::other("unexpected reply"));
+    }
+    Ok(())
+}
+
+/// The length a request starts with, if it is one worth reading.
+fn request_len(header: [u8; 4]) -> io::Result<usize> {
+    match u32::from_le_bytes(header) {
+        len if len <= MAX_REQUEST_LEN => Ok(len as usize),
+        len => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("request of {} bytes", len),
+        )),
+    }
+}
+
+fn decode(bytes: &[u8]) -> io::Result<LaunchRequest> {
+    LaunchRequest::decode(bytes)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed request"))
+}
+
+/// Reads one request, as `send_request` writes it, and acknowledges it.
+#[cfg_attr(not(unix), allow(dead_code))]
+pub(crate) fn receive_request(stream: &mut (impl Read + Write)) -> io::Result<LaunchRequest> {
+    let mut header = [0; 4];
+    stream.read_exact(&mut header)?;
+    let mut bytes = vec![0; request_len(header)?];
+    stream.read_exact(&mut bytes)?;
+    let request = decode(&bytes)?;
+    stream.write_all(&[ACK])?;
+    Ok(request)
+}
+
+/// Binds a Unix socket at `path`, clearing one left behind by a process
+/// that died. `None` if a live process is listening there.
+#[cfg(unix)]
+pub(crate) fn bind_socket(
+    path: &std::path::Path,
+) -> io::Result<Option<std::os::unix::net::UnixListener>> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    match UnixListener::bind(path) {
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            if UnixStream::connect(path).is_ok() {
+                return Ok(None);
+            }
+            std::fs::remove_file(path)?;
+            Ok(Some(UnixListener::bind(path)?))
+        }
+        result => Ok(Some(result?)),
+    }
+}
+
+/// The listening side, kept by the app while it runs. Files arrive as
+/// `GUIEvent::Open`.
+pub struct Instance {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    path: PathBuf,
+}
+
+impl Instance {
+    #[cfg(unix)]
+    pub fn listen(proxy: EventLoopProxy<UserEvent>) -> Result<Self, InstanceError> {
+        let path = socket_path().ok_or(InstanceError::NoSocketDir)?;
+        let listener =
+            bind_socket(&path)?.ok_or_else(|| InstanceError::AlreadyRunning(path.clone()))?;
+        log::info!("Listening for --remote at {}", path.display());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        log::warn!("Instance accept failed: {}", e);
+                        continue;
+                    }
+                };
+                // A client that never finishes its request mustn't block
+                // the next.
+                let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+                match receive_request(&mut stream) {
+                    Ok(request) => {
+                        if proxy
+                            .send_event(UserEvent::GUI(GUIEvent::Open(request)))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Err(e) => log::warn!("Ignoring a --remote request: {}", e),
+                }
+            }
+        });
+        Ok(Self { path })
+    }
+
+    #[cfg(windows)]
+    pub fn listen(proxy: EventLoopProxy<UserEvent>) -> Result<Self, InstanceError> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let path = socket_path().ok_or(InstanceError::NoSocketDir)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .enable_time()
+            .build()?;
+        // Only the first instance may create the pipe.
+        let first = runtime
+            .block_on(async { ServerOptions::new().first_pipe_instance(true).create(&path) })
+            .map_err(|_| InstanceError::AlreadyRunning(path.clone()))?;
+        log::info!("Listening for --remote at {}", path.display());
+
+        let name = path.clone();
+        std::thread::spawn(move || {
+            runtime.block_on(async move {
+                let mut server = first;
+                loop {
+                    if let Err(e) = server.connect().await {
+                        log::warn!("Instance accept failed: {}", e);
+                        continue;
+                    }
+                    let mut pipe = server;
+                    server = match ServerOptions::new().create(&name) {
+                        Ok(server) => server,
+                        Err(e) => {
+                            log::warn!("Stopped listening for --remote: {}", e);
+                            return;
+                        }
+                    };
+                    let received = async {
+                        let mut header = [0; 4];
+                        pipe.read_exact(&mut header).await?;
+                        let mut bytes = vec![0; request_len(header)?];
+                        pipe.read_exact(&mut bytes).await?;
+                        let request = decode(&bytes)?;
+                        pipe.write_all(&[ACK]).await?;
+                        io::Result::Ok(request)
+                    };
+                    match tokio::time::timeout(REQUEST_TIMEOUT, received).await {
+                        Ok(Ok(request)) => {
+                            if proxy
+                                .send_event(UserEvent::GUI(GUIEvent::Open(request)))
+                                .is_err()
+                            {
+                                return;
+                            }
+                        }
+                        Ok(Err(e)) => log::warn!("Ignoring a --remote request: {}", e),
+                        Err(_) => log::warn!("Ignoring a --remote request that never finished"),
+                    }
+                }
+            });
+        });
+        Ok(Self { path })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub fn listen(_proxy: EventLoopProxy<UserEvent>) -> Result<Self, InstanceError> {
+        Err(InstanceError::NoSocketDir)
+    }
+}
+
+impl Drop for Instance {
+    /// Frees the address for the next gui.nvim. A pipe goes with the
+    /// process.
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_send_and_receive() {
+        use std::os::unix::net::UnixStream;
+
+        let (mut client, mut server) = UnixStream::pair().unwrap();
+        let request = LaunchRequest {
+            cwd: PathBuf::from("/home/me"),
+            files: vec!["notes.md".to_string()],
+        };
+        let sent = std::thread::spawn({
+            let request = request.clone();
+            move || send_request(&mut client, &request)
+        });
+        assert_eq!(receive_request(&mut server).unwrap(), request);
+        sent.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_oversized_requests_are_refused() {
+        assert_eq!(request_len(5u32.to_le_bytes()).unwrap(), 5);
+        assert!(request_len((MAX_REQUEST_LEN + 1).to_le_bytes()).is_err());
+    }
+}
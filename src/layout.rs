@@ -0,0 +1,202 @@
+use crate::config::Padding;
+#[cfg(target_os = "macos")]
+use crate::constants::TITLEBAR_BUTTONS_WIDTH;
+use crate::constants::{DEFAULT_CELL_HEIGHT, DEFAULT_CELL_WIDTH, MAX_GRID_COLS, MAX_GRID_ROWS};
+
+/// A rectangle in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Where things sit inside the window, in physical pixels. The window uses
+/// it to size the grid, input to map pointer positions to cells, and the
+/// renderer to place the grid and the strip above it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowLayout {
+    pub cell_width: f64,
+    pub cell_height: f64,
+    /// `[window] padding`, in logical pixels; the gaps below are it scaled.
+    pub padding: Padding,
+    /// Gaps left of, right of and below the grid.
+    pub padding_left: f64,
+    pub padding_right: f64,
+    pub padding_bottom: f64,
+    /// Strip above the grid: the transparent titlebar on macOS, which also
+    /// holds the tab bar.
+    pub padding_top: f64,
+    /// Width at the left of the top strip taken by the window buttons.
+    pub titlebar_inset: f64,
+    /// `[ui] mirror`: everything is drawn right to left, the first column
+    /// at the right edge. Positions here are unmirrored; the renderer flips
+    /// them, and the window flips the pointer back.
+    pub mirrored: bool,
+}
+
+impl Default for WindowLayout {
+    fn default() -> Self {
+        let mut layout = Self {
+            cell_width: DEFAULT_CELL_WIDTH as f64,
+            cell_height: DEFAULT_CELL_HEIGHT as f64,
+            padding: Padding::default(),
+            padding_left: 0.0,
+            padding_right: 0.0,
+            padding_bottom: 0.0,
+            padding_top: 0.0,
+            titlebar_inset: 0.0,
+            mirrored: false,
+        };
+        layout.set_scale_factor(1.0);
+        layout
+    }
+}
+
+impl WindowLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the padding, in logical pixels, scaled by `scale_factor`.
+    pub fn set_padding(&mut self, padding: Padding, scale_factor: f64) {
+        self.padding = padding;
+        self.set_scale_factor(scale_factor);
+    }
+
+    /// Rescales the padding and insets, which are defined in logical pixels.
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        let scale = |px: u32| (px as f64 * scale_factor).round();
+        self.padding_left = scale(self.padding.left);
+        self.padding_right = scale(self.padding.right);
+        self.padding_bottom = scale(self.padding.bottom);
+        self.padding_top = scale(self.padding.top);
+        #[cfg(target_os = "macos")]
+        {
+            self.titlebar_inset = (TITLEBAR_BUTTONS_WIDTH as f64 * scale_factor).round();
+        }
+    }
+
+    pub fn set_cell_size(&mut self, cell_width: f64, cell_height: f64) {
+        self.cell_width = cell_width;
+        self.cell_height = cell_height;
+    }
+
+    /// Grid dimensions (cols, rows) that fit a window of the given size,
+    /// clamped to [`MAX_GRID_COLS`] x [`MAX_GRID_ROWS`].
+    pub fn grid_size(&self, width: u32, height: u32) -> (u64, u64) {
+        let cols = (width as f64 - self.horizontal_padding()).max(0.0) / self.cell_width;
+        let rows = (height as f64 - self.vertical_padding()).max(0.0) / self.cell_height;
+        (
+            (cols.max(1.0) as u64).min(MAX_GRID_COLS),
+            (rows.max(1.0) as u64).min(MAX_GRID_ROWS),
+        )
+    }
+
+    /// Window size that fits a grid of the given dimensions exactly.
+    pub fn window_size(&self, cols: u64, rows: u64) -> (f64, f64) {
+        (
+            cols as f64 * self.cell_width + self.horizontal_padding(),
+            rows as f64 * self.cell_height + self.vertical_padding(),
+        )
+    }
+
+    fn horizontal_padding(&self) -> f64 {
+        self.padding_left + self.padding_right
+    }
+
+    fn vertical_padding(&self) -> f64 {
+        self.padding_top + self.padding_bottom
+    }
+
+    /// Pixel area covered by a grid of the given dimensions.
+    pub fn grid_rect(&self, cols: usize, rows: usize) -> PixelRect {
+        PixelRect {
+            x: self.padding_left,
+            y: self.padding_top,
+            width: cols as f64 * self.cell_width,
+            height: rows as f64 * self.cell_height,
+        }
+    }
+
+    /// Grid columns hidden behind the window buttons in the top strip.
+    pub fn titlebar_inset_cols(&self) -> usize {
+        ((self.titlebar_inset - self.padding_left).max(0.0) / self.cell_width).ceil() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_layout() {
+        let layout = WindowLayout::default();
+        assert_eq!(layout.cell_width, 10.0);
+        assert_eq!(layout.cell_height, 20.0);
+        assert_eq!(layout.padding_left, 2.0);
+        assert_eq!(layout.padding_right, 2.0);
+        assert_eq!(layout.padding_bottom, 2.0);
+        assert_eq!(layout.padding_top, 30.0);
+    }
+
+    #[test]
+    fn test_configured_padding() {
+        let mut layout = WindowLayout::new();
+        let padding = Padding {
+            left: 10,
+            right: 4,
+            top: 0,
+            bottom: 6,
+        };
+        layout.set_padding(padding, 2.0);
+        assert_eq!(
+            layout.window_size(80, 24),
+            (80.0 * 10.0 + 28.0, 24.0 * 20.0 + 12.0)
+        );
+        assert_eq!(layout.grid_size(80 * 10 + 28, 24 * 20 + 12), (80, 24));
+        let rect = layout.grid_rect(80, 24);
+        assert_eq!((rect.x, rect.y), (20.0, 0.0));
+
+        // Rescaling keeps the configured padding.
+        layout.set_scale_factor(1.0);
+        assert_eq!(layout.padding_left, 10.0);
+    }
+
+    #[test]
+    fn test_grid_and_window_size_round_trip() {
+        let mut layout = WindowLayout::new();
+        layout.set_scale_factor(2.0);
+        layout.set_cell_size(16.0, 34.0);
+
+        let (width, height) = layout.window_size(80, 24);
+        assert_eq!((width, height), (80.0 * 16.0 + 8.0, 24.0 * 34.0 + 64.0));
+        assert_eq!(layout.grid_size(width as u32, height as u32), (80, 24));
+
+        let rect = layout.grid_rect(80, 24);
+        assert_eq!((rect.x, rect.y), (4.0, 60.0));
+        assert_eq!(rect.width, 80.0 * 16.0);
+    }
+
+    #[test]
+    fn test_grid_size_is_clamped() {
+        let mut layout = WindowLayout::new();
+        layout.set_cell_size(1.0, 1.0);
+        assert_eq!(
+            layout.grid_size(u32::MAX, u32::MAX),
+            (MAX_GRID_COLS, MAX_GRID_ROWS)
+        );
+        assert_eq!(layout.grid_size(0, 0), (1, 1));
+    }
+
+    #[test]
+    fn test_titlebar_inset_cols() {
+        let mut layout = WindowLayout::new();
+        layout.titlebar_inset = 72.0;
+        // 70px past the padding at 10px per cell
+        assert_eq!(layout.titlebar_inset_cols(), 7);
+        layout.titlebar_inset = 0.0;
+        assert_eq!(layout.titlebar_inset_cols(), 0);
+    }
+}
@@ -1,33 +1,87 @@
+pub mod bench;
 pub mod bridge;
 pub mod cli;
 pub mod config;
 pub mod constants;
+pub mod crash_report;
+pub mod daemon;
 pub mod editor;
 pub mod env;
 pub mod event;
+pub mod export;
+pub mod gpu_info;
+pub mod ipc;
 
 pub mod input;
+pub mod layout;
+pub mod logging;
+pub mod recent;
+pub mod render;
 pub mod renderer;
+pub mod snapshot;
+pub mod stats;
 pub mod window;
 
 use log::info;
 use winit::event_loop::{ControlFlow, EventLoop};
 
+use crate::bridge::ServerAddress;
+use crate::cli::WindowOptions;
+use crate::daemon::Daemon;
 use crate::event::UserEvent;
+use crate::ipc::{Instance, InstanceError};
 use crate::window::GuiApp;
 
-pub fn run(args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+/// Runs the GUI, spawning Neovim with `args`, or attaching to `server`.
+/// Returns the code to exit with, Neovim's own.
+pub fn run(
+    args: Vec<String>,
+    server: Option<ServerAddress>,
+    options: WindowOptions,
+) -> Result<i32, Box<dyn std::error::Error>> {
     let event_loop = EventLoop::<UserEvent>::with_user_event().build()?;
     event_loop.set_control_flow(ControlFlow::Wait);
 
     let proxy = event_loop.create_proxy();
+    // Dropped on the way out, freeing the address for the next window.
+    let _instance = match Instance::listen(proxy.clone()) {
+        Ok(instance) => Some(instance),
+        Err(InstanceError::AlreadyRunning(_)) => None,
+        Err(e) => {
+            log::warn!("Not listening for --remote: {}", e);
+            None
+        }
+    };
 
     let config = config::Config::load();
-    let mut app = GuiApp::new(proxy, config, args);
+    crash_report::install(&config);
+    let mut app = GuiApp::new(proxy, config, args, server, options);
 
     info!("Starting event loop");
     event_loop.run_app(&mut app)?;
 
+    info!("gui.nvim shutting down");
+    Ok(app.exit_code())
+}
+
+/// Runs as a daemon: Neovim starts right away, the window once a later
+/// `gui.nvim` hands over its files.
+pub fn run_daemon() -> Result<(), Box<dyn std::error::Error>> {
+    let event_loop = EventLoop::<UserEvent>::with_user_event().build()?;
+    event_loop.set_control_flow(ControlFlow::Wait);
+
+    let proxy = event_loop.create_proxy();
+    let daemon = Daemon::listen(proxy.clone())?;
+
+    let config = config::Config::load();
+    crash_report::install(&config);
+    daemon::warm_up(config.clone());
+    let mut app = GuiApp::new(proxy, config, Vec::new(), None, WindowOptions::default());
+    app.set_daemon(daemon);
+
+    info!("Starting event loop as a daemon");
+    event_loop.run_app(&mut app)?;
+
     info!("gui.nvim shutting down");
     Ok(())
 }
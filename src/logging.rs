@@ -0,0 +1,251 @@
+//! Logging. Records go to stderr as `RUST_LOG` says, as before, and with
+//! `--log-file` to a file too, timestamped and rotated before it grows
+//! past `MAX_FILE_BYTES`. The last `RECENT_LINES` are also kept in memory,
+//! so `:GuiLog` can show them even when gui.nvim was started from Finder
+//! or a desktop launcher, with nowhere to read stderr.
+//!
+//! `--log-level` sets the level of the file and of `:GuiLog`, `info`
+//! unless given. Release builds leave out everything below `info`.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::snapshot::civil_date;
+
+/// Lines `:GuiLog` shows at most.
+const RECENT_LINES: usize = 1000;
+
+/// Size a log file is rotated at.
+const MAX_FILE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Rotated files kept, as `gui-nvim.log.1` (the newest) and up.
+const ROTATED_FILES: usize = 3;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// The lines logged last, oldest first, for `:GuiLog`.
+pub fn recent_lines() -> Vec<String> {
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    recent.iter().cloned().collect()
+}
+
+fn remember(recent: &mut VecDeque<String>, line: String) {
+    if recent.len() == RECENT_LINES {
+        recent.pop_front();
+    }
+    recent.push_back(line);
+}
+
+/// `YYYY-MM-DDTHH:MM:SS.mmmZ`, `since_epoch` after 1970-01-01.
+fn timestamp(since_epoch: std::time::Duration) -> String {
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_date(secs);
+    let time = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+fn format_line(since_epoch: std::time::Duration, record: &Record) -> String {
+    format!(
+        "{} {:<5} {}: {}",
+        timestamp(since_epoch),
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+/// `path` with `.n` added, such as `gui-nvim.log.2`.
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// A log file that moves aside to `rotated(path, 1)` once full, shifting
+/// older ones up and dropping the one past `ROTATED_FILES`.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    len: u64,
+}
+
+impl RotatingFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = File::options().create(true).append(true).open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            len,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let bytes = line.len() as u64 + 1;
+        if self.len > 0 && self.len + bytes > MAX_FILE_BYTES {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.len += bytes;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for n in (1..ROTATED_FILES).rev() {
+            let from = rotated(&self.path, n);
+            if from.exists() {
+                std::fs::rename(from, rotated(&self.path, n + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, rotated(&self.path, 1))?;
+        *self = Self::open(&self.path)?;
+        Ok(())
+    }
+}
+
+struct Logger {
+    stderr: env_logger::Logger,
+    /// Level of the file and the recent lines.
+    level: LevelFilter,
+    file: Option<Mutex<RotatingFile>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.stderr.enabled(metadata) || metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if self.stderr.matches(record) {
+            self.stderr.log(record);
+        }
+        if record.level() > self.level {
+            return;
+        }
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let line = format_line(since_epoch, record);
+        if let Some(file) = &self.file {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            // Nowhere better to report it than stderr.
+            if let Err(e) = file.write_line(&line) {
+                eprintln!("Failed to write to {}: {}", file.path.display(), e);
+            }
+        }
+        remember(&mut RECENT.lock().unwrap_or_else(|e| e.into_inner()), line);
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap_or_else(|e| e.into_inner()).file.flush();
+        }
+    }
+}
+
+/// Sets up logging for the process: to stderr, to `file` if given, and to
+/// the recent lines, the last two at `level` or `info`.
+pub fn init(file: Option<&Path>, level: Option<LevelFilter>) {
+    let stderr =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).build();
+    let level = level.unwrap_or(LevelFilter::Info);
+    let mut failed = None;
+    let file = file.and_then(|path| match RotatingFile::open(path) {
+        Ok(file) => Some(Mutex::new(file)),
+        Err(e) => {
+            failed = Some((path, e));
+            None
+        }
+    });
+    log::set_max_level(stderr.filter().max(level));
+    let logger = Logger {
+        stderr,
+        level,
+        file,
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_err() {
+        return;
+    }
+    if let Some((path, e)) = failed {
+        log::warn!("Not logging to {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_lines_are_timestamped() {
+        let since_epoch = Duration::from_millis(1_791_980_130_042);
+        let line = format_line(
+            since_epoch,
+            &Record::builder()
+                .level(log::Level::Warn)
+                .target("gui_nvim::window")
+                .args(format_args!("Unknown GUI command: {}", "nope"))
+                .build(),
+        );
+        assert_eq!(
+            line,
+            "2026-10-14T12:15:30.042Z WARN  gui_nvim::window: Unknown GUI command: nope"
+        );
+    }
+
+    #[test]
+    fn test_recent_lines_are_capped() {
+        let mut recent = VecDeque::new();
+        for i in 0..RECENT_LINES + 5 {
+            remember(&mut recent, i.to_string());
+        }
+        assert_eq!(recent.len(), RECENT_LINES);
+        assert_eq!(recent.front().map(String::as_str), Some("5"));
+    }
+
+    #[test]
+    fn test_files_rotate_when_full() {
+        let dir = std::env::temp_dir().join(format!("gui-nvim-log-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("gui-nvim.log");
+        let line = "x".repeat(MAX_FILE_BYTES as usize / 2);
+
+        let mut file = RotatingFile::open(&path).unwrap();
+        for _ in 0..2 * (ROTATED_FILES + 2) {
+            file.write_line(&line).unwrap();
+        }
+        drop(file);
+        // Two lines don't fit in a file with their newlines.
+        for n in 1..=ROTATED_FILES {
+            let len = std::fs::metadata(rotated(&path, n)).unwrap().len();
+            assert_eq!(len, line.len() as u64 + 1);
+        }
+        assert!(!rotated(&path, ROTATED_FILES + 1).exists());
+
+        // Reopening appends.
+        let mut file = RotatingFile::open(&path).unwrap();
+        assert_eq!(file.len, line.len() as u64 + 1);
+        file.write_line("short").unwrap();
+        assert_eq!(file.len, line.len() as u64 + 7);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
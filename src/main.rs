@@ -1,12 +1,15 @@
 use clap::Parser;
+use gui_nvim::bench::{self, BenchOptions};
+use gui_nvim::bridge::ServerAddress;
 use gui_nvim::cli::{Cli, Command};
-use gui_nvim::{env, run};
+use gui_nvim::render::{self, RenderOptions};
+use gui_nvim::{daemon, env, gpu_info, ipc, logging, run, run_daemon};
 use log::info;
+use std::time::Duration;
 
 fn main() {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-
     let cli = Cli::parse();
+    logging::init(cli.log_file.as_deref(), cli.log_level);
 
     match cli.command {
         Some(Command::Env) => match env::dump_env() {
@@ -25,7 +28,53 @@ fn main() {
                 std::process::exit(1);
             }
         },
+        Some(Command::Bench { frames, cols, rows }) => {
+            if let Err(e) = bench::run(BenchOptions { frames, cols, rows }) {
+                eprintln!("Benchmark failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::GpuInfo) => {
+            if let Err(e) = gpu_info::run() {
+                eprintln!("GPU info failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Render {
+            out,
+            commands,
+            cols,
+            rows,
+            timeout,
+        }) => {
+            if let Err(e) = render::run(RenderOptions {
+                out,
+                commands,
+                cols,
+                rows,
+                timeout: Duration::from_secs(timeout),
+            }) {
+                eprintln!("Render failed: {}", e);
+                std::process::exit(1);
+            }
+        }
         None => {
+            let options = cli.window_options();
+            if cli.remote {
+                if let Some(code) = ipc::open_in_instance(&cli.nvim_args) {
+                    if options.is_set() {
+                        log::warn!("Ignoring window options for the running gui.nvim");
+                    }
+                    std::process::exit(code);
+                }
+            }
+            // Before anything else, so opening in a daemon stays quick.
+            if !cli.daemon && cli.server.is_none() && !options.is_set() {
+                if let Some(code) = daemon::open_in_daemon(&cli.nvim_args) {
+                    std::process::exit(code);
+                }
+            }
+
             match env::load_env() {
                 Ok(Some(count)) => {
                     info!("Loaded {} environment variables from config", count);
@@ -40,9 +89,32 @@ fn main() {
 
             info!("gui.nvim starting");
 
-            if let Err(e) = run(cli.nvim_args) {
-                log::error!("Application error: {}", e);
-                std::process::exit(1);
+            if cli.daemon {
+                if options.is_set() {
+                    log::warn!("Ignoring window options in a daemon; pass them to each launch");
+                }
+                if let Err(e) = run_daemon() {
+                    log::error!("Daemon error: {}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+
+            let server = cli.server.as_deref().map(ServerAddress::parse);
+            if server.is_some() && !cli.nvim_args.is_empty() {
+                log::warn!("Ignoring Neovim arguments when attaching to a server");
+            }
+            if server.is_some() && options.cwd.is_some() {
+                log::warn!("Ignoring --cwd when attaching to a server");
+            }
+
+            match run(cli.nvim_args, server, options) {
+                Ok(0) => {}
+                Ok(code) => std::process::exit(code),
+                Err(e) => {
+                    log::error!("Application error: {}", e);
+                    std::process::exit(1);
+                }
             }
         }
     }
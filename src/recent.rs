@@ -0,0 +1,219 @@
+//! Recently edited files with their last cursor positions.
+//!
+//! Neovim reports the file and cursor whenever a buffer is left (see
+//! `NeovimProcess::register_recent_files`). The list backs the startup
+//! dashboard shown when gui.nvim is launched without files.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_dir;
+
+/// Number of files kept in the list.
+pub const MAX_RECENT_FILES: usize = 20;
+
+/// Neovim options that consume the argument after them, so it isn't a file.
+const OPTIONS_WITH_VALUE: &[&str] = &[
+    "-c",
+    "--cmd",
+    "-i",
+    "-l",
+    "-r",
+    "-s",
+    "-S",
+    "-t",
+    "-q",
+    "-u",
+    "-w",
+    "-W",
+    "--listen",
+    "--startuptime",
+    "--server",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentFile {
+    pub path: String,
+    /// Cursor line, 1-based.
+    pub line: u64,
+    /// Cursor column, 1-based.
+    pub col: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentFiles {
+    #[serde(default)]
+    pub files: Vec<RecentFile>,
+}
+
+impl RecentFile {
+    /// Ex command that opens the file with the cursor where it was left.
+    pub fn open_command(&self) -> String {
+        format!(
+            "execute 'edit ' . fnameescape('{}') | call cursor({}, {})",
+            self.path.replace('\'', "''"),
+            self.line,
+            self.col
+        )
+    }
+}
+
+impl RecentFiles {
+    /// Loads the list from the config directory. A missing or unreadable
+    /// file gives an empty list.
+    pub fn load() -> Self {
+        recent_files_path()
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        toml::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("Failed to parse {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), RecentFilesError> {
+        let path = recent_files_path().ok_or(RecentFilesError::NoConfigDir)?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), RecentFilesError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Moves `path` to the front of the list with its latest cursor
+    /// position, dropping the oldest entry past `MAX_RECENT_FILES`.
+    pub fn record(&mut self, path: &str, line: u64, col: u64) {
+        self.files.retain(|f| f.path != path);
+        self.files.insert(
+            0,
+            RecentFile {
+                path: path.to_string(),
+                line,
+                col,
+            },
+        );
+        self.files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Entries whose file still exists.
+    pub fn existing(&self) -> Vec<RecentFile> {
+        self.files
+            .iter()
+            .filter(|f| Path::new(&f.path).exists())
+            .cloned()
+            .collect()
+    }
+}
+
+/// Location: `~/.config/gui-nvim/recent.toml`
+pub fn recent_files_path() -> Option<PathBuf> {
+    config_dir().map(|p| p.join("recent.toml"))
+}
+
+/// Whether the Neovim arguments name a file to open (or read stdin), in
+/// which case the dashboard stays out of the way.
+pub fn has_file_args(args: &[String]) -> bool {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            return args.next().is_some();
+        }
+        if OPTIONS_WITH_VALUE.contains(&arg.as_str()) {
+            args.next();
+        } else if arg == "-" || !arg.starts_with(['-', '+']) {
+            return true;
+        }
+    }
+    false
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecentFilesError {
+    #[error("Could not determine config directory")]
+    NoConfigDir,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_record_moves_to_front_and_caps() {
+        let mut recent = RecentFiles::default();
+        recent.record("/a", 1, 1);
+        recent.record("/b", 2, 1);
+        recent.record("/a", 10, 4);
+
+        assert_eq!(recent.files.len(), 2);
+        assert_eq!(
+            recent.files[0],
+            RecentFile {
+                path: "/a".to_string(),
+                line: 10,
+                col: 4
+            }
+        );
+
+        for i in 0..MAX_RECENT_FILES + 5 {
+            recent.record(&format!("/f{}", i), 1, 1);
+        }
+        assert_eq!(recent.files.len(), MAX_RECENT_FILES);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("gui-nvim-recent-{}", std::process::id()));
+        let path = dir.join("recent.toml");
+        let mut recent = RecentFiles::default();
+        recent.record("/tmp/x.rs", 3, 7);
+        recent.save_to(&path).unwrap();
+
+        assert_eq!(RecentFiles::load_from(&path), recent);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(RecentFiles::load_from(&path), RecentFiles::default());
+    }
+
+    #[test]
+    fn test_open_command_quotes_path() {
+        let file = RecentFile {
+            path: "/tmp/it's here.txt".to_string(),
+            line: 12,
+            col: 3,
+        };
+        assert_eq!(
+            file.open_command(),
+            "execute 'edit ' . fnameescape('/tmp/it''s here.txt') | call cursor(12, 3)"
+        );
+    }
+
+    #[test]
+    fn test_has_file_args() {
+        assert!(!has_file_args(&args(&[])));
+        assert!(!has_file_args(&args(&["--clean", "-c", "set nu"])));
+        assert!(!has_file_args(&args(&["-u", "init.lua", "+10"])));
+        assert!(has_file_args(&args(&["main.rs"])));
+        assert!(has_file_args(&args(&["-c", "x", "main.rs"])));
+        assert!(has_file_args(&args(&["-"])));
+        assert!(has_file_args(&args(&["--", "-weird-name"])));
+    }
+}
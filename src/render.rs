@@ -0,0 +1,214 @@
+//! `gui.nvim render`: runs Neovim without a window, draws its screen
+//! offscreen and writes it to a PNG once the given commands have run. For
+//! visual regression tests in CI, or a styled screenshot of some code.
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use nvim_rs::Value;
+use winit::dpi::PhysicalSize;
+
+use crate::bridge::events::RedrawEvent;
+use crate::bridge::{AppBridge, RequestError, RequestId};
+use crate::config::Config;
+use crate::editor::{EditorState, SpecialColors};
+use crate::event::{NeovimEvent, UserEvent};
+use crate::layout::WindowLayout;
+use crate::renderer::{CaptureError, GpuContext, Renderer, RendererError};
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub out: PathBuf,
+    /// Ex commands run after startup, such as `e file.rs`.
+    pub commands: Vec<String>,
+    pub cols: u64,
+    pub rows: u64,
+    /// How long Neovim gets to start and run the commands.
+    pub timeout: Duration,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("No GPU adapter found")]
+    NoAdapter,
+
+    #[error("Failed to create the tokio runtime: {0}")]
+    Runtime(io::Error),
+
+    #[error(transparent)]
+    Renderer(#[from] RendererError),
+
+    #[error("Neovim exited before drawing its screen")]
+    NeovimExited,
+
+    #[error("Neovim didn't finish drawing: {0}")]
+    Request(#[from] RequestError),
+
+    #[error("Failed to capture the frame: {0}")]
+    Capture(#[from] CaptureError),
+
+    #[error("Failed to write {0}: {1}")]
+    Write(PathBuf, io::Error),
+}
+
+/// Starts Neovim with the user's config, runs the commands and writes the
+/// screen as drawn afterwards to `options.out`.
+pub fn run(options: RenderOptions) -> Result<(), RenderError> {
+    let config = Config::load();
+    let (cols, rows) = (options.cols.max(1), options.rows.max(1));
+
+    // Set up the GPU first: without an adapter there is no point in
+    // starting Neovim.
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .map_err(RenderError::Runtime)?;
+    let adapter = runtime
+        .block_on(GpuContext::headless_adapters())
+        .into_iter()
+        .next()
+        .ok_or(RenderError::NoAdapter)?;
+    log::info!("Rendering on {}", adapter.get_info().name);
+
+    let mut layout = WindowLayout::new();
+    // Size the frame from the default cell size, then fit the real one.
+    let (width, height) = layout.window_size(cols, rows);
+    let size = PhysicalSize::new(width as u32, height as u32);
+    let mut renderer = runtime.block_on(Renderer::headless(&adapter, size, config.clone(), 1.0))?;
+    let (cell_width, cell_height) = renderer.cell_size();
+    layout.set_cell_size(cell_width as f64, cell_height as f64);
+    let (width, height) = layout.window_size(cols, rows);
+    renderer.resize(PhysicalSize::new(width as u32, height as u32));
+
+    let (sender, events) = mpsc::channel();
+    let bridge = AppBridge::new(sender, config.ui);
+    bridge.resize(cols, rows);
+    bridge.spawn_neovim(nvim_args(&options.commands), None);
+    // Neovim answers requests only once startup, and with it the commands,
+    // is done. `redraw` flushes the screen before the answer goes out.
+    let drawn = bridge.request("nvim_command", vec![Value::from("redraw")], options.timeout);
+
+    let mut state = EditorState::new(cols as usize, rows as usize);
+    state
+        .highlights
+        .set_special_colors(SpecialColors::from_config(&config.special_colors));
+    let result = wait_until_drawn(&events, drawn, &mut state).and_then(|default_colors| {
+        if let Some((fg, bg)) = default_colors {
+            renderer.update_default_colors(fg, bg);
+        }
+        let png = renderer.capture_png(&state, &layout)?;
+        std::fs::write(&options.out, png).map_err(|e| RenderError::Write(options.out.clone(), e))
+    });
+    bridge.shutdown();
+    result
+}
+
+/// Neovim's arguments for running `commands` once its config has loaded.
+fn nvim_args(commands: &[String]) -> Vec<String> {
+    commands
+        .iter()
+        .flat_map(|command| ["-c".to_string(), command.clone()])
+        .collect()
+}
+
+/// Applies redraws until the answer to request `drawn` arrives. Returns
+/// the last default colors set, which the renderer draws with.
+fn wait_until_drawn(
+    events: &mpsc::Receiver<UserEvent>,
+    drawn: RequestId,
+    state: &mut EditorState,
+) -> Result<Option<(u32, u32)>, RenderError> {
+    let mut default_colors = None;
+    // The request is always answered, with a timeout at worst, so this
+    // only ends early if the bridge does.
+    while let Ok(event) = events.recv() {
+        let UserEvent::Neovim(event) = event else {
+            continue;
+        };
+        match event {
+            NeovimEvent::Redraw(redraw) => {
+                for event in &redraw {
+                    state.handle_redraw_event(event);
+                    if let RedrawEvent::DefaultColorsSet { fg, bg, .. } = *event {
+                        default_colors = Some((fg, bg));
+                    }
+                }
+            }
+            NeovimEvent::Response { id, result } if id == drawn => {
+                return result.map(|_| default_colors).map_err(RenderError::from);
+            }
+            NeovimEvent::Quit(_) | NeovimEvent::Disconnected | NeovimEvent::Exited { .. } => {
+                return Err(RenderError::NeovimExited);
+            }
+            _ => {}
+        }
+    }
+    Err(RenderError::NeovimExited)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nvim_args() {
+        let commands = ["e file.rs".to_string(), "set nu".to_string()];
+        assert_eq!(nvim_args(&commands), ["-c", "e file.rs", "-c", "set nu"]);
+        assert!(nvim_args(&[]).is_empty());
+    }
+
+    fn neovim(event: NeovimEvent) -> UserEvent {
+        UserEvent::Neovim(event)
+    }
+
+    #[test]
+    fn test_wait_until_drawn() {
+        let (sender, events) = mpsc::channel();
+        let mut state = EditorState::new(4, 2);
+        let colors = RedrawEvent::DefaultColorsSet {
+            fg: 0xffffff,
+            bg: 0x101010,
+            sp: 0xff0000,
+        };
+        sender
+            .send(neovim(NeovimEvent::Redraw(vec![
+                colors,
+                RedrawEvent::Flush,
+            ])))
+            .unwrap();
+        sender
+            .send(neovim(NeovimEvent::Response {
+                id: 1,
+                result: Err(RequestError::NotConnected),
+            }))
+            .unwrap();
+        sender
+            .send(neovim(NeovimEvent::Response {
+                id: 2,
+                result: Ok(Value::Nil),
+            }))
+            .unwrap();
+        assert_eq!(
+            wait_until_drawn(&events, 2, &mut state).unwrap(),
+            Some((0xffffff, 0x101010))
+        );
+        assert_eq!(state.highlights.defaults.background.0 >> 8, 0x101010);
+    }
+
+    #[test]
+    fn test_quit_before_drawn() {
+        let (sender, events) = mpsc::channel();
+        let mut state = EditorState::new(4, 2);
+        sender.send(neovim(NeovimEvent::Quit(0))).unwrap();
+        assert!(matches!(
+            wait_until_drawn(&events, 1, &mut state),
+            Err(RenderError::NeovimExited)
+        ));
+        drop(sender);
+        assert!(matches!(
+            wait_until_drawn(&events, 1, &mut state),
+            Err(RenderError::NeovimExited)
+        ));
+    }
+}
@@ -6,33 +6,264 @@ use super::GpuContext;
 
 const INITIAL_ATLAS_SIZE: u32 = 1024;
 const MAX_ATLAS_SIZE: u32 = 8192;
+/// Pages (texture array layers) added once a page can't grow any further.
+const MAX_ATLAS_PAGES: u32 = 4;
 const ATLAS_PADDING: u32 = 1;
 
+/// Box that color glyphs (emoji) are scaled to fit before they go into the
+/// atlas. Color fonts often only come in fixed bitmap strikes far from the
+/// requested size, so their glyphs would otherwise overflow or underfill
+/// the cell. The box is two cells wide, as emoji are double-width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorGlyphBox {
+    pub cell_width: f32,
+    pub cell_height: f32,
+    /// Distance from the top of the cell to the baseline.
+    pub baseline: f32,
+    /// `font.emoji_scale`, applied to the box size.
+    pub scale: f32,
+}
+
+impl ColorGlyphBox {
+    /// Scales `glyph` to fit the box, keeping its aspect ratio, and moves
+    /// its bearings so it sits centered in two cells.
+    pub fn fit(&self, glyph: RasterizedGlyph) -> RasterizedGlyph {
+        let GlyphBuffer::Rgba(ref src) = glyph.buffer else {
+            return glyph;
+        };
+        if glyph.width == 0 || glyph.height == 0 {
+            return glyph;
+        }
+
+        let box_width = 2.0 * self.cell_width;
+        let factor = (box_width * self.scale / glyph.width as f32)
+            .min(self.cell_height * self.scale / glyph.height as f32);
+        let width = ((glyph.width as f32 * factor).round() as u32).max(1);
+        let height = ((glyph.height as f32 * factor).round() as u32).max(1);
+
+        let buffer = if (width, height) == (glyph.width, glyph.height) {
+            glyph.buffer
+        } else {
+            GlyphBuffer::Rgba(resample_rgba(src, glyph.width, glyph.height, width, height))
+        };
+
+        RasterizedGlyph {
+            character: glyph.character,
+            width,
+            height,
+            bearing_x: ((box_width - width as f32) / 2.0).round() as i32,
+            bearing_y: (self.baseline - (self.cell_height - height as f32) / 2.0).round() as i32,
+            buffer,
+        }
+    }
+}
+
+/// Resizes a premultiplied RGBA image. Each destination pixel averages the
+/// source pixels it covers, which is a box filter when shrinking and
+/// nearest-neighbour when enlarging.
+fn resample_rgba(src: &[u8], src_w: u32, src_h: u32, dst_w: u32, dst_h: u32) -> Vec<u8> {
+    let span = |d: u32, dst: u32, src: u32| {
+        let start = (d as u64 * src as u64 / dst as u64) as u32;
+        let end = ((d as u64 + 1) * src as u64).div_ceil(dst as u64) as u32;
+        start..end.max(start + 1).min(src)
+    };
+
+    let mut dst = Vec::with_capacity((dst_w * dst_h * 4) as usize);
+    for dy in 0..dst_h {
+        let rows = span(dy, dst_h, src_h);
+        for dx in 0..dst_w {
+            let cols = span(dx, dst_w, src_w);
+            let mut sum = [0u32; 4];
+            for sy in rows.clone() {
+                for sx in cols.clone() {
+                    let i = ((sy * src_w + sx) * 4) as usize;
+                    for (acc, &v) in sum.iter_mut().zip(&src[i..i + 4]) {
+                        *acc += v as u32;
+                    }
+                }
+            }
+            let count = rows.len() as u32 * cols.len() as u32;
+            dst.extend(sum.iter().map(|&v| ((v + count / 2) / count) as u8));
+        }
+    }
+    dst
+}
+
+/// A row of glyphs in the atlas. Glyphs are packed left to right; the
+/// whole row is reclaimed at once when it is evicted.
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    next_x: u32,
+    /// Frame in which a glyph on this shelf was last drawn.
+    last_used: u64,
+    glyphs: Vec<GlyphCacheKey>,
+}
+
+/// Shelf packing with least-recently-used eviction. Sizes include
+/// `ATLAS_PADDING`. Shelves are kept sorted by `y`.
+#[derive(Debug, Default)]
+struct ShelfAllocator {
+    shelves: Vec<Shelf>,
+}
+
+impl ShelfAllocator {
+    /// Finds room for a `width` x `height` glyph in an atlas of `size`,
+    /// returning its position and shelf. Prefers the shortest shelf the
+    /// glyph fits on; the bottom shelf may grow taller while there is room
+    /// below it.
+    fn allocate(&mut self, width: u32, height: u32, size: u32) -> Option<(u32, u32, usize)> {
+        let last = self.shelves.len().checked_sub(1);
+        let fits = |i: usize, shelf: &Shelf| {
+            shelf.next_x + width <= size
+                && (shelf.height >= height || (Some(i) == last && shelf.y + height <= size))
+        };
+        let index = self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(i, shelf)| fits(*i, shelf))
+            .min_by_key(|(i, shelf)| (shelf.height < height, shelf.height, *i))
+            .map(|(i, _)| i);
+
+        let index = match index {
+            Some(i) => i,
+            None => {
+                let y = self.shelves.last().map_or(0, |s| s.y + s.height);
+                if y + height > size || width > size {
+                    return None;
+                }
+                self.shelves.push(Shelf {
+                    y,
+                    height,
+                    next_x: 0,
+                    last_used: 0,
+                    glyphs: Vec::new(),
+                });
+                self.shelves.len() - 1
+            }
+        };
+
+        let shelf = &mut self.shelves[index];
+        let x = shelf.next_x;
+        shelf.next_x += width;
+        shelf.height = shelf.height.max(height);
+        Some((x, shelf.y, index))
+    }
+
+    fn record(&mut self, index: usize, key: GlyphCacheKey, frame: u64) {
+        let shelf = &mut self.shelves[index];
+        shelf.glyphs.push(key);
+        shelf.last_used = frame;
+    }
+
+    /// Marks the shelf at `y` as used in `frame`.
+    fn touch(&mut self, y: u32, frame: u64) {
+        if let Ok(i) = self.shelves.binary_search_by_key(&y, |s| s.y) {
+            self.shelves[i].last_used = frame;
+        }
+    }
+
+    /// Frame in which the shelf `evict_lru` would pick was last used.
+    fn coldest(&self, height: u32, frame: u64) -> Option<u64> {
+        self.shelves
+            .iter()
+            .filter(|s| s.height >= height && s.last_used < frame && !s.glyphs.is_empty())
+            .map(|s| s.last_used)
+            .min()
+    }
+
+    /// Empties the least recently used shelf that is at least `height`
+    /// tall and was not drawn from in `frame`, returning the glyphs it held.
+    fn evict_lru(&mut self, height: u32, frame: u64) -> Option<Vec<GlyphCacheKey>> {
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|s| s.height >= height && s.last_used < frame && !s.glyphs.is_empty())
+            .min_by_key(|s| s.last_used)?;
+        shelf.next_x = 0;
+        Some(std::mem::take(&mut shelf.glyphs))
+    }
+}
+
+/// Where a glyph was placed: position within `page`, and the shelf on it.
+#[derive(Debug, Clone, Copy)]
+struct AtlasSlot {
+    x: u32,
+    y: u32,
+    page: u32,
+    shelf: usize,
+}
+
 /// Texture atlas for storing rasterized glyphs.
 ///
-/// Starts at `INITIAL_ATLAS_SIZE` and grows by doubling when full, up to
-/// `MAX_ATLAS_SIZE`. Existing glyph data is preserved via GPU texture copy.
+/// The atlas is a texture array of equally sized pages. It starts as one
+/// `INITIAL_ATLAS_SIZE` page that grows by doubling when full, up to
+/// `MAX_ATLAS_SIZE` or the device limit, and then gains pages up to
+/// `MAX_ATLAS_PAGES`. Existing glyph data is preserved via GPU texture copy.
 /// The `generation` counter increments on each resize so consumers (e.g. the
 /// renderer's bind group and UV inverse) can detect when they need to update.
+///
+/// Once no more pages fit, the least recently drawn shelf of glyphs is
+/// evicted to make room. Shelves drawn from in the current frame (see
+/// `begin_frame`) are never evicted.
 pub struct GlyphAtlas {
     texture: wgpu::Texture,
     texture_view: wgpu::TextureView,
     sampler: wgpu::Sampler,
+    /// Width and height of every page.
     size: u32,
-    current_row_y: u32,
-    current_row_x: u32,
-    current_row_height: u32,
+    /// Device-limited `MAX_ATLAS_SIZE` and `MAX_ATLAS_PAGES`.
+    max_size: u32,
+    max_pages: u32,
+    /// Glyph packing, one allocator per page.
+    pages: Vec<ShelfAllocator>,
+    /// Current frame number, for LRU eviction.
+    frame: u64,
     cache: ShapedGlyphCache,
     /// Incremented each time the atlas texture is resized.
     generation: u64,
+    /// Shelves evicted so far. Quads batched before an eviction may
+    /// sample glyphs that were since overwritten.
+    evictions: u64,
+    /// Color glyphs are scaled to this box when set.
+    color_glyph_box: Option<ColorGlyphBox>,
+    /// Lifetime glyph cache lookups, reported by `:GuiStats`.
+    cache_hits: u64,
+    cache_misses: u64,
+}
+
+/// How large the atlas starts, and how far it grows, within the device's
+/// texture limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct AtlasLimits {
+    pub initial_size: u32,
+    pub max_size: u32,
+    pub max_pages: u32,
+}
+
+impl AtlasLimits {
+    pub fn from_limits(limits: &wgpu::Limits) -> Self {
+        let max_size = MAX_ATLAS_SIZE.min(limits.max_texture_dimension_2d);
+        Self {
+            initial_size: INITIAL_ATLAS_SIZE.min(max_size),
+            max_size,
+            max_pages: MAX_ATLAS_PAGES.min(limits.max_texture_array_layers),
+        }
+    }
 }
 
 impl GlyphAtlas {
     pub fn new(ctx: &GpuContext) -> Self {
-        let size = INITIAL_ATLAS_SIZE;
+        let AtlasLimits {
+            initial_size: size,
+            max_size,
+            max_pages,
+        } = AtlasLimits::from_limits(&ctx.device.limits());
 
-        let texture = Self::create_texture(ctx, size);
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let texture = Self::create_texture(ctx, size, 1);
+        let texture_view = Self::create_view(&texture);
 
         let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Glyph Sampler"),
@@ -50,14 +281,30 @@ impl GlyphAtlas {
             texture_view,
             sampler,
             size,
-            current_row_y: 0,
-            current_row_x: 0,
-            current_row_height: 0,
+            max_size,
+            max_pages,
+            pages: vec![ShelfAllocator::default()],
+            frame: 0,
             cache: ShapedGlyphCache::new(),
             generation: 0,
+            evictions: 0,
+            color_glyph_box: None,
+            cache_hits: 0,
+            cache_misses: 0,
         }
     }
 
+    /// Sets the box color glyphs are fitted to. Glyphs already in the atlas
+    /// keep their size, so call this before populating it.
+    pub fn set_color_glyph_box(&mut self, glyph_box: ColorGlyphBox) {
+        self.color_glyph_box = Some(glyph_box);
+    }
+
+    /// Starts a new frame. Glyphs looked up from now on count as used by it.
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+    }
+
     pub fn texture_view(&self) -> &wgpu::TextureView {
         &self.texture_view
     }
@@ -77,13 +324,36 @@ impl GlyphAtlas {
         self.generation
     }
 
-    fn create_texture(ctx: &GpuContext, size: u32) -> wgpu::Texture {
+    /// Number of shelves evicted to make room, for consumers that keep
+    /// quads across frames.
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// Lifetime (hits, misses) of the glyph cache.
+    pub fn cache_stats(&self) -> (u64, u64) {
+        (self.cache_hits, self.cache_misses)
+    }
+
+    /// Size of the atlas texture in bytes (RGBA8, all pages).
+    pub fn texture_bytes(&self) -> u64 {
+        self.size as u64 * self.size as u64 * 4 * self.pages.len() as u64
+    }
+
+    fn create_texture(ctx: &GpuContext, size: u32, pages: u32) -> wgpu::Texture {
+        // GL picks the view dimension from the layer count, and a single
+        // layer would make the texture a plain 2D one, not an array.
+        let layers = if ctx.adapter_info().backend == wgpu::Backend::Gl {
+            pages.max(2)
+        } else {
+            pages
+        };
         ctx.device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Glyph Atlas"),
             size: wgpu::Extent3d {
                 width: size,
                 height: size,
-                depth_or_array_layers: 1,
+                depth_or_array_layers: layers,
             },
             mip_level_count: 1,
             sample_count: 1,
@@ -96,6 +366,13 @@ impl GlyphAtlas {
         })
     }
 
+    fn create_view(texture: &wgpu::Texture) -> wgpu::TextureView {
+        texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        })
+    }
+
     /// Get a glyph by ID, or rasterize and cache it.
     ///
     /// This method uses the HarfBuzz-based shaping system where glyphs are
@@ -119,15 +396,24 @@ impl GlyphAtlas {
         key: GlyphCacheKey,
     ) -> (Option<ShapedCachedGlyph>, bool) {
         if let Some(cached_result) = self.cache.get(&key) {
-            return (cached_result.copied(), true);
+            self.cache_hits += 1;
+            let cached_result = cached_result.copied();
+            if let Some(glyph) = cached_result.filter(|g| g.width > 0) {
+                self.pages[glyph.page as usize].touch(glyph.atlas_y, self.frame);
+            }
+            return (cached_result, true);
         }
+        self.cache_misses += 1;
 
         let face = match collection.get_face(key.font_index) {
             Some(f) => f,
             None => return (None, false),
         };
         let rasterized = match face.render_glyph(key.glyph_id) {
-            Ok(g) => g,
+            Ok(g) => match self.color_glyph_box {
+                Some(glyph_box) if g.buffer.is_colored() => glyph_box.fit(g),
+                _ => g,
+            },
             Err(e) => {
                 log::warn!(
                     "Failed to rasterize glyph {} (font {:?}): {}",
@@ -146,65 +432,44 @@ impl GlyphAtlas {
                 atlas_y: 0,
                 width: 0,
                 height: 0,
+                page: 0,
                 bearing_x: rasterized.bearing_x,
                 bearing_y: rasterized.bearing_y,
                 is_colored: rasterized.buffer.is_colored(),
+                luminance: rasterized.buffer.luminance(),
             };
             self.cache.insert(key, Some(cached));
             return (Some(cached), false);
         }
 
-        let (atlas_x, atlas_y) = match self.allocate(rasterized.width, rasterized.height) {
-            Some(coords) => coords,
-            None => {
-                // Atlas is full — try to grow and retry the allocation.
-                if self.grow(ctx) {
-                    match self.allocate(rasterized.width, rasterized.height) {
-                        Some(coords) => coords,
-                        None => {
-                            log::error!(
-                                "Glyph {}x{} does not fit even after atlas resize to {}",
-                                rasterized.width,
-                                rasterized.height,
-                                self.size,
-                            );
-                            return (None, false);
-                        }
-                    }
-                } else {
-                    return (None, false);
-                }
-            }
+        let Some(slot) = self.allocate(ctx, rasterized.width, rasterized.height) else {
+            return (None, false);
         };
-        self.upload(ctx, &rasterized, atlas_x, atlas_y);
+        self.pages[slot.page as usize].record(slot.shelf, key, self.frame);
+        self.upload(ctx, &rasterized, slot);
 
         let cached = ShapedCachedGlyph {
-            atlas_x,
-            atlas_y,
+            atlas_x: slot.x,
+            atlas_y: slot.y,
             width: rasterized.width,
             height: rasterized.height,
+            page: slot.page,
             bearing_x: rasterized.bearing_x,
             bearing_y: rasterized.bearing_y,
             is_colored: rasterized.buffer.is_colored(),
+            luminance: rasterized.buffer.luminance(),
         };
 
         self.cache.insert(key, Some(cached));
         (Some(cached), false)
     }
 
-    /// Double the atlas texture size, copying existing glyph data to the new
-    /// texture. Returns `true` if growth succeeded, `false` if already at max.
+    /// Double the page size. Returns `false` if already at max.
     fn grow(&mut self, ctx: &GpuContext) -> bool {
         let new_size = self.size * 2;
-        if new_size > MAX_ATLAS_SIZE {
-            log::error!(
-                "Glyph atlas already at maximum size ({}x{}), cannot grow",
-                self.size,
-                self.size,
-            );
+        if new_size > self.max_size {
             return false;
         }
-
         log::info!(
             "Growing glyph atlas from {}x{} to {}x{}",
             self.size,
@@ -212,10 +477,27 @@ impl GlyphAtlas {
             new_size,
             new_size,
         );
+        self.resize(ctx, new_size, self.pages.len() as u32);
+        true
+    }
+
+    /// Add an empty page. Returns `false` if already at max.
+    fn add_page(&mut self, ctx: &GpuContext) -> bool {
+        let pages = self.pages.len() as u32 + 1;
+        if pages > self.max_pages {
+            return false;
+        }
+        log::info!("Adding glyph atlas page {} of {}", pages, self.max_pages);
+        self.resize(ctx, self.size, pages);
+        self.pages.push(ShelfAllocator::default());
+        true
+    }
 
-        let new_texture = Self::create_texture(ctx, new_size);
+    /// Replace the texture with one of `size` and `pages`, copying existing
+    /// pages into the top-left corner of the new ones.
+    fn resize(&mut self, ctx: &GpuContext, size: u32, pages: u32) {
+        let new_texture = Self::create_texture(ctx, size, pages);
 
-        // Copy existing texture data into the top-left corner of the new texture.
         let mut encoder = ctx
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -237,55 +519,76 @@ impl GlyphAtlas {
             wgpu::Extent3d {
                 width: self.size,
                 height: self.size,
-                depth_or_array_layers: 1,
+                depth_or_array_layers: self.pages.len() as u32,
             },
         );
         ctx.queue.submit(std::iter::once(encoder.finish()));
 
         self.texture = new_texture;
-        self.texture_view = self
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        self.size = new_size;
+        self.texture_view = Self::create_view(&self.texture);
+        self.size = size;
         self.generation += 1;
-
-        true
     }
 
-    /// Allocate space in the atlas using row-based packing.
-    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+    /// Allocate space in the atlas: on any page with room, else on a grown
+    /// or new page, else in the least recently used shelf across all pages.
+    fn allocate(&mut self, ctx: &GpuContext, width: u32, height: u32) -> Option<AtlasSlot> {
         let padded_width = width + ATLAS_PADDING;
         let padded_height = height + ATLAS_PADDING;
-
-        if self.current_row_x + padded_width <= self.size {
-            let x = self.current_row_x;
-            let y = self.current_row_y;
-            self.current_row_x += padded_width;
-            self.current_row_height = self.current_row_height.max(padded_height);
-            return Some((x, y));
-        }
-
-        let new_row_y = self.current_row_y + self.current_row_height;
-        if new_row_y + padded_height > self.size {
-            log::warn!("Glyph atlas full, cannot allocate {}x{}", width, height);
+        if padded_width > self.max_size || padded_height > self.max_size {
+            log::error!("Glyph {}x{} is larger than the atlas", width, height);
             return None;
         }
 
-        self.current_row_y = new_row_y;
-        self.current_row_x = padded_width;
-        self.current_row_height = padded_height;
+        loop {
+            for (page, allocator) in self.pages.iter_mut().enumerate() {
+                if let Some((x, y, shelf)) =
+                    allocator.allocate(padded_width, padded_height, self.size)
+                {
+                    return Some(AtlasSlot {
+                        x,
+                        y,
+                        page: page as u32,
+                        shelf,
+                    });
+                }
+            }
+            if self.grow(ctx) || self.add_page(ctx) {
+                continue;
+            }
 
-        Some((0, new_row_y))
+            let coldest = self
+                .pages
+                .iter()
+                .enumerate()
+                .filter_map(|(i, p)| Some((p.coldest(padded_height, self.frame)?, i)))
+                .min();
+            let evicted =
+                coldest.and_then(|(_, page)| self.pages[page].evict_lru(padded_height, self.frame));
+            let Some(evicted) = evicted else {
+                log::warn!("Glyph atlas full, cannot allocate {}x{}", width, height);
+                return None;
+            };
+            log::debug!("Evicted {} glyphs from the atlas", evicted.len());
+            self.evictions += 1;
+            for key in &evicted {
+                self.cache.remove(key);
+            }
+        }
     }
 
-    fn upload(&self, ctx: &GpuContext, glyph: &RasterizedGlyph, x: u32, y: u32) {
+    fn upload(&self, ctx: &GpuContext, glyph: &RasterizedGlyph, slot: AtlasSlot) {
         let rgba_data = self.to_rgba(&glyph.buffer, glyph.width, glyph.height);
 
         ctx.queue.write_texture(
             wgpu::TexelCopyTextureInfo {
                 texture: &self.texture,
                 mip_level: 0,
-                origin: wgpu::Origin3d { x, y, z: 0 },
+                origin: wgpu::Origin3d {
+                    x: slot.x,
+                    y: slot.y,
+                    z: slot.page,
+                },
                 aspect: wgpu::TextureAspect::All,
             },
             &rgba_data,
@@ -335,16 +638,12 @@ impl GlyphAtlas {
 
     pub fn clear(&mut self, ctx: &GpuContext) {
         self.cache.clear();
-        self.current_row_x = 0;
-        self.current_row_y = 0;
-        self.current_row_height = 0;
-
-        // Reset to initial size to reclaim GPU memory after font changes.
-        self.size = INITIAL_ATLAS_SIZE;
-        self.texture = Self::create_texture(ctx, self.size);
-        self.texture_view = self
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        self.pages = vec![ShelfAllocator::default()];
+
+        // Reset to one initial page to reclaim GPU memory after font changes.
+        self.size = INITIAL_ATLAS_SIZE.min(self.max_size);
+        self.texture = Self::create_texture(ctx, self.size, 1);
+        self.texture_view = Self::create_view(&self.texture);
         self.generation += 1;
     }
 
@@ -416,6 +715,56 @@ mod tests {
         assert_eq!(y, 20, "New row should start at previous row height");
     }
 
+    fn key(glyph_id: u32) -> GlyphCacheKey {
+        GlyphCacheKey::new(glyph_id, CollectionIndex::primary(Style::Regular))
+    }
+
+    #[test]
+    fn test_shelf_allocator_packs_rows() {
+        let mut shelves = ShelfAllocator::default();
+        assert_eq!(shelves.allocate(10, 20, 32), Some((0, 0, 0)));
+        assert_eq!(shelves.allocate(10, 22, 32), Some((10, 0, 0)));
+        // Row is full: a new shelf starts below the (grown) first one.
+        assert_eq!(shelves.allocate(20, 10, 32), Some((0, 22, 1)));
+        // A short glyph prefers the short shelf.
+        assert_eq!(shelves.allocate(5, 8, 32), Some((20, 22, 1)));
+        assert_eq!(shelves.allocate(20, 20, 32), None);
+    }
+
+    #[test]
+    fn test_shelf_allocator_evicts_least_recently_used() {
+        let mut shelves = ShelfAllocator::default();
+        for (id, frame) in [(1, 1), (2, 2)] {
+            let (_, _, shelf) = shelves.allocate(32, 16, 32).unwrap();
+            shelves.record(shelf, key(id), frame);
+        }
+        assert_eq!(shelves.allocate(8, 16, 32), None);
+
+        // The first shelf was drawn from in frame 3, so the second goes.
+        shelves.touch(0, 3);
+        assert_eq!(shelves.evict_lru(16, 3), Some(vec![key(2)]));
+        assert_eq!(shelves.allocate(8, 16, 32), Some((0, 16, 1)));
+
+        // Nothing left that wasn't used this frame.
+        shelves.record(1, key(3), 3);
+        assert_eq!(shelves.evict_lru(16, 3), None);
+        assert_eq!(shelves.evict_lru(17, 4), None);
+    }
+
+    #[test]
+    fn test_coldest_shelf_across_pages() {
+        let mut pages = [ShelfAllocator::default(), ShelfAllocator::default()];
+        for (page, frame) in [(0, 5), (1, 2)] {
+            let (_, _, shelf) = pages[page].allocate(32, 16, 32).unwrap();
+            pages[page].record(shelf, key(page as u32), frame);
+        }
+        assert_eq!(pages[0].coldest(16, 6), Some(5));
+        assert_eq!(pages[1].coldest(16, 6), Some(2));
+        // Shelves used this frame, or too short, are never candidates.
+        assert_eq!(pages[0].coldest(16, 5), None);
+        assert_eq!(pages[1].coldest(17, 6), None);
+    }
+
     #[test]
     fn test_to_rgba_rgb_conversion() {
         let rgb_data = vec![255, 128, 64];
@@ -433,6 +782,68 @@ mod tests {
         assert!(buffer.is_colored());
     }
 
+    fn color_glyph(width: u32, height: u32) -> RasterizedGlyph {
+        RasterizedGlyph {
+            character: '😀',
+            width,
+            height,
+            bearing_x: 0,
+            bearing_y: height as i32,
+            buffer: GlyphBuffer::Rgba(vec![255; (width * height * 4) as usize]),
+        }
+    }
+
+    const GLYPH_BOX: ColorGlyphBox = ColorGlyphBox {
+        cell_width: 10.0,
+        cell_height: 20.0,
+        baseline: 16.0,
+        scale: 1.0,
+    };
+
+    #[test]
+    fn test_color_glyph_fit_shrinks_large_strike() {
+        let fitted = GLYPH_BOX.fit(color_glyph(136, 128));
+        assert_eq!((fitted.width, fitted.height), (20, 19));
+        assert_eq!(fitted.buffer.as_bytes().len(), 20 * 19 * 4);
+        assert_eq!(fitted.bearing_x, 0);
+        assert_eq!(fitted.bearing_y, 16);
+    }
+
+    #[test]
+    fn test_color_glyph_fit_enlarges_and_centers() {
+        let fitted = GLYPH_BOX.fit(color_glyph(8, 8));
+        assert_eq!((fitted.width, fitted.height), (20, 20));
+
+        let glyph_box = ColorGlyphBox {
+            scale: 0.5,
+            ..GLYPH_BOX
+        };
+        let fitted = glyph_box.fit(color_glyph(8, 8));
+        assert_eq!((fitted.width, fitted.height), (10, 10));
+        assert_eq!(fitted.bearing_x, 5);
+        assert_eq!(fitted.bearing_y, 11);
+    }
+
+    #[test]
+    fn test_color_glyph_fit_ignores_gray_glyphs() {
+        let glyph = RasterizedGlyph {
+            buffer: GlyphBuffer::Rgb(vec![255; 4 * 4 * 3]),
+            ..color_glyph(4, 4)
+        };
+        let fitted = GLYPH_BOX.fit(glyph);
+        assert_eq!((fitted.width, fitted.height, fitted.bearing_y), (4, 4, 4));
+    }
+
+    #[test]
+    fn test_resample_rgba_averages_when_shrinking() {
+        let src = [
+            0, 0, 0, 0, 255, 255, 255, 255, //
+            255, 255, 255, 255, 0, 0, 0, 0,
+        ];
+        assert_eq!(resample_rgba(&src, 2, 2, 1, 1), vec![128, 128, 128, 128]);
+        assert_eq!(resample_rgba(&src, 2, 2, 4, 4).len(), 4 * 4 * 4);
+    }
+
     #[test]
     fn test_glyph_cache_key_creation() {
         let key = GlyphCacheKey::new(42, CollectionIndex::primary(Style::Regular));
@@ -453,9 +864,11 @@ mod tests {
             atlas_y: 100,
             width: 10,
             height: 20,
+            page: 0,
             bearing_x: 2,
             bearing_y: 18,
             is_colored: false,
+            luminance: 0.0,
         };
 
         cache.insert(key, Some(glyph));
@@ -501,7 +914,7 @@ mod tests {
         assert_eq!(MAX_ATLAS_SIZE, 8192, "Max atlas size should be 8192");
         assert_eq!(ATLAS_PADDING, 1, "Atlas padding should be 1");
         // Ensure max is a power-of-two multiple of initial
-        assert!(MAX_ATLAS_SIZE >= INITIAL_ATLAS_SIZE);
+        const { assert!(MAX_ATLAS_SIZE >= INITIAL_ATLAS_SIZE) };
         assert_eq!(MAX_ATLAS_SIZE % INITIAL_ATLAS_SIZE, 0);
     }
 
@@ -0,0 +1,317 @@
+//! Image drawn behind the grid (`[window] background_image`).
+//!
+//! The image is composited over the default background color by its own
+//! pipeline, drawn first in the main pass. Cells with the default
+//! background draw nothing, so the image shows through them, while cells
+//! that set a background cover it.
+
+pub(crate) mod png;
+
+use std::path::{Path, PathBuf};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use super::GpuContext;
+use crate::config::{BackgroundFit, WindowSettings};
+
+pub use png::PngError;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct BackgroundUniforms {
+    uv_scale: [f32; 2],
+    uv_offset: [f32; 2],
+    opacity: f32,
+    dim: f32,
+    /// Nonzero to encode the output to sRGB, see `GpuContext::encodes_srgb`.
+    encode_srgb: u32,
+    _padding: f32,
+    color: [f32; 4],
+}
+
+/// Maps window UV to image UV as `(scale, offset)` for an image of
+/// `image` pixels shown in a window of `window` pixels.
+fn fit_uv(fit: BackgroundFit, image: (u32, u32), window: (u32, u32)) -> ([f32; 2], [f32; 2]) {
+    let (iw, ih) = (image.0.max(1) as f32, image.1.max(1) as f32);
+    let (ww, wh) = (window.0.max(1) as f32, window.1.max(1) as f32);
+    match fit {
+        BackgroundFit::Stretch => ([1.0, 1.0], [0.0, 0.0]),
+        BackgroundFit::Tile => ([ww / iw, wh / ih], [0.0, 0.0]),
+        BackgroundFit::Cover => {
+            // Show the centered part of the image with the window's aspect.
+            let (image_aspect, window_aspect) = (iw / ih, ww / wh);
+            let scale = if window_aspect > image_aspect {
+                [1.0, image_aspect / window_aspect]
+            } else {
+                [window_aspect / image_aspect, 1.0]
+            };
+            (scale, [(1.0 - scale[0]) / 2.0, (1.0 - scale[1]) / 2.0])
+        }
+    }
+}
+
+pub struct Background {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+    image_size: (u32, u32),
+    fit: BackgroundFit,
+    opacity: f32,
+    dim: f32,
+    /// Texture plus uniforms, for `:GuiStats`.
+    bytes: u64,
+}
+
+impl Background {
+    /// Loads the configured image, if any.
+    pub fn from_settings(
+        ctx: &GpuContext,
+        settings: &WindowSettings,
+    ) -> Result<Option<Self>, BackgroundError> {
+        let Some(path) = settings.background_image_path() else {
+            return Ok(None);
+        };
+        let image = load_image(&path)?;
+        let max = ctx.device.limits().max_texture_dimension_2d;
+        if image.width > max || image.height > max {
+            return Err(BackgroundError::TooLarge {
+                width: image.width,
+                height: image.height,
+                max,
+            });
+        }
+        Ok(Some(Self::new(ctx, &image, settings)))
+    }
+
+    fn new(ctx: &GpuContext, image: &png::Image, settings: &WindowSettings) -> Self {
+        let texture = ctx.device.create_texture_with_data(
+            &ctx.queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Background Image"),
+                size: wgpu::Extent3d {
+                    width: image.width,
+                    height: image.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &image.rgba,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let address_mode = match settings.background_image_fit {
+            BackgroundFit::Tile => wgpu::AddressMode::Repeat,
+            BackgroundFit::Stretch | BackgroundFit::Cover => wgpu::AddressMode::ClampToEdge,
+        };
+        let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Background Sampler"),
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Background Uniform Buffer"),
+            size: std::mem::size_of::<BackgroundUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = ctx
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Background Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Background Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let pipeline_layout = ctx
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Background Pipeline Layout"),
+                bind_group_layouts: &[&layout],
+                immediate_size: 0,
+            });
+
+        let shader = ctx
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Background Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/background.wgsl").into()),
+            });
+
+        let pipeline = ctx
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Background Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: ctx.format(),
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: None,
+            });
+
+        let bytes = image.rgba.len() as u64 + std::mem::size_of::<BackgroundUniforms>() as u64;
+        Self {
+            pipeline,
+            bind_group,
+            uniform_buffer,
+            image_size: (image.width, image.height),
+            fit: settings.background_image_fit,
+            opacity: settings.background_image_opacity.clamp(0.0, 1.0),
+            dim: settings.background_image_dim.clamp(0.0, 1.0),
+            bytes,
+        }
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Updates the image placement and the color it is composited over.
+    /// Call after resizes and default color changes.
+    pub fn update(&self, ctx: &GpuContext, default_bg: [f32; 4]) {
+        let size = ctx.size();
+        let (uv_scale, uv_offset) = fit_uv(self.fit, self.image_size, (size.width, size.height));
+        let uniforms = BackgroundUniforms {
+            uv_scale,
+            uv_offset,
+            opacity: self.opacity,
+            dim: self.dim,
+            encode_srgb: ctx.encodes_srgb().into(),
+            _padding: 0.0,
+            color: default_bg,
+        };
+        ctx.queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    /// Draws the background over the whole render target (or scissor).
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn load_image(path: &Path) -> Result<png::Image, BackgroundError> {
+    let data = std::fs::read(path).map_err(|source| BackgroundError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(png::decode(&data)?)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackgroundError {
+    #[error("Failed to read {path:?}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to decode image: {0}")]
+    Decode(#[from] PngError),
+
+    #[error("Image is {width}x{height}, larger than the GPU allows ({max})")]
+    TooLarge { width: u32, height: u32, max: u32 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_uv() {
+        assert_eq!(
+            fit_uv(BackgroundFit::Stretch, (100, 50), (800, 600)),
+            ([1.0, 1.0], [0.0, 0.0])
+        );
+        assert_eq!(
+            fit_uv(BackgroundFit::Tile, (100, 50), (800, 600)),
+            ([8.0, 12.0], [0.0, 0.0])
+        );
+        // A 2:1 image in a 1:1 window shows its middle half horizontally.
+        assert_eq!(
+            fit_uv(BackgroundFit::Cover, (200, 100), (500, 500)),
+            ([0.5, 1.0], [0.25, 0.0])
+        );
+        // And a 1:2 image its middle half vertically.
+        assert_eq!(
+            fit_uv(BackgroundFit::Cover, (100, 200), (500, 500)),
+            ([1.0, 0.5], [0.0, 0.25])
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! PNG decoding for background images and icons, and encoding for frame
+//! captures, on top of the `png` crate.
+//!
+//! Every image is converted to straight-alpha RGBA8, so 16-bit samples
+//! are cut to 8 bits and gamma and color profiles are ignored.
+
+use std::io::Cursor;
+
+use ::png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+
+/// The most decoded RGBA bytes an image may take: a 8192x8192 image, the
+/// largest texture most GPUs allow.
+const MAX_IMAGE_BYTES: usize = 8192 * 8192 * 4;
+
+/// An image as straight-alpha RGBA8 rows, top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PngError {
+    #[error(transparent)]
+    Decode(#[from] ::png::DecodingError),
+
+    #[error(transparent)]
+    Encode(#[from] ::png::EncodingError),
+
+    #[error("Image is {width}x{height}, too large to decode")]
+    TooLarge { width: u32, height: u32 },
+}
+
+pub fn decode(data: &[u8]) -> Result<Image, PngError> {
+    decode_with_limit(data, MAX_IMAGE_BYTES)
+}
+
+fn decode_with_limit(data: &[u8], max_bytes: usize) -> Result<Image, PngError> {
+    let mut decoder = Decoder::new(Cursor::new(data));
+    // ALPHA gives every color type an alpha channel, so the output is
+    // either gray and alpha or RGBA.
+    decoder.set_transformations(Transformations::ALPHA | Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+
+    let (width, height) = reader.info().size();
+    let too_large = || PngError::TooLarge { width, height };
+    // Checked before anything is allocated, so a header claiming a huge
+    // image fails here instead of running out of memory.
+    let rgba_len = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|&len| len <= max_bytes)
+        .ok_or_else(too_large)?;
+    let mut buf = vec![0; reader.output_buffer_size().ok_or_else(too_large)?];
+    let info = reader.next_frame(&mut buf)?;
+    buf.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        ColorType::GrayscaleAlpha => {
+            let mut rgba = Vec::with_capacity(rgba_len);
+            for pixel in buf.chunks_exact(2) {
+                rgba.extend_from_slice(&[pixel[0], pixel[0], pixel[0], pixel[1]]);
+            }
+            rgba
+        }
+        _ => buf,
+    };
+    Ok(Image {
+        width,
+        height,
+        rgba,
+    })
+}
+
+/// Encodes `image` as an RGBA8 PNG.
+pub fn encode(image: &Image) -> Result<Vec<u8>, PngError> {
+    let mut out = Vec::new();
+    let mut encoder = Encoder::new(&mut out, image.width, image.height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.rgba)?;
+    writer.finish()?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 3x5 RGB, each row using the next filter type (none, sub, up,
+    /// average, Paeth), compressed with dynamic Huffman codes.
+    const RGB_FILTERS: &[u8] = &[
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 3, 0, 0, 0, 5, 8, 2,
+        0, 0, 0, 15, 19, 193, 245, 0, 0, 0, 43, 73, 68, 65, 84, 120, 218, 99, 96, 96, 96, 208, 96,
+        143, 10, 224, 219, 194, 200, 107, 196, 12, 100, 1, 17, 19, 144, 5, 65, 204, 82, 41, 108,
+        210, 178, 250, 64, 196, 2, 18, 96, 7, 33, 0, 157, 14, 5, 130, 121, 154, 142, 84, 0, 0, 0,
+        0, 73, 69, 78, 68, 174, 66, 96, 130,
+    ];
+
+    /// 5x1 two-bit palette image with indices 0, 1, 2, 3, 1 and tRNS
+    /// alphas for the first two entries.
+    const PALETTE_TRNS: &[u8] = &[
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 5, 0, 0, 0, 1, 2, 3,
+        0, 0, 0, 107, 144, 140, 96, 0, 0, 0, 12, 80, 76, 84, 69, 255, 0, 0, 0, 255, 0, 0, 0, 255,
+        9, 9, 9, 92, 113, 126, 134, 0, 0, 0, 2, 116, 82, 78, 83, 0, 128, 155, 43, 78, 24, 0, 0, 0,
+        11, 73, 68, 65, 84, 120, 218, 99, 144, 118, 0, 0, 0, 121, 0, 92, 156, 208, 48, 245, 0, 0,
+        0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+    ];
+
+    #[test]
+    fn test_encode_round_trip() {
+        let image = Image {
+            width: 3,
+            height: 2,
+            rgba: (0..24).map(|i| i * 10).collect(),
+        };
+        assert_eq!(decode(&encode(&image).unwrap()).unwrap(), image);
+    }
+
+    #[test]
+    fn test_decode_rgb_with_every_filter() {
+        let image = decode(RGB_FILTERS).unwrap();
+        assert_eq!((image.width, image.height), (3, 5));
+        let expected: Vec<u8> = (0..5u32)
+            .flat_map(|y| (0..3u32).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                [
+                    ((x * 40 + y * 13) % 256) as u8,
+                    ((x * 7 + y * 50) % 256) as u8,
+                    ((x * 90 + y * 3) % 256) as u8,
+                    255,
+                ]
+            })
+            .collect();
+        assert_eq!(image.rgba, expected);
+    }
+
+    #[test]
+    fn test_decode_palette_with_transparency() {
+        let image = decode(PALETTE_TRNS).unwrap();
+        assert_eq!(
+            image.rgba,
+            [
+                255, 0, 0, 0, //
+                0, 255, 0, 128, //
+                0, 0, 255, 255, //
+                9, 9, 9, 255, //
+                0, 255, 0, 128,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_input() {
+        assert!(matches!(decode(b"GIF89a"), Err(PngError::Decode(_))));
+        assert!(matches!(
+            decode(&RGB_FILTERS[..40]),
+            Err(PngError::Decode(_))
+        ));
+        // 3x5 RGBA is 60 bytes.
+        assert!(matches!(
+            decode_with_limit(RGB_FILTERS, 59),
+            Err(PngError::TooLarge {
+                width: 3,
+                height: 5
+            })
+        ));
+        assert!(decode_with_limit(RGB_FILTERS, 60).is_ok());
+    }
+}
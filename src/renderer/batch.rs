@@ -1,18 +1,56 @@
 #![allow(clippy::too_many_arguments)]
 
+use std::hash::Hasher;
+use std::ops::Range;
+
 use super::pipeline::QuadInstance;
 use super::GpuContext;
 
 const INITIAL_BATCH_CAPACITY: usize = 65536;
 
+/// The most instances a batch buffer can hold on a device with `limits`.
+pub(super) fn max_batch_instances(limits: &wgpu::Limits) -> usize {
+    let bytes = limits.max_buffer_size.min(usize::MAX as u64) as usize;
+    bytes / std::mem::size_of::<QuadInstance>()
+}
+
+/// The ranges of the first `len` instances outside `unchanged`, which
+/// is sorted and doesn't overlap.
+fn changed_ranges(len: usize, unchanged: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for range in unchanged {
+        if range.start > start {
+            ranges.push(start..range.start.min(len));
+        }
+        start = start.max(range.end);
+        if start >= len {
+            break;
+        }
+    }
+    if start < len {
+        ranges.push(start..len);
+    }
+    ranges
+}
+
 /// Batch of quads for efficient GPU submission.
 ///
-/// Grows the GPU buffer dynamically during `upload()` if more instances
-/// were pushed than the current capacity allows.
+/// The GPU buffer persists across frames. `upload()` skips the instances
+/// added with `extend_unchanged`, which the buffer holds already, so a
+/// frame where only the cursor line changed sends little more than that
+/// line. It grows the buffer if more instances were pushed than it holds.
 pub struct QuadBatch {
     instances: Vec<QuadInstance>,
+    /// Ranges of `instances` the buffer holds from the last `upload()`.
+    unchanged: Vec<Range<usize>>,
+    /// Bytes the last `upload()` wrote.
+    written: u64,
     buffer: wgpu::Buffer,
     capacity: usize,
+    /// The largest buffer the device allows, in instances. Quads past it
+    /// are dropped.
+    max_capacity: usize,
 }
 
 impl QuadBatch {
@@ -21,12 +59,17 @@ impl QuadBatch {
     }
 
     pub fn with_capacity(ctx: &GpuContext, capacity: usize) -> Self {
+        let max_capacity = max_batch_instances(&ctx.device.limits());
+        let capacity = capacity.min(max_capacity);
         let buffer = Self::create_buffer(ctx, capacity);
 
         Self {
             instances: Vec::with_capacity(capacity),
+            unchanged: Vec::new(),
+            written: 0,
             buffer,
             capacity,
+            max_capacity,
         }
     }
 
@@ -42,13 +85,13 @@ impl QuadBatch {
 
     pub fn clear(&mut self) {
         self.instances.clear();
+        self.unchanged.clear();
     }
 
     pub fn is_empty(&self) -> bool {
         self.instances.is_empty()
     }
 
-    #[allow(dead_code)]
     pub fn len(&self) -> usize {
         self.instances.len()
     }
@@ -59,6 +102,12 @@ impl QuadBatch {
             .push(QuadInstance::background(x, y, width, height, color));
     }
 
+    /// Add a pill-shaped quad.
+    pub fn push_pill(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) {
+        self.instances
+            .push(QuadInstance::pill(x, y, width, height, color));
+    }
+
     /// Add a glyph quad with atlas UV coordinates.
     pub fn push_glyph(
         &mut self,
@@ -72,20 +121,97 @@ impl QuadBatch {
         uv_h: f32,
         color: [f32; 4],
         is_colored: bool,
+        page: u32,
     ) {
         self.instances.push(QuadInstance::glyph(
-            x, y, width, height, uv_x, uv_y, uv_w, uv_h, color, is_colored,
+            x, y, width, height, uv_x, uv_y, uv_w, uv_h, color, is_colored, page,
         ));
     }
 
+    pub fn push(&mut self, instance: QuadInstance) {
+        self.instances.push(instance);
+    }
+
+    /// The instances pushed since the batch held `start` of them.
+    pub fn since(&self, start: usize) -> &[QuadInstance] {
+        &self.instances[start.min(self.instances.len())..]
+    }
+
+    /// Adds `instances`, moved down by `dy`.
+    pub fn extend_shifted(&mut self, instances: &[QuadInstance], dy: f32) {
+        self.instances.extend(instances.iter().map(|instance| {
+            let mut instance = *instance;
+            instance.position[1] += dy;
+            instance
+        }));
+    }
+
+    /// Like `extend_shifted`, keeping the tops on whole pixels.
+    pub fn extend_shifted_snapped(&mut self, instances: &[QuadInstance], dy: f32) {
+        self.instances.extend(instances.iter().map(|instance| {
+            let mut instance = *instance;
+            instance.position[1] = (instance.position[1] + dy).round();
+            instance
+        }));
+    }
+
+    /// Scales the instances pushed since the batch held `start` of them
+    /// `factor` times about `origin`, cut to the `width` by `height`
+    /// rectangle there. Those outside it are left empty rather than
+    /// removed, so ranges taken of the batch still hold.
+    pub fn scale_since(
+        &mut self,
+        start: usize,
+        origin: [f32; 2],
+        factor: f32,
+        width: f32,
+        height: f32,
+    ) {
+        let start = start.min(self.instances.len());
+        for instance in &mut self.instances[start..] {
+            let mut scaled = instance.scaled(origin, factor);
+            *instance = match scaled.clipped(origin[0], origin[1], width, height) {
+                Some(clipped) => clipped,
+                None => {
+                    scaled.size = [0.0, 0.0];
+                    scaled
+                }
+            };
+        }
+    }
+
+    /// Adds `instances`, which the last `upload()` wrote where they go now.
+    pub fn extend_unchanged(&mut self, instances: &[QuadInstance]) {
+        let range = self.instances.len()..self.instances.len() + instances.len();
+        self.instances.extend_from_slice(instances);
+        match self.unchanged.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => self.unchanged.push(range),
+        }
+    }
+
     /// Upload batch data to the GPU buffer, growing the buffer if needed.
     pub fn upload(&mut self, ctx: &GpuContext) {
+        self.written = 0;
         if self.instances.is_empty() {
             return;
         }
 
+        if self.instances.len() > self.max_capacity {
+            log::warn!(
+                "Dropping {} quads past the device's buffer limit of {}",
+                self.instances.len() - self.max_capacity,
+                self.max_capacity
+            );
+            self.instances.truncate(self.max_capacity);
+        }
+
         if self.instances.len() > self.capacity {
-            let new_capacity = self.instances.len().next_power_of_two();
+            let new_capacity = self
+                .instances
+                .len()
+                .next_power_of_two()
+                .min(self.max_capacity);
             log::info!(
                 "Batch buffer growing: {} -> {} instances",
                 self.capacity,
@@ -93,16 +219,32 @@ impl QuadBatch {
             );
             self.buffer = Self::create_buffer(ctx, new_capacity);
             self.capacity = new_capacity;
+            self.unchanged.clear();
+        }
+
+        let size = std::mem::size_of::<QuadInstance>();
+        for range in changed_ranges(self.instances.len(), &self.unchanged) {
+            let bytes = bytemuck::cast_slice(&self.instances[range.clone()]);
+            ctx.queue
+                .write_buffer(&self.buffer, (range.start * size) as u64, bytes);
+            self.written += bytes.len() as u64;
         }
+    }
 
-        ctx.queue
-            .write_buffer(&self.buffer, 0, bytemuck::cast_slice(&self.instances));
+    /// Bytes the last `upload()` sent to the GPU.
+    pub fn written_bytes(&self) -> u64 {
+        self.written
     }
 
     pub fn buffer(&self) -> &wgpu::Buffer {
         &self.buffer
     }
 
+    /// Size of the GPU instance buffer in bytes.
+    pub fn buffer_bytes(&self) -> u64 {
+        (self.capacity * std::mem::size_of::<QuadInstance>()) as u64
+    }
+
     #[allow(dead_code)]
     pub fn vertex_count(&self) -> u32 {
         (self.instances.len() * 6) as u32
@@ -111,13 +253,68 @@ impl QuadBatch {
     pub fn instance_count(&self) -> u32 {
         self.instances.len() as u32
     }
+
+    fn hash_instances(&self, hasher: &mut impl Hasher) {
+        hasher.write_usize(self.instances.len());
+        hasher.write(bytemuck::cast_slice(&self.instances));
+    }
+}
+
+/// Capacity for overlay batches, which only hold a handful of quads
+/// (e.g. the IME preedit string).
+const OVERLAY_BATCH_CAPACITY: usize = 256;
+
+/// Capacity for the float batch. Floats are small, but a large hover can
+/// hold a few thousand cells; the batch grows past this when needed.
+const FLOAT_BATCH_CAPACITY: usize = 4096;
+
+/// How far the grid batches had been filled, to take what was pushed
+/// after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridMark {
+    backgrounds: usize,
+    glyphs: usize,
+    decorations: usize,
+}
+
+/// Where in the grid batches quads were pushed, and which `upload()` sent
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridPlacement {
+    mark: GridMark,
+    upload: u64,
+}
+
+impl GridPlacement {
+    pub fn mark(&self) -> GridMark {
+        self.mark
+    }
 }
 
-/// Batcher that manages separate batches for backgrounds, glyphs, and decorations.
+/// Which set of batches a quad is drawn in.
+///
+/// Float and overlay quads are drawn after every grid batch, so they cover
+/// grid glyphs and decorations instead of sitting underneath them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Grid,
+    Float,
+    Overlay,
+}
+
+/// Batcher that manages separate batches for backgrounds, glyphs, and decorations,
+/// a batch for floating windows, and an overlay pair drawn on top of it all.
 pub struct RenderBatcher {
     backgrounds: QuadBatch,
     glyphs: QuadBatch,
     decorations: QuadBatch,
+    /// Every quad of the floating windows, drawn in the order pushed so
+    /// that each float covers those below it, glyphs included.
+    floats: QuadBatch,
+    overlay_backgrounds: QuadBatch,
+    overlay_glyphs: QuadBatch,
+    /// `upload()`s so far.
+    uploads: u64,
 }
 
 impl RenderBatcher {
@@ -126,6 +323,10 @@ impl RenderBatcher {
             backgrounds: QuadBatch::new(ctx),
             glyphs: QuadBatch::new(ctx),
             decorations: QuadBatch::new(ctx),
+            floats: QuadBatch::with_capacity(ctx, FLOAT_BATCH_CAPACITY),
+            overlay_backgrounds: QuadBatch::with_capacity(ctx, OVERLAY_BATCH_CAPACITY),
+            overlay_glyphs: QuadBatch::with_capacity(ctx, OVERLAY_BATCH_CAPACITY),
+            uploads: 0,
         }
     }
 
@@ -133,6 +334,9 @@ impl RenderBatcher {
         self.backgrounds.clear();
         self.glyphs.clear();
         self.decorations.clear();
+        self.floats.clear();
+        self.overlay_backgrounds.clear();
+        self.overlay_glyphs.clear();
     }
 
     pub fn push_background(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) {
@@ -141,6 +345,7 @@ impl RenderBatcher {
 
     pub fn push_glyph(
         &mut self,
+        layer: Layer,
         x: f32,
         y: f32,
         width: f32,
@@ -151,20 +356,190 @@ impl RenderBatcher {
         uv_h: f32,
         color: [f32; 4],
         is_colored: bool,
+        page: u32,
     ) {
-        self.glyphs.push_glyph(
-            x, y, width, height, uv_x, uv_y, uv_w, uv_h, color, is_colored,
+        let batch = match layer {
+            Layer::Grid => &mut self.glyphs,
+            Layer::Float => &mut self.floats,
+            Layer::Overlay => &mut self.overlay_glyphs,
+        };
+        batch.push_glyph(
+            x, y, width, height, uv_x, uv_y, uv_w, uv_h, color, is_colored, page,
         );
     }
 
+    /// Add a glyph quad built elsewhere, such as one cut from another.
+    pub fn push_glyph_instance(&mut self, layer: Layer, instance: QuadInstance) {
+        match layer {
+            Layer::Grid => self.glyphs.push(instance),
+            Layer::Float => self.floats.push(instance),
+            Layer::Overlay => self.overlay_glyphs.push(instance),
+        }
+    }
+
+    /// Add a pill behind the glyphs of `layer`.
+    pub fn push_pill(
+        &mut self,
+        layer: Layer,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: [f32; 4],
+    ) {
+        let batch = match layer {
+            Layer::Grid => &mut self.backgrounds,
+            Layer::Float => &mut self.floats,
+            Layer::Overlay => &mut self.overlay_backgrounds,
+        };
+        batch.push_pill(x, y, width, height, color);
+    }
+
+    pub fn push_overlay_background(
+        &mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: [f32; 4],
+    ) {
+        self.overlay_backgrounds
+            .push_background(x, y, width, height, color);
+    }
+
     pub fn push_decoration(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) {
         self.decorations.push_background(x, y, width, height, color);
     }
 
+    /// Add a solid quad to the float batch: a background, border line or
+    /// decoration of a floating window.
+    pub fn push_float_quad(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) {
+        self.floats.push_background(x, y, width, height, color);
+    }
+
+    /// Scales the float layer's quads pushed since it held `start` of
+    /// them, for a zoomed window; see `QuadBatch::scale_since`.
+    pub fn scale_floats_since(
+        &mut self,
+        start: usize,
+        origin: [f32; 2],
+        factor: f32,
+        width: f32,
+        height: f32,
+    ) {
+        self.floats
+            .scale_since(start, origin, factor, width, height);
+    }
+
+    pub fn grid_mark(&self) -> GridMark {
+        GridMark {
+            backgrounds: self.backgrounds.len(),
+            glyphs: self.glyphs.len(),
+            decorations: self.decorations.len(),
+        }
+    }
+
+    /// The backgrounds, glyphs and decorations pushed since `mark`.
+    pub fn grid_since(&self, mark: GridMark) -> [&[QuadInstance]; 3] {
+        [
+            self.backgrounds.since(mark.backgrounds),
+            self.glyphs.since(mark.glyphs),
+            self.decorations.since(mark.decorations),
+        ]
+    }
+
+    /// Where quads pushed now go, for the next `upload()`.
+    pub fn grid_placement(&self) -> GridPlacement {
+        GridPlacement {
+            mark: self.grid_mark(),
+            upload: self.uploads + 1,
+        }
+    }
+
+    /// Adds quads taken with `grid_since`, moved down by `dy`.
+    pub fn extend_grid(
+        &mut self,
+        backgrounds: &[QuadInstance],
+        glyphs: &[QuadInstance],
+        decorations: &[QuadInstance],
+        dy: f32,
+    ) {
+        self.backgrounds.extend_shifted(backgrounds, dy);
+        self.glyphs.extend_shifted(glyphs, dy);
+        // Lines are snapped to the screen's pixels, see
+        // `compute_decoration_geometry`.
+        self.decorations.extend_shifted_snapped(decorations, dy);
+    }
+
+    /// Like `extend_grid`, for quads the last `upload()` sent at `placed`:
+    /// if they go to the same place again, they aren't sent again. False
+    /// if they don't, and nothing was added.
+    pub fn extend_grid_unchanged(
+        &mut self,
+        backgrounds: &[QuadInstance],
+        glyphs: &[QuadInstance],
+        decorations: &[QuadInstance],
+        placed: GridPlacement,
+    ) -> bool {
+        if placed.upload != self.uploads || placed.mark != self.grid_mark() {
+            return false;
+        }
+        self.backgrounds.extend_unchanged(backgrounds);
+        self.glyphs.extend_unchanged(glyphs);
+        self.decorations.extend_unchanged(decorations);
+        true
+    }
+
     pub fn upload(&mut self, ctx: &GpuContext) {
+        self.uploads += 1;
         self.backgrounds.upload(ctx);
         self.glyphs.upload(ctx);
         self.decorations.upload(ctx);
+        self.floats.upload(ctx);
+        self.overlay_backgrounds.upload(ctx);
+        self.overlay_glyphs.upload(ctx);
+    }
+
+    /// Bytes the last `upload()` sent to the GPU, over every batch.
+    pub fn written_bytes(&self) -> u64 {
+        [
+            &self.backgrounds,
+            &self.glyphs,
+            &self.decorations,
+            &self.floats,
+            &self.overlay_backgrounds,
+            &self.overlay_glyphs,
+        ]
+        .iter()
+        .map(|batch| batch.written_bytes())
+        .sum()
+    }
+
+    /// A hash of every batched instance, which tells a frame that would
+    /// draw exactly what the previous one did.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ahash::AHasher::default();
+        for batch in [
+            &self.backgrounds,
+            &self.glyphs,
+            &self.decorations,
+            &self.floats,
+            &self.overlay_backgrounds,
+            &self.overlay_glyphs,
+        ] {
+            batch.hash_instances(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Total size of all GPU instance buffers in bytes.
+    pub fn buffer_bytes(&self) -> u64 {
+        self.backgrounds.buffer_bytes()
+            + self.glyphs.buffer_bytes()
+            + self.decorations.buffer_bytes()
+            + self.floats.buffer_bytes()
+            + self.overlay_backgrounds.buffer_bytes()
+            + self.overlay_glyphs.buffer_bytes()
     }
 
     pub fn backgrounds(&self) -> &QuadBatch {
@@ -178,6 +553,18 @@ impl RenderBatcher {
     pub fn decorations(&self) -> &QuadBatch {
         &self.decorations
     }
+
+    pub fn floats(&self) -> &QuadBatch {
+        &self.floats
+    }
+
+    pub fn overlay_backgrounds(&self) -> &QuadBatch {
+        &self.overlay_backgrounds
+    }
+
+    pub fn overlay_glyphs(&self) -> &QuadBatch {
+        &self.overlay_glyphs
+    }
 }
 
 #[cfg(test)]
@@ -187,7 +574,20 @@ mod tests {
     #[test]
     fn test_initial_batch_capacity() {
         // Should handle large grids: 200x100 = 20k cells, with glyphs + backgrounds + decorations
-        assert!(INITIAL_BATCH_CAPACITY >= 65536);
+        const { assert!(INITIAL_BATCH_CAPACITY >= 65536) };
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_only_changed_ranges_are_uploaded() {
+        assert!(changed_ranges(0, &[]).is_empty());
+        assert_eq!(changed_ranges(10, &[]), [0..10]);
+        assert!(changed_ranges(10, &[0..10]).is_empty());
+        assert_eq!(changed_ranges(10, &[2..4, 6..8]), [0..2, 4..6, 8..10]);
+        assert_eq!(changed_ranges(10, &[0..3, 3..5]), [5..10]);
+        // Ranges past what's left after dropping quads over the limit.
+        assert_eq!(changed_ranges(5, &[2..8]), [0..2]);
+        assert_eq!(changed_ranges(5, &[1..2, 7..9]), [0..1, 2..5]);
     }
 
     #[test]
@@ -0,0 +1,95 @@
+//! Reading rendered frames back from the GPU.
+
+use std::sync::mpsc;
+
+use super::background::png::{Image, PngError};
+use super::GpuContext;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CaptureError {
+    #[error("Can't read back {0:?} textures")]
+    UnsupportedFormat(wgpu::TextureFormat),
+
+    #[error("Failed to wait for the GPU: {0}")]
+    Poll(#[from] wgpu::PollError),
+
+    #[error("Failed to map the readback buffer: {0}")]
+    Map(#[from] wgpu::BufferAsyncError),
+
+    #[error("Failed to encode the frame: {0}")]
+    Encode(#[from] PngError),
+}
+
+/// Copies `texture` to the CPU as straight RGBA8, blocking until the GPU
+/// is done with it. Alpha is dropped: captures show the frame as if it
+/// were opaque.
+pub(super) fn read_texture(
+    ctx: &GpuContext,
+    texture: &wgpu::Texture,
+) -> Result<Image, CaptureError> {
+    let bgra = match texture.format() {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        format => return Err(CaptureError::UnsupportedFormat(format)),
+    };
+
+    let size = texture.size();
+    let row_bytes = size.width * 4;
+    let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Capture Readback Buffer"),
+        size: padded_row_bytes as u64 * size.height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = ctx
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Capture Encoder"),
+        });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: None,
+            },
+        },
+        size,
+    );
+    ctx.queue.submit(std::iter::once(encoder.finish()));
+
+    let (sender, receiver) = mpsc::channel();
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    ctx.device.poll(wgpu::PollType::wait_indefinitely())?;
+    // The callback has run once the poll returns.
+    receiver.recv().unwrap_or(Ok(()))?;
+
+    let mut rgba = Vec::with_capacity((row_bytes * size.height) as usize);
+    {
+        let mapped = slice.get_mapped_range();
+        for row in mapped.chunks_exact(padded_row_bytes as usize) {
+            for pixel in row[..row_bytes as usize].chunks_exact(4) {
+                let (r, b) = if bgra {
+                    (pixel[2], pixel[0])
+                } else {
+                    (pixel[0], pixel[2])
+                };
+                rgba.extend_from_slice(&[r, pixel[1], b, 255]);
+            }
+        }
+    }
+    buffer.unmap();
+
+    Ok(Image {
+        width: size.width,
+        height: size.height,
+        rgba,
+    })
+}
@@ -21,6 +21,17 @@ pub fn srgb_to_linear(c: f32) -> f32 {
     }
 }
 
+/// Convert a linear color component back to sRGB, for surfaces that
+/// don't encode it themselves.
+#[inline]
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
 /// Convert a packed RGB u32 color (0xRRGGBB) to linear RGBA.
 #[inline]
 pub fn u32_to_linear_rgba(color: u32) -> [f32; 4] {
@@ -30,10 +41,131 @@ pub fn u32_to_linear_rgba(color: u32) -> [f32; 4] {
     [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), 1.0]
 }
 
+/// Premultiply a linear RGBA color by `alpha`, matching the premultiplied
+/// blending used by the pipeline.
+#[inline]
+pub fn premultiply(color: [f32; 4], alpha: f32) -> [f32; 4] {
+    color.map(|c| c * alpha)
+}
+
+/// Relative luminance of a linear color, from 0.0 for black to 1.0 for
+/// white.
+pub fn relative_luminance(color: [f32; 4]) -> f32 {
+    0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]
+}
+
+/// WCAG contrast ratio between two relative luminances: 1.0 for the same
+/// luminance, up to 21.0 for black on white.
+pub fn contrast_ratio(a: f32, b: f32) -> f32 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Below this contrast with its background, a color glyph gets a backing
+/// (`[font] emoji_backing`).
+const MIN_COLOR_GLYPH_CONTRAST: f32 = 1.5;
+
+/// Opacity of a color glyph's backing: enough to set it apart, not so much
+/// that it reads as a highlight.
+const COLOR_GLYPH_BACKING_ALPHA: f32 = 0.3;
+
+/// The backing to draw behind a color glyph of `glyph_luminance` on `bg`,
+/// if the two are too alike: black over light backgrounds, white over
+/// dark ones, premultiplied.
+pub fn color_glyph_backing(glyph_luminance: f32, bg: [f32; 4]) -> Option<[f32; 4]> {
+    let bg_luminance = relative_luminance(bg);
+    if contrast_ratio(glyph_luminance, bg_luminance) >= MIN_COLOR_GLYPH_CONTRAST {
+        return None;
+    }
+    // Middle gray, as far from black as from white in contrast
+    let backing = if bg_luminance > 0.18 {
+        [0.0, 0.0, 0.0, 1.0]
+    } else {
+        [1.0, 1.0, 1.0, 1.0]
+    };
+    Some(premultiply(backing, COLOR_GLYPH_BACKING_ALPHA))
+}
+
+/// `color` if it keeps `min_contrast` with `against`, else the first of
+/// `fallback`, black and white that does, else whichever of black and
+/// white stands out more.
+pub fn ensure_contrast(
+    color: [f32; 4],
+    against: [f32; 4],
+    fallback: [f32; 4],
+    min_contrast: f32,
+) -> [f32; 4] {
+    let against = relative_luminance(against);
+    let contrast = |color: [f32; 4]| contrast_ratio(relative_luminance(color), against);
+    let black = [0.0, 0.0, 0.0, 1.0];
+    let white = [1.0, 1.0, 1.0, 1.0];
+    [color, fallback, black, white]
+        .into_iter()
+        .find(|&c| contrast(c) >= min_contrast)
+        .unwrap_or(if contrast(black) > contrast(white) {
+            black
+        } else {
+            white
+        })
+}
+
+/// Opacity for a Neovim blend percentage, where 100 is fully see-through.
+pub fn blend_alpha(blend: u8) -> f32 {
+    1.0 - f32::from(blend.min(100)) / 100.0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_blend_alpha() {
+        assert_eq!(blend_alpha(0), 1.0);
+        assert_eq!(blend_alpha(30), 0.7);
+        assert_eq!(blend_alpha(200), 0.0);
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        let white = relative_luminance([1.0, 1.0, 1.0, 1.0]);
+        let black = relative_luminance([0.0, 0.0, 0.0, 1.0]);
+        assert!((contrast_ratio(white, black) - 21.0).abs() < 0.01);
+        assert_eq!(contrast_ratio(black, white), contrast_ratio(white, black));
+        assert_eq!(contrast_ratio(0.5, 0.5), 1.0);
+    }
+
+    #[test]
+    fn test_color_glyph_backing() {
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let black = [0.0, 0.0, 0.0, 1.0];
+        // A bright emoji on a light background gets a dark backing
+        assert_eq!(color_glyph_backing(0.9, white), Some([0.0, 0.0, 0.0, 0.3]));
+        assert_eq!(color_glyph_backing(0.01, black), Some([0.3, 0.3, 0.3, 0.3]));
+        assert_eq!(color_glyph_backing(0.9, black), None);
+        assert_eq!(color_glyph_backing(0.01, white), None);
+    }
+
+    #[test]
+    fn test_ensure_contrast() {
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let black = [0.0, 0.0, 0.0, 1.0];
+        let gray = [0.2, 0.2, 0.2, 1.0];
+        assert_eq!(ensure_contrast(white, black, gray, 1.5), white);
+        // Too alike: the fallback, then black or white
+        assert_eq!(ensure_contrast(gray, gray, white, 1.5), white);
+        assert_eq!(ensure_contrast(gray, gray, gray, 1.5), black);
+        assert_eq!(ensure_contrast(white, white, white, 1.5), black);
+        // Nothing reaches it: the one that stands out more
+        assert_eq!(ensure_contrast(gray, gray, gray, 30.0), black);
+        assert_eq!(ensure_contrast(white, white, white, 30.0), black);
+        assert_eq!(ensure_contrast(gray, gray, gray, 1.0), gray);
+    }
+
+    #[test]
+    fn test_premultiply() {
+        let color = premultiply([1.0, 0.5, 0.0, 1.0], 0.5);
+        assert_eq!(color, [0.5, 0.25, 0.0, 0.5]);
+    }
+
     #[test]
     fn test_srgb_to_linear_black() {
         assert!((srgb_to_linear(0.0) - 0.0).abs() < 0.0001);
@@ -51,6 +183,13 @@ mod tests {
         assert!((linear - 0.214).abs() < 0.01);
     }
 
+    #[test]
+    fn test_linear_to_srgb_round_trip() {
+        for c in [0.0, 0.002, 0.214, 0.5, 1.0] {
+            assert!((linear_to_srgb(srgb_to_linear(c)) - c).abs() < 0.0001);
+        }
+    }
+
     #[test]
     fn test_u32_to_linear_rgba_white() {
         let rgba = u32_to_linear_rgba(0xFFFFFF);
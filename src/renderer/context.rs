@@ -2,17 +2,31 @@ use std::sync::Arc;
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
-use crate::config::VsyncMode;
+use super::support::GpuSupport;
+use crate::config::{TextBlend, VsyncMode};
 
 pub struct GpuContext {
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
-    pub surface: wgpu::Surface<'static>,
+    /// `None` for headless contexts, which only render offscreen.
+    pub surface: Option<wgpu::Surface<'static>>,
     surface_config: wgpu::SurfaceConfiguration,
+    /// Present modes the surface supports, to pick from again when vsync
+    /// changes.
+    present_modes: Vec<wgpu::PresentMode>,
+    adapter_info: wgpu::AdapterInfo,
 }
 
 impl GpuContext {
-    pub async fn new(window: Arc<Window>, vsync: VsyncMode) -> Result<Self, GpuContextError> {
+    /// `transparent` asks for a surface the compositor blends with whatever
+    /// is behind the window. `blend` picks an sRGB surface, which blends in
+    /// linear light, or one that blends the values as they are stored.
+    pub async fn new(
+        window: Arc<Window>,
+        vsync: VsyncMode,
+        transparent: bool,
+        blend: TextBlend,
+    ) -> Result<Self, GpuContextError> {
         let size = window.inner_size();
         if size.width == 0 || size.height == 0 {
             return Err(GpuContextError::InvalidSize);
@@ -33,11 +47,11 @@ impl GpuContext {
             })
             .await?;
 
-        log::info!("Using GPU adapter: {:?}", adapter.get_info().name);
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default())
-            .await?;
+        let adapter_info = adapter.get_info();
+        log::info!("Using GPU adapter: {:?}", adapter_info.name);
+        let device_descriptor = Self::device_descriptor(&adapter)?;
+        let (device, queue) = adapter.request_device(&device_descriptor).await?;
+        let max_size = device.limits().max_texture_dimension_2d;
 
         let surface_caps = surface.get_capabilities(&adapter);
 
@@ -45,17 +59,12 @@ impl GpuContext {
             .formats
             .iter()
             .copied()
-            .find(|f| f.is_srgb())
+            .find(|f| f.is_srgb() == (blend == TextBlend::Linear))
             .unwrap_or(surface_caps.formats[0]);
+        log::info!("Surface format: {:?}", surface_format);
 
-        let present_mode = Self::select_present_mode(&surface_caps, vsync);
-
-        // With VSync, use 1 frame in flight for lower latency (vs 2 which adds ~16-33ms)
-        let uses_vsync = matches!(
-            present_mode,
-            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::Fifo | wgpu::PresentMode::Mailbox
-        );
-        let desired_maximum_frame_latency = if uses_vsync { 1 } else { 2 };
+        let present_mode = Self::select_present_mode(&surface_caps.present_modes, vsync);
+        let desired_maximum_frame_latency = Self::frame_latency(present_mode);
 
         log::info!(
             "Present mode: {:?}, frame latency: {}",
@@ -63,13 +72,18 @@ impl GpuContext {
             desired_maximum_frame_latency
         );
 
+        // Copying into the surface lets the renderer present a persistent
+        // canvas it only partially redraws (see `Renderer::canvas`).
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (surface_caps.usages & wgpu::TextureUsages::COPY_DST);
+
         let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
-            width: size.width,
-            height: size.height,
+            width: size.width.min(max_size),
+            height: size.height.min(max_size),
             present_mode,
-            alpha_mode: surface_caps.alpha_modes[0],
+            alpha_mode: Self::select_alpha_mode(&surface_caps, transparent),
             view_formats: vec![],
             desired_maximum_frame_latency,
         };
@@ -78,19 +92,125 @@ impl GpuContext {
         Ok(Self {
             device,
             queue,
-            surface,
+            surface: Some(surface),
             surface_config,
+            present_modes: surface_caps.present_modes,
+            adapter_info,
         })
     }
 
+    /// Every adapter a headless context can be created on.
+    pub async fn headless_adapters() -> Vec<wgpu::Adapter> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        instance.enumerate_adapters(wgpu::Backends::all()).await
+    }
+
+    /// Creates a context without a window. Frames are only drawn into
+    /// offscreen textures of the given size (see `Renderer::headless`).
+    pub async fn headless(
+        adapter: &wgpu::Adapter,
+        size: PhysicalSize<u32>,
+        blend: TextBlend,
+    ) -> Result<Self, GpuContextError> {
+        if size.width == 0 || size.height == 0 {
+            return Err(GpuContextError::InvalidSize);
+        }
+
+        let adapter_info = adapter.get_info();
+        let device_descriptor = Self::device_descriptor(adapter)?;
+        let (device, queue) = adapter.request_device(&device_descriptor).await?;
+        let max_size = device.limits().max_texture_dimension_2d;
+
+        // Never configured; only describes the offscreen target.
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_DST,
+            format: match blend {
+                TextBlend::Linear => wgpu::TextureFormat::Rgba8UnormSrgb,
+                TextBlend::Srgb => wgpu::TextureFormat::Rgba8Unorm,
+            },
+            width: size.width.min(max_size),
+            height: size.height.min(max_size),
+            present_mode: wgpu::PresentMode::AutoNoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        Ok(Self {
+            device,
+            queue,
+            surface: None,
+            surface_config,
+            present_modes: Vec::new(),
+            adapter_info,
+        })
+    }
+
+    /// Logs what the renderer makes of `adapter`, and describes a device
+    /// with the limits it offers. Fails for adapters below the renderer's
+    /// requirements.
+    fn device_descriptor(
+        adapter: &wgpu::Adapter,
+    ) -> Result<wgpu::DeviceDescriptor<'static>, GpuContextError> {
+        let support = GpuSupport::new(adapter);
+        for line in support.lines() {
+            log::info!("GPU {}", line);
+        }
+        let unmet = support.unmet();
+        if !unmet.is_empty() {
+            return Err(GpuContextError::Unsupported(unmet.join(", ")));
+        }
+        Ok(wgpu::DeviceDescriptor {
+            required_limits: support.required_limits(),
+            ..Default::default()
+        })
+    }
+
+    /// Resizes the surface, to at most the largest texture the device
+    /// allows; a window past that shows the top-left part of the grid.
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            self.surface_config.width = new_size.width;
-            self.surface_config.height = new_size.height;
-            self.surface.configure(&self.device, &self.surface_config);
+            let max = self.device.limits().max_texture_dimension_2d;
+            if new_size.width > max || new_size.height > max {
+                log::warn!(
+                    "Window of {}x{} exceeds the GPU's {} px texture limit",
+                    new_size.width,
+                    new_size.height,
+                    max
+                );
+            }
+            self.surface_config.width = new_size.width.min(max);
+            self.surface_config.height = new_size.height.min(max);
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.surface_config);
+            }
         }
     }
 
+    /// Reconfigures the surface to present as `vsync` says, for
+    /// `:GuiSet vsync`.
+    pub fn set_vsync(&mut self, vsync: VsyncMode) {
+        let Some(surface) = &self.surface else {
+            return;
+        };
+        let present_mode = Self::select_present_mode(&self.present_modes, vsync);
+        self.surface_config.present_mode = present_mode;
+        self.surface_config.desired_maximum_frame_latency = Self::frame_latency(present_mode);
+        surface.configure(&self.device, &self.surface_config);
+        log::info!("Present mode: {:?}", present_mode);
+    }
+
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
     pub fn size(&self) -> PhysicalSize<u32> {
         PhysicalSize::new(self.surface_config.width, self.surface_config.height)
     }
@@ -99,22 +219,80 @@ impl GpuContext {
         self.surface_config.format
     }
 
+    /// Whether the target stores colors as written rather than encoding
+    /// them to sRGB, so the shaders have to. Blending then happens on
+    /// sRGB values.
+    pub fn encodes_srgb(&self) -> bool {
+        !self.format().is_srgb()
+    }
+
+    /// Whether frames can be copied into the surface, not just rendered.
+    pub fn surface_accepts_copies(&self) -> bool {
+        self.surface_config
+            .usage
+            .contains(wgpu::TextureUsages::COPY_DST)
+    }
+
+    pub fn alpha_mode(&self) -> wgpu::CompositeAlphaMode {
+        self.surface_config.alpha_mode
+    }
+
+    /// The next swapchain texture. Headless contexts have none and always
+    /// report the surface as lost.
     pub fn get_current_texture(&self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
-        self.surface.get_current_texture()
+        match &self.surface {
+            Some(surface) => surface.get_current_texture(),
+            None => Err(wgpu::SurfaceError::Lost),
+        }
     }
 
-    fn select_present_mode(
+    fn select_alpha_mode(
         surface_caps: &wgpu::SurfaceCapabilities,
+        transparent: bool,
+    ) -> wgpu::CompositeAlphaMode {
+        use wgpu::CompositeAlphaMode;
+
+        if !transparent {
+            return surface_caps.alpha_modes[0];
+        }
+        [
+            CompositeAlphaMode::PreMultiplied,
+            CompositeAlphaMode::PostMultiplied,
+            CompositeAlphaMode::Inherit,
+        ]
+        .into_iter()
+        .find(|mode| surface_caps.alpha_modes.contains(mode))
+        .unwrap_or_else(|| {
+            log::warn!(
+                "Surface has no transparent alpha mode ({:?}); window stays opaque",
+                surface_caps.alpha_modes
+            );
+            surface_caps.alpha_modes[0]
+        })
+    }
+
+    /// With VSync, use 1 frame in flight for lower latency (vs 2 which adds ~16-33ms)
+    fn frame_latency(present_mode: wgpu::PresentMode) -> u32 {
+        let uses_vsync = matches!(
+            present_mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::Fifo | wgpu::PresentMode::Mailbox
+        );
+        if uses_vsync {
+            1
+        } else {
+            2
+        }
+    }
+
+    fn select_present_mode(
+        present_modes: &[wgpu::PresentMode],
         vsync: VsyncMode,
     ) -> wgpu::PresentMode {
         match vsync {
             VsyncMode::Enabled => wgpu::PresentMode::AutoVsync,
             VsyncMode::Disabled => wgpu::PresentMode::AutoNoVsync,
             VsyncMode::MailboxIfAvailable => {
-                if surface_caps
-                    .present_modes
-                    .contains(&wgpu::PresentMode::Mailbox)
-                {
+                if present_modes.contains(&wgpu::PresentMode::Mailbox) {
                     wgpu::PresentMode::Mailbox
                 } else {
                     wgpu::PresentMode::AutoVsync
@@ -152,6 +330,9 @@ pub enum GpuContextError {
 
     #[error("Failed to request device: {0}")]
     RequestDevice(#[from] wgpu::RequestDeviceError),
+
+    #[error("GPU adapter below the renderer's requirements: {0}")]
+    Unsupported(String),
 }
 
 #[cfg(test)]
@@ -38,12 +38,16 @@ pub struct CachedGlyph {
     pub width: u32,
     /// Height of the glyph in pixels.
     pub height: u32,
+    /// Atlas page (texture array layer).
+    pub page: u32,
     /// Horizontal bearing (offset from origin to left edge).
     pub bearing_x: i32,
     /// Vertical bearing (offset from baseline to top edge).
     pub bearing_y: i32,
     /// Whether this is a color glyph (emoji).
     pub is_colored: bool,
+    /// Mean relative luminance of a color glyph, 0.0 for others.
+    pub luminance: f32,
 }
 
 impl CachedGlyph {
@@ -55,9 +59,11 @@ impl CachedGlyph {
             atlas_y: 0,
             width: 0,
             height: 0,
+            page: 0,
             bearing_x: 0,
             bearing_y: 0,
             is_colored: false,
+            luminance: 0.0,
         }
     }
 }
@@ -90,6 +96,11 @@ impl ShapedGlyphCache {
         self.cache.insert(key, glyph);
     }
 
+    /// Removes a glyph, e.g. after its atlas space was reclaimed.
+    pub fn remove(&mut self, key: &GlyphCacheKey) {
+        self.cache.remove(key);
+    }
+
     /// Checks if the cache contains an entry for the given key.
     #[allow(dead_code)]
     pub fn contains(&self, key: &GlyphCacheKey) -> bool {
@@ -174,9 +185,11 @@ mod tests {
             atlas_y: 20,
             width: 8,
             height: 16,
+            page: 0,
             bearing_x: 1,
             bearing_y: 14,
             is_colored: false,
+            luminance: 0.0,
         };
 
         cache.insert(key, Some(glyph));
@@ -228,9 +241,11 @@ mod tests {
                     atlas_y: 0,
                     width: 8,
                     height: 16,
+                    page: 0,
                     bearing_x: 0,
                     bearing_y: 14,
                     is_colored: false,
+                    luminance: 0.0,
                 }),
             );
         }
@@ -265,9 +280,11 @@ mod tests {
                     atlas_y: 0,
                     width: 8,
                     height: 16,
+                    page: 0,
                     bearing_x: 0,
                     bearing_y: 14,
                     is_colored: false,
+                    luminance: 0.0,
                 }),
             );
         }
@@ -298,9 +315,11 @@ mod tests {
                 atlas_y: 0,
                 width: 8,
                 height: 16,
+                page: 0,
                 bearing_x: 0,
                 bearing_y: 14,
                 is_colored: false,
+                luminance: 0.0,
             }),
         );
 
@@ -311,9 +330,11 @@ mod tests {
                 atlas_y: 0,
                 width: 10,
                 height: 16,
+                page: 0,
                 bearing_x: 0,
                 bearing_y: 14,
                 is_colored: true,
+                luminance: 0.0,
             }),
         );
 
@@ -2,13 +2,20 @@ use super::fallback::FallbackResolver;
 use super::platform::{create_fallback_resolver_with_embedded, Face, PlatformSystemFallback};
 use super::traits::SystemFallback;
 use super::types::{FaceError, FaceMetrics};
+use crate::editor::StyleFlags;
 
+/// Face a glyph is drawn from. The `Alt*` styles are the same weights and
+/// slants in `font.alt_family`, for cells highlighted with `altfont`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Style {
     Regular,
     Bold,
     Italic,
     BoldItalic,
+    AltRegular,
+    AltBold,
+    AltItalic,
+    AltBoldItalic,
 }
 
 impl Style {
@@ -21,14 +28,51 @@ impl Style {
         }
     }
 
+    /// The style a highlight's `bold`, `italic` and `altfont` select.
+    pub fn for_highlight(flags: StyleFlags) -> Self {
+        let style = Self::from_flags(
+            flags.contains(StyleFlags::BOLD),
+            flags.contains(StyleFlags::ITALIC),
+        );
+        if flags.contains(StyleFlags::ALTFONT) {
+            style.alt()
+        } else {
+            style
+        }
+    }
+
+    /// The same weight and slant in the alternate family.
+    pub fn alt(self) -> Self {
+        match self {
+            Style::Regular | Style::AltRegular => Style::AltRegular,
+            Style::Bold | Style::AltBold => Style::AltBold,
+            Style::Italic | Style::AltItalic => Style::AltItalic,
+            Style::BoldItalic | Style::AltBoldItalic => Style::AltBoldItalic,
+        }
+    }
+
+    /// The same weight and slant in the primary family.
+    pub fn base(self) -> Self {
+        match self {
+            Style::Regular | Style::AltRegular => Style::Regular,
+            Style::Bold | Style::AltBold => Style::Bold,
+            Style::Italic | Style::AltItalic => Style::Italic,
+            Style::BoldItalic | Style::AltBoldItalic => Style::BoldItalic,
+        }
+    }
+
+    pub fn is_alt(&self) -> bool {
+        *self != self.base()
+    }
+
     #[allow(dead_code)]
     pub fn is_bold(&self) -> bool {
-        matches!(self, Style::Bold | Style::BoldItalic)
+        matches!(self.base(), Style::Bold | Style::BoldItalic)
     }
 
     #[allow(dead_code)]
     pub fn is_italic(&self) -> bool {
-        matches!(self, Style::Italic | Style::BoldItalic)
+        matches!(self.base(), Style::Italic | Style::BoldItalic)
     }
 }
 
@@ -57,29 +101,22 @@ pub struct Collection {
     bold: Vec<Entry>,
     italic: Vec<Entry>,
     bold_italic: Vec<Entry>,
+    alt_regular: Vec<Entry>,
+    alt_bold: Vec<Entry>,
+    alt_italic: Vec<Entry>,
+    alt_bold_italic: Vec<Entry>,
     metrics: FaceMetrics,
-    #[allow(dead_code)]
     size_pt: f32,
-    #[allow(dead_code)]
     dpi: f32,
     fallback_resolver: FallbackResolver<Face, PlatformSystemFallback>,
 }
 
 impl Collection {
     pub fn new(family: &str, size_pt: f32, dpi: f32) -> Result<Self, FaceError> {
-        let regular_face = Face::new(family, size_pt, dpi)?;
+        let [regular_face, bold_face, italic_face, bold_italic_face] =
+            Self::load_family(family, size_pt, dpi)?;
         let metrics = *regular_face.metrics();
 
-        let bold_face = regular_face
-            .create_style_variant(Style::Bold)
-            .unwrap_or_else(|| regular_face.clone());
-        let italic_face = regular_face
-            .create_style_variant(Style::Italic)
-            .unwrap_or_else(|| regular_face.clone());
-        let bold_italic_face = regular_face
-            .create_style_variant(Style::BoldItalic)
-            .unwrap_or_else(|| regular_face.clone());
-
         let fallback_resolver = create_fallback_resolver_with_embedded(&regular_face)
             .unwrap_or_else(|| {
                 let size_px = regular_face.size_px();
@@ -88,10 +125,23 @@ impl Collection {
             });
 
         Ok(Self {
-            regular: vec![Entry { face: regular_face }],
-            bold: vec![Entry { face: bold_face }],
-            italic: vec![Entry { face: italic_face }],
+            regular: vec![Entry {
+                face: regular_face.clone(),
+            }],
+            bold: vec![Entry {
+                face: bold_face.clone(),
+            }],
+            italic: vec![Entry {
+                face: italic_face.clone(),
+            }],
             bold_italic: vec![Entry {
+                face: bold_italic_face.clone(),
+            }],
+            // Until `set_alt_family`, `altfont` cells look like any other.
+            alt_regular: vec![Entry { face: regular_face }],
+            alt_bold: vec![Entry { face: bold_face }],
+            alt_italic: vec![Entry { face: italic_face }],
+            alt_bold_italic: vec![Entry {
                 face: bold_italic_face,
             }],
             metrics,
@@ -101,6 +151,37 @@ impl Collection {
         })
     }
 
+    /// The regular, bold, italic and bold italic faces of `family`, with
+    /// the regular face standing in for a style the family lacks.
+    fn load_family(family: &str, size_pt: f32, dpi: f32) -> Result<[Face; 4], FaceError> {
+        let regular_face = Face::new(family, size_pt, dpi)?;
+        let variant = |style| {
+            regular_face
+                .create_style_variant(style)
+                .unwrap_or_else(|| regular_face.clone())
+        };
+        let bold_face = variant(Style::Bold);
+        let italic_face = variant(Style::Italic);
+        let bold_italic_face = variant(Style::BoldItalic);
+        Ok([regular_face, bold_face, italic_face, bold_italic_face])
+    }
+
+    /// Draws cells highlighted with `altfont` in `family`. The cell size
+    /// stays that of the primary family.
+    pub fn set_alt_family(&mut self, family: &str) -> Result<(), FaceError> {
+        let faces = Self::load_family(family, self.size_pt, self.dpi)?;
+        let styles = [
+            Style::AltRegular,
+            Style::AltBold,
+            Style::AltItalic,
+            Style::AltBoldItalic,
+        ];
+        for (style, face) in styles.into_iter().zip(faces) {
+            *self.entries_for_style_mut(style) = vec![Entry { face }];
+        }
+        Ok(())
+    }
+
     pub fn metrics(&self) -> &FaceMetrics {
         &self.metrics
     }
@@ -134,11 +215,15 @@ impl Collection {
             }
         }
 
-        if style != Style::Regular {
-            let regular_entries = self.entries_for_style(Style::Regular);
-            for (idx, entry) in regular_entries.iter().enumerate() {
+        // An alternate face falls back to the primary family's first.
+        for fallback in [style.base(), Style::Regular] {
+            if fallback == style {
+                continue;
+            }
+            let entries = self.entries_for_style(fallback);
+            for (idx, entry) in entries.iter().enumerate() {
                 if let Some(glyph_id) = entry.face.glyph_index(codepoint) {
-                    return Some((CollectionIndex::new(Style::Regular, idx as u16), glyph_id));
+                    return Some((CollectionIndex::new(fallback, idx as u16), glyph_id));
                 }
             }
         }
@@ -171,6 +256,10 @@ impl Collection {
             Style::Bold => &self.bold,
             Style::Italic => &self.italic,
             Style::BoldItalic => &self.bold_italic,
+            Style::AltRegular => &self.alt_regular,
+            Style::AltBold => &self.alt_bold,
+            Style::AltItalic => &self.alt_italic,
+            Style::AltBoldItalic => &self.alt_bold_italic,
         }
     }
 
@@ -180,6 +269,10 @@ impl Collection {
             Style::Bold => &mut self.bold,
             Style::Italic => &mut self.italic,
             Style::BoldItalic => &mut self.bold_italic,
+            Style::AltRegular => &mut self.alt_regular,
+            Style::AltBold => &mut self.alt_bold,
+            Style::AltItalic => &mut self.alt_italic,
+            Style::AltBoldItalic => &mut self.alt_bold_italic,
         }
     }
 
@@ -216,6 +309,23 @@ mod tests {
         assert!(Style::BoldItalic.is_italic());
     }
 
+    #[test]
+    fn test_style_for_highlight() {
+        assert_eq!(Style::for_highlight(StyleFlags::empty()), Style::Regular);
+        assert_eq!(
+            Style::for_highlight(StyleFlags::BOLD | StyleFlags::ALTFONT),
+            Style::AltBold
+        );
+        assert_eq!(
+            Style::for_highlight(StyleFlags::ITALIC | StyleFlags::ALTFONT).base(),
+            Style::Italic
+        );
+        assert!(Style::AltBoldItalic.is_bold());
+        assert!(Style::AltBoldItalic.is_alt());
+        assert!(!Style::BoldItalic.is_alt());
+        assert_eq!(Style::AltRegular.alt(), Style::AltRegular);
+    }
+
     #[test]
     fn test_collection_index() {
         let idx = CollectionIndex::new(Style::Bold, 2);
@@ -228,7 +338,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_collection_creation() {
         let collection = Collection::new("Menlo", 14.0, 72.0);
         assert!(
@@ -238,7 +348,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_collection_metrics() {
         let collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
         let metrics = collection.metrics();
@@ -248,7 +358,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_collection_get_face() {
         let collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
 
@@ -260,7 +370,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_collection_resolve_glyph() {
         let mut collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
 
@@ -274,7 +384,21 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    fn test_collection_alt_family() {
+        let mut collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
+        let cell_width = collection.metrics().cell_width;
+        collection.set_alt_family("Courier").unwrap();
+        assert_eq!(collection.metrics().cell_width, cell_width);
+
+        let (index, _) = collection
+            .resolve_glyph('A' as u32, Style::AltBold)
+            .unwrap();
+        assert_eq!(index, CollectionIndex::primary(Style::AltBold));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_collection_fallback_discovery() {
         let mut collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
 
@@ -291,7 +415,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_collection_nerd_font_fallback() {
         let mut collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
 
@@ -1,72 +1,333 @@
 //! Linux font face implementation using FreeType and fontconfig.
-//!
-//! TODO: Implement using:
-//! - `freetype-rs` or `freetype-sys` for font loading and glyph rasterization
-//! - `fontconfig` crate for font discovery
-//! - `hb_ft_font_create` for HarfBuzz integration
 
-use crate::renderer::font::{FaceError, FaceMetrics, FontFace, HbFontWrapper, RasterizedGlyph};
+use std::ffi::CStr;
+use std::rc::Rc;
 
-pub struct Face;
+use freetype_sys::{
+    ft_sfnt_os2, FT_Get_Char_Index, FT_Get_Sfnt_Table, FT_Load_Glyph, FT_Render_Glyph,
+    FT_HAS_COLOR, FT_IS_SCALABLE, FT_LOAD_COLOR, FT_LOAD_DEFAULT, FT_LOAD_NO_HINTING,
+    FT_LOAD_TARGET_LIGHT, FT_PIXEL_MODE_BGRA, FT_PIXEL_MODE_GRAY, FT_PIXEL_MODE_MONO,
+    FT_RENDER_MODE_NORMAL, TT_OS2,
+};
+
+use super::fontconfig;
+use super::loader::{create_font_from_bytes, create_font_from_file, FtFace};
+use crate::renderer::font::collection::Style;
+use crate::renderer::font::{
+    FaceError, FaceMetrics, FontFace, GlyphBuffer, HbFontWrapper, RasterizedGlyph,
+};
+
+fn hb_font_from_ft_face(ft_face: &FtFace, size_px: f32) -> Option<HbFontWrapper> {
+    let hb_font = unsafe { harfbuzz_sys::freetype::hb_ft_font_create_referenced(ft_face.as_ptr()) };
+    if hb_font.is_null() {
+        return None;
+    }
+    let scale = (size_px * 64.0) as i32;
+    unsafe {
+        harfbuzz_sys::hb_font_set_scale(hb_font, scale, scale);
+    }
+    unsafe { HbFontWrapper::from_raw(hb_font) }
+}
+
+pub struct Face {
+    ft_face: Rc<FtFace>,
+    hb_font: HbFontWrapper,
+    metrics: FaceMetrics,
+    size_px: f32,
+    has_color: bool,
+}
 
 impl Clone for Face {
     fn clone(&self) -> Self {
-        Self
+        let ft_face = self.ft_face.clone();
+        let hb_font = hb_font_from_ft_face(&ft_face, self.size_px)
+            .expect("Failed to create HarfBuzz font for cloned Face");
+        Self {
+            ft_face,
+            hb_font,
+            metrics: self.metrics,
+            size_px: self.size_px,
+            has_color: self.has_color,
+        }
     }
 }
 
 impl Face {
-    pub fn new(_name: &str, size_pt: f32, dpi: f32) -> Result<Self, FaceError> {
-        let _ = size_pt;
-        let _ = dpi;
+    pub fn new(name: &str, size_pt: f32, dpi: f32) -> Result<Self, FaceError> {
+        let size_px = size_pt * dpi / 72.0;
+        let file = fontconfig::match_family(name, Style::Regular)
+            .ok_or_else(|| FaceError::FontNotFound(name.to_string()))?;
+        Self::from_file(&file.path, file.index, size_px)
+    }
+
+    pub fn from_bytes(data: &'static [u8], size_px: f32) -> Result<Self, FaceError> {
+        let ft_face = create_font_from_bytes(data, size_px).ok_or(FaceError::TableCopyFailed)?;
+        Self::from_ft_face(ft_face, size_px)
+    }
+
+    pub fn from_file(path: &CStr, index: isize, size_px: f32) -> Result<Self, FaceError> {
+        let ft_face = create_font_from_file(path, index, size_px)
+            .ok_or_else(|| FaceError::FontNotFound(path.to_string_lossy().into_owned()))?;
+        Self::from_ft_face(ft_face, size_px)
+    }
+
+    pub fn create_style_variant(&self, style: Style) -> Option<Self> {
+        if style.base() == Style::Regular {
+            return Some(self.clone());
+        }
+
+        // Fontconfig hands back the regular face when the family has no such
+        // style; let the caller fall back the same way it does on macOS.
+        let family = self.family_name()?;
+        let file = fontconfig::match_family(&family, style)?;
+        let variant = Self::from_file(&file.path, file.index, self.size_px).ok()?;
+        if variant.family_name().as_deref() != Some(family.as_str())
+            || variant.style_name() == self.style_name()
+        {
+            return None;
+        }
+        Some(variant)
+    }
+
+    fn from_ft_face(ft_face: FtFace, size_px: f32) -> Result<Self, FaceError> {
+        let hb_font =
+            hb_font_from_ft_face(&ft_face, size_px).ok_or(FaceError::HarfBuzzFaceCreation)?;
+        let metrics = Self::compute_metrics(&ft_face, size_px);
+        let has_color = FT_HAS_COLOR(ft_face.as_ptr());
 
-        // TODO: Use fontconfig to find font, then FreeType to load it
-        Err(FaceError::NotImplemented)
+        Ok(Self {
+            ft_face: Rc::new(ft_face),
+            hb_font,
+            metrics,
+            size_px,
+            has_color,
+        })
     }
 
-    pub fn from_bytes(_data: &'static [u8], _size_px: f32) -> Result<Self, FaceError> {
-        // TODO: Use FT_New_Memory_Face to load from bytes
-        Err(FaceError::NotImplemented)
+    fn compute_metrics(ft_face: &FtFace, size_px: f32) -> FaceMetrics {
+        let raw = ft_face.as_ptr();
+        let size_metrics = unsafe { (*(*raw).size).metrics };
+
+        let ascent = size_metrics.ascender as f32 / 64.0;
+        let descent = -size_metrics.descender as f32 / 64.0;
+        let leading = (size_metrics.height as f32 / 64.0 - ascent - descent).max(0.0);
+
+        let cell_height = ascent + descent + leading;
+
+        let cell_width = Self::measure_advance(ft_face, 'M');
+
+        let (underline_position, underline_thickness) = if FT_IS_SCALABLE(raw) {
+            let scale = size_px / unsafe { (*raw).units_per_EM } as f32;
+            unsafe {
+                (
+                    (*raw).underline_position as f32 * scale,
+                    ((*raw).underline_thickness as f32 * scale).max(1.0),
+                )
+            }
+        } else {
+            (-descent / 2.0, 1.0)
+        };
+
+        let strikeout_position =
+            Self::os2_strikeout_position(ft_face, size_px).unwrap_or(ascent / 3.0);
+        let strikeout_thickness = underline_thickness;
+
+        FaceMetrics {
+            cell_width,
+            cell_height,
+            ascent,
+            descent,
+            line_gap: leading,
+            underline_position,
+            underline_thickness,
+            strikeout_position,
+            strikeout_thickness,
+        }
+    }
+
+    fn os2_strikeout_position(ft_face: &FtFace, size_px: f32) -> Option<f32> {
+        let raw = ft_face.as_ptr();
+        let os2 = unsafe { FT_Get_Sfnt_Table(raw, ft_sfnt_os2) } as *const TT_OS2;
+        if os2.is_null() || !FT_IS_SCALABLE(raw) {
+            return None;
+        }
+        let position = unsafe { (*os2).yStrikeoutPosition };
+        (position > 0).then(|| position as f32 * size_px / unsafe { (*raw).units_per_EM } as f32)
     }
 
-    pub fn create_style_variant(
-        &self,
-        _style: crate::renderer::font::collection::Style,
-    ) -> Option<Self> {
-        None
+    fn measure_advance(ft_face: &FtFace, ch: char) -> f32 {
+        let raw = ft_face.as_ptr();
+        let glyph = unsafe { FT_Get_Char_Index(raw, ch as _) };
+        if glyph == 0 {
+            return 8.0;
+        }
+        if unsafe { FT_Load_Glyph(raw, glyph, FT_LOAD_DEFAULT | FT_LOAD_NO_HINTING) } != 0 {
+            return 8.0;
+        }
+        unsafe { (*(*raw).glyph).advance.x as f32 / 64.0 }
+    }
+
+    pub fn metrics(&self) -> &FaceMetrics {
+        &self.metrics
+    }
+
+    pub fn size_px(&self) -> f32 {
+        self.size_px
+    }
+
+    #[allow(dead_code)]
+    pub fn has_color(&self) -> bool {
+        self.has_color
     }
 
-    /// Returns the HarfBuzz font handle for text shaping.
-    ///
-    /// # Panics
-    ///
-    /// Panics because the Linux font backend is not yet implemented.
-    /// This stub exists to satisfy the interface required by `Shaper`.
     pub fn hb_font(&self) -> &HbFontWrapper {
-        unimplemented!("Linux HarfBuzz font integration not yet implemented")
+        &self.hb_font
+    }
+
+    pub fn family_name(&self) -> Option<String> {
+        let name = unsafe { (*self.ft_face.as_ptr()).family_name };
+        if name.is_null() {
+            return None;
+        }
+        Some(
+            unsafe { CStr::from_ptr(name) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    fn style_name(&self) -> Option<String> {
+        let name = unsafe { (*self.ft_face.as_ptr()).style_name };
+        if name.is_null() {
+            return None;
+        }
+        Some(
+            unsafe { CStr::from_ptr(name) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    pub fn glyph_index(&self, codepoint: u32) -> Option<u32> {
+        char::from_u32(codepoint)?;
+        let glyph = unsafe { FT_Get_Char_Index(self.ft_face.as_ptr(), codepoint as _) };
+        (glyph != 0).then_some(glyph)
+    }
+
+    pub fn has_codepoint(&self, codepoint: u32) -> bool {
+        self.glyph_index(codepoint).is_some()
+    }
+
+    pub fn render_glyph(&self, glyph_id: u32) -> Result<RasterizedGlyph, FaceError> {
+        let raw = self.ft_face.as_ptr();
+        let mut flags = FT_LOAD_DEFAULT | FT_LOAD_TARGET_LIGHT;
+        if self.has_color {
+            flags |= FT_LOAD_COLOR;
+        }
+
+        let slot = unsafe {
+            if FT_Load_Glyph(raw, glyph_id, flags) != 0 {
+                return Err(FaceError::GlyphNotFound(glyph_id));
+            }
+            let slot = (*raw).glyph;
+            if FT_Render_Glyph(slot, FT_RENDER_MODE_NORMAL) != 0 {
+                return Err(FaceError::GlyphNotFound(glyph_id));
+            }
+            &*slot
+        };
+
+        let bitmap = &slot.bitmap;
+        let width = bitmap.width.max(0) as usize;
+        let height = bitmap.rows.max(0) as usize;
+
+        if width == 0 || height == 0 || bitmap.buffer.is_null() {
+            return Ok(RasterizedGlyph {
+                character: '\0',
+                width: 0,
+                height: 0,
+                bearing_x: 0,
+                bearing_y: 0,
+                buffer: GlyphBuffer::Rgba(Vec::new()),
+            });
+        }
+
+        let pitch = bitmap.pitch.unsigned_abs() as usize;
+        let rows: Vec<&[u8]> = (0..height)
+            .map(|y| unsafe { std::slice::from_raw_parts(bitmap.buffer.add(y * pitch), pitch) })
+            .collect();
+
+        let buffer = match bitmap.pixel_mode as u32 {
+            FT_PIXEL_MODE_BGRA => {
+                // Premultiplied BGRA, the same layout CoreText produces
+                let mut rgba = Vec::with_capacity(width * height * 4);
+                for row in &rows {
+                    rgba.extend_from_slice(&row[..width * 4]);
+                }
+                GlyphBuffer::Rgba(rgba)
+            }
+            FT_PIXEL_MODE_GRAY => {
+                let mut gray = Vec::with_capacity(width * height);
+                for row in &rows {
+                    gray.extend_from_slice(&row[..width]);
+                }
+                Self::convert_gray_to_rgb(gray, width, height)
+            }
+            FT_PIXEL_MODE_MONO => {
+                let mut gray = Vec::with_capacity(width * height);
+                for row in &rows {
+                    gray.extend((0..width).map(|x| {
+                        if row[x / 8] & (0x80 >> (x % 8)) != 0 {
+                            255
+                        } else {
+                            0
+                        }
+                    }));
+                }
+                Self::convert_gray_to_rgb(gray, width, height)
+            }
+            _ => return Err(FaceError::GlyphNotFound(glyph_id)),
+        };
+
+        Ok(RasterizedGlyph {
+            character: '\0',
+            width: width as u32,
+            height: height as u32,
+            bearing_x: slot.bitmap_left,
+            bearing_y: slot.bitmap_top,
+            buffer,
+        })
+    }
+
+    fn convert_gray_to_rgb(gray_buffer: Vec<u8>, width: usize, height: usize) -> GlyphBuffer {
+        let mut rgb = Vec::with_capacity(width * height * 3);
+        for byte in gray_buffer {
+            rgb.push(byte);
+            rgb.push(byte);
+            rgb.push(byte);
+        }
+        GlyphBuffer::Rgb(rgb)
     }
 }
 
 impl FontFace for Face {
     fn metrics(&self) -> &FaceMetrics {
-        static DEFAULT_METRICS: FaceMetrics = FaceMetrics::default();
-        &DEFAULT_METRICS
+        &self.metrics
     }
 
     fn size_px(&self) -> f32 {
-        0.0
+        self.size_px
     }
 
-    fn has_codepoint(&self, _codepoint: u32) -> bool {
-        false
+    fn has_codepoint(&self, codepoint: u32) -> bool {
+        self.has_codepoint(codepoint)
     }
 
-    fn glyph_index(&self, _codepoint: u32) -> Option<u32> {
-        None
+    fn glyph_index(&self, codepoint: u32) -> Option<u32> {
+        self.glyph_index(codepoint)
     }
 
-    fn render_glyph(&self, _glyph_id: u32) -> Result<RasterizedGlyph, FaceError> {
-        Err(FaceError::NotImplemented)
+    fn render_glyph(&self, glyph_id: u32) -> Result<RasterizedGlyph, FaceError> {
+        self.render_glyph(glyph_id)
     }
 
     fn hb_font(&self) -> &HbFontWrapper {
@@ -77,19 +338,57 @@ impl FontFace for Face {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::renderer::font::embedded::NERD_FONT_DATA;
+
+    #[test]
+    fn test_face_creation() {
+        let face = Face::new("monospace", 14.0, 72.0);
+        assert!(face.is_ok(), "Should create face from system font");
+    }
+
+    #[test]
+    fn test_face_metrics() {
+        let face = Face::new("monospace", 14.0, 72.0).unwrap();
+        let metrics = face.metrics();
+
+        assert!(metrics.cell_width > 0.0, "Cell width should be positive");
+        assert!(metrics.cell_height > 0.0, "Cell height should be positive");
+        assert!(metrics.ascent > 0.0, "Ascent should be positive");
+    }
+
+    #[test]
+    fn test_glyph_index() {
+        let face = Face::new("monospace", 14.0, 72.0).unwrap();
+
+        assert!(
+            face.glyph_index('A' as u32).is_some(),
+            "Should find glyph for 'A'"
+        );
+        assert!(face.has_codepoint('Z' as u32), "Should have codepoint 'Z'");
+    }
+
+    #[test]
+    fn test_render_glyph() {
+        let face = Face::new("monospace", 14.0, 72.0).unwrap();
+        let glyph_id = face.glyph_index('A' as u32).unwrap();
+
+        let glyph = face.render_glyph(glyph_id).expect("Should render glyph");
+        assert!(glyph.width > 0, "Glyph width should be positive");
+        assert!(glyph.height > 0, "Glyph height should be positive");
+        assert!(!glyph.buffer.is_colored());
+    }
 
     #[test]
-    fn test_face_creation_not_implemented() {
-        let face = Face::new("Menlo", 14.0, 72.0);
-        assert!(face.is_err());
-        assert_eq!(face.unwrap_err(), FaceError::NotImplemented);
+    fn test_from_bytes() {
+        let face = Face::from_bytes(NERD_FONT_DATA, 14.0).expect("Should load embedded font");
+        assert!(face.has_codepoint(0xE62B));
+        assert_eq!(face.size_px(), 14.0);
     }
 
     #[test]
-    fn test_from_bytes_not_implemented() {
+    fn test_from_invalid_bytes() {
         let data: &'static [u8] = &[];
         let face = Face::from_bytes(data, 14.0);
-        assert!(face.is_err());
-        assert_eq!(face.unwrap_err(), FaceError::NotImplemented);
+        assert_eq!(face.err(), Some(FaceError::TableCopyFailed));
     }
 }
@@ -1,32 +1,41 @@
 //! System fallback implementation for Linux using fontconfig.
 
+use super::fontconfig;
 use super::Face;
 use crate::renderer::font::fallback::FallbackResolver;
 use crate::renderer::font::traits::SystemFallback;
 
 /// System fallback for Linux using fontconfig.
 ///
-/// TODO: Implement using `fontconfig` crate to discover fallback fonts
-/// that contain specific codepoints (emoji, international scripts, etc.).
-pub struct LinuxSystemFallback;
+/// Asks fontconfig for the best installed font whose charset covers the
+/// codepoint (emoji, international scripts, etc.).
+pub struct LinuxSystemFallback {
+    size_px: f32,
+}
 
 impl SystemFallback<Face> for LinuxSystemFallback {
-    fn new(_base_face: &Face, _size_px: f32) -> Self {
-        Self
+    fn new(_base_face: &Face, size_px: f32) -> Self {
+        Self { size_px }
     }
 
-    fn discover(&self, _codepoint: u32) -> Option<Face> {
-        // TODO: Use fontconfig to find fallback font
-        None
+    fn discover(&self, codepoint: u32) -> Option<Face> {
+        let file = fontconfig::match_codepoint(codepoint)?;
+        let face = Face::from_file(&file.path, file.index, self.size_px).ok()?;
+        if face.has_codepoint(codepoint) {
+            Some(face)
+        } else {
+            None
+        }
     }
 }
 
 /// Creates a FallbackResolver, optionally with a nerd font for icon support.
 pub fn create_fallback_resolver(
-    _base_face: &Face,
+    base_face: &Face,
     nerd_font: Option<Face>,
 ) -> FallbackResolver<Face, LinuxSystemFallback> {
-    let system_fallback = LinuxSystemFallback;
+    let size_px = base_face.size_px();
+    let system_fallback = LinuxSystemFallback::new(base_face, size_px);
     let resolver = FallbackResolver::new(system_fallback);
     if let Some(nerd_font) = nerd_font {
         resolver.with_nerd_font(nerd_font)
@@ -35,14 +44,18 @@ pub fn create_fallback_resolver(
     }
 }
 
-/// Creates a FallbackResolver with embedded nerd font support.
-///
-/// TODO: Load embedded nerd font when Linux font loading is implemented.
+/// Creates a FallbackResolver with the embedded nerd font loaded.
 pub fn create_fallback_resolver_with_embedded(
-    _base_face: &Face,
+    base_face: &Face,
 ) -> Option<FallbackResolver<Face, LinuxSystemFallback>> {
-    // Return None until Linux font loading is implemented
-    None
+    use crate::renderer::font::embedded::NERD_FONT_DATA;
+
+    let size_px = base_face.size_px();
+    let nerd_font = Face::from_bytes(NERD_FONT_DATA, size_px).ok()?;
+
+    log::info!("Loaded embedded Nerd Font: size={}px", size_px);
+
+    Some(create_fallback_resolver(base_face, Some(nerd_font)))
 }
 
 #[cfg(test)]
@@ -50,18 +63,29 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_system_fallback_not_implemented() {
-        let face = Face;
-        let system_fallback = LinuxSystemFallback::new(&face, 14.0);
+    fn test_system_fallback_ascii() {
+        let base_face = Face::new("monospace", 14.0, 72.0).unwrap();
+        let system_fallback = LinuxSystemFallback::new(&base_face, 14.0);
 
-        assert!(system_fallback.discover('A' as u32).is_none());
-        assert!(system_fallback.discover('😀' as u32).is_none());
+        assert!(system_fallback.discover('A' as u32).is_some());
     }
 
     #[test]
     fn test_create_fallback_resolver() {
-        let face = Face;
-        let resolver = create_fallback_resolver(&face, None);
+        let base_face = Face::new("monospace", 14.0, 72.0).unwrap();
+        let resolver = create_fallback_resolver(&base_face, None);
         let _ = resolver;
     }
+
+    #[test]
+    fn test_create_fallback_resolver_with_embedded() {
+        let base_face = Face::new("monospace", 14.0, 72.0).unwrap();
+
+        let mut resolver = create_fallback_resolver_with_embedded(&base_face).unwrap();
+
+        let nerd_icons = [0xE62B, 0xE0A0, 0xEF3E, 0xF001];
+        for cp in nerd_icons {
+            assert!(resolver.discover(cp).is_some(), "Should find 0x{:X}", cp);
+        }
+    }
 }
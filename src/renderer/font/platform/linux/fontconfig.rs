@@ -0,0 +1,109 @@
+//! Font discovery through fontconfig.
+
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use fontconfig_sys::constants::{
+    FC_CHARSET, FC_FAMILY, FC_FILE, FC_INDEX, FC_SLANT, FC_SLANT_ITALIC, FC_SLANT_ROMAN, FC_WEIGHT,
+    FC_WEIGHT_BOLD, FC_WEIGHT_REGULAR,
+};
+use fontconfig_sys::{
+    FcCharSetAddChar, FcCharSetCreate, FcCharSetDestroy, FcConfigSubstitute, FcDefaultSubstitute,
+    FcFontMatch, FcMatchPattern, FcPattern, FcPatternAddCharSet, FcPatternAddInteger,
+    FcPatternAddString, FcPatternCreate, FcPatternDestroy, FcPatternGetInteger, FcPatternGetString,
+    FcResultMatch,
+};
+
+use crate::renderer::font::collection::Style;
+
+/// A font file, and the face inside it, picked by fontconfig.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontFile {
+    pub path: CString,
+    pub index: isize,
+}
+
+/// Finds the best match for a family name in the given style. Fontconfig
+/// always substitutes something, so this only fails when no fonts are
+/// installed at all.
+pub fn match_family(family: &str, style: Style) -> Option<FontFile> {
+    let family = CString::new(family).ok()?;
+    let (weight, slant) = match style {
+        Style::Regular | Style::AltRegular => (FC_WEIGHT_REGULAR, FC_SLANT_ROMAN),
+        Style::Bold | Style::AltBold => (FC_WEIGHT_BOLD, FC_SLANT_ROMAN),
+        Style::Italic | Style::AltItalic => (FC_WEIGHT_REGULAR, FC_SLANT_ITALIC),
+        Style::BoldItalic | Style::AltBoldItalic => (FC_WEIGHT_BOLD, FC_SLANT_ITALIC),
+    };
+
+    unsafe {
+        let pattern = FcPatternCreate();
+        if pattern.is_null() {
+            return None;
+        }
+        FcPatternAddString(pattern, FC_FAMILY.as_ptr(), family.as_ptr() as *const u8);
+        FcPatternAddInteger(pattern, FC_WEIGHT.as_ptr(), weight);
+        FcPatternAddInteger(pattern, FC_SLANT.as_ptr(), slant);
+        font_match(pattern)
+    }
+}
+
+/// Finds an installed font that covers `codepoint`.
+pub fn match_codepoint(codepoint: u32) -> Option<FontFile> {
+    unsafe {
+        let pattern = FcPatternCreate();
+        if pattern.is_null() {
+            return None;
+        }
+        let charset = FcCharSetCreate();
+        FcCharSetAddChar(charset, codepoint);
+        FcPatternAddCharSet(pattern, FC_CHARSET.as_ptr(), charset);
+        FcCharSetDestroy(charset);
+        font_match(pattern)
+    }
+}
+
+/// Runs the configured substitutions on `pattern` and returns the file of
+/// the best match. Takes ownership of the pattern.
+unsafe fn font_match(pattern: *mut FcPattern) -> Option<FontFile> {
+    FcConfigSubstitute(ptr::null_mut(), pattern, FcMatchPattern);
+    FcDefaultSubstitute(pattern);
+
+    let mut result = FcResultMatch;
+    let matched = FcFontMatch(ptr::null_mut(), pattern, &mut result);
+    FcPatternDestroy(pattern);
+    if matched.is_null() {
+        return None;
+    }
+
+    let mut file: *mut u8 = ptr::null_mut();
+    let mut index = 0;
+    let font_file = if FcPatternGetString(matched, FC_FILE.as_ptr(), 0, &mut file) == FcResultMatch
+        && !file.is_null()
+    {
+        FcPatternGetInteger(matched, FC_INDEX.as_ptr(), 0, &mut index);
+        Some(FontFile {
+            path: CStr::from_ptr(file as *const _).to_owned(),
+            index: index as isize,
+        })
+    } else {
+        None
+    };
+    FcPatternDestroy(matched);
+    font_file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_family() {
+        let file = match_family("monospace", Style::Regular).expect("Should match a font");
+        assert!(!file.path.as_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_match_codepoint() {
+        assert!(match_codepoint('A' as u32).is_some());
+    }
+}
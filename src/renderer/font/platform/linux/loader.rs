@@ -1,11 +1,129 @@
 //! Font loader for Linux using FreeType.
 
-use crate::renderer::font::FaceError;
-
-/// Creates a FreeType font directly from font data.
-///
-/// TODO: Implement using `FT_New_Memory_Face` from FreeType.
-pub fn create_font_from_bytes(_data: &[u8], _size_px: f32) -> Option<()> {
-    // TODO: Use FT_New_Memory_Face to load font from memory
-    None
+use std::ffi::CStr;
+use std::ptr;
+use std::rc::Rc;
+
+use freetype_sys::{
+    FT_Done_Face, FT_Done_FreeType, FT_F26Dot6, FT_Face, FT_Init_FreeType, FT_Library, FT_New_Face,
+    FT_New_Memory_Face, FT_Select_Size, FT_Set_Char_Size, FT_IS_SCALABLE,
+};
+
+/// Owned FreeType library handle. FreeType objects are not thread-safe, so
+/// each thread gets its own (see `library`).
+struct Library(FT_Library);
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        unsafe {
+            FT_Done_FreeType(self.0);
+        }
+    }
+}
+
+thread_local! {
+    static LIBRARY: Option<Rc<Library>> = {
+        let mut raw: FT_Library = ptr::null_mut();
+        let error = unsafe { FT_Init_FreeType(&mut raw) };
+        (error == 0).then(|| Rc::new(Library(raw)))
+    };
+}
+
+fn library() -> Option<Rc<Library>> {
+    LIBRARY.with(|library| library.clone())
+}
+
+/// Owned FreeType face, keeping its library alive.
+pub struct FtFace {
+    raw: FT_Face,
+    _library: Rc<Library>,
+}
+
+impl FtFace {
+    pub fn as_ptr(&self) -> FT_Face {
+        self.raw
+    }
+}
+
+impl Drop for FtFace {
+    fn drop(&mut self) {
+        unsafe {
+            FT_Done_Face(self.raw);
+        }
+    }
+}
+
+/// Creates a FreeType face directly from font data. FreeType reads the data
+/// in place, so it has to outlive the face.
+pub fn create_font_from_bytes(data: &'static [u8], size_px: f32) -> Option<FtFace> {
+    let library = library()?;
+    let mut raw: FT_Face = ptr::null_mut();
+    let error =
+        unsafe { FT_New_Memory_Face(library.0, data.as_ptr(), data.len() as _, 0, &mut raw) };
+    if error != 0 {
+        return None;
+    }
+    let face = FtFace {
+        raw,
+        _library: library,
+    };
+    set_size(&face, size_px).then_some(face)
+}
+
+/// Creates a FreeType face from face `index` of the font file at `path`.
+pub fn create_font_from_file(path: &CStr, index: isize, size_px: f32) -> Option<FtFace> {
+    let library = library()?;
+    let mut raw: FT_Face = ptr::null_mut();
+    let error = unsafe { FT_New_Face(library.0, path.as_ptr(), index as _, &mut raw) };
+    if error != 0 {
+        return None;
+    }
+    let face = FtFace {
+        raw,
+        _library: library,
+    };
+    set_size(&face, size_px).then_some(face)
+}
+
+/// Sizes the face to `size_px`. Bitmap-only fonts (most color emoji fonts)
+/// come in fixed strikes, so the one closest to the requested size is used.
+fn set_size(face: &FtFace, size_px: f32) -> bool {
+    let raw = face.as_ptr();
+    unsafe {
+        if FT_IS_SCALABLE(raw) {
+            let size = (size_px * 64.0) as FT_F26Dot6;
+            return FT_Set_Char_Size(raw, 0, size, 72, 72) == 0;
+        }
+
+        let strikes = (*raw).num_fixed_sizes.max(0) as usize;
+        if strikes == 0 {
+            return false;
+        }
+        let sizes = std::slice::from_raw_parts((*raw).available_sizes, strikes);
+        let best = sizes
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, s)| (s.y_ppem as f32 / 64.0 - size_px).abs() as u32)
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        FT_Select_Size(raw, best as _) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::font::embedded::NERD_FONT_DATA;
+
+    #[test]
+    fn test_create_font_from_bytes() {
+        let face = create_font_from_bytes(NERD_FONT_DATA, 14.0).expect("Should load embedded font");
+        assert!(!face.as_ptr().is_null());
+    }
+
+    #[test]
+    fn test_create_font_from_invalid_bytes() {
+        let data: &'static [u8] = &[0, 1, 2, 3];
+        assert!(create_font_from_bytes(data, 14.0).is_none());
+    }
 }
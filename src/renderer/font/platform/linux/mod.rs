@@ -1,8 +1,7 @@
 pub mod face;
 pub mod fallback;
+mod fontconfig;
 pub mod loader;
 
 pub use face::Face;
-pub use fallback::{
-    create_fallback_resolver, create_fallback_resolver_with_embedded, LinuxSystemFallback,
-};
+pub use fallback::{create_fallback_resolver_with_embedded, LinuxSystemFallback};
@@ -8,6 +8,7 @@ use crate::renderer::font::{
     FaceError, FaceMetrics, FontFace, GlyphBuffer, HbFontWrapper, RasterizedGlyph,
 };
 
+#[cfg(hb_coretext)]
 mod hb_coretext_ffi {
     use std::ffi::c_void;
 
@@ -16,9 +17,40 @@ mod hb_coretext_ffi {
     }
 }
 
+mod ct_table_ffi {
+    use std::ffi::c_void;
+
+    #[link(name = "CoreText", kind = "framework")]
+    extern "C" {
+        pub fn CTFontCopyTable(font: *const c_void, tag: u32, options: u32) -> *const c_void;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        pub fn CFDataGetBytePtr(data: *const c_void) -> *const u8;
+        pub fn CFDataGetLength(data: *const c_void) -> isize;
+        pub fn CFRetain(cf: *const c_void) -> *const c_void;
+        pub fn CFRelease(cf: *const c_void);
+    }
+}
+
+/// Creates the HarfBuzz font for `ct_font`, preferring HarfBuzz's own
+/// CoreText integration when it was built with one (see build.rs).
 fn hb_font_from_ct_font(ct_font: &CTFont, size_px: f32) -> Option<HbFontWrapper> {
     let ct_font_ptr = ct_font as *const CTFont as *const std::ffi::c_void;
+
+    #[cfg(hb_coretext)]
     let hb_font = unsafe { hb_coretext_ffi::hb_coretext_font_create(ct_font_ptr) };
+    #[cfg(not(hb_coretext))]
+    let hb_font: *mut harfbuzz_sys::hb_font_t = ptr::null_mut();
+
+    let hb_font = if hb_font.is_null() {
+        #[cfg(hb_coretext)]
+        log::debug!("hb_coretext_font_create failed, shaping from font tables");
+        unsafe { hb_font_from_tables(ct_font_ptr) }
+    } else {
+        hb_font
+    };
     if hb_font.is_null() {
         return None;
     }
@@ -29,6 +61,41 @@ fn hb_font_from_ct_font(ct_font: &CTFont, size_px: f32) -> Option<HbFontWrapper>
     unsafe { HbFontWrapper::from_raw(hb_font) }
 }
 
+/// Builds a HarfBuzz font on a face that reads its tables through
+/// `CTFontCopyTable`, for HarfBuzz builds without CoreText. Glyph
+/// positions then come from HarfBuzz's OpenType functions.
+unsafe fn hb_font_from_tables(ct_font: *const std::ffi::c_void) -> *mut harfbuzz_sys::hb_font_t {
+    unsafe extern "C" fn reference_table(
+        _face: *mut harfbuzz_sys::hb_face_t,
+        tag: harfbuzz_sys::hb_tag_t,
+        user_data: *mut std::ffi::c_void,
+    ) -> *mut harfbuzz_sys::hb_blob_t {
+        let data = ct_table_ffi::CTFontCopyTable(user_data, tag, 0);
+        if data.is_null() {
+            return ptr::null_mut();
+        }
+        harfbuzz_sys::hb_blob_create(
+            ct_table_ffi::CFDataGetBytePtr(data) as *const _,
+            ct_table_ffi::CFDataGetLength(data) as u32,
+            harfbuzz_sys::HB_MEMORY_MODE_READONLY,
+            data as *mut _,
+            Some(release),
+        )
+    }
+
+    unsafe extern "C" fn release(cf: *mut std::ffi::c_void) {
+        ct_table_ffi::CFRelease(cf);
+    }
+
+    // The face keeps its own reference to the font, dropped with it.
+    let user_data = ct_table_ffi::CFRetain(ct_font) as *mut _;
+    let hb_face =
+        harfbuzz_sys::hb_face_create_for_tables(Some(reference_table), user_data, Some(release));
+    let hb_font = harfbuzz_sys::hb_font_create(hb_face);
+    harfbuzz_sys::hb_face_destroy(hb_face);
+    hb_font
+}
+
 pub struct Face {
     ct_font: CFRetained<CTFont>,
     hb_font: HbFontWrapper,
@@ -84,10 +151,16 @@ impl Face {
         style: crate::renderer::font::collection::Style,
     ) -> Option<Self> {
         let traits = match style {
-            crate::renderer::font::collection::Style::Regular => return Some(self.clone()),
-            crate::renderer::font::collection::Style::Bold => CTFontSymbolicTraits::TraitBold,
-            crate::renderer::font::collection::Style::Italic => CTFontSymbolicTraits::TraitItalic,
-            crate::renderer::font::collection::Style::BoldItalic => {
+            crate::renderer::font::collection::Style::Regular
+            | crate::renderer::font::collection::Style::AltRegular => return Some(self.clone()),
+            crate::renderer::font::collection::Style::Bold
+            | crate::renderer::font::collection::Style::AltBold => CTFontSymbolicTraits::TraitBold,
+            crate::renderer::font::collection::Style::Italic
+            | crate::renderer::font::collection::Style::AltItalic => {
+                CTFontSymbolicTraits::TraitItalic
+            }
+            crate::renderer::font::collection::Style::BoldItalic
+            | crate::renderer::font::collection::Style::AltBoldItalic => {
                 CTFontSymbolicTraits::TraitBold | CTFontSymbolicTraits::TraitItalic
             }
         };
@@ -1,72 +1,310 @@
 //! Windows font face implementation using DirectWrite.
 //!
-//! TODO: Implement using:
-//! - `directwrite` crate for font loading and glyph rasterization
-//! - `IDWriteFontFallback` for fallback discovery
-//! - `hb_font_create` or `hb_directwrite_font_create` for HarfBuzz integration
+//! Glyphs are rasterized by DirectWrite; HarfBuzz shapes from the font file
+//! with its built-in OpenType functions. Color glyphs (emoji) are drawn as
+//! grayscale masks for now.
 
-use crate::renderer::font::{FaceError, FaceMetrics, FontFace, HbFontWrapper, RasterizedGlyph};
+use std::ptr;
 
-pub struct Face;
+use dwrote::{
+    DWRITE_TEXTURE_CLEARTYPE_3x1, FontCollection, FontStretch, FontStyle, FontWeight, GlyphOffset,
+    GlyphRunAnalysis, DWRITE_GLYPH_RUN, DWRITE_MEASURING_MODE_NATURAL,
+    DWRITE_RENDERING_MODE_NATURAL_SYMMETRIC,
+};
+
+use super::loader::{create_font_from_bytes, font_file_data};
+use crate::renderer::font::collection::Style;
+use crate::renderer::font::{
+    FaceError, FaceMetrics, FontFace, GlyphBuffer, HbFontWrapper, RasterizedGlyph,
+};
+
+fn hb_font_from_data(data: &[u8], index: u32, size_px: f32) -> Option<HbFontWrapper> {
+    let hb_font = unsafe {
+        let blob = harfbuzz_sys::hb_blob_create(
+            data.as_ptr() as *const _,
+            data.len() as u32,
+            harfbuzz_sys::HB_MEMORY_MODE_DUPLICATE,
+            ptr::null_mut(),
+            None,
+        );
+        let hb_face = harfbuzz_sys::hb_face_create(blob, index);
+        harfbuzz_sys::hb_blob_destroy(blob);
+        let hb_font = harfbuzz_sys::hb_font_create(hb_face);
+        harfbuzz_sys::hb_face_destroy(hb_face);
+        hb_font
+    };
+    if hb_font.is_null() {
+        return None;
+    }
+    let scale = (size_px * 64.0) as i32;
+    unsafe {
+        harfbuzz_sys::hb_font_set_scale(hb_font, scale, scale);
+    }
+    unsafe { HbFontWrapper::from_raw(hb_font) }
+}
+
+pub struct Face {
+    font_face: dwrote::FontFace,
+    /// The system font this face came from; None for faces loaded from memory.
+    font: Option<dwrote::Font>,
+    hb_font: HbFontWrapper,
+    metrics: FaceMetrics,
+    size_px: f32,
+}
 
 impl Clone for Face {
     fn clone(&self) -> Self {
-        Self
+        let hb_font = unsafe {
+            HbFontWrapper::from_raw(harfbuzz_sys::hb_font_reference(self.hb_font.as_ptr()))
+        }
+        .expect("Failed to create HarfBuzz font for cloned Face");
+        Self {
+            font_face: self.font_face.clone(),
+            font: self.font.clone(),
+            hb_font,
+            metrics: self.metrics,
+            size_px: self.size_px,
+        }
     }
 }
 
 impl Face {
-    pub fn new(_name: &str, size_pt: f32, dpi: f32) -> Result<Self, FaceError> {
-        let _ = size_pt;
-        let _ = dpi;
+    pub fn new(name: &str, size_pt: f32, dpi: f32) -> Result<Self, FaceError> {
+        let size_px = size_pt * dpi / 72.0;
+        let family = FontCollection::system()
+            .font_family_by_name(name)
+            .ok()
+            .flatten()
+            .ok_or_else(|| FaceError::FontNotFound(name.to_string()))?;
+        let font = family
+            .first_matching_font(FontWeight::Regular, FontStretch::Normal, FontStyle::Normal)
+            .map_err(|_| FaceError::FontNotFound(name.to_string()))?;
+
+        Self::from_font(font, size_px)
+    }
+
+    pub fn from_bytes(data: &'static [u8], size_px: f32) -> Result<Self, FaceError> {
+        let font_face = create_font_from_bytes(data).ok_or(FaceError::TableCopyFailed)?;
+        let hb_font = hb_font_from_data(data, 0, size_px).ok_or(FaceError::HarfBuzzFaceCreation)?;
+        let metrics = Self::compute_metrics(&font_face, size_px);
+
+        Ok(Self {
+            font_face,
+            font: None,
+            hb_font,
+            metrics,
+            size_px,
+        })
+    }
+
+    pub fn from_font(font: dwrote::Font, size_px: f32) -> Result<Self, FaceError> {
+        let font_face = font.create_font_face();
+        let data = font_file_data(&font_face).ok_or(FaceError::TableCopyFailed)?;
+        let hb_font = hb_font_from_data(&data, font_face.get_index(), size_px)
+            .ok_or(FaceError::HarfBuzzFaceCreation)?;
+        let metrics = Self::compute_metrics(&font_face, size_px);
+
+        Ok(Self {
+            font_face,
+            font: Some(font),
+            hb_font,
+            metrics,
+            size_px,
+        })
+    }
+
+    pub fn create_style_variant(&self, style: Style) -> Option<Self> {
+        let (weight, font_style) = match style {
+            Style::Regular | Style::AltRegular => return Some(self.clone()),
+            Style::Bold | Style::AltBold => (FontWeight::Bold, FontStyle::Normal),
+            Style::Italic | Style::AltItalic => (FontWeight::Regular, FontStyle::Italic),
+            Style::BoldItalic | Style::AltBoldItalic => (FontWeight::Bold, FontStyle::Italic),
+        };
+
+        let font = self.font.as_ref()?;
+        let family = FontCollection::system()
+            .font_family_by_name(&font.family_name())
+            .ok()??;
+        let variant = family
+            .first_matching_font(weight, FontStretch::Normal, font_style)
+            .ok()?;
+        if variant.weight() == font.weight() && variant.style() == font.style() {
+            return None;
+        }
+
+        Self::from_font(variant, self.size_px).ok()
+    }
+
+    fn compute_metrics(font_face: &dwrote::FontFace, size_px: f32) -> FaceMetrics {
+        let design = font_face.metrics().metrics0();
+        let scale = size_px / design.designUnitsPerEm as f32;
+
+        let ascent = design.ascent as f32 * scale;
+        let descent = design.descent as f32 * scale;
+        let leading = (design.lineGap as f32 * scale).max(0.0);
+
+        let cell_height = ascent + descent + leading;
+
+        let cell_width = Self::measure_advance(font_face, 'M', scale);
+
+        let underline_position = design.underlinePosition as f32 * scale;
+        let underline_thickness = (design.underlineThickness as f32 * scale).max(1.0);
+
+        let strikeout_position = if design.strikethroughPosition > 0 {
+            design.strikethroughPosition as f32 * scale
+        } else {
+            ascent / 3.0
+        };
+        let strikeout_thickness = (design.strikethroughThickness as f32 * scale).max(1.0);
+
+        FaceMetrics {
+            cell_width,
+            cell_height,
+            ascent,
+            descent,
+            line_gap: leading,
+            underline_position,
+            underline_thickness,
+            strikeout_position,
+            strikeout_thickness,
+        }
+    }
 
-        // TODO: Use DirectWrite to find and load font
-        Err(FaceError::NotImplemented)
+    fn measure_advance(font_face: &dwrote::FontFace, ch: char, scale: f32) -> f32 {
+        let glyph = match font_face.glyph_indices(&[ch as u32]) {
+            Ok(glyphs) if glyphs.first().is_some_and(|&g| g != 0) => glyphs[0],
+            _ => return 8.0,
+        };
+        match font_face.design_glyph_metrics(&[glyph], false) {
+            Ok(metrics) => metrics[0].advanceWidth as f32 * scale,
+            Err(_) => 8.0,
+        }
     }
 
-    pub fn from_bytes(_data: &'static [u8], _size_px: f32) -> Result<Self, FaceError> {
-        // TODO: Use DirectWrite in-memory font loader
-        Err(FaceError::NotImplemented)
+    pub fn metrics(&self) -> &FaceMetrics {
+        &self.metrics
     }
 
-    pub fn create_style_variant(
-        &self,
-        _style: crate::renderer::font::collection::Style,
-    ) -> Option<Self> {
-        None
+    pub fn size_px(&self) -> f32 {
+        self.size_px
     }
 
-    /// Returns the HarfBuzz font handle for text shaping.
-    ///
-    /// # Panics
-    ///
-    /// Panics because the Windows font backend is not yet implemented.
-    /// This stub exists to satisfy the interface required by `Shaper`.
     pub fn hb_font(&self) -> &HbFontWrapper {
-        unimplemented!("Windows HarfBuzz font integration not yet implemented")
+        &self.hb_font
+    }
+
+    pub fn family_name(&self) -> Option<String> {
+        self.font.as_ref().map(|font| font.family_name())
+    }
+
+    pub fn glyph_index(&self, codepoint: u32) -> Option<u32> {
+        char::from_u32(codepoint)?;
+        let glyphs = self.font_face.glyph_indices(&[codepoint]).ok()?;
+        glyphs.first().filter(|&&g| g != 0).map(|&g| g as u32)
+    }
+
+    pub fn has_codepoint(&self, codepoint: u32) -> bool {
+        self.glyph_index(codepoint).is_some()
+    }
+
+    pub fn render_glyph(&self, glyph_id: u32) -> Result<RasterizedGlyph, FaceError> {
+        let glyph = u16::try_from(glyph_id).map_err(|_| FaceError::GlyphNotFound(glyph_id))?;
+        let indices = [glyph];
+        let advances = [0.0f32];
+        let offsets = [GlyphOffset {
+            advanceOffset: 0.0,
+            ascenderOffset: 0.0,
+        }];
+        let run = DWRITE_GLYPH_RUN {
+            fontFace: unsafe { self.font_face.as_ptr() },
+            fontEmSize: self.size_px,
+            glyphCount: 1,
+            glyphIndices: indices.as_ptr(),
+            glyphAdvances: advances.as_ptr(),
+            glyphOffsets: offsets.as_ptr(),
+            isSideways: 0,
+            bidiLevel: 0,
+        };
+
+        let analysis = GlyphRunAnalysis::create(
+            &run,
+            1.0,
+            None,
+            DWRITE_RENDERING_MODE_NATURAL_SYMMETRIC,
+            DWRITE_MEASURING_MODE_NATURAL,
+            0.0,
+            0.0,
+        )
+        .map_err(|_| FaceError::ContextCreationFailed)?;
+        let bounds = analysis
+            .get_alpha_texture_bounds(DWRITE_TEXTURE_CLEARTYPE_3x1)
+            .map_err(|_| FaceError::ContextCreationFailed)?;
+
+        let width = (bounds.right - bounds.left).max(0) as usize;
+        let height = (bounds.bottom - bounds.top).max(0) as usize;
+
+        if width == 0 || height == 0 {
+            return Ok(RasterizedGlyph {
+                character: '\0',
+                width: 0,
+                height: 0,
+                bearing_x: 0,
+                bearing_y: 0,
+                buffer: GlyphBuffer::Rgba(Vec::new()),
+            });
+        }
+
+        let texture = analysis
+            .create_alpha_texture(DWRITE_TEXTURE_CLEARTYPE_3x1, bounds)
+            .map_err(|_| FaceError::ContextCreationFailed)?;
+
+        // Average the ClearType subpixel coverage down to one value per
+        // pixel, the same grayscale coverage the other backends produce
+        let gray = texture
+            .chunks_exact(3)
+            .map(|p| ((p[0] as u16 + p[1] as u16 + p[2] as u16) / 3) as u8)
+            .collect();
+
+        Ok(RasterizedGlyph {
+            character: '\0',
+            width: width as u32,
+            height: height as u32,
+            bearing_x: bounds.left,
+            bearing_y: -bounds.top,
+            buffer: Self::convert_gray_to_rgb(gray, width, height),
+        })
+    }
+
+    fn convert_gray_to_rgb(gray_buffer: Vec<u8>, width: usize, height: usize) -> GlyphBuffer {
+        let mut rgb = Vec::with_capacity(width * height * 3);
+        for byte in gray_buffer {
+            rgb.push(byte);
+            rgb.push(byte);
+            rgb.push(byte);
+        }
+        GlyphBuffer::Rgb(rgb)
     }
 }
 
 impl FontFace for Face {
     fn metrics(&self) -> &FaceMetrics {
-        static DEFAULT_METRICS: FaceMetrics = FaceMetrics::default();
-        &DEFAULT_METRICS
+        &self.metrics
     }
 
     fn size_px(&self) -> f32 {
-        0.0
+        self.size_px
     }
 
-    fn has_codepoint(&self, _codepoint: u32) -> bool {
-        false
+    fn has_codepoint(&self, codepoint: u32) -> bool {
+        self.has_codepoint(codepoint)
     }
 
-    fn glyph_index(&self, _codepoint: u32) -> Option<u32> {
-        None
+    fn glyph_index(&self, codepoint: u32) -> Option<u32> {
+        self.glyph_index(codepoint)
     }
 
-    fn render_glyph(&self, _glyph_id: u32) -> Result<RasterizedGlyph, FaceError> {
-        Err(FaceError::NotImplemented)
+    fn render_glyph(&self, glyph_id: u32) -> Result<RasterizedGlyph, FaceError> {
+        self.render_glyph(glyph_id)
     }
 
     fn hb_font(&self) -> &HbFontWrapper {
@@ -77,19 +315,43 @@ impl FontFace for Face {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::renderer::font::embedded::NERD_FONT_DATA;
 
     #[test]
-    fn test_face_creation_not_implemented() {
+    fn test_face_creation() {
         let face = Face::new("Consolas", 14.0, 96.0);
-        assert!(face.is_err());
-        assert_eq!(face.unwrap_err(), FaceError::NotImplemented);
+        assert!(face.is_ok(), "Should create face from system font");
+    }
+
+    #[test]
+    fn test_face_metrics() {
+        let face = Face::new("Consolas", 14.0, 96.0).unwrap();
+        let metrics = face.metrics();
+
+        assert!(metrics.cell_width > 0.0, "Cell width should be positive");
+        assert!(metrics.cell_height > 0.0, "Cell height should be positive");
+        assert!(metrics.ascent > 0.0, "Ascent should be positive");
+    }
+
+    #[test]
+    fn test_render_glyph() {
+        let face = Face::new("Consolas", 14.0, 96.0).unwrap();
+        let glyph_id = face.glyph_index('A' as u32).unwrap();
+
+        let glyph = face.render_glyph(glyph_id).expect("Should render glyph");
+        assert!(glyph.width > 0, "Glyph width should be positive");
+        assert!(glyph.height > 0, "Glyph height should be positive");
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let face = Face::from_bytes(NERD_FONT_DATA, 14.0).expect("Should load embedded font");
+        assert!(face.has_codepoint(0xE62B));
     }
 
     #[test]
-    fn test_from_bytes_not_implemented() {
-        let data: &'static [u8] = &[];
-        let face = Face::from_bytes(data, 14.0);
-        assert!(face.is_err());
-        assert_eq!(face.unwrap_err(), FaceError::NotImplemented);
+    fn test_unknown_family() {
+        let face = Face::new("No Such Font Family", 14.0, 96.0);
+        assert!(matches!(face, Err(FaceError::FontNotFound(_))));
     }
 }
@@ -1,32 +1,87 @@
 //! System fallback implementation for Windows using DirectWrite.
 
+use std::borrow::Cow;
+
+use dwrote::{
+    FontCollection, FontFallback, FontStretch, FontStyle, FontWeight, TextAnalysisSource,
+    TextAnalysisSourceMethods,
+};
+use winapi::um::dwrite::{DWRITE_READING_DIRECTION, DWRITE_READING_DIRECTION_LEFT_TO_RIGHT};
+
 use super::Face;
 use crate::renderer::font::fallback::FallbackResolver;
 use crate::renderer::font::traits::SystemFallback;
 
+/// Text source handed to `IDWriteFontFallback::MapCharacters`.
+struct FallbackText {
+    len: u32,
+}
+
+impl TextAnalysisSourceMethods for FallbackText {
+    fn get_locale_name(&self, _text_position: u32) -> (Cow<'_, str>, u32) {
+        (Cow::Borrowed("en-us"), self.len)
+    }
+
+    fn get_paragraph_reading_direction(&self) -> DWRITE_READING_DIRECTION {
+        DWRITE_READING_DIRECTION_LEFT_TO_RIGHT
+    }
+}
+
 /// System fallback for Windows using DirectWrite.
 ///
-/// TODO: Implement using `IDWriteFontFallback` to discover fallback fonts
-/// that contain specific codepoints (emoji, international scripts, etc.).
-pub struct WindowsSystemFallback;
+/// Uses the system `IDWriteFontFallback` to discover fallback fonts that
+/// contain specific codepoints (emoji, international scripts, etc.).
+pub struct WindowsSystemFallback {
+    fallback: Option<FontFallback>,
+    family: Option<String>,
+    size_px: f32,
+}
 
 impl SystemFallback<Face> for WindowsSystemFallback {
-    fn new(_base_face: &Face, _size_px: f32) -> Self {
-        Self
+    fn new(base_face: &Face, size_px: f32) -> Self {
+        Self {
+            fallback: FontFallback::get_system_fallback(),
+            family: base_face.family_name(),
+            size_px,
+        }
     }
 
-    fn discover(&self, _codepoint: u32) -> Option<Face> {
-        // TODO: Use IDWriteFontFallback to find fallback font
-        None
+    fn discover(&self, codepoint: u32) -> Option<Face> {
+        let fallback = self.fallback.as_ref()?;
+        let ch = char::from_u32(codepoint)?;
+        let mut units = [0u16; 2];
+        let text = ch.encode_utf16(&mut units).to_vec();
+        let len = text.len() as u32;
+
+        let source =
+            TextAnalysisSource::from_text(Box::new(FallbackText { len }), Cow::Owned(text));
+        let result = fallback.map_characters(
+            &source,
+            0,
+            len,
+            &FontCollection::system(),
+            self.family.as_deref(),
+            FontWeight::Regular,
+            FontStyle::Normal,
+            FontStretch::Normal,
+        );
+
+        let face = Face::from_font(result.mapped_font?, self.size_px).ok()?;
+        if face.has_codepoint(codepoint) {
+            Some(face)
+        } else {
+            None
+        }
     }
 }
 
 /// Creates a FallbackResolver, optionally with a nerd font for icon support.
 pub fn create_fallback_resolver(
-    _base_face: &Face,
+    base_face: &Face,
     nerd_font: Option<Face>,
 ) -> FallbackResolver<Face, WindowsSystemFallback> {
-    let system_fallback = WindowsSystemFallback;
+    let size_px = base_face.size_px();
+    let system_fallback = WindowsSystemFallback::new(base_face, size_px);
     let resolver = FallbackResolver::new(system_fallback);
     if let Some(nerd_font) = nerd_font {
         resolver.with_nerd_font(nerd_font)
@@ -35,14 +90,18 @@ pub fn create_fallback_resolver(
     }
 }
 
-/// Creates a FallbackResolver with embedded nerd font support.
-///
-/// TODO: Load embedded nerd font when Windows font loading is implemented.
+/// Creates a FallbackResolver with the embedded nerd font loaded.
 pub fn create_fallback_resolver_with_embedded(
-    _base_face: &Face,
+    base_face: &Face,
 ) -> Option<FallbackResolver<Face, WindowsSystemFallback>> {
-    // Return None until Windows font loading is implemented
-    None
+    use crate::renderer::font::embedded::NERD_FONT_DATA;
+
+    let size_px = base_face.size_px();
+    let nerd_font = Face::from_bytes(NERD_FONT_DATA, size_px).ok()?;
+
+    log::info!("Loaded embedded Nerd Font: size={}px", size_px);
+
+    Some(create_fallback_resolver(base_face, Some(nerd_font)))
 }
 
 #[cfg(test)]
@@ -50,18 +109,22 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_system_fallback_not_implemented() {
-        let face = Face;
-        let system_fallback = WindowsSystemFallback::new(&face, 14.0);
+    fn test_system_fallback_emoji() {
+        let base_face = Face::new("Consolas", 14.0, 96.0).unwrap();
+        let system_fallback = WindowsSystemFallback::new(&base_face, 14.0);
 
-        assert!(system_fallback.discover('A' as u32).is_none());
-        assert!(system_fallback.discover('😀' as u32).is_none());
+        assert!(system_fallback.discover('😀' as u32).is_some());
     }
 
     #[test]
-    fn test_create_fallback_resolver() {
-        let face = Face;
-        let resolver = create_fallback_resolver(&face, None);
-        let _ = resolver;
+    fn test_create_fallback_resolver_with_embedded() {
+        let base_face = Face::new("Consolas", 14.0, 96.0).unwrap();
+
+        let mut resolver = create_fallback_resolver_with_embedded(&base_face).unwrap();
+
+        let nerd_icons = [0xE62B, 0xE0A0, 0xEF3E, 0xF001];
+        for cp in nerd_icons {
+            assert!(resolver.discover(cp).is_some(), "Should find 0x{:X}", cp);
+        }
     }
 }
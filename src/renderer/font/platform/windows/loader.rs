@@ -1,11 +1,17 @@
 //! Font loader for Windows using DirectWrite.
 
-use crate::renderer::font::FaceError;
-
-/// Creates a DirectWrite font directly from font data.
-///
-/// TODO: Implement using DirectWrite in-memory font loader.
-pub fn create_font_from_bytes(_data: &[u8], _size_px: f32) -> Option<()> {
-    // TODO: Use DirectWrite in-memory font loader
-    None
+use std::sync::Arc;
+
+use dwrote::{FontFace, FontFile, DWRITE_FONT_SIMULATIONS_NONE};
+
+/// Creates a DirectWrite font face directly from font data, without
+/// installing it system-wide.
+pub fn create_font_from_bytes(data: &'static [u8]) -> Option<FontFace> {
+    let file = FontFile::new_from_buffer(Arc::new(data))?;
+    file.create_face(0, DWRITE_FONT_SIMULATIONS_NONE).ok()
+}
+
+/// Raw bytes of the file backing a font face, for HarfBuzz.
+pub fn font_file_data(face: &FontFace) -> Option<Vec<u8>> {
+    face.files().ok()?.first()?.font_file_bytes().ok()
 }
@@ -1,7 +1,14 @@
-use crate::editor::{Cell, HighlightMap, StyleFlags};
+use crate::editor::{Cell, HighlightMap};
 
 use super::collection::Style;
 
+/// Runs longer than this many cells end at the next space, so a long line
+/// is shaped in pieces that are cheap to shape and likely to be cached.
+const RUN_CHUNK_CELLS: usize = 64;
+
+/// Runs are cut here even without a space, as in minified code.
+const MAX_RUN_CELLS: usize = 2 * RUN_CHUNK_CELLS;
+
 /// A text run is a sequence of consecutive cells with the same styling.
 ///
 /// Runs are the unit of text shaping - HarfBuzz shapes entire runs at once
@@ -16,6 +23,9 @@ pub struct Run {
     pub style: Style,
     /// Highlight ID for color resolution.
     pub highlight_id: u64,
+    /// Byte offsets in `text` of the characters taking two cells, whose
+    /// right half is a wide spacer.
+    pub wide: Vec<u32>,
 }
 
 impl Run {
@@ -29,6 +39,8 @@ impl Run {
 ///
 /// A new run starts when:
 /// - The highlight ID changes (different colors or styles)
+/// - The run is `RUN_CHUNK_CELLS` long and reaches a space, or is
+///   `MAX_RUN_CELLS` long
 /// - A wide spacer is encountered (skip it)
 ///
 /// Wide spacers are skipped entirely as they are placeholders for
@@ -49,13 +61,19 @@ impl<'a> RunIterator<'a> {
         }
     }
 
+    /// Adds the current cell to a run's text and moves past it.
+    fn push_cell(&mut self, text: &mut String, wide: &mut Vec<u32>) {
+        let next = self.cells.get(self.current_pos + 1);
+        if next.is_some_and(Cell::is_wide_spacer) {
+            wide.push(text.len() as u32);
+        }
+        text.push_str(&self.cells[self.current_pos].text);
+        self.current_pos += 1;
+    }
+
     /// Determines the font style from highlight attributes.
     fn style_for_highlight(&self, highlight_id: u64) -> Style {
-        let attrs = self.highlights.get(highlight_id);
-        Style::from_flags(
-            attrs.style.contains(StyleFlags::BOLD),
-            attrs.style.contains(StyleFlags::ITALIC),
-        )
+        Style::for_highlight(self.highlights.get(highlight_id).style)
     }
 }
 
@@ -81,8 +99,8 @@ impl<'a> Iterator for RunIterator<'a> {
         let style = self.style_for_highlight(highlight_id);
 
         let mut text = String::new();
-        text.push_str(&first_cell.text);
-        self.current_pos += 1;
+        let mut wide = Vec::new();
+        self.push_cell(&mut text, &mut wide);
 
         // Continue accumulating while highlight_id matches
         while self.current_pos < self.cells.len() {
@@ -99,8 +117,15 @@ impl<'a> Iterator for RunIterator<'a> {
                 break;
             }
 
-            text.push_str(&cell.text);
-            self.current_pos += 1;
+            // Break long runs, after a space where there is one so
+            // ligatures stay whole
+            let len = self.current_pos - start_col;
+            let after_space = self.cells[self.current_pos - 1].text == " ";
+            if len >= MAX_RUN_CELLS || (len >= RUN_CHUNK_CELLS && after_space) {
+                break;
+            }
+
+            self.push_cell(&mut text, &mut wide);
         }
 
         Some(Run {
@@ -108,6 +133,7 @@ impl<'a> Iterator for RunIterator<'a> {
             text,
             style,
             highlight_id,
+            wide,
         })
     }
 }
@@ -115,7 +141,7 @@ impl<'a> Iterator for RunIterator<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::editor::{CellFlags, HighlightAttributes};
+    use crate::editor::{CellFlags, HighlightAttributes, StyleFlags};
 
     fn make_cell(text: &str, highlight_id: u64) -> Cell {
         Cell {
@@ -194,6 +220,25 @@ mod tests {
         assert_eq!(runs.len(), 1);
         assert_eq!(runs[0].text, "あい");
         assert_eq!(runs[0].start_col, 0);
+        assert_eq!(runs[0].wide, [0, 3]);
+    }
+
+    #[test]
+    fn test_wide_chars_among_narrow() {
+        let cells = vec![
+            make_cell("a", 0),
+            make_cell("漢", 0),
+            make_wide_spacer(0),
+            make_cell("b", 0),
+            // Cut off by the edge of the window
+            make_cell("字", 0),
+        ];
+        let highlights = HighlightMap::new();
+
+        let runs: Vec<_> = RunIterator::new(&cells, &highlights).collect();
+
+        assert_eq!(runs[0].text, "a漢b字");
+        assert_eq!(runs[0].wide, [1]);
     }
 
     #[test]
@@ -293,6 +338,7 @@ mod tests {
             text: String::new(),
             style: Style::Regular,
             highlight_id: 0,
+            wide: Vec::new(),
         };
         assert!(run.is_empty());
 
@@ -301,6 +347,7 @@ mod tests {
             text: "a".to_string(),
             style: Style::Regular,
             highlight_id: 0,
+            wide: Vec::new(),
         };
         assert!(!run.is_empty());
     }
@@ -323,6 +370,34 @@ mod tests {
         assert_eq!(runs[0].text, "-> ==");
     }
 
+    #[test]
+    fn test_long_runs_split() {
+        let mut cells: Vec<_> = (0..RUN_CHUNK_CELLS + 4)
+            .map(|_| make_cell("x", 0))
+            .collect();
+        cells[RUN_CHUNK_CELLS + 1] = make_cell(" ", 0);
+        cells.extend((0..3 * MAX_RUN_CELLS).map(|_| make_cell(";", 0)));
+        let highlights = HighlightMap::new();
+
+        let runs: Vec<_> = RunIterator::new(&cells, &highlights).collect();
+
+        let starts: Vec<_> = runs.iter().map(|run| run.start_col).collect();
+        let first = RUN_CHUNK_CELLS + 2;
+        assert_eq!(
+            starts,
+            [
+                0,
+                first,
+                first + MAX_RUN_CELLS,
+                first + 2 * MAX_RUN_CELLS,
+                first + 3 * MAX_RUN_CELLS
+            ]
+        );
+        assert!(runs[0].text.ends_with(' '));
+        let text: String = runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(text.len(), cells.len());
+    }
+
     #[test]
     fn test_mixed_content() {
         // Realistic example: keyword (bold) + space + identifier (regular) + operator
@@ -338,7 +338,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_shape_simple_text() {
         let collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
         let mut shaper = Shaper::new();
@@ -356,7 +356,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_shape_with_collection() {
         let mut collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
         let mut shaper = Shaper::new();
@@ -371,7 +371,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_shape_ligature_potential() {
         let collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
         let mut shaper = Shaper::new();
@@ -389,7 +389,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_shape_cluster_indices() {
         let collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
         let mut shaper = Shaper::new();
@@ -413,7 +413,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_shape_advances() {
         let collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
         let mut shaper = Shaper::new();
@@ -429,7 +429,7 @@ mod tests {
         assert!(shaped[0].x_advance > 0, "x_advance should be positive");
     }
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn test_shape_nerd_font_icons() {
         let mut collection = Collection::new("Menlo", 14.0, 72.0).unwrap();
         let mut shaper = Shaper::new();
@@ -3,11 +3,12 @@
 //! This cache stores the results of HarfBuzz text shaping to avoid redundant
 //! shaping calls when the same text content appears in multiple frames.
 //!
-//! The cache uses a content-based hash key (text + style + font) so that
-//! identical text runs at different screen positions share the same cache entry.
+//! The cache uses a content-based hash key (text + style + font generation)
+//! so that identical text runs at different screen positions share the same
+//! cache entry, and a row that scrolled keeps its glyphs.
 //! This is inspired by Ghostty's shaping cache design.
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 
 use super::shaper::ShapedGlyph;
@@ -17,16 +18,21 @@ use super::Style;
 /// ~2048 entries should cover most terminal content.
 const MAX_CACHE_ENTRIES: usize = 2048;
 
+/// Share of the entries dropped when the cache is full, the least
+/// recently used first.
+const EVICT_DIVISOR: usize = 4;
+
 /// Key for the shaping cache, based on run content hash.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ShapingCacheKey(u64);
 
 impl ShapingCacheKey {
-    /// Create a cache key from text content and style.
+    /// Create a cache key from text content, style and the generation of
+    /// the fonts it is shaped with.
     ///
     /// The key is position-independent: identical text with the same style
     /// will produce the same key regardless of where it appears on screen.
-    pub fn new(text: &str, style: Style) -> Self {
+    pub fn new(text: &str, style: Style, font_generation: u64) -> Self {
         use std::collections::hash_map::DefaultHasher;
 
         let mut hasher = DefaultHasher::new();
@@ -37,6 +43,9 @@ impl ShapingCacheKey {
         // Hash the style (bold/italic affects shaping)
         style.hash(&mut hasher);
 
+        // Runs shaped with a previous font never match again
+        font_generation.hash(&mut hasher);
+
         Self(hasher.finish())
     }
 }
@@ -46,18 +55,22 @@ impl ShapingCacheKey {
 pub struct CachedShapedRun {
     /// The shaped glyphs, with positions relative to run start.
     pub glyphs: Vec<ShapedGlyph>,
+    /// `ShapingCache::clock` when the run was last looked up.
+    last_used: u64,
 }
 
-/// LRU-style cache for shaped text runs.
+/// LRU cache for shaped text runs.
 ///
-/// Uses a simple strategy: when full, remove the oldest entries.
-/// This is simpler than true LRU but effective for terminal workloads
-/// where recent content is most likely to be reused.
+/// When full, the least recently used quarter of the entries goes at
+/// once, so a screen of new text doesn't pay for an eviction per run.
+/// The rows on screen, looked up every frame, stay.
 pub struct ShapingCache {
     /// Map from content hash to shaped glyphs.
     entries: HashMap<ShapingCacheKey, CachedShapedRun>,
-    /// Order of insertion for simple eviction (VecDeque for O(1) pop_front).
-    insertion_order: VecDeque<ShapingCacheKey>,
+    /// Counts lookups and inserts, to order the entries by use.
+    clock: u64,
+    hits: u64,
+    misses: u64,
 }
 
 impl ShapingCache {
@@ -65,46 +78,59 @@ impl ShapingCache {
     pub fn new() -> Self {
         Self {
             entries: HashMap::with_capacity(MAX_CACHE_ENTRIES),
-            insertion_order: VecDeque::with_capacity(MAX_CACHE_ENTRIES),
+            clock: 0,
+            hits: 0,
+            misses: 0,
         }
     }
 
-    /// Get glyphs by key. Returns None on cache miss.
-    pub fn get_glyphs(&self, key: ShapingCacheKey) -> Option<&[ShapedGlyph]> {
-        self.entries.get(&key).map(|e| e.glyphs.as_slice())
+    /// Get glyphs by key, marking them used. Returns None on cache miss.
+    pub fn get_glyphs(&mut self, key: ShapingCacheKey) -> Option<&[ShapedGlyph]> {
+        self.clock += 1;
+        match self.entries.get_mut(&key) {
+            Some(entry) => {
+                self.hits += 1;
+                entry.last_used = self.clock;
+                Some(entry.glyphs.as_slice())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
     }
 
     /// Insert a shaped run into the cache.
     pub fn insert(&mut self, key: ShapingCacheKey, glyphs: Vec<ShapedGlyph>) {
-        if let std::collections::hash_map::Entry::Occupied(mut e) = self.entries.entry(key) {
-            e.insert(CachedShapedRun { glyphs });
-            return;
+        self.clock += 1;
+        if !self.entries.contains_key(&key) && self.entries.len() >= MAX_CACHE_ENTRIES {
+            self.evict();
         }
-        self.insert_inner(key, glyphs);
+        let last_used = self.clock;
+        self.entries
+            .insert(key, CachedShapedRun { glyphs, last_used });
     }
 
-    /// Internal insert that handles eviction and assumes key is not present.
-    fn insert_inner(&mut self, key: ShapingCacheKey, glyphs: Vec<ShapedGlyph>) {
-        // Evict oldest entries if at capacity (O(1) with VecDeque)
-        while self.entries.len() >= MAX_CACHE_ENTRIES {
-            if let Some(old_key) = self.insertion_order.pop_front() {
-                self.entries.remove(&old_key);
-            } else {
-                break;
-            }
-        }
+    /// Drops the least recently used entries.
+    fn evict(&mut self) {
+        let mut stamps: Vec<u64> = self.entries.values().map(|e| e.last_used).collect();
+        let nth = (stamps.len() / EVICT_DIVISOR).max(1) - 1;
+        let (_, &mut oldest_kept, _) = stamps.select_nth_unstable(nth);
+        self.entries.retain(|_, e| e.last_used > oldest_kept);
+    }
 
-        self.entries.insert(key, CachedShapedRun { glyphs });
-        self.insertion_order.push_back(key);
+    /// Lifetime (hits, misses) of `get_glyphs`.
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
     }
 
     /// Clear the entire cache.
     ///
-    /// Should be called when fonts change, as cached shaping results
-    /// become invalid.
+    /// A font change needs no clearing: keys of the new generation never
+    /// meet the old entries, which age out.
+    #[cfg(test)]
     pub fn clear(&mut self) {
         self.entries.clear();
-        self.insertion_order.clear();
     }
 
     /// Returns the number of entries in the cache.
@@ -112,6 +138,11 @@ impl ShapingCache {
     pub fn len(&self) -> usize {
         self.entries.len()
     }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 }
 
 impl Default for ShapingCache {
@@ -141,7 +172,7 @@ mod tests {
     fn test_cache_hit() {
         let mut cache = ShapingCache::new();
 
-        let key = ShapingCacheKey::new("hello", Style::Regular);
+        let key = ShapingCacheKey::new("hello", Style::Regular, 0);
         let glyphs = vec![make_glyph(1, 100), make_glyph(2, 100)];
 
         cache.insert(key, glyphs.clone());
@@ -153,24 +184,24 @@ mod tests {
 
     #[test]
     fn test_cache_miss() {
-        let cache = ShapingCache::new();
+        let mut cache = ShapingCache::new();
 
-        let key = ShapingCacheKey::new("hello", Style::Regular);
+        let key = ShapingCacheKey::new("hello", Style::Regular, 0);
         assert!(cache.get_glyphs(key).is_none());
     }
 
     #[test]
     fn test_different_styles_different_keys() {
-        let key_regular = ShapingCacheKey::new("hello", Style::Regular);
-        let key_bold = ShapingCacheKey::new("hello", Style::Bold);
+        let key_regular = ShapingCacheKey::new("hello", Style::Regular, 0);
+        let key_bold = ShapingCacheKey::new("hello", Style::Bold, 0);
 
         assert_ne!(key_regular, key_bold);
     }
 
     #[test]
     fn test_same_content_same_key() {
-        let key1 = ShapingCacheKey::new("hello", Style::Regular);
-        let key2 = ShapingCacheKey::new("hello", Style::Regular);
+        let key1 = ShapingCacheKey::new("hello", Style::Regular, 0);
+        let key2 = ShapingCacheKey::new("hello", Style::Regular, 0);
 
         assert_eq!(key1, key2);
     }
@@ -182,7 +213,7 @@ mod tests {
         // Fill cache beyond capacity
         for i in 0..(MAX_CACHE_ENTRIES + 100) {
             let text = format!("text{}", i);
-            let key = ShapingCacheKey::new(&text, Style::Regular);
+            let key = ShapingCacheKey::new(&text, Style::Regular, 0);
             cache.insert(key, vec![make_glyph(i as u32, 100)]);
         }
 
@@ -190,27 +221,57 @@ mod tests {
         assert!(cache.len() <= MAX_CACHE_ENTRIES);
 
         // Oldest entries should be gone
-        let old_key = ShapingCacheKey::new("text0", Style::Regular);
+        let old_key = ShapingCacheKey::new("text0", Style::Regular, 0);
         assert!(cache.get_glyphs(old_key).is_none());
 
         // Newest entries should still be there
-        let new_key =
-            ShapingCacheKey::new(&format!("text{}", MAX_CACHE_ENTRIES + 99), Style::Regular);
+        let new_key = ShapingCacheKey::new(
+            &format!("text{}", MAX_CACHE_ENTRIES + 99),
+            Style::Regular,
+            0,
+        );
         assert!(cache.get_glyphs(new_key).is_some());
     }
 
+    #[test]
+    fn test_font_generations_different_keys() {
+        let key = ShapingCacheKey::new("hello", Style::Regular, 0);
+        assert_ne!(key, ShapingCacheKey::new("hello", Style::Regular, 1));
+    }
+
+    #[test]
+    fn test_eviction_keeps_recently_used() {
+        let mut cache = ShapingCache::new();
+        let key = |i: usize| ShapingCacheKey::new(&format!("text{}", i), Style::Regular, 0);
+        for i in 0..MAX_CACHE_ENTRIES {
+            cache.insert(key(i), vec![make_glyph(i as u32, 100)]);
+        }
+        // A row still on screen, looked up every frame
+        assert!(cache.get_glyphs(key(0)).is_some());
+
+        cache.insert(key(MAX_CACHE_ENTRIES), vec![make_glyph(0, 100)]);
+        assert_eq!(
+            cache.len(),
+            MAX_CACHE_ENTRIES - MAX_CACHE_ENTRIES / EVICT_DIVISOR + 1
+        );
+        assert!(cache.get_glyphs(key(0)).is_some());
+        assert!(cache.get_glyphs(key(1)).is_none());
+        assert!(cache.get_glyphs(key(MAX_CACHE_ENTRIES)).is_some());
+        assert_eq!(cache.stats(), (3, 1));
+    }
+
     #[test]
     fn test_clear() {
         let mut cache = ShapingCache::new();
 
-        let key = ShapingCacheKey::new("hello", Style::Regular);
+        let key = ShapingCacheKey::new("hello", Style::Regular, 0);
         cache.insert(key, vec![make_glyph(1, 100)]);
 
         assert_eq!(cache.len(), 1);
 
         cache.clear();
 
-        assert_eq!(cache.len(), 0);
+        assert!(cache.is_empty());
         assert!(cache.get_glyphs(key).is_none());
     }
 }
@@ -1,4 +1,6 @@
-use crate::config::FontSettings;
+use crate::config::{CellSpacing, FontSettings};
+use crate::constants::DEFAULT_FONT_SIZE;
+use crate::renderer::color::relative_luminance;
 
 /// RAII wrapper around a HarfBuzz `hb_font_t` raw pointer.
 ///
@@ -61,6 +63,31 @@ impl GlyphBuffer {
         matches!(self, GlyphBuffer::Rgba(_))
     }
 
+    /// Mean relative luminance of a color glyph's pixels, weighted by their
+    /// coverage. 0.0 for grayscale glyphs, which take the text color.
+    pub fn luminance(&self) -> f32 {
+        let GlyphBuffer::Rgba(pixels) = self else {
+            return 0.0;
+        };
+        let (mut sum, mut coverage) = (0.0, 0.0);
+        for pixel in pixels.chunks_exact(4) {
+            let alpha = f32::from(pixel[3]) / 255.0;
+            if alpha == 0.0 {
+                continue;
+            }
+            // Premultiplied
+            let color = [pixel[0], pixel[1], pixel[2], pixel[3]]
+                .map(|c| (f32::from(c) / 255.0 / alpha).min(1.0));
+            sum += relative_luminance(color) * alpha;
+            coverage += alpha;
+        }
+        if coverage == 0.0 {
+            0.0
+        } else {
+            sum / coverage
+        }
+    }
+
     #[allow(dead_code)]
     pub fn as_bytes(&self) -> &[u8] {
         match self {
@@ -72,16 +99,40 @@ impl GlyphBuffer {
 /// Font configuration with fallback chain.
 pub struct FontConfig {
     pub family: String,
+    /// Family for cells highlighted with `altfont`.
+    pub alt_family: Option<String>,
     pub size_pt: f32,
     pub scale_factor: f32,
+    pub emoji_scale: f32,
+    /// Draw a backing behind color glyphs that blend into the background.
+    pub emoji_backing: bool,
+    /// Extra pixels between rows, from Neovim's `linespace`.
+    pub linespace: f32,
+    pub line_height: Option<CellSpacing>,
+    pub letter_spacing: Option<CellSpacing>,
+    /// `[font.underline]` in pixels: a thickness over the font's, and how
+    /// far below where the font puts it the underline goes.
+    pub underline_thickness: Option<f32>,
+    pub underline_offset: f32,
 }
 
 impl FontConfig {
     pub fn new(settings: &FontSettings, scale_factor: f64) -> Self {
         Self {
             family: settings.family.clone().unwrap_or_else(default_font_family),
-            size_pt: settings.size.unwrap_or(14.0),
+            alt_family: settings.alt_family.clone(),
+            size_pt: settings.size.unwrap_or(DEFAULT_FONT_SIZE),
             scale_factor: scale_factor as f32,
+            emoji_scale: settings.emoji_scale.unwrap_or(1.0).clamp(0.1, 2.0),
+            emoji_backing: settings.emoji_backing,
+            linespace: (settings.linespace as f64 * scale_factor).round() as f32,
+            line_height: settings.line_height,
+            letter_spacing: settings.letter_spacing,
+            underline_thickness: settings
+                .underline
+                .thickness
+                .map(|thickness| thickness * scale_factor as f32),
+            underline_offset: settings.underline.position_offset * scale_factor as f32,
         }
     }
 
@@ -95,8 +146,16 @@ impl Default for FontConfig {
     fn default() -> Self {
         Self {
             family: default_font_family(),
+            alt_family: None,
             size_pt: 14.0,
             scale_factor: 1.0,
+            emoji_scale: 1.0,
+            emoji_backing: false,
+            linespace: 0.0,
+            line_height: None,
+            letter_spacing: None,
+            underline_thickness: None,
+            underline_offset: 0.0,
         }
     }
 }
@@ -129,6 +188,45 @@ pub struct FaceMetrics {
     pub strikeout_thickness: f32,
 }
 
+impl FaceMetrics {
+    /// Grows the cell by `extra` pixels, split above and below the text so
+    /// it stays centered. Negative values shrink it, down to one pixel.
+    pub fn with_linespace(mut self, extra: f32) -> Self {
+        let extra = extra.max(1.0 - self.cell_height);
+        let above = (extra / 2.0).floor();
+        self.cell_height += extra;
+        self.ascent += above;
+        self.descent = self.descent.abs() + extra - above;
+        self
+    }
+
+    /// Widens the cell by `extra` pixels, or narrows it down to one pixel.
+    /// Glyphs stay at the cell's left edge.
+    pub fn with_letter_spacing(mut self, extra: f32) -> Self {
+        self.cell_width = (self.cell_width + extra).max(1.0);
+        self
+    }
+
+    /// The cell as `config` sizes it: `line_height` and `linespace` add
+    /// rows' worth of space, `letter_spacing` columns' worth. Underlines
+    /// move and thicken as `[font.underline]` says.
+    pub fn spaced(mut self, config: &FontConfig) -> Self {
+        if let Some(thickness) = config.underline_thickness {
+            self.underline_thickness = thickness;
+        }
+        self.underline_position -= config.underline_offset;
+        let scale_factor = f64::from(config.scale_factor);
+        let line_height = config
+            .line_height
+            .map_or(0.0, |spacing| spacing.extra(self.cell_height, scale_factor));
+        let letter_spacing = config
+            .letter_spacing
+            .map_or(0.0, |spacing| spacing.extra(self.cell_width, scale_factor));
+        self.with_linespace(line_height + config.linespace)
+            .with_letter_spacing(letter_spacing)
+    }
+}
+
 impl Default for FaceMetrics {
     fn default() -> Self {
         Self {
@@ -160,3 +258,64 @@ pub enum FaceError {
     #[error("Platform not implemented")]
     NotImplemented,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UnderlineSettings;
+
+    #[test]
+    fn test_glyph_luminance() {
+        // Premultiplied white at half coverage, next to a clear pixel
+        let white = GlyphBuffer::Rgba(vec![128, 128, 128, 128, 0, 0, 0, 0]);
+        assert!((white.luminance() - 1.0).abs() < 0.01);
+        let red = GlyphBuffer::Rgba(vec![255, 0, 0, 255]);
+        assert!((red.luminance() - 0.2126).abs() < 0.01);
+        assert_eq!(GlyphBuffer::Rgba(vec![0, 0, 0, 0]).luminance(), 0.0);
+        assert_eq!(GlyphBuffer::Rgb(vec![255, 255, 255]).luminance(), 0.0);
+    }
+
+    #[test]
+    fn test_with_linespace() {
+        let metrics = FaceMetrics::default().with_linespace(5.0);
+        assert_eq!(metrics.cell_height, 21.0);
+        assert_eq!((metrics.ascent, metrics.descent), (14.0, 7.0));
+        assert_eq!(metrics.cell_width, 8.0);
+
+        let metrics = FaceMetrics::default().with_linespace(-2.0);
+        assert_eq!(metrics.cell_height, 14.0);
+        assert_eq!((metrics.ascent, metrics.descent), (11.0, 3.0));
+
+        assert_eq!(
+            FaceMetrics::default().with_linespace(-40.0).cell_height,
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_spaced_metrics() {
+        let config = FontConfig {
+            linespace: 1.0,
+            line_height: Some(CellSpacing::Factor(1.25)),
+            letter_spacing: Some(CellSpacing::Pixels(-2.0)),
+            ..FontConfig::default()
+        };
+        let metrics = FaceMetrics::default().spaced(&config);
+        assert_eq!((metrics.cell_width, metrics.cell_height), (6.0, 21.0));
+        assert_eq!(metrics.ascent, 14.0);
+
+        let metrics = FaceMetrics::default().spaced(&FontConfig::default());
+        assert_eq!((metrics.cell_width, metrics.cell_height), (8.0, 16.0));
+
+        let settings = FontSettings {
+            underline: UnderlineSettings {
+                thickness: Some(1.5),
+                position_offset: 1.0,
+            },
+            ..FontSettings::default()
+        };
+        let metrics = FaceMetrics::default().spaced(&FontConfig::new(&settings, 2.0));
+        assert_eq!(metrics.underline_thickness, 3.0);
+        assert_eq!(metrics.underline_position, 0.0);
+    }
+}
@@ -1,5 +1,7 @@
 #![allow(clippy::too_many_arguments)]
 
+use std::ops::Range;
+
 use crate::editor::{CursorShape, UnderlineStyle};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -10,6 +12,15 @@ pub struct CursorGeometry {
     pub height: f32,
 }
 
+/// Region of the surface a frame is limited to, in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DecorationGeometry {
     pub lines: Vec<DecorationLine>,
@@ -23,6 +34,66 @@ pub struct DecorationLine {
     pub height: f32,
 }
 
+/// Lines drawn over a cell: underline in the special color, strikethrough
+/// in the foreground color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decoration {
+    pub underline_style: UnderlineStyle,
+    pub has_strikethrough: bool,
+    pub underline_color: [f32; 4],
+    pub strikethrough_color: [f32; 4],
+}
+
+/// A run of adjacent cells in a row sharing one decoration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecorationSpan {
+    pub start_col: usize,
+    pub len: usize,
+    pub decoration: Decoration,
+}
+
+/// Merges a row's cell decorations, fed left to right, into spans so a
+/// decorated word gets one quad per line instead of one per cell. Besides
+/// the instance count, this avoids seams between cells at fractional scale
+/// factors.
+#[derive(Debug, Default)]
+pub struct DecorationMerger {
+    pending: Option<DecorationSpan>,
+}
+
+impl DecorationMerger {
+    /// Adds the decoration of the cell at `col`. Returns the previous span
+    /// once the cell doesn't continue it.
+    pub fn push(&mut self, col: usize, decoration: Option<Decoration>) -> Option<DecorationSpan> {
+        if let (Some(span), Some(decoration)) = (&mut self.pending, &decoration) {
+            if span.decoration == *decoration && span.start_col + span.len == col {
+                span.len += 1;
+                return None;
+            }
+        }
+        let finished = self.pending.take();
+        self.pending = decoration.map(|decoration| DecorationSpan {
+            start_col: col,
+            len: 1,
+            decoration,
+        });
+        finished
+    }
+
+    /// Ends the row, returning the span still open.
+    pub fn finish(&mut self) -> Option<DecorationSpan> {
+        self.pending.take()
+    }
+}
+
+/// Lines are at least this many device pixels thick, so thin ones from
+/// small fonts don't vanish.
+const MIN_DECORATION_THICKNESS: f32 = 1.0;
+
+/// Geometry is in device pixels. Each line is snapped to whole ones on
+/// the screen, so it is equally thick on every row and stays sharp while
+/// the rows scroll smoothly, at most half a pixel off where the font
+/// puts it.
 pub fn compute_decoration_geometry(
     x: f32,
     y: f32,
@@ -37,10 +108,13 @@ pub fn compute_decoration_geometry(
     has_strikethrough: bool,
 ) -> DecorationGeometry {
     let mut lines = Vec::new();
-    let baseline_y = y + cell_height - descent.abs();
+    let baseline = cell_height - descent.abs();
+    let snap = |offset: f32| (y + offset).round();
+    let thickness = |thickness: f32| thickness.round().max(MIN_DECORATION_THICKNESS);
 
     if underline_style != UnderlineStyle::None {
-        let underline_y = baseline_y - underline_pos;
+        let underline_y = baseline - underline_pos;
+        let height = thickness(underline_thickness);
 
         match underline_style {
             UnderlineStyle::Single
@@ -49,24 +123,24 @@ pub fn compute_decoration_geometry(
             | UnderlineStyle::Dashed => {
                 lines.push(DecorationLine {
                     x,
-                    y: underline_y,
+                    y: snap(underline_y),
                     width: cell_width,
-                    height: underline_thickness,
+                    height,
                 });
             }
             UnderlineStyle::Double => {
-                let gap = underline_thickness;
+                let gap = height;
                 lines.push(DecorationLine {
                     x,
-                    y: underline_y - gap,
+                    y: snap(underline_y) - gap,
                     width: cell_width,
-                    height: underline_thickness,
+                    height,
                 });
                 lines.push(DecorationLine {
                     x,
-                    y: underline_y + gap,
+                    y: snap(underline_y) + gap,
                     width: cell_width,
-                    height: underline_thickness,
+                    height,
                 });
             }
             UnderlineStyle::None => {}
@@ -74,18 +148,27 @@ pub fn compute_decoration_geometry(
     }
 
     if has_strikethrough {
-        let strikeout_y = baseline_y - strikeout_pos;
+        let strikeout_y = baseline - strikeout_pos;
         lines.push(DecorationLine {
             x,
-            y: strikeout_y,
+            y: snap(strikeout_y),
             width: cell_width,
-            height: strikeout_thickness,
+            height: thickness(strikeout_thickness),
         });
     }
 
     DecorationGeometry { lines }
 }
 
+/// Places a glyph `advance` wide in a character `cells` cells wide:
+/// centered, so a CJK glyph from a fallback font sits in the middle of its
+/// two cells. Returns how far right of the first cell it goes, and how far
+/// the next character is.
+pub fn center_in_cells(advance: f32, cells: usize, cell_width: f32) -> (f32, f32) {
+    let width = cells as f32 * cell_width;
+    (((width - advance) / 2.0).max(0.0), width)
+}
+
 pub fn compute_cursor_geometry(
     cursor_shape: CursorShape,
     row: usize,
@@ -135,10 +218,185 @@ pub fn compute_cursor_geometry(
     }
 }
 
+/// The edges of `cursor`, `thickness` wide and inside it: the hollow block
+/// drawn while the window is unfocused.
+pub fn compute_cursor_outline(cursor: &CursorGeometry, thickness: f32) -> [CursorGeometry; 4] {
+    let t = thickness
+        .max(1.0)
+        .min(cursor.width / 2.0)
+        .min(cursor.height / 2.0);
+    let edge = |x, y, width, height| CursorGeometry {
+        x,
+        y,
+        width,
+        height,
+    };
+    let inner = cursor.height - 2.0 * t;
+    [
+        edge(cursor.x, cursor.y, cursor.width, t),
+        edge(cursor.x, cursor.y + cursor.height - t, cursor.width, t),
+        edge(cursor.x, cursor.y + t, t, inner),
+        edge(cursor.x + cursor.width - t, cursor.y + t, t, inner),
+    ]
+}
+
+/// Full-width surface region covering the damaged main grid `rows`. It is
+/// widened by a row on each side, since glyph ink and undercurls can spill
+/// into the neighbouring rows. Clipped to the surface, which can leave it
+/// empty.
+pub fn compute_damage_rect(
+    rows: Range<usize>,
+    cell_height: f32,
+    y_offset: f32,
+    surface_width: u32,
+    surface_height: u32,
+) -> ScissorRect {
+    let top = y_offset + rows.start.saturating_sub(1) as f32 * cell_height;
+    let bottom = y_offset + (rows.end + 1) as f32 * cell_height;
+    let top = (top.floor().max(0.0) as u32).min(surface_height);
+    let bottom = (bottom.ceil().max(0.0) as u32).min(surface_height);
+    ScissorRect {
+        x: 0,
+        y: top,
+        width: surface_width,
+        height: bottom.saturating_sub(top),
+    }
+}
+
+/// How a box-drawing character strokes from the middle of its cell towards
+/// one edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stroke {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+/// Strokes of the box-drawing characters float borders are made of, as
+/// (up, right, down, left).
+fn box_strokes(c: char) -> Option<[Stroke; 4]> {
+    use Stroke::{Double as D, Heavy as H, Light as L, None as N};
+    Some(match c {
+        '─' => [N, L, N, L],
+        '│' => [L, N, L, N],
+        '┌' | '╭' => [N, L, L, N],
+        '┐' | '╮' => [N, N, L, L],
+        '└' | '╰' => [L, L, N, N],
+        '┘' | '╯' => [L, N, N, L],
+        '├' => [L, L, L, N],
+        '┤' => [L, N, L, L],
+        '┬' => [N, L, L, L],
+        '┴' => [L, L, N, L],
+        '┼' => [L, L, L, L],
+        '━' => [N, H, N, H],
+        '┃' => [H, N, H, N],
+        '┏' => [N, H, H, N],
+        '┓' => [N, N, H, H],
+        '┗' => [H, H, N, N],
+        '┛' => [H, N, N, H],
+        '═' => [N, D, N, D],
+        '║' => [D, N, D, N],
+        '╔' => [N, D, D, N],
+        '╗' => [N, N, D, D],
+        '╚' => [D, D, N, N],
+        '╝' => [D, N, N, D],
+        _ => return None,
+    })
+}
+
+/// Lines that draw box-drawing character `c` in the cell at (x, y), meeting
+/// the lines of neighbouring cells edge to edge, which glyphs rarely do.
+/// Light lines are `thickness` thick, heavy ones twice that, and double
+/// ones two light lines a line apart. Rounded corners are drawn square.
+/// `None` if `c` isn't a border character.
+pub fn compute_box_geometry(
+    c: char,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    thickness: f32,
+) -> Option<Vec<DecorationLine>> {
+    let [up, right, down, left] = box_strokes(c)?;
+    let thin = thickness.round().max(1.0);
+    let line = |x, y, width, height| DecorationLine {
+        x,
+        y,
+        width,
+        height,
+    };
+    // Where a line of width `size` starts to sit centered.
+    let center = |origin: f32, extent: f32, size: f32| origin + ((extent - size) / 2.0).floor();
+    // Double lines turn into a double arm at right angles so that the
+    // corner nests: offset `d` rounds the corner outside or inside.
+    let turn = |d: f32, towards: Stroke, away: Stroke| match (towards, away) {
+        (Stroke::Double, _) => d,
+        (_, Stroke::Double) => -d,
+        _ => 0.0,
+    };
+
+    let mut lines = Vec::new();
+    for (stroke, towards_end, horizontal) in [
+        (right, true, true),
+        (left, false, true),
+        (down, true, false),
+        (up, false, false),
+    ] {
+        let (origin, extent, cross_origin, cross_extent) = if horizontal {
+            (x, width, y, height)
+        } else {
+            (y, height, x, width)
+        };
+        // The arms at right angles to this one, for double corners.
+        let (towards, away) = if horizontal {
+            (down, up)
+        } else {
+            (right, left)
+        };
+        let offsets: &[f32] = match stroke {
+            Stroke::None => &[],
+            Stroke::Double => &[-thin, thin],
+            _ => &[0.0],
+        };
+        let size = if stroke == Stroke::Heavy {
+            thin * 2.0
+        } else {
+            thin
+        };
+
+        let mid = center(origin, extent, size);
+        for &d in offsets {
+            let bend = turn(d, towards, away);
+            let (start, end) = if towards_end {
+                (mid + bend, origin + extent)
+            } else {
+                (origin, mid - bend + size)
+            };
+            let cross = center(cross_origin, cross_extent, size) + d;
+            lines.push(if horizontal {
+                line(start, cross, end - start, size)
+            } else {
+                line(cross, start, size, end - start)
+            });
+        }
+    }
+    Some(lines)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_center_in_cells() {
+        // A 16px glyph in two 10px cells
+        assert_eq!(center_in_cells(16.0, 2, 10.0), (2.0, 20.0));
+        assert_eq!(center_in_cells(20.0, 2, 10.0), (0.0, 20.0));
+        // Too wide to center starts at the cell still.
+        assert_eq!(center_in_cells(24.0, 2, 10.0), (0.0, 20.0));
+    }
+
     #[test]
     fn test_cursor_geometry_block() {
         let geom = compute_cursor_geometry(CursorShape::Block, 5, 10, 10.0, 20.0, 0);
@@ -189,6 +447,24 @@ mod tests {
         assert!(geom.height >= 1.0);
     }
 
+    #[test]
+    fn test_cursor_outline() {
+        let geom = compute_cursor_geometry(CursorShape::Block, 1, 2, 10.0, 20.0, 0);
+        let [top, bottom, left, right] = compute_cursor_outline(&geom, 2.0);
+        assert_eq!(
+            (top.x, top.y, top.width, top.height),
+            (20.0, 20.0, 10.0, 2.0)
+        );
+        assert_eq!(bottom.y, 38.0);
+        assert_eq!((left.x, left.y, left.height), (20.0, 22.0, 16.0));
+        assert_eq!(right.x, 28.0);
+
+        // Never thicker than half the cursor, so the edges don't overlap.
+        let bar = compute_cursor_geometry(CursorShape::Vertical, 0, 0, 10.0, 20.0, 10);
+        let [top, ..] = compute_cursor_outline(&bar, 4.0);
+        assert_eq!(top.height, 0.5);
+    }
+
     #[test]
     fn test_decoration_geometry_single_underline() {
         let geom = compute_decoration_geometry(
@@ -251,7 +527,38 @@ mod tests {
         let line = &geom.lines[0];
         assert_eq!(line.x, 5.0);
         assert_eq!(line.width, 10.0);
-        assert_eq!(line.height, 1.5);
+        // Rounded to whole device pixels
+        assert_eq!(line.height, 2.0);
+    }
+
+    #[test]
+    fn test_decoration_geometry_snaps_to_pixels() {
+        let underline = |y: f32, thickness: f32| {
+            compute_decoration_geometry(
+                0.0,
+                y,
+                10.0,
+                20.0,
+                4.3,
+                -1.4,
+                thickness,
+                8.0,
+                1.0,
+                UnderlineStyle::Single,
+                false,
+            )
+            .lines
+            .remove(0)
+        };
+        // Never thinner than a pixel, nor between two
+        assert_eq!(underline(0.0, 0.3).height, 1.0);
+        assert_eq!(underline(0.0, 1.4).height, 1.0);
+        assert_eq!(underline(0.0, 1.6).height, 2.0);
+        assert_eq!(underline(0.0, 1.0).y, 17.0);
+        // On whole device pixels, scrolled or not
+        assert_eq!(underline(20.0, 1.0).y, 37.0);
+        assert_eq!(underline(20.25, 1.0).y, 37.0);
+        assert_eq!(underline(20.75, 1.0).y, 38.0);
     }
 
     #[test]
@@ -289,4 +596,116 @@ mod tests {
         );
         assert!(geom.lines.is_empty());
     }
+
+    fn decoration(style: UnderlineStyle, color: [f32; 4]) -> Option<Decoration> {
+        Some(Decoration {
+            underline_style: style,
+            has_strikethrough: false,
+            underline_color: color,
+            strikethrough_color: [1.0; 4],
+        })
+    }
+
+    #[test]
+    fn test_decoration_merger_joins_adjacent_cells() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let mut merger = DecorationMerger::default();
+
+        assert_eq!(merger.push(0, None), None);
+        for col in 1..4 {
+            assert_eq!(
+                merger.push(col, decoration(UnderlineStyle::Curl, red)),
+                None
+            );
+        }
+        let span = merger.push(4, None).unwrap();
+        assert_eq!((span.start_col, span.len), (1, 3));
+        assert_eq!(span.decoration.underline_color, red);
+        assert_eq!(merger.finish(), None);
+    }
+
+    #[test]
+    fn test_decoration_merger_splits_on_change() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let blue = [0.0, 0.0, 1.0, 1.0];
+        let mut merger = DecorationMerger::default();
+
+        merger.push(0, decoration(UnderlineStyle::Single, red));
+        merger.push(1, decoration(UnderlineStyle::Single, red));
+        let first = merger
+            .push(2, decoration(UnderlineStyle::Single, blue))
+            .unwrap();
+        assert_eq!((first.start_col, first.len), (0, 2));
+
+        let second = merger
+            .push(3, decoration(UnderlineStyle::Double, blue))
+            .unwrap();
+        assert_eq!((second.start_col, second.len), (2, 1));
+
+        let last = merger.finish().unwrap();
+        assert_eq!((last.start_col, last.len), (3, 1));
+        assert_eq!(last.decoration.underline_style, UnderlineStyle::Double);
+    }
+
+    #[test]
+    fn test_damage_rect_widens_and_clips() {
+        let rect = compute_damage_rect(2..3, 10.0, 5.0, 800, 600);
+        assert_eq!(
+            rect,
+            ScissorRect {
+                x: 0,
+                y: 15,
+                width: 800,
+                height: 30,
+            }
+        );
+
+        let first_row = compute_damage_rect(0..1, 10.0, 5.5, 800, 600);
+        assert_eq!((first_row.y, first_row.height), (5, 21));
+
+        let clipped = compute_damage_rect(58..60, 10.0, 0.0, 800, 600);
+        assert_eq!((clipped.y, clipped.height), (570, 30));
+
+        assert_eq!(compute_damage_rect(70..71, 10.0, 0.0, 800, 600).height, 0);
+    }
+
+    fn rects(c: char) -> Vec<(f32, f32, f32, f32)> {
+        compute_box_geometry(c, 0.0, 0.0, 10.0, 20.0, 1.0)
+            .unwrap()
+            .iter()
+            .map(|l| (l.x, l.y, l.width, l.height))
+            .collect()
+    }
+
+    #[test]
+    fn test_box_geometry_light() {
+        assert_eq!(rects('─'), [(4.0, 9.0, 6.0, 1.0), (0.0, 9.0, 5.0, 1.0)]);
+        // A corner's arms meet in the middle of the cell
+        assert_eq!(rects('╭'), [(4.0, 9.0, 6.0, 1.0), (4.0, 9.0, 1.0, 11.0)]);
+        assert_eq!(rects('┃'), [(4.0, 9.0, 2.0, 11.0), (4.0, 0.0, 2.0, 11.0)]);
+        assert!(compute_box_geometry('x', 0.0, 0.0, 10.0, 20.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn test_box_geometry_double_corner_nests() {
+        // Outer lines (top, left) reach past the inner ones by a line's gap
+        assert_eq!(
+            rects('╔'),
+            [
+                (3.0, 8.0, 7.0, 1.0),
+                (5.0, 10.0, 5.0, 1.0),
+                (3.0, 8.0, 1.0, 12.0),
+                (5.0, 10.0, 1.0, 10.0),
+            ]
+        );
+        assert_eq!(
+            rects('╝'),
+            [
+                (0.0, 8.0, 4.0, 1.0),
+                (0.0, 10.0, 6.0, 1.0),
+                (3.0, 0.0, 1.0, 9.0),
+                (5.0, 0.0, 1.0, 11.0),
+            ]
+        );
+    }
 }
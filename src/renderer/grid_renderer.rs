@@ -1,14 +1,30 @@
-use super::atlas::GlyphAtlas;
-use super::batch::RenderBatcher;
-use super::color::u32_to_linear_rgba;
+use std::borrow::Cow;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use super::atlas::{ColorGlyphBox, GlyphAtlas};
+use super::batch::{GridPlacement, Layer, RenderBatcher};
+use super::color::{
+    blend_alpha, color_glyph_backing, ensure_contrast, premultiply, u32_to_linear_rgba,
+};
 use super::font::{
     Collection, FaceMetrics, FontConfig, GlyphCacheKey, RunIterator, ShapedCachedGlyph,
     ShapedGlyph, Shaper, ShapingCache, ShapingCacheKey, Style, TextRun,
 };
-use super::geometry::{compute_cursor_geometry, compute_decoration_geometry};
+use super::geometry::{
+    center_in_cells, compute_box_geometry, compute_cursor_geometry, compute_cursor_outline,
+    compute_decoration_geometry, Decoration, DecorationMerger, DecorationSpan,
+};
+use super::overlay::{CellRect, Element, ListView, OverlayStyle, Scene};
+use super::pipeline::QuadInstance;
+use super::row_cache::{row_key, CachedRow, RowCache};
 use super::GpuContext;
-use crate::config::FontSettings;
-use crate::editor::{CursorShape, EditorState, HighlightAttributes, StyleFlags, UnderlineStyle};
+use crate::config::{CursorSettings, CursorShapeSetting, FontSettings, UnfocusedCursor};
+use crate::editor::{
+    CrashChoice, CursorShape, Dashboard, EditorState, Grid, HighlightAttributes, Message,
+    ReportChoice, Scrollback, Splash, StyleFlags, StyledChunk, SwapChoice, UnderlineStyle,
+};
+use crate::layout::WindowLayout;
 
 #[cfg(feature = "perf-stats")]
 use std::time::{Duration, Instant};
@@ -25,22 +41,62 @@ fn compute_dpi(scale_factor: f64) -> f32 {
     BASE_DPI * scale_factor as f32
 }
 
+/// Points `altfont` cells at `font.alt_family`. A family that fails to
+/// load leaves them in the primary one.
+fn set_alt_family(collection: &mut Collection, font_config: &FontConfig) {
+    if let Some(family) = &font_config.alt_family {
+        if let Err(e) = collection.set_alt_family(family) {
+            log::warn!("Failed to load alt font {:?}: {}", family, e);
+        }
+    }
+}
+
+/// A modal question for `GridRenderer::paint_prompt`.
+struct Prompt<'a> {
+    title: &'static str,
+    /// Highlight group the title is drawn with.
+    title_group: &'static str,
+    header: &'a [String],
+    lines: &'a [String],
+    selected: usize,
+}
+
 /// Parameters for rendering operations, grouped to reduce function argument count.
 #[derive(Clone, Copy)]
 pub struct RenderParams {
     pub default_bg: [f32; 4],
     pub default_fg: [f32; 4],
+    /// Origin of the grid in the window, in physical pixels.
     pub x_offset: f32,
     pub y_offset: f32,
+    /// Height of the strip above the grid that holds the tab bar.
+    pub top_strip: f32,
+    /// Window size to paint with `default_bg` first. Set when the frame is
+    /// drawn over the previous one instead of onto a cleared surface.
+    pub backdrop: Option<(f32, f32)>,
+    /// Background of the startup splash, `default_bg` unless configured.
+    pub splash_bg: [f32; 4],
 }
 
 impl RenderParams {
-    pub fn new(default_bg: [f32; 4], default_fg: [f32; 4], x_offset: f32, y_offset: f32) -> Self {
+    /// The same parameters with the grid shifted down by `offset` pixels.
+    pub fn scrolled(self, offset: f32) -> Self {
+        Self {
+            y_offset: self.y_offset + offset,
+            ..self
+        }
+    }
+
+    pub fn new(default_bg: [f32; 4], default_fg: [f32; 4], layout: &WindowLayout) -> Self {
+        let grid = layout.grid_rect(0, 0);
         Self {
             default_bg,
             default_fg,
-            x_offset,
-            y_offset,
+            x_offset: grid.x as f32,
+            y_offset: grid.y as f32,
+            top_strip: layout.padding_top as f32,
+            backdrop: None,
+            splash_bg: default_bg,
         }
     }
 }
@@ -66,14 +122,25 @@ fn compute_glyph_y(
     }
 }
 
+fn color_glyph_box(metrics: &FaceMetrics, baseline_offset: f32, scale: f32) -> ColorGlyphBox {
+    ColorGlyphBox {
+        cell_width: metrics.cell_width,
+        cell_height: metrics.cell_height,
+        baseline: baseline_offset,
+        scale,
+    }
+}
+
 /// Compute the X advance for a glyph, clamping fallback fonts to cell width.
+/// The primary font's advances are scaled by `advance_scale`, the cell
+/// width over the font's own, so `letter_spacing` carries through runs.
 #[inline]
-fn compute_glyph_advance(glyph: &ShapedGlyph, cell_width: f32) -> f32 {
+fn compute_glyph_advance(glyph: &ShapedGlyph, cell_width: f32, advance_scale: f32) -> f32 {
     let advance = glyph.x_advance as f32 / HARFBUZZ_SCALE;
     if glyph.font_index.idx > 0 {
         advance.min(cell_width)
     } else {
-        advance
+        advance * advance_scale
     }
 }
 
@@ -90,6 +157,8 @@ pub struct PrepareStats {
     pub glyph_cache_misses: usize,
     pub shaping_cache_hits: usize,
     pub shaping_cache_misses: usize,
+    /// Rows copied from the last frame rather than built.
+    pub rows_cached: usize,
     pub time_backgrounds: Duration,
     pub time_shaping: Duration,
     pub time_glyph_lookup: Duration,
@@ -114,10 +183,28 @@ pub struct GridRenderer {
     /// Distance from the top of the cell to the baseline.
     /// Computed as: ascent + (line_gap / 2) to center text vertically.
     baseline_offset: f32,
+    /// Cell width over the primary font's advance, from `letter_spacing`.
+    advance_scale: f32,
+    /// `[font] emoji_backing`.
+    emoji_backing: bool,
     /// Inverse of atlas size for UV coordinate calculation (avoids division per glyph).
     atlas_size_inv: f32,
     /// Tracks atlas generation to detect resizes.
     atlas_generation: u64,
+    /// Bumped by `update_font`, so runs shaped with the old font are
+    /// shaped again.
+    font_generation: u64,
+    /// The main grid's rows as batched for the last frame.
+    row_cache: RowCache,
+    /// Whether rows are taken from `row_cache` or always built again.
+    reuse_rows: bool,
+    /// Lines of `:GuiPerfOverlay`, empty while it is off.
+    perf_overlay: Vec<String>,
+    /// `[cursor]`.
+    cursor_settings: CursorSettings,
+    /// The float batch's glyphs for the cursor's row, while the cursor is
+    /// in a float.
+    cursor_float_glyphs: Range<usize>,
 }
 
 impl GridRenderer {
@@ -129,15 +216,22 @@ impl GridRenderer {
         let font_config = FontConfig::new(font_settings, scale_factor);
         let dpi = compute_dpi(scale_factor);
         let mut collection = Collection::new(&font_config.family, font_config.size_pt, dpi)?;
+        set_alt_family(&mut collection, &font_config);
         let shaper = Shaper::new();
 
-        let metrics = *collection.metrics();
+        let metrics = collection.metrics().spaced(&font_config);
+        let advance_scale = metrics.cell_width / collection.metrics().cell_width;
         // Compute baseline offset from top of cell.
         // We split the line_gap in half to center text vertically within the cell,
         // matching Ghostty's approach.
         let baseline_offset = metrics.ascent + (metrics.line_gap / 2.0);
 
         let mut atlas = GlyphAtlas::new(ctx);
+        atlas.set_color_glyph_box(color_glyph_box(
+            &metrics,
+            baseline_offset,
+            font_config.emoji_scale,
+        ));
         atlas.prepopulate_ascii(ctx, &mut collection, Style::Regular);
         let atlas_size_inv = 1.0 / atlas.atlas_size() as f32;
 
@@ -152,8 +246,16 @@ impl GridRenderer {
             glyph_scratch: Vec::with_capacity(64),
             metrics,
             baseline_offset,
+            advance_scale,
+            emoji_backing: font_config.emoji_backing,
             atlas_size_inv,
             atlas_generation: 0,
+            font_generation: 0,
+            row_cache: RowCache::default(),
+            reuse_rows: true,
+            perf_overlay: Vec::new(),
+            cursor_settings: CursorSettings::default(),
+            cursor_float_glyphs: 0..0,
         })
     }
 
@@ -166,19 +268,27 @@ impl GridRenderer {
         let font_config = FontConfig::new(font_settings, scale_factor);
         let dpi = compute_dpi(scale_factor);
         let mut collection = Collection::new(&font_config.family, font_config.size_pt, dpi)?;
+        set_alt_family(&mut collection, &font_config);
         self.shaper = Shaper::new();
 
-        self.metrics = *collection.metrics();
+        self.metrics = collection.metrics().spaced(&font_config);
+        self.advance_scale = self.metrics.cell_width / collection.metrics().cell_width;
         self.baseline_offset = self.metrics.ascent + (self.metrics.line_gap / 2.0);
+        self.emoji_backing = font_config.emoji_backing;
 
         self.atlas.clear(ctx);
+        self.atlas.set_color_glyph_box(color_glyph_box(
+            &self.metrics,
+            self.baseline_offset,
+            font_config.emoji_scale,
+        ));
         self.atlas
             .prepopulate_ascii(ctx, &mut collection, Style::Regular);
         self.atlas_size_inv = 1.0 / self.atlas.atlas_size() as f32;
         self.collection = collection;
 
-        // Clear shaping cache - cached results are invalid with new font
-        self.shaping_cache.clear();
+        // Cached shaping results are invalid with the new font
+        self.font_generation += 1;
 
         Ok(())
     }
@@ -187,10 +297,37 @@ impl GridRenderer {
         (self.metrics.cell_width, self.metrics.cell_height)
     }
 
+    pub fn baseline_offset(&self) -> f32 {
+        self.baseline_offset
+    }
+
     pub fn atlas(&self) -> &GlyphAtlas {
         &self.atlas
     }
 
+    /// Lifetime (hits, misses) of the shaping cache, reported by
+    /// `:GuiStats`.
+    pub fn shaping_cache_stats(&self) -> (u64, u64) {
+        self.shaping_cache.stats()
+    }
+
+    /// Whether unchanged rows are taken from the row cache, or shaped and
+    /// batched again every frame (`:GuiSet renderer=naive`).
+    pub fn set_reuse_rows(&mut self, reuse_rows: bool) {
+        self.reuse_rows = reuse_rows;
+    }
+
+    /// `[cursor]`, for the cursor drawn from the next frame on.
+    pub fn set_cursor_settings(&mut self, settings: CursorSettings) {
+        self.cursor_settings = settings;
+    }
+
+    /// Sets the lines `:GuiPerfOverlay` shows in the next frame; none
+    /// hide it.
+    pub fn set_perf_overlay(&mut self, lines: Vec<String>) {
+        self.perf_overlay = lines;
+    }
+
     #[cfg(feature = "perf-stats")]
     pub fn prepare(
         &mut self,
@@ -198,21 +335,87 @@ impl GridRenderer {
         state: &EditorState,
         params: RenderParams,
     ) -> PrepareStats {
-        self.batcher.clear();
-        let stats = self.prepare_grid_cells(ctx, state, params);
-        self.prepare_cursor(ctx, state, params);
+        self.atlas.begin_frame();
+        let stats = loop {
+            let generation = self.atlas.generation();
+            let evictions = self.atlas.evictions();
+            self.batcher.clear();
+            if let Some((width, height)) = params.backdrop {
+                self.batcher
+                    .push_background(0.0, 0.0, width, height, params.default_bg);
+            }
+            let grid_params = params.scrolled(state.scroll_offset);
+            let stats = self.prepare_grid_cells(ctx, state, grid_params);
+            self.prepare_floats(ctx, state, grid_params);
+            self.prepare_message_separator(state, grid_params);
+            self.prepare_invisible(ctx, state, grid_params);
+            self.prepare_cursor(ctx, state, grid_params);
+            self.prepare_dashboard(ctx, state, params);
+            self.prepare_splash(ctx, state, params);
+            self.prepare_file_hover(ctx, state, params);
+            self.prepare_zoom_indicator(ctx, state, params);
+            self.prepare_perf_overlay(ctx, state, params);
+            self.prepare_messages(ctx, state, params);
+            self.prepare_tabline(ctx, state, params);
+            self.prepare_popupmenu(ctx, state, params);
+            self.prepare_scrollback(ctx, state, params);
+            self.prepare_swap_prompt(ctx, state, params);
+            self.prepare_crash_prompt(ctx, state, params);
+            self.prepare_report_prompt(ctx, state, params);
+            self.prepare_preedit(ctx, state, params);
+            self.sync_atlas_generation();
+            // UVs pushed before a mid-frame atlas resize are normalized to
+            // the old size, and rows copied from the last frame may sample
+            // a shelf evicted since. The glyphs are cached now, so the
+            // redo is cheap.
+            if self.atlas.generation() == generation && self.atlas.evictions() == evictions {
+                break stats;
+            }
+        };
         self.batcher.upload(ctx);
-        self.sync_atlas_generation();
         stats
     }
 
     #[cfg(not(feature = "perf-stats"))]
     pub fn prepare(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
-        self.batcher.clear();
-        self.prepare_grid_cells(ctx, state, params);
-        self.prepare_cursor(ctx, state, params);
+        self.atlas.begin_frame();
+        loop {
+            let generation = self.atlas.generation();
+            let evictions = self.atlas.evictions();
+            self.batcher.clear();
+            if let Some((width, height)) = params.backdrop {
+                self.batcher
+                    .push_background(0.0, 0.0, width, height, params.default_bg);
+            }
+            let grid_params = params.scrolled(state.scroll_offset);
+            self.prepare_grid_cells(ctx, state, grid_params);
+            self.prepare_floats(ctx, state, grid_params);
+            self.prepare_message_separator(state, grid_params);
+            self.prepare_invisible(ctx, state, grid_params);
+            self.prepare_cursor(ctx, state, grid_params);
+            self.prepare_dashboard(ctx, state, params);
+            self.prepare_splash(ctx, state, params);
+            self.prepare_file_hover(ctx, state, params);
+            self.prepare_zoom_indicator(ctx, state, params);
+            self.prepare_perf_overlay(ctx, state, params);
+            self.prepare_messages(ctx, state, params);
+            self.prepare_tabline(ctx, state, params);
+            self.prepare_popupmenu(ctx, state, params);
+            self.prepare_scrollback(ctx, state, params);
+            self.prepare_swap_prompt(ctx, state, params);
+            self.prepare_crash_prompt(ctx, state, params);
+            self.prepare_report_prompt(ctx, state, params);
+            self.prepare_preedit(ctx, state, params);
+            self.sync_atlas_generation();
+            // UVs pushed before a mid-frame atlas resize are normalized to
+            // the old size, and rows copied from the last frame may sample
+            // a shelf evicted since. The glyphs are cached now, so the
+            // redo is cheap.
+            if self.atlas.generation() == generation && self.atlas.evictions() == evictions {
+                break;
+            }
+        }
         self.batcher.upload(ctx);
-        self.sync_atlas_generation();
     }
 
     /// Update cached atlas inverse size if the atlas was resized during this frame.
@@ -232,34 +435,51 @@ impl GridRenderer {
         params: RenderParams,
     ) -> PrepareStats {
         let mut stats = PrepareStats::default();
-        let grid = state.main_grid();
         let highlights = &state.highlights;
-
-        for (row_idx, row_cells) in grid.rows().enumerate() {
+        let hovered_link = state.hovered_link.as_deref();
+        let separator = state.message_area().and_then(|a| a.separator_row());
+
+        let (visible_cols, visible_rows) = self.visible_cells(ctx, params);
+        self.sync_row_cache(state, params, visible_cols);
+        for (row_idx, row_cells) in state.screen_rows().enumerate().take(visible_rows) {
+            if separator == Some(row_idx) {
+                continue;
+            }
+            let row_cells = &row_cells[..row_cells.len().min(visible_cols)];
             let y = row_idx as f32 * self.metrics.cell_height + params.y_offset;
+            let key = row_key(row_cells);
+            if self.push_cached_row(row_idx, key, y) {
+                stats.rows_cached += 1;
+                continue;
+            }
+            let placement = self.batcher.grid_placement();
 
-            // First pass: backgrounds and decorations (cell by cell)
+            // First pass: backgrounds cell by cell, decorations merged into spans
             let bg_start = Instant::now();
             let mut last_hl_id = u64::MAX;
             let mut last_bg = params.default_bg;
-            let mut last_fg = params.default_fg;
-            let mut last_attrs = highlights.get(0);
+            let mut last_decoration = None;
+            let mut decorations = DecorationMerger::default();
 
             for (col_idx, cell) in row_cells.iter().enumerate() {
                 stats.cells_processed += 1;
 
                 if cell.highlight_id != last_hl_id {
                     last_hl_id = cell.highlight_id;
-                    last_attrs = highlights.get(last_hl_id);
-                    let (bg, fg) =
-                        self.resolve_colors(last_attrs, params.default_bg, params.default_fg);
+                    let attrs = highlights.get(last_hl_id);
+                    let (bg, fg) = self.resolve_colors(attrs, params.default_bg, params.default_fg);
                     last_bg = bg;
-                    last_fg = fg;
+                    last_decoration = cell_decoration(attrs, fg, hovered_link);
                 }
 
                 let x = col_idx as f32 * self.metrics.cell_width + params.x_offset;
                 self.push_cell_background(x, y, last_bg, params.default_bg);
-                self.push_cell_decorations(x, y, last_attrs, last_fg);
+                if let Some(span) = decorations.push(col_idx, last_decoration) {
+                    self.push_decoration_span(span, y, params.x_offset);
+                }
+            }
+            if let Some(span) = decorations.finish() {
+                self.push_decoration_span(span, y, params.x_offset);
             }
             stats.time_backgrounds += bg_start.elapsed();
 
@@ -272,40 +492,23 @@ impl GridRenderer {
                 stats.runs_processed += 1;
 
                 let attrs = highlights.get(run.highlight_id);
-                let (_, fg) = self.resolve_colors(attrs, params.default_bg, params.default_fg);
+                let (bg, fg) = self.resolve_colors(attrs, params.default_bg, params.default_fg);
 
-                let cache_key = ShapingCacheKey::new(&run.text, run.style);
                 let shape_start = Instant::now();
-
-                // Single lookup: get_glyphs returns None on cache miss
-                self.glyph_scratch.clear();
-                if let Some(cached_glyphs) = self.shaping_cache.get_glyphs(cache_key) {
+                if self.shape_into_scratch(&run.text, run.style) {
                     stats.shaping_cache_hits += 1;
-                    self.glyph_scratch.extend_from_slice(cached_glyphs);
-                    stats.glyphs_shaped += self.glyph_scratch.len();
                 } else {
                     stats.shaping_cache_misses += 1;
                     stats.shape_calls += 1;
-
-                    let text_run = TextRun {
-                        text: &run.text,
-                        style: run.style,
-                    };
-
-                    let new_shaped = self
-                        .shaper
-                        .shape_with_collection(&text_run, &mut self.collection);
-                    stats.glyphs_shaped += new_shaped.len();
-
-                    self.glyph_scratch.extend_from_slice(&new_shaped);
-                    self.shaping_cache.insert(cache_key, new_shaped);
                 }
+                stats.glyphs_shaped += self.glyph_scratch.len();
 
                 stats.time_shaping += shape_start.elapsed();
                 let run_x = run.start_col as f32 * self.metrics.cell_width + params.x_offset;
 
-                self.push_shaped_run_with_stats(ctx, run_x, y, fg, &mut stats);
+                self.push_shaped_run_with_stats(ctx, run_x, y, fg, bg, &run.wide, &mut stats);
             }
+            self.cache_row(row_idx, key, y, placement);
         }
 
         stats
@@ -313,31 +516,47 @@ impl GridRenderer {
 
     #[cfg(not(feature = "perf-stats"))]
     fn prepare_grid_cells(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
-        let grid = state.main_grid();
         let highlights = &state.highlights;
-
-        for (row_idx, row_cells) in grid.rows().enumerate() {
+        let hovered_link = state.hovered_link.as_deref();
+        let separator = state.message_area().and_then(|a| a.separator_row());
+
+        let (visible_cols, visible_rows) = self.visible_cells(ctx, params);
+        self.sync_row_cache(state, params, visible_cols);
+        for (row_idx, row_cells) in state.screen_rows().enumerate().take(visible_rows) {
+            if separator == Some(row_idx) {
+                continue;
+            }
+            let row_cells = &row_cells[..row_cells.len().min(visible_cols)];
             let y = row_idx as f32 * self.metrics.cell_height + params.y_offset;
+            let key = row_key(row_cells);
+            if self.push_cached_row(row_idx, key, y) {
+                continue;
+            }
+            let placement = self.batcher.grid_placement();
 
-            // First pass: backgrounds and decorations (cell by cell)
+            // First pass: backgrounds cell by cell, decorations merged into spans
             let mut last_hl_id = u64::MAX;
             let mut last_bg = params.default_bg;
-            let mut last_fg = params.default_fg;
-            let mut last_attrs = highlights.get(0);
+            let mut last_decoration = None;
+            let mut decorations = DecorationMerger::default();
 
             for (col_idx, cell) in row_cells.iter().enumerate() {
                 if cell.highlight_id != last_hl_id {
                     last_hl_id = cell.highlight_id;
-                    last_attrs = highlights.get(last_hl_id);
-                    let (bg, fg) =
-                        self.resolve_colors(last_attrs, params.default_bg, params.default_fg);
+                    let attrs = highlights.get(last_hl_id);
+                    let (bg, fg) = self.resolve_colors(attrs, params.default_bg, params.default_fg);
                     last_bg = bg;
-                    last_fg = fg;
+                    last_decoration = cell_decoration(attrs, fg, hovered_link);
                 }
 
                 let x = col_idx as f32 * self.metrics.cell_width + params.x_offset;
                 self.push_cell_background(x, y, last_bg, params.default_bg);
-                self.push_cell_decorations(x, y, last_attrs, last_fg);
+                if let Some(span) = decorations.push(col_idx, last_decoration) {
+                    self.push_decoration_span(span, y, params.x_offset);
+                }
+            }
+            if let Some(span) = decorations.finish() {
+                self.push_decoration_span(span, y, params.x_offset);
             }
 
             // Second pass: text runs with shaping
@@ -347,45 +566,110 @@ impl GridRenderer {
                 }
 
                 let attrs = highlights.get(run.highlight_id);
-                let (_, fg) = self.resolve_colors(attrs, params.default_bg, params.default_fg);
-
-                let cache_key = ShapingCacheKey::new(&run.text, run.style);
+                let (bg, fg) = self.resolve_colors(attrs, params.default_bg, params.default_fg);
 
-                self.glyph_scratch.clear();
-                if let Some(cached_glyphs) = self.shaping_cache.get_glyphs(cache_key) {
-                    self.glyph_scratch.extend_from_slice(cached_glyphs);
-                } else {
-                    let text_run = TextRun {
-                        text: &run.text,
-                        style: run.style,
-                    };
-
-                    let new_shaped = self
-                        .shaper
-                        .shape_with_collection(&text_run, &mut self.collection);
-
-                    self.glyph_scratch.extend_from_slice(&new_shaped);
-                    self.shaping_cache.insert(cache_key, new_shaped);
-                }
+                self.shape_into_scratch(&run.text, run.style);
 
                 let run_x = run.start_col as f32 * self.metrics.cell_width + params.x_offset;
-                self.push_shaped_run(ctx, run_x, y, fg);
+                self.push_shaped_run(ctx, run_x, y, fg, bg, &run.wide);
             }
+            self.cache_row(row_idx, key, y, placement);
+        }
+    }
+
+    /// Drops the cached rows if anything they were built with changed,
+    /// and moves them along with the grid's scrolls.
+    fn sync_row_cache(&mut self, state: &EditorState, params: RenderParams, visible_cols: usize) {
+        let mut hasher = ahash::AHasher::default();
+        for color in [params.default_bg, params.default_fg] {
+            color.map(f32::to_bits).hash(&mut hasher);
         }
+        params.x_offset.to_bits().hash(&mut hasher);
+        visible_cols.hash(&mut hasher);
+        state.highlights.generation().hash(&mut hasher);
+        state.hovered_link.hash(&mut hasher);
+        self.font_generation.hash(&mut hasher);
+        // UVs are normalized to the atlas size, and an evicted glyph's
+        // slot may hold another one now.
+        self.atlas.generation().hash(&mut hasher);
+        self.atlas.evictions().hash(&mut hasher);
+        self.row_cache.validate(hasher.finish());
+        self.row_cache.scroll(state.main_grid_scrolls());
     }
 
-    /// Push a single glyph to the render batch.
+    /// Pushes the row at `row` as batched before, moved to `y`. False if
+    /// it has to be built. A row pushed where the last frame put it isn't
+    /// uploaded again.
+    fn push_cached_row(&mut self, row: usize, key: u64, y: f32) -> bool {
+        let Some(cached) = self.row_cache.get(row, key).filter(|_| self.reuse_rows) else {
+            return false;
+        };
+        let placement = self.batcher.grid_placement();
+        let in_place = cached.placed.is_some_and(|(placed, placed_y)| {
+            placed_y == y
+                && self.batcher.extend_grid_unchanged(
+                    &cached.backgrounds,
+                    &cached.glyphs,
+                    &cached.decorations,
+                    placed,
+                )
+        });
+        if !in_place {
+            self.batcher.extend_grid(
+                &cached.backgrounds,
+                &cached.glyphs,
+                &cached.decorations,
+                y - cached.y,
+            );
+        }
+        self.row_cache.place(row, (placement, y));
+        true
+    }
+
+    /// Keeps what was batched for the row at `row` since `placement`.
+    fn cache_row(&mut self, row: usize, key: u64, y: f32, placement: GridPlacement) {
+        let [backgrounds, glyphs, decorations] = self.batcher.grid_since(placement.mark());
+        let cached = CachedRow {
+            key,
+            y,
+            backgrounds: backgrounds.to_vec(),
+            glyphs: glyphs.to_vec(),
+            decorations: decorations.to_vec(),
+            placed: Some((placement, y)),
+        };
+        self.row_cache.insert(row, cached);
+    }
+
+    /// Columns and rows of the grid that fall inside the surface. The grid
+    /// outgrows the window while Neovim catches up with a shrink, and
+    /// everything past the edge is skipped rather than drawn off screen.
+    fn visible_cells(&self, ctx: &GpuContext, params: RenderParams) -> (usize, usize) {
+        let size = ctx.size();
+        let visible = |extent: u32, offset: f32, cell: f32| {
+            ((extent as f32 - offset) / cell).ceil().max(0.0) as usize
+        };
+        (
+            visible(size.width, params.x_offset, self.metrics.cell_width),
+            visible(size.height, params.y_offset, self.metrics.cell_height),
+        )
+    }
+
+    /// Push a single glyph to the render batch, over a backing if it is a
+    /// color glyph that would blend into `bg`.
     ///
     /// This is the core rendering logic shared by all glyph rendering paths.
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn push_glyph_to_batch(
         &mut self,
+        layer: Layer,
         glyph: &ShapedGlyph,
         cached: &ShapedCachedGlyph,
         x: f32,
         y: f32,
         baseline_y: f32,
         fg: [f32; 4],
+        bg: Option<[f32; 4]>,
     ) {
         if cached.width == 0 || cached.height == 0 {
             return;
@@ -401,7 +685,24 @@ impl GridRenderer {
         let glyph_x = x + x_offset + cached.bearing_x as f32;
         let glyph_y = compute_glyph_y(glyph, cached, y, baseline_y, self.metrics.cell_height);
 
+        if let Some(backing) = bg
+            .filter(|_| self.emoji_backing && cached.is_colored)
+            .and_then(|bg| color_glyph_backing(cached.luminance, bg))
+        {
+            // Kept within the row, which the next row's backgrounds cover
+            let pad = self.metrics.cell_height / 4.0;
+            self.batcher.push_pill(
+                layer,
+                glyph_x - pad,
+                y,
+                cached.width as f32 + 2.0 * pad,
+                self.metrics.cell_height,
+                backing,
+            );
+        }
+
         self.batcher.push_glyph(
+            layer,
             glyph_x,
             glyph_y,
             cached.width as f32,
@@ -412,45 +713,94 @@ impl GridRenderer {
             uv_h,
             fg,
             cached.is_colored,
+            cached.page,
         );
     }
 
     /// Render glyphs from glyph_scratch without stats tracking (non-perf-stats mode).
+    /// `wide`: the byte offsets of wide characters in the run (see
+    /// `place_glyph`).
     #[cfg(not(feature = "perf-stats"))]
-    fn push_shaped_run(&mut self, ctx: &GpuContext, run_x: f32, y: f32, fg: [f32; 4]) {
+    #[allow(clippy::too_many_arguments)]
+    fn push_shaped_run(
+        &mut self,
+        ctx: &GpuContext,
+        run_x: f32,
+        y: f32,
+        fg: [f32; 4],
+        bg: [f32; 4],
+        wide: &[u32],
+    ) {
         let mut x = run_x;
         let baseline_y = y + self.baseline_offset;
-        let cell_width = self.metrics.cell_width;
+        let mut previous = None;
 
         for i in 0..self.glyph_scratch.len() {
             let glyph = self.glyph_scratch[i];
             let key = GlyphCacheKey::new(glyph.glyph_id, glyph.font_index);
+            let (shift, advance) = self.place_glyph(&glyph, previous, wide);
+            previous = Some(glyph.cluster);
 
             if let Some(cached) = self.atlas.get_glyph_by_id(ctx, &self.collection, key) {
-                self.push_glyph_to_batch(&glyph, &cached, x, y, baseline_y, fg);
+                self.push_glyph_to_batch(
+                    Layer::Grid,
+                    &glyph,
+                    &cached,
+                    x + shift,
+                    y,
+                    baseline_y,
+                    fg,
+                    Some(bg),
+                );
             }
 
-            x += compute_glyph_advance(&glyph, cell_width);
+            x += advance;
         }
     }
 
+    /// How far right of the pen a glyph goes, and how far the pen moves
+    /// after it. The first glyph of a wide character, one whose cluster is
+    /// in `wide`, is centered in the character's two cells, whatever width
+    /// its font gives it; a fallback font's CJK glyph would otherwise be
+    /// squeezed into one.
+    #[inline]
+    fn place_glyph(&self, glyph: &ShapedGlyph, previous: Option<u32>, wide: &[u32]) -> (f32, f32) {
+        let cell_width = self.metrics.cell_width;
+        let starts_wide = !wide.is_empty()
+            && previous != Some(glyph.cluster)
+            && wide.binary_search(&glyph.cluster).is_ok();
+        if starts_wide {
+            let advance = glyph.x_advance as f32 / HARFBUZZ_SCALE;
+            return center_in_cells(advance, 2, cell_width);
+        }
+        (
+            0.0,
+            compute_glyph_advance(glyph, cell_width, self.advance_scale),
+        )
+    }
+
     /// Render glyphs from glyph_scratch with stats tracking (perf-stats mode).
     #[cfg(feature = "perf-stats")]
+    #[allow(clippy::too_many_arguments)]
     fn push_shaped_run_with_stats(
         &mut self,
         ctx: &GpuContext,
         run_x: f32,
         y: f32,
         fg: [f32; 4],
+        bg: [f32; 4],
+        wide: &[u32],
         stats: &mut PrepareStats,
     ) {
         let mut x = run_x;
         let baseline_y = y + self.baseline_offset;
-        let cell_width = self.metrics.cell_width;
+        let mut previous = None;
 
         for i in 0..self.glyph_scratch.len() {
             let glyph = self.glyph_scratch[i];
             let key = GlyphCacheKey::new(glyph.glyph_id, glyph.font_index);
+            let (shift, advance) = self.place_glyph(&glyph, previous, wide);
+            previous = Some(glyph.cluster);
 
             let lookup_start = Instant::now();
             let (cached_opt, was_cache_hit) =
@@ -466,35 +816,52 @@ impl GridRenderer {
 
             if let Some(cached) = cached_opt {
                 let batch_start = Instant::now();
-                self.push_glyph_to_batch(&glyph, &cached, x, y, baseline_y, fg);
+                self.push_glyph_to_batch(
+                    Layer::Grid,
+                    &glyph,
+                    &cached,
+                    x + shift,
+                    y,
+                    baseline_y,
+                    fg,
+                    Some(bg),
+                );
                 stats.time_batching += batch_start.elapsed();
             }
 
-            x += compute_glyph_advance(&glyph, cell_width);
+            x += advance;
         }
     }
 
-    /// Render shaped glyphs from an external slice (for cursor rendering).
+    /// Render shaped glyphs from an external slice (for cursor and overlay rendering).
+    /// `bg` is the background under the text, for color glyph backings,
+    /// and `wide` the byte offsets of its wide characters.
+    #[allow(clippy::too_many_arguments)]
     fn render_glyphs(
         &mut self,
         ctx: &GpuContext,
+        layer: Layer,
         run_x: f32,
         y: f32,
         glyphs: &[ShapedGlyph],
         fg: [f32; 4],
+        bg: Option<[f32; 4]>,
+        wide: &[u32],
     ) {
         let mut x = run_x;
         let baseline_y = y + self.baseline_offset;
-        let cell_width = self.metrics.cell_width;
+        let mut previous = None;
 
         for glyph in glyphs {
             let key = GlyphCacheKey::new(glyph.glyph_id, glyph.font_index);
+            let (shift, advance) = self.place_glyph(glyph, previous, wide);
+            previous = Some(glyph.cluster);
 
             if let Some(cached) = self.atlas.get_glyph_by_id(ctx, &self.collection, key) {
-                self.push_glyph_to_batch(glyph, &cached, x, y, baseline_y, fg);
+                self.push_glyph_to_batch(layer, glyph, &cached, x + shift, y, baseline_y, fg, bg);
             }
 
-            x += compute_glyph_advance(glyph, cell_width);
+            x += advance;
         }
     }
 
@@ -511,36 +878,35 @@ impl GridRenderer {
         }
     }
 
-    #[inline(always)]
-    fn push_cell_decorations(&mut self, x: f32, y: f32, attrs: &HighlightAttributes, fg: [f32; 4]) {
-        let underline_style = attrs.underline_style();
-        let has_strikethrough = attrs.has_strikethrough();
-
-        if underline_style == UnderlineStyle::None && !has_strikethrough {
-            return;
-        }
-
-        let special_color = attrs
-            .special
-            .map(|c| u32_to_linear_rgba(c.0 >> 8))
-            .unwrap_or(fg);
+    /// Draws the lines of a decoration span as one quad each.
+    fn push_decoration_span(&mut self, span: DecorationSpan, y: f32, x_offset: f32) {
+        self.push_decoration_span_in(Layer::Grid, span, y, x_offset);
+    }
 
-        let metrics = self.collection.metrics();
+    fn push_decoration_span_in(
+        &mut self,
+        layer: Layer,
+        span: DecorationSpan,
+        y: f32,
+        x_offset: f32,
+    ) {
+        let decoration = span.decoration;
+        let metrics = self.metrics;
         let geom = compute_decoration_geometry(
-            x,
+            span.start_col as f32 * self.metrics.cell_width + x_offset,
             y,
-            self.metrics.cell_width,
+            span.len as f32 * self.metrics.cell_width,
             self.metrics.cell_height,
             metrics.descent,
             metrics.underline_position,
             metrics.underline_thickness,
             metrics.strikeout_position,
             metrics.strikeout_thickness,
-            underline_style,
-            has_strikethrough,
+            decoration.underline_style,
+            decoration.has_strikethrough,
         );
 
-        let underline_count = match underline_style {
+        let underline_count = match decoration.underline_style {
             UnderlineStyle::None => 0,
             UnderlineStyle::Double => 2,
             _ => 1,
@@ -548,12 +914,38 @@ impl GridRenderer {
 
         for (i, line) in geom.lines.iter().enumerate() {
             let color = if i < underline_count {
-                special_color
+                decoration.underline_color
             } else {
-                fg
+                decoration.strikethrough_color
             };
-            self.batcher
-                .push_decoration(line.x, line.y, line.width, line.height, color);
+            match layer {
+                Layer::Float => {
+                    self.batcher
+                        .push_float_quad(line.x, line.y, line.width, line.height, color)
+                }
+                _ => self
+                    .batcher
+                    .push_decoration(line.x, line.y, line.width, line.height, color),
+            }
+        }
+    }
+
+    /// Shapes `text` into `glyph_scratch`, through the shaping cache.
+    /// Returns whether the cache had it, so HarfBuzz wasn't needed.
+    fn shape_into_scratch(&mut self, text: &str, style: Style) -> bool {
+        let cache_key = ShapingCacheKey::new(text, style, self.font_generation);
+        self.glyph_scratch.clear();
+        if let Some(cached_glyphs) = self.shaping_cache.get_glyphs(cache_key) {
+            self.glyph_scratch.extend_from_slice(cached_glyphs);
+            true
+        } else {
+            let text_run = TextRun { text, style };
+            let shaped = self
+                .shaper
+                .shape_with_collection(&text_run, &mut self.collection);
+            self.glyph_scratch.extend_from_slice(&shaped);
+            self.shaping_cache.insert(cache_key, shaped);
+            false
         }
     }
 
@@ -584,82 +976,1315 @@ impl GridRenderer {
         &self.batcher
     }
 
-    fn prepare_cursor(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+    /// With invisible characters shown, outline each cell holding one and
+    /// name the one under the cursor in a label just above it.
+    fn prepare_invisible(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        if !state.show_invisible {
+            return;
+        }
+        let default = OverlayStyle::new(params.default_bg, params.default_fg);
+        let style = self.group_style(state, "NonText", default);
+        let (cell_width, cell_height) = (self.metrics.cell_width, self.metrics.cell_height);
         let cursor = &state.cursor;
-        if !cursor.visible || !cursor.blink_visible {
+
+        let mut under_cursor = None;
+        for (row, cells) in state.screen_rows().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let Some(c) = cell.invisible_char() else {
+                    continue;
+                };
+                let x = col as f32 * cell_width + params.x_offset;
+                let y = row as f32 * cell_height + params.y_offset;
+                for (x, y, width, height) in [
+                    (x, y, cell_width, 1.0),
+                    (x, y + cell_height - 1.0, cell_width, 1.0),
+                    (x, y, 1.0, cell_height),
+                    (x + cell_width - 1.0, y, 1.0, cell_height),
+                ] {
+                    self.batcher.push_background(x, y, width, height, style.fg);
+                }
+                if cursor.grid == 1 && (row, col) == (cursor.row, cursor.col) {
+                    under_cursor = Some((row, col, c));
+                }
+            }
+        }
+
+        let Some((row, col, c)) = under_cursor else {
             return;
+        };
+        let grid = state.main_grid();
+        let label = format!(" U+{:04X} ", c as u32);
+        let width = label.len().min(grid.width());
+        let label_row = match row {
+            0 if grid.height() > 1 => 1,
+            row => row.saturating_sub(1),
+        };
+        let label_col = col.min(grid.width() - width);
+        let style = style.inverted();
+        let mut scene = Scene::new();
+        scene.fill(CellRect::row(label_row, label_col, width), style.bg);
+        scene.label(
+            label_row,
+            label_col,
+            width,
+            [(label.as_str(), style)],
+            style.bg,
+        );
+        self.paint_scene(ctx, params, &scene);
+    }
+
+    /// Draw floating windows over the grid, bottom to top, into the float
+    /// batch. Backgrounds take their highlight's blend ('winblend') as
+    /// alpha, so the windows below show through, and the box-drawing border
+    /// Neovim puts around a float's edges is drawn as lines in the border
+    /// highlight's color rather than as glyphs.
+    /// Draws zoomed windows and then floating windows over the grid, in
+    /// the float layer. A zoomed window is drawn at the normal cell size
+    /// and then scaled about its corner, cut to the room it takes.
+    fn prepare_floats(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        let (cell_width, cell_height) = (self.metrics.cell_width, self.metrics.cell_height);
+        self.cursor_float_glyphs = 0..0;
+
+        for window in state.zoomed_windows() {
+            let start = self.batcher.floats().len();
+            let (row, col) = (window.row as f64, window.col as f64);
+            self.push_float_grid(ctx, state, params, window.grid, row, col, false);
+            let origin = [
+                window.col as f32 * cell_width + params.x_offset,
+                window.row as f32 * cell_height + params.y_offset,
+            ];
+            self.batcher.scale_floats_since(
+                start,
+                origin,
+                window.zoom,
+                window.width as f32 * cell_width,
+                window.height as f32 * cell_height,
+            );
+        }
+        for float in state.floats() {
+            self.push_float_grid(ctx, state, params, float.grid, float.row, float.col, true);
         }
+    }
 
-        if cursor.grid != 1 {
+    /// Pushes `grid` to the float layer with its top-left corner at screen
+    /// cell (`origin_row`, `origin_col`). With `borders`, box drawing
+    /// characters around its edge are drawn as lines.
+    #[allow(clippy::too_many_arguments)]
+    fn push_float_grid(
+        &mut self,
+        ctx: &GpuContext,
+        state: &EditorState,
+        params: RenderParams,
+        grid: &Grid,
+        origin_row: f64,
+        origin_col: f64,
+        borders: bool,
+    ) {
+        let (cell_width, cell_height) = (self.metrics.cell_width, self.metrics.cell_height);
+        let thickness = self.collection.metrics().underline_thickness;
+        let highlights = &state.highlights;
+        let hovered_link = state.hovered_link.as_deref();
+        let x_offset = origin_col as f32 * cell_width + params.x_offset;
+        let y_offset = origin_row as f32 * cell_height + params.y_offset;
+        let last_row = grid.height().saturating_sub(1);
+        let last_col = grid.width().saturating_sub(1);
+
+        for (row, cells) in grid.rows().enumerate() {
+            let y = y_offset + row as f32 * cell_height;
+            // Border cells are blanked before shaping; lines stand in.
+            let mut text_cells = Cow::Borrowed(cells);
+            let mut decorations = DecorationMerger::default();
+
+            for (col, cell) in cells.iter().enumerate() {
+                let attrs = highlights.get(cell.highlight_id);
+                let (bg, fg) = self.resolve_colors(attrs, params.default_bg, params.default_fg);
+                let x = x_offset + col as f32 * cell_width;
+                let alpha = blend_alpha(attrs.blend);
+                self.batcher
+                    .push_float_quad(x, y, cell_width, cell_height, premultiply(bg, alpha));
+
+                let on_edge =
+                    borders && (row == 0 || row == last_row || col == 0 || col == last_col);
+                let border = on_edge
+                    .then(|| single_char(&cell.text))
+                    .flatten()
+                    .and_then(|c| {
+                        compute_box_geometry(c, x, y, cell_width, cell_height, thickness)
+                    });
+                if let Some(lines) = border {
+                    for line in lines {
+                        self.batcher
+                            .push_float_quad(line.x, line.y, line.width, line.height, fg);
+                    }
+                    text_cells.to_mut()[col].text = " ".into();
+                }
+                if let Some(span) = decorations.push(col, cell_decoration(attrs, fg, hovered_link))
+                {
+                    self.push_decoration_span_in(Layer::Float, span, y, x_offset);
+                }
+            }
+            if let Some(span) = decorations.finish() {
+                self.push_decoration_span_in(Layer::Float, span, y, x_offset);
+            }
+
+            let glyphs_start = self.batcher.floats().len();
+            for run in RunIterator::new(&text_cells, highlights) {
+                if run.is_empty() {
+                    continue;
+                }
+                let attrs = highlights.get(run.highlight_id);
+                let (bg, fg) = self.resolve_colors(attrs, params.default_bg, params.default_fg);
+                self.shape_into_scratch(&run.text, run.style);
+                let glyphs = std::mem::take(&mut self.glyph_scratch);
+                let run_x = x_offset + run.start_col as f32 * cell_width;
+                self.render_glyphs(
+                    ctx,
+                    Layer::Float,
+                    run_x,
+                    y,
+                    &glyphs,
+                    fg,
+                    Some(bg),
+                    &run.wide,
+                );
+                self.glyph_scratch = glyphs;
+            }
+            if grid.id == state.cursor.grid && row == state.cursor.row {
+                self.cursor_float_glyphs = glyphs_start..self.batcher.floats().len();
+            }
+        }
+    }
+
+    /// Draw the separator above scrolled messages as a crisp 1px line
+    /// across its row, in place of the row of msgsep characters Neovim's
+    /// compositor would draw.
+    fn prepare_message_separator(&mut self, state: &EditorState, params: RenderParams) {
+        let Some(row) = state.message_area().and_then(|a| a.separator_row()) else {
+            return;
+        };
+        let default = OverlayStyle::new(params.default_bg, params.default_fg);
+        let style = self.group_style(state, "MsgSeparator", default);
+        let width = state.main_grid().width() as f32 * self.metrics.cell_width;
+        let y = row as f32 * self.metrics.cell_height + params.y_offset;
+
+        if style.bg != params.default_bg {
+            self.batcher.push_background(
+                params.x_offset,
+                y,
+                width,
+                self.metrics.cell_height,
+                style.bg,
+            );
+        }
+        let line_y = (y + self.metrics.cell_height / 2.0).floor();
+        self.batcher
+            .push_background(params.x_offset, line_y, width, 1.0, style.fg);
+    }
+
+    fn prepare_cursor(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        let cursor = &state.cursor;
+        if !cursor.visible || !cursor.blink_visible || state.busy || state.splash.is_some() {
             return;
         }
 
+        // The cursor sits on the message grid at prompts like hit-enter, in
+        // a float while it has focus, where it is drawn over the float, and
+        // in a split window's grid with ext_multigrid, zoomed or not.
+        let (grid, origin_row, origin_col, layer, zoom) = match state.message_area() {
+            _ if cursor.grid == 1 => (state.main_grid(), 0.0, 0.0, Layer::Grid, 1.0),
+            Some(area) if area.grid == cursor.grid => match state.grid(area.grid) {
+                Some(grid) => (grid, area.row as f64, 0.0, Layer::Grid, 1.0),
+                None => return,
+            },
+            _ => {
+                let float = state
+                    .floats()
+                    .into_iter()
+                    .find(|f| f.grid.id == cursor.grid);
+                let zoomed = || {
+                    state
+                        .zoomed_windows()
+                        .into_iter()
+                        .find(|w| w.grid.id == cursor.grid)
+                };
+                let split = || {
+                    state
+                        .split_windows()
+                        .into_iter()
+                        .find(|(grid, ..)| grid.id == cursor.grid)
+                };
+                if let Some(float) = float {
+                    (float.grid, float.row, float.col, Layer::Float, 1.0)
+                } else if let Some(window) = zoomed() {
+                    let (row, col) = (window.row as f64, window.col as f64);
+                    (window.grid, row, col, Layer::Float, window.zoom)
+                } else if let Some((grid, row, col)) = split() {
+                    (grid, row as f64, col as f64, Layer::Grid, 1.0)
+                } else {
+                    return;
+                }
+            }
+        };
+
         let mode = state.current_mode();
-        let grid = state.main_grid();
+        let custom = self.cursor_settings.style(state.mode_name());
+        let shape = match custom.shape {
+            Some(CursorShapeSetting::Block) => CursorShape::Block,
+            Some(CursorShapeSetting::Bar) => CursorShape::Vertical,
+            Some(CursorShapeSetting::Underline) => CursorShape::Horizontal,
+            None => mode.cursor_shape,
+        };
 
         if cursor.row >= grid.height() || cursor.col >= grid.width() {
             return;
         }
 
         let mut geom = compute_cursor_geometry(
-            mode.cursor_shape,
+            shape,
             cursor.row,
             cursor.col,
-            self.metrics.cell_width,
-            self.metrics.cell_height,
-            mode.cell_percentage,
+            self.metrics.cell_width * zoom,
+            self.metrics.cell_height * zoom,
+            custom.size.unwrap_or(mode.cell_percentage),
         );
 
-        geom.x += params.x_offset;
-        geom.y += params.y_offset;
+        geom.x += origin_col as f32 * self.metrics.cell_width + params.x_offset;
+        geom.y += origin_row as f32 * self.metrics.cell_height + params.y_offset;
+        // On a wide character the block and underline cover both halves.
+        let on_wide = grid
+            .get(cursor.row, cursor.col)
+            .is_some_and(|c| c.is_wide());
+        if on_wide && shape != CursorShape::Vertical {
+            geom.width *= 2.0;
+        }
+
+        let cell = grid.get(cursor.row, cursor.col);
+        let cell_attrs = state.highlights.get(cell.map_or(0, |c| c.highlight_id));
+        let (cell_bg, cell_fg) =
+            self.resolve_colors(cell_attrs, params.default_bg, params.default_fg);
 
         let hl = state.highlights.get(mode.attr_id);
-        let cursor_color = match (mode.attr_id, hl.background, hl.foreground) {
-            (1.., Some(bg), _) => u32_to_linear_rgba(bg.0 >> 8),
-            (1.., None, Some(fg)) => u32_to_linear_rgba(fg.0 >> 8),
+        let cursor_color = match (custom.color, mode.attr_id, hl.background, hl.foreground) {
+            (Some(color), ..) => u32_to_linear_rgba(color),
+            (None, 1.., Some(bg), _) => u32_to_linear_rgba(bg.0 >> 8),
+            (None, 1.., None, Some(fg)) => u32_to_linear_rgba(fg.0 >> 8),
             _ => params.default_fg,
         };
+        // Kept apart from the cell, so it can't vanish into text of its
+        // own color.
+        let min_contrast = self.cursor_settings.min_contrast;
+        let cursor_color = ensure_contrast(cursor_color, cell_bg, cell_fg, min_contrast);
+
+        // Unfocused, the block is hollow or an underline, and the text under
+        // it untouched.
+        let unfocused = !state.focused() && shape == CursorShape::Block;
+        let hollow = unfocused && self.cursor_settings.unfocused == UnfocusedCursor::Hollow;
+        let thickness = self.collection.metrics().underline_thickness;
+        if unfocused && !hollow {
+            let height = thickness.max(1.0);
+            geom.y += geom.height - height;
+            geom.height = height;
+        }
+        let outline;
+        let quads = if hollow {
+            outline = compute_cursor_outline(&geom, thickness);
+            &outline[..]
+        } else {
+            std::slice::from_ref(&geom)
+        };
+        for quad in quads {
+            match layer {
+                Layer::Float => self.batcher.push_float_quad(
+                    quad.x,
+                    quad.y,
+                    quad.width,
+                    quad.height,
+                    cursor_color,
+                ),
+                _ => self.batcher.push_background(
+                    quad.x,
+                    quad.y,
+                    quad.width,
+                    quad.height,
+                    cursor_color,
+                ),
+            }
+        }
 
-        self.batcher
-            .push_background(geom.x, geom.y, geom.width, geom.height, cursor_color);
+        if shape != CursorShape::Block || unfocused {
+            return;
+        }
+
+        let text_color = match (custom.text_color, mode.attr_id, hl.foreground) {
+            (Some(color), ..) => u32_to_linear_rgba(color),
+            (None, 1.., Some(fg)) => u32_to_linear_rgba(fg.0 >> 8),
+            _ => cell_bg,
+        };
+        let text_color = ensure_contrast(text_color, cursor_color, cell_bg, min_contrast);
+
+        // The row's text as shaped, so the cursor shows its part of a
+        // ligature rather than the cell's character on its own: each glyph
+        // over the cell again, cut to it and in the cursor's text color.
+        let screen_row = origin_row as usize + cursor.row;
+        let (glyphs, dy) = self.cursor_row_glyphs(ctx, state, params, layer, screen_row);
+        let quads: Vec<QuadInstance> = glyphs
+            .iter()
+            .filter(|quad| quad.is_glyph())
+            .filter_map(|quad| {
+                let mut quad = *quad;
+                quad.position[1] += dy;
+                quad.clipped(geom.x, geom.y, geom.width, geom.height)
+            })
+            .map(|mut quad| {
+                if !quad.is_colored_glyph() {
+                    quad.color = text_color;
+                }
+                quad
+            })
+            .collect();
+        for quad in quads {
+            self.batcher.push_glyph_instance(layer, quad);
+        }
+    }
 
-        if mode.cursor_shape != CursorShape::Block {
+    /// The glyphs batched this frame for the cursor's row, `row` of the
+    /// screen, and how far down they have to move to where it's drawn: the
+    /// main grid's rows come from the row cache, which may have built them
+    /// frames ago, and a float's from the float batch.
+    fn cursor_row_glyphs(
+        &self,
+        ctx: &GpuContext,
+        state: &EditorState,
+        params: RenderParams,
+        layer: Layer,
+        row: usize,
+    ) -> (&[QuadInstance], f32) {
+        if layer == Layer::Float {
+            let range = self.cursor_float_glyphs.clone();
+            let floats = self.batcher.floats().since(range.start);
+            return (&floats[..range.len().min(floats.len())], 0.0);
+        }
+        let (visible_cols, _) = self.visible_cells(ctx, params);
+        let Some(cells) = state.screen_rows().nth(row) else {
+            return (&[], 0.0);
+        };
+        let cells = &cells[..cells.len().min(visible_cols)];
+        match self.row_cache.get(row, row_key(cells)) {
+            Some(cached) => {
+                let y = row as f32 * self.metrics.cell_height + params.y_offset;
+                (&cached.glyphs, y - cached.y)
+            }
+            None => (&[], 0.0),
+        }
+    }
+
+    /// Colors and font style of highlight group `name`, or `fallback` when
+    /// the colorscheme doesn't define it.
+    fn group_style(&self, state: &EditorState, name: &str, fallback: OverlayStyle) -> OverlayStyle {
+        match state.highlights.group(name) {
+            Some(attrs) => self.highlight_style(attrs, fallback),
+            None => fallback,
+        }
+    }
+
+    fn highlight_style(&self, attrs: &HighlightAttributes, base: OverlayStyle) -> OverlayStyle {
+        let (bg, fg) = self.resolve_colors(attrs, base.bg, base.fg);
+        OverlayStyle {
+            bg,
+            fg,
+            font: Style::for_highlight(attrs.style),
+        }
+    }
+
+    /// Styles message chunks over `base`, faded by `opacity`.
+    fn chunk_spans<'a>(
+        &self,
+        state: &EditorState,
+        chunks: &'a [StyledChunk],
+        base: OverlayStyle,
+        opacity: f32,
+    ) -> Vec<(&'a str, OverlayStyle)> {
+        chunks
+            .iter()
+            .map(|chunk| {
+                let attrs = state.highlights.get(chunk.hl_id);
+                (
+                    chunk.text.as_str(),
+                    self.highlight_style(attrs, base).faded(opacity),
+                )
+            })
+            .collect()
+    }
+
+    /// Paint an overlay scene: fills as quads, text shaped like the grid.
+    fn paint_scene(&mut self, ctx: &GpuContext, params: RenderParams, scene: &Scene) {
+        let cell_width = self.metrics.cell_width;
+        let cell_height = self.metrics.cell_height;
+        let origin = |row: usize, col: usize| {
+            (
+                col as f32 * cell_width + params.x_offset,
+                row as f32 * cell_height + params.y_offset,
+            )
+        };
+
+        for element in scene.elements() {
+            match element {
+                Element::Fill { rect, color } => {
+                    let (x, y) = origin(rect.row, rect.col);
+                    self.batcher.push_overlay_background(
+                        x,
+                        y,
+                        rect.width as f32 * cell_width,
+                        rect.height as f32 * cell_height,
+                        *color,
+                    );
+                }
+                Element::Text {
+                    row,
+                    col,
+                    text,
+                    style,
+                } => {
+                    let (x, y) = origin(*row, *col);
+                    let text_run = TextRun {
+                        text,
+                        style: style.font,
+                    };
+                    let shaped = self
+                        .shaper
+                        .shape_with_collection(&text_run, &mut self.collection);
+                    self.render_glyphs(ctx, Layer::Overlay, x, y, &shaped, style.fg, None, &[]);
+                }
+                Element::Caret { row, col, color } => {
+                    let (x, y) = origin(*row, *col);
+                    let caret_width = (cell_width / 8.0).max(1.0);
+                    self.batcher
+                        .push_overlay_background(x, y, caret_width, cell_height, *color);
+                }
+            }
+        }
+    }
+
+    /// Draw the startup dashboard centered over the grid: a title, then the
+    /// recent files numbered for quick picks, with the selection highlighted.
+    fn prepare_dashboard(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        const TITLE: &str = " Recent files";
+
+        // Picking a file needs Neovim, so wait until it is up.
+        let Some(dashboard) = state.dashboard.as_ref().filter(|_| state.splash.is_none()) else {
+            return;
+        };
+        let grid = state.main_grid();
+        let (cols, rows) = (grid.width(), grid.height());
+        if cols == 0 || rows < 3 {
+            return;
+        }
+
+        let default = OverlayStyle::new(params.default_bg, params.default_fg);
+        let normal = self.group_style(state, "NormalFloat", default);
+        let selected = self.group_style(state, "PmenuSel", normal.inverted());
+        let title = self.group_style(state, "Title", normal);
+
+        let lines: Vec<String> = dashboard
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let key = if i < 9 {
+                    (i + 1).to_string()
+                } else {
+                    " ".to_string()
+                };
+                format!(" {}  {} ", key, Dashboard::label(entry))
+            })
+            .collect();
+        let width = lines
+            .iter()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(TITLE.len() + 1)
+            .min(cols);
+        let height = (lines.len() + 2).min(rows);
+        let (top, left) = ((rows - height) / 2, (cols - width) / 2);
+
+        let mut scene = Scene::new();
+        scene.fill(CellRect::new(top, left, width, 2), normal.bg);
+        scene.label(top, left, width, [(TITLE, title)], normal.bg);
+
+        let list = ListView {
+            rect: CellRect::new(top + 2, left, width, height - 2),
+            len: lines.len(),
+            first: 0,
+            selected: Some(dashboard.selected()),
+        };
+        list.paint_frame(&mut scene, (normal.bg, selected.bg), (normal.bg, normal.bg));
+        for row in list.rows() {
+            let style = if row.selected { selected } else { normal };
+            scene.label(
+                row.rect.row,
+                row.rect.col,
+                row.rect.width,
+                [(lines[row.index].as_str(), style)],
+                style.bg,
+            );
+        }
+        self.paint_scene(ctx, params, &scene);
+    }
+
+    /// Draw the "swap file found" question centered over the grid: the
+    /// file and its swap file, then the choices with their letters.
+    fn prepare_swap_prompt(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        let Some(prompt) = &state.swap_prompt else {
+            return;
+        };
+        let header = [format!(" {}", prompt.file), format!(" {}", prompt.swapname)];
+        let lines: Vec<String> = SwapChoice::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| format!(" {}  ({}) {} ", i + 1, choice.key(), choice.label()))
+            .collect();
+        let prompt = Prompt {
+            title: " Swap file found",
+            title_group: "WarningMsg",
+            header: &header,
+            lines: &lines,
+            selected: prompt.selected(),
+        };
+        self.paint_prompt(ctx, state, params, prompt);
+    }
+
+    /// Draw the question after Neovim crashed, like the swap prompt.
+    fn prepare_crash_prompt(
+        &mut self,
+        ctx: &GpuContext,
+        state: &EditorState,
+        params: RenderParams,
+    ) {
+        let Some(prompt) = &state.crash_prompt else {
+            return;
+        };
+        let header = [format!(" Neovim crashed ({})", prompt.status)];
+        let lines: Vec<String> = CrashChoice::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| format!(" {}  ({}) {} ", i + 1, choice.key(), choice.label()))
+            .collect();
+        let prompt = Prompt {
+            title: " Neovim exited",
+            title_group: "ErrorMsg",
+            header: &header,
+            lines: &lines,
+            selected: prompt.selected(),
+        };
+        self.paint_prompt(ctx, state, params, prompt);
+    }
+
+    /// Draw the pointer to the report gui.nvim left when it last crashed,
+    /// like the swap prompt, once Neovim is up to open it in.
+    fn prepare_report_prompt(
+        &mut self,
+        ctx: &GpuContext,
+        state: &EditorState,
+        params: RenderParams,
+    ) {
+        let Some(prompt) = &state.report_prompt else {
+            return;
+        };
+        if state.splash.is_some() {
             return;
         }
+        let header = [
+            " gui.nvim crashed last time. The report is in".to_string(),
+            format!(" {}", prompt.path.display()),
+        ];
+        let lines: Vec<String> = ReportChoice::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| format!(" {}  ({}) {} ", i + 1, choice.key(), choice.label()))
+            .collect();
+        let prompt = Prompt {
+            title: " Crash report",
+            title_group: "WarningMsg",
+            header: &header,
+            lines: &lines,
+            selected: prompt.selected(),
+        };
+        self.paint_prompt(ctx, state, params, prompt);
+    }
 
-        let Some(c) = grid.get(cursor.row, cursor.col) else {
+    /// Paint the `:GuiMessages` history over the whole grid: a title, the
+    /// lines it scrolled to and a footer with the position and keys.
+    fn prepare_scrollback(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        let Some(scrollback) = &state.scrollback else {
             return;
         };
+        let grid = state.main_grid();
+        let (cols, rows) = (grid.width(), grid.height());
+        let (width, height) = Scrollback::text_area(cols, rows);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let default = OverlayStyle::new(params.default_bg, params.default_fg);
+        let normal = self.group_style(state, "NormalFloat", default);
+        let title = self.group_style(state, "Title", normal);
+        let footer = self.group_style(state, "MoreMsg", normal);
+
+        let lines = scrollback.rows(width);
+        let first = scrollback.first_row(width, height);
+        let shown = &lines[first..(first + height).min(lines.len())];
+        let position = if lines.is_empty() {
+            " No messages".to_string()
+        } else {
+            format!(" {}-{}/{}", first + 1, first + shown.len(), lines.len())
+        };
+        let footer_text = format!("{}  j/k scroll  gg/G top/bottom  q close", position);
+
+        let mut scene = Scene::new();
+        scene.fill(CellRect::new(0, 0, cols, rows), normal.bg);
+        scene.label(0, 0, cols, [(" Messages", title)], normal.bg);
+        for (i, row) in shown.iter().enumerate() {
+            let style = match row.group {
+                Some(group) => self.group_style(state, group, normal),
+                None => normal,
+            };
+            scene.label(1 + i, 1, width, [(row.text.as_str(), style)], normal.bg);
+        }
+        scene.label(
+            rows - 1,
+            0,
+            cols,
+            [(footer_text.as_str(), footer)],
+            normal.bg,
+        );
+        self.paint_scene(ctx, params, &scene);
+    }
 
-        if c.is_empty() || c.is_wide_spacer() {
+    /// Paint a modal question centered over the grid: a title and header
+    /// lines, then the choices.
+    fn paint_prompt(
+        &mut self,
+        ctx: &GpuContext,
+        state: &EditorState,
+        params: RenderParams,
+        prompt: Prompt<'_>,
+    ) {
+        let Prompt {
+            title: title_text,
+            title_group,
+            header,
+            lines,
+            selected: selected_line,
+        } = prompt;
+        let grid = state.main_grid();
+        let (cols, rows) = (grid.width(), grid.height());
+        if cols == 0 || rows < 3 {
             return;
         }
 
-        let cell_attrs = state.highlights.get(c.highlight_id);
-        let text_color = match (mode.attr_id, hl.foreground) {
-            (1.., Some(fg)) => u32_to_linear_rgba(fg.0 >> 8),
-            _ => cell_attrs
-                .background
-                .map(|c| u32_to_linear_rgba(c.0 >> 8))
-                .unwrap_or(params.default_bg),
+        let default = OverlayStyle::new(params.default_bg, params.default_fg);
+        let normal = self.group_style(state, "NormalFloat", default);
+        let selected = self.group_style(state, "PmenuSel", normal.inverted());
+        let title = self.group_style(state, title_group, normal);
+
+        let width = header
+            .iter()
+            .chain(lines)
+            .map(|l| l.chars().count() + 1)
+            .max()
+            .unwrap_or(0)
+            .max(title_text.len() + 1)
+            .min(cols);
+        let header_height = header.len() + 2;
+        let height = (lines.len() + header_height).min(rows);
+        let (top, left) = ((rows - height) / 2, (cols - width) / 2);
+
+        let mut scene = Scene::new();
+        scene.fill(
+            CellRect::new(top, left, width, header_height.min(height)),
+            normal.bg,
+        );
+        scene.label(top, left, width, [(title_text, title)], normal.bg);
+        for (i, line) in header.iter().enumerate().take(height.saturating_sub(1)) {
+            scene.label(
+                top + 1 + i,
+                left,
+                width,
+                [(line.as_str(), normal)],
+                normal.bg,
+            );
+        }
+
+        let list = ListView {
+            rect: CellRect::new(
+                top + header_height.min(height),
+                left,
+                width,
+                height.saturating_sub(header_height),
+            ),
+            len: lines.len(),
+            first: 0,
+            selected: Some(selected_line),
+        };
+        list.paint_frame(&mut scene, (normal.bg, selected.bg), (normal.bg, normal.bg));
+        for row in list.rows() {
+            let style = if row.selected { selected } else { normal };
+            scene.label(
+                row.rect.row,
+                row.rect.col,
+                row.rect.width,
+                [(lines[row.index].as_str(), style)],
+                style.bg,
+            );
+        }
+        self.paint_scene(ctx, params, &scene);
+    }
+
+    /// Until Neovim's first flush, batch the startup splash in place of the
+    /// grid, over the whole window in `params.splash_bg`.
+    pub fn prepare_splash_screen(
+        &mut self,
+        ctx: &GpuContext,
+        state: &EditorState,
+        params: RenderParams,
+    ) {
+        self.atlas.begin_frame();
+        loop {
+            let generation = self.atlas.generation();
+            let evictions = self.atlas.evictions();
+            self.batcher.clear();
+            if let Some((width, height)) = params.backdrop {
+                self.batcher
+                    .push_background(0.0, 0.0, width, height, params.splash_bg);
+            }
+            let scene = self.splash_scene(state, params, 1.0);
+            self.paint_scene(ctx, params, &scene);
+            self.sync_atlas_generation();
+            if self.atlas.generation() == generation && self.atlas.evictions() == evictions {
+                break;
+            }
+        }
+        self.batcher.upload(ctx);
+    }
+
+    /// The startup splash over the grid at `opacity`: the logo in the
+    /// middle and, once startup is slow, a spinner and what it waits on
+    /// below.
+    fn splash_scene(&self, state: &EditorState, params: RenderParams, opacity: f32) -> Scene {
+        const LOGO: &str = "gui.nvim";
+        let grid = state.main_grid();
+        let (cols, rows) = (grid.width(), grid.height());
+        let mut scene = Scene::new();
+        if cols == 0 || rows == 0 {
+            return scene;
+        }
+
+        let style = OverlayStyle::new(params.splash_bg, params.default_fg).faded(opacity);
+        scene.fill(CellRect::new(0, 0, cols, rows), style.bg);
+        let logo_row = rows.saturating_sub(1) / 2;
+        let width = LOGO.len().min(cols);
+        scene.label(
+            logo_row,
+            (cols - width) / 2,
+            width,
+            [(LOGO, style)],
+            style.bg,
+        );
+        if let Some(line) = state
+            .splash
+            .as_ref()
+            .and_then(|s| splash_line(s, state.busy))
+        {
+            let width = line.chars().count().min(cols);
+            if logo_row + 2 < rows {
+                scene.label(
+                    logo_row + 2,
+                    (cols - width) / 2,
+                    width,
+                    [(line.as_str(), style)],
+                    style.bg,
+                );
+            }
+        }
+        scene
+    }
+
+    /// Draw the splash over the grid: the startup splash fading into the
+    /// first frame, or the hint centered over the last frame while
+    /// reconnecting.
+    fn prepare_splash(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        if let Some(fade) = &state.splash_fade {
+            let scene = self.splash_scene(state, params, fade.opacity);
+            self.paint_scene(ctx, params, &scene);
+            return;
+        }
+        let Some(hint) = state
+            .splash
+            .as_ref()
+            .and_then(|s| splash_line(s, state.busy))
+        else {
+            return;
         };
+        let grid = state.main_grid();
+        let (cols, rows) = (grid.width(), grid.height());
+        let width = hint.chars().count().min(cols);
+        if width == 0 || rows == 0 {
+            return;
+        }
 
-        let style = Style::from_flags(
-            cell_attrs.style.contains(StyleFlags::BOLD),
-            cell_attrs.style.contains(StyleFlags::ITALIC),
+        let style = OverlayStyle::new(params.default_bg, params.default_fg);
+        let mut scene = Scene::new();
+        scene.label(
+            rows / 2,
+            (cols - width) / 2,
+            width,
+            [(hint.as_str(), style)],
+            style.bg,
         );
+        self.paint_scene(ctx, params, &scene);
+    }
+
+    /// While files are dragged over the window, say that dropping opens them.
+    fn prepare_file_hover(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        const HINT: &str = " Drop to open ";
+        if !state.file_hover {
+            return;
+        }
+        let grid = state.main_grid();
+        let (cols, rows) = (grid.width(), grid.height());
+        let width = HINT.chars().count().min(cols);
+        if width == 0 || rows == 0 {
+            return;
+        }
+
+        let style = OverlayStyle::new(params.default_bg, params.default_fg).inverted();
+        let (row, col) = (rows / 2, (cols - width) / 2);
+        let mut scene = Scene::new();
+        scene.fill(CellRect::row(row, col, width), style.bg);
+        scene.label(row, col, width, [(HINT, style)], style.bg);
+        self.paint_scene(ctx, params, &scene);
+    }
+
+    /// After zooming, show the font size centered near the top of the grid.
+    fn prepare_zoom_indicator(
+        &mut self,
+        ctx: &GpuContext,
+        state: &EditorState,
+        params: RenderParams,
+    ) {
+        let Some(indicator) = &state.zoom_indicator else {
+            return;
+        };
+        let label = indicator.label();
+        let grid = state.main_grid();
+        let (cols, rows) = (grid.width(), grid.height());
+        let width = label.chars().count().min(cols);
+        if width == 0 || rows == 0 {
+            return;
+        }
+
+        let style = OverlayStyle::new(params.default_bg, params.default_fg)
+            .inverted()
+            .faded(indicator.opacity);
+        // A row down from the top, where it covers little but the tabline.
+        let (row, col) = (usize::from(rows > 1), (cols - width) / 2);
+        let mut scene = Scene::new();
+        scene.fill(CellRect::row(row, col, width), style.bg);
+        scene.label(row, col, width, [(label.as_str(), style)], style.bg);
+        self.paint_scene(ctx, params, &scene);
+    }
+
+    /// Draw `:GuiPerfOverlay` in the top right corner of the grid.
+    fn prepare_perf_overlay(
+        &mut self,
+        ctx: &GpuContext,
+        state: &EditorState,
+        params: RenderParams,
+    ) {
+        if self.perf_overlay.is_empty() {
+            return;
+        }
+        let grid = state.main_grid();
+        let (cols, rows) = (grid.width(), grid.height());
+        // A column of padding on either side.
+        let longest = self.perf_overlay.iter().map(|line| line.chars().count());
+        let width = (longest.max().unwrap_or(0) + 2).min(cols);
+        let height = self.perf_overlay.len().min(rows);
+        if width < 3 || height == 0 {
+            return;
+        }
+
+        let default = OverlayStyle::new(params.default_bg, params.default_fg);
+        let style = self.group_style(state, "NormalFloat", default);
+        let col = cols - width;
+        let mut scene = Scene::new();
+        for (row, line) in self.perf_overlay.iter().take(height).enumerate() {
+            scene.fill(CellRect::row(row, col, width), style.bg);
+            scene.label(row, col + 1, width - 2, [(line.as_str(), style)], style.bg);
+        }
+        self.paint_scene(ctx, params, &scene);
+    }
+
+    /// Draw ext_messages state on the overlay layer: the `:messages` history
+    /// panel, a floating stack of recent messages in the bottom right, and
+    /// the bottom row with either the cmdline or showmode/showcmd/ruler.
+    fn prepare_messages(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        let grid = state.main_grid();
+        let (cols, rows) = (grid.width(), grid.height());
+        if cols == 0 || rows == 0 {
+            return;
+        }
+        let bottom = rows - 1;
+
+        let default = OverlayStyle::new(params.default_bg, params.default_fg);
+        let msg_area = self.group_style(state, "MsgArea", default);
+        // The message stack stands in for Neovim's message float, so it
+        // blends like other floats do.
+        let float = self
+            .group_style(state, "NormalFloat", msg_area)
+            .blended(state.options().winblend());
+        let mut scene = Scene::new();
+
+        let messages = &state.messages;
+        if let Some(history) = &messages.history {
+            let lines: Vec<_> = history.iter().flat_map(Message::lines).collect();
+            let shown = lines.len().min(bottom);
+            let top = bottom - shown;
+            for (i, line) in lines[lines.len() - shown..].iter().enumerate() {
+                scene.fill(CellRect::row(top + i, 0, cols), msg_area.bg);
+                let spans = self.chunk_spans(state, line, msg_area, 1.0);
+                scene.label(top + i, 0, cols, spans, msg_area.bg);
+            }
+        } else if !messages.shown.is_empty() {
+            // Newest lines sit closest to the bottom; keep the stack to half
+            // the screen so it never buries the buffer.
+            let lines: Vec<_> = messages
+                .shown
+                .iter()
+                .flat_map(|m| m.lines().into_iter().map(move |l| (l, m.opacity)))
+                .collect();
+            let shown = lines.len().min(bottom.max(1) / 2).max(1).min(bottom);
+            let lines = &lines[lines.len() - shown..];
+            let text_width = lines
+                .iter()
+                .map(|(l, _)| l.iter().map(|c| c.text.chars().count()).sum::<usize>())
+                .max()
+                .unwrap_or(0);
+            let width = (text_width + 2).min(cols);
+            let left = cols - width;
+            let top = bottom - shown;
+            for (i, (line, opacity)) in lines.iter().enumerate() {
+                let faded = float.faded(*opacity);
+                scene.fill(CellRect::row(top + i, left, width), faded.bg);
+                let spans = self.chunk_spans(state, line, float, *opacity);
+                scene.label(top + i, left + 1, width.saturating_sub(2), spans, faded.bg);
+            }
+        }
+
+        if let Some(cmdline) = state.cmdline.active() {
+            scene.fill(CellRect::row(bottom, 0, cols), msg_area.bg);
+            let prefix = cmdline.prefix();
+            let used = scene.label(bottom, 0, cols, [(prefix.as_str(), msg_area)], msg_area.bg);
+            let spans = self.chunk_spans(state, &cmdline.content, msg_area, 1.0);
+            scene.label(bottom, used, cols - used, spans, msg_area.bg);
+
+            let caret_col = cmdline.cursor_col().min(cols - 1);
+            if let Some(c) = &cmdline.special_char {
+                scene.fill(CellRect::row(bottom, caret_col, 1), msg_area.bg);
+                scene.label(bottom, caret_col, 1, [(c.as_str(), msg_area)], msg_area.bg);
+            }
+            scene.caret(bottom, caret_col, msg_area.fg);
+            self.paint_scene(ctx, params, &scene);
+            return;
+        }
+
+        let width =
+            |chunks: &[StyledChunk]| chunks.iter().map(|c| c.text.chars().count()).sum::<usize>();
+        let ruler_width = width(&messages.ruler).min(cols);
+        let ruler_col = cols - ruler_width;
+        let showcmd_width = width(&messages.showcmd).min(ruler_col.saturating_sub(1));
+        let showcmd_col = ruler_col.saturating_sub(showcmd_width + 1);
+        let showmode_width = width(&messages.showmode).min(showcmd_col);
+
+        for (chunks, col, width) in [
+            (&messages.showmode, 0, showmode_width),
+            (&messages.showcmd, showcmd_col, showcmd_width),
+            (&messages.ruler, ruler_col, ruler_width),
+        ] {
+            if width == 0 {
+                continue;
+            }
+            scene.fill(CellRect::row(bottom, col, width), msg_area.bg);
+            let spans = self.chunk_spans(state, chunks, msg_area, 1.0);
+            scene.label(bottom, col, width, spans, msg_area.bg);
+        }
+        self.paint_scene(ctx, params, &scene);
+    }
+
+    /// Draw the tab bar (ext_tabline) in the top padding strip, with labels
+    /// centered vertically. Colors come from TabLine, TabLineSel and
+    /// TabLineFill like Neovim's own tabline.
+    fn prepare_tabline(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        let cols = state.main_grid().width();
+        let tabs = state.tabline.layout(cols);
+        if tabs.is_empty() {
+            return;
+        }
+
+        let default = OverlayStyle::new(params.default_bg, params.default_fg);
+        let fill = self.group_style(state, "TabLineFill", default);
+        let normal = self.group_style(state, "TabLine", fill);
+        let selected = self.group_style(
+            state,
+            "TabLineSel",
+            OverlayStyle {
+                font: Style::Bold,
+                ..default
+            },
+        );
+
+        let cell_width = self.metrics.cell_width;
+        let height = params.top_strip;
+        let text_y = ((height - self.metrics.cell_height) / 2.0).max(0.0);
+        let start = state.tabline.inset_cols;
+        self.batcher.push_overlay_background(
+            start as f32 * cell_width + params.x_offset,
+            0.0,
+            cols.saturating_sub(start) as f32 * cell_width,
+            height,
+            fill.bg,
+        );
+
+        for tab in &tabs {
+            let OverlayStyle { bg, fg, font } = if tab.index == state.tabline.current {
+                selected
+            } else {
+                normal
+            };
+            let x = tab.col as f32 * cell_width + params.x_offset;
+            self.batcher
+                .push_overlay_background(x, 0.0, tab.width as f32 * cell_width, height, bg);
+            let text_run = TextRun {
+                text: &tab.label,
+                style: font,
+            };
+            let shaped = self
+                .shaper
+                .shape_with_collection(&text_run, &mut self.collection);
+            self.render_glyphs(ctx, Layer::Overlay, x, text_y, &shaped, fg, None, &[]);
+        }
+    }
+
+    /// Draw the completion menu (ext_popupmenu) on the overlay layer. Colors
+    /// come from the Pmenu highlight family reported by hl_group_set, so the
+    /// menu follows the colorscheme like Neovim's built-in one.
+    fn prepare_popupmenu(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        let (Some(pum), Some(layout)) = (&state.popupmenu, state.popupmenu_layout()) else {
+            return;
+        };
+
+        let default = OverlayStyle::new(params.default_bg, params.default_fg);
+        let normal = self.group_style(state, "Pmenu", default);
+        let selected = self.group_style(state, "PmenuSel", normal.inverted());
+        let kind = [
+            self.group_style(state, "PmenuKind", normal),
+            self.group_style(state, "PmenuKindSel", selected),
+        ];
+        let extra = [
+            self.group_style(state, "PmenuExtra", normal),
+            self.group_style(state, "PmenuExtraSel", selected),
+        ];
+        let scrollbar = self.group_style(state, "PmenuSbar", normal).bg;
+        let thumb = self.group_style(state, "PmenuThumb", normal.inverted()).bg;
+
+        // 'pumblend' lets the buffer show through the menu.
+        let blend = state.options().pumblend();
+        let (normal, selected) = (normal.blended(blend), selected.blended(blend));
+        let kind = kind.map(|style| style.blended(blend));
+        let extra = extra.map(|style| style.blended(blend));
+        let alpha = blend_alpha(blend);
+        let (scrollbar, thumb) = (premultiply(scrollbar, alpha), premultiply(thumb, alpha));
+
+        let list = ListView {
+            rect: CellRect::new(layout.row, layout.col, layout.width, layout.height),
+            len: pum.items.len(),
+            first: layout.first,
+            selected: pum.selected,
+        };
+        let mut scene = Scene::new();
+        list.paint_frame(&mut scene, (normal.bg, selected.bg), (scrollbar, thumb));
+
+        // Column positions in cells, each followed by one cell of padding
+        let text_width = list.content_width();
+        let kind_col = 1 + layout.word_width + 1;
+        let menu_col = if layout.kind_width > 0 {
+            kind_col + layout.kind_width + 1
+        } else {
+            kind_col
+        };
+
+        for row in list.rows() {
+            let item = &pum.items[row.index];
+            let sel = usize::from(row.selected);
+            let style = if row.selected { selected } else { normal };
+
+            let columns = [
+                (&item.word, 1, layout.word_width, style),
+                (&item.kind, kind_col, layout.kind_width, kind[sel]),
+                (&item.menu, menu_col, layout.menu_width, extra[sel]),
+            ];
+            for (text, col, width, column_style) in columns {
+                let fits = width.min(text_width.saturating_sub(col + 1));
+                if text.is_empty() || fits == 0 {
+                    continue;
+                }
+                scene.label(
+                    row.rect.row,
+                    row.rect.col + col,
+                    fits,
+                    [(text.as_str(), column_style)],
+                    style.bg,
+                );
+            }
+        }
+        self.paint_scene(ctx, params, &scene);
+    }
+
+    /// Draw the IME preedit string at the cursor on the overlay layer, so it
+    /// covers whatever the grid has underneath. The text is underlined as
+    /// platform IMEs do, with a thin caret at the IME cursor position.
+    fn prepare_preedit(&mut self, ctx: &GpuContext, state: &EditorState, params: RenderParams) {
+        let Some(preedit) = &state.preedit else {
+            return;
+        };
+
+        let cursor = &state.cursor;
+        if cursor.grid != 1 {
+            return;
+        }
+
+        let cell_width = self.metrics.cell_width;
+        let cell_height = self.metrics.cell_height;
+        let x = cursor.col as f32 * cell_width + params.x_offset;
+        let y = cursor.row as f32 * cell_height + params.y_offset;
 
         let text_run = TextRun {
-            text: &c.text,
-            style,
+            text: &preedit.text,
+            style: Style::Regular,
         };
         let shaped = self
             .shaper
             .shape_with_collection(&text_run, &mut self.collection);
-        self.render_glyphs(ctx, geom.x, geom.y, &shaped, text_color);
+
+        let advances: Vec<f32> = shaped
+            .iter()
+            .map(|g| compute_glyph_advance(g, cell_width, self.advance_scale))
+            .collect();
+        let width = advances.iter().sum::<f32>().max(cell_width);
+
+        self.batcher
+            .push_overlay_background(x, y, width, cell_height, params.default_bg);
+
+        let metrics = self.metrics;
+        let geom = compute_decoration_geometry(
+            x,
+            y,
+            width,
+            cell_height,
+            metrics.descent,
+            metrics.underline_position,
+            metrics.underline_thickness.max(1.0),
+            metrics.strikeout_position,
+            metrics.strikeout_thickness,
+            UnderlineStyle::Single,
+            false,
+        );
+        for line in &geom.lines {
+            self.batcher.push_overlay_background(
+                line.x,
+                line.y,
+                line.width,
+                line.height,
+                params.default_fg,
+            );
+        }
+
+        if let Some((caret, _)) = preedit.cursor {
+            let caret_x: f32 = shaped
+                .iter()
+                .zip(&advances)
+                .filter(|(g, _)| (g.cluster as usize) < caret)
+                .map(|(_, a)| a)
+                .sum();
+            let caret_width = (cell_width / 8.0).max(1.0);
+            self.batcher.push_overlay_background(
+                x + caret_x,
+                y,
+                caret_width,
+                cell_height,
+                params.default_fg,
+            );
+        }
+
+        self.render_glyphs(
+            ctx,
+            Layer::Overlay,
+            x,
+            y,
+            &shaped,
+            params.default_fg,
+            None,
+            &[],
+        );
     }
 }
 
+/// The spinner and the hint of a splash, such as `⠋ Loading plugins…`,
+/// once startup has been slow for a while.
+fn splash_line(splash: &Splash, busy: bool) -> Option<String> {
+    Some(format!("{} {}", splash.spinner()?, splash.hint(busy)?))
+}
+
+/// The character of a cell's text, if it is exactly one.
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    let c = chars.next()?;
+    chars.next().is_none().then_some(c)
+}
+
+/// Decoration of cells with the given highlight, if any. Underlines use the
+/// special color when set, falling back to the foreground.
+/// Underline and strikethrough of a cell. Cells of the link under the
+/// pointer, `hovered_link`, are underlined if they aren't already.
+fn cell_decoration(
+    attrs: &HighlightAttributes,
+    fg: [f32; 4],
+    hovered_link: Option<&str>,
+) -> Option<Decoration> {
+    let mut underline_style = attrs.underline_style();
+    if underline_style == UnderlineStyle::None
+        && hovered_link.is_some()
+        && attrs.url.as_deref() == hovered_link
+    {
+        underline_style = UnderlineStyle::Single;
+    }
+    let has_strikethrough = attrs.has_strikethrough();
+    if underline_style == UnderlineStyle::None && !has_strikethrough {
+        return None;
+    }
+
+    Some(Decoration {
+        underline_style,
+        has_strikethrough,
+        underline_color: attrs
+            .special
+            .map(|c| u32_to_linear_rgba(c.0 >> 8))
+            .unwrap_or(fg),
+        strikethrough_color: fg,
+    })
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum GridRendererError {
     #[error("Face error: {0}")]
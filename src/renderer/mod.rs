@@ -1,28 +1,40 @@
 mod atlas;
+mod background;
 mod batch;
+mod capture;
 mod color;
 mod context;
 pub mod font;
 mod geometry;
 mod grid_renderer;
+pub mod overlay;
+mod perf_overlay;
 mod pipeline;
+mod row_cache;
+mod support;
 
+pub use capture::CaptureError;
 pub use context::{GpuContext, GpuContextError};
 pub use grid_renderer::GridRendererError;
+pub use support::GpuSupport;
 
-use color::{u32_to_linear_rgba, DEFAULT_BG_COLOR, DEFAULT_FG_COLOR};
-use grid_renderer::{GridRenderer, RenderParams};
+pub(crate) use background::png;
+
+use background::Background;
+use color::{linear_to_srgb, premultiply, u32_to_linear_rgba, DEFAULT_BG_COLOR, DEFAULT_FG_COLOR};
+use geometry::{compute_damage_rect, ScissorRect};
+use grid_renderer::{GridRenderer, PrepareStats, RenderParams};
+use perf_overlay::FrameTimes;
 use pipeline::RenderPipeline;
 
 use std::sync::Arc;
-
-#[cfg(feature = "perf-stats")]
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
-use crate::config::Config;
-use crate::editor::EditorState;
+use crate::config::{Config, VsyncMode};
+use crate::editor::{Damage, EditorState, Splash};
+use crate::layout::WindowLayout;
 
 pub struct Renderer {
     ctx: GpuContext,
@@ -33,13 +45,164 @@ pub struct Renderer {
     atlas_bind_group_generation: u64,
     default_bg: [f32; 4],
     default_fg: [f32; 4],
+    /// Opacity of the default background (`[window] opacity`).
+    opacity: f32,
+    /// Scale factor the font is rasterized for.
+    scale_factor: f64,
+    /// Offscreen copy of the last frame, so a frame that only changes a few
+    /// grid rows redraws just those and copies the result to the surface.
+    /// wgpu has no way to pass damage hints on to the compositor, so the
+    /// whole surface is still presented. `None` for translucent windows
+    /// (the background can't be painted over old pixels) and surfaces that
+    /// don't accept copies.
+    canvas: Option<Canvas>,
+    /// Set when the canvas no longer matches the last frame.
+    full_redraw: bool,
+    /// `[window] background_image`, drawn before the grid.
+    background: Option<Background>,
+    /// The last frame presented, to skip presenting it again when a redraw
+    /// changes nothing visible.
+    last_frame: Option<FrameFingerprint>,
+    /// Frames presented since startup.
+    frame_count: u64,
+    /// Frames skipped for repeating the one on screen.
+    frames_skipped: u64,
+    /// Accumulated CPU time spent in `render()`.
+    frame_time_total: Duration,
+    /// Instance data written to the GPU since startup.
+    uploaded_bytes: u64,
+    /// Times of the last frames presented, for `:GuiPerfOverlay`.
+    frame_times: FrameTimes,
+    /// `[window] splash_background`, premultiplied like `default_bg`.
+    splash_bg: Option<[f32; 4]>,
+    mode: RenderMode,
+}
+
+/// How much of the last frame the renderer reuses, switched at runtime
+/// with `:GuiSet renderer`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Unchanged rows come from the row cache, only damaged rows are
+    /// redrawn into the canvas, and a frame like the one on screen isn't
+    /// presented again.
+    #[default]
+    Optimized,
+    /// Every row shaped and every frame drawn and presented in full, to
+    /// tell whether a glitch comes from one of those shortcuts.
+    Naive,
+}
+
+impl RenderMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "optimized" => Some(Self::Optimized),
+            "naive" => Some(Self::Naive),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Optimized => "optimized",
+            Self::Naive => "naive",
+        }
+    }
+}
+
+/// What a frame draws: its instances, the atlas they sample and the part
+/// of the canvas they are limited to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameFingerprint {
+    content: u64,
+    atlas_generation: u64,
+    damage_rect: Option<ScissorRect>,
+}
+
+struct Canvas {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Canvas {
+    fn new(ctx: &GpuContext) -> Self {
+        let size = ctx.size();
+        let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Canvas"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ctx.format(),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { texture, view }
+    }
+
+    fn bytes(&self) -> u64 {
+        let size = self.texture.size();
+        size.width as u64 * size.height as u64 * 4
+    }
+}
+
+/// Lifetime renderer counters, reported by `:GuiStats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RendererStats {
+    pub frames: u64,
+    /// Redraws that would have presented the frame already on screen.
+    pub frames_skipped: u64,
+    pub frame_time_total: Duration,
+    pub glyph_cache_hits: u64,
+    pub glyph_cache_misses: u64,
+    pub shaping_cache_hits: u64,
+    pub shaping_cache_misses: u64,
+    /// Instance data written to the GPU, only the parts that changed
+    /// from frame to frame.
+    pub uploaded_bytes: u64,
+    /// Atlas texture, canvas, background image and instance buffers. Excludes the swapchain and
+    /// driver overhead, so treat it as a lower bound.
+    pub gpu_memory_bytes: u64,
 }
 
 impl Renderer {
     pub async fn new(window: Arc<Window>, config: Config) -> Result<Self, RendererError> {
         let scale_factor = window.scale_factor();
-        let ctx = GpuContext::new(window, config.performance.vsync).await?;
-        let grid_renderer = GridRenderer::new(&ctx, &config.font, scale_factor)?;
+        let opacity = config.window.opacity();
+        let ctx = GpuContext::new(
+            window,
+            config.performance.vsync,
+            opacity < 1.0,
+            config.font.text_blend,
+        )
+        .await?;
+        Self::from_context(ctx, config, scale_factor)
+    }
+
+    /// Creates a renderer without a window, drawing `size` pixel frames into
+    /// its canvas with `render_offscreen`.
+    pub async fn headless(
+        adapter: &wgpu::Adapter,
+        size: PhysicalSize<u32>,
+        config: Config,
+        scale_factor: f64,
+    ) -> Result<Self, RendererError> {
+        let ctx = GpuContext::headless(adapter, size, config.font.text_blend).await?;
+        Self::from_context(ctx, config, scale_factor)
+    }
+
+    fn from_context(
+        ctx: GpuContext,
+        config: Config,
+        scale_factor: f64,
+    ) -> Result<Self, RendererError> {
+        let opacity = config.window.opacity();
+        let mut grid_renderer = GridRenderer::new(&ctx, &config.font, scale_factor)?;
+        grid_renderer.set_cursor_settings(config.cursor.clone());
         let (cell_width, cell_height) = grid_renderer.cell_size();
         let pipeline = RenderPipeline::new(&ctx, cell_width, cell_height);
 
@@ -50,10 +213,21 @@ impl Renderer {
         );
 
         // Default colors in linear space
-        let default_bg = u32_to_linear_rgba(DEFAULT_BG_COLOR);
+        let default_bg = premultiply(u32_to_linear_rgba(DEFAULT_BG_COLOR), opacity);
         let default_fg = u32_to_linear_rgba(DEFAULT_FG_COLOR);
 
         let atlas_bind_group_generation = grid_renderer.atlas().generation();
+        // Headless renderers always draw into the canvas, even translucent.
+        let canvas = (ctx.is_headless() || (opacity >= 1.0 && ctx.surface_accepts_copies()))
+            .then(|| Canvas::new(&ctx));
+
+        let background = Background::from_settings(&ctx, &config.window).unwrap_or_else(|e| {
+            log::warn!("Background image disabled: {}", e);
+            None
+        });
+        if let Some(background) = &background {
+            background.update(&ctx, default_bg);
+        }
 
         Ok(Self {
             ctx,
@@ -63,6 +237,22 @@ impl Renderer {
             atlas_bind_group_generation,
             default_bg,
             default_fg,
+            opacity,
+            scale_factor,
+            canvas,
+            full_redraw: true,
+            background,
+            last_frame: None,
+            frame_count: 0,
+            frames_skipped: 0,
+            uploaded_bytes: 0,
+            frame_time_total: Duration::ZERO,
+            frame_times: FrameTimes::default(),
+            splash_bg: config
+                .window
+                .splash_background()
+                .map(|color| premultiply(u32_to_linear_rgba(color), opacity)),
+            mode: RenderMode::default(),
         })
     }
 
@@ -70,16 +260,78 @@ impl Renderer {
         self.grid_renderer.cell_size()
     }
 
+    /// From the top of a cell down to the baseline, in physical pixels.
+    pub fn baseline_offset(&self) -> f32 {
+        self.grid_renderer.baseline_offset()
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        self.ctx.adapter_info()
+    }
+
+    /// Makes the next frame redraw the whole window, whatever changed.
+    pub fn invalidate(&mut self) {
+        self.full_redraw = true;
+    }
+
+    pub fn set_mode(&mut self, mode: RenderMode) {
+        self.mode = mode;
+        self.grid_renderer
+            .set_reuse_rows(mode == RenderMode::Optimized);
+        self.full_redraw = true;
+    }
+
+    pub fn set_vsync(&mut self, vsync: VsyncMode) {
+        self.ctx.set_vsync(vsync);
+        self.full_redraw = true;
+    }
+
+    pub fn stats(&self) -> RendererStats {
+        let atlas = self.grid_renderer.atlas();
+        let (glyph_cache_hits, glyph_cache_misses) = atlas.cache_stats();
+        let (shaping_cache_hits, shaping_cache_misses) = self.grid_renderer.shaping_cache_stats();
+        RendererStats {
+            frames: self.frame_count,
+            frames_skipped: self.frames_skipped,
+            frame_time_total: self.frame_time_total,
+            glyph_cache_hits,
+            glyph_cache_misses,
+            shaping_cache_hits,
+            shaping_cache_misses,
+            uploaded_bytes: self.uploaded_bytes,
+            gpu_memory_bytes: atlas.texture_bytes()
+                + self.canvas.as_ref().map_or(0, Canvas::bytes)
+                + self.background.as_ref().map_or(0, Background::bytes)
+                + self.grid_renderer.batcher().buffer_bytes(),
+        }
+    }
+
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
         if size.width > 0 && size.height > 0 {
             self.ctx.resize(size);
             self.pipeline.resize(&self.ctx, size.width, size.height);
+            if self.canvas.is_some() {
+                self.canvas = Some(Canvas::new(&self.ctx));
+            }
+            if let Some(background) = &self.background {
+                background.update(&self.ctx, self.default_bg);
+            }
+            self.full_redraw = true;
         }
     }
 
     pub fn update_default_colors(&mut self, fg: u32, bg: u32) {
         self.default_fg = u32_to_linear_rgba(fg);
-        self.default_bg = u32_to_linear_rgba(bg);
+        self.default_bg = premultiply(u32_to_linear_rgba(bg), self.opacity);
+        if let Some(background) = &self.background {
+            background.update(&self.ctx, self.default_bg);
+        }
+        // The clear color isn't part of the batched instances.
+        self.full_redraw = true;
     }
 
     pub fn update_font(
@@ -89,14 +341,100 @@ impl Renderer {
     ) -> Result<(), RendererError> {
         self.grid_renderer
             .update_font(&self.ctx, &config.font, scale_factor)?;
+        self.scale_factor = scale_factor;
         let (cell_width, cell_height) = self.grid_renderer.cell_size();
         self.pipeline
             .update_cell_size(&self.ctx, cell_width, cell_height);
         // Font change clears the atlas, so force bind group refresh
         self.sync_atlas_bind_group();
+        self.full_redraw = true;
         Ok(())
     }
 
+    /// The default background as the frame's clear color. Post-multiplied
+    /// surfaces expect straight alpha, everything else premultiplied, and
+    /// surfaces that don't encode sRGB get it encoded.
+    fn clear_color(&self) -> wgpu::Color {
+        let [mut r, mut g, mut b, a] = self.default_bg;
+        let straight = self.ctx.alpha_mode() == wgpu::CompositeAlphaMode::PostMultiplied;
+        if (straight || self.ctx.encodes_srgb()) && a > 0.0 {
+            (r, g, b) = (r / a, g / a, b / a);
+        }
+        if self.ctx.encodes_srgb() {
+            (r, g, b) = (linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b));
+            if !straight {
+                (r, g, b) = (r * a, g * a, b * a);
+            }
+        }
+        wgpu::Color {
+            r: r as f64,
+            g: g as f64,
+            b: b as f64,
+            a: a as f64,
+        }
+    }
+
+    /// Hands the grid renderer what `:GuiPerfOverlay` shows, taken from the
+    /// frames before this one.
+    fn sync_perf_overlay(&mut self, state: &EditorState) {
+        let lines = if state.perf_overlay {
+            let batcher = self.grid_renderer.batcher();
+            let quads = [
+                batcher.backgrounds().instance_count(),
+                batcher.glyphs().instance_count(),
+                batcher.decorations().instance_count(),
+            ];
+            perf_overlay::overlay_lines(&self.frame_times, quads, &self.stats())
+        } else {
+            Vec::new()
+        };
+        self.grid_renderer.set_perf_overlay(lines);
+    }
+
+    /// Flips the frame when `layout` was mirrored or unmirrored since the
+    /// last one.
+    fn sync_mirror(&mut self, layout: &WindowLayout) {
+        if self.pipeline.mirrored() != layout.mirrored {
+            self.pipeline.set_mirrored(&self.ctx, layout.mirrored);
+            self.full_redraw = true;
+        }
+    }
+
+    /// The part of the canvas to redraw, or `None` to redraw everything.
+    fn damage_rect(&self, damage: &Damage, layout: &WindowLayout) -> Option<ScissorRect> {
+        if self.canvas.is_none()
+            || self.full_redraw
+            || self.opacity < 1.0
+            || self.mode == RenderMode::Naive
+        {
+            return None;
+        }
+        let Damage::Rows(rows) = damage else {
+            return None;
+        };
+        let size = self.ctx.size();
+        let (_, cell_height) = self.grid_renderer.cell_size();
+        let y_offset = layout.grid_rect(0, 0).y as f32;
+        Some(compute_damage_rect(
+            rows.clone(),
+            cell_height,
+            y_offset,
+            size.width,
+            size.height,
+        ))
+    }
+
+    /// The frame just batched. When it is the one last presented, and the
+    /// surface hasn't changed since, presenting it again would show nothing
+    /// new.
+    fn frame_fingerprint(&self, damage_rect: Option<ScissorRect>) -> FrameFingerprint {
+        FrameFingerprint {
+            content: self.grid_renderer.batcher().content_hash(),
+            atlas_generation: self.atlas_bind_group_generation,
+            damage_rect,
+        }
+    }
+
     /// Recreate the atlas bind group only when the atlas texture has changed
     /// (resize or clear), avoiding redundant GPU object creation every frame.
     fn sync_atlas_bind_group(&mut self) {
@@ -111,19 +449,202 @@ impl Renderer {
         }
     }
 
+    /// Draws the batches into `view`. With a `damage_rect`, the frame is
+    /// drawn over the previous one and limited to that region; otherwise
+    /// the view is cleared first.
+    fn encode_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        damage_rect: Option<ScissorRect>,
+    ) {
+        let load = match damage_rect {
+            Some(_) => wgpu::LoadOp::Load,
+            None => wgpu::LoadOp::Clear(self.clear_color()),
+        };
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Main Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+
+        if let Some(rect) = damage_rect {
+            render_pass.set_scissor_rect(rect.x, rect.y, rect.width, rect.height);
+        }
+        if let Some(background) = &self.background {
+            background.draw(&mut render_pass);
+        }
+        render_pass.set_pipeline(self.pipeline.pipeline());
+        render_pass.set_bind_group(0, self.pipeline.uniform_bind_group(), &[]);
+        render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+
+        let batcher = self.grid_renderer.batcher();
+
+        if !batcher.backgrounds().is_empty() {
+            render_pass.set_vertex_buffer(0, batcher.backgrounds().buffer().slice(..));
+            render_pass.draw(0..6, 0..batcher.backgrounds().instance_count());
+        }
+
+        if !batcher.glyphs().is_empty() {
+            render_pass.set_vertex_buffer(0, batcher.glyphs().buffer().slice(..));
+            render_pass.draw(0..6, 0..batcher.glyphs().instance_count());
+        }
+
+        if !batcher.decorations().is_empty() {
+            render_pass.set_vertex_buffer(0, batcher.decorations().buffer().slice(..));
+            render_pass.draw(0..6, 0..batcher.decorations().instance_count());
+        }
+
+        // Floating windows cover the grid, bottom to top within the batch.
+        if !batcher.floats().is_empty() {
+            render_pass.set_vertex_buffer(0, batcher.floats().buffer().slice(..));
+            render_pass.draw(0..6, 0..batcher.floats().instance_count());
+        }
+
+        // Overlay (IME preedit) is drawn last so it covers the grid.
+        if !batcher.overlay_backgrounds().is_empty() {
+            render_pass.set_vertex_buffer(0, batcher.overlay_backgrounds().buffer().slice(..));
+            render_pass.draw(0..6, 0..batcher.overlay_backgrounds().instance_count());
+        }
+
+        if !batcher.overlay_glyphs().is_empty() {
+            render_pass.set_vertex_buffer(0, batcher.overlay_glyphs().buffer().slice(..));
+            render_pass.draw(0..6, 0..batcher.overlay_glyphs().instance_count());
+        }
+    }
+
+    /// Batches the grid, or until Neovim's first flush the startup splash
+    /// alone: there is no screen to draw yet, and the grid would show
+    /// whatever the UI events before it left half done.
+    fn prepare_grid(&mut self, state: &EditorState, mut params: RenderParams) -> PrepareStats {
+        params.splash_bg = self.splash_bg.unwrap_or(self.default_bg);
+        if !state.splash.as_ref().is_some_and(Splash::covers_grid) {
+            return self.grid_renderer.prepare(&self.ctx, state, params);
+        }
+        let size = self.ctx.size();
+        params.backdrop = Some((size.width as f32, size.height as f32));
+        self.grid_renderer
+            .prepare_splash_screen(&self.ctx, state, params);
+        PrepareStats::default()
+    }
+
+    /// Batches the frame and syncs the atlas bind group. Returns the part of
+    /// the canvas to redraw, as `damage_rect` does.
+    fn prepare_frame(&mut self, state: &EditorState, layout: &WindowLayout) -> Option<ScissorRect> {
+        self.sync_mirror(layout);
+        let damage_rect = self.damage_rect(&state.damage(), layout);
+        let mut params = RenderParams::new(self.default_bg, self.default_fg, layout);
+        // The background image covers the previous frame by itself.
+        if damage_rect.is_some() && self.background.is_none() {
+            let size = self.ctx.size();
+            params.backdrop = Some((size.width as f32, size.height as f32));
+        }
+        self.sync_perf_overlay(state);
+        self.prepare_grid(state, params);
+        self.uploaded_bytes += self.grid_renderer.batcher().written_bytes();
+        // Recreate atlas bind group only if the atlas texture changed
+        self.sync_atlas_bind_group();
+        damage_rect
+    }
+
+    /// Draws a frame into the canvas instead of the surface and waits for
+    /// the GPU to finish it. Only headless renderers are sure to have one.
+    pub fn render_offscreen(&mut self, state: &EditorState, layout: &WindowLayout) {
+        let frame_start = Instant::now();
+        let damage_rect = self.prepare_frame(state, layout);
+        let Some(canvas) = &self.canvas else {
+            return;
+        };
+
+        let mut encoder = self
+            .ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Offscreen Render Encoder"),
+            });
+        self.encode_pass(&mut encoder, &canvas.view, damage_rect);
+        self.ctx.queue.submit(std::iter::once(encoder.finish()));
+        if let Err(e) = self.ctx.device.poll(wgpu::PollType::wait_indefinitely()) {
+            log::warn!("Failed to wait for offscreen frame: {}", e);
+        }
+        self.full_redraw = false;
+
+        self.frame_count += 1;
+        self.frame_time_total += frame_start.elapsed();
+        self.frame_times.push(frame_start.elapsed());
+    }
+
+    /// Draws the whole frame into a fresh texture and returns it as a PNG,
+    /// for snapshots. Works whether or not there is a canvas.
+    pub fn capture_png(
+        &mut self,
+        state: &EditorState,
+        layout: &WindowLayout,
+    ) -> Result<Vec<u8>, CaptureError> {
+        self.full_redraw = true;
+        self.prepare_frame(state, layout);
+
+        let size = self.ctx.size();
+        let texture = self.ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.ctx.format(),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Render Encoder"),
+            });
+        self.encode_pass(&mut encoder, &view, None);
+        self.ctx.queue.submit(std::iter::once(encoder.finish()));
+
+        let image = capture::read_texture(&self.ctx, &texture)?;
+        Ok(background::png::encode(&image)?)
+    }
+
     #[cfg(feature = "perf-stats")]
     pub fn render(
         &mut self,
         state: &EditorState,
-        x_offset: f32,
-        y_offset: f32,
+        layout: &WindowLayout,
     ) -> Result<(), wgpu::SurfaceError> {
         let frame_start = Instant::now();
 
         // Phase 1: Prepare grid (batching, shaping, etc.)
         let prepare_start = Instant::now();
-        let params = RenderParams::new(self.default_bg, self.default_fg, x_offset, y_offset);
-        let prepare_stats = self.grid_renderer.prepare(&self.ctx, state, params);
+        self.sync_mirror(layout);
+        let damage_rect = self.damage_rect(&state.damage(), layout);
+        let mut params = RenderParams::new(self.default_bg, self.default_fg, layout);
+        // The background image covers the previous frame by itself.
+        if damage_rect.is_some() && self.background.is_none() {
+            let size = self.ctx.size();
+            params.backdrop = Some((size.width as f32, size.height as f32));
+        }
+        self.sync_perf_overlay(state);
+        let prepare_stats = self.prepare_grid(state, params);
+        self.uploaded_bytes += self.grid_renderer.batcher().written_bytes();
         let prepare_duration = prepare_start.elapsed();
 
         // Phase 2: Recreate atlas bind group only if the atlas texture changed
@@ -131,6 +652,13 @@ impl Renderer {
         self.sync_atlas_bind_group();
         let bind_group_duration = bind_group_start.elapsed();
 
+        let frame = self.frame_fingerprint(damage_rect);
+        let optimized = self.mode == RenderMode::Optimized;
+        if optimized && !self.full_redraw && self.last_frame == Some(frame) {
+            self.frames_skipped += 1;
+            return Ok(());
+        }
+
         // Phase 3: Get swap chain texture
         let swap_chain_start = Instant::now();
         let output = self.ctx.get_current_texture()?;
@@ -148,49 +676,16 @@ impl Renderer {
                 label: Some("Render Encoder"),
             });
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Main Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    depth_slice: None,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: self.default_bg[0] as f64,
-                            g: self.default_bg[1] as f64,
-                            b: self.default_bg[2] as f64,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-                multiview_mask: None,
-            });
-
-            render_pass.set_pipeline(self.pipeline.pipeline());
-            render_pass.set_bind_group(0, self.pipeline.uniform_bind_group(), &[]);
-            render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
-
-            let batcher = self.grid_renderer.batcher();
-
-            if !batcher.backgrounds().is_empty() {
-                render_pass.set_vertex_buffer(0, batcher.backgrounds().buffer().slice(..));
-                render_pass.draw(0..6, 0..batcher.backgrounds().instance_count());
-            }
-
-            if !batcher.glyphs().is_empty() {
-                render_pass.set_vertex_buffer(0, batcher.glyphs().buffer().slice(..));
-                render_pass.draw(0..6, 0..batcher.glyphs().instance_count());
-            }
-
-            if !batcher.decorations().is_empty() {
-                render_pass.set_vertex_buffer(0, batcher.decorations().buffer().slice(..));
-                render_pass.draw(0..6, 0..batcher.decorations().instance_count());
+        match &self.canvas {
+            Some(canvas) => {
+                self.encode_pass(&mut encoder, &canvas.view, damage_rect);
+                encoder.copy_texture_to_texture(
+                    canvas.texture.as_image_copy(),
+                    output.texture.as_image_copy(),
+                    canvas.texture.size(),
+                );
             }
+            None => self.encode_pass(&mut encoder, &view, None),
         }
         let encode_duration = encode_start.elapsed();
 
@@ -198,9 +693,14 @@ impl Renderer {
         let submit_start = Instant::now();
         self.ctx.queue.submit(std::iter::once(encoder.finish()));
         output.present();
+        self.full_redraw = false;
+        self.last_frame = Some(frame);
         let submit_duration = submit_start.elapsed();
 
         let frame_duration = frame_start.elapsed();
+        self.frame_count += 1;
+        self.frame_time_total += frame_duration;
+        self.frame_times.push(frame_duration);
 
         // Log performance metrics
         let batcher = self.grid_renderer.batcher();
@@ -214,16 +714,17 @@ impl Renderer {
             submit_duration.as_secs_f64() * 1000.0,
         );
         log::debug!(
-            "[PERF] Batches: {} bg, {} glyphs, {} deco | Grid: {}x{} ({} cells)",
+            "[PERF] Batches: {} bg, {} glyphs, {} deco, {:.1} KiB uploaded | Grid: {}x{} ({} cells)",
             batcher.backgrounds().instance_count(),
             batcher.glyphs().instance_count(),
             batcher.decorations().instance_count(),
+            batcher.written_bytes() as f64 / 1024.0,
             state.main_grid().width(),
             state.main_grid().height(),
             state.main_grid().width() * state.main_grid().height(),
         );
         log::debug!(
-            "[PERF] Prepare breakdown: cells={}, runs={}, shape_calls={}, glyphs_shaped={}, glyph_cache={}/{}, shaping_cache={}/{}",
+            "[PERF] Prepare breakdown: cells={}, runs={}, shape_calls={}, glyphs_shaped={}, glyph_cache={}/{}, shaping_cache={}/{}, rows_cached={}",
             prepare_stats.cells_processed,
             prepare_stats.runs_processed,
             prepare_stats.shape_calls,
@@ -232,6 +733,7 @@ impl Renderer {
             prepare_stats.glyph_cache_hits + prepare_stats.glyph_cache_misses,
             prepare_stats.shaping_cache_hits,
             prepare_stats.shaping_cache_hits + prepare_stats.shaping_cache_misses,
+            prepare_stats.rows_cached,
         );
         log::debug!(
             "[PERF] Prepare timing: backgrounds={:.2}ms, shaping={:.2}ms, glyph_lookup={:.2}ms, batching={:.2}ms",
@@ -248,15 +750,19 @@ impl Renderer {
     pub fn render(
         &mut self,
         state: &EditorState,
-        x_offset: f32,
-        y_offset: f32,
+        layout: &WindowLayout,
     ) -> Result<(), wgpu::SurfaceError> {
-        // Phase 1: Prepare grid (batching, shaping, etc.)
-        let params = RenderParams::new(self.default_bg, self.default_fg, x_offset, y_offset);
-        self.grid_renderer.prepare(&self.ctx, state, params);
+        let frame_start = Instant::now();
 
-        // Phase 2: Recreate atlas bind group only if the atlas texture changed
-        self.sync_atlas_bind_group();
+        // Phase 1 and 2: Prepare grid and atlas bind group
+        let damage_rect = self.prepare_frame(state, layout);
+
+        let frame = self.frame_fingerprint(damage_rect);
+        let optimized = self.mode == RenderMode::Optimized;
+        if optimized && !self.full_redraw && self.last_frame == Some(frame) {
+            self.frames_skipped += 1;
+            return Ok(());
+        }
 
         // Phase 3: Get swap chain texture
         let output = self.ctx.get_current_texture()?;
@@ -272,54 +778,27 @@ impl Renderer {
                 label: Some("Render Encoder"),
             });
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Main Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    depth_slice: None,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: self.default_bg[0] as f64,
-                            g: self.default_bg[1] as f64,
-                            b: self.default_bg[2] as f64,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-                multiview_mask: None,
-            });
-
-            render_pass.set_pipeline(self.pipeline.pipeline());
-            render_pass.set_bind_group(0, self.pipeline.uniform_bind_group(), &[]);
-            render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
-
-            let batcher = self.grid_renderer.batcher();
-
-            if !batcher.backgrounds().is_empty() {
-                render_pass.set_vertex_buffer(0, batcher.backgrounds().buffer().slice(..));
-                render_pass.draw(0..6, 0..batcher.backgrounds().instance_count());
-            }
-
-            if !batcher.glyphs().is_empty() {
-                render_pass.set_vertex_buffer(0, batcher.glyphs().buffer().slice(..));
-                render_pass.draw(0..6, 0..batcher.glyphs().instance_count());
-            }
-
-            if !batcher.decorations().is_empty() {
-                render_pass.set_vertex_buffer(0, batcher.decorations().buffer().slice(..));
-                render_pass.draw(0..6, 0..batcher.decorations().instance_count());
+        match &self.canvas {
+            Some(canvas) => {
+                self.encode_pass(&mut encoder, &canvas.view, damage_rect);
+                encoder.copy_texture_to_texture(
+                    canvas.texture.as_image_copy(),
+                    output.texture.as_image_copy(),
+                    canvas.texture.size(),
+                );
             }
+            None => self.encode_pass(&mut encoder, &view, None),
         }
 
         // Phase 5: Submit and present
         self.ctx.queue.submit(std::iter::once(encoder.finish()));
         output.present();
+        self.full_redraw = false;
+        self.last_frame = Some(frame);
+
+        self.frame_count += 1;
+        self.frame_time_total += frame_start.elapsed();
+        self.frame_times.push(frame_start.elapsed());
 
         Ok(())
     }
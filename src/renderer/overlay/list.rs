@@ -0,0 +1,190 @@
+use super::scene::{CellRect, Scene};
+
+/// Wrapping keyboard selection in a non-empty list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListState {
+    len: usize,
+    selected: usize,
+}
+
+impl ListState {
+    /// Returns None for an empty list.
+    pub fn new(len: usize) -> Option<Self> {
+        (len > 0).then_some(Self { len, selected: 0 })
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(self.len - 1);
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.len;
+    }
+
+    /// Selects `index` if it is in the list.
+    pub fn pick(&mut self, index: usize) -> bool {
+        let exists = index < self.len;
+        if exists {
+            self.selected = index;
+        }
+        exists
+    }
+}
+
+/// Scroll offset that keeps `selected` inside a view of `height` of `len`
+/// items, moving `scroll` as little as possible.
+pub fn scroll_into_view(
+    scroll: usize,
+    selected: Option<usize>,
+    height: usize,
+    len: usize,
+) -> usize {
+    if height == 0 {
+        return scroll;
+    }
+    let scroll = match selected {
+        Some(selected) if selected < scroll => selected,
+        Some(selected) if selected >= scroll + height => selected + 1 - height,
+        _ => scroll,
+    };
+    scroll.min(len.saturating_sub(height))
+}
+
+/// Scrollbar thumb (start row, length) for `len` items shown `height` at a
+/// time from `first`, or None when they all fit.
+pub fn scrollbar_thumb(len: usize, height: usize, first: usize) -> Option<(usize, usize)> {
+    (height > 0 && len > height).then(|| {
+        let thumb_len = (height * height / len).max(1);
+        let thumb_start = (first * height / len).min(height - thumb_len);
+        (thumb_start, thumb_len)
+    })
+}
+
+/// A visible row of a [`ListView`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListRow {
+    /// Item index.
+    pub index: usize,
+    /// Cells the row's content goes in, excluding the scrollbar.
+    pub rect: CellRect,
+    pub selected: bool,
+}
+
+/// A list placed on screen: `rect.height` items from `first`, with a
+/// one-cell scrollbar on the right when they don't all fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListView {
+    pub rect: CellRect,
+    pub len: usize,
+    pub first: usize,
+    pub selected: Option<usize>,
+}
+
+impl ListView {
+    pub fn scrollbar(&self) -> Option<(usize, usize)> {
+        scrollbar_thumb(self.len, self.rect.height, self.first)
+    }
+
+    /// Width left for item content.
+    pub fn content_width(&self) -> usize {
+        self.rect.width - usize::from(self.scrollbar().is_some())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = ListRow> + '_ {
+        let width = self.content_width();
+        (0..self.rect.height)
+            .map(move |i| (i, self.first + i))
+            .take_while(|&(_, index)| index < self.len)
+            .map(move |(i, index)| ListRow {
+                index,
+                rect: CellRect::row(self.rect.row + i, self.rect.col, width),
+                selected: self.selected == Some(index),
+            })
+    }
+
+    /// Draws the row backgrounds and the scrollbar. Row content is up to
+    /// the caller.
+    pub fn paint_frame(
+        &self,
+        scene: &mut Scene,
+        (normal, selected): ([f32; 4], [f32; 4]),
+        (track, thumb): ([f32; 4], [f32; 4]),
+    ) {
+        for row in self.rows() {
+            scene.fill(row.rect, if row.selected { selected } else { normal });
+        }
+        if let Some((start, len)) = self.scrollbar() {
+            let col = self.rect.col + self.content_width();
+            for i in 0..self.rect.height {
+                let color = if (start..start + len).contains(&i) {
+                    thumb
+                } else {
+                    track
+                };
+                scene.fill(CellRect::row(self.rect.row + i, col, 1), color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::overlay::Element;
+
+    #[test]
+    fn test_list_state_wraps() {
+        assert!(ListState::new(0).is_none());
+        let mut list = ListState::new(3).unwrap();
+        list.select_previous();
+        assert_eq!(list.selected(), 2);
+        list.select_next();
+        assert_eq!(list.selected(), 0);
+        assert!(list.pick(1));
+        assert!(!list.pick(3));
+        assert_eq!(list.selected(), 1);
+    }
+
+    #[test]
+    fn test_scroll_into_view() {
+        assert_eq!(scroll_into_view(0, Some(15), 10, 30), 6);
+        assert_eq!(scroll_into_view(6, Some(2), 10, 30), 2);
+        assert_eq!(scroll_into_view(6, None, 10, 30), 6);
+        assert_eq!(scroll_into_view(25, None, 10, 30), 20);
+    }
+
+    #[test]
+    fn test_scrollbar_thumb() {
+        assert_eq!(scrollbar_thumb(5, 10, 0), None);
+        assert_eq!(scrollbar_thumb(30, 10, 0), Some((0, 3)));
+        assert_eq!(scrollbar_thumb(30, 10, 20), Some((6, 3)));
+    }
+
+    #[test]
+    fn test_list_view_rows_and_frame() {
+        let view = ListView {
+            rect: CellRect::new(4, 2, 8, 3),
+            len: 5,
+            first: 1,
+            selected: Some(2),
+        };
+        let rows: Vec<_> = view.rows().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].index, 1);
+        assert_eq!(rows[1].rect, CellRect::row(5, 2, 7));
+        assert!(rows[1].selected);
+
+        let mut scene = Scene::new();
+        view.paint_frame(&mut scene, ([0.0; 4], [1.0; 4]), ([0.2; 4], [0.8; 4]));
+        let scrollbar_cells = scene
+            .elements()
+            .iter()
+            .filter(|e| matches!(e, Element::Fill { rect, .. } if rect.col == 9))
+            .count();
+        assert_eq!(scrollbar_cells, 3);
+    }
+}
@@ -0,0 +1,13 @@
+//! Widget layer for the overlays the GUI draws itself: the popupmenu, the
+//! startup dashboard, messages and the cmdline.
+//!
+//! Widgets lay out in cells and record what to draw into a [`Scene`];
+//! `GridRenderer::paint_scene` turns a scene into overlay quads and glyphs
+//! shaped like the grid's. Lists share selection, scrolling and scrollbar
+//! logic through [`ListState`] and [`ListView`].
+
+mod list;
+mod scene;
+
+pub use list::{scroll_into_view, scrollbar_thumb, ListRow, ListState, ListView};
+pub use scene::{CellRect, Element, OverlayStyle, Scene};
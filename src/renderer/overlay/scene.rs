@@ -0,0 +1,201 @@
+use crate::renderer::color::{blend_alpha, premultiply};
+use crate::renderer::font::Style;
+
+/// Colors and font style of overlay text. Colors are premultiplied linear
+/// RGBA, like everything else handed to the batcher.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverlayStyle {
+    pub bg: [f32; 4],
+    pub fg: [f32; 4],
+    pub font: Style,
+}
+
+impl OverlayStyle {
+    pub fn new(bg: [f32; 4], fg: [f32; 4]) -> Self {
+        Self {
+            bg,
+            fg,
+            font: Style::Regular,
+        }
+    }
+
+    /// Background and foreground swapped, as a fallback selection style.
+    pub fn inverted(self) -> Self {
+        Self {
+            bg: self.fg,
+            fg: self.bg,
+            ..self
+        }
+    }
+
+    /// The background made see-through by a 'pumblend' or 'winblend' of
+    /// `blend` percent. Text stays opaque so it remains readable.
+    pub fn blended(self, blend: u8) -> Self {
+        Self {
+            bg: premultiply(self.bg, blend_alpha(blend)),
+            ..self
+        }
+    }
+
+    /// Both colors scaled by `opacity`, for fading out.
+    pub fn faded(self, opacity: f32) -> Self {
+        Self {
+            bg: premultiply(self.bg, opacity),
+            fg: premultiply(self.fg, opacity),
+            ..self
+        }
+    }
+}
+
+/// A rectangle of cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellRect {
+    pub row: usize,
+    pub col: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl CellRect {
+    pub fn new(row: usize, col: usize, width: usize, height: usize) -> Self {
+        Self {
+            row,
+            col,
+            width,
+            height,
+        }
+    }
+
+    /// A single row of `width` cells.
+    pub fn row(row: usize, col: usize, width: usize) -> Self {
+        Self::new(row, col, width, 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    /// Solid fill.
+    Fill { rect: CellRect, color: [f32; 4] },
+    /// Text starting at a cell, already clipped to the space it has.
+    Text {
+        row: usize,
+        col: usize,
+        text: String,
+        style: OverlayStyle,
+    },
+    /// Thin text caret at the left edge of a cell.
+    Caret {
+        row: usize,
+        col: usize,
+        color: [f32; 4],
+    },
+}
+
+/// Overlay drawing commands, painted in order.
+#[derive(Debug, Default)]
+pub struct Scene {
+    elements: Vec<Element>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn elements(&self) -> &[Element] {
+        &self.elements
+    }
+
+    pub fn fill(&mut self, rect: CellRect, color: [f32; 4]) {
+        if rect.width > 0 && rect.height > 0 {
+            self.elements.push(Element::Fill { rect, color });
+        }
+    }
+
+    pub fn caret(&mut self, row: usize, col: usize, color: [f32; 4]) {
+        self.elements.push(Element::Caret { row, col, color });
+    }
+
+    /// Draws styled spans on `row` from `col`, clipped to `max_cols` cells.
+    /// A span's background is filled only where it differs from `base_bg`,
+    /// which the caller has already drawn. Returns the number of cells used.
+    pub fn label<'a>(
+        &mut self,
+        row: usize,
+        col: usize,
+        max_cols: usize,
+        spans: impl IntoIterator<Item = (&'a str, OverlayStyle)>,
+        base_bg: [f32; 4],
+    ) -> usize {
+        let mut used = 0;
+        for (text, style) in spans {
+            let fits = text.chars().count().min(max_cols - used);
+            if fits == 0 {
+                break;
+            }
+            if style.bg != base_bg {
+                self.fill(CellRect::row(row, col + used, fits), style.bg);
+            }
+            self.elements.push(Element::Text {
+                row,
+                col: col + used,
+                text: text.chars().take(fits).collect(),
+                style,
+            });
+            used += fits;
+        }
+        used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BG: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    const FG: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+    #[test]
+    fn test_label_clips_and_fills_differing_backgrounds() {
+        let normal = OverlayStyle::new(BG, FG);
+        let mut scene = Scene::new();
+        let used = scene.label(2, 1, 6, [("abcd", normal), ("efgh", normal.inverted())], BG);
+
+        assert_eq!(used, 6);
+        assert_eq!(
+            scene.elements(),
+            &[
+                Element::Text {
+                    row: 2,
+                    col: 1,
+                    text: "abcd".to_string(),
+                    style: normal,
+                },
+                Element::Fill {
+                    rect: CellRect::row(2, 5, 2),
+                    color: FG,
+                },
+                Element::Text {
+                    row: 2,
+                    col: 5,
+                    text: "ef".to_string(),
+                    style: normal.inverted(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_fill_is_skipped() {
+        let mut scene = Scene::new();
+        scene.fill(CellRect::row(0, 0, 0), BG);
+        assert!(scene.elements().is_empty());
+    }
+
+    #[test]
+    fn test_faded_style() {
+        let style = OverlayStyle::new(BG, FG).faded(0.5);
+        assert_eq!(style.fg, [0.5; 4]);
+        assert_eq!(style.bg, [0.0, 0.0, 0.0, 0.5]);
+    }
+}
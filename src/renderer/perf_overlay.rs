@@ -0,0 +1,167 @@
+//! `:GuiPerfOverlay`: frame times, quad counts and cache hit rates drawn
+//! over the top right of the grid, for telling where a stutter comes from
+//! without a `perf-stats` build.
+//!
+//! The numbers describe the frames before the one they are drawn in: a
+//! frame's time isn't known until it has been presented.
+
+use std::time::Duration;
+
+use super::RendererStats;
+
+/// Frames the graph and the average cover.
+const HISTORY: usize = 60;
+
+/// Bars of the graph, from the shortest frame to the longest.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Frame time a full bar stands for when no frame took longer, one frame
+/// at 60 Hz.
+const GRAPH_FLOOR: Duration = Duration::from_micros(16_667);
+
+/// The last `HISTORY` frame times, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct FrameTimes {
+    times: Vec<Duration>,
+    /// Where the next time goes once the history is full.
+    next: usize,
+}
+
+impl FrameTimes {
+    pub fn push(&mut self, time: Duration) {
+        if self.times.len() < HISTORY {
+            self.times.push(time);
+        } else {
+            self.times[self.next] = time;
+            self.next = (self.next + 1) % HISTORY;
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = Duration> + '_ {
+        let (newer, older) = self.times.split_at(self.next);
+        older.iter().chain(newer).copied()
+    }
+
+    pub fn average(&self) -> Duration {
+        match self.times.len() {
+            0 => Duration::ZERO,
+            len => self.times.iter().sum::<Duration>() / len as u32,
+        }
+    }
+
+    pub fn max(&self) -> Duration {
+        self.times.iter().copied().max().unwrap_or_default()
+    }
+
+    /// One bar per frame, the newest on the right, scaled to the longest
+    /// frame or `GRAPH_FLOOR`, whichever is more.
+    pub fn graph(&self) -> String {
+        let top = self.max().max(GRAPH_FLOOR).as_secs_f32();
+        self.iter()
+            .map(|time| {
+                let level = (time.as_secs_f32() / top * BARS.len() as f32).ceil() as usize;
+                BARS[level.clamp(1, BARS.len()) - 1]
+            })
+            .collect()
+    }
+}
+
+fn millis(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.0
+}
+
+fn hit_rate(hits: u64, misses: u64) -> String {
+    match hits + misses {
+        0 => "-".to_string(),
+        total => format!("{:.1}%", hits as f64 * 100.0 / total as f64),
+    }
+}
+
+/// The overlay's lines. `quads` are the background, glyph and decoration
+/// instances of the last frame.
+pub fn overlay_lines(times: &FrameTimes, quads: [u32; 3], stats: &RendererStats) -> Vec<String> {
+    let [backgrounds, glyphs, decorations] = quads;
+    vec![
+        format!(
+            "frame {:.2} ms avg, {:.2} ms max",
+            millis(times.average()),
+            millis(times.max())
+        ),
+        times.graph(),
+        format!(
+            "quads {} bg, {} glyphs, {} deco",
+            backgrounds, glyphs, decorations
+        ),
+        format!(
+            "cache {} glyphs, {} shaping",
+            hit_rate(stats.glyph_cache_hits, stats.glyph_cache_misses),
+            hit_rate(stats.shaping_cache_hits, stats.shaping_cache_misses)
+        ),
+        format!(
+            "skipped {} of {} frames",
+            stats.frames_skipped, stats.frames
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_history_keeps_the_last_frames() {
+        let mut times = FrameTimes::default();
+        assert_eq!(times.average(), Duration::ZERO);
+        assert_eq!(times.graph(), "");
+
+        times.push(ms(2));
+        times.push(ms(4));
+        assert_eq!(times.average(), ms(3));
+        assert_eq!(times.max(), ms(4));
+
+        times.push(ms(100));
+        for _ in 0..HISTORY - 1 {
+            times.push(ms(8));
+        }
+        // The 100 ms frame is the oldest left.
+        assert_eq!(times.max(), ms(100));
+        assert!(times.graph().starts_with('█'));
+        times.push(ms(8));
+        assert_eq!(times.max(), ms(8));
+        assert_eq!(times.graph().chars().count(), HISTORY);
+    }
+
+    #[test]
+    fn test_graph_scales_to_a_frame_at_60_hz() {
+        let mut times = FrameTimes::default();
+        for time in [0, 2, 8, 15, 16] {
+            times.push(ms(time));
+        }
+        assert_eq!(times.graph(), "▁▁▄██");
+
+        times.push(ms(36));
+        assert_eq!(times.graph(), "▁▁▂▄▄█");
+    }
+
+    #[test]
+    fn test_overlay_lines() {
+        let mut times = FrameTimes::default();
+        times.push(ms(5));
+        let stats = RendererStats {
+            frames: 10,
+            frames_skipped: 2,
+            glyph_cache_hits: 99,
+            glyph_cache_misses: 1,
+            ..Default::default()
+        };
+        let lines = overlay_lines(&times, [3, 40, 1], &stats);
+        assert_eq!(lines[0], "frame 5.00 ms avg, 5.00 ms max");
+        assert_eq!(lines[2], "quads 3 bg, 40 glyphs, 1 deco");
+        assert_eq!(lines[3], "cache 99.0% glyphs, - shaping");
+        assert_eq!(lines[4], "skipped 2 of 10 frames");
+    }
+}
@@ -11,6 +11,13 @@ pub struct Uniforms {
     pub projection: [[f32; 4]; 4],
     pub screen_size: [f32; 2],
     pub cell_size: [f32; 2],
+    /// Nonzero to place quads mirrored, right to left, without flipping
+    /// what they show.
+    pub mirror: u32,
+    /// Nonzero to encode colors to sRGB in the shader, see
+    /// `GpuContext::encodes_srgb`.
+    pub encode_srgb: u32,
+    _padding: [u32; 2],
 }
 
 impl Uniforms {
@@ -19,6 +26,9 @@ impl Uniforms {
             projection: Self::orthographic_projection(width, height),
             screen_size: [width, height],
             cell_size: [cell_width, cell_height],
+            mirror: 0,
+            encode_srgb: 0,
+            _padding: [0; 2],
         }
     }
 
@@ -54,17 +64,20 @@ pub struct QuadInstance {
     pub uv_size: [f32; 2],
     pub color: [f32; 4],
     pub flags: u32,
-    _padding: [u32; 3],
+    /// Atlas page (texture array layer) sampled by textured quads.
+    pub page: u32,
+    _padding: [u32; 2],
 }
 
 impl QuadInstance {
-    pub const ATTRIBS: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+    pub const ATTRIBS: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
         0 => Float32x2,  // position
         1 => Float32x2,  // size
         2 => Float32x2,  // uv_offset
         3 => Float32x2,  // uv_size
         4 => Float32x4,  // color
         5 => Uint32,     // flags
+        6 => Uint32,     // page
     ];
 
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
@@ -83,7 +96,23 @@ impl QuadInstance {
             uv_size: [0.0, 0.0],
             color,
             flags: 0,
-            _padding: [0; 3],
+            page: 0,
+            _padding: [0; 2],
+        }
+    }
+
+    /// A solid quad with fully rounded ends. The shader draws the pill
+    /// from its UVs, which run from minus to plus half the size.
+    pub fn pill(x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) -> Self {
+        Self {
+            position: [x, y],
+            size: [width, height],
+            uv_offset: [-width / 2.0, -height / 2.0],
+            uv_size: [width, height],
+            color,
+            flags: FLAG_PILL,
+            page: 0,
+            _padding: [0; 2],
         }
     }
 
@@ -98,6 +127,7 @@ impl QuadInstance {
         uv_h: f32,
         color: [f32; 4],
         is_colored: bool,
+        page: u32,
     ) -> Self {
         let flags = FLAG_TEXTURED | if is_colored { FLAG_COLORED_GLYPH } else { 0 };
         Self {
@@ -107,13 +137,68 @@ impl QuadInstance {
             uv_size: [uv_w, uv_h],
             color,
             flags,
-            _padding: [0; 3],
+            page,
+            _padding: [0; 2],
+        }
+    }
+
+    pub fn is_glyph(&self) -> bool {
+        self.flags & FLAG_TEXTURED != 0
+    }
+
+    pub fn is_colored_glyph(&self) -> bool {
+        self.flags & FLAG_COLORED_GLYPH != 0
+    }
+
+    /// The part of a textured quad inside the `width` by `height`
+    /// rectangle at `(x, y)`, sampling the same part of its texture. None
+    /// if the two don't overlap.
+    pub fn clipped(&self, x: f32, y: f32, width: f32, height: f32) -> Option<Self> {
+        let [left, top] = self.position;
+        let [w, h] = self.size;
+        let (x0, x1) = (left.max(x), (left + w).min(x + width));
+        let (y0, y1) = (top.max(y), (top + h).min(y + height));
+        if x0 >= x1 || y0 >= y1 {
+            return None;
+        }
+        let uv_per_x = self.uv_size[0] / w;
+        let uv_per_y = self.uv_size[1] / h;
+        Some(Self {
+            position: [x0, y0],
+            size: [x1 - x0, y1 - y0],
+            uv_offset: [
+                self.uv_offset[0] + (x0 - left) * uv_per_x,
+                self.uv_offset[1] + (y0 - top) * uv_per_y,
+            ],
+            uv_size: [(x1 - x0) * uv_per_x, (y1 - y0) * uv_per_y],
+            ..*self
+        })
+    }
+
+    /// The quad `factor` times the size, as seen from `origin`. A textured
+    /// quad stretches the same part of its texture; a pill's UVs, which
+    /// are in pixels, grow with it.
+    pub fn scaled(&self, origin: [f32; 2], factor: f32) -> Self {
+        let scale = |position: f32, origin: f32| origin + (position - origin) * factor;
+        let mut quad = Self {
+            position: [
+                scale(self.position[0], origin[0]),
+                scale(self.position[1], origin[1]),
+            ],
+            size: [self.size[0] * factor, self.size[1] * factor],
+            ..*self
+        };
+        if self.flags & FLAG_PILL != 0 {
+            quad.uv_offset = [self.uv_offset[0] * factor, self.uv_offset[1] * factor];
+            quad.uv_size = quad.size;
         }
+        quad
     }
 }
 
 pub const FLAG_TEXTURED: u32 = 1;
 pub const FLAG_COLORED_GLYPH: u32 = 2;
+pub const FLAG_PILL: u32 = 4;
 
 pub struct RenderPipeline {
     pipeline: wgpu::RenderPipeline,
@@ -126,12 +211,13 @@ pub struct RenderPipeline {
 impl RenderPipeline {
     pub fn new(ctx: &GpuContext, cell_width: f32, cell_height: f32) -> Self {
         let size = ctx.size();
-        let uniforms = Uniforms::new(
+        let mut uniforms = Uniforms::new(
             size.width as f32,
             size.height as f32,
             cell_width,
             cell_height,
         );
+        uniforms.encode_srgb = ctx.encodes_srgb().into();
 
         let uniform_buffer = ctx
             .device
@@ -176,7 +262,7 @@ impl RenderPipeline {
                             visibility: wgpu::ShaderStages::FRAGMENT,
                             ty: wgpu::BindingType::Texture {
                                 sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                view_dimension: wgpu::TextureViewDimension::D2,
+                                view_dimension: wgpu::TextureViewDimension::D2Array,
                                 multisampled: false,
                             },
                             count: None,
@@ -259,6 +345,19 @@ impl RenderPipeline {
         );
     }
 
+    pub fn mirrored(&self) -> bool {
+        self.uniforms.mirror != 0
+    }
+
+    pub fn set_mirrored(&mut self, ctx: &GpuContext, mirrored: bool) {
+        self.uniforms.mirror = mirrored.into();
+        ctx.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniforms]),
+        );
+    }
+
     #[allow(dead_code)]
     pub fn update_cell_size(&mut self, ctx: &GpuContext, cell_width: f32, cell_height: f32) {
         self.uniforms.cell_size = [cell_width, cell_height];
@@ -328,6 +427,12 @@ mod tests {
         assert!((br[1] - (-1.0)).abs() < 0.001);
     }
 
+    #[test]
+    fn test_uniforms_size() {
+        // WGSL rounds the uniform struct up to a multiple of 16 bytes
+        assert_eq!(std::mem::size_of::<Uniforms>() % 16, 0);
+    }
+
     #[test]
     fn test_quad_instance_size() {
         // Ensure struct is properly aligned for GPU
@@ -355,6 +460,7 @@ mod tests {
             0.1,
             [1.0, 1.0, 1.0, 1.0],
             false,
+            0,
         );
         assert_eq!(quad.flags, FLAG_TEXTURED);
 
@@ -369,7 +475,80 @@ mod tests {
             0.1,
             [1.0, 1.0, 1.0, 1.0],
             true,
+            2,
         );
         assert_eq!(colored.flags, FLAG_TEXTURED | FLAG_COLORED_GLYPH);
+        assert_eq!(colored.page, 2);
+    }
+
+    #[test]
+    fn test_quad_instance_clipped() {
+        let quad = QuadInstance::glyph(
+            10.0,
+            20.0,
+            16.0,
+            16.0,
+            0.5,
+            0.0,
+            0.25,
+            0.25,
+            [1.0, 1.0, 1.0, 1.0],
+            false,
+            1,
+        );
+        assert!(quad.is_glyph() && !quad.is_colored_glyph());
+
+        // The right half of a two-cell ligature
+        let half = quad.clipped(18.0, 0.0, 8.0, 100.0).unwrap();
+        assert_eq!(half.position, [18.0, 20.0]);
+        assert_eq!(half.size, [8.0, 16.0]);
+        assert_eq!(half.uv_offset, [0.625, 0.0]);
+        assert_eq!(half.uv_size, [0.125, 0.25]);
+        assert_eq!(half.page, 1);
+
+        assert_eq!(
+            quad.clipped(0.0, 0.0, 100.0, 100.0).unwrap().size,
+            quad.size
+        );
+        assert!(quad.clipped(26.0, 0.0, 8.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_quad_instance_scaled() {
+        let quad = QuadInstance::glyph(
+            10.0,
+            20.0,
+            8.0,
+            16.0,
+            0.5,
+            0.0,
+            0.25,
+            0.25,
+            [1.0, 1.0, 1.0, 1.0],
+            false,
+            0,
+        );
+        let zoomed = quad.scaled([4.0, 10.0], 2.0);
+        assert_eq!(zoomed.position, [16.0, 30.0]);
+        assert_eq!(zoomed.size, [16.0, 32.0]);
+        assert_eq!(
+            (zoomed.uv_offset, zoomed.uv_size),
+            (quad.uv_offset, quad.uv_size)
+        );
+
+        let pill = QuadInstance::pill(10.0, 20.0, 30.0, 16.0, [0.0, 0.0, 0.0, 0.3]);
+        let zoomed = pill.scaled([10.0, 20.0], 0.5);
+        assert_eq!(zoomed.position, [10.0, 20.0]);
+        assert_eq!(zoomed.uv_offset, [-7.5, -4.0]);
+        assert_eq!(zoomed.uv_size, [15.0, 8.0]);
+    }
+
+    #[test]
+    fn test_quad_instance_pill() {
+        let quad = QuadInstance::pill(10.0, 20.0, 30.0, 16.0, [0.0, 0.0, 0.0, 0.3]);
+        assert_eq!(quad.flags, FLAG_PILL);
+        // UVs run across the quad from its center
+        assert_eq!(quad.uv_offset, [-15.0, -8.0]);
+        assert_eq!(quad.uv_size, [30.0, 16.0]);
     }
 }
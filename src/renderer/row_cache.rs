@@ -0,0 +1,163 @@
+//! The main grid's rows as batched for the last frame.
+//!
+//! A row whose cells haven't changed is copied into the next frame as it
+//! was, instead of being shaped and batched again. On `grid_scroll` the
+//! rows move along with the cells, so only the rows it exposes are built
+//! anew; that is most of the work saved on `<C-d>` or a flood of terminal
+//! output. Each row keeps the hash of the cells it was built from, which
+//! is checked before it is reused.
+
+use std::hash::{Hash, Hasher};
+
+use crate::editor::{Cell, GridScrolled};
+
+use super::batch::GridPlacement;
+use super::pipeline::QuadInstance;
+
+/// The quads of one row, in the grid batches they were pushed to.
+#[derive(Debug, Clone, Default)]
+pub struct CachedRow {
+    /// Hash of the cells the row was built from.
+    pub key: u64,
+    /// Top of the row when it was built.
+    pub y: f32,
+    pub backgrounds: Vec<QuadInstance>,
+    pub glyphs: Vec<QuadInstance>,
+    pub decorations: Vec<QuadInstance>,
+    /// Where the last frame pushed the row, and its top there.
+    pub placed: Option<(GridPlacement, f32)>,
+}
+
+/// The hash a row is kept under.
+pub fn row_key(cells: &[Cell]) -> u64 {
+    let mut hasher = ahash::AHasher::default();
+    cells.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Default)]
+pub struct RowCache {
+    rows: Vec<Option<CachedRow>>,
+    /// Hash of everything else the rows were built with: colors, fonts,
+    /// the atlas. Any change drops them all.
+    context: u64,
+    /// `GridScrolled::seq` of the last scroll applied.
+    scrolled: u64,
+}
+
+impl RowCache {
+    /// Drops every row unless they were built in `context`.
+    pub fn validate(&mut self, context: u64) {
+        if context != self.context {
+            self.rows.clear();
+            self.context = context;
+        }
+    }
+
+    /// Moves the rows along with the scrolls not applied yet.
+    pub fn scroll(&mut self, scrolls: &[GridScrolled]) {
+        let seen = self.scrolled;
+        for scroll in scrolls.iter().filter(|s| s.seq > seen) {
+            self.scroll_rows(scroll);
+            self.scrolled = scroll.seq;
+        }
+    }
+
+    fn scroll_rows(&mut self, scroll: &GridScrolled) {
+        let bot = scroll.rows.end.min(self.rows.len());
+        let top = scroll.rows.start.min(bot);
+        let region = &mut self.rows[top..bot];
+        let len = region.len();
+        let distance = scroll.delta.unsigned_abs() as usize;
+        if distance >= len {
+            region.fill(None);
+        } else if scroll.delta > 0 {
+            region.rotate_left(distance);
+            region[len - distance..].fill(None);
+        } else {
+            region.rotate_right(distance);
+            region[..distance].fill(None);
+        }
+    }
+
+    /// The row at `row`, if it was built from cells hashing to `key`.
+    pub fn get(&self, row: usize, key: u64) -> Option<&CachedRow> {
+        self.rows
+            .get(row)?
+            .as_ref()
+            .filter(|cached| cached.key == key)
+    }
+
+    /// Records where the row at `row` was pushed this frame.
+    pub fn place(&mut self, row: usize, placed: (GridPlacement, f32)) {
+        if let Some(Some(cached)) = self.rows.get_mut(row) {
+            cached.placed = Some(placed);
+        }
+    }
+
+    pub fn insert(&mut self, row: usize, cached: CachedRow) {
+        if row >= self.rows.len() {
+            self.rows.resize(row + 1, None);
+        }
+        self.rows[row] = Some(cached);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(key: u64) -> CachedRow {
+        CachedRow {
+            key,
+            ..Default::default()
+        }
+    }
+
+    fn keys(cache: &RowCache) -> Vec<Option<u64>> {
+        cache
+            .rows
+            .iter()
+            .map(|row| row.as_ref().map(|row| row.key))
+            .collect()
+    }
+
+    fn scrolled(seq: u64, rows: std::ops::Range<usize>, delta: i64) -> GridScrolled {
+        GridScrolled { seq, rows, delta }
+    }
+
+    #[test]
+    fn test_rows_move_with_scrolls() {
+        let mut cache = RowCache::default();
+        for i in 0..5 {
+            cache.insert(i, row(i as u64));
+        }
+
+        // Up by 2 within rows 0-3, leaving the last row alone
+        cache.scroll(&[scrolled(1, 0..4, 2)]);
+        assert_eq!(keys(&cache), [Some(2), Some(3), None, None, Some(4)]);
+
+        // Down by 1 over everything; the first scroll was seen already.
+        cache.scroll(&[scrolled(1, 0..4, 2), scrolled(2, 0..5, -1)]);
+        assert_eq!(keys(&cache), [None, Some(2), Some(3), None, None]);
+
+        cache.scroll(&[scrolled(3, 1..10, 20)]);
+        assert_eq!(keys(&cache), [None, None, None, None, None]);
+    }
+
+    #[test]
+    fn test_rows_are_checked_before_use() {
+        let mut cache = RowCache::default();
+        cache.validate(1);
+        let cells = vec![Cell::default(); 3];
+        cache.insert(2, row(row_key(&cells)));
+        assert!(cache.get(2, row_key(&cells)).is_some());
+        assert!(cache.get(2, row_key(&cells[..2])).is_none());
+        assert!(cache.get(3, row_key(&cells)).is_none());
+
+        cache.validate(1);
+        assert!(cache.get(2, row_key(&cells)).is_some());
+        cache.validate(2);
+        assert!(cache.get(2, row_key(&cells)).is_none());
+    }
+}
@@ -0,0 +1,58 @@
+// Background image shader, drawn before the grid.
+//
+// A single full-screen triangle samples the image and composites it over
+// the default background color, then blends that color back over the
+// result by the dim amount. The output replaces whatever was in the target.
+
+struct Uniforms {
+    // Maps window UV (0..1) to image UV: image_uv = uv * uv_scale + uv_offset
+    uv_scale: vec2<f32>,
+    uv_offset: vec2<f32>,
+    // Image opacity over the default background
+    opacity: f32,
+    // Fraction of the default background blended back over the image
+    dim: f32,
+    // Nonzero to encode the output to sRGB, for a target that doesn't
+    encode_srgb: u32,
+    // Default background (premultiplied linear RGBA)
+    color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(1)
+var image: texture_2d<f32>;
+
+@group(0) @binding(2)
+var image_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // Vertices (0,0), (2,0), (0,2) in UV space cover the whole window.
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var output: VertexOutput;
+    output.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(image, image_sampler, input.uv * uniforms.uv_scale + uniforms.uv_offset);
+    let alpha = texel.a * uniforms.opacity;
+    let over = vec4<f32>(texel.rgb * alpha, alpha) + uniforms.color * (1.0 - alpha);
+    let color = mix(over, uniforms.color, uniforms.dim);
+    if uniforms.encode_srgb == 0u || color.a <= 0.0 {
+        return color;
+    }
+    let rgb = max(color.rgb / color.a, vec3<f32>(0.0));
+    let srgb = select(1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - 0.055, rgb * 12.92, rgb <= vec3<f32>(0.0031308));
+    return vec4<f32>(srgb * color.a, color.a);
+}
@@ -3,6 +3,7 @@
 // Rendering is done via instanced quads. Each instance represents either:
 // - A cell background (solid color, no texture)
 // - A text glyph (textured, alpha-blended)
+// - A pill (solid color with rounded ends, antialiased)
 //
 // The shader uses 6 vertices per quad (two triangles), with vertices
 // generated procedurally from instance data.
@@ -14,6 +15,11 @@ struct Uniforms {
     screen_size: vec2<f32>,
     // Cell dimensions in pixels
     cell_size: vec2<f32>,
+    // Nonzero to place quads right to left; their contents aren't flipped
+    mirror: u32,
+    // Nonzero when the target stores colors as they are, not encoding
+    // them to sRGB itself; see `encode`
+    encode_srgb: u32,
 }
 
 struct VertexInput {
@@ -32,8 +38,11 @@ struct InstanceInput {
     @location(3) uv_size: vec2<f32>,
     // RGBA color (all outputs use premultiplied alpha blending)
     @location(4) color: vec4<f32>,
-    // Flags: bit 0 = is_textured (use atlas alpha), bit 1 = is_colored_glyph
+    // Flags: bit 0 = is_textured (use atlas alpha), bit 1 = is_colored_glyph,
+    // bit 2 = is_pill (UVs are pixels from the center)
     @location(5) flags: u32,
+    // Atlas page (texture array layer) for textured quads
+    @location(6) page: u32,
 }
 
 struct VertexOutput {
@@ -41,13 +50,15 @@ struct VertexOutput {
     @location(0) tex_coord: vec2<f32>,
     @location(1) color: vec4<f32>,
     @location(2) @interpolate(flat) flags: u32,
+    @location(3) @interpolate(flat) page: u32,
+    @location(4) @interpolate(flat) half_size: vec2<f32>,
 }
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
 @group(1) @binding(0)
-var glyph_atlas: texture_2d<f32>;
+var glyph_atlas: texture_2d_array<f32>;
 
 @group(1) @binding(1)
 var atlas_sampler: sampler;
@@ -76,7 +87,11 @@ fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
     }
 
     // Calculate position in screen space
-    let screen_pos = instance.position + corner * instance.size;
+    var position = instance.position;
+    if uniforms.mirror != 0u {
+        position.x = uniforms.screen_size.x - position.x - instance.size.x;
+    }
+    let screen_pos = position + corner * instance.size;
 
     // Apply projection
     output.clip_position = uniforms.projection * vec4<f32>(screen_pos, 0.0, 1.0);
@@ -84,28 +99,57 @@ fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
     // Calculate texture coordinates
     output.tex_coord = instance.uv_offset + corner * instance.uv_size;
 
-    // Pass through color and flags
+    // Pass through color, flags and page
     output.color = instance.color;
     output.flags = instance.flags;
+    output.page = instance.page;
+    output.half_size = instance.size * 0.5;
 
     return output;
 }
 
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let c0 = max(c, vec3<f32>(0.0));
+    let low = c0 * 12.92;
+    let high = 1.055 * pow(c0, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, c0 <= vec3<f32>(0.0031308));
+}
+
+// Encodes a premultiplied linear color to sRGB for targets that don't.
+// Blending then happens on the encoded values, as in most terminals,
+// which draws text heavier than blending in linear light.
+fn encode(color: vec4<f32>) -> vec4<f32> {
+    if uniforms.encode_srgb == 0u || color.a <= 0.0 {
+        return color;
+    }
+    return vec4<f32>(linear_to_srgb(color.rgb / color.a) * color.a, color.a);
+}
+
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return encode(shade(input));
+}
+
+fn shade(input: VertexOutput) -> vec4<f32> {
     let is_textured = (input.flags & 1u) != 0u;
     let is_colored_glyph = (input.flags & 2u) != 0u;
 
     if is_textured {
         if is_colored_glyph {
             // Color glyph (emoji): use texture color directly
-            let tex_color = textureSample(glyph_atlas, atlas_sampler, input.tex_coord);
+            let tex_color = textureSample(glyph_atlas, atlas_sampler, input.tex_coord, input.page);
             return tex_color;
         } else {
             // Grayscale glyph: use texture alpha with text color
-            let alpha = textureSample(glyph_atlas, atlas_sampler, input.tex_coord).a;
+            let alpha = textureSample(glyph_atlas, atlas_sampler, input.tex_coord, input.page).a;
             return vec4<f32>(input.color.rgb * alpha, alpha * input.color.a);
         }
+    } else if (input.flags & 4u) != 0u {
+        // Pill: distance to a rounded rect with radius half the short side
+        let radius = min(input.half_size.x, input.half_size.y);
+        let inner = max(abs(input.tex_coord) - (input.half_size - radius), vec2<f32>(0.0));
+        let distance = length(inner) - radius;
+        return input.color * clamp(0.5 - distance, 0.0, 1.0);
     } else {
         // Solid color quad (cell background)
         return input.color;
@@ -0,0 +1,239 @@
+//! What the renderer needs from a GPU adapter, and how it makes do with
+//! less than WebGPU's default limits.
+//!
+//! A few limits are hard requirements (see `REQUIREMENTS`). Everything else
+//! scales down: the glyph atlas to the largest texture and the number of
+//! layers allowed, instance buffers to the largest buffer, and the
+//! offscreen canvas is dropped where the surface doesn't accept copies.
+
+use wgpu::{AdapterInfo, DownlevelCapabilities, Limits};
+
+use super::atlas::AtlasLimits;
+use super::batch::max_batch_instances;
+use super::pipeline::QuadInstance;
+
+/// Smallest texture side the renderer works with, WebGL2's. The window
+/// and the glyph atlas are textures of up to this size on such adapters.
+const MIN_TEXTURE_DIMENSION: u32 = 2048;
+
+/// Smallest instance buffer allowed: 16384 quads, the backgrounds and
+/// glyphs of a 100x80 grid.
+const MIN_BUFFER_SIZE: u64 = 1024 * 1024;
+
+/// A limit the renderer can't do without.
+struct Requirement {
+    name: &'static str,
+    minimum: u64,
+    value: fn(&Limits) -> u64,
+}
+
+const REQUIREMENTS: &[Requirement] = &[
+    Requirement {
+        name: "max_texture_dimension_2d",
+        minimum: MIN_TEXTURE_DIMENSION as u64,
+        value: |limits| limits.max_texture_dimension_2d.into(),
+    },
+    Requirement {
+        name: "max_texture_array_layers",
+        minimum: 1,
+        value: |limits| limits.max_texture_array_layers.into(),
+    },
+    // Uniforms and the atlas.
+    Requirement {
+        name: "max_bind_groups",
+        minimum: 2,
+        value: |limits| limits.max_bind_groups.into(),
+    },
+    Requirement {
+        name: "max_vertex_buffers",
+        minimum: 1,
+        value: |limits| limits.max_vertex_buffers.into(),
+    },
+    Requirement {
+        name: "max_vertex_attributes",
+        minimum: QuadInstance::ATTRIBS.len() as u64,
+        value: |limits| limits.max_vertex_attributes.into(),
+    },
+    Requirement {
+        name: "max_vertex_buffer_array_stride",
+        minimum: std::mem::size_of::<QuadInstance>() as u64,
+        value: |limits| limits.max_vertex_buffer_array_stride.into(),
+    },
+    Requirement {
+        name: "max_buffer_size",
+        minimum: MIN_BUFFER_SIZE,
+        value: |limits| limits.max_buffer_size,
+    },
+];
+
+/// An adapter, the limits it offers and what the renderer makes of them.
+#[derive(Debug, Clone)]
+pub struct GpuSupport {
+    info: AdapterInfo,
+    limits: Limits,
+    downlevel: DownlevelCapabilities,
+}
+
+impl GpuSupport {
+    pub fn new(adapter: &wgpu::Adapter) -> Self {
+        Self::from_parts(
+            adapter.get_info(),
+            adapter.limits(),
+            adapter.get_downlevel_capabilities(),
+        )
+    }
+
+    fn from_parts(info: AdapterInfo, limits: Limits, downlevel: DownlevelCapabilities) -> Self {
+        Self {
+            info,
+            limits,
+            downlevel,
+        }
+    }
+
+    /// The requirements this adapter doesn't meet, such as
+    /// `max_bind_groups 1 (needs 2)`.
+    pub fn unmet(&self) -> Vec<String> {
+        REQUIREMENTS
+            .iter()
+            .filter_map(|requirement| {
+                let value = (requirement.value)(&self.limits);
+                (value < requirement.minimum).then(|| {
+                    format!(
+                        "{} {} (needs {})",
+                        requirement.name, value, requirement.minimum
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.unmet().is_empty()
+    }
+
+    /// The limits to request the device with: all the adapter has, so the
+    /// atlas and buffers can grow as far as it allows, rather than WebGPU's
+    /// defaults, which downlevel adapters fail to provide.
+    pub fn required_limits(&self) -> Limits {
+        self.limits.clone()
+    }
+
+    /// Whether the adapter is a full WebGPU implementation, or which of its
+    /// features it lacks.
+    fn compliance(&self) -> String {
+        if self.downlevel.is_webgpu_compliant() {
+            return "compliant".to_string();
+        }
+        let missing: Vec<&str> = (wgpu::DownlevelFlags::all() - self.downlevel.flags)
+            .iter_names()
+            .map(|(name, _)| name)
+            .collect();
+        if missing.is_empty() {
+            // Only the shader model falls short.
+            format!("downlevel ({:?})", self.downlevel.shader_model)
+        } else {
+            format!("downlevel, without {}", missing.join(", "))
+        }
+    }
+
+    /// The report `gui.nvim gpu-info` prints and the log starts with.
+    pub fn lines(&self) -> Vec<String> {
+        let atlas = AtlasLimits::from_limits(&self.limits);
+        let mut lines = vec![
+            format!(
+                "adapter:         {} ({:?}, {:?})",
+                self.info.name, self.info.backend, self.info.device_type
+            ),
+            format!(
+                "driver:          {}",
+                [&self.info.driver, &self.info.driver_info]
+                    .map(|s| s.trim())
+                    .join(" ")
+                    .trim()
+            ),
+            format!("webgpu:          {}", self.compliance()),
+            format!(
+                "textures:        up to {} px, {} layers",
+                self.limits.max_texture_dimension_2d, self.limits.max_texture_array_layers
+            ),
+            format!(
+                "buffers:         up to {:.1} MiB",
+                self.limits.max_buffer_size as f64 / (1024.0 * 1024.0)
+            ),
+            format!(
+                "glyph atlas:     {}-{} px, {} pages",
+                atlas.initial_size, atlas.max_size, atlas.max_pages
+            ),
+            format!(
+                "instances:       up to {} per batch",
+                max_batch_instances(&self.limits)
+            ),
+        ];
+        let unmet = self.unmet();
+        lines.push(if unmet.is_empty() {
+            "status:          supported".to_string()
+        } else {
+            format!("status:          unsupported: {}", unmet.join(", "))
+        });
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn support(limits: Limits) -> GpuSupport {
+        let info = AdapterInfo {
+            name: "Test GPU".to_string(),
+            vendor: 0,
+            device: 0,
+            device_type: wgpu::DeviceType::Cpu,
+            device_pci_bus_id: String::new(),
+            driver: "test".to_string(),
+            driver_info: "1.0".to_string(),
+            backend: wgpu::Backend::Noop,
+            subgroup_min_size: 0,
+            subgroup_max_size: 0,
+            transient_saves_memory: false,
+        };
+        GpuSupport::from_parts(info, limits, DownlevelCapabilities::default())
+    }
+
+    #[test]
+    fn test_downlevel_defaults_are_supported() {
+        assert!(support(Limits::default()).is_supported());
+        assert!(support(Limits::downlevel_webgl2_defaults()).is_supported());
+    }
+
+    #[test]
+    fn test_unmet_requirements() {
+        let limits = Limits {
+            max_texture_dimension_2d: 1024,
+            max_bind_groups: 1,
+            ..Limits::downlevel_webgl2_defaults()
+        };
+        let support = support(limits);
+        assert_eq!(
+            support.unmet(),
+            [
+                "max_texture_dimension_2d 1024 (needs 2048)",
+                "max_bind_groups 1 (needs 2)"
+            ]
+        );
+        assert!(support.lines().last().unwrap().contains("unsupported"));
+    }
+
+    #[test]
+    fn test_report_scales_to_limits() {
+        let limits = Limits {
+            max_texture_dimension_2d: 4096,
+            max_texture_array_layers: 2,
+            ..Limits::default()
+        };
+        let lines = support(limits).lines();
+        assert!(lines.contains(&"glyph atlas:     1024-4096 px, 2 pages".to_string()));
+        assert_eq!(lines.last().unwrap(), "status:          supported");
+    }
+}
@@ -0,0 +1,271 @@
+//! Snapshots for bug reports, taken with F12 or `:GuiSnapshot`.
+//!
+//! Each snapshot is a folder holding `frame.png`, the frame as drawn, and
+//! `state.json` with the grid contents, the highlight table and the
+//! effective config, so a report shows exactly what the user saw.
+
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::editor::{Color, EditorState};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("No data directory to save snapshots in")]
+    NoDataDir,
+
+    #[error("Failed to write snapshot: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Where snapshots are saved, one folder each.
+pub fn snapshots_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("gui-nvim").join("snapshots"))
+}
+
+/// Saves a snapshot into a new timestamped folder and returns its path.
+/// `frame` is the PNG to include, if the frame could be captured.
+pub fn save(
+    frame: Option<&[u8]>,
+    state: &EditorState,
+    config: &Config,
+) -> Result<PathBuf, SnapshotError> {
+    let root = snapshots_dir().ok_or(SnapshotError::NoDataDir)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let dir = unique_dir(&root, &utc_timestamp(now.as_secs()));
+    save_to(&dir, frame, state, config)?;
+    Ok(dir)
+}
+
+fn save_to(
+    dir: &Path,
+    frame: Option<&[u8]>,
+    state: &EditorState,
+    config: &Config,
+) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    if let Some(png) = frame {
+        std::fs::write(dir.join("frame.png"), png)?;
+    }
+    std::fs::write(dir.join("state.json"), state_json(state, config))
+}
+
+/// `root/name`, or `root/name-2` and so on if that is taken.
+fn unique_dir(root: &Path, name: &str) -> PathBuf {
+    let mut dir = root.join(name);
+    let mut n = 2;
+    while dir.exists() {
+        dir = root.join(format!("{}-{}", name, n));
+        n += 1;
+    }
+    dir
+}
+
+/// The UTC (year, month, day) `secs` after 1970-01-01, by Howard
+/// Hinnant's algorithm.
+pub(crate) fn civil_date(secs: u64) -> (i64, i64, i64) {
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// `YYYY-MM-DDTHH-MM-SS` in UTC, safe to use in file names.
+pub(crate) fn utc_timestamp(secs: u64) -> String {
+    let (year, month, day) = civil_date(secs);
+    let time = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+fn state_json(state: &EditorState, config: &Config) -> String {
+    let mut out = String::from("{\n");
+    let _ = writeln!(
+        out,
+        "  \"version\": {},",
+        json_string(env!("CARGO_PKG_VERSION"))
+    );
+    let cursor = &state.cursor;
+    let _ = writeln!(
+        out,
+        "  \"cursor\": {{\"grid\": {}, \"row\": {}, \"col\": {}, \"mode\": {}}},",
+        cursor.grid,
+        cursor.row,
+        cursor.col,
+        json_string(state.mode_name())
+    );
+
+    let defaults = &state.highlights.defaults;
+    let _ = writeln!(
+        out,
+        "  \"default_colors\": {{\"fg\": {}, \"bg\": {}, \"sp\": {}}},",
+        json_color(Some(defaults.foreground)),
+        json_color(Some(defaults.background)),
+        json_color(Some(defaults.special))
+    );
+
+    out.push_str("  \"highlights\": {");
+    for (i, id) in state.highlights.ids().into_iter().enumerate() {
+        let attrs = state.highlights.get(id);
+        let style: Vec<String> = attrs
+            .style
+            .iter_names()
+            .map(|(name, _)| json_string(&name.to_lowercase()))
+            .collect();
+        let _ = write!(
+            out,
+            "{}\n    \"{}\": {{\"fg\": {}, \"bg\": {}, \"sp\": {}, \"blend\": {}, \"style\": [{}]}}",
+            if i == 0 { "" } else { "," },
+            id,
+            json_color(attrs.foreground),
+            json_color(attrs.background),
+            json_color(attrs.special),
+            attrs.blend,
+            style.join(", ")
+        );
+    }
+    out.push_str("\n  },\n");
+
+    out.push_str("  \"grids\": [");
+    for (i, id) in state.grid_ids().into_iter().enumerate() {
+        let Some(grid) = state.grid(id) else {
+            continue;
+        };
+        let _ = write!(
+            out,
+            "{}\n    {{\"id\": {}, \"width\": {}, \"height\": {}, \"rows\": [",
+            if i == 0 { "" } else { "," },
+            id,
+            grid.width(),
+            grid.height()
+        );
+        for (row, cells) in grid.rows().enumerate() {
+            let text = grid.row_text(row).unwrap_or_default();
+            let hl: Vec<String> = cells.iter().map(|c| c.highlight_id.to_string()).collect();
+            let _ = write!(
+                out,
+                "{}\n      {{\"text\": {}, \"hl\": [{}]}}",
+                if row == 0 { "" } else { "," },
+                json_string(&text),
+                hl.join(",")
+            );
+        }
+        out.push_str("\n    ]}");
+    }
+    out.push_str("\n  ],\n");
+
+    let _ = writeln!(
+        out,
+        "  \"config\": {}",
+        json_string(&format!("{:#?}", config))
+    );
+    out.push_str("}\n");
+    out
+}
+
+fn json_color(color: Option<Color>) -> String {
+    match color {
+        Some(Color(rgba)) => format!("\"#{:06x}\"", rgba >> 8),
+        None => "null".to_string(),
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::events::{GridCell, RedrawEvent};
+    use crate::editor::{HighlightAttributes, StyleFlags};
+
+    #[test]
+    fn test_utc_timestamp() {
+        assert_eq!(utc_timestamp(0), "1970-01-01T00-00-00");
+        assert_eq!(utc_timestamp(951_782_400), "2000-02-29T00-00-00");
+        assert_eq!(utc_timestamp(1_791_980_130), "2026-10-14T12-15-30");
+    }
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}é"), r#""a\"b\\c\nd\u0001é""#);
+    }
+
+    #[test]
+    fn test_state_json() {
+        let mut state = EditorState::new(4, 2);
+        state.handle_redraw_event(&RedrawEvent::HlAttrDefine {
+            id: 3,
+            attrs: HighlightAttributes {
+                foreground: Some(Color::from_u24(0xff8000)),
+                style: StyleFlags::BOLD,
+                ..Default::default()
+            },
+        });
+        state.handle_redraw_event(&RedrawEvent::GridLine {
+            grid: 1,
+            row: 0,
+            col_start: 0,
+            cells: vec![GridCell {
+                text: "\"x".into(),
+                hl_id: Some(3),
+                repeat: 1,
+            }],
+        });
+
+        let json = state_json(&state, &Config::default());
+        assert!(json.contains(
+            r##""3": {"fg": "#ff8000", "bg": null, "sp": null, "blend": 0, "style": ["bold"]}"##
+        ));
+        assert!(json.contains(r#"{"text": "\"x", "hl": [3,0,0,0]}"#));
+        assert!(json.contains(r#""mode": "normal""#));
+        assert!(json.contains(r#""config": "Config {"#));
+    }
+
+    #[test]
+    fn test_save_to_writes_files() {
+        let root = std::env::temp_dir().join(format!("gui-nvim-snapshot-{}", std::process::id()));
+        let dir = unique_dir(&root, "test");
+        let state = EditorState::new(2, 1);
+        save_to(&dir, Some(b"png"), &state, &Config::default()).unwrap();
+        assert_eq!(std::fs::read(dir.join("frame.png")).unwrap(), b"png");
+        assert!(dir.join("state.json").exists());
+        assert_eq!(unique_dir(&root, "test"), root.join("test-2"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+//! Session statistics reported by `:GuiStats`.
+//!
+//! The report is plain text so users can paste it into performance issues.
+
+use std::time::Duration;
+
+use crate::editor::HighlightStats;
+use crate::renderer::RendererStats;
+
+#[derive(Debug, Clone, Default)]
+pub struct StatsReport {
+    pub uptime: Duration,
+    pub rss_bytes: Option<u64>,
+    pub highlights: HighlightStats,
+    /// `None` while the renderer is still initializing.
+    pub renderer: Option<RendererStats>,
+}
+
+impl StatsReport {
+    pub fn collect(
+        uptime: Duration,
+        highlights: HighlightStats,
+        renderer: Option<RendererStats>,
+    ) -> Self {
+        Self {
+            uptime,
+            rss_bytes: process_rss_bytes(),
+            highlights,
+            renderer,
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("gui.nvim {}", env!("CARGO_PKG_VERSION")),
+            format!("uptime:          {}", format_duration(self.uptime)),
+            format!(
+                "resident memory: {}",
+                self.rss_bytes
+                    .map(format_bytes)
+                    .unwrap_or_else(|| "unavailable".to_string())
+            ),
+            format!(
+                "highlights:      {} defined, {} stale, {} compacted",
+                self.highlights.defined, self.highlights.stale, self.highlights.compacted
+            ),
+        ];
+
+        let Some(r) = &self.renderer else {
+            lines.push("renderer:        not initialized".to_string());
+            return lines;
+        };
+
+        let avg_ms = if r.frames > 0 {
+            r.frame_time_total.as_secs_f64() * 1000.0 / r.frames as f64
+        } else {
+            0.0
+        };
+        lines.push(format!(
+            "gpu memory:      {} (estimate)",
+            format_bytes(r.gpu_memory_bytes)
+        ));
+        lines.push(format!(
+            "frames:          {} ({} skipped as unchanged)",
+            r.frames, r.frames_skipped
+        ));
+        lines.push(format!(
+            "gpu uploads:     {}",
+            format_bytes(r.uploaded_bytes)
+        ));
+        lines.push(format!("avg frame time:  {:.2} ms", avg_ms));
+        lines.push(format!(
+            "glyph cache:     {}",
+            format_hit_rate(r.glyph_cache_hits, r.glyph_cache_misses)
+        ));
+        lines.push(format!(
+            "shaping cache:   {}",
+            format_hit_rate(r.shaping_cache_hits, r.shaping_cache_misses)
+        ));
+        lines
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!(
+        "{}h {:02}m {:02}s",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    format!("{:.1} MiB", bytes as f64 / MIB)
+}
+
+fn format_hit_rate(hits: u64, misses: u64) -> String {
+    let total = hits + misses;
+    if total == 0 {
+        return "no lookups".to_string();
+    }
+    format!(
+        "{:.1}% hit ({} / {})",
+        hits as f64 * 100.0 / total as f64,
+        hits,
+        total
+    )
+}
+
+/// Resident set size of this process.
+#[cfg(target_os = "linux")]
+fn process_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_rss(&status)
+}
+
+/// `VmRSS` from `/proc/self/status`, which the kernel gives in KiB.
+#[cfg(any(target_os = "linux", test))]
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?;
+    let kib: u64 = line.trim().strip_suffix("kB")?.trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Resident set size of this process.
+#[cfg(target_os = "windows")]
+fn process_rss_bytes() -> Option<u64> {
+    use winapi::um::processthreadsapi::GetCurrentProcess;
+    use winapi::um::psapi::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+
+    let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    // SAFETY: the pseudo handle needs no closing, and `counters` is as
+    // large as `size` says.
+    let ok = unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, size) };
+    (ok != 0).then_some(counters.WorkingSetSize as u64)
+}
+
+/// Resident set size of this process.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn process_rss_bytes() -> Option<u64> {
+    // `ps` reports RSS in KiB on macOS and the BSDs.
+    let output = std::process::Command::new("ps")
+        .args(["-o", "rss=", "-p", &std::process::id().to_string()])
+        .output()
+        .ok()?;
+    let kib: u64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1h 02m 05s");
+    }
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\tgui-nvim\nVmPeak:\t  9000 kB\nVmRSS:\t    2048 kB\nThreads:\t8\n";
+        assert_eq!(parse_vm_rss(status), Some(2048 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tgui-nvim\n"), None);
+    }
+
+    #[test]
+    fn test_format_hit_rate() {
+        assert_eq!(format_hit_rate(0, 0), "no lookups");
+        assert_eq!(format_hit_rate(3, 1), "75.0% hit (3 / 4)");
+    }
+
+    #[test]
+    fn test_report_without_renderer() {
+        let report = StatsReport {
+            uptime: Duration::from_secs(1),
+            rss_bytes: None,
+            highlights: HighlightStats {
+                defined: 12,
+                stale: 3,
+                compacted: 40,
+            },
+            renderer: None,
+        };
+        let lines = report.lines();
+        assert!(lines.contains(&"highlights:      12 defined, 3 stale, 40 compacted".to_string()));
+        assert!(lines.iter().any(|l| l.contains("unavailable")));
+        assert!(lines.iter().any(|l| l.contains("not initialized")));
+    }
+
+    #[test]
+    fn test_report_with_renderer() {
+        let report = StatsReport {
+            uptime: Duration::from_secs(60),
+            rss_bytes: Some(64 * 1024 * 1024),
+            highlights: HighlightStats::default(),
+            renderer: Some(RendererStats {
+                frames: 4,
+                frame_time_total: Duration::from_millis(10),
+                glyph_cache_hits: 9,
+                glyph_cache_misses: 1,
+                uploaded_bytes: 3 * 1024 * 1024,
+                ..Default::default()
+            }),
+        };
+        let lines = report.lines();
+        assert!(lines.contains(&"resident memory: 64.0 MiB".to_string()));
+        assert!(lines.contains(&"avg frame time:  2.50 ms".to_string()));
+        assert!(lines.contains(&"gpu uploads:     3.0 MiB".to_string()));
+        assert!(lines.contains(&"glyph cache:     90.0% hit (9 / 10)".to_string()));
+    }
+}
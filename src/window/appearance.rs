@@ -0,0 +1,120 @@
+//! The system's light or dark appearance, which gui.nvim follows until a
+//! colorscheme says otherwise: it picks the default colors drawn before
+//! Neovim sends its own, and Neovim starts with `--cmd 'set background=…'`
+//! to match, which init.lua can still override.
+//!
+//! macOS and Windows report the appearance through winit. Linux asks the
+//! XDG desktop portal with `dbus-send`; without one, gui.nvim stays dark.
+
+use winit::event_loop::ActiveEventLoop;
+use winit::window::Theme;
+
+use crate::editor::DefaultColors;
+
+/// The system's appearance, if it can be told.
+pub fn system_theme(event_loop: &ActiveEventLoop) -> Option<Theme> {
+    event_loop.system_theme().or_else(portal_theme)
+}
+
+#[cfg(target_os = "linux")]
+fn portal_theme() -> Option<Theme> {
+    let output = std::process::Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--reply-timeout=200",
+            "--dest=org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings.Read",
+            "string:org.freedesktop.appearance",
+            "string:color-scheme",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_portal_reply(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn portal_theme() -> Option<Theme> {
+    None
+}
+
+/// The theme in the portal's answer for `color-scheme`: 1 prefers dark,
+/// 2 light and 0 says nothing.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_portal_reply(reply: &str) -> Option<Theme> {
+    let value = reply.split("uint32").nth(1)?.split_whitespace().next()?;
+    match value {
+        "1" => Some(Theme::Dark),
+        "2" => Some(Theme::Light),
+        _ => None,
+    }
+}
+
+/// The value of `'background'` matching `theme`.
+pub fn background(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        Theme::Light => "light",
+    }
+}
+
+/// Neovim arguments that start it with `'background'` matching `theme`,
+/// before init.lua runs.
+pub fn background_args(theme: Theme) -> [String; 2] {
+    [
+        "--cmd".to_string(),
+        format!("set background={}", background(theme)),
+    ]
+}
+
+/// Command switching `'background'` over after the system went from
+/// `from` to `to`. Left alone if something other than the hint set it.
+pub fn follow_command(from: Theme, to: Theme) -> String {
+    format!(
+        "if &background ==# '{}' | set background={} | endif",
+        background(from),
+        background(to)
+    )
+}
+
+/// Colors drawn until Neovim sends its own.
+pub fn default_colors(theme: Theme) -> DefaultColors {
+    match theme {
+        Theme::Dark => DefaultColors::default(),
+        Theme::Light => DefaultColors::light(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_portal_reply() {
+        let reply = "method return time=1791980130.042 sender=:1.12 -> destination=:1.99 serial=7 reply_serial=2\n   variant       variant          uint32 1\n";
+        assert_eq!(parse_portal_reply(reply), Some(Theme::Dark));
+        let reply = "method return\n   variant       variant          uint32 2\n";
+        assert_eq!(parse_portal_reply(reply), Some(Theme::Light));
+        let reply = "method return\n   variant       variant          uint32 0\n";
+        assert_eq!(parse_portal_reply(reply), None);
+        assert_eq!(parse_portal_reply(""), None);
+    }
+
+    #[test]
+    fn test_background_follows_the_theme() {
+        assert_eq!(
+            background_args(Theme::Light),
+            ["--cmd", "set background=light"]
+        );
+        assert_eq!(
+            follow_command(Theme::Light, Theme::Dark),
+            "if &background ==# 'light' | set background=dark | endif"
+        );
+        assert!(!default_colors(Theme::Light).background.is_dark());
+        assert!(default_colors(Theme::Dark).background.is_dark());
+    }
+}
@@ -0,0 +1,466 @@
+//! Native file pickers for `:GuiOpen` and `:GuiSaveAs`.
+//!
+//! A picker never holds up the event loop or the bridge: macOS shows it as
+//! a sheet on the window, Linux and Windows run it on a thread of its own,
+//! and the choice comes back as `GUIEvent::FileChosen`. Linux has no
+//! picker of its own, so this runs zenity or, under KDE, kdialog.
+
+use std::path::{Path, PathBuf};
+
+use winit::event_loop::EventLoopProxy;
+use winit::window::Window;
+
+use crate::event::{GUIEvent, UserEvent};
+
+/// Which picker to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileDialog {
+    /// Files to open, several at once.
+    Open,
+    /// A file to write the current buffer to.
+    SaveAs,
+}
+
+impl FileDialog {
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    fn title(self) -> &'static str {
+        match self {
+            FileDialog::Open => "Open",
+            FileDialog::SaveAs => "Save As",
+        }
+    }
+}
+
+/// What the picker came back with: the files chosen, none if it was
+/// cancelled, or why it couldn't be shown.
+pub type FileChoice = Result<Vec<PathBuf>, String>;
+
+#[derive(Debug, thiserror::Error)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+enum DialogError {
+    #[error("No file picker found; install zenity or kdialog")]
+    NoPicker,
+
+    #[error("Failed to run {program}: {source}")]
+    Run {
+        program: &'static str,
+        source: std::io::Error,
+    },
+
+    #[cfg(target_os = "windows")]
+    #[error("The file dialog failed with error {0:#x}")]
+    Windows(u32),
+}
+
+/// Shows `dialog` over `window`, starting next to `current`, the file of
+/// the current buffer. For Save As it also suggests that name.
+pub fn show(
+    dialog: FileDialog,
+    window: &Window,
+    current: Option<&Path>,
+    proxy: EventLoopProxy<UserEvent>,
+) {
+    let send = move |choice: FileChoice| {
+        let _ = proxy.send_event(UserEvent::GUI(GUIEvent::FileChosen(dialog, choice)));
+    };
+    #[cfg(target_os = "macos")]
+    macos::show(dialog, window, current, send);
+    #[cfg(not(target_os = "macos"))]
+    {
+        let owner = platform::owner(window);
+        let current = current.map(Path::to_path_buf);
+        let spawned = std::thread::Builder::new()
+            .name("file-dialog".to_string())
+            .spawn(move || {
+                let choice = platform::run(dialog, owner, current.as_deref());
+                send(choice.map_err(|e| e.to_string()));
+            });
+        if let Err(e) = spawned {
+            log::error!("Failed to start the file dialog: {}", e);
+        }
+    }
+}
+
+/// The directory to start in, and for Save As the name to suggest.
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn split_current(current: Option<&Path>) -> (Option<&Path>, Option<String>) {
+    let Some(current) = current else {
+        return (None, None);
+    };
+    let name = current
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned());
+    (current.parent(), name)
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use winit::window::Window;
+
+    use super::{kdialog_args, zenity_args, DialogError, FileDialog};
+
+    /// The window's X11 id, which kdialog can stay on top of. Wayland has
+    /// no way to hand a window to another program.
+    pub(super) fn owner(window: &Window) -> Option<String> {
+        match window.window_handle().ok()?.as_raw() {
+            RawWindowHandle::Xlib(handle) => Some(handle.window.to_string()),
+            RawWindowHandle::Xcb(handle) => Some(handle.window.to_string()),
+            _ => None,
+        }
+    }
+
+    pub(super) fn run(
+        dialog: FileDialog,
+        owner: Option<String>,
+        current: Option<&Path>,
+    ) -> Result<Vec<PathBuf>, DialogError> {
+        let kde = std::env::var("XDG_CURRENT_DESKTOP").is_ok_and(|desktop| desktop.contains("KDE"));
+        let pickers = if kde {
+            ["kdialog", "zenity"]
+        } else {
+            ["zenity", "kdialog"]
+        };
+        let program = pickers
+            .into_iter()
+            .find(|program| which::which(program).is_ok())
+            .ok_or(DialogError::NoPicker)?;
+        let args = match program {
+            "kdialog" => kdialog_args(dialog, current, owner.as_deref()),
+            _ => zenity_args(dialog, current),
+        };
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|source| DialogError::Run { program, source })?;
+        // Both exit with 1 when cancelled.
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        Ok(super::parse_paths(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// zenity's arguments for `dialog`, one chosen path per line of output.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn zenity_args(dialog: FileDialog, current: Option<&Path>) -> Vec<String> {
+    let mut args = vec![
+        "--file-selection".to_string(),
+        format!("--title={}", dialog.title()),
+    ];
+    match dialog {
+        FileDialog::Open => {
+            args.extend(["--multiple".to_string(), "--separator=\n".to_string()]);
+            // A trailing slash starts in the directory without picking it.
+            if let Some(dir) = current.and_then(Path::parent) {
+                args.push(format!("--filename={}/", dir.display()));
+            }
+        }
+        FileDialog::SaveAs => {
+            args.extend(["--save".to_string(), "--confirm-overwrite".to_string()]);
+            if let Some(current) = current {
+                args.push(format!("--filename={}", current.display()));
+            }
+        }
+    }
+    args
+}
+
+/// kdialog's arguments for `dialog`, one chosen path per line of output.
+/// It stays over the X11 window `owner`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn kdialog_args(dialog: FileDialog, current: Option<&Path>, owner: Option<&str>) -> Vec<String> {
+    let mut args = vec![format!("--title={}", dialog.title())];
+    if let Some(owner) = owner {
+        args.push(format!("--attach={}", owner));
+    }
+    let start = match dialog {
+        FileDialog::Open => {
+            args.extend([
+                "--getopenfilename".to_string(),
+                "--multiple".to_string(),
+                "--separate-output".to_string(),
+            ]);
+            current.and_then(Path::parent)
+        }
+        FileDialog::SaveAs => {
+            args.push("--getsavefilename".to_string());
+            current
+        }
+    };
+    if let Some(start) = start {
+        args.push(start.display().to_string());
+    }
+    args
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_paths(output: &str) -> Vec<PathBuf> {
+    output
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use std::path::{Path, PathBuf};
+
+    use winapi::um::combaseapi::CoInitializeEx;
+    use winapi::um::commdlg::{
+        CommDlgExtendedError, GetOpenFileNameW, GetSaveFileNameW, OFN_ALLOWMULTISELECT,
+        OFN_EXPLORER, OFN_FILEMUSTEXIST, OFN_NOCHANGEDIR, OFN_OVERWRITEPROMPT, OFN_PATHMUSTEXIST,
+        OPENFILENAMEW,
+    };
+    use winapi::um::objbase::COINIT_APARTMENTTHREADED;
+    use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use winit::window::Window;
+
+    use super::{DialogError, FileDialog};
+
+    /// Room for the chosen paths, as much as a multiple selection can name.
+    const BUFFER_LEN: usize = 32 * 1024;
+
+    /// The window's HWND, which the dialog is modal over.
+    pub(super) fn owner(window: &Window) -> isize {
+        match window.window_handle().map(|handle| handle.as_raw()) {
+            Ok(RawWindowHandle::Win32(handle)) => handle.hwnd.get(),
+            _ => 0,
+        }
+    }
+
+    fn wide(s: &std::ffi::OsStr) -> Vec<u16> {
+        s.encode_wide().chain(std::iter::once(0)).collect()
+    }
+
+    pub(super) fn run(
+        dialog: FileDialog,
+        owner: isize,
+        current: Option<&Path>,
+    ) -> Result<Vec<PathBuf>, DialogError> {
+        // The dialog's shell extensions want COM on this thread.
+        unsafe { CoInitializeEx(std::ptr::null_mut(), COINIT_APARTMENTTHREADED) };
+
+        let (dir, name) = super::split_current(current);
+        let dir = dir.map(|dir| wide(dir.as_os_str()));
+        let title = wide(dialog.title().as_ref());
+        let mut buffer = vec![0u16; BUFFER_LEN];
+        if let (FileDialog::SaveAs, Some(name)) = (dialog, name) {
+            let name: Vec<u16> = name.encode_utf16().take(BUFFER_LEN - 1).collect();
+            buffer[..name.len()].copy_from_slice(&name);
+        }
+
+        let mut ofn: OPENFILENAMEW = unsafe { std::mem::zeroed() };
+        ofn.lStructSize = std::mem::size_of::<OPENFILENAMEW>() as u32;
+        ofn.hwndOwner = owner as _;
+        ofn.lpstrFile = buffer.as_mut_ptr();
+        ofn.nMaxFile = BUFFER_LEN as u32;
+        ofn.lpstrInitialDir = dir.as_ref().map_or(std::ptr::null(), |dir| dir.as_ptr());
+        ofn.lpstrTitle = title.as_ptr();
+        ofn.Flags = OFN_EXPLORER | OFN_NOCHANGEDIR | OFN_PATHMUSTEXIST;
+        let chosen = match dialog {
+            FileDialog::Open => {
+                ofn.Flags |= OFN_ALLOWMULTISELECT | OFN_FILEMUSTEXIST;
+                unsafe { GetOpenFileNameW(&mut ofn) }
+            }
+            FileDialog::SaveAs => {
+                ofn.Flags |= OFN_OVERWRITEPROMPT;
+                unsafe { GetSaveFileNameW(&mut ofn) }
+            }
+        };
+        if chosen == 0 {
+            // Zero when cancelled, an error code otherwise.
+            return match unsafe { CommDlgExtendedError() } {
+                0 => Ok(Vec::new()),
+                code => Err(DialogError::Windows(code)),
+            };
+        }
+        Ok(parse_selection(&buffer))
+    }
+
+    /// The paths in the dialog's buffer: one full path, or with several
+    /// files the directory followed by their names, each ending in a nul
+    /// and the list in another.
+    pub(super) fn parse_selection(buffer: &[u16]) -> Vec<PathBuf> {
+        let parts: Vec<PathBuf> = buffer
+            .split(|&c| c == 0)
+            .take_while(|part| !part.is_empty())
+            .map(|part| PathBuf::from(OsString::from_wide(part)))
+            .collect();
+        match parts.split_first() {
+            Some((dir, names)) if !names.is_empty() => {
+                names.iter().map(|name| dir.join(name)).collect()
+            }
+            _ => parts,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::path::{Path, PathBuf};
+
+    use block2::RcBlock;
+    use objc2_app_kit::{NSModalResponse, NSModalResponseOK, NSOpenPanel, NSSavePanel, NSView};
+    use objc2_foundation::{MainThreadMarker, NSString, NSURL};
+    use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use winit::window::Window;
+
+    use super::{FileChoice, FileDialog};
+
+    pub(super) fn show(
+        dialog: FileDialog,
+        window: &Window,
+        current: Option<&Path>,
+        send: impl Fn(FileChoice) + 'static,
+    ) {
+        let Some(mtm) = MainThreadMarker::new() else {
+            return;
+        };
+        let Ok(handle) = window.window_handle() else {
+            return;
+        };
+        let RawWindowHandle::AppKit(handle) = handle.as_raw() else {
+            return;
+        };
+        let view: &NSView = unsafe { handle.ns_view.cast::<NSView>().as_ref() };
+        let Some(ns_window) = view.window() else {
+            return;
+        };
+
+        let (dir, name) = super::split_current(current);
+        let url =
+            |path: &Path| NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy()));
+        let chosen = |url: &NSURL| url.path().map(|path| PathBuf::from(path.to_string()));
+        match dialog {
+            FileDialog::Open => {
+                let panel = NSOpenPanel::openPanel(mtm);
+                panel.setCanChooseFiles(true);
+                panel.setCanChooseDirectories(false);
+                panel.setAllowsMultipleSelection(true);
+                panel.setDirectoryURL(dir.map(url).as_deref());
+                let sheet = panel.clone();
+                let handler = RcBlock::new(move |response: NSModalResponse| {
+                    let paths = if response == NSModalResponseOK {
+                        sheet.URLs().iter().filter_map(|url| chosen(&url)).collect()
+                    } else {
+                        Vec::new()
+                    };
+                    send(Ok(paths));
+                });
+                // SAFETY: The handler runs once, on the main thread.
+                unsafe { panel.beginSheetModalForWindow_completionHandler(&ns_window, &handler) };
+            }
+            FileDialog::SaveAs => {
+                let panel = NSSavePanel::savePanel(mtm);
+                panel.setCanCreateDirectories(true);
+                panel.setDirectoryURL(dir.map(url).as_deref());
+                if let Some(name) = name {
+                    panel.setNameFieldStringValue(&NSString::from_str(&name));
+                }
+                let sheet = panel.clone();
+                let handler = RcBlock::new(move |response: NSModalResponse| {
+                    let paths = if response == NSModalResponseOK {
+                        sheet
+                            .URL()
+                            .and_then(|url| chosen(&url))
+                            .into_iter()
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    send(Ok(paths));
+                });
+                // SAFETY: The handler runs once, on the main thread.
+                unsafe { panel.beginSheetModalForWindow_completionHandler(&ns_window, &handler) };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zenity_args() {
+        let current = Path::new("/home/me/notes.md");
+        assert_eq!(
+            zenity_args(FileDialog::Open, Some(current)),
+            [
+                "--file-selection",
+                "--title=Open",
+                "--multiple",
+                "--separator=\n",
+                "--filename=/home/me/",
+            ]
+        );
+        assert_eq!(
+            zenity_args(FileDialog::SaveAs, Some(current)),
+            [
+                "--file-selection",
+                "--title=Save As",
+                "--save",
+                "--confirm-overwrite",
+                "--filename=/home/me/notes.md",
+            ]
+        );
+        assert_eq!(zenity_args(FileDialog::SaveAs, None).len(), 4);
+    }
+
+    #[test]
+    fn test_kdialog_args() {
+        let current = Path::new("/home/me/notes.md");
+        assert_eq!(
+            kdialog_args(FileDialog::Open, Some(current), Some("42")),
+            [
+                "--title=Open",
+                "--attach=42",
+                "--getopenfilename",
+                "--multiple",
+                "--separate-output",
+                "/home/me",
+            ]
+        );
+        assert_eq!(
+            kdialog_args(FileDialog::SaveAs, None, None),
+            ["--title=Save As", "--getsavefilename"]
+        );
+    }
+
+    #[test]
+    fn test_parse_paths() {
+        assert_eq!(
+            parse_paths("/tmp/a b.txt\n/tmp/c.rs\n"),
+            [PathBuf::from("/tmp/a b.txt"), PathBuf::from("/tmp/c.rs")]
+        );
+        assert!(parse_paths("").is_empty());
+        assert_eq!(
+            split_current(Some(Path::new("/tmp/a.txt"))),
+            (Some(Path::new("/tmp")), Some("a.txt".to_string()))
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_parse_selection() {
+        let wide = |s: &str| s.encode_utf16().collect::<Vec<u16>>();
+        assert_eq!(
+            platform::parse_selection(&wide("C:\\a.txt\0\0")),
+            [PathBuf::from("C:\\a.txt")]
+        );
+        assert_eq!(
+            platform::parse_selection(&wide("C:\\dir\0a.txt\0b.txt\0\0")),
+            [
+                PathBuf::from("C:\\dir\\a.txt"),
+                PathBuf::from("C:\\dir\\b.txt")
+            ]
+        );
+    }
+}
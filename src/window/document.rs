@@ -0,0 +1,26 @@
+//! The file of the current buffer as the window's represented file on
+//! macOS: its icon in the titlebar can be dragged like the file itself, and
+//! Cmd-clicking the title shows where it is.
+
+use std::path::Path;
+
+use objc2_app_kit::NSView;
+use objc2_foundation::{NSString, NSURL};
+use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use winit::window::Window;
+
+/// Makes `path` the file `window` stands for, or none with `None`.
+pub fn set_represented_file(window: &Window, path: Option<&Path>) {
+    let Ok(handle) = window.window_handle() else {
+        return;
+    };
+    let RawWindowHandle::AppKit(handle) = handle.as_raw() else {
+        return;
+    };
+    let view: &NSView = unsafe { handle.ns_view.cast::<NSView>().as_ref() };
+    let Some(ns_window) = view.window() else {
+        return;
+    };
+    let url = path.map(|path| NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy())));
+    ns_window.setRepresentedURL(url.as_deref());
+}
@@ -0,0 +1,281 @@
+//! Dragging the Visual selection out of the window, on macOS: a Cmd-drag
+//! that starts inside the selection hands its text to AppKit as the
+//! contents of a drag, which other apps take as dropped text.
+
+use nvim_rs::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// `v`
+    Char,
+    /// `V`
+    Line,
+    /// `CTRL-V`
+    Block,
+}
+
+/// A position on screen as `screenpos()` reports it, 0-based.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ScreenPos {
+    row: i64,
+    col: i64,
+    /// The last column of the character, past `col` for wide ones.
+    endcol: i64,
+}
+
+/// Where the Visual selection is on the grid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectionRegion {
+    mode: SelectionMode,
+    start: ScreenPos,
+    end: ScreenPos,
+    /// The window's columns, as `left..right`.
+    cols: (i64, i64),
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    value
+        .as_map()?
+        .iter()
+        .find(|(key, _)| key.as_str() == Some(name))
+        .map(|(_, value)| value)
+}
+
+fn int(value: &Value, name: &str) -> Option<i64> {
+    field(value, name)?.as_i64()
+}
+
+/// `screenpos()`'s result, or None when the position is scrolled out of
+/// the window, which it reports as all zeros.
+fn screen_pos(value: &Value) -> Option<ScreenPos> {
+    let row = int(value, "row")?;
+    if row == 0 {
+        return None;
+    }
+    let col = int(value, "col")?;
+    Some(ScreenPos {
+        row: row - 1,
+        col: col - 1,
+        endcol: int(value, "endcol").unwrap_or(col) - 1,
+    })
+}
+
+impl SelectionRegion {
+    /// Parses the region the `selection` notification carries:
+    /// `{mode, v, cursor, forward, win}`, where `v` and `cursor` are the
+    /// `screenpos()` of either end, `forward` whether `v` comes first in
+    /// the buffer, and `win` is `win_screenpos()` followed by the window's
+    /// width and height.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let mode = match field(value, "mode")?.as_str()? {
+            "v" => SelectionMode::Char,
+            "V" => SelectionMode::Line,
+            "\x16" => SelectionMode::Block,
+            _ => return None,
+        };
+        let win: Vec<i64> = field(value, "win")?
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_i64)
+            .collect();
+        let &[top, left, width, height] = win.as_slice() else {
+            return None;
+        };
+        let rows = (top - 1, top - 1 + height);
+        let cols = (left - 1, left - 1 + width);
+
+        let v = screen_pos(field(value, "v")?);
+        let cursor = screen_pos(field(value, "cursor")?);
+        // A comparison, so 0 or 1 rather than a boolean.
+        let forward = int(value, "forward")? != 0;
+        let (start, end) = if forward { (v, cursor) } else { (cursor, v) };
+        // An end scrolled out of the window is past its edge.
+        let start = start.unwrap_or(ScreenPos {
+            row: rows.0,
+            col: cols.0,
+            endcol: cols.0,
+        });
+        let end = end.unwrap_or(ScreenPos {
+            row: rows.1 - 1,
+            col: cols.1 - 1,
+            endcol: cols.1 - 1,
+        });
+        Some(Self {
+            mode,
+            start,
+            end,
+            cols,
+        })
+    }
+
+    /// Whether the cell at `row`, `col` is selected.
+    pub fn contains(&self, row: i64, col: i64) -> bool {
+        let (start, end) = (self.start, self.end);
+        if row < start.row || row > end.row || col < self.cols.0 || col >= self.cols.1 {
+            return false;
+        }
+        match self.mode {
+            SelectionMode::Line => true,
+            SelectionMode::Block => {
+                let left = start.col.min(end.col);
+                let right = start.endcol.max(end.endcol);
+                (left..=right).contains(&col)
+            }
+            SelectionMode::Char => {
+                (row > start.row || col >= start.col) && (row < end.row || col <= end.endcol)
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub use self::native::start_drag;
+
+#[cfg(target_os = "macos")]
+mod native {
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, ProtocolObject};
+    use objc2::{define_class, msg_send, MainThreadOnly};
+    use objc2_app_kit::{
+        NSApplication, NSDragOperation, NSDraggingContext, NSDraggingItem, NSDraggingSession,
+        NSDraggingSource, NSView,
+    };
+    use objc2_foundation::{
+        MainThreadMarker, NSArray, NSObject, NSObjectProtocol, NSRect, NSSize, NSString,
+    };
+    use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use winit::window::Window;
+
+    define_class!(
+        // SAFETY: NSObject has no subclassing requirements, we don't implement Drop.
+        #[unsafe(super(NSObject))]
+        #[thread_kind = MainThreadOnly]
+        #[name = "GUIDragSource"]
+        struct DragSource;
+
+        unsafe impl NSObjectProtocol for DragSource {}
+
+        unsafe impl NSDraggingSource for DragSource {
+            #[unsafe(method(draggingSession:sourceOperationMaskForDraggingContext:))]
+            fn source_operation_mask(
+                &self,
+                _session: &NSDraggingSession,
+                _context: NSDraggingContext,
+            ) -> NSDragOperation {
+                // The text is handed over, never moved out of the buffer.
+                NSDragOperation::Copy
+            }
+        }
+    );
+
+    impl DragSource {
+        fn new(mtm: MainThreadMarker) -> Retained<Self> {
+            unsafe { msg_send![mtm.alloc::<Self>(), init] }
+        }
+    }
+
+    /// Starts dragging `text` out of `window`. Must be called while
+    /// handling the mouse move that starts the drag: AppKit takes the
+    /// pointer from the event it is handling, and tracks it from there.
+    pub fn start_drag(window: &Window, text: &str) {
+        let Some(mtm) = MainThreadMarker::new() else {
+            return;
+        };
+        let Some(event) = NSApplication::sharedApplication(mtm).currentEvent() else {
+            return;
+        };
+        let Ok(handle) = window.window_handle() else {
+            return;
+        };
+        let RawWindowHandle::AppKit(handle) = handle.as_raw() else {
+            return;
+        };
+        let view: &NSView = unsafe { handle.ns_view.cast::<NSView>().as_ref() };
+
+        let text = NSString::from_str(text);
+        let item = NSDraggingItem::initWithPasteboardWriter(
+            NSDraggingItem::alloc(),
+            ProtocolObject::from_ref(&*text),
+        );
+        let location = view.convertPoint_fromView(event.locationInWindow(), None);
+        unsafe {
+            item.setDraggingFrame_contents(
+                NSRect::new(location, NSSize::new(1.0, 1.0)),
+                None::<&AnyObject>,
+            );
+        }
+
+        // The session retains its source until the drag ends.
+        let source = DragSource::new(mtm);
+        let _session = view.beginDraggingSessionWithItems_event_source(
+            &NSArray::from_retained_slice(&[item]),
+            &event,
+            ProtocolObject::from_ref(&*source),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(row: i64, col: i64) -> Value {
+        Value::Map(vec![
+            ("row".into(), row.into()),
+            ("col".into(), col.into()),
+            ("endcol".into(), col.into()),
+        ])
+    }
+
+    fn region(mode: &str, v: Value, cursor: Value, forward: bool) -> SelectionRegion {
+        let value = Value::Map(vec![
+            ("mode".into(), mode.into()),
+            ("v".into(), v),
+            ("cursor".into(), cursor),
+            ("forward".into(), i64::from(forward).into()),
+            // Rows 2-11, columns 1-40 of the screen
+            (
+                "win".into(),
+                Value::Array(vec![2.into(), 1.into(), 40.into(), 10.into()]),
+            ),
+        ]);
+        SelectionRegion::from_value(&value).unwrap()
+    }
+
+    #[test]
+    fn test_charwise_region() {
+        // From row 3, column 10 back to row 5, column 4 (screen, 1-based)
+        let region = region("v", pos(5, 4), pos(3, 10), false);
+        assert!(!region.contains(2, 8));
+        assert!(region.contains(2, 9));
+        assert!(region.contains(3, 0));
+        assert!(region.contains(3, 39));
+        assert!(!region.contains(3, 40));
+        assert!(region.contains(4, 3));
+        assert!(!region.contains(4, 4));
+    }
+
+    #[test]
+    fn test_linewise_and_blockwise_regions() {
+        let lines = region("V", pos(3, 10), pos(4, 2), true);
+        assert!(lines.contains(2, 0));
+        assert!(lines.contains(3, 39));
+        assert!(!lines.contains(4, 0));
+
+        let block = region("\x16", pos(3, 10), pos(4, 2), true);
+        assert!(block.contains(2, 1));
+        assert!(block.contains(3, 9));
+        assert!(!block.contains(3, 10));
+        assert!(!block.contains(2, 0));
+    }
+
+    #[test]
+    fn test_ends_out_of_view() {
+        // Selected from above the window down to row 4
+        let region = region("v", pos(0, 0), pos(4, 5), true);
+        assert!(region.contains(1, 0));
+        assert!(!region.contains(0, 0));
+        assert!(region.contains(3, 4));
+        assert!(!region.contains(3, 5));
+    }
+}
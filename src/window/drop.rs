@@ -0,0 +1,87 @@
+//! Opening files dropped on the window or picked with `:GuiOpen`, and
+//! saving to a file picked with `:GuiSaveAs`.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::OpenFilesIn;
+
+/// Ex commands that open `paths` in Neovim. Files open as `open_in` says;
+/// a directory becomes the working directory instead, the first one if
+/// there are several.
+pub fn open_commands(paths: &[PathBuf], open_in: OpenFilesIn) -> Vec<String> {
+    let (dirs, files): (Vec<&PathBuf>, Vec<&PathBuf>) = paths.iter().partition(|p| p.is_dir());
+
+    let mut commands = Vec::new();
+    if let Some(dir) = dirs.first() {
+        commands.push(execute("cd", &[dir]));
+    }
+    match open_in {
+        OpenFilesIn::Window if files.len() > 1 => commands.push(execute("args", &files)),
+        OpenFilesIn::Window => commands.extend(files.iter().map(|f| execute("drop", &[f]))),
+        OpenFilesIn::Tab => commands.extend(files.iter().map(|f| execute("tab drop", &[f]))),
+        OpenFilesIn::Split => commands.extend(files.iter().map(|f| execute("split", &[f]))),
+        OpenFilesIn::Vsplit => commands.extend(files.iter().map(|f| execute("vsplit", &[f]))),
+    }
+    commands
+}
+
+/// Ex command writing the current buffer to `path` and editing that from
+/// then on. The picker has already asked before overwriting, hence the `!`.
+pub fn save_as_command(path: &Path) -> String {
+    execute("saveas!", &[path])
+}
+
+/// `:execute` for `command` on `paths`, escaped by Neovim's `fnameescape()`.
+fn execute(command: &str, paths: &[impl AsRef<Path>]) -> String {
+    let args: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            let path = path.as_ref().to_string_lossy().replace('\'', "''");
+            format!("fnameescape('{}')", path)
+        })
+        .collect();
+    format!("execute '{} ' . {}", command, args.join(" . ' ' . "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_as_command() {
+        assert_eq!(
+            save_as_command(Path::new("/tmp/a b.txt")),
+            "execute 'saveas! ' . fnameescape('/tmp/a b.txt')"
+        );
+    }
+
+    #[test]
+    fn test_open_commands() {
+        let file = PathBuf::from("/tmp/it's.txt");
+        assert_eq!(
+            open_commands(std::slice::from_ref(&file), OpenFilesIn::Window),
+            ["execute 'drop ' . fnameescape('/tmp/it''s.txt')"]
+        );
+        assert_eq!(
+            open_commands(&[file.clone(), "b.rs".into()], OpenFilesIn::Window),
+            ["execute 'args ' . fnameescape('/tmp/it''s.txt') . ' ' . fnameescape('b.rs')"]
+        );
+        assert_eq!(
+            open_commands(&["a".into(), "b".into()], OpenFilesIn::Vsplit),
+            [
+                "execute 'vsplit ' . fnameescape('a')",
+                "execute 'vsplit ' . fnameescape('b')"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_open_commands_cd_to_directory() {
+        let dir = std::env::temp_dir();
+        let commands = open_commands(&[dir.clone(), "a".into()], OpenFilesIn::Tab);
+        assert_eq!(commands.len(), 2);
+        assert!(commands[0].starts_with("execute 'cd ' . "));
+        assert!(commands[0].contains(&*dir.to_string_lossy()));
+        assert_eq!(commands[1], "execute 'tab drop ' . fnameescape('a')");
+    }
+}
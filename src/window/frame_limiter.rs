@@ -0,0 +1,118 @@
+//! Frame pacing at `max_fps`, with any vsync mode.
+//!
+//! Without vsync, presenting returns immediately and nothing stops the app
+//! from redrawing as fast as the GPU allows. The limiter spaces frames at
+//! `max_fps`: the event loop sleeps until shortly before the next frame is
+//! due, and `wait` spins through the last stretch, which OS timers are too
+//! coarse to hit reliably. With vsync a lower cap saves battery.
+
+use std::time::{Duration, Instant};
+
+/// The final part of each wait, spent spinning instead of sleeping.
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Frame rate used when `max_fps` is unset and the monitor doesn't report one.
+pub const DEFAULT_MAX_FPS: u32 = 60;
+
+#[derive(Debug)]
+pub struct FrameLimiter {
+    interval: Duration,
+    next_frame: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(max_fps: u32, now: Instant) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / max_fps.max(1),
+            next_frame: now,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Whether the next frame is too far off to wait for in place, so the
+    /// event loop should sleep until `wake_at` first.
+    pub fn should_defer(&self, now: Instant) -> bool {
+        self.next_frame.saturating_duration_since(now) > SPIN_MARGIN
+    }
+
+    /// When the event loop should wake up to start the next frame.
+    pub fn wake_at(&self) -> Instant {
+        self.next_frame
+            .checked_sub(SPIN_MARGIN)
+            .unwrap_or(self.next_frame)
+    }
+
+    /// Blocks until the next frame is due: sleeps for most of the time and
+    /// spins for the last `SPIN_MARGIN`.
+    pub fn wait(&self) {
+        let remaining = self.next_frame.saturating_duration_since(Instant::now());
+        if remaining > SPIN_MARGIN {
+            std::thread::sleep(remaining - SPIN_MARGIN);
+        }
+        while Instant::now() < self.next_frame {
+            std::hint::spin_loop();
+        }
+    }
+
+    /// Records a frame starting at `now` and schedules the next one. A
+    /// frame that is at most one interval late keeps the cadence; after
+    /// longer gaps (e.g. idle) the schedule restarts from `now`.
+    pub fn frame_started(&mut self, now: Instant) {
+        let next = self.next_frame + self.interval;
+        self.next_frame = if next < now {
+            now + self.interval
+        } else {
+            next
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_from_max_fps() {
+        let now = Instant::now();
+        assert_eq!(
+            FrameLimiter::new(100, now).interval(),
+            Duration::from_millis(10)
+        );
+        assert_eq!(FrameLimiter::new(0, now).interval(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_frame_schedule() {
+        let start = Instant::now();
+        let mut limiter = FrameLimiter::new(100, start);
+        assert!(!limiter.should_defer(start));
+
+        limiter.frame_started(start);
+        assert!(limiter.should_defer(start));
+        assert_eq!(limiter.wake_at(), start + Duration::from_millis(8));
+        assert!(!limiter.should_defer(start + Duration::from_millis(9)));
+
+        // Slightly late: the cadence is kept.
+        limiter.frame_started(start + Duration::from_millis(13));
+        assert_eq!(limiter.next_frame, start + Duration::from_millis(20));
+
+        // After an idle gap the schedule restarts.
+        limiter.frame_started(start + Duration::from_secs(1));
+        assert_eq!(
+            limiter.next_frame,
+            start + Duration::from_secs(1) + Duration::from_millis(10)
+        );
+    }
+
+    #[test]
+    fn test_wait_reaches_deadline() {
+        let start = Instant::now();
+        let mut limiter = FrameLimiter::new(250, start);
+        limiter.frame_started(start);
+        limiter.wait();
+        assert!(Instant::now() >= start + Duration::from_millis(4));
+    }
+}
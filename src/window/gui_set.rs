@@ -0,0 +1,106 @@
+//! `:GuiSet key=value ...`: renderer settings changed without a restart,
+//! to narrow down a rendering bug while it is on screen.
+//!
+//! - `renderer=optimized|naive`: naive turns off the row cache, partial
+//!   redraws and skipped frames.
+//! - `vsync=on|off`: whether frames wait for the display's refresh.
+
+use crate::renderer::RenderMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuiSetting {
+    Renderer(RenderMode),
+    Vsync(bool),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum GuiSetError {
+    #[error("Expected key=value, got {0:?}")]
+    Malformed(String),
+
+    #[error("Unknown setting {0:?}, expected renderer or vsync")]
+    UnknownKey(String),
+
+    #[error("Invalid value {value:?} for {key}")]
+    InvalidValue { key: &'static str, value: String },
+}
+
+/// The settings in `args`, all of them or none if one doesn't parse.
+pub fn parse(args: &[&str]) -> Result<Vec<GuiSetting>, GuiSetError> {
+    args.iter().map(|arg| parse_one(arg)).collect()
+}
+
+fn parse_one(arg: &str) -> Result<GuiSetting, GuiSetError> {
+    let Some((key, value)) = arg.split_once('=') else {
+        return Err(GuiSetError::Malformed(arg.to_string()));
+    };
+    let invalid = |key| GuiSetError::InvalidValue {
+        key,
+        value: value.to_string(),
+    };
+    match key {
+        "renderer" => RenderMode::parse(value)
+            .map(GuiSetting::Renderer)
+            .ok_or_else(|| invalid("renderer")),
+        "vsync" => match value {
+            "on" => Ok(GuiSetting::Vsync(true)),
+            "off" => Ok(GuiSetting::Vsync(false)),
+            _ => Err(invalid("vsync")),
+        },
+        _ => Err(GuiSetError::UnknownKey(key.to_string())),
+    }
+}
+
+/// The current settings as `:GuiSet` takes them.
+pub fn describe(renderer: RenderMode, vsync: bool) -> String {
+    format!(
+        "renderer={} vsync={}",
+        renderer.name(),
+        if vsync { "on" } else { "off" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_settings() {
+        assert_eq!(
+            parse(&["renderer=naive", "vsync=off"]),
+            Ok(vec![
+                GuiSetting::Renderer(RenderMode::Naive),
+                GuiSetting::Vsync(false),
+            ])
+        );
+        assert_eq!(
+            parse(&["renderer=optimized"]),
+            Ok(vec![GuiSetting::Renderer(RenderMode::Optimized)])
+        );
+        assert_eq!(parse(&[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse(&["vsync=on", "vsync"]),
+            Err(GuiSetError::Malformed("vsync".to_string()))
+        );
+        assert_eq!(
+            parse(&["fps=60"]),
+            Err(GuiSetError::UnknownKey("fps".to_string()))
+        );
+        assert_eq!(
+            parse(&["renderer=fast"]),
+            Err(GuiSetError::InvalidValue {
+                key: "renderer",
+                value: "fast".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(describe(RenderMode::Naive, true), "renderer=naive vsync=on");
+    }
+}
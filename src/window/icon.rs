@@ -0,0 +1,85 @@
+//! The window and taskbar icon on Linux and Windows: `[window] icon`, or
+//! the gui.nvim icon built in. macOS takes it from the app bundle, and
+//! Wayland compositors from the desktop entry, so neither needs one.
+
+use std::path::{Path, PathBuf};
+
+use winit::window::{BadIcon, Icon};
+
+use crate::config::WindowSettings;
+use crate::renderer::png::{self, PngError};
+
+/// The gui.nvim icon, small enough to decode quickly at startup.
+const DEFAULT_ICON: &[u8] = include_bytes!("../../assets/icons/gui-nvim-256.png");
+
+#[derive(Debug, thiserror::Error)]
+pub enum IconError {
+    #[error("Failed to read {path:?}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to decode icon: {0}")]
+    Png(#[from] PngError),
+
+    #[error("Invalid icon: {0}")]
+    Icon(#[from] BadIcon),
+}
+
+/// The icon `settings` ask for, or the built-in one if they don't ask or
+/// it can't be loaded.
+pub fn window_icon(settings: &WindowSettings) -> Option<Icon> {
+    if let Some(path) = settings.icon_path() {
+        match load(&path) {
+            Ok(icon) => return Some(icon),
+            Err(e) => log::warn!("Using the default window icon: {}", e),
+        }
+    }
+    match decode(DEFAULT_ICON) {
+        Ok(icon) => Some(icon),
+        Err(e) => {
+            log::error!("Failed to load the default window icon: {}", e);
+            None
+        }
+    }
+}
+
+fn load(path: &Path) -> Result<Icon, IconError> {
+    let data = std::fs::read(path).map_err(|source| IconError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    decode(&data)
+}
+
+fn decode(data: &[u8]) -> Result<Icon, IconError> {
+    let image = png::decode(data)?;
+    Ok(Icon::from_rgba(image.rgba, image.width, image.height)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_icon_decodes() {
+        let image = png::decode(DEFAULT_ICON).unwrap();
+        assert_eq!((image.width, image.height), (256, 256));
+        assert!(window_icon(&WindowSettings::default()).is_some());
+    }
+
+    #[test]
+    fn test_bad_icons_fall_back() {
+        let settings = WindowSettings {
+            icon: Some("/nonexistent/icon.png".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            load(&settings.icon_path().unwrap()),
+            Err(IconError::Read { .. })
+        ));
+        assert!(matches!(decode(b"GIF89a"), Err(IconError::Png(_))));
+        assert!(window_icon(&settings).is_some());
+    }
+}
@@ -0,0 +1,68 @@
+//! Opening links, text Neovim highlights with a `url`, when clicked.
+
+use std::process::Command;
+
+/// Whether `url` starts with a scheme such as `https:`, so the system
+/// opener can't take it for a file name or an option.
+fn has_scheme(url: &str) -> bool {
+    let Some((scheme, _)) = url.split_once(':') else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Opens `url` in the default application for its scheme, such as the
+/// browser.
+pub fn open_url(url: &str) {
+    if !has_scheme(url) {
+        log::warn!("Not opening {:?}, which has no scheme", url);
+        return;
+    }
+    match opener(url).spawn() {
+        Ok(mut child) => {
+            log::info!("Opening {}", url);
+            // Reaped in the background so it doesn't linger as a zombie.
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => log::warn!("Failed to open {}: {}", url, e),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn opener(url: &str) -> Command {
+    let mut command = Command::new("open");
+    command.arg(url);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn opener(url: &str) -> Command {
+    let mut command = Command::new("explorer");
+    command.arg(url);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn opener(url: &str) -> Command {
+    let mut command = Command::new("xdg-open");
+    command.arg(url);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_scheme() {
+        assert!(has_scheme("https://neovim.io"));
+        assert!(has_scheme("mailto:me@example.com"));
+        assert!(has_scheme("git+ssh://host/repo"));
+        assert!(!has_scheme("--help"));
+        assert!(!has_scheme("src/main.rs"));
+        assert!(!has_scheme(":h"));
+        assert!(!has_scheme("1ttp://x"));
+    }
+}
@@ -0,0 +1,156 @@
+//! Native macOS menu bar.
+//!
+//! Replaces winit's default menu with File, Edit, View and Window menus so
+//! the usual Cmd shortcuts work. Items are performed by the app, not by
+//! AppKit: each one carries its `MenuAction` in its tag, and a shared
+//! target forwards it to the event loop as `GUIEvent::Menu`.
+
+use objc2::rc::Retained;
+use objc2::runtime::Sel;
+use objc2::{define_class, msg_send, sel, DefinedClass, MainThreadOnly};
+use objc2_app_kit::{NSApplication, NSEventModifierFlags, NSMenu, NSMenuItem};
+use objc2_foundation::{MainThreadMarker, NSObject, NSObjectProtocol, NSString};
+use winit::event_loop::EventLoopProxy;
+
+use crate::event::{GUIEvent, MenuAction, UserEvent};
+
+struct MenuTargetIvars {
+    event_proxy: EventLoopProxy<UserEvent>,
+}
+
+define_class!(
+    // SAFETY: NSObject has no subclassing requirements, we don't implement Drop.
+    #[unsafe(super(NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[name = "GUIMenuTarget"]
+    #[ivars = MenuTargetIvars]
+    struct MenuTarget;
+
+    impl MenuTarget {
+        #[unsafe(method(menuAction:))]
+        fn menu_action(&self, item: &NSMenuItem) {
+            if let Some(action) = MenuAction::from_tag(item.tag()) {
+                let _ = DefinedClass::ivars(self)
+                    .event_proxy
+                    .send_event(UserEvent::GUI(GUIEvent::Menu(action)));
+            }
+        }
+    }
+
+    unsafe impl NSObjectProtocol for MenuTarget {}
+);
+
+impl MenuTarget {
+    fn new(mtm: MainThreadMarker, event_proxy: EventLoopProxy<UserEvent>) -> Retained<Self> {
+        let this = mtm
+            .alloc::<Self>()
+            .set_ivars(MenuTargetIvars { event_proxy });
+        unsafe { msg_send![super(this), init] }
+    }
+}
+
+/// The installed menu bar. Menu items don't retain their target, so this
+/// must outlive the menu.
+pub struct MenuBar {
+    _target: Retained<MenuTarget>,
+}
+
+impl MenuBar {
+    /// Installs the menu bar. Call after the app finished launching, or
+    /// winit's default menu replaces it. Returns `None` off the main thread.
+    pub fn install(event_proxy: EventLoopProxy<UserEvent>) -> Option<Self> {
+        let mtm = MainThreadMarker::new()?;
+        let target = MenuTarget::new(mtm, event_proxy);
+        let builder = MenuBuilder {
+            mtm,
+            target: &target,
+        };
+
+        let app_menu = builder.menu("gui.nvim");
+        builder.add_native(&app_menu, "Hide gui.nvim", sel!(hide:), "h");
+        let hide_others =
+            builder.add_native(&app_menu, "Hide Others", sel!(hideOtherApplications:), "h");
+        hide_others.setKeyEquivalentModifierMask(
+            NSEventModifierFlags::Command | NSEventModifierFlags::Option,
+        );
+        builder.add_native(&app_menu, "Show All", sel!(unhideAllApplications:), "");
+        app_menu.addItem(&NSMenuItem::separatorItem(mtm));
+        builder.add(&app_menu, "Quit gui.nvim", MenuAction::Quit, "q");
+
+        let file_menu = builder.menu("File");
+        builder.add(&file_menu, "New Window", MenuAction::NewWindow, "n");
+        builder.add(&file_menu, "Open…", MenuAction::Open, "o");
+        // An uppercase key equivalent takes Shift.
+        builder.add(&file_menu, "Save As…", MenuAction::SaveAs, "S");
+
+        let edit_menu = builder.menu("Edit");
+        builder.add(&edit_menu, "Cut", MenuAction::Cut, "x");
+        builder.add(&edit_menu, "Copy", MenuAction::Copy, "c");
+        builder.add(&edit_menu, "Paste", MenuAction::Paste, "v");
+        builder.add(&edit_menu, "Select All", MenuAction::SelectAll, "a");
+
+        let view_menu = builder.menu("View");
+        builder.add(&view_menu, "Zoom In", MenuAction::ZoomIn, "=");
+        builder.add(&view_menu, "Zoom Out", MenuAction::ZoomOut, "-");
+
+        let window_menu = builder.menu("Window");
+        builder.add_native(&window_menu, "Minimize", sel!(performMiniaturize:), "m");
+        builder.add_native(&window_menu, "Zoom", sel!(performZoom:), "");
+
+        let main_menu = NSMenu::new(mtm);
+        for menu in [&app_menu, &file_menu, &edit_menu, &view_menu, &window_menu] {
+            let item = NSMenuItem::new(mtm);
+            item.setSubmenu(Some(menu));
+            main_menu.addItem(&item);
+        }
+
+        let app = NSApplication::sharedApplication(mtm);
+        app.setMainMenu(Some(&main_menu));
+        app.setWindowsMenu(Some(&window_menu));
+
+        Some(Self { _target: target })
+    }
+}
+
+struct MenuBuilder<'a> {
+    mtm: MainThreadMarker,
+    target: &'a MenuTarget,
+}
+
+impl MenuBuilder<'_> {
+    fn menu(&self, title: &str) -> Retained<NSMenu> {
+        NSMenu::initWithTitle(NSMenu::alloc(self.mtm), &NSString::from_str(title))
+    }
+
+    /// Adds an item performed by the app.
+    fn add(&self, menu: &NSMenu, title: &str, action: MenuAction, key: &str) {
+        let item = self.item(title, sel!(menuAction:), key);
+        unsafe { item.setTarget(Some(self.target)) };
+        item.setTag(action.tag());
+        menu.addItem(&item);
+    }
+
+    /// Adds an item AppKit performs itself, sent down the responder chain.
+    fn add_native(
+        &self,
+        menu: &NSMenu,
+        title: &str,
+        action: Sel,
+        key: &str,
+    ) -> Retained<NSMenuItem> {
+        let item = self.item(title, action, key);
+        menu.addItem(&item);
+        item
+    }
+
+    fn item(&self, title: &str, action: Sel, key: &str) -> Retained<NSMenuItem> {
+        unsafe {
+            NSMenuItem::initWithTitle_action_keyEquivalent(
+                NSMenuItem::alloc(self.mtm),
+                &NSString::from_str(title),
+                Some(action),
+                &NSString::from_str(key),
+            )
+        }
+    }
+}
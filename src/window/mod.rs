@@ -1,8 +1,23 @@
 #![allow(clippy::module_inception)]
 
+pub mod appearance;
+pub mod dialogs;
 #[cfg(target_os = "macos")]
 pub mod displaylink;
+#[cfg(target_os = "macos")]
+pub mod document;
+pub mod drag;
+pub mod drop;
+pub mod frame_limiter;
+pub mod gui_set;
+#[cfg(not(target_os = "macos"))]
+pub mod icon;
+pub mod link;
+#[cfg(target_os = "macos")]
+pub mod menu;
+pub mod overlay;
 pub mod render_loop;
+pub mod selection;
 pub mod settings;
 pub mod window;
 
@@ -0,0 +1,138 @@
+//! Keys of the `:GuiMessages` scrollback, which takes the keyboard while
+//! it is open. They move like a pager: `j`/`k` and the arrows by a line,
+//! `<C-d>`/`<C-u>` by half a page, `<C-f>`/`<C-b>` and PageDown/PageUp by
+//! a page, `gg`/`G` and Home/End to either end, and `q` or Esc closes it.
+
+use winit::keyboard::{Key, NamedKey};
+
+use crate::input::Modifiers;
+
+/// What a key does to the scrollback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollbackAction {
+    /// Rows down, or up if negative.
+    Lines(isize),
+    HalfPages(isize),
+    Pages(isize),
+    Top,
+    Bottom,
+    Close,
+}
+
+impl ScrollbackAction {
+    /// Rows this scrolls by in a text area `height` rows tall, or `None`
+    /// if it doesn't scroll by a distance.
+    pub fn rows(self, height: usize) -> Option<isize> {
+        match self {
+            ScrollbackAction::Lines(n) => Some(n),
+            ScrollbackAction::HalfPages(n) => Some(n * (height / 2).max(1) as isize),
+            ScrollbackAction::Pages(n) => Some(n * height.max(1) as isize),
+            _ => None,
+        }
+    }
+}
+
+/// Maps key presses to scrollback actions, remembering a first `g`.
+#[derive(Debug, Default)]
+pub struct ScrollbackKeys {
+    pending_g: bool,
+}
+
+impl ScrollbackKeys {
+    /// The action of a press of `key`, the logical key with Shift
+    /// applied. Anything but a second `g` forgets the first.
+    pub fn action(&mut self, key: &Key, modifiers: &Modifiers) -> Option<ScrollbackAction> {
+        let pending_g = std::mem::take(&mut self.pending_g);
+        if modifiers.alt || modifiers.logo {
+            return None;
+        }
+        if modifiers.ctrl {
+            let Key::Character(c) = key else {
+                return None;
+            };
+            return match c.to_lowercase().as_str() {
+                "d" => Some(ScrollbackAction::HalfPages(1)),
+                "u" => Some(ScrollbackAction::HalfPages(-1)),
+                "f" => Some(ScrollbackAction::Pages(1)),
+                "b" => Some(ScrollbackAction::Pages(-1)),
+                "e" => Some(ScrollbackAction::Lines(1)),
+                "y" => Some(ScrollbackAction::Lines(-1)),
+                _ => None,
+            };
+        }
+        match key {
+            Key::Named(NamedKey::ArrowDown) => Some(ScrollbackAction::Lines(1)),
+            Key::Named(NamedKey::ArrowUp) => Some(ScrollbackAction::Lines(-1)),
+            Key::Named(NamedKey::PageDown) => Some(ScrollbackAction::Pages(1)),
+            Key::Named(NamedKey::PageUp) => Some(ScrollbackAction::Pages(-1)),
+            Key::Named(NamedKey::Home) => Some(ScrollbackAction::Top),
+            Key::Named(NamedKey::End) => Some(ScrollbackAction::Bottom),
+            Key::Named(NamedKey::Escape) => Some(ScrollbackAction::Close),
+            Key::Character(c) => match c.as_str() {
+                "j" => Some(ScrollbackAction::Lines(1)),
+                "k" => Some(ScrollbackAction::Lines(-1)),
+                "G" => Some(ScrollbackAction::Bottom),
+                "q" => Some(ScrollbackAction::Close),
+                "g" if pending_g => Some(ScrollbackAction::Top),
+                "g" => {
+                    self.pending_g = true;
+                    None
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(keys: &mut ScrollbackKeys, key: &str) -> Option<ScrollbackAction> {
+        keys.action(&Key::Character(key.into()), &Modifiers::default())
+    }
+
+    #[test]
+    fn test_pager_keys() {
+        let mut keys = ScrollbackKeys::default();
+        assert_eq!(press(&mut keys, "j"), Some(ScrollbackAction::Lines(1)));
+        assert_eq!(press(&mut keys, "k"), Some(ScrollbackAction::Lines(-1)));
+        assert_eq!(press(&mut keys, "G"), Some(ScrollbackAction::Bottom));
+        assert_eq!(press(&mut keys, "q"), Some(ScrollbackAction::Close));
+        assert_eq!(
+            keys.action(&Key::Named(NamedKey::PageUp), &Modifiers::default()),
+            Some(ScrollbackAction::Pages(-1))
+        );
+
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            keys.action(&Key::Character("d".into()), &ctrl),
+            Some(ScrollbackAction::HalfPages(1))
+        );
+        assert_eq!(keys.action(&Key::Character("j".into()), &ctrl), None);
+    }
+
+    #[test]
+    fn test_gg_goes_to_the_top() {
+        let mut keys = ScrollbackKeys::default();
+        assert_eq!(press(&mut keys, "g"), None);
+        assert_eq!(press(&mut keys, "g"), Some(ScrollbackAction::Top));
+        // A key in between starts over.
+        assert_eq!(press(&mut keys, "g"), None);
+        assert_eq!(press(&mut keys, "j"), Some(ScrollbackAction::Lines(1)));
+        assert_eq!(press(&mut keys, "g"), None);
+    }
+
+    #[test]
+    fn test_distances() {
+        assert_eq!(ScrollbackAction::Lines(-1).rows(20), Some(-1));
+        assert_eq!(ScrollbackAction::HalfPages(1).rows(21), Some(10));
+        assert_eq!(ScrollbackAction::HalfPages(-1).rows(1), Some(-1));
+        assert_eq!(ScrollbackAction::Pages(1).rows(20), Some(20));
+        assert_eq!(ScrollbackAction::Top.rows(20), None);
+    }
+}
@@ -3,12 +3,16 @@
 
 use std::sync::Arc;
 use std::task::{Context, Poll, Wake, Waker};
+use std::time::Instant;
 use winit::event_loop::EventLoopProxy;
 use winit::window::Window;
 
+use super::frame_limiter::FrameLimiter;
 use crate::config::Config;
+use crate::crash_report;
 use crate::editor::EditorState;
 use crate::event::UserEvent;
+use crate::layout::WindowLayout;
 use crate::renderer::Renderer;
 
 pub enum RenderState {
@@ -43,9 +47,15 @@ impl Wake for EventLoopWaker {
     }
 }
 
+/// Owns the renderer and decides when frames are drawn. Redraw requests
+/// between two frames collapse into one, and with a frame limit set, a
+/// frame that comes too early waits until it is due.
 pub struct RenderLoop {
     state: RenderState,
     event_proxy: Option<EventLoopProxy<UserEvent>>,
+    frame_limiter: Option<FrameLimiter>,
+    /// The window was asked for a redraw that hasn't arrived yet.
+    redraw_pending: bool,
 }
 
 impl Default for RenderLoop {
@@ -53,6 +63,8 @@ impl Default for RenderLoop {
         Self {
             state: RenderState::Uninitialized,
             event_proxy: None,
+            frame_limiter: None,
+            redraw_pending: false,
         }
     }
 }
@@ -66,6 +78,57 @@ impl RenderLoop {
         self.event_proxy = Some(proxy);
     }
 
+    /// Caps the frame rate at `max_fps`, or lifts the cap with `None`.
+    pub fn set_max_fps(&mut self, max_fps: Option<u32>) {
+        self.frame_limiter = max_fps.map(|fps| FrameLimiter::new(fps, Instant::now()));
+    }
+
+    /// Asks the window for a redraw, unless one is already on its way.
+    pub fn request_redraw(&mut self, window: &Window) {
+        if !self.redraw_pending {
+            self.redraw_pending = true;
+            window.request_redraw();
+        }
+    }
+
+    /// Notes that the requested redraw arrived, so the next request goes
+    /// through to the window again.
+    pub fn redraw_received(&mut self) {
+        self.redraw_pending = false;
+    }
+
+    /// Whether a frame may be drawn now. Waits out the last moments before
+    /// the frame is due; a frame further off returns `false` and is drawn
+    /// once `next_frame_at` wakes the event loop.
+    pub fn begin_frame(&mut self) -> bool {
+        let Some(limiter) = &mut self.frame_limiter else {
+            return true;
+        };
+        if limiter.should_defer(Instant::now()) {
+            return false;
+        }
+        limiter.wait();
+        limiter.frame_started(Instant::now());
+        true
+    }
+
+    /// When to wake the event loop for a frame `begin_frame` held back, if
+    /// there is one waiting (`dirty`). A frame that is already due is
+    /// requested from `window` right away.
+    pub fn next_frame_at(&mut self, dirty: bool, window: &Window) -> Option<Instant> {
+        if !dirty {
+            return None;
+        }
+        match &self.frame_limiter {
+            Some(limiter) if limiter.should_defer(Instant::now()) => Some(limiter.wake_at()),
+            Some(_) => {
+                self.request_redraw(window);
+                None
+            }
+            None => None,
+        }
+    }
+
     pub fn initialize(&mut self, window: Arc<Window>, config: Config) {
         self.state = RenderState::Initializing(Box::pin(Renderer::new(window, config)));
     }
@@ -93,6 +156,7 @@ impl RenderLoop {
                 match future.as_mut().poll(&mut cx) {
                     Poll::Ready(Ok(renderer)) => {
                         log::info!("GPU renderer initialized");
+                        crash_report::set_gpu(renderer.adapter_info());
                         RenderState::Ready(renderer)
                     }
                     Poll::Ready(Err(e)) => {
@@ -118,6 +182,12 @@ impl RenderLoop {
         }
     }
 
+    /// Drop the renderer (and any pending initialization) so the GPU surface
+    /// is released before the window it was created from.
+    pub fn shutdown(&mut self) {
+        self.state = RenderState::Uninitialized;
+    }
+
     pub fn renderer(&mut self) -> Option<&mut Renderer> {
         match &mut self.state {
             RenderState::Ready(renderer) => Some(renderer),
@@ -128,12 +198,11 @@ impl RenderLoop {
     pub fn render(
         &mut self,
         state: &EditorState,
-        x_offset: f32,
-        y_offset: f32,
+        layout: &WindowLayout,
         window: &Window,
     ) -> Result<(), ()> {
         if let RenderState::Ready(ref mut renderer) = self.state {
-            match renderer.render(state, x_offset, y_offset) {
+            match renderer.render(state, layout) {
                 Ok(()) => Ok(()),
                 Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
                     renderer.resize(window.inner_size());
@@ -153,3 +222,23 @@ impl RenderLoop {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_begin_frame_paces_at_max_fps() {
+        let mut render_loop = RenderLoop::new();
+        assert!(render_loop.begin_frame());
+        assert!(render_loop.begin_frame());
+
+        render_loop.set_max_fps(Some(10));
+        assert!(render_loop.begin_frame());
+        // The next frame is 100ms off.
+        assert!(!render_loop.begin_frame());
+
+        render_loop.set_max_fps(None);
+        assert!(render_loop.begin_frame());
+    }
+}
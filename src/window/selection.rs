@@ -0,0 +1,108 @@
+//! The X11/Wayland primary selection: text selected in Visual mode is
+//! offered to other programs, and a middle click pastes what they offer.
+//!
+//! Goes through `wl-copy`/`wl-paste` on Wayland and `xclip` on X11, the
+//! tools Neovim's own clipboard provider uses, rather than the provider:
+//! with `--server` that would set the selection of the machine Neovim runs
+//! on.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// How long a middle click waits for the selection's owner to hand it over.
+const PASTE_TIMEOUT: Duration = Duration::from_millis(250);
+
+#[derive(Default)]
+pub struct PrimarySelection {
+    /// The text last offered, so moving through a selection without
+    /// changing it doesn't start the tool again.
+    offered: Option<String>,
+}
+
+impl PrimarySelection {
+    /// Offers `text` as the primary selection.
+    pub fn set(&mut self, text: String) {
+        if text.is_empty() || self.offered.as_ref() == Some(&text) {
+            return;
+        }
+        let mut child = match copy_command()
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                log::warn!("Failed to set the primary selection: {}", e);
+                return;
+            }
+        };
+        let stdin = child.stdin.take();
+        let written = text.clone();
+        // Written and reaped in the background: the tool may not read a
+        // long selection all at once.
+        std::thread::spawn(move || {
+            if let Some(mut stdin) = stdin {
+                let _ = stdin.write_all(written.as_bytes());
+            }
+            let _ = child.wait();
+        });
+        self.offered = Some(text);
+    }
+
+    /// The primary selection, whichever program owns it. None if there is
+    /// none, or its owner doesn't answer within `PASTE_TIMEOUT`.
+    pub fn get(&self) -> Option<String> {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(paste_command().stderr(Stdio::null()).output());
+        });
+        match receiver.recv_timeout(PASTE_TIMEOUT) {
+            Ok(Ok(output)) if output.status.success() => String::from_utf8(output.stdout)
+                .ok()
+                .filter(|text| !text.is_empty()),
+            Ok(Ok(_)) => None,
+            Ok(Err(e)) => {
+                log::warn!("Failed to read the primary selection: {}", e);
+                None
+            }
+            Err(_) => {
+                log::warn!(
+                    "The primary selection didn't arrive within {:?}",
+                    PASTE_TIMEOUT
+                );
+                None
+            }
+        }
+    }
+}
+
+fn is_wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+fn copy_command() -> Command {
+    if is_wayland() {
+        let mut command = Command::new("wl-copy");
+        command.arg("--primary");
+        command
+    } else {
+        let mut command = Command::new("xclip");
+        command.args(["-in", "-selection", "primary"]);
+        command
+    }
+}
+
+fn paste_command() -> Command {
+    if is_wayland() {
+        let mut command = Command::new("wl-paste");
+        command.args(["--primary", "--no-newline"]);
+        command
+    } else {
+        let mut command = Command::new("xclip");
+        command.args(["-out", "-selection", "primary"]);
+        command
+    }
+}
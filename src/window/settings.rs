@@ -1,11 +1,17 @@
-use crate::constants::{DEFAULT_COLS, DEFAULT_ROWS, PADDING, PADDING_TOP};
-use crate::input::CellMetrics;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+
+use crate::constants::{DEFAULT_COLS, DEFAULT_ROWS};
+use crate::layout::WindowLayout;
 
 #[derive(Debug, Clone)]
 pub struct WindowSettings {
     pub cols: u64,
     pub rows: u64,
-    pub cell_metrics: CellMetrics,
+    pub layout: WindowLayout,
 }
 
 impl Default for WindowSettings {
@@ -13,11 +19,7 @@ impl Default for WindowSettings {
         Self {
             cols: DEFAULT_COLS,
             rows: DEFAULT_ROWS,
-            cell_metrics: CellMetrics {
-                padding_x: PADDING as f64,
-                padding_y: PADDING_TOP as f64,
-                ..Default::default()
-            },
+            layout: WindowLayout::default(),
         }
     }
 }
@@ -26,18 +28,140 @@ impl WindowSettings {
     pub fn new() -> Self {
         Self::default()
     }
+}
+
+/// Window geometry and font size saved on exit and restored on the next
+/// start, in `window.toml` under the local data directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    /// Inner size in logical pixels, so it survives a change of scale.
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    /// Outer position in physical pixels, where the platform reports one.
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    #[serde(default)]
+    pub maximized: bool,
+    /// Grid size, sent to Neovim before it attaches.
+    pub cols: Option<u64>,
+    pub rows: Option<u64>,
+    /// Font size after zooming.
+    pub font_size: Option<f32>,
+}
+
+impl WindowState {
+    /// Loads the saved state. A missing or unreadable file restores nothing.
+    pub fn load() -> Self {
+        window_state_path()
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        toml::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("Failed to parse {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), WindowStateError> {
+        let path = window_state_path().ok_or(WindowStateError::NoDataDir)?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), WindowStateError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn size(&self) -> Option<LogicalSize<f64>> {
+        Some(LogicalSize::new(self.width?, self.height?))
+    }
+
+    /// The saved position, if it still lies on one of `monitors` (given as
+    /// position and size). Monitors change between runs, and a window
+    /// restored off screen can't be reached.
+    pub fn position(
+        &self,
+        monitors: &[(PhysicalPosition<i32>, PhysicalSize<u32>)],
+    ) -> Option<PhysicalPosition<i32>> {
+        let (x, y) = (self.x?, self.y?);
+        monitors
+            .iter()
+            .any(|(origin, size)| {
+                (origin.x..origin.x + size.width as i32).contains(&x)
+                    && (origin.y..origin.y + size.height as i32).contains(&y)
+            })
+            .then_some(PhysicalPosition::new(x, y))
+    }
+}
+
+/// Location: `window.toml` in `~/.local/share/gui-nvim` or the platform's
+/// equivalent (`~/Library/Application Support/gui-nvim` on macOS).
+fn window_state_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("gui-nvim").join("window.toml"))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WindowStateError {
+    #[error("Could not determine data directory")]
+    NoDataDir,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_state_round_trip() {
+        let dir = std::env::temp_dir().join(format!("gui-nvim-window-{}", std::process::id()));
+        let path = dir.join("window.toml");
+        let state = WindowState {
+            width: Some(900.0),
+            height: Some(600.5),
+            x: Some(-20),
+            y: Some(40),
+            maximized: true,
+            cols: Some(110),
+            rows: Some(33),
+            font_size: Some(16.0),
+        };
+        state.save_to(&path).unwrap();
 
-    pub fn update_padding(&mut self, scale_factor: f64) {
-        self.cell_metrics.padding_x = (PADDING as f64 * scale_factor).round();
-        self.cell_metrics.padding_y = (PADDING_TOP as f64 * scale_factor).round();
+        assert_eq!(WindowState::load_from(&path), state);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(WindowState::load_from(&path), WindowState::default());
     }
 
-    pub fn calculate_grid_size(&self, width: u32, height: u32) -> (u64, u64) {
-        let cols = (width as f64 - 2.0 * self.cell_metrics.padding_x).max(0.0)
-            / self.cell_metrics.cell_width;
-        let rows = (height as f64 - (self.cell_metrics.padding_y + self.cell_metrics.padding_x))
-            .max(0.0)
-            / self.cell_metrics.cell_height;
-        (cols.max(1.0) as u64, rows.max(1.0) as u64)
+    #[test]
+    fn test_position_must_be_on_a_monitor() {
+        let monitors = [
+            (PhysicalPosition::new(0, 0), PhysicalSize::new(1920, 1080)),
+            (
+                PhysicalPosition::new(-1280, 0),
+                PhysicalSize::new(1280, 1024),
+            ),
+        ];
+        let at = |x, y| WindowState {
+            x: Some(x),
+            y: Some(y),
+            ..Default::default()
+        };
+        assert_eq!(
+            at(-20, 40).position(&monitors),
+            Some(PhysicalPosition::new(-20, 40))
+        );
+        assert_eq!(at(2000, 40).position(&monitors), None);
+        assert_eq!(WindowState::default().position(&monitors), None);
     }
 }
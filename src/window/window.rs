@@ -1,79 +1,297 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
-use winit::dpi::LogicalSize;
-use winit::event::{ElementState, WindowEvent};
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, Ime, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoopProxy};
-use winit::window::{Window, WindowAttributes, WindowId};
-
-use crate::bridge::events::RedrawEvent;
-use crate::bridge::AppBridge;
-use crate::config::{Config, VsyncMode};
-use crate::constants::{DEFAULT_COLS, DEFAULT_ROWS, PADDING, PADDING_TOP};
-use crate::editor::EditorState;
-use crate::event::{GUIEvent, NeovimEvent, UserEvent};
-use crate::input::InputHandler;
+use winit::monitor::MonitorHandle;
+use winit::window::{CursorIcon, Fullscreen, Theme, Window, WindowAttributes, WindowId};
+
+use crate::bridge::{AppBridge, RequestId, ServerAddress};
+use crate::cli::WindowOptions;
+use crate::config::{Config, FullscreenMode, OpenFilesIn, VsyncMode};
+use crate::constants::{
+    DEFAULT_COLS, DEFAULT_FONT_SIZE, DEFAULT_ROWS, FONT_ZOOM_STEP, MAX_FONT_SIZE, MAX_WINDOW_ZOOM,
+    MIN_FONT_SIZE, MIN_WINDOW_ZOOM,
+};
+use crate::crash_report;
+use crate::daemon::{Daemon, LaunchRequest};
+use crate::editor::{
+    CrashChoice, Dashboard, EditorCore, EditorState, ReportChoice, ReportPrompt, Scrollback,
+    SpecialColors, SwapChoice, SwapPrompt,
+};
+use crate::event::{GUIEvent, MenuAction, NeovimEvent, Reply, UserEvent};
+use crate::export::{self, ExportFormat, ExportMetrics};
+use crate::input::{ime_commit_to_neovim, link_at, InputHandler, Keybinds, MenuKey, ZoomedArea};
+use crate::logging;
+use crate::recent::{has_file_args, RecentFiles};
+use crate::renderer::font::FontConfig;
+use crate::renderer::RenderMode;
+use crate::snapshot;
+use crate::stats::StatsReport;
+use crate::window::appearance;
+use crate::window::dialogs::{self, FileChoice, FileDialog};
+use crate::window::drop::{open_commands, save_as_command};
+use crate::window::frame_limiter::DEFAULT_MAX_FPS;
+use crate::window::gui_set::{self, GuiSetting};
+use crate::window::link::open_url;
+use crate::window::overlay::{ScrollbackAction, ScrollbackKeys};
 use crate::window::render_loop::RenderLoop;
-use crate::window::settings::WindowSettings;
+use crate::window::selection::PrimarySelection;
+use crate::window::settings::{WindowSettings, WindowState};
 
+#[cfg(target_os = "macos")]
+use crate::input::TabShortcut;
 #[cfg(target_os = "macos")]
 use crate::window::displaylink::DisplayLink;
 #[cfg(target_os = "macos")]
-use winit::platform::macos::WindowAttributesExtMacOS;
+use crate::window::document::set_represented_file;
+#[cfg(target_os = "macos")]
+use crate::window::drag::{start_drag, SelectionRegion};
+#[cfg(not(target_os = "macos"))]
+use crate::window::icon::window_icon;
+#[cfg(target_os = "macos")]
+use crate::window::menu::MenuBar;
+#[cfg(target_os = "macos")]
+use winit::platform::macos::{WindowAttributesExtMacOS, WindowExtMacOS};
+#[cfg(target_os = "windows")]
+use winit::platform::windows::{BackdropType, WindowAttributesExtWindows};
 
 pub struct GuiApp {
     window: Option<Arc<Window>>,
     event_proxy: EventLoopProxy<UserEvent>,
     config: Config,
     args: Vec<String>,
+    /// `--server`: attach to this Neovim instead of spawning one.
+    server: Option<ServerAddress>,
     app_bridge: Option<AppBridge>,
     close_requested: bool,
+    /// Closing the window asked Neovim to `:confirm qa` and it hasn't
+    /// answered yet.
+    quit_pending: bool,
+    /// What gui.nvim exits with: Neovim's exit code, once it has one.
+    exit_code: i32,
     input_handler: InputHandler,
-    editor_state: EditorState,
+    /// Applies Neovim's redraws off the event loop.
+    editor: EditorCore,
     render_loop: RenderLoop,
     settings: WindowSettings,
     current_scale_factor: f64,
+    started_at: Instant,
+    /// Last cursor cell reported to the IME, to avoid redundant updates.
+    ime_cursor_cell: Option<(usize, usize)>,
+    /// Files for the startup dashboard; only kept with `[ui] recent_files`.
+    recent_files: RecentFiles,
+    /// Files dropped on the window since the last `about_to_wait`; winit
+    /// delivers them one event each.
+    dropped_files: Vec<PathBuf>,
+    /// Geometry saved by the last session, applied to the new window.
+    restored: WindowState,
+    /// Command-line flags, which win over `restored`.
+    options: WindowOptions,
+    /// Geometry from before going fullscreen, saved instead of the
+    /// fullscreen one.
+    windowed: Option<WindowState>,
+    /// `--daemon`: Neovim starts right away, the window on `Launch`.
+    daemon: Option<Daemon>,
+    /// The title Neovim last set, before the read-only marker. Empty
+    /// until it sets one.
+    title: String,
+    /// The icon text Neovim last set, shown while there's no title.
+    icon: String,
+    /// The current buffer is read-only or not modifiable.
+    readonly: bool,
+    /// The current buffer has changes not written yet.
+    modified: bool,
+    /// The file the current buffer edits, for the macOS titlebar icon.
+    document: Option<PathBuf>,
+    /// Where the answer to the open swap prompt goes.
+    swap_reply: Option<Reply<String>>,
+    /// The `:GuiMessages` request waiting on the message history.
+    messages_request: Option<RequestId>,
+    scrollback_keys: ScrollbackKeys,
+    /// How frames are presented: `[performance] vsync`, until
+    /// `:GuiSet vsync` changes it.
+    vsync: VsyncMode,
+    /// `:GuiSet renderer`, kept here for a renderer still starting.
+    render_mode: RenderMode,
+    /// The grid sizes asked of Neovim for zoomed windows, by grid.
+    zoom_requests: HashMap<u64, (usize, usize)>,
+    /// The system's light or dark appearance, as last seen.
+    system_theme: Option<Theme>,
+    /// Neovim has sent its colors, so the system's defaults are done with.
+    neovim_colors: bool,
+    /// What Visual mode last offered as the primary selection.
+    primary_selection: PrimarySelection,
+    /// The text selected in Visual mode and where it is, for dragging it
+    /// out of the window.
+    #[cfg(target_os = "macos")]
+    selection: Option<(String, SelectionRegion)>,
     #[cfg(target_os = "macos")]
     display_link: Option<DisplayLink>,
+    #[cfg(target_os = "macos")]
+    menu_bar: Option<MenuBar>,
 }
 
 impl GuiApp {
-    pub fn new(event_proxy: EventLoopProxy<UserEvent>, config: Config, args: Vec<String>) -> Self {
+    pub fn new(
+        event_proxy: EventLoopProxy<UserEvent>,
+        mut config: Config,
+        args: Vec<String>,
+        server: Option<ServerAddress>,
+        options: WindowOptions,
+    ) -> Self {
+        let restored = WindowState::load();
+        if let Some(size) = restored.font_size {
+            config.font.size = Some(size);
+        }
+        if let Some(font) = &options.font {
+            if let Some(family) = &font.family {
+                config.font.family = Some(family.clone());
+            }
+            if let Some(size) = font.size {
+                config.font.size = Some(size);
+            }
+        }
         let mut render_loop = RenderLoop::new();
         render_loop.set_event_proxy(event_proxy.clone());
+        let mut editor_state = EditorState::new(DEFAULT_COLS as usize, DEFAULT_ROWS as usize);
+        editor_state.tabline.visibility = config.ui.tabline;
+        editor_state.show_invisible = config.ui.show_invisible;
+        editor_state.cursor_blink = config.cursor.blink;
+        editor_state.report_prompt = crash_report::take_pending().map(ReportPrompt::new);
+        editor_state
+            .highlights
+            .set_special_colors(SpecialColors::from_config(&config.special_colors));
+        let mut input_handler = InputHandler::new();
+        input_handler.set_keybinds(Keybinds::from_config(&config.keybinds));
+        input_handler.set_key_encoding(config.keyboard.key_encoding);
+        input_handler.set_option_as_meta(config.keyboard.macos_option_is_meta);
+        input_handler.set_primary_selection(config.ui.primary_selection());
+        let mut settings = WindowSettings::new();
+        settings.layout.mirrored = config.ui.mirror;
+        settings.layout.set_padding(config.window.padding, 1.0);
+        let recent_files = if config.ui.recent_files {
+            RecentFiles::load()
+        } else {
+            RecentFiles::default()
+        };
         Self {
             window: None,
             event_proxy,
+            vsync: config.performance.vsync,
+            render_mode: RenderMode::default(),
+            zoom_requests: HashMap::new(),
             config,
             args,
+            server,
             app_bridge: None,
             close_requested: false,
-            input_handler: InputHandler::new(),
-            editor_state: EditorState::new(DEFAULT_COLS as usize, DEFAULT_ROWS as usize),
+            quit_pending: false,
+            exit_code: 0,
+            input_handler,
+            editor: EditorCore::new(editor_state),
             render_loop,
-            settings: WindowSettings::new(),
+            settings,
             current_scale_factor: 1.0,
+            started_at: Instant::now(),
+            ime_cursor_cell: None,
+            recent_files,
+            dropped_files: Vec::new(),
+            restored,
+            options,
+            windowed: None,
+            daemon: None,
+            title: String::new(),
+            icon: String::new(),
+            readonly: false,
+            modified: false,
+            document: None,
+            swap_reply: None,
+            messages_request: None,
+            scrollback_keys: ScrollbackKeys::default(),
+            system_theme: None,
+            neovim_colors: false,
+            primary_selection: PrimarySelection::default(),
+            #[cfg(target_os = "macos")]
+            selection: None,
             #[cfg(target_os = "macos")]
             display_link: None,
+            #[cfg(target_os = "macos")]
+            menu_bar: None,
         }
     }
 
+    /// The code to exit with, Neovim's, after the event loop has ended.
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+
+    /// Waits for a launch from `daemon` before opening the window.
+    pub fn set_daemon(&mut self, daemon: Daemon) {
+        self.daemon = Some(daemon);
+    }
+
     fn update_padding(&mut self, scale_factor: f64) {
-        self.settings.update_padding(scale_factor);
+        self.settings.layout.set_scale_factor(scale_factor);
+        self.update_tabline_inset();
     }
 
     fn create_window(&mut self, event_loop: &ActiveEventLoop) {
-        let (cell_width, cell_height) = (
-            self.settings.cell_metrics.cell_width,
-            self.settings.cell_metrics.cell_height,
-        );
-        let width = DEFAULT_COLS as u32 * cell_width as u32 + 2 * PADDING;
-        let height = DEFAULT_ROWS as u32 * cell_height as u32 + PADDING + PADDING_TOP;
+        let (width, height) = self.settings.layout.window_size(DEFAULT_COLS, DEFAULT_ROWS);
+        let size = match self.options.geometry {
+            Some(geometry) => {
+                let (width, height) = self
+                    .settings
+                    .layout
+                    .window_size(geometry.cols, geometry.rows);
+                LogicalSize::new(width, height)
+            }
+            None => self
+                .restored
+                .size()
+                .unwrap_or(LogicalSize::new(width, height)),
+        };
 
         let window_attrs = WindowAttributes::default()
             .with_title("gui.nvim")
-            .with_inner_size(LogicalSize::new(width, height))
-            .with_min_inner_size(LogicalSize::new(200, 100));
+            .with_inner_size(size)
+            .with_min_inner_size(LogicalSize::new(200, 100))
+            .with_maximized(self.restored.maximized || self.options.maximized)
+            .with_fullscreen(self.options.fullscreen.then(|| {
+                fullscreen_on(self.config.window.fullscreen, event_loop.primary_monitor())
+            }));
+        if self.options.fullscreen {
+            self.windowed = Some(self.restored.clone());
+        }
+
+        let monitors: Vec<_> = event_loop
+            .available_monitors()
+            .map(|m| (m.position(), m.size()))
+            .collect();
+        let window_attrs = match self.restored.position(&monitors) {
+            Some(position) => window_attrs.with_position(position),
+            None => window_attrs,
+        };
+
+        // The renderer clears to a translucent background when opacity < 1.
+        // winit blurs through the window server on macOS; Windows gets the
+        // DWM acrylic backdrop instead.
+        let transparent = self.config.window.is_transparent();
+        let blur = transparent && self.config.window.blur;
+        let window_attrs = window_attrs.with_transparent(transparent).with_blur(blur);
+
+        #[cfg(target_os = "windows")]
+        let window_attrs = if blur {
+            window_attrs.with_system_backdrop(BackdropType::TransientWindow)
+        } else {
+            window_attrs
+        };
+
+        #[cfg(not(target_os = "macos"))]
+        let window_attrs = window_attrs.with_window_icon(window_icon(&self.config.window));
 
         #[cfg(target_os = "macos")]
         let window_attrs = window_attrs
@@ -81,21 +299,31 @@ impl GuiApp {
             .with_fullsize_content_view(true)
             .with_title_hidden(true);
 
+        // Windows sharing a tabbing identifier can be merged into one frame.
+        // Tabs are labelled with the window title, which follows set_title.
+        #[cfg(target_os = "macos")]
+        let window_attrs = if self.config.macos.native_tabs {
+            window_attrs.with_tabbing_identifier("gui.nvim")
+        } else {
+            window_attrs
+        };
+
         match event_loop.create_window(window_attrs) {
             Ok(window) => {
                 log::info!("Window created: {:?}", window.id());
                 self.current_scale_factor = window.scale_factor();
                 self.update_padding(self.current_scale_factor);
+                window.set_ime_allowed(true);
 
-                // Initialize display link for frame synchronization (macOS 14+)
                 #[cfg(target_os = "macos")]
-                if self.config.performance.vsync == VsyncMode::DisplayLink {
-                    self.display_link = DisplayLink::new(&window, self.event_proxy.clone());
-                    if self.display_link.is_some() {
-                        log::info!("CADisplayLink initialized for frame synchronization");
-                    } else {
-                        log::warn!("DisplayLink mode requested but CADisplayLink unavailable");
-                    }
+                self.update_display_link(&window);
+                self.update_max_fps(&window);
+
+                // winit installs its default menu when the app finishes
+                // launching, which has happened by the time a window exists.
+                #[cfg(target_os = "macos")]
+                {
+                    self.menu_bar = MenuBar::install(self.event_proxy.clone());
                 }
 
                 let window = Arc::new(window);
@@ -104,9 +332,7 @@ impl GuiApp {
                 self.render_loop
                     .initialize(window.clone(), self.config.clone());
 
-                let bridge = AppBridge::new(self.event_proxy.clone());
-                bridge.spawn_neovim(self.args.clone());
-                self.app_bridge = Some(bridge);
+                self.start_neovim();
 
                 let _ = self
                     .event_proxy
@@ -114,19 +340,276 @@ impl GuiApp {
             }
             Err(e) => {
                 log::error!("Failed to create window: {}", e);
-                self.close_requested = true;
+                self.shutdown(event_loop);
+            }
+        }
+    }
+
+    /// Spawns or attaches to Neovim, unless a daemon already has.
+    fn start_neovim(&mut self) {
+        if self.app_bridge.is_some() {
+            return;
+        }
+        let bridge = AppBridge::new(self.editor.spawn(self.event_proxy.clone()), self.config.ui);
+        // Attach at the grid size the window opens with, so Neovim doesn't
+        // lay out for the default size first.
+        let grid_size = match self.options.geometry {
+            Some(geometry) => Some((geometry.cols, geometry.rows)),
+            None => self.restored.cols.zip(self.restored.rows),
+        };
+        if let Some((cols, rows)) = grid_size {
+            self.settings.cols = cols;
+            self.settings.rows = rows;
+            bridge.resize(cols, rows);
+        }
+        match &self.server {
+            Some(address) => bridge.connect(address.clone()),
+            None => {
+                let hint = self.system_theme.map(appearance::background_args);
+                let args = hint
+                    .into_iter()
+                    .flatten()
+                    .chain(self.args.clone())
+                    .collect();
+                bridge.spawn_neovim(args, self.options.cwd.clone());
+            }
+        }
+        self.app_bridge = Some(bridge);
+
+        if self.config.ui.recent_files && self.server.is_none() && !has_file_args(&self.args) {
+            self.editor.state().dashboard = Dashboard::new(self.recent_files.existing());
+        }
+    }
+
+    /// Opens the window a daemon was waiting to, in the directory and on
+    /// the files of the `gui.nvim` that handed them over.
+    fn launch(&mut self, event_loop: &ActiveEventLoop, request: &LaunchRequest) {
+        log::info!("Launched from the daemon in {}", request.cwd.display());
+        self.create_window(event_loop);
+        let Some(ref bridge) = self.app_bridge else {
+            return;
+        };
+        for command in open_commands(&request.paths(), OpenFilesIn::Window) {
+            bridge.command(command);
+        }
+        if !request.files.is_empty() {
+            self.editor.state().dashboard = None;
+        }
+    }
+
+    /// Opens files from `gui.nvim --remote` as dropped ones are, without
+    /// moving to the launching shell's directory, and raises the window.
+    fn open_remote(&mut self, request: &LaunchRequest) {
+        log::info!("Opening {} file(s) from --remote", request.files.len());
+        if let Some(ref bridge) = self.app_bridge {
+            for command in open_commands(&request.file_paths(), self.config.ui.open_files_in) {
+                bridge.command(command);
             }
         }
+        if !request.files.is_empty() {
+            self.editor.state().dashboard = None;
+        }
+        if let Some(ref window) = self.window {
+            window.set_minimized(false);
+            window.focus_window();
+        }
+    }
+
+    /// Remembers the window's geometry and font size for the next start.
+    fn save_window_state(&self) {
+        let Some(ref window) = self.window else {
+            return;
+        };
+        let state = match &self.windowed {
+            Some(windowed) if window.fullscreen().is_some() => WindowState {
+                font_size: self.config.font.size,
+                ..windowed.clone()
+            },
+            _ => self.window_state(window),
+        };
+        if let Err(e) = state.save() {
+            log::warn!("Failed to save window state: {}", e);
+        }
+    }
+
+    fn window_state(&self, window: &Window) -> WindowState {
+        let size = window.inner_size().to_logical::<f64>(window.scale_factor());
+        let position = window.outer_position().ok();
+        WindowState {
+            width: Some(size.width),
+            height: Some(size.height),
+            x: position.map(|p| p.x),
+            y: position.map(|p| p.y),
+            maximized: window.is_maximized(),
+            cols: Some(self.settings.cols),
+            rows: Some(self.settings.rows),
+            font_size: self.config.font.size,
+        }
+    }
+
+    /// Vsync paces frames at the refresh rate by itself; without it they
+    /// are capped there unless `max_fps` says otherwise.
+    fn update_max_fps(&mut self, window: &Window) {
+        let max_fps = self.config.performance.max_fps.or_else(|| {
+            (self.vsync == VsyncMode::Disabled).then(|| {
+                window
+                    .current_monitor()
+                    .and_then(|monitor| monitor.refresh_rate_millihertz())
+                    .map_or(DEFAULT_MAX_FPS, |mhz| mhz.div_ceil(1000))
+            })
+        });
+        if let Some(max_fps) = max_fps {
+            log::info!("Limiting to {} fps", max_fps);
+        }
+        self.render_loop.set_max_fps(max_fps);
+    }
+
+    /// `:GuiSet`: applies the settings in `args`, or with none, says what
+    /// they are.
+    fn gui_set(&mut self, args: &[&str]) {
+        let settings = match gui_set::parse(args) {
+            Ok(settings) => settings,
+            Err(e) => {
+                if let Some(ref bridge) = self.app_bridge {
+                    bridge.echo(vec![e.to_string()]);
+                }
+                return;
+            }
+        };
+        for setting in &settings {
+            match *setting {
+                GuiSetting::Renderer(mode) => {
+                    self.render_mode = mode;
+                    if let Some(renderer) = self.render_loop.renderer() {
+                        renderer.set_mode(mode);
+                    }
+                    log::info!("Renderer: {}", mode.name());
+                }
+                GuiSetting::Vsync(on) => self.set_vsync(on),
+            }
+        }
+        if settings.is_empty() {
+            let vsync = self.vsync != VsyncMode::Disabled;
+            if let Some(ref bridge) = self.app_bridge {
+                bridge.echo(vec![gui_set::describe(self.render_mode, vsync)]);
+            }
+        }
+        self.editor.state().mark_dirty();
+        self.request_redraw();
+    }
+
+    /// Turns vsync on or off. On goes back to the configured mode, or
+    /// plain vsync if that is off.
+    fn set_vsync(&mut self, on: bool) {
+        let vsync = match (on, self.config.performance.vsync) {
+            (false, _) => VsyncMode::Disabled,
+            (true, VsyncMode::Disabled) => VsyncMode::Enabled,
+            (true, configured) => configured,
+        };
+        if vsync == self.vsync {
+            return;
+        }
+        self.vsync = vsync;
+        if let Some(renderer) = self.render_loop.renderer() {
+            renderer.set_vsync(vsync);
+        }
+        if let Some(window) = self.window.clone() {
+            #[cfg(target_os = "macos")]
+            self.update_display_link(&window);
+            self.update_max_fps(&window);
+        }
+    }
+
+    /// Runs the display link frames wait for when vsync is `"displaylink"`
+    /// (macOS 14+), and stops it otherwise.
+    #[cfg(target_os = "macos")]
+    fn update_display_link(&mut self, window: &Window) {
+        self.display_link = None;
+        if self.vsync != VsyncMode::DisplayLink {
+            return;
+        }
+        self.display_link = DisplayLink::new(window, self.event_proxy.clone());
+        if self.display_link.is_some() {
+            log::info!("CADisplayLink initialized for frame synchronization");
+        } else {
+            log::warn!("DisplayLink mode requested but CADisplayLink unavailable");
+        }
+    }
+
+    /// Makes the window fullscreen, as `[window] fullscreen` says, or
+    /// windowed again; `None` toggles. The grid follows with the `Resized`
+    /// this brings.
+    fn set_fullscreen(&mut self, on: Option<bool>) {
+        let Some(window) = self.window.clone() else {
+            return;
+        };
+        let fullscreen = window.fullscreen().is_some();
+        let on = on.unwrap_or(!fullscreen);
+        if on == fullscreen {
+            return;
+        }
+        if on {
+            self.windowed = Some(self.window_state(&window));
+            let mode = fullscreen_on(self.config.window.fullscreen, window.current_monitor());
+            window.set_fullscreen(Some(mode));
+        } else {
+            self.windowed = None;
+            window.set_fullscreen(None);
+        }
+    }
+
+    /// Closes the window, letting Neovim ask about unsaved changes first;
+    /// the window closes once it exits. A server is only detached from,
+    /// and a second request while the prompt is up forces the issue.
+    fn request_close(&mut self, event_loop: &ActiveEventLoop) {
+        match &self.app_bridge {
+            Some(bridge) if self.server.is_none() && !self.quit_pending => {
+                self.quit_pending = true;
+                bridge.confirm_quit();
+            }
+            _ => self.shutdown(event_loop),
+        }
+    }
+
+    /// Tear the app down in dependency order: stop vblank callbacks and the
+    /// renderer so nothing schedules work against a closing window, let the
+    /// bridge flush queued input, quit Neovim and reap the child, and only
+    /// then leave the event loop. Safe to call more than once.
+    fn shutdown(&mut self, event_loop: &ActiveEventLoop) {
+        if !self.close_requested {
+            self.save_window_state();
+        }
+        self.close_requested = true;
+
+        #[cfg(target_os = "macos")]
+        {
+            self.display_link = None;
+        }
+        self.render_loop.shutdown();
+
+        if let Some(bridge) = self.app_bridge.take() {
+            if !bridge.shutdown() {
+                log::warn!("Exiting without a clean Neovim shutdown");
+            }
+        }
+        // Only now that Neovim has written its files may a waiting `git
+        // commit` go on.
+        if let Some(ref daemon) = self.daemon {
+            daemon.finish(self.exit_code);
+        }
+
+        event_loop.set_control_flow(ControlFlow::Wait);
+        event_loop.exit();
     }
 
     fn update_metrics_and_resize(&mut self, cw: f32, ch: f32) {
-        self.settings.cell_metrics.cell_width = cw as f64;
-        self.settings.cell_metrics.cell_height = ch as f64;
+        self.settings.layout.set_cell_size(cw as f64, ch as f64);
+        self.update_tabline_inset();
 
         if let Some(ref bridge) = self.app_bridge {
             if let Some(ref window) = self.window {
                 let size = window.inner_size();
-                let (cols, rows) = self.settings.calculate_grid_size(size.width, size.height);
+                let (cols, rows) = self.settings.layout.grid_size(size.width, size.height);
                 if cols != self.settings.cols || rows != self.settings.rows {
                     self.settings.cols = cols;
                     self.settings.rows = rows;
@@ -136,13 +619,41 @@ impl GuiApp {
         }
     }
 
+    /// Keeps the tab bar clear of the window buttons, which sit in the
+    /// transparent titlebar on macOS.
+    fn update_tabline_inset(&mut self) {
+        self.editor.state().tabline.inset_cols = self.settings.layout.titlebar_inset_cols();
+    }
+
     fn poll_renderer(&mut self) {
         if let Some(ref window) = self.window {
             use std::task::Poll;
+            let starting = self.render_loop.renderer().is_none();
             if let Poll::Ready(Ok(renderer)) = self.render_loop.poll(window) {
+                // Colors set while it started, by Neovim or for the system
+                // theme, and `:GuiSet` settings.
+                if starting {
+                    renderer.set_mode(self.render_mode);
+                    if self.vsync != self.config.performance.vsync {
+                        renderer.set_vsync(self.vsync);
+                    }
+                    let defaults = self.editor.state().highlights.defaults.clone();
+                    renderer.update_default_colors(
+                        defaults.foreground.0 >> 8,
+                        defaults.background.0 >> 8,
+                    );
+                }
+                // The window moved to a monitor of another scale while the
+                // renderer was starting, and `update_layout` had nothing to
+                // update yet.
+                if renderer.scale_factor() != self.current_scale_factor {
+                    let scale_factor = self.current_scale_factor;
+                    self.update_layout(scale_factor);
+                    return;
+                }
                 let (cw, ch) = renderer.cell_size();
-                if self.settings.cell_metrics.cell_width != cw as f64
-                    || self.settings.cell_metrics.cell_height != ch as f64
+                if self.settings.layout.cell_width != cw as f64
+                    || self.settings.layout.cell_height != ch as f64
                 {
                     self.update_metrics_and_resize(cw, ch);
                 }
@@ -157,16 +668,160 @@ impl GuiApp {
             } else {
                 let (cw, ch) = renderer.cell_size();
                 self.update_metrics_and_resize(cw, ch);
-                self.editor_state.mark_dirty();
+                self.editor.state().mark_dirty();
+
+                self.request_redraw();
+            }
+        }
+    }
+
+    /// Changes the font size by `delta` points, showing the new size for a
+    /// moment.
+    fn zoom(&mut self, delta: f32) {
+        let size = self.config.font.size.unwrap_or(DEFAULT_FONT_SIZE);
+        let size = (size + delta).clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+        self.editor.state().show_zoom(size);
+        self.request_redraw();
+        if self.config.font.size == Some(size) {
+            return;
+        }
+        self.config.font.size = Some(size);
+        let scale_factor = self.current_scale_factor;
+        self.update_layout(scale_factor);
+    }
+
+    /// With `[ui] smooth_scroll`, shifts the grid by the scroll not yet
+    /// sent.
+    fn update_smooth_scroll(&mut self) {
+        if self.config.ui.smooth_scroll {
+            let cell_height = self.settings.layout.cell_height;
+            let offset = self.input_handler.scroll_offset(cell_height) as f32;
+            self.set_scroll_offset(offset);
+        }
+    }
+
+    /// Opens `paths` in Neovim as `[ui] open_files_in` says.
+    fn open_files(&mut self, paths: &[PathBuf]) {
+        let Some(ref bridge) = self.app_bridge else {
+            return;
+        };
+        for command in open_commands(paths, self.config.ui.open_files_in) {
+            bridge.command(command);
+        }
+        let mut state = self.editor.state();
+        if state.dashboard.take().is_some() {
+            state.mark_dirty();
+        }
+    }
+
+    /// Shows a file picker next to the current file. The choice arrives
+    /// as `GUIEvent::FileChosen`.
+    fn show_file_dialog(&mut self, dialog: FileDialog) {
+        let Some(ref window) = self.window else {
+            return;
+        };
+        dialogs::show(
+            dialog,
+            window,
+            self.document.as_deref(),
+            self.event_proxy.clone(),
+        );
+    }
+
+    fn file_chosen(&mut self, dialog: FileDialog, choice: FileChoice) {
+        let paths = match choice {
+            Ok(paths) => paths,
+            Err(message) => {
+                log::error!("{}", message);
+                if let Some(ref bridge) = self.app_bridge {
+                    bridge.echo(vec![message]);
+                }
+                return;
+            }
+        };
+        match dialog {
+            FileDialog::Open => self.open_files(&paths),
+            FileDialog::SaveAs => {
+                if let (Some(path), Some(bridge)) = (paths.first(), &self.app_bridge) {
+                    bridge.command(save_as_command(path));
+                }
+            }
+        }
+    }
+
+    fn set_scroll_offset(&mut self, offset: f32) {
+        let mut state = self.editor.state();
+        if state.scroll_offset != offset {
+            state.scroll_offset = offset;
+            state.mark_dirty();
+            drop(state);
+            self.request_redraw();
+        }
+    }
+
+    /// Underlines the link under the mouse pointer and shows a pointing
+    /// hand over it.
+    fn update_hovered_link(&mut self) {
+        let mut state = self.editor.state();
+        let link = self
+            .input_handler
+            .pointer_cell(&self.settings.layout)
+            .and_then(|cell| link_at(&state, cell))
+            .map(str::to_owned);
+        if link == state.hovered_link {
+            return;
+        }
+        if let Some(ref window) = self.window {
+            window.set_cursor(if link.is_some() {
+                CursorIcon::Pointer
+            } else {
+                CursorIcon::Default
+            });
+        }
+        state.hovered_link = link;
+        state.mark_dirty();
+        drop(state);
+        self.request_redraw();
+    }
+
+    fn set_file_hover(&mut self, hover: bool) {
+        let mut state = self.editor.state();
+        if state.file_hover != hover {
+            state.file_hover = hover;
+            state.mark_dirty();
+            drop(state);
+            self.request_redraw();
+        }
+    }
 
-                if let Some(ref window) = self.window {
-                    window.request_redraw();
+    fn handle_menu_action(&mut self, event_loop: &ActiveEventLoop, action: MenuAction) {
+        match action {
+            MenuAction::NewWindow => {
+                let spawned =
+                    std::env::current_exe().and_then(|exe| std::process::Command::new(exe).spawn());
+                if let Err(e) = spawned {
+                    log::error!("Failed to open a new window: {}", e);
+                }
+            }
+            MenuAction::Open => self.show_file_dialog(FileDialog::Open),
+            MenuAction::SaveAs => self.show_file_dialog(FileDialog::SaveAs),
+            MenuAction::Quit => self.request_close(event_loop),
+            MenuAction::ZoomIn => self.zoom(FONT_ZOOM_STEP),
+            MenuAction::ZoomOut => self.zoom(-FONT_ZOOM_STEP),
+            MenuAction::Cut | MenuAction::Copy | MenuAction::Paste | MenuAction::SelectAll => {
+                let keys = action.keys(self.editor.state().mode_name());
+                if let (Some(keys), Some(bridge)) = (keys, &self.app_bridge) {
+                    bridge.input(keys.to_string());
                 }
             }
         }
     }
 
     fn handle_option_set(&mut self, name: &str, value: &nvim_rs::Value) {
+        if name == "mousemoveevent" {
+            let enabled = value.as_bool().unwrap_or(false);
+            self.input_handler.set_mouse_move_events(enabled);
+        }
         if name == "guifont" {
             if let Some(s) = value.as_str() {
                 if let Some(font_settings) = crate::config::FontSettings::from_guifont(s) {
@@ -186,32 +841,727 @@ impl GuiApp {
                 }
             }
         }
+        if name == "linespace" {
+            let linespace = value.as_i64().unwrap_or(0);
+            if linespace != self.config.font.linespace {
+                self.config.font.linespace = linespace;
+                let scale_factor = self.current_scale_factor;
+                self.update_layout(scale_factor);
+            }
+        }
     }
 
-    fn apply_redraw_events(&mut self, events: Vec<RedrawEvent>) {
-        for event in events {
-            self.editor_state.handle_redraw_event(&event);
+    /// Switches native tabs on Cmd-Shift-[ / ]. Only consumes the key when
+    /// there is another tab to switch to, so Neovim still receives it in a
+    /// lone window.
+    #[cfg(target_os = "macos")]
+    fn handle_tab_shortcut(&self, event: &winit::event::KeyEvent) -> bool {
+        if !self.config.macos.native_tabs {
+            return false;
+        }
+        let (Some(shortcut), Some(window)) = (self.input_handler.tab_shortcut(event), &self.window)
+        else {
+            return false;
+        };
+        if window.num_tabs() < 2 {
+            return false;
+        }
+        match shortcut {
+            TabShortcut::Previous => window.select_previous_tab(),
+            TabShortcut::Next => window.select_next_tab(),
+        }
+        true
+    }
 
-            match event {
-                RedrawEvent::DefaultColorsSet { fg, bg, .. } => {
-                    if let Some(renderer) = self.render_loop.renderer() {
-                        renderer.update_default_colors(fg, bg);
-                    }
+    /// Handles a key while the swap prompt is open. The prompt is modal,
+    /// so every key is consumed: choices are picked by letter or number,
+    /// or with the arrows and Enter; Esc aborts. Returns true if the
+    /// prompt is open.
+    fn handle_swap_key(&mut self, event: &winit::event::KeyEvent) -> bool {
+        let menu_key = self.input_handler.menu_key(event);
+        let mut state = self.editor.state();
+        let Some(prompt) = &mut state.swap_prompt else {
+            return false;
+        };
+        if event.state != ElementState::Pressed {
+            return true;
+        }
+
+        let letter = match &event.logical_key {
+            winit::keyboard::Key::Character(c) => SwapChoice::from_key(&c.to_lowercase()),
+            _ => None,
+        };
+        let chosen = match (letter, menu_key) {
+            (Some(choice), _) => Some(choice),
+            (None, Some(MenuKey::Up)) => {
+                prompt.select_previous();
+                None
+            }
+            (None, Some(MenuKey::Down)) => {
+                prompt.select_next();
+                None
+            }
+            (None, Some(MenuKey::Open)) => Some(prompt.current()),
+            (None, Some(MenuKey::Pick(index))) => prompt.pick(index),
+            (None, Some(MenuKey::Close)) => Some(SwapChoice::Abort),
+            (None, None) => None,
+        };
+
+        if let Some(choice) = chosen {
+            state.swap_prompt = None;
+            if let Some(reply) = self.swap_reply.take() {
+                reply.send(choice.key().to_string());
+            }
+        }
+        state.mark_dirty();
+        drop(state);
+        self.request_redraw();
+        true
+    }
+
+    /// Handles a key while the crash report prompt is open, modal like the
+    /// swap prompt: `o` or Enter on Open opens the report in a new tab, `d`
+    /// or Esc dismisses it. Returns true if the prompt is open.
+    fn handle_report_key(&mut self, event: &winit::event::KeyEvent) -> bool {
+        // Not drawn until Neovim is up, to open the report in.
+        if self.editor.state().splash.is_some() {
+            return false;
+        }
+        let menu_key = self.input_handler.menu_key(event);
+        let mut state = self.editor.state();
+        let Some(prompt) = &mut state.report_prompt else {
+            return false;
+        };
+        if event.state != ElementState::Pressed {
+            return true;
+        }
+
+        let letter = match &event.logical_key {
+            winit::keyboard::Key::Character(c) => ReportChoice::from_key(&c.to_lowercase()),
+            _ => None,
+        };
+        let chosen = match (letter, menu_key) {
+            (Some(choice), _) => Some(choice),
+            (None, Some(MenuKey::Up)) => {
+                prompt.select_previous();
+                None
+            }
+            (None, Some(MenuKey::Down)) => {
+                prompt.select_next();
+                None
+            }
+            (None, Some(MenuKey::Open)) => Some(prompt.current()),
+            (None, Some(MenuKey::Pick(index))) => prompt.pick(index),
+            (None, Some(MenuKey::Close)) => Some(ReportChoice::Dismiss),
+            (None, None) => None,
+        };
+
+        if let Some(choice) = chosen {
+            let path = prompt.path.clone();
+            state.report_prompt = None;
+            if let (ReportChoice::Open, Some(bridge)) = (choice, &self.app_bridge) {
+                for command in open_commands(&[path], OpenFilesIn::Tab) {
+                    bridge.command(command);
                 }
-                RedrawEvent::SetTitle { title } => {
-                    if let Some(ref window) = self.window {
-                        window.set_title(&title);
-                    }
+            }
+        }
+        state.mark_dirty();
+        drop(state);
+        self.request_redraw();
+        true
+    }
+
+    /// Handles a key while the `:GuiMessages` scrollback is open, which
+    /// keeps every key from Neovim until it is closed. Returns true if it
+    /// is open.
+    fn handle_scrollback_key(&mut self, event: &winit::event::KeyEvent) -> bool {
+        let mut state = self.editor.state();
+        let grid = state.main_grid();
+        let (width, height) = Scrollback::text_area(grid.width(), grid.height());
+        let Some(scrollback) = &mut state.scrollback else {
+            return false;
+        };
+        if event.state != ElementState::Pressed {
+            return true;
+        }
+
+        let modifiers = self.input_handler.modifiers();
+        match self.scrollback_keys.action(&event.logical_key, modifiers) {
+            Some(ScrollbackAction::Top) => scrollback.to_top(),
+            Some(ScrollbackAction::Bottom) => scrollback.to_bottom(),
+            Some(ScrollbackAction::Close) => state.scrollback = None,
+            Some(action) => {
+                if let Some(rows) = action.rows(height) {
+                    scrollback.scroll_by(rows, width, height);
                 }
-                RedrawEvent::OptionSet { name, value } => {
-                    self.handle_option_set(&name, &value);
+            }
+            None => return true,
+        }
+        state.mark_dirty();
+        drop(state);
+        self.request_redraw();
+        true
+    }
+
+    /// Handles a key while the crash prompt is open, modal like the swap
+    /// prompt: `r` or Enter on Restart starts Neovim again, `q` or Esc
+    /// closes the window. Returns true if the prompt is open.
+    fn handle_crash_key(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        event: &winit::event::KeyEvent,
+    ) -> bool {
+        let menu_key = self.input_handler.menu_key(event);
+        let mut state = self.editor.state();
+        let Some(prompt) = &mut state.crash_prompt else {
+            return false;
+        };
+        if event.state != ElementState::Pressed {
+            return true;
+        }
+
+        let letter = match &event.logical_key {
+            winit::keyboard::Key::Character(c) => CrashChoice::from_key(&c.to_lowercase()),
+            _ => None,
+        };
+        let chosen = match (letter, menu_key) {
+            (Some(choice), _) => Some(choice),
+            (None, Some(MenuKey::Up)) => {
+                prompt.select_previous();
+                None
+            }
+            (None, Some(MenuKey::Down)) => {
+                prompt.select_next();
+                None
+            }
+            (None, Some(MenuKey::Open)) => Some(prompt.current()),
+            (None, Some(MenuKey::Pick(index))) => prompt.pick(index),
+            (None, Some(MenuKey::Close)) => Some(CrashChoice::Quit),
+            (None, None) => None,
+        };
+
+        match chosen {
+            Some(CrashChoice::Restart) => {
+                // The grid keeps its size, so Neovim starts at the
+                // window's.
+                state.restart();
+                drop(state);
+                self.exit_code = 0;
+                if let Some(bridge) = &self.app_bridge {
+                    bridge.restart();
                 }
-                _ => {}
             }
+            Some(CrashChoice::Quit) => {
+                drop(state);
+                self.shutdown(event_loop);
+                return true;
+            }
+            None => {
+                state.mark_dirty();
+                drop(state);
+            }
+        }
+        self.request_redraw();
+        true
+    }
+
+    /// Shows the title Neovim set and the state of the current buffer. A
+    /// modified buffer gets a dot in the close button on macOS, with the
+    /// file in the titlebar, and a `•` before the title elsewhere.
+    fn update_title(&self) {
+        let Some(ref window) = self.window else {
+            return;
+        };
+        let mark_modified = self.modified && !cfg!(target_os = "macos");
+        let title = title_or_icon(&self.title, &self.icon);
+        window.set_title(&window_title(title, self.readonly, mark_modified));
+        #[cfg(target_os = "macos")]
+        {
+            window.set_document_edited(self.modified);
+            set_represented_file(window, self.document.as_deref());
+        }
+    }
+
+    /// Handles a key while the startup dashboard is open. Navigation keys
+    /// are consumed; any other key closes the dashboard and goes on to
+    /// Neovim. Returns true if the key was consumed.
+    fn handle_dashboard_key(&mut self, event: &winit::event::KeyEvent) -> bool {
+        // Not drawn until Neovim is up (see the startup splash).
+        if event.state != ElementState::Pressed || self.editor.state().splash.is_some() {
+            return false;
+        }
+        let menu_key = self.input_handler.menu_key(event);
+        let mut state = self.editor.state();
+        let Some(dashboard) = &mut state.dashboard else {
+            return false;
+        };
+
+        let chosen = match menu_key {
+            Some(MenuKey::Up) => {
+                dashboard.select_previous();
+                None
+            }
+            Some(MenuKey::Down) => {
+                dashboard.select_next();
+                None
+            }
+            Some(MenuKey::Open) => Some(dashboard.current().clone()),
+            Some(MenuKey::Pick(index)) => dashboard.pick(index).cloned(),
+            Some(MenuKey::Close) | None => {
+                state.dashboard = None;
+                state.mark_dirty();
+                return menu_key.is_some();
+            }
+        };
+
+        if let Some(entry) = chosen {
+            state.dashboard = None;
+            if let Some(ref bridge) = self.app_bridge {
+                bridge.command(entry.open_command());
+            }
+        }
+        state.mark_dirty();
+        drop(state);
+        self.request_redraw();
+        true
+    }
+
+    fn handle_ime(&mut self, ime: Ime) {
+        match ime {
+            Ime::Preedit(text, cursor) => {
+                self.editor.state().set_preedit(&text, cursor);
+            }
+            Ime::Commit(text) => {
+                self.editor.state().clear_preedit();
+                if let (Some(keys), Some(bridge)) =
+                    (ime_commit_to_neovim(&text), self.app_bridge.as_ref())
+                {
+                    bridge.input(keys);
+                }
+            }
+            Ime::Enabled => {}
+            Ime::Disabled => self.editor.state().clear_preedit(),
+        }
+
+        if self.editor.state().is_dirty() {
+            self.request_redraw();
+        }
+    }
+
+    /// Tell the IME where the cursor is so its candidate window opens next
+    /// to the text being composed.
+    /// `position` in the window as drawn unmirrored, which is where
+    /// `WindowLayout` places things.
+    fn unmirror(&self, position: PhysicalPosition<f64>) -> PhysicalPosition<f64> {
+        match &self.window {
+            Some(window) if self.settings.layout.mirrored => {
+                PhysicalPosition::new(window.inner_size().width as f64 - position.x, position.y)
+            }
+            _ => position,
+        }
+    }
+
+    fn update_ime_cursor_area(&mut self) {
+        let cursor = self.editor.state().cursor.clone();
+        let cell = (cursor.row, cursor.col);
+        if self.ime_cursor_cell == Some(cell) {
+            return;
+        }
+
+        if let Some(ref window) = self.window {
+            let layout = &self.settings.layout;
+            let mut x = cursor.col as f64 * layout.cell_width + layout.padding_left;
+            if layout.mirrored {
+                x = window.inner_size().width as f64 - x - layout.cell_width;
+            }
+            let y = cursor.row as f64 * layout.cell_height + layout.padding_top;
+            window.set_ime_cursor_area(
+                PhysicalPosition::new(x, y),
+                PhysicalSize::new(layout.cell_width, layout.cell_height),
+            );
+            self.ime_cursor_cell = Some(cell);
+        }
+    }
+
+    fn handle_gui_command(&mut self, name: &str, args: &[nvim_rs::Value]) {
+        match name {
+            "recent_file" => {
+                let (Some(path), Some(line), Some(col)) = (
+                    args.first().and_then(|v| v.as_str()),
+                    args.get(1).and_then(|v| v.as_u64()),
+                    args.get(2).and_then(|v| v.as_u64()),
+                ) else {
+                    log::warn!("Malformed recent_file notification: {:?}", args);
+                    return;
+                };
+                self.recent_files.record(path, line, col);
+                if let Err(e) = self.recent_files.save() {
+                    log::warn!("Failed to save recent files: {}", e);
+                }
+            }
+            "stats" => {
+                let renderer = self.render_loop.renderer().map(|r| r.stats());
+                let report = StatsReport::collect(
+                    self.started_at.elapsed(),
+                    self.editor.state().highlights.stats(),
+                    renderer,
+                );
+                if let Some(ref bridge) = self.app_bridge {
+                    bridge.echo(report.lines());
+                }
+            }
+            "buffer_state" => {
+                let flag = |i: usize| args.get(i).and_then(|v| v.as_bool()).unwrap_or(false);
+                let (readonly, modified) = (flag(0), flag(1));
+                let document = args
+                    .get(2)
+                    .and_then(|v| v.as_str())
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from);
+                if (readonly, modified, &document) != (self.readonly, self.modified, &self.document)
+                {
+                    self.readonly = readonly;
+                    self.modified = modified;
+                    self.document = document;
+                    self.update_title();
+                }
+            }
+            "selection" => {
+                let Some(lines) = args.first().and_then(|v| v.as_array()) else {
+                    log::warn!("Malformed selection notification: {:?}", args);
+                    return;
+                };
+                let lines: Vec<&str> = lines.iter().filter_map(|line| line.as_str()).collect();
+                let text = lines.join("\n");
+                #[cfg(target_os = "macos")]
+                {
+                    self.selection = args
+                        .get(1)
+                        .and_then(SelectionRegion::from_value)
+                        .filter(|_| !text.is_empty())
+                        .map(|region| (text.clone(), region));
+                }
+                self.primary_selection.set(text);
+            }
+            "window_zoom" => {
+                let win = args.first().and_then(|v| v.as_u64());
+                let zoom = args
+                    .get(1)
+                    .and_then(|v| v.as_f64().or_else(|| v.as_i64().map(|i| i as f64)));
+                let (Some(win), Some(zoom)) = (win, zoom) else {
+                    log::warn!("Malformed window_zoom notification: {:?}", args);
+                    return;
+                };
+                self.set_window_zoom(win, zoom as f32);
+            }
+            "snapshot" => self.save_snapshot(),
+            "export" => {
+                let args: Vec<&str> = args.iter().filter_map(|v| v.as_str()).collect();
+                let path = (args.len() > 1).then(|| args[1..].join(" "));
+                self.export(args.first().copied(), path.as_deref());
+            }
+            "set" => {
+                let args: Vec<&str> = args.iter().filter_map(|v| v.as_str()).collect();
+                self.gui_set(&args);
+            }
+            "open" => self.show_file_dialog(FileDialog::Open),
+            "save_as" => self.show_file_dialog(FileDialog::SaveAs),
+            "fullscreen" => {
+                let on = match args.first().and_then(|v| v.as_str()) {
+                    Some("on") => Some(true),
+                    Some("off") => Some(false),
+                    _ => None,
+                };
+                self.set_fullscreen(on);
+            }
+            "refresh_colors" => self.refresh_colors(),
+            "invisible" => {
+                let mut state = self.editor.state();
+                state.show_invisible = match args.first().and_then(|v| v.as_str()) {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => !state.show_invisible,
+                };
+                state.mark_dirty();
+                drop(state);
+                self.request_redraw();
+            }
+            "messages" => {
+                if let Some(ref bridge) = self.app_bridge {
+                    let args = vec![
+                        nvim_rs::Value::from("messages"),
+                        nvim_rs::Value::Map(vec![(
+                            nvim_rs::Value::from("output"),
+                            nvim_rs::Value::from(true),
+                        )]),
+                    ];
+                    self.messages_request =
+                        Some(bridge.request("nvim_exec2", args, MESSAGES_TIMEOUT));
+                }
+            }
+            "log" => {
+                if let Some(ref bridge) = self.app_bridge {
+                    bridge.show_lines("gui.nvim log".to_string(), logging::recent_lines());
+                }
+            }
+            "perf_overlay" => {
+                let mut state = self.editor.state();
+                state.perf_overlay = match args.first().and_then(|v| v.as_str()) {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => !state.perf_overlay,
+                };
+                state.mark_dirty();
+                drop(state);
+                self.request_redraw();
+            }
+            "mirror" => {
+                let layout = &mut self.settings.layout;
+                layout.mirrored = match args.first().and_then(|v| v.as_str()) {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => !layout.mirrored,
+                };
+                // Everything under the pointer and the cursor moved sides.
+                self.input_handler.handle_cursor_left();
+                self.update_hovered_link();
+                self.ime_cursor_cell = None;
+                self.update_ime_cursor_area();
+                self.editor.state().mark_dirty();
+                self.request_redraw();
+            }
+            _ => log::warn!("Unknown GUI command: {}", name),
+        }
+    }
+
+    /// `gui.set_window_zoom(factor)`: draws window `win` `zoom` times the
+    /// size of the rest, which takes a grid of its own (`[ui] multigrid`).
+    fn set_window_zoom(&mut self, win: u64, zoom: f32) {
+        let error = if !(MIN_WINDOW_ZOOM..=MAX_WINDOW_ZOOM).contains(&zoom) {
+            Some(format!(
+                "gui.set_window_zoom: factor must be between {} and {}",
+                MIN_WINDOW_ZOOM, MAX_WINDOW_ZOOM
+            ))
+        } else if !self.editor.state().set_window_zoom(win, zoom) {
+            Some("gui.set_window_zoom: needs [ui] multigrid and a split window".to_string())
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            if let Some(ref bridge) = self.app_bridge {
+                bridge.echo(vec![error]);
+            }
+            return;
+        }
+        self.sync_window_zooms();
+        self.request_redraw();
+    }
+
+    /// Asks Neovim for each zoomed window's grid at the size that fills
+    /// the window in zoomed cells, again whenever the window is resized,
+    /// and lets unzoomed ones follow their window again. The pointer is
+    /// mapped to the zoomed cells from then on.
+    fn sync_window_zooms(&mut self) {
+        let state = self.editor.state();
+        let sizes = state.layout.zoomed_sizes();
+        if sizes.is_empty() && self.zoom_requests.is_empty() {
+            return;
+        }
+        let zoomed = state
+            .zoomed_windows()
+            .iter()
+            .map(|w| ZoomedArea {
+                grid: w.grid.id,
+                row: w.row,
+                col: w.col,
+                width: w.width,
+                height: w.height,
+                zoom: w.zoom,
+            })
+            .collect();
+        self.input_handler.set_zoomed_windows(zoomed);
+        let Some(ref bridge) = self.app_bridge else {
+            return;
+        };
+        let dropped: Vec<u64> = self
+            .zoom_requests
+            .keys()
+            .copied()
+            .filter(|grid| !sizes.iter().any(|(zoomed, ..)| zoomed == grid))
+            .collect();
+        for grid in dropped {
+            self.zoom_requests.remove(&grid);
+            // A closed window's grid is gone along with the request.
+            if state.layout.get(grid).is_some() {
+                bridge.resize_grid(grid, 0, 0);
+            }
+        }
+        for (grid, cols, rows) in sizes {
+            if self.zoom_requests.insert(grid, (cols, rows)) != Some((cols, rows)) {
+                bridge.resize_grid(grid, cols as u64, rows as u64);
+            }
+        }
+    }
+
+    /// `:GuiExport`: writes the screen as HTML or PDF laid out in the
+    /// renderer's cells, and says where.
+    fn export(&mut self, format: Option<&str>, path: Option<&str>) {
+        let Some(ref bridge) = self.app_bridge else {
+            return;
+        };
+        let Some(format) = format.and_then(ExportFormat::parse) else {
+            bridge.echo(vec!["Usage: :GuiExport html|pdf [path]".to_string()]);
+            return;
+        };
+        let Some(renderer) = self.render_loop.renderer() else {
+            bridge.echo(vec!["Nothing drawn to export yet".to_string()]);
+            return;
+        };
+        let scale = renderer.scale_factor() as f32;
+        let (cell_width, cell_height) = renderer.cell_size();
+        let font = FontConfig::new(&self.config.font, renderer.scale_factor());
+        let metrics = ExportMetrics {
+            cell_width: cell_width / scale,
+            cell_height: cell_height / scale,
+            ascent: renderer.baseline_offset() / scale,
+            font_size: font.size_pt,
+            family: font.family,
+        };
+        let title = title_or_icon(&self.title, &self.icon);
+        let message = match export::save(format, path, &self.editor.state(), &metrics, title) {
+            Ok(path) => format!("Exported to {}", path.display()),
+            Err(e) => {
+                log::error!("{}", e);
+                e.to_string()
+            }
+        };
+        bridge.echo(vec![message]);
+    }
+
+    /// Saves the current frame and editor state for a bug report, and
+    /// tells the user where.
+    fn save_snapshot(&mut self) {
+        let frame = self.render_loop.renderer().and_then(|renderer| {
+            renderer
+                .capture_png(&self.editor.state(), &self.settings.layout)
+                .map_err(|e| log::warn!("Snapshot without a frame: {}", e))
+                .ok()
+        });
+        let message = match snapshot::save(frame.as_deref(), &self.editor.state(), &self.config) {
+            Ok(dir) => {
+                log::info!("Saved snapshot to {}", dir.display());
+                format!("Snapshot saved to {}", dir.display())
+            }
+            Err(e) => {
+                log::error!("{}", e);
+                e.to_string()
+            }
+        };
+        if let Some(ref bridge) = self.app_bridge {
+            bridge.echo(vec![message]);
+        }
+    }
+
+    /// Acts on the redraws the editor core applied since the last update.
+    fn apply_editor_update(&mut self) {
+        let Some(update) = self.editor.take_update() else {
+            return;
+        };
+        if update.title.is_some() || update.icon.is_some() {
+            if let Some(title) = update.title {
+                self.title = title;
+            }
+            if let Some(icon) = update.icon {
+                self.icon = icon;
+            }
+            self.update_title();
+        }
+        for (name, value) in &update.options {
+            self.handle_option_set(name, value);
+        }
+        if let Some(enabled) = update.mouse_enabled {
+            self.input_handler.set_mouse_enabled(enabled);
+        }
+        self.sync_window_zooms();
+        if update.tabline_changed {
+            if let Some(ref bridge) = self.app_bridge {
+                bridge.query_tabs_modified();
+            }
+        }
+        // A :colorscheme arrives as a burst of these; refreshing once after
+        // the batch gets everything into the same frame.
+        if update.colors_changed {
+            self.neovim_colors = true;
+            self.refresh_colors();
+        }
+        self.update_ime_cursor_area();
+        // The text under a still pointer may have changed.
+        self.update_hovered_link();
+        self.request_redraw();
+    }
+
+    /// Brings everything the GUI colors itself from the colorscheme up to
+    /// date: the clear color behind the grid and padding, the background
+    /// image tint, the titlebar's light or dark appearance, and overlays
+    /// drawn with the UI highlight groups.
+    fn refresh_colors(&mut self) {
+        let defaults = self.editor.state().highlights.defaults.clone();
+        if let Some(renderer) = self.render_loop.renderer() {
+            renderer.update_default_colors(defaults.foreground.0 >> 8, defaults.background.0 >> 8);
+        }
+        if let Some(ref window) = self.window {
+            let theme = if defaults.background.is_dark() {
+                Theme::Dark
+            } else {
+                Theme::Light
+            };
+            window.set_theme(Some(theme));
+        }
+        self.editor.state().mark_dirty();
+        self.request_redraw();
+    }
+
+    /// Follows the system's appearance to `theme`: `'background'`, if it
+    /// is still the one Neovim was given, and the default colors until
+    /// Neovim sends its own.
+    fn set_system_theme(&mut self, theme: Option<Theme>) {
+        let Some(theme) = theme else {
+            return;
+        };
+        let previous = self.system_theme.replace(theme);
+        if previous == Some(theme) {
+            return;
+        }
+        log::debug!("System theme: {:?}", theme);
+        if let (Some(previous), Some(bridge)) = (previous, &self.app_bridge) {
+            bridge.command(appearance::follow_command(previous, theme));
+        }
+        if self.neovim_colors {
+            return;
+        }
+        let mut state = self.editor.state();
+        let defaults = appearance::default_colors(theme);
+        state
+            .highlights
+            .set_defaults(defaults.foreground, defaults.background, defaults.special);
+        state.mark_dirty();
+        drop(state);
+        if let Some(renderer) = self.render_loop.renderer() {
+            renderer.update_default_colors(defaults.foreground.0 >> 8, defaults.background.0 >> 8);
+        }
+        self.request_redraw();
+    }
+
+    /// Asks for a frame. Requests made before it is drawn add nothing.
+    fn request_redraw(&mut self) {
+        if let Some(window) = &self.window {
+            self.render_loop.request_redraw(window);
         }
     }
 
     fn do_render(&mut self) {
+        self.render_loop.redraw_received();
+
         // If display link is active, only render when frame is ready
         #[cfg(target_os = "macos")]
         if let Some(ref display_link) = self.display_link {
@@ -223,7 +1573,7 @@ impl GuiApp {
         // Skip rendering if nothing has changed since the last frame.
         // Consume the vblank regardless so the DisplayLink ready flag
         // is cleared and we don't spin-loop in about_to_wait().
-        if !self.editor_state.is_dirty() {
+        if !self.editor.state().is_dirty() {
             #[cfg(target_os = "macos")]
             if let Some(ref display_link) = self.display_link {
                 display_link.request_frame();
@@ -231,13 +1581,18 @@ impl GuiApp {
             return;
         }
 
+        // Too early for the next frame: about_to_wait wakes the loop in time.
+        if !self.render_loop.begin_frame() {
+            return;
+        }
+
         if let Some(window) = &self.window {
-            let render_result = self.render_loop.render(
-                &self.editor_state,
-                self.settings.cell_metrics.padding_x as f32,
-                self.settings.cell_metrics.padding_y as f32,
-                window,
-            );
+            // Held until the dirty flag is cleared, so redraws the core
+            // applies meanwhile aren't marked as drawn.
+            let mut state = self.editor.state();
+            let render_result = self
+                .render_loop
+                .render(&state, &self.settings.layout, window);
 
             if render_result.is_err() {
                 if self.render_loop.renderer().is_none() {
@@ -249,7 +1604,7 @@ impl GuiApp {
             } else {
                 // Frame rendered successfully — clear dirty flag and
                 // request next frame from display link
-                self.editor_state.clear_dirty();
+                state.clear_dirty();
 
                 #[cfg(target_os = "macos")]
                 if let Some(ref display_link) = self.display_link {
@@ -260,9 +1615,72 @@ impl GuiApp {
     }
 }
 
+/// The fullscreen `mode` makes on `monitor`. Exclusive fullscreen takes
+/// the monitor's largest, fastest video mode, and falls back to borderless
+/// without one.
+fn fullscreen_on(mode: FullscreenMode, monitor: Option<MonitorHandle>) -> Fullscreen {
+    let video_mode = match mode {
+        FullscreenMode::Borderless => None,
+        FullscreenMode::Exclusive => monitor.and_then(|monitor| {
+            monitor.video_modes().max_by_key(|video_mode| {
+                let size = video_mode.size();
+                (
+                    u64::from(size.width) * u64::from(size.height),
+                    video_mode.refresh_rate_millihertz(),
+                )
+            })
+        }),
+    };
+    match video_mode {
+        Some(video_mode) => Fullscreen::Exclusive(video_mode),
+        None => Fullscreen::Borderless(None),
+    }
+}
+
+/// The window title for Neovim's `title`, with a lock in front while the
+/// buffer can't be written.
+/// How long `:GuiMessages` waits for the history.
+const MESSAGES_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The `output` of an `nvim_exec2` result.
+fn exec_output(result: &nvim_rs::Value) -> Option<&str> {
+    result
+        .as_map()?
+        .iter()
+        .find(|(key, _)| key.as_str() == Some("output"))?
+        .1
+        .as_str()
+}
+
+/// The title to show: Neovim's, or its icon text if the title is empty,
+/// which is all `'icon'` can do without a separate icon title.
+fn title_or_icon<'a>(title: &'a str, icon: &'a str) -> &'a str {
+    [title, icon]
+        .into_iter()
+        .find(|s| !s.is_empty())
+        .unwrap_or("gui.nvim")
+}
+
+fn window_title(title: &str, readonly: bool, modified: bool) -> String {
+    let mut marked = String::new();
+    if modified {
+        marked.push_str("\u{2022} ");
+    }
+    if readonly {
+        marked.push_str("\u{1f512} ");
+    }
+    marked + title
+}
+
 impl ApplicationHandler<UserEvent> for GuiApp {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        if self.window.is_none() {
+        if self.window.is_some() {
+            return;
+        }
+        self.set_system_theme(appearance::system_theme(event_loop));
+        if self.daemon.is_some() {
+            self.start_neovim();
+        } else {
             self.create_window(event_loop);
         }
     }
@@ -273,44 +1691,38 @@ impl ApplicationHandler<UserEvent> for GuiApp {
         window_id: WindowId,
         event: WindowEvent,
     ) {
-        if self.window.as_ref().map(|w| w.id()) != Some(window_id) {
+        if self.close_requested || self.window.as_ref().map(|w| w.id()) != Some(window_id) {
             return;
         }
 
         match event {
             WindowEvent::CloseRequested => {
                 log::info!("Close requested");
-                if let Some(ref bridge) = self.app_bridge {
-                    bridge.quit();
-                }
-                self.close_requested = true;
-                event_loop.exit();
+                self.request_close(event_loop);
             }
 
-            WindowEvent::Resized(size) => {
-                if size.width > 0 && size.height > 0 {
-                    log::debug!("Window resized: {}x{}", size.width, size.height);
+            WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
+                log::debug!("Window resized: {}x{}", size.width, size.height);
 
-                    if let Some(renderer) = self.render_loop.renderer() {
-                        renderer.resize(size);
-                    }
+                if let Some(renderer) = self.render_loop.renderer() {
+                    renderer.resize(size);
+                }
 
-                    let (cols, rows) = self.settings.calculate_grid_size(size.width, size.height);
-                    if cols != self.settings.cols || rows != self.settings.rows {
-                        self.settings.cols = cols;
-                        self.settings.rows = rows;
-                        if let Some(ref bridge) = self.app_bridge {
-                            bridge.resize(cols, rows);
-                        }
+                let (cols, rows) = self.settings.layout.grid_size(size.width, size.height);
+                if cols != self.settings.cols || rows != self.settings.rows {
+                    self.settings.cols = cols;
+                    self.settings.rows = rows;
+                    if let Some(ref bridge) = self.app_bridge {
+                        bridge.resize(cols, rows);
                     }
+                }
 
-                    // Mark dirty so the resized frame gets rendered
-                    self.editor_state.mark_dirty();
+                // Mark dirty so the resized frame gets rendered
+                self.editor.state().mark_dirty();
 
-                    let _ = self
-                        .event_proxy
-                        .send_event(UserEvent::GUI(GUIEvent::Resized(size)));
-                }
+                let _ = self
+                    .event_proxy
+                    .send_event(UserEvent::GUI(GUIEvent::Resized(size)));
             }
 
             WindowEvent::RedrawRequested => {
@@ -323,52 +1735,215 @@ impl ApplicationHandler<UserEvent> for GuiApp {
             }
 
             WindowEvent::KeyboardInput { event, .. } => {
+                // While the IME is composing, keys belong to the IME; the
+                // result arrives as Ime::Commit.
+                if self.editor.state().preedit.is_some() {
+                    return;
+                }
+
+                if self.handle_crash_key(event_loop, &event)
+                    || self.handle_swap_key(&event)
+                    || self.handle_report_key(&event)
+                    || self.handle_scrollback_key(&event)
+                    || self.handle_dashboard_key(&event)
+                {
+                    return;
+                }
+
+                if let Some(keys) = self.input_handler.keybind(&event) {
+                    if let Some(ref bridge) = self.app_bridge {
+                        log::trace!("Keybind: {}", keys);
+                        bridge.input(keys.to_string());
+                    }
+                    return;
+                }
+
+                if self.input_handler.is_snapshot_key(&event) {
+                    self.save_snapshot();
+                    return;
+                }
+
+                if self.input_handler.is_fullscreen_key(&event) {
+                    self.set_fullscreen(None);
+                    return;
+                }
+
+                #[cfg(target_os = "macos")]
+                if self.handle_tab_shortcut(&event) {
+                    return;
+                }
+
                 if let Some(ref bridge) = self.app_bridge {
                     self.input_handler.handle_keyboard_input(&event, bridge);
                 }
 
                 if event.state == ElementState::Pressed {
+                    // Any key closes the `:messages` view, like the pager
+                    let mut editor = self.editor.state();
+                    if editor.messages.dismiss_history() {
+                        editor.mark_dirty();
+                    }
+                    drop(editor);
                     let _ = self
                         .event_proxy
                         .send_event(UserEvent::GUI(GUIEvent::KeyboardInput(event)));
                 }
             }
 
+            WindowEvent::Ime(ime) => {
+                self.handle_ime(ime);
+            }
+
             WindowEvent::MouseInput { state, button, .. } => {
+                let link = {
+                    let mut editor = self.editor.state();
+                    if state == ElementState::Pressed && editor.dashboard.take().is_some() {
+                        editor.mark_dirty();
+                    }
+                    editor.hovered_link.clone()
+                };
+                if self
+                    .input_handler
+                    .handle_link_click(state, button, link.is_some())
+                {
+                    if let (ElementState::Pressed, Some(url)) = (state, link.as_deref()) {
+                        open_url(url);
+                    }
+                    return;
+                }
+                #[cfg(target_os = "macos")]
+                {
+                    let in_selection = self
+                        .input_handler
+                        .pointer_cell(&self.settings.layout)
+                        .zip(self.selection.as_ref())
+                        .is_some_and(|(cell, (_, region))| region.contains(cell.row, cell.col));
+                    if self
+                        .input_handler
+                        .handle_selection_drag_press(state, button, in_selection)
+                    {
+                        return;
+                    }
+                }
                 if let Some(ref bridge) = self.app_bridge {
+                    let primary = &self.primary_selection;
+                    if self
+                        .input_handler
+                        .handle_primary_paste(state, button, bridge, || primary.get())
+                    {
+                        return;
+                    }
+                    let tabs = self
+                        .editor
+                        .state()
+                        .tabline
+                        .layout(self.settings.cols as usize);
+                    if self.input_handler.handle_tabline_click(
+                        state,
+                        button,
+                        &self.settings.layout,
+                        &tabs,
+                        bridge,
+                    ) {
+                        return;
+                    }
                     self.input_handler.handle_mouse_input(state, button, bridge);
                 }
             }
 
             WindowEvent::CursorMoved { position, .. } => {
+                let position = self.unmirror(position);
                 if let Some(ref bridge) = self.app_bridge {
-                    self.input_handler.handle_cursor_moved(
-                        position,
-                        &self.settings.cell_metrics,
-                        bridge,
-                    );
+                    self.input_handler
+                        .handle_cursor_moved(position, &self.settings.layout, bridge);
+                }
+                #[cfg(target_os = "macos")]
+                if self
+                    .input_handler
+                    .take_selection_drag(&self.settings.layout)
+                {
+                    if let (Some(window), Some((text, _))) = (&self.window, &self.selection) {
+                        start_drag(window, text);
+                    }
                 }
+                self.update_hovered_link();
             }
 
-            WindowEvent::MouseWheel { delta, .. } => {
+            WindowEvent::CursorLeft { .. } => {
+                self.input_handler.handle_cursor_left();
+                self.update_hovered_link();
+            }
+
+            WindowEvent::MouseWheel { delta, phase, .. } => {
+                let cell_height = self.settings.layout.cell_height;
+                if let Some(steps) = self
+                    .input_handler
+                    .handle_zoom_wheel(delta, phase, cell_height)
+                {
+                    if steps != 0 {
+                        self.zoom(steps as f32 * FONT_ZOOM_STEP);
+                    }
+                    return;
+                }
                 if let Some(ref bridge) = self.app_bridge {
-                    self.input_handler.handle_mouse_wheel(delta, bridge);
+                    self.input_handler
+                        .handle_mouse_wheel(delta, phase, cell_height, bridge);
+                    self.update_smooth_scroll();
                 }
             }
 
-            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
-                if (self.current_scale_factor - scale_factor).abs() >= f64::EPSILON {
-                    log::debug!("Scale factor changed: {}", scale_factor);
-                    self.current_scale_factor = scale_factor;
-                    self.update_padding(scale_factor);
-                    let _ = self
-                        .event_proxy
-                        .send_event(UserEvent::GUI(GUIEvent::ScaleFactorChanged(scale_factor)));
+            WindowEvent::PinchGesture { delta, phase, .. } => {
+                let size = self.config.font.size.unwrap_or(DEFAULT_FONT_SIZE);
+                let steps_per_size = (size / FONT_ZOOM_STEP) as f64;
+                let steps = self
+                    .input_handler
+                    .handle_pinch(delta, phase, steps_per_size);
+                if steps != 0 {
+                    self.zoom(steps as f32 * FONT_ZOOM_STEP);
                 }
             }
 
+            WindowEvent::Touch(touch) => {
+                if let Some(ref bridge) = self.app_bridge {
+                    let now = self.editor.now();
+                    self.input_handler
+                        .handle_touch(touch, now, &self.settings.layout, bridge);
+                    self.update_smooth_scroll();
+                }
+            }
+
+            WindowEvent::ScaleFactorChanged { scale_factor, .. }
+                if (self.current_scale_factor - scale_factor).abs() >= f64::EPSILON =>
+            {
+                log::debug!("Scale factor changed: {}", scale_factor);
+                self.current_scale_factor = scale_factor;
+                self.update_padding(scale_factor);
+                let _ = self
+                    .event_proxy
+                    .send_event(UserEvent::GUI(GUIEvent::ScaleFactorChanged(scale_factor)));
+            }
+
+            WindowEvent::HoveredFile(_) => self.set_file_hover(true),
+            WindowEvent::HoveredFileCancelled => self.set_file_hover(false),
+            WindowEvent::DroppedFile(path) => {
+                self.set_file_hover(false);
+                self.dropped_files.push(path);
+            }
+
+            // Only says the window's appearance changed, which follows the
+            // colorscheme once there is one.
+            WindowEvent::ThemeChanged(_) => {
+                self.set_system_theme(appearance::system_theme(event_loop));
+            }
+
             WindowEvent::Focused(focused) => {
                 log::debug!("Window focused: {}", focused);
+                // The window hears nothing of the system's appearance once
+                // it has one of its own; coming back from the settings app
+                // is when it is likely to have changed.
+                if focused {
+                    self.set_system_theme(appearance::system_theme(event_loop));
+                }
                 let _ = self
                     .event_proxy
                     .send_event(UserEvent::GUI(GUIEvent::Focused(focused)));
@@ -378,18 +1953,93 @@ impl ApplicationHandler<UserEvent> for GuiApp {
         }
     }
 
-    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: UserEvent) {
+        // Events already queued by the bridge or display link may still
+        // arrive after shutdown started; the state they target is gone.
+        if self.close_requested {
+            return;
+        }
+
         match event {
             UserEvent::Neovim(neovim_event) => match neovim_event {
+                NeovimEvent::EditorUpdated => self.apply_editor_update(),
+                // Only without the core's thread, which failed to start.
                 NeovimEvent::Redraw(events) => {
-                    self.apply_redraw_events(events);
-                    if let Some(window) = &self.window {
-                        window.request_redraw();
+                    self.editor.apply(events);
+                    self.apply_editor_update();
+                }
+                NeovimEvent::GuiCommand { name, args } => {
+                    self.handle_gui_command(&name, &args);
+                }
+                NeovimEvent::TabsModified(modified) => {
+                    let dirty = {
+                        let mut state = self.editor.state();
+                        state.set_tabs_modified(&modified);
+                        state.is_dirty()
+                    };
+                    if dirty {
+                        self.request_redraw();
                     }
                 }
-                NeovimEvent::Quit => {
-                    self.close_requested = true;
-                    _event_loop.exit();
+                NeovimEvent::Capabilities(caps) => {
+                    self.input_handler.set_analog_scroll(caps.analog_scroll());
+                }
+                NeovimEvent::SwapExists {
+                    file,
+                    swapname,
+                    reply,
+                } => {
+                    log::info!("Swap file {} found for {}", swapname, file);
+                    let mut state = self.editor.state();
+                    state.swap_prompt = Some(SwapPrompt::new(file, swapname));
+                    state.mark_dirty();
+                    drop(state);
+                    self.swap_reply = Some(reply);
+                    self.request_redraw();
+                }
+                NeovimEvent::Response { id, result } if self.messages_request == Some(id) => {
+                    self.messages_request = None;
+                    match result {
+                        Ok(value) => {
+                            let output = exec_output(&value).unwrap_or_default();
+                            let mut state = self.editor.state();
+                            state.scrollback = Some(Scrollback::new(output));
+                            state.mark_dirty();
+                            drop(state);
+                            self.request_redraw();
+                        }
+                        Err(e) => log::warn!("Failed to fetch the message history: {}", e),
+                    }
+                }
+                NeovimEvent::Response { id, result } => {
+                    // Answers to requests nothing waits for any more.
+                    log::debug!("Response to request {}: {:?}", id, result);
+                }
+                NeovimEvent::QuitCancelled => {
+                    self.quit_pending = false;
+                }
+                NeovimEvent::Disconnected => {
+                    log::warn!("Lost connection to the Neovim server, reconnecting");
+                    self.editor.state().disconnected();
+                    if let Some(bridge) = &self.app_bridge {
+                        bridge.reconnect();
+                    }
+                    self.request_redraw();
+                }
+                NeovimEvent::Exited { status } => {
+                    // Kept should the window be closed rather than restart.
+                    self.exit_code = status.code().unwrap_or(1);
+                    self.quit_pending = false;
+                    self.swap_reply = None;
+                    self.editor.state().crashed(status.to_string());
+                    self.request_redraw();
+                }
+                NeovimEvent::Quit(code) => {
+                    log::info!("Neovim exited with {}", code);
+                    if code != 0 {
+                        self.exit_code = code;
+                    }
+                    self.shutdown(event_loop);
                 }
             },
             UserEvent::GUI(event) => {
@@ -397,14 +2047,29 @@ impl ApplicationHandler<UserEvent> for GuiApp {
                     GUIEvent::ScaleFactorChanged(scale_factor) => {
                         self.update_layout(scale_factor);
                     }
-                    GUIEvent::RedrawRequested => {
-                        // DisplayLink vblank arrived — request a redraw if we
-                        // have pending changes so the frame is presented promptly.
-                        if self.editor_state.is_dirty() {
-                            if let Some(window) = &self.window {
-                                window.request_redraw();
-                            }
+                    GUIEvent::Menu(action) => {
+                        self.handle_menu_action(event_loop, action);
+                    }
+                    GUIEvent::Focused(focused) => {
+                        self.editor.state().set_focused(focused);
+                        if let Some(ref bridge) = self.app_bridge {
+                            bridge.set_focus(focused);
                         }
+                        self.request_redraw();
+                    }
+                    GUIEvent::Launch(request) => {
+                        self.launch(event_loop, &request);
+                    }
+                    GUIEvent::Open(request) => {
+                        self.open_remote(&request);
+                    }
+                    GUIEvent::FileChosen(dialog, choice) => {
+                        self.file_chosen(dialog, choice);
+                    }
+                    // DisplayLink vblank arrived — request a redraw if we
+                    // have pending changes so the frame is presented promptly.
+                    GUIEvent::RedrawRequested if self.editor.state().is_dirty() => {
+                        self.request_redraw();
                     }
                     _ => {}
                 }
@@ -414,43 +2079,64 @@ impl ApplicationHandler<UserEvent> for GuiApp {
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
         if self.close_requested {
+            // Covers paths that flag a close without an event loop handle,
+            // such as a fatal render error.
+            self.shutdown(event_loop);
             return;
         }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
+        if !self.dropped_files.is_empty() {
+            let paths = std::mem::take(&mut self.dropped_files);
+            log::info!("Opening {} dropped file(s)", paths.len());
+            self.open_files(&paths);
+        }
 
-        if self.editor_state.update_blink(now) {
-            if let Some(window) = &self.window {
-                window.request_redraw();
-            }
+        let now = self.editor.now();
+        let tick = self.editor.state().tick(now);
+        if tick.changed {
+            self.request_redraw();
         }
+        let coast = self.app_bridge.as_ref().and_then(|bridge| {
+            let cell_height = self.settings.layout.cell_height;
+            self.input_handler
+                .coast_touch_scroll(now, cell_height, bridge)
+        });
+        if coast.is_some() {
+            self.update_smooth_scroll();
+        }
+        let deadline = match (tick.deadline, coast) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
 
         // When display link is active, it wakes the event loop via EventLoopProxy
         // on each vblank. Only request a redraw if a frame is ready AND we have
         // something to render. Without the dirty check, we'd spin-loop:
         // about_to_wait -> request_redraw -> do_render (no-op) -> about_to_wait -> ...
         #[cfg(target_os = "macos")]
-        if let Some(ref display_link) = self.display_link {
-            if display_link.is_frame_ready() && self.editor_state.is_dirty() {
-                if let Some(window) = &self.window {
-                    window.request_redraw();
-                }
+        if self.editor.state().is_dirty()
+            && self
+                .display_link
+                .as_ref()
+                .is_some_and(|display_link| display_link.is_frame_ready())
+        {
+            self.request_redraw();
+        }
+
+        let mut wake_at = deadline.map(|deadline| {
+            std::time::Instant::now() + Duration::from_millis(deadline.saturating_sub(now))
+        });
+        // A frame held back by the frame limit is drawn once it is due.
+        if let Some(window) = &self.window {
+            let dirty = self.editor.state().is_dirty();
+            if let Some(at) = self.render_loop.next_frame_at(dirty, window) {
+                wake_at = Some(wake_at.map_or(at, |w: std::time::Instant| w.min(at)));
             }
         }
 
-        let mode = self.editor_state.current_mode();
-        if mode.blink_on > 0 && mode.blink_off > 0 {
-            // Schedule next check. Since update_blink uses absolute time,
-            // we can just wake up periodically to check.
-            // 100ms is a reasonable resolution for cursor blinking.
-            event_loop.set_control_flow(ControlFlow::WaitUntil(
-                std::time::Instant::now() + Duration::from_millis(100),
-            ));
-        } else {
-            event_loop.set_control_flow(ControlFlow::Wait);
+        match wake_at {
+            Some(at) => event_loop.set_control_flow(ControlFlow::WaitUntil(at)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
         }
     }
 }
@@ -458,14 +2144,46 @@ impl ApplicationHandler<UserEvent> for GuiApp {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::constants::PADDING;
 
     #[test]
     fn test_default_dimensions() {
         let settings = WindowSettings::new();
-        let width = DEFAULT_COLS as u32 * settings.cell_metrics.cell_width as u32 + 2 * PADDING;
-        let height = DEFAULT_ROWS as u32 * settings.cell_metrics.cell_height as u32 + 2 * PADDING;
-        assert!(width > 0);
-        assert!(height > 0);
+        let (width, height) = settings.layout.window_size(DEFAULT_COLS, DEFAULT_ROWS);
+        assert!(width > 0.0);
+        assert!(height > 0.0);
+    }
+
+    #[test]
+    fn test_title_falls_back_to_the_icon() {
+        assert_eq!(title_or_icon("notes.md - NVIM", "notes"), "notes.md - NVIM");
+        assert_eq!(title_or_icon("", "notes"), "notes");
+        assert_eq!(title_or_icon("", ""), "gui.nvim");
+    }
+
+    #[test]
+    fn test_window_title() {
+        assert_eq!(
+            window_title("notes.md - NVIM", false, false),
+            "notes.md - NVIM"
+        );
+        assert_eq!(
+            window_title("notes.md - NVIM", true, false),
+            "\u{1f512} notes.md - NVIM"
+        );
+        assert_eq!(
+            window_title("notes.md - NVIM", true, true),
+            "\u{2022} \u{1f512} notes.md - NVIM"
+        );
+    }
+
+    #[test]
+    fn test_exec_output() {
+        let result = nvim_rs::Value::Map(vec![(
+            nvim_rs::Value::from("output"),
+            nvim_rs::Value::from("E492: Not an editor command: x"),
+        )]);
+        assert_eq!(exec_output(&result), Some("E492: Not an editor command: x"));
+        assert_eq!(exec_output(&nvim_rs::Value::Map(vec![])), None);
+        assert_eq!(exec_output(&nvim_rs::Value::Nil), None);
     }
 }
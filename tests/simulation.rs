@@ -156,3 +156,142 @@ fn test_simulation_workflow() {
     // Row 1 should have "H"
     assert_eq!(state.main_grid()[(1, 0)].text, "H");
 }
+
+/// Replays a recorded multigrid redraw stream: a split, a float over it, the
+/// message grid, then the float hidden and the split closed. Nothing that was
+/// hidden or closed may remain in the visible layer stack.
+#[test]
+fn test_multigrid_window_lifecycle_stream() {
+    use gui_nvim::bridge::parser::parse_redraw;
+    use nvim_rs::Value;
+
+    fn event(name: &str, args: Vec<Value>) -> Value {
+        Value::Array(vec![Value::from(name), Value::Array(args)])
+    }
+    fn win() -> Value {
+        Value::Ext(1, vec![0xcd, 0x03, 0xe8])
+    }
+    fn visible(state: &EditorState) -> Vec<u64> {
+        state
+            .layout
+            .visible_in_z_order()
+            .iter()
+            .map(|w| w.grid)
+            .collect()
+    }
+
+    let mut state = EditorState::new(80, 24);
+    let apply = |state: &mut EditorState, batch: Vec<Value>| {
+        for e in parse_redraw(batch) {
+            state.handle_redraw_event(&e);
+        }
+    };
+
+    apply(
+        &mut state,
+        vec![
+            event("grid_resize", vec![2u64.into(), 80u64.into(), 22u64.into()]),
+            event(
+                "win_pos",
+                vec![
+                    2u64.into(),
+                    win(),
+                    0u64.into(),
+                    0u64.into(),
+                    80u64.into(),
+                    22u64.into(),
+                ],
+            ),
+            event("grid_resize", vec![4u64.into(), 20u64.into(), 5u64.into()]),
+            event(
+                "win_float_pos",
+                vec![
+                    4u64.into(),
+                    win(),
+                    "NW".into(),
+                    2u64.into(),
+                    3.0.into(),
+                    10.0.into(),
+                    true.into(),
+                    50u64.into(),
+                ],
+            ),
+            event("grid_resize", vec![3u64.into(), 80u64.into(), 2u64.into()]),
+            event(
+                "msg_set_pos",
+                vec![3u64.into(), 22u64.into(), false.into(), "".into()],
+            ),
+            event("flush", vec![]),
+        ],
+    );
+    assert_eq!(visible(&state), vec![2, 4, 3]);
+    assert_eq!(state.window_origin(4), Some((3.0, 10.0)));
+
+    apply(
+        &mut state,
+        vec![
+            event("win_hide", vec![4u64.into()]),
+            event("win_close", vec![2u64.into()]),
+            event("flush", vec![]),
+        ],
+    );
+    assert_eq!(visible(&state), vec![3]);
+    assert!(state.layout.get(2).is_none());
+
+    apply(
+        &mut state,
+        vec![
+            event("grid_destroy", vec![4u64.into()]),
+            event("grid_destroy", vec![2u64.into()]),
+        ],
+    );
+    assert!(state.layout.get(4).is_none());
+    assert!(state.grid(4).is_none());
+    assert_eq!(visible(&state), vec![3]);
+}
+
+/// Opening and closing floats over and over must not leave grids behind.
+#[test]
+fn test_grid_create_destroy_stream_frees_grids() {
+    use gui_nvim::bridge::parser::parse_redraw;
+    use nvim_rs::Value;
+
+    fn event(name: &str, args: Vec<Value>) -> Value {
+        Value::Array(vec![Value::from(name), Value::Array(args)])
+    }
+
+    let mut state = EditorState::new(80, 24);
+    for grid in 2u64..50 {
+        let batch = vec![
+            event("grid_resize", vec![grid.into(), 30u64.into(), 8u64.into()]),
+            event(
+                "win_float_pos",
+                vec![
+                    grid.into(),
+                    Value::Ext(1, vec![0xcd, 0x03, 0xe8]),
+                    "NW".into(),
+                    1u64.into(),
+                    2.0.into(),
+                    2.0.into(),
+                    true.into(),
+                    50u64.into(),
+                ],
+            ),
+            event(
+                "grid_cursor_goto",
+                vec![grid.into(), 1u64.into(), 1u64.into()],
+            ),
+            event("flush", vec![]),
+            event("win_close", vec![grid.into()]),
+            event("grid_destroy", vec![grid.into()]),
+            event("flush", vec![]),
+        ];
+        for e in parse_redraw(batch) {
+            state.handle_redraw_event(&e);
+        }
+    }
+
+    assert_eq!(state.grid_count(), 1);
+    assert!(state.layout.is_empty());
+    assert_eq!(state.cursor.grid, 1);
+}